@@ -0,0 +1,468 @@
+use anyhow::Result;
+use colored::*;
+use rayon::prelude::*;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tree_sitter::{Node, Parser as TSParser};
+
+use crate::definition::{
+    is_declaration_kind, normalize, resolve_definition, Definition, DefinitionOptions, SymbolKind,
+};
+use crate::fs_access;
+use crate::impls::{node_text, walk};
+use crate::language::factory::get_language_impl;
+use crate::language::is_test_file;
+use crate::path_encoding::encode_path;
+use crate::search::file_list_cache;
+use crate::search::search_tokens::count_tokens;
+
+/// Affixes of test file stems that don't name the module under test
+const TEST_STEM_AFFIXES: &[&str] = &["tests", "test", "spec"];
+
+/// A test block that references the symbol, with the score it was ranked by
+#[derive(Debug, Clone)]
+pub struct TestBlock {
+    pub file_path: PathBuf,
+    /// Name of the test function, or its first line when it has none (e.g. `it(...)` calls)
+    pub name: String,
+    pub line_start: usize,
+    pub line_end: usize,
+    pub node_type: String,
+    pub code: String,
+    /// How often the block mentions the symbol
+    pub references: usize,
+    pub score: f64,
+    /// Why the test scored as it did, e.g. "named after the symbol"
+    pub reasons: Vec<String>,
+}
+
+/// A symbol's definition with the tests that exercise it
+#[derive(Debug, Clone)]
+pub struct CaseStudy {
+    pub name: String,
+    /// The best definition, if one was found
+    pub definition: Option<Definition>,
+    /// Most relevant tests first, within the token budget
+    pub tests: Vec<TestBlock>,
+    /// Tests left out to stay within the token budget
+    pub omitted_tests: usize,
+    /// Estimated tokens of the definition and tests
+    pub tokens: usize,
+}
+
+/// Options for building a case study
+pub struct CaseStudyOptions<'a> {
+    pub path: &'a Path,
+    /// The symbol, optionally qualified as with `probe def`
+    pub symbol: &'a str,
+    pub ignore: &'a [String],
+    pub max_tokens: Option<usize>,
+}
+
+/// Helper function to check whether a node is a function with a test attribute or annotation
+///
+/// Rust attributes come before the function as siblings, while Java and C# keep
+/// annotations among the function's own children.
+fn has_test_attribute(node: Node, content: &[u8]) -> bool {
+    let mut sibling = node.prev_named_sibling();
+    while let Some(attribute) = sibling.filter(|s| s.kind() == "attribute_item") {
+        if node_text(attribute, content).contains("test") {
+            return true;
+        }
+        sibling = attribute.prev_named_sibling();
+    }
+    let mut cursor = node.walk();
+    let mut children = node.children(&mut cursor);
+    children.any(|child| {
+        matches!(child.kind(), "modifiers" | "attribute_list")
+            && node_text(child, content).contains("Test")
+    })
+}
+
+/// Helper function to get the module a test file is about, e.g. `config` for `config_test.go`
+fn tested_module(path: &Path) -> String {
+    let mut stem = normalize(path.file_stem().and_then(|s| s.to_str()).unwrap_or(""));
+    for affix in TEST_STEM_AFFIXES {
+        if let Some(rest) = stem
+            .strip_prefix(affix)
+            .or_else(|| stem.strip_suffix(affix))
+        {
+            stem = rest.to_string();
+        }
+    }
+    stem
+}
+
+/// Helper function to find the test blocks of a file that mention the symbol
+///
+/// A test block is a node the language marks as a test, or a function in a test file or
+/// with a test attribute. Only the innermost block mentioning the symbol is kept, so a
+/// test module doesn't repeat the tests inside it.
+fn scan_file(file_path: &Path, reference: &Regex) -> Result<Vec<TestBlock>> {
+    let extension = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let Some(language_impl) = get_language_impl(extension) else {
+        return Ok(Vec::new());
+    };
+
+    let content = fs_access::read_to_string(file_path)?;
+    if !reference.is_match(&content) {
+        return Ok(Vec::new());
+    }
+    let mut parser = TSParser::new();
+    parser.set_language(&language_impl.get_tree_sitter_language())?;
+    let Some(tree) = parser.parse(&content, None) else {
+        return Ok(Vec::new());
+    };
+
+    let bytes = content.as_bytes();
+    let in_test_file = is_test_file(file_path);
+    let mut blocks: Vec<TestBlock> = Vec::new();
+    walk(tree.root_node(), &mut |node| {
+        let is_function = is_declaration_kind(node.kind())
+            && SymbolKind::from_node_kind(node.kind()) == SymbolKind::Function;
+        let is_test = language_impl.is_test_node(&node, bytes)
+            || (is_function && (in_test_file || has_test_attribute(node, bytes)));
+        if !is_test {
+            return;
+        }
+        let code = node_text(node, bytes);
+        let references = reference.find_iter(code).count();
+        if references == 0 {
+            return;
+        }
+        let name = node
+            .child_by_field_name("name")
+            .map(|name| node_text(name, bytes))
+            .unwrap_or_else(|| code.lines().next().unwrap_or("").trim());
+        blocks.push(TestBlock {
+            file_path: file_path.to_path_buf(),
+            name: name.to_string(),
+            line_start: node.start_position().row + 1,
+            line_end: node.end_position().row + 1,
+            node_type: node.kind().to_string(),
+            code: code.to_string(),
+            references,
+            score: 0.0,
+            reasons: Vec::new(),
+        });
+    });
+
+    // Keep the innermost block where blocks nest, e.g. a test module and its tests
+    blocks.dedup_by_key(|block| (block.line_start, block.line_end));
+    let encloses = |outer: &TestBlock, inner: &TestBlock| {
+        (outer.line_start, outer.line_end) != (inner.line_start, inner.line_end)
+            && outer.line_start <= inner.line_start
+            && inner.line_end <= outer.line_end
+    };
+    let innermost = blocks
+        .iter()
+        .filter(|block| !blocks.iter().any(|inner| encloses(block, inner)))
+        .cloned()
+        .collect();
+    Ok(innermost)
+}
+
+/// Helper function to score a test block by how closely it exercises the symbol
+fn score_test(test: &mut TestBlock, name: &str, definition: Option<&Definition>) {
+    let mut score = (1.0 + test.references as f64).ln();
+    let plural = if test.references == 1 { "" } else { "s" };
+    let mut reasons = vec![format!("{} reference{}", test.references, plural)];
+
+    let symbol = normalize(name);
+    if !symbol.is_empty() && normalize(&test.name).contains(&symbol) {
+        score += 2.0;
+        reasons.push("named after the symbol".to_string());
+    }
+
+    if let Some(definition) = definition {
+        let module = normalize(
+            definition
+                .file_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or(""),
+        );
+        if test.file_path == definition.file_path {
+            score += 1.5;
+            reasons.push("next to the definition".to_string());
+        } else if !module.is_empty() && tested_module(&test.file_path) == module {
+            score += 1.0;
+            reasons.push("tests the definition's module".to_string());
+        }
+    }
+
+    test.score = score;
+    test.reasons = reasons;
+}
+
+/// Build a case study: the symbol's definition followed by the tests that reference it
+///
+/// Tests are ranked by how often they mention the symbol, whether they are named after
+/// it, and whether they sit next to or test the definition's module. With a token budget
+/// the definition always comes first and the best tests that fit follow.
+pub fn case_study(options: &CaseStudyOptions) -> Result<CaseStudy> {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    let resolution = resolve_definition(&DefinitionOptions {
+        path: options.path,
+        identifier: options.symbol,
+        from: None,
+        ignore: options.ignore,
+        allow_tests: false,
+        max_results: Some(1),
+    })?;
+    let definition = resolution.definitions.into_iter().next();
+    let name = resolution.name;
+
+    let reference = Regex::new(&format!(r"\b{}\b", regex::escape(&name)))?;
+    let file_list = file_list_cache::get_file_list(
+        options.path,
+        true,
+        options.ignore,
+        false,
+        file_list_cache::FileFilters::default(),
+    )?;
+
+    let mut tests: Vec<TestBlock> = file_list
+        .files
+        .par_iter()
+        .filter_map(|path| match scan_file(path, &reference) {
+            Ok(found) => Some(found),
+            Err(e) => {
+                if debug_mode {
+                    println!("DEBUG: Error scanning file {:?}: {:?}", path, e);
+                }
+                None
+            }
+        })
+        .flatten()
+        .collect();
+
+    for test in &mut tests {
+        score_test(test, &name, definition.as_ref());
+    }
+    tests.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.file_path.cmp(&b.file_path))
+            .then_with(|| a.line_start.cmp(&b.line_start))
+    });
+
+    let mut tokens = definition
+        .as_ref()
+        .map_or(0, |definition| count_tokens(&definition.code));
+    let mut omitted_tests = 0;
+    tests.retain(|test| {
+        let test_tokens = count_tokens(&test.code);
+        if options
+            .max_tokens
+            .is_some_and(|max_tokens| tokens + test_tokens > max_tokens)
+        {
+            omitted_tests += 1;
+            return false;
+        }
+        tokens += test_tokens;
+        true
+    });
+
+    Ok(CaseStudy {
+        name,
+        definition,
+        tests,
+        omitted_tests,
+        tokens,
+    })
+}
+
+/// Helper function to escape XML special characters
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Print a case study
+pub fn format_and_print_case_study(study: &CaseStudy, format: &str) -> Result<()> {
+    match format {
+        "json" => {
+            let definition = study.definition.as_ref().map(|definition| {
+                let encoded = encode_path(&definition.file_path);
+                let mut json = serde_json::json!({
+                    "file": encoded.value,
+                    "lines": [definition.line_start, definition.line_end],
+                    "node_type": definition.node_type,
+                    "name": definition.name,
+                    "kind": definition.kind.to_string(),
+                    "code": definition.code,
+                });
+                if let Some(encoding) = encoded.encoding {
+                    json["file_encoding"] = encoding.into();
+                }
+                json
+            });
+            let tests: Vec<_> = study
+                .tests
+                .iter()
+                .map(|test| {
+                    let encoded = encode_path(&test.file_path);
+                    let mut json = serde_json::json!({
+                        "file": encoded.value,
+                        "lines": [test.line_start, test.line_end],
+                        "node_type": test.node_type,
+                        "name": test.name,
+                        "references": test.references,
+                        "score": test.score,
+                        "reasons": test.reasons,
+                        "code": test.code,
+                    });
+                    if let Some(encoding) = encoded.encoding {
+                        json["file_encoding"] = encoding.into();
+                    }
+                    json
+                })
+                .collect();
+            let wrapper = serde_json::json!({
+                "symbol": study.name,
+                "definition": definition,
+                "tests": tests,
+                "summary": {
+                    "tests": study.tests.len(),
+                    "omitted_tests": study.omitted_tests,
+                    "tokens": study.tokens,
+                }
+            });
+            println!("{}", serde_json::to_string_pretty(&wrapper)?);
+        }
+        "xml" => {
+            println!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+            println!("<probe_results>");
+            let blocks = study
+                .definition
+                .iter()
+                .map(|d| {
+                    (
+                        "definition",
+                        &d.file_path,
+                        d.line_start,
+                        d.line_end,
+                        &d.code,
+                    )
+                })
+                .chain(
+                    study
+                        .tests
+                        .iter()
+                        .map(|t| ("test", &t.file_path, t.line_start, t.line_end, &t.code)),
+                );
+            for (role, file_path, line_start, line_end, code) in blocks {
+                println!("  <result role=\"{}\">", role);
+                let encoded = encode_path(file_path);
+                println!(
+                    "    <file{}>{}</file>",
+                    encoded.xml_attribute(),
+                    escape_xml(&encoded.value)
+                );
+                println!("    <lines>{}-{}</lines>", line_start, line_end);
+                println!("    <code><![CDATA[{}]]></code>", code);
+                println!("  </result>");
+            }
+            println!("  <summary>");
+            println!("    <tests>{}</tests>", study.tests.len());
+            println!("    <omitted_tests>{}</omitted_tests>", study.omitted_tests);
+            println!("    <tokens>{}</tokens>", study.tokens);
+            println!("  </summary>");
+            println!("</probe_results>");
+        }
+        _ => {
+            let blocks = study
+                .definition
+                .iter()
+                .map(|d| {
+                    let label = format!("{} ({})", d.name, d.kind);
+                    (&d.file_path, d.line_start, d.line_end, label, &d.code)
+                })
+                .chain(study.tests.iter().map(|t| {
+                    let label = format!(
+                        "test {} (score {:.2}: {})",
+                        t.name,
+                        t.score,
+                        t.reasons.join(", ")
+                    );
+                    (&t.file_path, t.line_start, t.line_end, label, &t.code)
+                }));
+            for (file_path, line_start, line_end, label, code) in blocks {
+                let location = format!("{}:{}-{}", file_path.display(), line_start, line_end);
+                match format {
+                    "markdown" => {
+                        let lang = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
+                        println!("**{}** {}", location, label);
+                        println!("```{}", lang);
+                        println!("{}", code);
+                        println!("```");
+                    }
+                    "plain" => {
+                        println!("{} {}", location, label);
+                        println!("{}", code);
+                    }
+                    _ => {
+                        println!("{} {}", location.cyan(), label.bold());
+                        println!("{}", code);
+                    }
+                }
+                println!();
+            }
+            if study.omitted_tests > 0 {
+                println!(
+                    "{} more tests omitted to stay within the token budget",
+                    study.omitted_tests
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle the spec command
+pub fn handle_spec(
+    symbol: &str,
+    path: &Path,
+    ignore: &[String],
+    max_tokens: Option<usize>,
+    format: &str,
+) -> Result<()> {
+    // Only print information for non-JSON/XML formats
+    if format != "json" && format != "xml" {
+        println!("{} {}", "Symbol:".bold().green(), symbol);
+        println!("{} {}", "Path:".bold().green(), path.display());
+    }
+
+    let start_time = Instant::now();
+    let study = case_study(&CaseStudyOptions {
+        path,
+        symbol,
+        ignore,
+        max_tokens,
+    })?;
+    let duration = start_time.elapsed();
+
+    if format != "json" && format != "xml" {
+        if study.definition.is_none() {
+            let message = format!("No definition of {} found.", study.name);
+            println!("{}", message.yellow().bold());
+        }
+        println!(
+            "Found {} tests of {} (~{} tokens) in {:.2?}",
+            study.tests.len(),
+            study.name,
+            study.tokens,
+            duration
+        );
+        println!();
+    }
+
+    format_and_print_case_study(&study, format)
+}
@@ -15,7 +15,7 @@ pub struct Args {
     #[arg(value_name = "PATTERN")]
     pub pattern: Option<String>,
 
-    /// Files or directories to search (used when no subcommand is provided)
+    /// Files or directories to search, ranked together (used when no subcommand is provided)
     #[arg(value_name = "PATH")]
     pub paths: Vec<PathBuf>,
 
@@ -31,8 +31,8 @@ pub struct Args {
     #[arg(short = 'n', long = "exclude-filenames")]
     pub exclude_filenames: bool,
 
-    /// BM25 ranking for search results
-    #[arg(short = 'r', long = "reranker", default_value = "bm25", value_parser = ["bm25"])]
+    /// Ranking for search results; hybrid, hybrid2, tfidf and combined are older names for BM25
+    #[arg(short = 'r', long = "reranker", default_value = "bm25", value_parser = crate::search::options_validation::RERANKERS)]
     pub reranker: String,
 
     /// Use frequency-based search with stemming and stopword removal (enabled by default)
@@ -43,6 +43,10 @@ pub struct Args {
     #[arg(long = "exact")]
     pub exact: bool,
 
+    /// Treat the pattern as a code snippet, matched token by token regardless of formatting
+    #[arg(long = "code-pattern")]
+    pub code_pattern: bool,
+
     /// Maximum number of results to return
     #[arg(long = "max-results")]
     pub max_results: Option<usize>,
@@ -67,24 +71,252 @@ pub struct Args {
     #[arg(long = "merge-threshold")]
     pub merge_threshold: Option<usize>,
 
+    /// List the original blocks of each merged result as `merged_from` in JSON and XML output
+    #[arg(long = "merge-provenance", conflicts_with = "no_merge")]
+    pub merge_provenance: bool,
+
+    /// Don't record this search in the query history (also PROBE_NO_HISTORY=1)
+    #[arg(long = "no-history")]
+    pub no_history: bool,
+
     /// Output only file names and line numbers without full content
     #[arg(long = "dry-run")]
     pub dry_run: bool,
 
-    /// Output format (default: color)
+    /// Output format (default: auto, which is color on a terminal and json when piped)
     /// Use 'json' or 'xml' for machine-readable output
-    #[arg(short = 'o', long = "format", default_value = "color", value_parser = ["terminal", "markdown", "plain", "json", "xml", "color"])]
+    #[arg(short = 'o', long = "format", default_value = "auto", value_parser = ["auto", "terminal", "markdown", "plain", "json", "xml", "color"])]
     pub format: String,
 
     /// Session ID for caching search results
     #[arg(long = "session")]
     pub session: Option<String>,
 
+    /// List every file that was excluded from the results and the reason it was skipped
+    #[arg(long = "report-skipped")]
+    pub report_skipped: bool,
+
+    /// Include test files only when they reference this symbol (ignored with --allow-tests)
+    #[arg(long = "tests-for", value_name = "SYMBOL")]
+    pub tests_for: Option<String>,
+
+    /// Prioritize call sites and instantiations of the queried symbol over its definition,
+    /// and group results into definition and usages sections
+    #[arg(long = "usages")]
+    pub usages: bool,
+
+    /// Render results with a template file instead of a built-in format
+    /// Templates use TinyTemplate syntax over the JSON result fields
+    #[arg(long = "template", value_name = "FILE")]
+    pub template: Option<PathBuf>,
+
+    /// Path separator style in output: native (OS default) or unix (always /)
+    #[arg(long = "path-style", default_value = "native", value_parser = ["native", "unix"])]
+    pub path_style: String,
+
+    /// Follow symbolic links while scanning (cycles are detected and skipped)
+    #[arg(long = "follow-symlinks")]
+    pub follow_symlinks: bool,
+
+    /// Reject paths that resolve outside this directory, symlinks included (also PROBE_RESTRICT_ROOT)
+    #[arg(long = "restrict-root", value_name = "DIR")]
+    pub restrict_root: Option<PathBuf>,
+
+    /// Send search metrics to statsd://host:port or an OTLP/HTTP collector at http://host:port[/path]
+    #[arg(long = "metrics-endpoint", value_name = "URL")]
+    pub metrics_endpoint: Option<String>,
+
+    /// Export tracing spans for each search stage to an OTLP/HTTP collector at http://host:port[/path]
+    #[arg(long = "trace-endpoint", value_name = "URL")]
+    pub trace_endpoint: Option<String>,
+
+    /// Fail instead of leaving out files that cannot be read (permission denied, deleted during the scan)
+    #[arg(long = "strict")]
+    pub strict: bool,
+
+    /// Search root, repeatable in place of PATH; --ignore patterns after a --path apply to that root only
+    #[arg(long = "path", value_name = "DIR")]
+    pub roots: Vec<PathBuf>,
+
+    /// Only search files at most N directories deep (1 = files directly in the search path)
+    #[arg(long = "max-depth", value_name = "N")]
+    pub max_depth: Option<usize>,
+
+    /// Only search files at least N directories deep (2 = skip files directly in the search path)
+    #[arg(long = "min-depth", value_name = "N")]
+    pub min_depth: Option<usize>,
+
+    /// Only search files modified within AGE (e.g. 30m, 12h, 7d, 2w)
+    #[arg(long = "newer-than", value_name = "AGE", value_parser = crate::search::file_list_cache::parse_age)]
+    pub newer_than: Option<std::time::Duration>,
+
+    /// Only search files last modified more than AGE ago (e.g. 30m, 12h, 7d, 2w)
+    #[arg(long = "older-than", value_name = "AGE", value_parser = crate::search::file_list_cache::parse_age)]
+    pub older_than: Option<std::time::Duration>,
+
+    /// Only search files of at least SIZE bytes (k, M and G suffixes allowed)
+    #[arg(long = "min-size", value_name = "SIZE", value_parser = crate::search::file_list_cache::parse_size)]
+    pub min_size: Option<u64>,
+
+    /// Only search files of at most SIZE bytes (k, M and G suffixes allowed)
+    #[arg(long = "max-size", value_name = "SIZE", value_parser = crate::search::file_list_cache::parse_size)]
+    pub max_size: Option<u64>,
+
+    /// Only keep blocks whose last-touching commit author matches PATTERN (regex on name or email)
+    #[arg(long = "author", value_name = "PATTERN")]
+    pub author: Option<String>,
+
+    /// Trim large blocks to N lines around each matching line, keeping the signature line
+    #[arg(long = "snippet-window", value_name = "N")]
+    pub snippet_window: Option<usize>,
+
+    /// What to return for blocks longer than --oversize-threshold: the full block, its first lines (truncate), or an outline of signatures and matched lines
+    #[arg(long = "oversize-strategy", value_name = "STRATEGY", default_value = "full", value_parser = ["full", "truncate", "outline"])]
+    pub oversize_strategy: String,
+
+    /// Number of lines above which --oversize-strategy applies to a block
+    #[arg(
+        long = "oversize-threshold",
+        value_name = "LINES",
+        default_value = "300"
+    )]
+    pub oversize_threshold: usize,
+
+    /// Fold compatibility variants and case (e.g. STRASSE matches Straße, ﬁle matches file) in query terms and content
+    #[arg(long = "case-fold")]
+    pub case_fold: bool,
+
+    /// Normalize identifier terms by one language's naming conventions (e.g. getUser and UserImpl
+    /// match user, indices matches index), or by every language's with `all`
+    #[arg(long = "code-terms", value_name = "LANG", value_parser = ["all", "rust", "go", "python", "javascript", "typescript", "java", "csharp", "ruby", "php"])]
+    pub code_terms: Option<String>,
+
+    /// Also match short terms against the initials of camelCase and snake_case identifiers
+    /// (e.g. hsr matches handleSearchResults), ranking those matches below full-term matches
+    #[arg(long = "acronyms")]
+    pub acronyms: bool,
+
+    /// Search identifiers in every form they are serialized in across languages
+    /// (e.g. user_id also matches userId and user-id)
+    #[arg(long = "api-names")]
+    pub api_names: bool,
+
+    /// Table of name forms for --api-names, one `label = template` per line (implies --api-names)
+    #[arg(long = "api-names-table", value_name = "FILE")]
+    pub api_names_table: Option<PathBuf>,
+
+    /// Include the enclosing #if/#ifdef, #else and #endif lines of C/C++ blocks
+    #[arg(long = "preproc-context")]
+    pub preproc_context: bool,
+
+    /// Search SQL queries and HTML templates inside string literals as text, reporting each
+    /// matching literal as a result of its own within its enclosing block
+    #[arg(long = "embedded")]
+    pub embedded: bool,
+
+    /// Parse files with an extension as another language, e.g. inc=php or tpp=cpp (repeatable)
+    #[arg(long = "force-language", value_name = "EXT=LANG")]
+    pub force_language: Vec<String>,
+
+    /// Only search the blocks already returned in this session, to narrow down earlier results
+    #[arg(long = "within-session", value_name = "ID")]
+    pub within_session: Option<String>,
+
+    /// Drop these blocks (file:start-end, comma-separated) from the results, e.g. ones already judged irrelevant
+    #[arg(long = "exclude-blocks", value_name = "IDS", value_delimiter = ',')]
+    pub exclude_blocks: Vec<String>,
+
+    /// Always return these blocks (file:start-end, comma-separated), re-read from disk, even if the session has seen them
+    #[arg(long = "pin-blocks", value_name = "IDS", value_delimiter = ',')]
+    pub pin_blocks: Vec<String>,
+
+    /// Weight of file name matches in ranking: 0 ranks by content alone, 1 is the default, higher favors file name hits
+    #[arg(long = "filename-weight", value_name = "WEIGHT", default_value = "1.0")]
+    pub filename_weight: f64,
+
+    /// Add the README (or mod.rs/__init__.py docs) of directories that several results come from, as low-ranked context blocks
+    #[arg(long = "include-dir-context")]
+    pub include_dir_context: bool,
+
+    /// Fraction of their score that deprecated blocks lose in ranking, e.g. 0.5: 0 (the default) ranks them like any other block, 1 ranks them last
+    #[arg(
+        long = "deprecated-penalty",
+        value_name = "FACTOR",
+        default_value = "0"
+    )]
+    pub deprecated_penalty: f64,
+
+    /// Only keep blocks that declare public (exported) or private symbols, using per-language rules
+    #[arg(long = "visibility", value_name = "VISIBILITY", default_value = "any", value_parser = ["public", "private", "any"])]
+    pub visibility: String,
+
+    /// Only keep results of these kinds (comma-separated or repeatable), found from the syntax
+    /// around the query terms: definition, call_site, import, comment, string or test
+    #[arg(long = "kind", value_name = "KIND", value_delimiter = ',', value_parser = ["definition", "call_site", "import", "comment", "string", "test"])]
+    pub kinds: Vec<String>,
+
+    /// Order of results: by relevance score, by file path and line (stops early once --max-results is filled), or most complex blocks first
+    #[arg(long = "sort", value_name = "ORDER", default_value = "score", value_parser = ["score", "path", "complexity"])]
+    pub sort: String,
+
+    /// Only extract blocks from the K files that score best on a cheap pass over match counts, file names and recency
+    #[arg(long = "candidate-files", value_name = "K")]
+    pub candidate_files: Option<usize>,
+
+    /// Lines longer than this are not searched, which keeps minified and generated code out of the results
+    #[arg(long = "max-line-length", value_name = "CHARS", default_value = "2000")]
+    pub max_line_length: usize,
+
+    /// Stop scanning a file after this many matched lines
+    #[arg(
+        long = "max-matches-per-file",
+        value_name = "N",
+        default_value = "10000"
+    )]
+    pub max_matches_per_file: usize,
+
+    /// Stop scanning further files once this many lines matched in total
+    #[arg(long = "max-total-matches", value_name = "N")]
+    pub max_total_matches: Option<usize>,
+
+    /// Reject queries that expand to more than this many distinct terms
+    #[arg(long = "max-query-terms", value_name = "N", default_value = "128")]
+    pub max_query_terms: usize,
+
+    /// Write the results to this file instead of stdout
+    #[arg(long = "output", value_name = "PATH")]
+    pub output: Option<PathBuf>,
+
+    /// Compress the --output file (json and xml formats only)
+    #[arg(long = "compress", value_parser = ["gzip", "zstd"])]
+    pub compress: Option<String>,
+
+    /// Write one output file per source file into this directory instead of stdout
+    #[arg(
+        long = "split-per-file",
+        value_name = "DIR",
+        conflicts_with_all = ["output", "template"]
+    )]
+    pub split_per_file: Option<PathBuf>,
+
+    /// Refuse every file write outside probe's cache directories (also PROBE_READ_ONLY=1)
+    #[arg(long = "read-only", global = true)]
+    pub read_only: bool,
+
+    /// Read files from unsaved buffers: a JSON object mapping paths to contents, inline or in a file
+    #[arg(long = "overlay", value_name = "JSON", global = true)]
+    pub overlay: Option<String>,
+
+    /// Maximum number of threads to search with (defaults to one per CPU)
+    #[arg(long = "threads", value_name = "N", global = true)]
+    pub threads: Option<usize>,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
 
 #[derive(Subcommand, Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum Commands {
     /// Search code using patterns with intelligent ranking
     ///
@@ -97,7 +329,7 @@ pub enum Commands {
         #[arg(value_name = "PATTERN")]
         pattern: String,
 
-        /// Files or directories to search (defaults to current directory)
+        /// Files or directories to search, ranked together (defaults to current directory)
         #[arg(value_name = "PATH", default_value = ".")]
         paths: Vec<PathBuf>,
 
@@ -113,8 +345,8 @@ pub enum Commands {
         #[arg(short = 'n', long = "exclude-filenames")]
         exclude_filenames: bool,
 
-        /// BM25 ranking for search results
-        #[arg(short = 'r', long = "reranker", default_value = "bm25", value_parser = ["bm25"])]
+        /// Ranking for search results; hybrid, hybrid2, tfidf and combined are older names for BM25
+        #[arg(short = 'r', long = "reranker", default_value = "bm25", value_parser = crate::search::options_validation::RERANKERS)]
         reranker: String,
 
         /// Use frequency-based search with stemming and stopword removal (enabled by default)
@@ -125,6 +357,10 @@ pub enum Commands {
         #[arg(long = "exact")]
         exact: bool,
 
+        /// Treat the pattern as a code snippet, matched token by token regardless of formatting
+        #[arg(long = "code-pattern")]
+        code_pattern: bool,
+
         /// Maximum number of results to return
         #[arg(long = "max-results")]
         max_results: Option<usize>,
@@ -149,18 +385,233 @@ pub enum Commands {
         #[arg(long = "merge-threshold")]
         merge_threshold: Option<usize>,
 
+        /// List the original blocks of each merged result as `merged_from` in JSON and XML output
+        #[arg(long = "merge-provenance", conflicts_with = "no_merge")]
+        merge_provenance: bool,
+
+        /// Don't record this search in the query history (also PROBE_NO_HISTORY=1)
+        #[arg(long = "no-history")]
+        no_history: bool,
+
         /// Output only file names and line numbers without full content
         #[arg(long = "dry-run")]
         dry_run: bool,
 
-        /// Output format (default: color)
+        /// Output format (default: auto, which is color on a terminal and json when piped)
         /// Use 'json' or 'xml' for machine-readable output with structured data
-        #[arg(short = 'o', long = "format", default_value = "color", value_parser = ["terminal", "markdown", "plain", "json", "xml", "color"])]
+        #[arg(short = 'o', long = "format", default_value = "auto", value_parser = ["auto", "terminal", "markdown", "plain", "json", "xml", "color"])]
         format: String,
 
         /// Session ID for caching search results
         #[arg(long = "session")]
         session: Option<String>,
+
+        /// List every file that was excluded from the results and the reason it was skipped
+        #[arg(long = "report-skipped")]
+        report_skipped: bool,
+
+        /// Include test files only when they reference this symbol (ignored with --allow-tests)
+        #[arg(long = "tests-for", value_name = "SYMBOL")]
+        tests_for: Option<String>,
+
+        /// Prioritize call sites and instantiations of the queried symbol over its definition,
+        /// and group results into definition and usages sections
+        #[arg(long = "usages")]
+        usages: bool,
+
+        /// Render results with a template file instead of a built-in format
+        /// Templates use TinyTemplate syntax over the JSON result fields
+        #[arg(long = "template", value_name = "FILE")]
+        template: Option<PathBuf>,
+
+        /// Path separator style in output: native (OS default) or unix (always /)
+        #[arg(long = "path-style", default_value = "native", value_parser = ["native", "unix"])]
+        path_style: String,
+
+        /// Follow symbolic links while scanning (cycles are detected and skipped)
+        #[arg(long = "follow-symlinks")]
+        follow_symlinks: bool,
+
+        /// Reject paths that resolve outside this directory, symlinks included (also PROBE_RESTRICT_ROOT)
+        #[arg(long = "restrict-root", value_name = "DIR")]
+        restrict_root: Option<PathBuf>,
+
+        /// Send search metrics to statsd://host:port or an OTLP/HTTP collector at http://host:port[/path]
+        #[arg(long = "metrics-endpoint", value_name = "URL")]
+        metrics_endpoint: Option<String>,
+
+        /// Export tracing spans for each search stage to an OTLP/HTTP collector at http://host:port[/path]
+        #[arg(long = "trace-endpoint", value_name = "URL")]
+        trace_endpoint: Option<String>,
+
+        /// Fail instead of leaving out files that cannot be read (permission denied, deleted during the scan)
+        #[arg(long = "strict")]
+        strict: bool,
+
+        /// Search root, repeatable in place of PATH; --ignore patterns after a --path apply to that root only
+        #[arg(long = "path", value_name = "DIR")]
+        roots: Vec<PathBuf>,
+
+        /// Only search files at most N directories deep (1 = files directly in the search path)
+        #[arg(long = "max-depth", value_name = "N")]
+        max_depth: Option<usize>,
+
+        /// Only search files at least N directories deep (2 = skip files directly in the search path)
+        #[arg(long = "min-depth", value_name = "N")]
+        min_depth: Option<usize>,
+
+        /// Only search files modified within AGE (e.g. 30m, 12h, 7d, 2w)
+        #[arg(long = "newer-than", value_name = "AGE", value_parser = crate::search::file_list_cache::parse_age)]
+        newer_than: Option<std::time::Duration>,
+
+        /// Only search files last modified more than AGE ago (e.g. 30m, 12h, 7d, 2w)
+        #[arg(long = "older-than", value_name = "AGE", value_parser = crate::search::file_list_cache::parse_age)]
+        older_than: Option<std::time::Duration>,
+
+        /// Only search files of at least SIZE bytes (k, M and G suffixes allowed)
+        #[arg(long = "min-size", value_name = "SIZE", value_parser = crate::search::file_list_cache::parse_size)]
+        min_size: Option<u64>,
+
+        /// Only search files of at most SIZE bytes (k, M and G suffixes allowed)
+        #[arg(long = "max-size", value_name = "SIZE", value_parser = crate::search::file_list_cache::parse_size)]
+        max_size: Option<u64>,
+
+        /// Only keep blocks whose last-touching commit author matches PATTERN (regex on name or email)
+        #[arg(long = "author", value_name = "PATTERN")]
+        author: Option<String>,
+
+        /// Trim large blocks to N lines around each matching line, keeping the signature line
+        #[arg(long = "snippet-window", value_name = "N")]
+        snippet_window: Option<usize>,
+
+        /// What to return for blocks longer than --oversize-threshold: the full block, its first lines (truncate), or an outline of signatures and matched lines
+        #[arg(long = "oversize-strategy", value_name = "STRATEGY", default_value = "full", value_parser = ["full", "truncate", "outline"])]
+        oversize_strategy: String,
+
+        /// Number of lines above which --oversize-strategy applies to a block
+        #[arg(
+            long = "oversize-threshold",
+            value_name = "LINES",
+            default_value = "300"
+        )]
+        oversize_threshold: usize,
+
+        /// Fold compatibility variants and case (e.g. STRASSE matches Straße, ﬁle matches file) in query terms and content
+        #[arg(long = "case-fold")]
+        case_fold: bool,
+
+        /// Normalize identifier terms by one language's naming conventions (e.g. getUser and UserImpl
+        /// match user, indices matches index), or by every language's with `all`
+        #[arg(long = "code-terms", value_name = "LANG", value_parser = ["all", "rust", "go", "python", "javascript", "typescript", "java", "csharp", "ruby", "php"])]
+        code_terms: Option<String>,
+
+        /// Also match short terms against the initials of camelCase and snake_case identifiers
+        /// (e.g. hsr matches handleSearchResults), ranking those matches below full-term matches
+        #[arg(long = "acronyms")]
+        acronyms: bool,
+
+        /// Search identifiers in every form they are serialized in across languages
+        /// (e.g. user_id also matches userId and user-id)
+        #[arg(long = "api-names")]
+        api_names: bool,
+
+        /// Table of name forms for --api-names, one `label = template` per line (implies --api-names)
+        #[arg(long = "api-names-table", value_name = "FILE")]
+        api_names_table: Option<PathBuf>,
+
+        /// Include the enclosing #if/#ifdef, #else and #endif lines of C/C++ blocks
+        #[arg(long = "preproc-context")]
+        preproc_context: bool,
+
+        /// Search SQL queries and HTML templates inside string literals as text, reporting each
+        /// matching literal as a result of its own within its enclosing block
+        #[arg(long = "embedded")]
+        embedded: bool,
+
+        /// Parse files with an extension as another language, e.g. inc=php or tpp=cpp (repeatable)
+        #[arg(long = "force-language", value_name = "EXT=LANG")]
+        force_language: Vec<String>,
+
+        /// Only search the blocks already returned in this session, to narrow down earlier results
+        #[arg(long = "within-session", value_name = "ID")]
+        within_session: Option<String>,
+
+        /// Drop these blocks (file:start-end, comma-separated) from the results, e.g. ones already judged irrelevant
+        #[arg(long = "exclude-blocks", value_name = "IDS", value_delimiter = ',')]
+        exclude_blocks: Vec<String>,
+
+        /// Always return these blocks (file:start-end, comma-separated), re-read from disk, even if the session has seen them
+        #[arg(long = "pin-blocks", value_name = "IDS", value_delimiter = ',')]
+        pin_blocks: Vec<String>,
+
+        /// Weight of file name matches in ranking: 0 ranks by content alone, 1 is the default, higher favors file name hits
+        #[arg(long = "filename-weight", value_name = "WEIGHT", default_value = "1.0")]
+        filename_weight: f64,
+
+        /// Add the README (or mod.rs/__init__.py docs) of directories that several results come from, as low-ranked context blocks
+        #[arg(long = "include-dir-context")]
+        include_dir_context: bool,
+
+        /// Fraction of their score that deprecated blocks lose in ranking, e.g. 0.5: 0 (the default) ranks them like any other block, 1 ranks them last
+        #[arg(
+            long = "deprecated-penalty",
+            value_name = "FACTOR",
+            default_value = "0"
+        )]
+        deprecated_penalty: f64,
+
+        /// Only keep blocks that declare public (exported) or private symbols, using per-language rules
+        #[arg(long = "visibility", value_name = "VISIBILITY", default_value = "any", value_parser = ["public", "private", "any"])]
+        visibility: String,
+
+        /// Only keep results of these kinds (comma-separated or repeatable), found from the syntax
+        /// around the query terms: definition, call_site, import, comment, string or test
+        #[arg(long = "kind", value_name = "KIND", value_delimiter = ',', value_parser = ["definition", "call_site", "import", "comment", "string", "test"])]
+        kinds: Vec<String>,
+
+        /// Order of results: by relevance score, by file path and line (stops early once --max-results is filled), or most complex blocks first
+        #[arg(long = "sort", value_name = "ORDER", default_value = "score", value_parser = ["score", "path", "complexity"])]
+        sort: String,
+
+        /// Only extract blocks from the K files that score best on a cheap pass over match counts, file names and recency
+        #[arg(long = "candidate-files", value_name = "K")]
+        candidate_files: Option<usize>,
+
+        /// Lines longer than this are not searched, which keeps minified and generated code out of the results
+        #[arg(long = "max-line-length", value_name = "CHARS", default_value = "2000")]
+        max_line_length: usize,
+
+        /// Stop scanning a file after this many matched lines
+        #[arg(
+            long = "max-matches-per-file",
+            value_name = "N",
+            default_value = "10000"
+        )]
+        max_matches_per_file: usize,
+
+        /// Stop scanning further files once this many lines matched in total
+        #[arg(long = "max-total-matches", value_name = "N")]
+        max_total_matches: Option<usize>,
+
+        /// Reject queries that expand to more than this many distinct terms
+        #[arg(long = "max-query-terms", value_name = "N", default_value = "128")]
+        max_query_terms: usize,
+
+        /// Write the results to this file instead of stdout
+        #[arg(long = "output", value_name = "PATH")]
+        output: Option<PathBuf>,
+
+        /// Compress the --output file (json and xml formats only)
+        #[arg(long = "compress", value_parser = ["gzip", "zstd"])]
+        compress: Option<String>,
+
+        /// Write one output file per source file into this directory instead of stdout
+        #[arg(
+            long = "split-per-file",
+            value_name = "DIR",
+            conflicts_with_all = ["output", "template"]
+        )]
+        split_per_file: Option<PathBuf>,
     },
 
     /// Extract code blocks from files
@@ -171,7 +622,8 @@ pub enum Commands {
     /// You can also specify a symbol name using the hash syntax (e.g., file.rs#function_name) to
     /// extract the code block for that specific symbol.
     Extract {
-        /// Files to extract from (can include line numbers with colon, e.g., file.rs:10, or symbol names with hash, e.g., file.rs#function_name)
+        /// Files to extract from (can include line numbers with colon, e.g., file.rs:10, or symbol names with hash, e.g., file.rs#function_name).
+        /// Use `-` to read a list of files from stdin, one per line
         #[arg(value_name = "FILES")]
         files: Vec<String>,
 
@@ -183,9 +635,9 @@ pub enum Commands {
         #[arg(short = 'c', long = "context", default_value = "0")]
         context_lines: usize,
 
-        /// Output format (default: color)
+        /// Output format (default: auto, which is color on a terminal and json when piped)
         /// Use 'json' or 'xml' for machine-readable output with structured data
-        #[arg(short = 'o', long = "format", default_value = "color", value_parser = ["markdown", "plain", "json", "xml", "color"])]
+        #[arg(short = 'o', long = "format", default_value = "auto", value_parser = ["auto", "markdown", "plain", "json", "xml", "color"])]
         format: String,
 
         /// Read input from clipboard instead of files
@@ -204,9 +656,68 @@ pub enum Commands {
         #[arg(long = "diff")]
         diff: bool,
 
+        /// Diff the working tree against this git revision and extract the changed blocks.
+        /// FILES, if given, limit the diff to those paths
+        #[arg(long = "diff-base", value_name = "REV")]
+        diff_base: Option<String>,
+
+        /// Extract the files, symbols and line ranges listed in a YAML or JSON spec file, in order,
+        /// with optional per-entry `context` and `allow_tests`
+        #[arg(long = "spec", value_name = "FILE", conflicts_with_all = ["diff", "diff_base", "from_clipboard"])]
+        spec: Option<PathBuf>,
+
+        /// After the extracted blocks, list the sibling symbols, callers and callees of each
+        /// block (names and locations only) as candidates to extract next
+        #[arg(long = "suggest-related")]
+        suggest_related: bool,
+
         /// Allow test files and test code blocks in extraction results (only applies when reading from stdin or clipboard)
         #[arg(long = "allow-tests")]
         allow_tests: bool,
+
+        /// Let glob patterns match files through symbolic links (named files are always read)
+        #[arg(long = "follow-symlinks")]
+        follow_symlinks: bool,
+
+        /// Reject paths that resolve outside this directory, symlinks included (also PROBE_RESTRICT_ROOT)
+        #[arg(long = "restrict-root", value_name = "DIR")]
+        restrict_root: Option<PathBuf>,
+
+        /// Write the results to this file instead of stdout
+        #[arg(long = "output", value_name = "PATH", conflicts_with = "to_clipboard")]
+        output: Option<PathBuf>,
+
+        /// Compress the --output file (json and xml formats only)
+        #[arg(long = "compress", value_parser = ["gzip", "zstd"])]
+        compress: Option<String>,
+    },
+
+    /// Preview the blocks a proposed edit would change, without writing it
+    ///
+    /// This command applies a unified diff in memory and extracts the code blocks
+    /// around each changed line from the patched files, so a proposed edit can be
+    /// checked in context before it is applied.
+    Preview {
+        /// Unified diff to apply, as written by `git diff` or `diff -u` (`-` reads stdin)
+        #[arg(long = "patch", value_name = "FILE")]
+        patch: PathBuf,
+
+        /// Number of context lines to include before and after the extracted block
+        #[arg(short = 'c', long = "context", default_value = "0")]
+        context_lines: usize,
+
+        /// Output format (default: auto, which is color on a terminal and json when piped)
+        /// Use 'json' or 'xml' for machine-readable output with structured data
+        #[arg(short = 'o', long = "format", default_value = "auto", value_parser = ["auto", "markdown", "plain", "json", "xml", "color"])]
+        format: String,
+
+        /// Allow test files and test code blocks in the preview
+        #[arg(long = "allow-tests")]
+        allow_tests: bool,
+
+        /// Reject paths that resolve outside this directory, symlinks included (also PROBE_RESTRICT_ROOT)
+        #[arg(long = "restrict-root", value_name = "DIR")]
+        restrict_root: Option<PathBuf>,
     },
 
     /// Search code using AST patterns for precise structural matching
@@ -244,9 +755,532 @@ pub enum Commands {
         #[arg(long = "max-results")]
         max_results: Option<usize>,
 
-        /// Output format (default: color)
+        /// Output format (default: auto, which is color on a terminal and json when piped)
+        /// Use 'json' or 'xml' for machine-readable output with structured data
+        #[arg(short = 'o', long = "format", default_value = "auto", value_parser = ["auto", "markdown", "plain", "json", "xml", "color"])]
+        format: String,
+
+        /// Reject paths that resolve outside this directory, symlinks included (also PROBE_RESTRICT_ROOT)
+        #[arg(long = "restrict-root", value_name = "DIR")]
+        restrict_root: Option<PathBuf>,
+    },
+
+    /// Search for functions by their type signature
+    ///
+    /// This command parses source files with tree-sitter and matches function
+    /// parameter and return types against a signature pattern. Use `_` for any
+    /// single type and `..` for any number of parameters. Supports Rust (.rs),
+    /// Go (.go) and TypeScript (.ts, .tsx).
+    Sig {
+        /// Signature pattern (e.g., "fn(&str) -> Result<_>", "func(string) error", "function(string): Promise<_>")
+        #[arg(value_name = "PATTERN")]
+        pattern: String,
+
+        /// Files or directories to search (defaults to current directory)
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+
+        /// Only search files of this language (all supported languages if not specified)
+        #[arg(short = 'l', long = "language", value_parser = ["rust", "go", "typescript"])]
+        language: Option<String>,
+
+        /// Custom patterns to ignore (in addition to .gitignore and common patterns)
+        #[arg(short, long)]
+        ignore: Vec<String>,
+
+        /// Allow test files in search results
+        #[arg(long = "allow-tests")]
+        allow_tests: bool,
+
+        /// Maximum number of results to return
+        #[arg(long = "max-results")]
+        max_results: Option<usize>,
+
+        /// Output format (default: auto, which is color on a terminal and json when piped)
+        /// Use 'json' or 'xml' for machine-readable output with structured data
+        #[arg(short = 'o', long = "format", default_value = "auto", value_parser = ["auto", "markdown", "plain", "json", "xml", "color"])]
+        format: String,
+    },
+
+    /// Show how a code block changed over time
+    ///
+    /// This command locates a block with the `FILE#SYMBOL` addressing used by
+    /// `extract`, then follows its lines back through git history with
+    /// `git log -L`, listing every commit that touched it with its diff.
+    History {
+        /// Block to follow (e.g., "src/main.rs#handle_search")
+        #[arg(value_name = "FILE#SYMBOL")]
+        target: String,
+
+        /// Maximum number of commits to show (newest first)
+        #[arg(long = "max-commits")]
+        max_commits: Option<usize>,
+
+        /// Also show the extracted block before and after each commit
+        #[arg(long = "versions")]
+        versions: bool,
+
+        /// Output format (default: auto, which is color on a terminal and json when piped)
         /// Use 'json' or 'xml' for machine-readable output with structured data
-        #[arg(short = 'o', long = "format", default_value = "color", value_parser = ["markdown", "plain", "json", "xml", "color"])]
+        #[arg(short = 'o', long = "format", default_value = "auto", value_parser = ["auto", "markdown", "plain", "json", "xml", "color"])]
         format: String,
     },
+
+    /// Trace how an error type moves through the code
+    ///
+    /// This command finds where an error type is defined, constructed, converted
+    /// (e.g. `impl From`, `map_err`), propagated (e.g. `?`, `throws`) and matched
+    /// on (e.g. `match`, `catch`, `except`), and groups the results by role.
+    Errors {
+        /// Name of the error type (e.g., "ConfigError")
+        #[arg(value_name = "TYPE")]
+        type_name: String,
+
+        /// Files or directories to search (defaults to current directory)
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+
+        /// Custom patterns to ignore (in addition to .gitignore and common patterns)
+        #[arg(short, long)]
+        ignore: Vec<String>,
+
+        /// Allow test files in search results
+        #[arg(long = "allow-tests")]
+        allow_tests: bool,
+
+        /// Maximum number of results to return
+        #[arg(long = "max-results")]
+        max_results: Option<usize>,
+
+        /// Output format (default: auto, which is color on a terminal and json when piped)
+        /// Use 'json' or 'xml' for machine-readable output with structured data
+        #[arg(short = 'o', long = "format", default_value = "auto", value_parser = ["auto", "markdown", "plain", "json", "xml", "color"])]
+        format: String,
+    },
+
+    /// Find the types implementing a trait or interface
+    ///
+    /// This command finds Rust `impl Trait for Type` blocks, class and interface
+    /// declarations that list the interface (`implements`, `extends`, `: Base`),
+    /// and Go types whose methods cover all of the interface's methods.
+    Impls {
+        /// Name of the trait or interface (e.g., "Display", "io.Reader")
+        #[arg(value_name = "TRAIT")]
+        trait_name: String,
+
+        /// Files or directories to search (defaults to current directory)
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+
+        /// Custom patterns to ignore (in addition to .gitignore and common patterns)
+        #[arg(short, long)]
+        ignore: Vec<String>,
+
+        /// Allow test files in search results
+        #[arg(long = "allow-tests")]
+        allow_tests: bool,
+
+        /// Maximum number of results to return
+        #[arg(long = "max-results")]
+        max_results: Option<usize>,
+
+        /// Output format (default: auto, which is color on a terminal and json when piped)
+        /// Use 'json' or 'xml' for machine-readable output with structured data
+        #[arg(short = 'o', long = "format", default_value = "auto", value_parser = ["auto", "markdown", "plain", "json", "xml", "color"])]
+        format: String,
+    },
+
+    /// Show the supertypes and subtypes of a type
+    ///
+    /// This command builds the type hierarchy from Rust `impl` blocks and supertraits,
+    /// `extends`/`implements` clauses, base class lists and Go embedding and method
+    /// sets, and prints the supertypes and subtypes of the type as trees with the
+    /// file location of each relationship.
+    Hierarchy {
+        /// Name of the type, trait or interface (e.g., "Shape", "io.Reader")
+        #[arg(value_name = "TYPE")]
+        type_name: String,
+
+        /// Files or directories to search (defaults to current directory)
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+
+        /// Custom patterns to ignore (in addition to .gitignore and common patterns)
+        #[arg(short, long)]
+        ignore: Vec<String>,
+
+        /// Allow test files in search results
+        #[arg(long = "allow-tests")]
+        allow_tests: bool,
+
+        /// Maximum number of levels to show above and below the type
+        #[arg(long = "depth")]
+        depth: Option<usize>,
+
+        /// Output format (default: auto, which is color on a terminal and json when piped)
+        /// Use 'json' for machine-readable output with structured data
+        #[arg(short = 'o', long = "format", default_value = "auto", value_parser = ["auto", "markdown", "plain", "json", "color"])]
+        format: String,
+    },
+
+    /// Find the most likely definition of an identifier
+    ///
+    /// Instead of returning every textual match, this command finds the declarations
+    /// of the identifier with tree-sitter and ranks them by symbol kind and, given
+    /// the place the identifier is used with --from, by whether the definition is in
+    /// the same file, in a module that file imports, or in a nearby directory.
+    Def {
+        /// The identifier, optionally qualified (e.g., "load", "Config::load", "config.load")
+        #[arg(value_name = "IDENTIFIER")]
+        identifier: String,
+
+        /// Files or directories to search (defaults to current directory)
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+
+        /// Where the identifier is used, as FILE or FILE:LINE
+        #[arg(long = "from", value_name = "FILE[:LINE]")]
+        from: Option<String>,
+
+        /// Custom patterns to ignore (in addition to .gitignore and common patterns)
+        #[arg(short, long)]
+        ignore: Vec<String>,
+
+        /// Allow test files in search results
+        #[arg(long = "allow-tests")]
+        allow_tests: bool,
+
+        /// Maximum number of definitions to return, best first (default: 1)
+        #[arg(long = "max-results")]
+        max_results: Option<usize>,
+
+        /// Output format (default: auto, which is color on a terminal and json when piped)
+        /// Use 'json' or 'xml' for machine-readable output with structured data
+        #[arg(short = 'o', long = "format", default_value = "auto", value_parser = ["auto", "markdown", "plain", "json", "xml", "color"])]
+        format: String,
+    },
+
+    /// Show a symbol's definition followed by the tests that exercise it
+    ///
+    /// This command resolves the definition like `probe def`, then finds every test
+    /// block that references the symbol: test functions, functions in test files and
+    /// functions with a test attribute. Tests are ranked by how often they mention
+    /// the symbol, whether they are named after it and whether they sit next to or
+    /// test the definition's module. With --max-tokens, the best tests that fit are kept.
+    Spec {
+        /// The symbol, optionally qualified (e.g., "load", "Config::load", "config.load")
+        #[arg(value_name = "SYMBOL")]
+        symbol: String,
+
+        /// Files or directories to search (defaults to current directory)
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+
+        /// Custom patterns to ignore (in addition to .gitignore and common patterns)
+        #[arg(short, long)]
+        ignore: Vec<String>,
+
+        /// Maximum number of tokens for the definition and tests together
+        #[arg(long = "max-tokens")]
+        max_tokens: Option<usize>,
+
+        /// Output format (default: auto, which is color on a terminal and json when piped)
+        /// Use 'json' or 'xml' for machine-readable output with structured data
+        #[arg(short = 'o', long = "format", default_value = "auto", value_parser = ["auto", "markdown", "plain", "json", "xml", "color"])]
+        format: String,
+    },
+
+    /// Summarize the public API of a module or package
+    ///
+    /// This command lists the public symbols of each file in a directory with their
+    /// signatures and doc comments, and each file's module documentation, as an
+    /// "API card" for the module. With --max-tokens, doc comments are cut to their
+    /// first sentence and then symbols left out until the card fits.
+    Summarize {
+        /// Directory or file to summarize (defaults to current directory)
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+
+        /// Custom patterns to ignore (in addition to .gitignore and common patterns)
+        #[arg(short, long)]
+        ignore: Vec<String>,
+
+        /// Allow test files in search results
+        #[arg(long = "allow-tests")]
+        allow_tests: bool,
+
+        /// Maximum number of tokens for the whole card
+        #[arg(long = "max-tokens")]
+        max_tokens: Option<usize>,
+
+        /// Output format (default: auto, which is color on a terminal and json when piped)
+        /// Use 'json' for machine-readable output with structured data
+        #[arg(short = 'o', long = "format", default_value = "auto", value_parser = ["auto", "markdown", "plain", "json", "color"])]
+        format: String,
+    },
+
+    /// List the searches run in a repository
+    ///
+    /// Every search is recorded with its arguments and a digest of its results
+    /// in a per-repository history under `~/.cache/probe/history`. Entries are
+    /// numbered from the oldest; pass a number to `probe replay` to run it again.
+    HistoryQueries {
+        /// A path in the repository (defaults to current directory)
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+
+        /// Only show the N most recent searches
+        #[arg(long = "limit")]
+        limit: Option<usize>,
+
+        /// Output format (default: auto, which is color on a terminal and json when piped)
+        /// Use 'json' for machine-readable output with structured data
+        #[arg(short = 'o', long = "format", default_value = "auto", value_parser = ["auto", "plain", "json", "color"])]
+        format: String,
+    },
+
+    /// Run a search from the query history again
+    ///
+    /// The search runs with its original arguments in its original working
+    /// directory, and reports whether its results changed since it was recorded.
+    Replay {
+        /// Number of the search, as listed by `probe history-queries`
+        #[arg(value_name = "N")]
+        number: usize,
+
+        /// A path in the repository (defaults to current directory)
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+    },
+
+    /// Check whether saved search results still match the code
+    ///
+    /// This command reads the JSON output of an earlier `probe search`, run from the
+    /// same directory, and hashes the files its results came from again. Results whose
+    /// file changed or disappeared since the search are reported as stale, and the
+    /// command fails when there are any. With --rerun, the query runs again over the
+    /// changed files only.
+    Verify {
+        /// JSON output of `probe search --format json` (`-` reads stdin)
+        #[arg(value_name = "RESULTS")]
+        results: PathBuf,
+
+        /// Search the changed files again for this query and print the fresh results
+        #[arg(long = "rerun", value_name = "QUERY")]
+        rerun: Option<String>,
+
+        /// Output format (default: auto, which is color on a terminal and json when piped)
+        /// Use 'json' for machine-readable output with structured data
+        #[arg(short = 'o', long = "format", default_value = "auto", value_parser = ["auto", "plain", "json", "color"])]
+        format: String,
+    },
+
+    /// Find blocks similar to a piece of code
+    ///
+    /// This command answers "have we written this before?". The snippet is the given
+    /// lines of --file, or stdin without --file. Blocks containing its most distinctive
+    /// identifiers are collected with the reranker, then ordered by the share of token
+    /// shingles (runs of three consecutive tokens) they have in common with the snippet.
+    /// The block the snippet was taken from is left out.
+    Similar {
+        /// Directory to search (defaults to current directory)
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+
+        /// File to take the snippet from
+        #[arg(long = "file", value_name = "FILE")]
+        file: Option<PathBuf>,
+
+        /// Lines of --file to take, e.g. 10-40 (defaults to the whole file)
+        #[arg(long = "lines", value_name = "START-END", requires = "file")]
+        lines: Option<String>,
+
+        /// Custom patterns to ignore (in addition to .gitignore and common patterns)
+        #[arg(short, long)]
+        ignore: Vec<String>,
+
+        /// Allow test files in search results
+        #[arg(long = "allow-tests")]
+        allow_tests: bool,
+
+        /// Maximum number of results to return
+        #[arg(long = "max-results", default_value = "10")]
+        max_results: usize,
+
+        /// Leave out blocks sharing less than this share of shingles with the snippet (0 to 1)
+        #[arg(long = "min-similarity", value_name = "SHARE", default_value = "0.1")]
+        min_similarity: f64,
+
+        /// Output format (default: auto, which is color on a terminal and json when piped)
+        /// Use 'json' for machine-readable output with structured data
+        #[arg(short = 'o', long = "format", default_value = "auto", value_parser = ["auto", "plain", "json", "color"])]
+        format: String,
+    },
+
+    /// Measure search quality on a dataset of labeled queries
+    ///
+    /// The dataset is a JSON file listing queries with the files or `file:START-END`
+    /// blocks each should find, and optionally several search configurations to compare.
+    /// Precision and recall at the result limit and mean reciprocal rank are reported for
+    /// each configuration, and queries missing expected results are listed on stderr.
+    Eval {
+        /// Dataset file
+        #[arg(value_name = "DATASET")]
+        dataset: PathBuf,
+
+        /// Results per query that are scored (overrides the dataset's max_results, default 10)
+        #[arg(long = "max-results")]
+        max_results: Option<usize>,
+
+        /// Output format (default: auto, which is color on a terminal and json when piped)
+        /// Use 'json' for machine-readable output with structured data
+        #[arg(short = 'o', long = "format", default_value = "auto", value_parser = ["auto", "plain", "json", "color"])]
+        format: String,
+    },
+
+    /// Run a Model Context Protocol server over stdio
+    ///
+    /// The server offers `search`, `extract` and `query` as tools, so AI agents can call
+    /// probe directly. Requests are read from stdin and responses written to stdout, one
+    /// JSON-RPC message per line. Search sessions last as long as the server runs.
+    Mcp,
+
+    /// Answer search queries from a long-running process with warm caches
+    ///
+    /// The directory is walked once, and the term counts of every file, the parse trees of
+    /// matched files and compiled query patterns are kept between queries. Queries are read
+    /// one per line from stdin, or from clients of a Unix socket with --socket, either as
+    /// the query itself or as JSON like {"query": "...", "session": "id", "paths": ["src"]};
+    /// each gets one line of JSON results back.
+    Serve {
+        /// Directory to search (defaults to current directory)
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+
+        /// Watch the directory and apply file changes before the next query
+        #[arg(long = "watch")]
+        watch: bool,
+
+        /// Read queries from clients of a Unix socket at this path instead of stdin
+        #[arg(long = "socket", value_name = "PATH")]
+        socket: Option<PathBuf>,
+
+        /// POST results of the --notify-query queries that are new after file changes to this
+        /// URL, as JSON
+        #[arg(long = "notify-url", value_name = "URL", requires_all = ["watch", "notify_queries"])]
+        notify_url: Option<String>,
+
+        /// Query whose new results are sent to --notify-url (can be given several times)
+        #[arg(long = "notify-query", value_name = "QUERY", requires = "notify_url")]
+        notify_queries: Vec<String>,
+
+        /// Refuse requests that don't send this token as `token` (also PROBE_SERVE_TOKEN)
+        #[arg(long = "auth-token", value_name = "TOKEN")]
+        auth_token: Option<String>,
+
+        /// Answer at most this many requests a minute for each token; requests without the
+        /// token share one count
+        #[arg(long = "rate-limit", value_name = "N")]
+        rate_limit: Option<usize>,
+
+        /// Only search this file or directory below PATH, refusing requests for others (can
+        /// be given several times)
+        #[arg(long = "allow-path", value_name = "PATH")]
+        allow_paths: Vec<PathBuf>,
+
+        /// Custom patterns to ignore (in addition to .gitignore and common patterns)
+        #[arg(short, long)]
+        ignore: Vec<String>,
+
+        /// Allow test files and test code blocks in search results
+        #[arg(long = "allow-tests")]
+        allow_tests: bool,
+
+        /// Use exact matching without stemming or stopword removal
+        #[arg(long = "exact")]
+        exact: bool,
+
+        /// Maximum number of results to return per query
+        #[arg(long = "max-results")]
+        max_results: Option<usize>,
+
+        /// Maximum total tokens in code content to return per query
+        #[arg(long = "max-tokens")]
+        max_tokens: Option<usize>,
+    },
+
+    /// Build or update the search index of a directory
+    ///
+    /// The index records the modification time, length and distinct tokens of every
+    /// file in `.probe/index.json`. Searches under the directory use it on their own to
+    /// leave unread the unchanged files that cannot match, and scan every file as usual
+    /// without one.
+    Index {
+        #[command(subcommand)]
+        action: IndexCommands,
+    },
+}
+
+/// What `probe index` does
+#[derive(Subcommand, Debug)]
+pub enum IndexCommands {
+    /// Index every file in a directory, replacing any index it has
+    Build {
+        /// Directory to index (defaults to current directory)
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+    },
+
+    /// Read again only the files added or changed since the directory was indexed
+    Update {
+        /// Indexed directory (defaults to current directory)
+        #[arg(value_name = "PATH", default_value = ".")]
+        path: PathBuf,
+    },
+}
+
+/// Function to pick the concrete output format for `--format auto`
+///
+/// Terminals get colored output; anything else (a pipe, a file, an agent capturing
+/// stdout) gets JSON so it can be parsed. Any explicit format is returned unchanged.
+pub fn resolve_format(format: &str, stdout_is_tty: bool) -> String {
+    match format {
+        "auto" if stdout_is_tty => "color".to_string(),
+        "auto" => "json".to_string(),
+        explicit => explicit.to_string(),
+    }
+}
+
+impl Args {
+    /// Replace `--format auto` on the top-level search and every subcommand with
+    /// the format chosen for where stdout goes
+    pub fn resolve_auto_format(&mut self) {
+        let stdout_is_tty = atty::is(atty::Stream::Stdout);
+        let resolve = |format: &mut String| *format = resolve_format(format, stdout_is_tty);
+
+        resolve(&mut self.format);
+        match &mut self.command {
+            Some(Commands::Search { format, .. })
+            | Some(Commands::Extract { format, .. })
+            | Some(Commands::Preview { format, .. })
+            | Some(Commands::Query { format, .. })
+            | Some(Commands::Sig { format, .. })
+            | Some(Commands::History { format, .. })
+            | Some(Commands::Errors { format, .. })
+            | Some(Commands::Impls { format, .. })
+            | Some(Commands::Hierarchy { format, .. })
+            | Some(Commands::Def { format, .. })
+            | Some(Commands::Spec { format, .. })
+            | Some(Commands::Summarize { format, .. })
+            | Some(Commands::HistoryQueries { format, .. })
+            | Some(Commands::Verify { format, .. })
+            | Some(Commands::Similar { format, .. })
+            | Some(Commands::Eval { format, .. }) => resolve(format),
+            Some(Commands::Replay { .. })
+            | Some(Commands::Mcp)
+            | Some(Commands::Serve { .. })
+            | Some(Commands::Index { .. })
+            | None => {}
+        }
+    }
 }
@@ -0,0 +1,777 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::time::Instant;
+use tree_sitter::{Node, Parser as TSParser};
+
+use crate::fs_access;
+use crate::impls::{go_receiver_type, node_text, walk};
+use crate::language::detection::{detect_language, LanguageOverrides};
+use crate::language::factory::get_language_impl;
+use crate::language::visibility::{block_visibility, Visibility};
+use crate::path_encoding::encode_path;
+use crate::search::file_list_cache;
+
+/// What kind of symbol a definition declares
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    Function,
+    Type,
+    Module,
+    Constant,
+    Field,
+    Variable,
+}
+
+impl SymbolKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SymbolKind::Function => "function",
+            SymbolKind::Type => "type",
+            SymbolKind::Module => "module",
+            SymbolKind::Constant => "constant",
+            SymbolKind::Field => "field",
+            SymbolKind::Variable => "variable",
+        }
+    }
+
+    /// How likely a lookup is to be after a symbol of this kind
+    fn weight(&self) -> f64 {
+        match self {
+            SymbolKind::Function | SymbolKind::Type => 1.0,
+            SymbolKind::Module => 0.8,
+            SymbolKind::Constant => 0.7,
+            SymbolKind::Field => 0.5,
+            SymbolKind::Variable => 0.4,
+        }
+    }
+
+    /// Helper function to classify a tree-sitter declaration node kind
+    pub(crate) fn from_node_kind(kind: &str) -> Self {
+        let has = |words: &[&str]| words.iter().any(|word| kind.contains(word));
+        if has(&["variant", "enumerator", "enum_member", "enum_entry"]) {
+            SymbolKind::Constant
+        } else if has(&["function", "method", "constructor", "macro"]) {
+            SymbolKind::Function
+        } else if has(&[
+            "class",
+            "struct",
+            "enum",
+            "trait",
+            "interface",
+            "type",
+            "union",
+            "protocol",
+            "object",
+            "record",
+        ]) {
+            SymbolKind::Type
+        } else if has(&["mod", "namespace"]) {
+            SymbolKind::Module
+        } else if has(&["const", "static"]) {
+            SymbolKind::Constant
+        } else if has(&["field", "property"]) {
+            SymbolKind::Field
+        } else {
+            SymbolKind::Variable
+        }
+    }
+}
+
+impl fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A definition of the identifier, with the score it was ranked by
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub file_path: PathBuf,
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Name of the type, module or function the definition is nested in
+    pub container: Option<String>,
+    pub line_start: usize,
+    pub line_end: usize,
+    pub node_type: String,
+    pub code: String,
+    pub score: f64,
+    /// Why the definition scored as it did, e.g. "imported by name"
+    pub reasons: Vec<String>,
+}
+
+/// Where the identifier is used, from `--from file[:line]`
+#[derive(Debug, Clone)]
+pub struct UseSite {
+    pub file_path: PathBuf,
+    pub line: Option<usize>,
+}
+
+impl UseSite {
+    /// Parse a `file` or `file:line` reference
+    pub fn parse(value: &str) -> Result<Self> {
+        if let Some((file, line)) = value.rsplit_once(':') {
+            if let Ok(line) = line.parse::<usize>() {
+                return Ok(UseSite {
+                    file_path: PathBuf::from(file),
+                    line: Some(line),
+                });
+            }
+        }
+        if value.is_empty() {
+            return Err(anyhow!(
+                "Invalid --from location: expected FILE or FILE:LINE"
+            ));
+        }
+        Ok(UseSite {
+            file_path: PathBuf::from(value),
+            line: None,
+        })
+    }
+}
+
+/// Options for resolving a definition
+pub struct DefinitionOptions<'a> {
+    pub path: &'a Path,
+    /// The identifier, optionally qualified (`Config::load`, `config.load`)
+    pub identifier: &'a str,
+    pub from: Option<&'a UseSite>,
+    pub ignore: &'a [String],
+    pub allow_tests: bool,
+    pub max_results: Option<usize>,
+}
+
+/// The ranked definitions of an identifier
+#[derive(Debug, Clone)]
+pub struct Resolution {
+    pub name: String,
+    /// Best definitions first, up to `max_results`
+    pub definitions: Vec<Definition>,
+    /// Number of definitions found before truncating to `max_results`
+    pub candidates: usize,
+}
+
+/// Declaration node kinds that name a symbol but are not definitions
+const EXCLUDED_KIND_WORDS: &[&str] = &["import", "use_", "parameter", "argument", "call"];
+
+/// Module file names that stand for their directory (`mod.rs`, `index.ts`, `__init__.py`)
+const DIRECTORY_MODULE_STEMS: &[&str] = &["mod", "index", "__init__", "lib", "main"];
+
+/// Segments of import paths that don't name a module
+const IMPORT_NOISE: &[&str] = &["crate", "self", "super", "std", "h", "js", "ts", "py"];
+
+/// Helper function to check whether a node kind declares a named symbol
+pub(crate) fn is_declaration_kind(kind: &str) -> bool {
+    if EXCLUDED_KIND_WORDS.iter().any(|word| kind.contains(word)) {
+        return false;
+    }
+    [
+        "_item",
+        "_declaration",
+        "_definition",
+        "_spec",
+        "_signature",
+        "_declarator",
+        "_specifier",
+    ]
+    .iter()
+    .any(|suffix| kind.ends_with(suffix))
+        || matches!(
+            kind,
+            "class" | "module" | "method" | "singleton_method" | "enum_variant"
+        )
+}
+
+/// Helper function to follow C and C++ declarators down to the declared name
+pub(crate) fn declarator_name(node: Node) -> Option<Node> {
+    let mut current = node.child_by_field_name("declarator")?;
+    while let Some(inner) = current.child_by_field_name("declarator") {
+        current = inner;
+    }
+    Some(current)
+}
+
+/// Helper function to check whether a declaration node declares `name`
+fn declares(node: Node, name: &str, content: &[u8]) -> bool {
+    let kind = node.kind();
+    // `struct foo x;` names a struct without defining it
+    if kind.ends_with("_specifier") && node.child_by_field_name("body").is_none() {
+        return false;
+    }
+    let mut cursor = node.walk();
+    let named = node
+        .children_by_field_name("name", &mut cursor)
+        .any(|child| node_text(child, content) == name);
+    named
+        || (matches!(kind, "function_definition" | "type_definition")
+            && declarator_name(node).is_some_and(|declarator| {
+                let text = node_text(declarator, content);
+                text.rsplit("::").next() == Some(name)
+            }))
+}
+
+/// Helper function to find the name of the type, module or function a node is nested in
+pub(crate) fn container_name(node: Node, content: &[u8]) -> Option<String> {
+    if node.kind() == "method_declaration" {
+        if let Some(receiver) = node.child_by_field_name("receiver") {
+            return go_receiver_type(node_text(receiver, content));
+        }
+    }
+    let mut current = node.parent();
+    while let Some(parent) = current {
+        if parent.kind() == "impl_item" {
+            return parent
+                .child_by_field_name("type")
+                .map(|type_node| node_text(type_node, content).to_string());
+        }
+        if is_declaration_kind(parent.kind()) {
+            if let Some(name) = parent.child_by_field_name("name") {
+                return Some(node_text(name, content).to_string());
+            }
+        }
+        current = parent.parent();
+    }
+    None
+}
+
+/// Helper function to widen a declarator or spec to the statement that declares it
+///
+/// `const load = () => {}` and Go's `type Config struct {}` are reported whole.
+pub(crate) fn reported_node(node: Node) -> Node {
+    let Some(parent) = node.parent() else {
+        return node;
+    };
+    let wraps = parent.kind().ends_with("_declaration") || parent.kind() == "export_statement";
+    if wraps && parent.named_child_count() == 1 {
+        reported_node(parent)
+    } else {
+        node
+    }
+}
+
+/// Find the definitions of `name` in one file
+fn scan_file(file_path: &Path, name: &str, allow_tests: bool) -> Result<Vec<Definition>> {
+    let extension = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let Some(language_impl) = get_language_impl(extension) else {
+        return Ok(Vec::new());
+    };
+
+    let content = fs_access::read_to_string(file_path)?;
+    if !content.contains(name) {
+        return Ok(Vec::new());
+    }
+    let mut parser = TSParser::new();
+    parser.set_language(&language_impl.get_tree_sitter_language())?;
+    let Some(tree) = parser.parse(&content, None) else {
+        return Ok(Vec::new());
+    };
+
+    let bytes = content.as_bytes();
+    let mut definitions = Vec::new();
+    walk(tree.root_node(), &mut |node| {
+        if !is_declaration_kind(node.kind()) || !declares(node, name, bytes) {
+            return;
+        }
+        if !allow_tests && language_impl.is_test_node(&node, bytes) {
+            return;
+        }
+        let reported = reported_node(node);
+        definitions.push(Definition {
+            file_path: file_path.to_path_buf(),
+            name: name.to_string(),
+            kind: SymbolKind::from_node_kind(node.kind()),
+            container: container_name(node, bytes),
+            line_start: reported.start_position().row + 1,
+            line_end: reported.end_position().row + 1,
+            node_type: node.kind().to_string(),
+            code: node_text(reported, bytes).to_string(),
+            score: 0.0,
+            reasons: Vec::new(),
+        });
+    });
+
+    Ok(definitions)
+}
+
+/// Helper function to normalize a module or file name for comparison
+pub(crate) fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Helper function to get the names a file can be imported by: its stem and its directory
+fn module_names(path: &Path) -> HashSet<String> {
+    let mut names = HashSet::new();
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    if !DIRECTORY_MODULE_STEMS.contains(&stem) {
+        names.insert(normalize(stem));
+    }
+    if let Some(dir) = path
+        .parent()
+        .and_then(|parent| parent.file_name())
+        .and_then(|name| name.to_str())
+    {
+        names.insert(normalize(dir));
+    }
+    names.remove("");
+    names
+}
+
+/// An import statement in the file the identifier is used in
+struct Import {
+    /// Normalized module path segments, e.g. `config` and `loader` for `./config/loader`
+    segments: HashSet<String>,
+    /// Whether the statement imports the identifier by name
+    names_identifier: bool,
+}
+
+/// Helper function to find the import statements of a file, in any supported language
+///
+/// Recognizes `use`, `import` (including Go import blocks), `from .. import`, `require(..)`
+/// and `#include`.
+fn parse_imports(content: &str, name: &str) -> Vec<Import> {
+    let mut imports = Vec::new();
+    // Inside a Go `import ( .. )` block
+    let mut in_block = false;
+    for line in content.lines().map(str::trim) {
+        if in_block && line.starts_with(')') {
+            in_block = false;
+        }
+        let is_import = in_block
+            || [
+                "use ", "pub use ", "import ", "from ", "#include", "require ",
+            ]
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+            || line.contains("require(");
+        if line == "import (" {
+            in_block = true;
+            continue;
+        }
+        if !is_import {
+            continue;
+        }
+        let words: Vec<&str> = line
+            .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .filter(|word| !word.is_empty())
+            .collect();
+        let names_identifier = words.contains(&name);
+        let segments = words
+            .iter()
+            .filter(|word| {
+                !matches!(
+                    **word,
+                    "use" | "pub" | "import" | "from" | "include" | "require" | "as" | "const"
+                )
+            })
+            .map(|word| normalize(word))
+            .filter(|word| !word.is_empty() && !IMPORT_NOISE.contains(&word.as_str()))
+            .collect();
+        imports.push(Import {
+            segments,
+            names_identifier,
+        });
+    }
+    imports
+}
+
+/// Helper function to get the directories of a path, for measuring how far apart files are
+fn directories(path: &Path) -> Vec<String> {
+    path.parent()
+        .map(|parent| {
+            parent
+                .components()
+                .filter_map(|component| match component {
+                    Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Context from the use site that definitions are scored against
+struct UseContext {
+    file_path: PathBuf,
+    line: Option<usize>,
+    imports: Vec<Import>,
+    directories: Vec<String>,
+}
+
+/// Helper function to score a definition by kind, qualifier and closeness to the use site
+fn score_definition(
+    definition: &mut Definition,
+    qualifiers: &[String],
+    context: Option<&UseContext>,
+) {
+    let mut score = definition.kind.weight();
+    let mut reasons = vec![format!("{} definition", definition.kind)];
+
+    if !qualifiers.is_empty() {
+        let mut names = module_names(&definition.file_path);
+        if let Some(container) = &definition.container {
+            names.insert(normalize(container));
+        }
+        if qualifiers.iter().any(|qualifier| names.contains(qualifier)) {
+            score += 1.0;
+            reasons.push("matches qualifier".to_string());
+        }
+    }
+
+    let path = canonical(&definition.file_path);
+    let Some(context) = context else {
+        definition.score = score;
+        definition.reasons = reasons;
+        return;
+    };
+
+    if path == context.file_path {
+        score += 2.0;
+        reasons.push("same file".to_string());
+        // The enclosing definition of the use site is the least likely target; a
+        // definition above the use site is more likely than one below it
+        if let Some(line) = context.line {
+            if line >= definition.line_start && line <= definition.line_end {
+                score -= 0.5;
+            } else if definition.line_start < line {
+                score += 0.25;
+            }
+        }
+    } else {
+        let names = module_names(&path);
+        let imported = context.imports.iter().filter(|import| {
+            import
+                .segments
+                .iter()
+                .any(|segment| names.contains(segment))
+        });
+        let mut by_module = false;
+        let mut by_name = false;
+        for import in imported {
+            by_module = true;
+            by_name |= import.names_identifier;
+        }
+        if by_name {
+            score += 3.0;
+            reasons.push("imported by name".to_string());
+        } else if by_module {
+            score += 1.5;
+            reasons.push("imported module".to_string());
+        }
+
+        let language = fs_access::read_to_string(&path)
+            .ok()
+            .and_then(|content| detect_language(&path, &content, &LanguageOverrides::new()));
+        if language
+            .and_then(|language| block_visibility(language, &definition.code))
+            .is_some_and(|visibility| visibility == Visibility::Public)
+        {
+            score += 0.25;
+            reasons.push("public".to_string());
+        }
+    }
+
+    // Up to one point for sharing directories with the use site
+    let dirs = directories(&path);
+    let shared = dirs
+        .iter()
+        .zip(&context.directories)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let depth = dirs.len().max(context.directories.len());
+    if depth > 0 && shared > 0 {
+        let proximity = shared as f64 / depth as f64;
+        score += proximity;
+        if proximity >= 1.0 {
+            reasons.push("same directory".to_string());
+        }
+    }
+
+    definition.score = score;
+    definition.reasons = reasons;
+}
+
+/// Resolve the most likely definitions of an identifier
+///
+/// Definitions are found with tree-sitter and ranked by symbol kind, by whether their
+/// module or enclosing type matches the identifier's qualifier, and, given a use site,
+/// by whether they are in the same file, in a module the file imports, or nearby.
+pub fn resolve_definition(options: &DefinitionOptions) -> Result<Resolution> {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    let mut segments: Vec<&str> = options
+        .identifier
+        .split(['.', ':', '#', '\\', '/'])
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    let name = segments
+        .pop()
+        .filter(|name| {
+            name.chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+        })
+        .ok_or_else(|| anyhow!("Invalid identifier: {}", options.identifier))?;
+    let qualifiers: Vec<String> = segments.iter().map(|segment| normalize(segment)).collect();
+
+    let context = match options.from {
+        Some(site) => {
+            let content = fs_access::read_to_string(&site.file_path).map_err(|e| {
+                anyhow!(
+                    "Failed to read --from file {}: {}",
+                    site.file_path.display(),
+                    e
+                )
+            })?;
+            let file_path = canonical(&site.file_path);
+            Some(UseContext {
+                directories: directories(&file_path),
+                imports: parse_imports(&content, name),
+                line: site.line,
+                file_path,
+            })
+        }
+        None => None,
+    };
+
+    let file_list = file_list_cache::get_file_list(
+        options.path,
+        options.allow_tests,
+        options.ignore,
+        false,
+        file_list_cache::FileFilters::default(),
+    )?;
+
+    let mut definitions: Vec<Definition> = file_list
+        .files
+        .par_iter()
+        .filter_map(|path| match scan_file(path, name, options.allow_tests) {
+            Ok(found) => Some(found),
+            Err(e) => {
+                if debug_mode {
+                    println!("DEBUG: Error scanning file {:?}: {:?}", path, e);
+                }
+                None
+            }
+        })
+        .flatten()
+        .collect();
+
+    for definition in &mut definitions {
+        score_definition(definition, &qualifiers, context.as_ref());
+    }
+    definitions.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.file_path.cmp(&b.file_path))
+            .then_with(|| a.line_start.cmp(&b.line_start))
+    });
+
+    if debug_mode {
+        for definition in &definitions {
+            println!(
+                "DEBUG: {:.2} {}:{} ({})",
+                definition.score,
+                definition.file_path.display(),
+                definition.line_start,
+                definition.reasons.join(", ")
+            );
+        }
+    }
+
+    let candidates = definitions.len();
+    definitions.truncate(options.max_results.unwrap_or(1));
+    Ok(Resolution {
+        name: name.to_string(),
+        definitions,
+        candidates,
+    })
+}
+
+/// Helper function to escape XML special characters
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Print resolved definitions
+pub fn format_and_print_definitions(resolution: &Resolution, format: &str) -> Result<()> {
+    let definitions = &resolution.definitions;
+    match format {
+        "json" => {
+            let results: Vec<_> = definitions
+                .iter()
+                .map(|definition| {
+                    let encoded = encode_path(&definition.file_path);
+                    let mut json = serde_json::json!({
+                        "file": encoded.value,
+                        "lines": [definition.line_start, definition.line_end],
+                        "node_type": definition.node_type,
+                        "name": definition.name,
+                        "kind": definition.kind.as_str(),
+                        "container": definition.container,
+                        "score": definition.score,
+                        "reasons": definition.reasons,
+                        "code": definition.code,
+                    });
+                    if let Some(encoding) = encoded.encoding {
+                        json["file_encoding"] = encoding.into();
+                    }
+                    json
+                })
+                .collect();
+            let wrapper = serde_json::json!({
+                "results": results,
+                "summary": {
+                    "count": definitions.len(),
+                    "candidates": resolution.candidates,
+                }
+            });
+            println!("{}", serde_json::to_string_pretty(&wrapper)?);
+        }
+        "xml" => {
+            println!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+            println!("<probe_results>");
+            for definition in definitions {
+                println!("  <result>");
+                let encoded = encode_path(&definition.file_path);
+                println!(
+                    "    <file{}>{}</file>",
+                    encoded.xml_attribute(),
+                    escape_xml(&encoded.value)
+                );
+                println!(
+                    "    <lines>{}-{}</lines>",
+                    definition.line_start, definition.line_end
+                );
+                println!(
+                    "    <node_type>{}</node_type>",
+                    escape_xml(&definition.node_type)
+                );
+                println!("    <kind>{}</kind>", definition.kind);
+                println!("    <score>{:.2}</score>", definition.score);
+                println!("    <code><![CDATA[{}]]></code>", definition.code);
+                println!("  </result>");
+            }
+            println!("  <summary>");
+            println!("    <count>{}</count>", definitions.len());
+            println!("    <candidates>{}</candidates>", resolution.candidates);
+            println!("  </summary>");
+            println!("</probe_results>");
+        }
+        _ => {
+            for definition in definitions {
+                let location = format!(
+                    "{}:{}-{}",
+                    definition.file_path.display(),
+                    definition.line_start,
+                    definition.line_end
+                );
+                let label = format!(
+                    "{} ({}, score {:.2}: {})",
+                    definition.name,
+                    definition.kind,
+                    definition.score,
+                    definition.reasons.join(", ")
+                );
+                match format {
+                    "markdown" => {
+                        let lang = definition
+                            .file_path
+                            .extension()
+                            .and_then(|e| e.to_str())
+                            .unwrap_or("");
+                        println!("**{}** {}", location, label);
+                        println!("```{}", lang);
+                        println!("{}", definition.code);
+                        println!("```");
+                    }
+                    "plain" => {
+                        println!("{} {}", location, label);
+                        println!("{}", definition.code);
+                    }
+                    _ => {
+                        println!("{} {}", location.cyan(), label.bold());
+                        println!("{}", definition.code);
+                    }
+                }
+                println!();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle the def command
+pub fn handle_definition(
+    identifier: &str,
+    path: &Path,
+    from: Option<&str>,
+    ignore: &[String],
+    allow_tests: bool,
+    max_results: Option<usize>,
+    format: &str,
+) -> Result<()> {
+    let from = from.map(UseSite::parse).transpose()?;
+
+    // Only print information for non-JSON/XML formats
+    if format != "json" && format != "xml" {
+        println!("{} {}", "Identifier:".bold().green(), identifier);
+        println!("{} {}", "Path:".bold().green(), path.display());
+        if let Some(site) = &from {
+            match site.line {
+                Some(line) => println!(
+                    "{} {}:{}",
+                    "From:".bold().green(),
+                    site.file_path.display(),
+                    line
+                ),
+                None => println!("{} {}", "From:".bold().green(), site.file_path.display()),
+            }
+        }
+    }
+
+    let start_time = Instant::now();
+    let options = DefinitionOptions {
+        path,
+        identifier,
+        from: from.as_ref(),
+        ignore,
+        allow_tests,
+        max_results,
+    };
+    let resolution = resolve_definition(&options)?;
+    let duration = start_time.elapsed();
+
+    if format != "json" && format != "xml" {
+        if resolution.definitions.is_empty() {
+            let message = format!("No definition of {} found.", resolution.name);
+            println!("{}", message.yellow().bold());
+            println!("Search completed in {:.2?}", duration);
+            return Ok(());
+        }
+        println!(
+            "Found {} of {} candidate definitions of {} in {:.2?}",
+            resolution.definitions.len(),
+            resolution.candidates,
+            resolution.name,
+            duration
+        );
+        println!();
+    }
+
+    format_and_print_definitions(&resolution, format)
+}
@@ -0,0 +1,127 @@
+//! Errors returned by the library API.
+//!
+//! Searches are built on `anyhow` internally. At the public entry points
+//! ([`perform_probe`](crate::search::perform_probe) and its variants, and
+//! [`ProbeEngine`](crate::search::ProbeEngine)) errors are turned into a [`ProbeError`],
+//! whose variant tells embedders what went wrong, e.g. to pick an HTTP status or MCP error
+//! code, and whose message is the full error with its context.
+
+use crate::search::cancellation::Cancelled;
+use crate::search::elastic_query::ParseError;
+
+/// Result of a library call
+pub type ProbeResult<T> = std::result::Result<T, ProbeError>;
+
+/// What made a library call fail
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[allow(dead_code)]
+pub enum ProbeError {
+    /// The query could not be parsed, or the search options are invalid
+    #[error("{0}")]
+    QueryParse(String),
+    /// Files, directories or caches could not be read or written
+    #[error("{0}")]
+    Io(String),
+    /// A language name or mapping is not known
+    #[error("{0}")]
+    Language(String),
+    /// A stored session or cache could not be read back
+    #[error("{0}")]
+    CacheCorrupt(String),
+    /// The query, or the work it needs, goes beyond a configured limit
+    #[error("{0}")]
+    LimitExceeded(String),
+    /// The search was cancelled, e.g. because its deadline passed, before it finished
+    #[error("{0}")]
+    Timeout(String),
+    /// The request lacks the token a server requires
+    #[error("{0}")]
+    Unauthorized(String),
+    /// Any other failure
+    #[error("{0}")]
+    Other(String),
+}
+
+#[allow(dead_code)]
+impl ProbeError {
+    /// Stable name of the variant, such as `query_parse` or `timeout`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ProbeError::QueryParse(_) => "query_parse",
+            ProbeError::Io(_) => "io",
+            ProbeError::Language(_) => "language",
+            ProbeError::CacheCorrupt(_) => "cache_corrupt",
+            ProbeError::LimitExceeded(_) => "limit_exceeded",
+            ProbeError::Timeout(_) => "timeout",
+            ProbeError::Unauthorized(_) => "unauthorized",
+            ProbeError::Other(_) => "other",
+        }
+    }
+
+    /// The error message, without the variant
+    pub fn message(&self) -> &str {
+        match self {
+            ProbeError::QueryParse(message)
+            | ProbeError::Io(message)
+            | ProbeError::Language(message)
+            | ProbeError::CacheCorrupt(message)
+            | ProbeError::LimitExceeded(message)
+            | ProbeError::Timeout(message)
+            | ProbeError::Unauthorized(message)
+            | ProbeError::Other(message) => message,
+        }
+    }
+
+    /// Constructor of the variant, to rebuild it with another message
+    fn variant(&self) -> fn(String) -> ProbeError {
+        match self {
+            ProbeError::QueryParse(_) => ProbeError::QueryParse,
+            ProbeError::Io(_) => ProbeError::Io,
+            ProbeError::Language(_) => ProbeError::Language,
+            ProbeError::CacheCorrupt(_) => ProbeError::CacheCorrupt,
+            ProbeError::LimitExceeded(_) => ProbeError::LimitExceeded,
+            ProbeError::Timeout(_) => ProbeError::Timeout,
+            ProbeError::Unauthorized(_) => ProbeError::Unauthorized,
+            ProbeError::Other(_) => ProbeError::Other,
+        }
+    }
+}
+
+/// Helper function to find the variant for one error in a chain, if it tells
+fn classify(cause: &(dyn std::error::Error + 'static)) -> Option<fn(String) -> ProbeError> {
+    if let Some(error) = cause.downcast_ref::<ProbeError>() {
+        return Some(error.variant());
+    }
+    if cause.is::<ParseError>() {
+        return Some(ProbeError::QueryParse);
+    }
+    if cause.is::<Cancelled>() {
+        return Some(ProbeError::Timeout);
+    }
+    #[cfg(feature = "sqlite")]
+    if let Some(rusqlite::Error::SqliteFailure(failure, _)) = cause.downcast_ref() {
+        use rusqlite::ErrorCode;
+        if matches!(
+            failure.code,
+            ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase
+        ) {
+            return Some(ProbeError::CacheCorrupt);
+        }
+    }
+    if cause.is::<std::io::Error>() {
+        return Some(ProbeError::Io);
+    }
+    None
+}
+
+impl From<anyhow::Error> for ProbeError {
+    /// The outermost error in the chain that tells what went wrong picks the variant, and
+    /// the message keeps the whole chain
+    fn from(error: anyhow::Error) -> Self {
+        let variant = error
+            .chain()
+            .find_map(classify)
+            .unwrap_or(ProbeError::Other);
+        variant(format!("{:#}", error))
+    }
+}
@@ -0,0 +1,566 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+use rayon::prelude::*;
+use regex::Regex;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tree_sitter::{Node, Parser as TSParser};
+
+use crate::fs_access;
+use crate::language::factory::get_language_impl;
+use crate::language::language_trait::LanguageImpl;
+use crate::path_encoding::encode_path;
+use crate::search::file_list_cache;
+
+/// The part a code block plays in the life of an error type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ErrorRole {
+    // The type (struct, enum, class, ...) is declared here
+    Definition,
+    // A value of the type is created
+    Construction,
+    // Another error is converted into the type, or the type into another error
+    Conversion,
+    // The error is passed up to the caller (`?`, `throws`)
+    Propagation,
+    // The error is inspected (`match`, `catch`, `except`, type switches)
+    Match,
+}
+
+impl ErrorRole {
+    /// All roles, in the order they are reported
+    pub const ALL: [ErrorRole; 5] = [
+        ErrorRole::Definition,
+        ErrorRole::Construction,
+        ErrorRole::Conversion,
+        ErrorRole::Propagation,
+        ErrorRole::Match,
+    ];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ErrorRole::Definition => "definition",
+            ErrorRole::Construction => "construction",
+            ErrorRole::Conversion => "conversion",
+            ErrorRole::Propagation => "propagation",
+            ErrorRole::Match => "match",
+        }
+    }
+
+    fn heading(&self) -> &'static str {
+        match self {
+            ErrorRole::Definition => "Defined",
+            ErrorRole::Construction => "Constructed",
+            ErrorRole::Conversion => "Converted",
+            ErrorRole::Propagation => "Propagated",
+            ErrorRole::Match => "Matched on",
+        }
+    }
+}
+
+impl fmt::Display for ErrorRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A code block where the error type plays a role
+#[derive(Debug, Clone)]
+pub struct ErrorSite {
+    pub file_path: PathBuf,
+    pub role: ErrorRole,
+    pub line_start: usize,
+    pub line_end: usize,
+    pub node_type: String,
+    pub code: String,
+}
+
+/// Options for tracing an error type
+pub struct ErrorTraceOptions<'a> {
+    pub path: &'a Path,
+    pub type_name: &'a str,
+    pub ignore: &'a [String],
+    pub allow_tests: bool,
+    pub max_results: Option<usize>,
+}
+
+/// Declaration node kinds whose `name` field names a type
+const DEFINITION_KINDS: &[&str] = &[
+    // Rust
+    "struct_item",
+    "enum_item",
+    "type_item",
+    // Go
+    "type_spec",
+    // Java, C#, JavaScript, TypeScript
+    "class_declaration",
+    "interface_declaration",
+    "record_declaration",
+    "type_alias_declaration",
+    // Python
+    "class_definition",
+    // Ruby
+    "class",
+];
+
+/// Node kinds that inspect an error and branch on its type
+const MATCH_KINDS: &[&str] = &[
+    // Rust `match` arms and `if let` / `while let`
+    "match_arm",
+    "let_condition",
+    // Java, C#, JavaScript, TypeScript
+    "catch_clause",
+    // Python
+    "except_clause",
+    // Go type switches and assertions
+    "type_case",
+    "type_assertion_expression",
+    // Ruby
+    "rescue",
+];
+
+fn node_text<'a>(node: Node, content: &'a [u8]) -> &'a str {
+    node.utf8_text(content).unwrap_or("")
+}
+
+/// Text of a node up to its body, e.g. a function or impl header
+fn header_text<'a>(node: Node, content: &'a [u8]) -> &'a str {
+    let end = node
+        .child_by_field_name("body")
+        .map(|body| body.start_byte())
+        .unwrap_or_else(|| node.end_byte());
+    std::str::from_utf8(&content[node.start_byte()..end]).unwrap_or("")
+}
+
+/// Work out which role, if any, a single node gives the error type
+fn classify_node(
+    node: Node,
+    content: &[u8],
+    type_re: &Regex,
+    type_name: &str,
+) -> Option<ErrorRole> {
+    let kind = node.kind();
+    let mentions = |n: Node| type_re.is_match(node_text(n, content));
+    let field_mentions = |field: &str| node.child_by_field_name(field).is_some_and(mentions);
+
+    if DEFINITION_KINDS.contains(&kind) {
+        let is_definition = node
+            .child_by_field_name("name")
+            .is_some_and(|name| node_text(name, content) == type_name);
+        return is_definition.then_some(ErrorRole::Definition);
+    }
+
+    if MATCH_KINDS.contains(&kind) {
+        // Only the pattern/catch part counts, not the handler body
+        let pattern_text = match kind {
+            "match_arm" | "let_condition" => node
+                .child_by_field_name("pattern")
+                .map(|p| node_text(p, content))
+                .unwrap_or(""),
+            _ => header_text(node, content),
+        };
+        return type_re.is_match(pattern_text).then_some(ErrorRole::Match);
+    }
+
+    match kind {
+        // Rust `impl From<Other> for Error` and `impl From<Error> for Other`
+        "impl_item" => {
+            let trait_is_from = node
+                .child_by_field_name("trait")
+                .is_some_and(|t| node_text(t, content).starts_with("From"));
+            (trait_is_from && type_re.is_match(header_text(node, content)))
+                .then_some(ErrorRole::Conversion)
+        }
+        // Rust `.map_err(Error::from)` and friends
+        "call_expression" => {
+            let function = node.child_by_field_name("function")?;
+            let function_text = node_text(function, content);
+            if function_text.ends_with("map_err") || function_text.ends_with(".into") {
+                return field_mentions("arguments").then_some(ErrorRole::Conversion);
+            }
+            // Rust `Error::new(..)` / `Error::Variant(..)`, Python `Error(..)`
+            let is_constructor = function_text == type_name
+                || function_text.starts_with(&format!("{}::", type_name));
+            is_constructor.then_some(ErrorRole::Construction)
+        }
+        // Python `Error(..)`
+        "call" => {
+            let function = node.child_by_field_name("function")?;
+            (node_text(function, content) == type_name).then_some(ErrorRole::Construction)
+        }
+        // Python `raise Other(..) from err` converts one error into another
+        "raise_statement" => {
+            let has_cause = node.child_by_field_name("cause").is_some()
+                || node_text(node, content).contains(" from ");
+            (has_cause && mentions(node)).then_some(ErrorRole::Conversion)
+        }
+        // Rust struct literals and Go composite literals
+        "struct_expression" => field_mentions("name").then_some(ErrorRole::Construction),
+        "composite_literal" => field_mentions("type").then_some(ErrorRole::Construction),
+        // Rust unit variants such as `Error::NotFound` used as values
+        "scoped_identifier" => {
+            let in_call = node
+                .parent()
+                .is_some_and(|p| p.kind() == "call_expression" || p.kind() == "scoped_identifier");
+            let is_variant = node
+                .child_by_field_name("path")
+                .is_some_and(|p| node_text(p, content) == type_name);
+            (is_variant && !in_call && !in_pattern_or_import(node))
+                .then_some(ErrorRole::Construction)
+        }
+        // JavaScript/TypeScript `new Error()`, Java/C# `new Error()`
+        "new_expression" => field_mentions("constructor").then_some(ErrorRole::Construction),
+        "object_creation_expression" => field_mentions("type").then_some(ErrorRole::Construction),
+        // JavaScript/TypeScript `err instanceof Error`
+        "binary_expression" => {
+            let is_instanceof = node
+                .child_by_field_name("operator")
+                .is_some_and(|op| node_text(op, content) == "instanceof");
+            (is_instanceof && field_mentions("right")).then_some(ErrorRole::Match)
+        }
+        // Rust functions returning the error that use `?`, Java methods declaring `throws`
+        "function_item" | "method_declaration" => {
+            let returns_error = field_mentions("return_type");
+            let throws_error = {
+                let mut cursor = node.walk();
+                let throws = node
+                    .children(&mut cursor)
+                    .find(|child| child.kind() == "throws");
+                throws.is_some_and(mentions)
+            };
+            if throws_error || (returns_error && contains_kind(node, "try_expression")) {
+                Some(ErrorRole::Propagation)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Check whether a node is part of a pattern or a `use` declaration rather than an expression
+fn in_pattern_or_import(node: Node) -> bool {
+    let mut current = node.parent();
+    while let Some(parent) = current {
+        let kind = parent.kind();
+        if kind.ends_with("_pattern") || kind == "let_condition" || kind == "use_declaration" {
+            return true;
+        }
+        if kind == "match_arm" {
+            // The arm body is an expression, only its pattern is not
+            return parent.child_by_field_name("pattern").is_some_and(|p| {
+                p.start_byte() <= node.start_byte() && node.end_byte() <= p.end_byte()
+            });
+        }
+        if matches!(kind, "block" | "function_item" | "source_file") {
+            return false;
+        }
+        current = parent.parent();
+    }
+    false
+}
+
+/// Check whether a node contains a descendant of the given kind, without
+/// descending into nested functions
+fn contains_kind(node: Node, kind: &str) -> bool {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if child.kind() == kind {
+            return true;
+        }
+        if matches!(child.kind(), "function_item" | "closure_expression") {
+            continue;
+        }
+        if contains_kind(child, kind) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Find the block a site is reported as: definitions, conversions and
+/// propagating functions are reported whole, other sites by their enclosing block
+fn reported_block<'a>(
+    node: Node<'a>,
+    role: ErrorRole,
+    language_impl: &dyn LanguageImpl,
+) -> Node<'a> {
+    match role {
+        ErrorRole::Definition | ErrorRole::Propagation => node,
+        ErrorRole::Conversion if node.kind() == "impl_item" => node,
+        _ => {
+            let mut current = node;
+            while let Some(parent) = current.parent() {
+                if language_impl.is_acceptable_parent(&parent) {
+                    return parent;
+                }
+                current = parent;
+            }
+            node
+        }
+    }
+}
+
+fn walk<'a>(node: Node<'a>, visit: &mut dyn FnMut(Node<'a>)) {
+    visit(node);
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        walk(child, visit);
+    }
+}
+
+/// Find every site in one file where the error type plays a role
+fn trace_file(
+    file_path: &Path,
+    type_name: &str,
+    type_re: &Regex,
+    allow_tests: bool,
+) -> Result<Vec<ErrorSite>> {
+    let extension = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let Some(language_impl) = get_language_impl(extension) else {
+        return Ok(Vec::new());
+    };
+
+    let content = fs_access::read_to_string(file_path)?;
+    if !type_re.is_match(&content) {
+        return Ok(Vec::new());
+    }
+
+    let mut parser = TSParser::new();
+    parser.set_language(&language_impl.get_tree_sitter_language())?;
+    let Some(tree) = parser.parse(&content, None) else {
+        return Ok(Vec::new());
+    };
+
+    let bytes = content.as_bytes();
+    let mut sites: Vec<ErrorSite> = Vec::new();
+    walk(tree.root_node(), &mut |node| {
+        let Some(role) = classify_node(node, bytes, type_re, type_name) else {
+            return;
+        };
+        let block = reported_block(node, role, language_impl.as_ref());
+        if !allow_tests && language_impl.is_test_node(&block, bytes) {
+            return;
+        }
+
+        let line_start = block.start_position().row + 1;
+        let line_end = block.end_position().row + 1;
+        let duplicate = sites.iter().any(|site| {
+            site.role == role && site.line_start == line_start && site.line_end == line_end
+        });
+        if !duplicate {
+            sites.push(ErrorSite {
+                file_path: file_path.to_path_buf(),
+                role,
+                line_start,
+                line_end,
+                node_type: block.kind().to_string(),
+                code: node_text(block, bytes).to_string(),
+            });
+        }
+    });
+
+    Ok(sites)
+}
+
+/// Find where an error type is defined, constructed, converted, propagated and matched on
+///
+/// Results are sorted by role, then by file and line.
+pub fn trace_error_type(options: &ErrorTraceOptions) -> Result<Vec<ErrorSite>> {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    let type_name = options.type_name.trim();
+    if type_name.is_empty() || !type_name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(anyhow!("Invalid error type name: {}", options.type_name));
+    }
+    let type_re = Regex::new(&format!(r"\b{}\b", regex::escape(type_name)))?;
+
+    let file_list = file_list_cache::get_file_list(
+        options.path,
+        options.allow_tests,
+        options.ignore,
+        false,
+        file_list_cache::FileFilters::default(),
+    )?;
+
+    let mut sites: Vec<ErrorSite> = file_list
+        .files
+        .par_iter()
+        .flat_map(
+            |path| match trace_file(path, type_name, &type_re, options.allow_tests) {
+                Ok(sites) => sites,
+                Err(e) => {
+                    if debug_mode {
+                        println!("DEBUG: Error tracing file {:?}: {:?}", path, e);
+                    }
+                    Vec::new()
+                }
+            },
+        )
+        .collect();
+
+    sites.sort_by(|a, b| {
+        a.role
+            .cmp(&b.role)
+            .then_with(|| a.file_path.cmp(&b.file_path))
+            .then_with(|| a.line_start.cmp(&b.line_start))
+    });
+
+    if let Some(max) = options.max_results {
+        sites.truncate(max);
+    }
+
+    Ok(sites)
+}
+
+/// Helper function to escape XML special characters
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Print error sites grouped by role
+pub fn format_and_print_error_sites(sites: &[ErrorSite], format: &str) -> Result<()> {
+    match format {
+        "json" => {
+            let results: Vec<_> = sites
+                .iter()
+                .map(|site| {
+                    let encoded = encode_path(&site.file_path);
+                    let mut json = serde_json::json!({
+                        "file": encoded.value,
+                        "lines": [site.line_start, site.line_end],
+                        "node_type": site.node_type,
+                        "role": site.role.as_str(),
+                        "code": site.code,
+                    });
+                    if let Some(encoding) = encoded.encoding {
+                        json["file_encoding"] = encoding.into();
+                    }
+                    json
+                })
+                .collect();
+            let wrapper = serde_json::json!({
+                "results": results,
+                "summary": {
+                    "count": sites.len(),
+                    "total_bytes": sites.iter().map(|s| s.code.len()).sum::<usize>(),
+                }
+            });
+            println!("{}", serde_json::to_string_pretty(&wrapper)?);
+        }
+        "xml" => {
+            println!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+            println!("<probe_results>");
+            for site in sites {
+                println!("  <result>");
+                let encoded = encode_path(&site.file_path);
+                println!(
+                    "    <file{}>{}</file>",
+                    encoded.xml_attribute(),
+                    escape_xml(&encoded.value)
+                );
+                println!("    <lines>{}-{}</lines>", site.line_start, site.line_end);
+                println!("    <node_type>{}</node_type>", escape_xml(&site.node_type));
+                println!("    <role>{}</role>", site.role);
+                println!("    <code><![CDATA[{}]]></code>", site.code);
+                println!("  </result>");
+            }
+            println!("  <summary>");
+            println!("    <count>{}</count>", sites.len());
+            println!("  </summary>");
+            println!("</probe_results>");
+        }
+        _ => {
+            for role in ErrorRole::ALL {
+                let in_role: Vec<&ErrorSite> = sites.iter().filter(|s| s.role == role).collect();
+                if in_role.is_empty() {
+                    continue;
+                }
+
+                let heading = format!("{} ({})", role.heading(), in_role.len());
+                match format {
+                    "markdown" => println!("## {}\n", heading),
+                    "plain" => println!("=== {} ===\n", heading),
+                    _ => println!("{}\n", heading.bold().blue()),
+                }
+
+                for site in in_role {
+                    let location = format!(
+                        "{}:{}-{}",
+                        site.file_path.display(),
+                        site.line_start,
+                        site.line_end
+                    );
+                    match format {
+                        "markdown" => {
+                            let lang = site
+                                .file_path
+                                .extension()
+                                .and_then(|e| e.to_str())
+                                .unwrap_or("");
+                            println!("**{}**", location);
+                            println!("```{}", lang);
+                            println!("{}", site.code);
+                            println!("```");
+                        }
+                        "plain" => {
+                            println!("{}", location);
+                            println!("{}", site.code);
+                        }
+                        _ => {
+                            println!("{}", location.cyan());
+                            println!("{}", site.code);
+                        }
+                    }
+                    println!();
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle the errors command
+pub fn handle_errors(
+    type_name: &str,
+    path: &Path,
+    ignore: &[String],
+    allow_tests: bool,
+    max_results: Option<usize>,
+    format: &str,
+) -> Result<()> {
+    // Only print information for non-JSON/XML formats
+    if format != "json" && format != "xml" {
+        println!("{} {}", "Error type:".bold().green(), type_name);
+        println!("{} {}", "Path:".bold().green(), path.display());
+    }
+
+    let start_time = Instant::now();
+    let options = ErrorTraceOptions {
+        path,
+        type_name,
+        ignore,
+        allow_tests,
+        max_results,
+    };
+    let sites = trace_error_type(&options)?;
+    let duration = start_time.elapsed();
+
+    if format != "json" && format != "xml" {
+        if sites.is_empty() {
+            println!("{}", "No results found.".yellow().bold());
+            println!("Search completed in {:.2?}", duration);
+            return Ok(());
+        }
+        println!("Found {} sites in {:.2?}", sites.len(), duration);
+        println!();
+    }
+
+    format_and_print_error_sites(&sites, format)
+}
@@ -0,0 +1,401 @@
+//! Measuring search quality against labeled queries.
+//!
+//! `probe eval dataset.json` runs every query of a dataset under each search configuration
+//! it lists and compares the results with the files or blocks each query is expected to
+//! find, so a ranking change can be judged by precision, recall and mean reciprocal rank
+//! rather than by eye. A dataset looks like:
+//!
+//! ```json
+//! {
+//!   "path": "..",
+//!   "max_results": 10,
+//!   "configs": [
+//!     { "name": "default" },
+//!     { "name": "exact", "exact": true }
+//!   ],
+//!   "cases": [
+//!     { "query": "parse config", "expected": ["src/config.rs", "src/main.rs:40-60"] }
+//!   ]
+//! }
+//! ```
+//!
+//! `path` is the directory searched, relative to the dataset file, and defaults to the
+//! directory the dataset is in. An expected entry is a file, which any block from it
+//! matches, or a `file:START-END` block, which results overlapping those lines match.
+//! Without `configs`, the default search is measured.
+
+use anyhow::{anyhow, Context, Result};
+use colored::*;
+use serde::Deserialize;
+use std::path::{Component, Path, PathBuf};
+
+use crate::fs_access;
+use crate::models::SearchResult;
+use crate::search::{EngineOptions, ProbeEngine};
+
+/// Results per query when the dataset doesn't say
+const DEFAULT_MAX_RESULTS: usize = 10;
+
+/// A set of labeled queries
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvalDataset {
+    /// Directory to search, relative to the dataset file
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// Results per query that are scored
+    #[serde(default)]
+    pub max_results: Option<usize>,
+    #[serde(default)]
+    pub configs: Vec<EvalConfig>,
+    pub cases: Vec<EvalCase>,
+}
+
+/// One query and what it should find
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvalCase {
+    pub query: String,
+    /// Files, or `file:START-END` blocks, relative to the searched directory
+    pub expected: Vec<String>,
+}
+
+/// Search settings a dataset is measured under
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EvalConfig {
+    pub name: String,
+    pub reranker: String,
+    pub exact: bool,
+    pub no_merge: bool,
+    pub exclude_filenames: bool,
+    pub allow_tests: bool,
+}
+
+impl Default for EvalConfig {
+    fn default() -> Self {
+        Self {
+            name: "default".to_string(),
+            reranker: "bm25".to_string(),
+            exact: false,
+            no_merge: false,
+            exclude_filenames: false,
+            allow_tests: false,
+        }
+    }
+}
+
+/// A file or block a query is expected to find
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expected {
+    pub file: PathBuf,
+    pub lines: Option<(usize, usize)>,
+}
+
+impl Expected {
+    /// Parse `file` or `file:START-END`
+    pub fn parse(spec: &str) -> Result<Self> {
+        if let Some((file, range)) = spec.rsplit_once(':') {
+            if let Some((start, end)) = range.split_once('-') {
+                if let (Ok(start), Ok(end)) = (start.parse::<usize>(), end.parse::<usize>()) {
+                    if start == 0 || start > end {
+                        return Err(anyhow!("Invalid line range in expected block {:?}", spec));
+                    }
+                    return Ok(Self {
+                        file: normalize(Path::new(file)),
+                        lines: Some((start, end)),
+                    });
+                }
+            }
+        }
+        Ok(Self {
+            file: normalize(Path::new(spec)),
+            lines: None,
+        })
+    }
+
+    /// Whether a result, named relative to the searched directory, is this file or block
+    fn matches(&self, file: &Path, lines: (usize, usize)) -> bool {
+        self.file == file
+            && self
+                .lines
+                .is_none_or(|(start, end)| lines.0 <= end && start <= lines.1)
+    }
+}
+
+/// Helper function to drop `.` components, so `./src/a.rs` and `src/a.rs` compare equal
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| *c != Component::CurDir)
+        .collect()
+}
+
+/// How one query scored
+#[derive(Debug, Clone)]
+pub struct CaseScore {
+    pub query: String,
+    /// Share of the results that were expected
+    pub precision: f64,
+    /// Share of the expected files and blocks that were found
+    pub recall: f64,
+    /// One over the rank of the first expected result, 0 when none was found
+    pub reciprocal_rank: f64,
+    /// Expected files and blocks no result matched
+    pub missed: Vec<String>,
+}
+
+/// How one configuration scored over the dataset
+#[derive(Debug, Clone)]
+pub struct ConfigReport {
+    pub name: String,
+    pub cases: Vec<CaseScore>,
+}
+
+impl ConfigReport {
+    fn mean(&self, score: impl Fn(&CaseScore) -> f64) -> f64 {
+        if self.cases.is_empty() {
+            return 0.0;
+        }
+        self.cases.iter().map(score).sum::<f64>() / self.cases.len() as f64
+    }
+
+    pub fn precision(&self) -> f64 {
+        self.mean(|case| case.precision)
+    }
+
+    pub fn recall(&self) -> f64 {
+        self.mean(|case| case.recall)
+    }
+
+    /// Mean reciprocal rank
+    pub fn mrr(&self) -> f64 {
+        self.mean(|case| case.reciprocal_rank)
+    }
+}
+
+/// Function to score the results of one query
+///
+/// `root` is the searched directory, which result paths are made relative to.
+pub fn score_case(
+    query: &str,
+    expected: &[String],
+    results: &[SearchResult],
+    root: &Path,
+) -> Result<CaseScore> {
+    let expected: Vec<Expected> = expected
+        .iter()
+        .map(|spec| Expected::parse(spec))
+        .collect::<Result<_>>()?;
+    let mut found = vec![false; expected.len()];
+    let mut relevant = 0;
+    let mut first_relevant = None;
+
+    for (rank, result) in results.iter().enumerate() {
+        let path = result.path();
+        let file = normalize(path.strip_prefix(root).unwrap_or(&path));
+        let mut is_relevant = false;
+        for (i, item) in expected.iter().enumerate() {
+            if item.matches(&file, result.lines) {
+                found[i] = true;
+                is_relevant = true;
+            }
+        }
+        if is_relevant {
+            relevant += 1;
+            first_relevant.get_or_insert(rank + 1);
+        }
+    }
+
+    let found_count = found.iter().filter(|f| **f).count();
+    Ok(CaseScore {
+        query: query.to_string(),
+        precision: if results.is_empty() {
+            0.0
+        } else {
+            relevant as f64 / results.len() as f64
+        },
+        recall: if expected.is_empty() {
+            1.0
+        } else {
+            found_count as f64 / expected.len() as f64
+        },
+        reciprocal_rank: first_relevant.map_or(0.0, |rank| 1.0 / rank as f64),
+        missed: expected
+            .iter()
+            .zip(&found)
+            .filter(|(_, found)| !**found)
+            .map(|(item, _)| match item.lines {
+                Some((start, end)) => format!("{}:{}-{}", item.file.display(), start, end),
+                None => item.file.display().to_string(),
+            })
+            .collect(),
+    })
+}
+
+/// Function to run a dataset under each of its configurations
+///
+/// `root` is the searched directory and `max_results` overrides the dataset's.
+pub fn run_eval(
+    dataset: &EvalDataset,
+    root: &Path,
+    max_results: Option<usize>,
+) -> Result<Vec<ConfigReport>> {
+    if dataset.cases.is_empty() {
+        return Err(anyhow!("The dataset has no cases"));
+    }
+    let max_results = max_results
+        .or(dataset.max_results)
+        .unwrap_or(DEFAULT_MAX_RESULTS);
+    let configs = if dataset.configs.is_empty() {
+        vec![EvalConfig::default()]
+    } else {
+        dataset.configs.clone()
+    };
+
+    let mut reports = Vec::new();
+    for config in configs {
+        let engine = ProbeEngine::with_options(
+            root,
+            EngineOptions {
+                reranker: config.reranker.clone(),
+                exact: config.exact,
+                no_merge: config.no_merge,
+                exclude_filenames: config.exclude_filenames,
+                allow_tests: config.allow_tests,
+                max_results: Some(max_results),
+                ..EngineOptions::default()
+            },
+        )?;
+
+        let mut cases = Vec::new();
+        for case in &dataset.cases {
+            let results = engine
+                .search(&case.query)
+                .with_context(|| format!("Query {:?} failed", case.query))?;
+            cases.push(score_case(
+                &case.query,
+                &case.expected,
+                &results.results,
+                root,
+            )?);
+        }
+        reports.push(ConfigReport {
+            name: config.name,
+            cases,
+        });
+    }
+    Ok(reports)
+}
+
+/// Function to read a dataset, returning it with the directory it searches
+pub fn load_dataset(path: &Path) -> Result<(EvalDataset, PathBuf)> {
+    let json = fs_access::read_to_string(path)
+        .with_context(|| format!("Failed to read dataset {:?}", path))?;
+    let dataset: EvalDataset = serde_json::from_str(&json)
+        .with_context(|| format!("Failed to parse dataset {:?}", path))?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let root = match &dataset.path {
+        Some(root) => dir.join(root),
+        None => dir.to_path_buf(),
+    };
+    let root = if root.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        root
+    };
+    Ok((dataset, root))
+}
+
+/// Helper function to print the reports in the given format
+fn print_reports(reports: &[ConfigReport], max_results: usize, format: &str) -> Result<()> {
+    if format == "json" {
+        let configs: Vec<serde_json::Value> = reports
+            .iter()
+            .map(|report| {
+                let cases: Vec<serde_json::Value> = report
+                    .cases
+                    .iter()
+                    .map(|case| {
+                        serde_json::json!({
+                            "query": case.query,
+                            "precision": case.precision,
+                            "recall": case.recall,
+                            "reciprocal_rank": case.reciprocal_rank,
+                            "missed": case.missed,
+                        })
+                    })
+                    .collect();
+                serde_json::json!({
+                    "name": report.name,
+                    "precision": report.precision(),
+                    "recall": report.recall(),
+                    "mrr": report.mrr(),
+                    "cases": cases,
+                })
+            })
+            .collect();
+        let wrapper = serde_json::json!({
+            "configs": configs,
+            "summary": {
+                "cases": reports.first().map_or(0, |r| r.cases.len()),
+                "max_results": max_results,
+            }
+        });
+        println!("{}", serde_json::to_string_pretty(&wrapper)?);
+        return Ok(());
+    }
+
+    let width = reports
+        .iter()
+        .map(|r| r.name.len())
+        .max()
+        .unwrap_or(0)
+        .max("Config".len());
+    let header = format!(
+        "{:<width$}  {:>9}  {:>9}  {:>9}",
+        "Config",
+        format!("P@{}", max_results),
+        format!("R@{}", max_results),
+        "MRR",
+    );
+    if format == "plain" {
+        println!("{}", header);
+    } else {
+        println!("{}", header.bold());
+    }
+    for report in reports {
+        println!(
+            "{:<width$}  {:>9.3}  {:>9.3}  {:>9.3}",
+            report.name,
+            report.precision(),
+            report.recall(),
+            report.mrr(),
+        );
+    }
+
+    // Queries missing expected results, under each configuration
+    for report in reports {
+        for case in report.cases.iter().filter(|case| !case.missed.is_empty()) {
+            let line = format!(
+                "[{}] {:?} missed {}",
+                report.name,
+                case.query,
+                case.missed.join(", ")
+            );
+            if format == "plain" {
+                eprintln!("{}", line);
+            } else {
+                eprintln!("{}", line.yellow());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Handle the eval command
+pub fn handle_eval(dataset: &Path, max_results: Option<usize>, format: &str) -> Result<()> {
+    let (loaded, root) = load_dataset(dataset)?;
+    let reports = run_eval(&loaded, &root, max_results)?;
+    let max_results = max_results
+        .or(loaded.max_results)
+        .unwrap_or(DEFAULT_MAX_RESULTS);
+    print_reports(&reports, max_results, format)
+}
@@ -4,11 +4,14 @@
 //! line ranges, or symbol references from text input.
 
 use crate::language::is_test_file;
+use crate::models::LineChange;
+use crate::path_encoding::{decode_path, PERCENT_ENCODING};
+use crate::search::file_list_cache::reached_via_symlink;
 use glob::glob;
 use ignore::WalkBuilder;
 use regex::Regex;
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 /// Represents a file path with optional line numbers and symbol information
 ///
@@ -217,6 +220,67 @@ pub fn extract_file_paths_from_git_diff(text: &str, allow_tests: bool) -> Vec<Fi
     results
 }
 
+/// Classify the changed lines of each file in a git diff
+///
+/// Line numbers refer to the new version of the file, like the lines returned by
+/// [`extract_file_paths_from_git_diff`]. Within a run of changes, added lines that
+/// take the place of removed ones are `Modified`; any extra added lines are `Added`.
+pub fn extract_line_changes_from_git_diff(
+    text: &str,
+) -> HashMap<PathBuf, BTreeMap<usize, LineChange>> {
+    let diff_header_regex = Regex::new(r"^diff --git a/(.*) b/(.*)$").unwrap();
+    let hunk_header_regex = Regex::new(r"^@@ -\d+(?:,\d+)? \+(\d+)(?:,\d+)? @@").unwrap();
+
+    let mut changes: HashMap<PathBuf, BTreeMap<usize, LineChange>> = HashMap::new();
+    let mut current_file: Option<PathBuf> = None;
+    let mut in_hunk = false;
+    let mut current_line = 0;
+    // Removed lines in the current run that have not been paired with an added line yet
+    let mut unpaired_removals = 0;
+
+    for line in text.lines() {
+        if let Some(cap) = diff_header_regex.captures(line) {
+            current_file = Some(PathBuf::from(cap.get(2).unwrap().as_str()));
+            in_hunk = false;
+            continue;
+        }
+        if let Some(cap) = hunk_header_regex.captures(line) {
+            current_line = cap.get(1).unwrap().as_str().parse().unwrap_or(1);
+            in_hunk = true;
+            unpaired_removals = 0;
+            continue;
+        }
+        let Some(file) = current_file.as_ref().filter(|_| in_hunk) else {
+            continue;
+        };
+
+        match line.chars().next() {
+            Some('-') => unpaired_removals += 1,
+            Some('+') => {
+                let kind = if unpaired_removals > 0 {
+                    unpaired_removals -= 1;
+                    LineChange::Modified
+                } else {
+                    LineChange::Added
+                };
+                changes
+                    .entry(file.clone())
+                    .or_default()
+                    .insert(current_line, kind);
+                current_line += 1;
+            }
+            // "\ No newline at end of file" belongs to the previous line
+            Some('\\') => {}
+            _ => {
+                unpaired_removals = 0;
+                current_line += 1;
+            }
+        }
+    }
+
+    changes
+}
+
 /// Extract file paths from text (for stdin mode)
 ///
 /// This function takes a string of text and extracts file paths with optional
@@ -295,8 +359,9 @@ pub fn extract_file_paths_from_text(text: &str, allow_tests: bool) -> Vec<FilePa
                 for entry in paths.flatten() {
                     // Check if the file should be ignored or is a test file
                     let is_test = is_test_file(&entry);
-                    let should_include =
-                        !is_ignored_by_gitignore(&entry) && (allow_tests || !is_test);
+                    let should_include = !is_ignored_by_gitignore(&entry)
+                        && !is_skipped_symlink(file_path, &entry)
+                        && (allow_tests || !is_test);
                     if should_include {
                         let path_str = entry.to_string_lossy().to_string();
                         processed_paths.insert(path_str.clone());
@@ -350,8 +415,9 @@ pub fn extract_file_paths_from_text(text: &str, allow_tests: bool) -> Vec<FilePa
                     for entry in paths.flatten() {
                         // Check if the file should be ignored or is a test file
                         let is_test = is_test_file(&entry);
-                        let should_include =
-                            !is_ignored_by_gitignore(&entry) && (allow_tests || !is_test);
+                        let should_include = !is_ignored_by_gitignore(&entry)
+                            && !is_skipped_symlink(file_path, &entry)
+                            && (allow_tests || !is_test);
                         if should_include {
                             processed_paths.insert(entry.to_string_lossy().to_string());
                             results.push((entry, Some(start), Some(end), None, None));
@@ -404,8 +470,9 @@ pub fn extract_file_paths_from_text(text: &str, allow_tests: bool) -> Vec<FilePa
                     if !processed_paths.contains(&path_str) {
                         // Check if the file should be ignored or is a test file
                         let is_test = is_test_file(&entry);
-                        let should_include =
-                            !is_ignored_by_gitignore(&entry) && (allow_tests || !is_test);
+                        let should_include = !is_ignored_by_gitignore(&entry)
+                            && !is_skipped_symlink(file_path, &entry)
+                            && (allow_tests || !is_test);
                         if should_include {
                             processed_paths.insert(path_str);
                             results.push((entry, line_num, None, None, None));
@@ -453,8 +520,9 @@ pub fn extract_file_paths_from_text(text: &str, allow_tests: bool) -> Vec<FilePa
                         if !processed_paths.contains(&path_str) {
                             // Check if the file should be ignored or is a test file
                             let is_test = is_test_file(&entry);
-                            let should_include =
-                                !is_ignored_by_gitignore(&entry) && (allow_tests || !is_test);
+                            let should_include = !is_ignored_by_gitignore(&entry)
+                                && !is_skipped_symlink(file_path, &entry)
+                                && (allow_tests || !is_test);
                             if should_include {
                                 processed_paths.insert(path_str);
                                 results.push((entry, None, None, None, None));
@@ -488,6 +556,50 @@ pub fn extract_file_paths_from_text(text: &str, allow_tests: bool) -> Vec<FilePa
     results
 }
 
+/// Resolve a file argument, accepting the percent-encoded paths that JSON and XML
+/// output use for file names that are not valid UTF-8
+fn resolve_file_path(file: &str) -> PathBuf {
+    let path = PathBuf::from(file);
+    if !path.exists() && file.contains('%') {
+        if let Ok(decoded) = decode_path(file, Some(PERCENT_ENCODING)) {
+            if decoded.exists() {
+                return decoded;
+            }
+        }
+    }
+    path
+}
+
+/// Parse a list of file specs, one per line, as read from stdin with `probe extract -`
+///
+/// Each line may carry a line number, range or symbol like a command-line argument.
+/// Lines may also be separated by NUL bytes (`git diff --name-only -z`, `find -print0`).
+/// Blank lines are skipped and repeated specs are only returned once.
+pub fn parse_file_list(text: &str, allow_tests: bool) -> Vec<FilePathInfo> {
+    let mut results = Vec::new();
+    for line in text.split(['\n', '\0']) {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            continue;
+        }
+        results.extend(parse_file_with_line(line, allow_tests));
+    }
+    dedup_file_paths(results)
+}
+
+/// Remove repeated file specs, keeping the first occurrence of each
+pub fn dedup_file_paths(file_paths: Vec<FilePathInfo>) -> Vec<FilePathInfo> {
+    let mut seen = HashSet::new();
+    file_paths
+        .into_iter()
+        .filter(|(path, start, end, symbol, lines)| {
+            let mut lines: Vec<usize> = lines.iter().flatten().copied().collect();
+            lines.sort_unstable();
+            seen.insert((path.clone(), *start, *end, symbol.clone(), lines))
+        })
+        .collect()
+}
+
 /// Parse a file path with optional line number or range (e.g., "file.rs:10" or "file.rs:1-60")
 ///
 /// If allow_tests is false, test files will be filtered out.
@@ -513,7 +625,7 @@ pub fn parse_file_with_line(input: &str, allow_tests: bool) -> Vec<FilePathInfo>
     if let Some((file_part, symbol)) = cleaned_input.split_once('#') {
         // For symbol references, we don't have line numbers yet
         // We'll need to find the symbol in the file later
-        let path = PathBuf::from(file_part);
+        let path = resolve_file_path(file_part);
         let is_test = is_test_file(&path);
         if allow_tests || !is_test {
             results.push((path, None, None, Some(symbol.to_string()), None));
@@ -543,15 +655,16 @@ pub fn parse_file_with_line(input: &str, allow_tests: bool) -> Vec<FilePathInfo>
                         for entry in paths.flatten() {
                             // Check if the file should be ignored or is a test file
                             let is_test = is_test_file(&entry);
-                            let should_include =
-                                !is_ignored_by_gitignore(&entry) && (allow_tests || !is_test);
+                            let should_include = !is_ignored_by_gitignore(&entry)
+                                && !is_skipped_symlink(file_part, &entry)
+                                && (allow_tests || !is_test);
                             if should_include {
                                 results.push((entry, Some(start), Some(end), None, None));
                             }
                         }
                     }
                 } else {
-                    let path = PathBuf::from(file_part);
+                    let path = resolve_file_path(file_part);
                     let is_test = is_test_file(&path);
                     if !is_ignored_by_gitignore(&path) && (allow_tests || !is_test) {
                         results.push((path, Some(start), Some(end), None, None));
@@ -570,8 +683,9 @@ pub fn parse_file_with_line(input: &str, allow_tests: bool) -> Vec<FilePathInfo>
                         for entry in paths.flatten() {
                             // Check if the file should be ignored or is a test file
                             let is_test = is_test_file(&entry);
-                            let should_include =
-                                !is_ignored_by_gitignore(&entry) && (allow_tests || !is_test);
+                            let should_include = !is_ignored_by_gitignore(&entry)
+                                && !is_skipped_symlink(file_part, &entry)
+                                && (allow_tests || !is_test);
                             if should_include {
                                 // Create a HashSet with just this line number
                                 let mut lines_set = HashSet::new();
@@ -581,7 +695,7 @@ pub fn parse_file_with_line(input: &str, allow_tests: bool) -> Vec<FilePathInfo>
                         }
                     }
                 } else {
-                    let path = PathBuf::from(file_part);
+                    let path = resolve_file_path(file_part);
                     let is_test = is_test_file(&path);
                     if !is_ignored_by_gitignore(&path) && (allow_tests || !is_test) {
                         // Create a HashSet with just this line number
@@ -600,15 +714,16 @@ pub fn parse_file_with_line(input: &str, allow_tests: bool) -> Vec<FilePathInfo>
                 for entry in paths.flatten() {
                     // Check if the file should be ignored or is a test file
                     let is_test = is_test_file(&entry);
-                    let should_include =
-                        !is_ignored_by_gitignore(&entry) && (allow_tests || !is_test);
+                    let should_include = !is_ignored_by_gitignore(&entry)
+                        && !is_skipped_symlink(cleaned_input, &entry)
+                        && (allow_tests || !is_test);
                     if should_include {
                         results.push((entry, None, None, None, None));
                     }
                 }
             }
         } else {
-            let path = PathBuf::from(cleaned_input);
+            let path = resolve_file_path(cleaned_input);
             let is_test = is_test_file(&path);
             if !is_ignored_by_gitignore(&path) && (allow_tests || !is_test) {
                 results.push((path, None, None, None, None));
@@ -624,7 +739,15 @@ thread_local! {
     static CUSTOM_IGNORES: std::cell::RefCell<Vec<String>> = const { std::cell::RefCell::new(Vec::new()) };
 }
 
+// Thread-local storage for whether glob matches may go through symbolic links
+thread_local! {
+    static FOLLOW_SYMLINKS: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
 /// Set custom ignore patterns for the current thread
+///
+/// They stay set for every later call on the thread; [`with_glob_settings`] scopes them.
+#[allow(dead_code)]
 pub fn set_custom_ignores(patterns: &[String]) {
     CUSTOM_IGNORES.with(|cell| {
         let mut ignores = cell.borrow_mut();
@@ -633,6 +756,58 @@ pub fn set_custom_ignores(patterns: &[String]) {
     });
 }
 
+/// Function to run `f` with custom ignore patterns and symbolic link handling for globs
+///
+/// They apply to the current thread only, and the previous settings are restored
+/// afterwards, so one extraction's settings don't carry over to the next.
+pub fn with_glob_settings<T>(
+    custom_ignores: &[String],
+    follow_symlinks: bool,
+    f: impl FnOnce() -> T,
+) -> T {
+    struct Restore(Vec<String>, bool);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let ignores = std::mem::take(&mut self.0);
+            CUSTOM_IGNORES.with(|cell| *cell.borrow_mut() = ignores);
+            FOLLOW_SYMLINKS.with(|cell| cell.set(self.1));
+        }
+    }
+
+    let previous_ignores = CUSTOM_IGNORES.with(|cell| cell.replace(custom_ignores.to_vec()));
+    let previous_follow = FOLLOW_SYMLINKS.with(|cell| cell.replace(follow_symlinks));
+    let _restore = Restore(previous_ignores, previous_follow);
+    f()
+}
+
+/// Check if a glob match should be skipped because it goes through a symbolic link
+///
+/// Only the part of the path matched by wildcards counts, the same way the search
+/// scanner follows a linked root but not links below it. Paths named explicitly are
+/// always read.
+fn is_skipped_symlink(pattern: &str, entry: &Path) -> bool {
+    if FOLLOW_SYMLINKS.with(|cell| cell.get()) {
+        return false;
+    }
+    let skipped = reached_via_symlink(&glob_base(pattern), entry);
+    if skipped && std::env::var("DEBUG").unwrap_or_default() == "1" {
+        println!("DEBUG: Skipping symbolic link: {:?}", entry);
+    }
+    skipped
+}
+
+/// The leading part of a glob pattern without wildcards, e.g. `src` for `src/**/*.rs`
+fn glob_base(pattern: &str) -> PathBuf {
+    Path::new(pattern)
+        .components()
+        .take_while(|c| {
+            !c.as_os_str()
+                .to_string_lossy()
+                .contains(['*', '?', '[', '{'])
+        })
+        .collect()
+}
+
 /// Check if a file should be ignored according to .gitignore rules
 fn is_ignored_by_gitignore(path: &PathBuf) -> bool {
     // Check if debug mode is enabled
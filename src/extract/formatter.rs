@@ -3,7 +3,9 @@
 //! This module provides functions for formatting and printing extraction results
 //! in various formats (terminal, markdown, plain, json, xml, color).
 
+use crate::extract::related::{RelatedCode, RelatedSymbol};
 use crate::models::SearchResult;
+use crate::path_encoding::{encode_path, encode_result_path};
 use crate::search::search_tokens::count_tokens;
 use anyhow::Result;
 use std::path::Path;
@@ -23,7 +25,9 @@ pub fn format_extraction_dry_run(results: &[SearchResult], format: &str) -> Resu
             // Create a simplified version of the results for JSON output
             #[derive(serde::Serialize)]
             struct JsonDryRunResult<'a> {
-                file: &'a str,
+                file: String,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                file_encoding: Option<&'static str>,
                 #[serde(serialize_with = "serialize_lines_as_array")]
                 lines: (usize, usize),
                 node_type: &'a str,
@@ -46,10 +50,14 @@ pub fn format_extraction_dry_run(results: &[SearchResult], format: &str) -> Resu
 
             let json_results: Vec<JsonDryRunResult> = results
                 .iter()
-                .map(|r| JsonDryRunResult {
-                    file: &r.file,
-                    lines: r.lines,
-                    node_type: &r.node_type,
+                .map(|r| {
+                    let encoded = encode_result_path(&r.file, r.file_path.as_deref());
+                    JsonDryRunResult {
+                        file: encoded.value,
+                        file_encoding: encoded.encoding,
+                        lines: r.lines,
+                        node_type: &r.node_type,
+                    }
                 })
                 .collect();
 
@@ -69,7 +77,14 @@ pub fn format_extraction_dry_run(results: &[SearchResult], format: &str) -> Resu
 
             for result in results {
                 writeln!(output, "  <result>").unwrap();
-                writeln!(output, "    <file>{}</file>", escape_xml(&result.file)).unwrap();
+                let encoded = encode_result_path(&result.file, result.file_path.as_deref());
+                writeln!(
+                    output,
+                    "    <file{}>{}</file>",
+                    encoded.xml_attribute(),
+                    escape_xml(&encoded.value)
+                )
+                .unwrap();
 
                 if result.node_type != "file" {
                     writeln!(output, "    <lines>").unwrap();
@@ -147,8 +162,13 @@ pub fn format_extraction_dry_run(results: &[SearchResult], format: &str) -> Resu
 /// # Arguments
 ///
 /// * `results` - The search results to format
+/// * `related` - Code related to each result, from `--suggest-related` (empty otherwise)
 /// * `format` - The output format (terminal, markdown, plain, json, or color)
-pub fn format_extraction_results(results: &[SearchResult], format: &str) -> Result<String> {
+pub fn format_extraction_results(
+    results: &[SearchResult],
+    related: &[RelatedCode],
+    format: &str,
+) -> Result<String> {
     use std::fmt::Write;
     let mut output = String::new();
 
@@ -160,10 +180,10 @@ pub fn format_extraction_results(results: &[SearchResult], format: &str) -> Resu
             format_plain_results(&mut output, results);
         }
         "json" => {
-            format_json_results(&mut output, results)?;
+            format_json_results(&mut output, results, related)?;
         }
         "xml" => {
-            format_xml_results(&mut output, results)?;
+            format_xml_results(&mut output, results, related)?;
         }
         "color" => {
             format_color_results(&mut output, results);
@@ -173,6 +193,10 @@ pub fn format_extraction_results(results: &[SearchResult], format: &str) -> Resu
         }
     }
 
+    if format != "json" && format != "xml" {
+        format_related_text(&mut output, related, format)?;
+    }
+
     // Add summary (only for non-JSON/XML formats)
     if format != "json" && format != "xml" {
         use colored::*;
@@ -199,6 +223,67 @@ pub fn format_extraction_results(results: &[SearchResult], format: &str) -> Resu
     Ok(output)
 }
 
+/// Format the code related to each result as a list of names and locations
+fn format_related_text(output: &mut String, related: &[RelatedCode], format: &str) -> Result<()> {
+    use colored::*;
+    use std::fmt::Write;
+
+    if related.is_empty() {
+        return Ok(());
+    }
+    let markdown = format == "markdown";
+    writeln!(output)?;
+    if markdown {
+        writeln!(output, "## Related code")?;
+    } else {
+        writeln!(output, "{}", "Related code:".bold().green())?;
+    }
+    for block in related {
+        let location = format!(
+            "{}:{}-{}",
+            block.file_path.display(),
+            block.lines.0,
+            block.lines.1
+        );
+        let heading = match &block.symbol {
+            Some(symbol) => format!("{} ({})", symbol, location),
+            None => location,
+        };
+        writeln!(output)?;
+        if markdown {
+            writeln!(output, "### {}", heading)?;
+        } else {
+            writeln!(output, "{}", heading.bold())?;
+        }
+        for (label, symbols) in [
+            ("Siblings", &block.siblings),
+            ("Callers", &block.callers),
+            ("Callees", &block.callees),
+        ] {
+            if symbols.is_empty() {
+                continue;
+            }
+            let list: Vec<String> = symbols
+                .iter()
+                .map(|symbol| {
+                    format!(
+                        "{} ({}:{})",
+                        symbol.name,
+                        symbol.file_path.display(),
+                        symbol.line
+                    )
+                })
+                .collect();
+            if markdown {
+                writeln!(output, "- {}: {}", label, list.join(", "))?;
+            } else {
+                writeln!(output, "  {}: {}", label, list.join(", "))?;
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Format and print the extraction results in the specified format
 ///
 /// # Arguments
@@ -207,7 +292,7 @@ pub fn format_extraction_results(results: &[SearchResult], format: &str) -> Resu
 /// * `format` - The output format (terminal, markdown, plain, json, or color)
 #[allow(dead_code)]
 pub fn format_and_print_extraction_results(results: &[SearchResult], format: &str) -> Result<()> {
-    let output = format_extraction_results(results, format)?;
+    let output = format_extraction_results(results, &[], format)?;
     println!("{}", output);
     Ok(())
 }
@@ -376,7 +461,11 @@ pub fn format_and_print_plain_results(results: &[SearchResult]) {
 }
 
 /// Format results in XML format and write to a string buffer
-pub fn format_xml_results(output: &mut String, results: &[SearchResult]) -> Result<()> {
+pub fn format_xml_results(
+    output: &mut String,
+    results: &[SearchResult],
+    related: &[RelatedCode],
+) -> Result<()> {
     use std::fmt::Write;
 
     writeln!(output, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").unwrap();
@@ -384,7 +473,14 @@ pub fn format_xml_results(output: &mut String, results: &[SearchResult]) -> Resu
 
     for result in results {
         writeln!(output, "  <result>").unwrap();
-        writeln!(output, "    <file>{}</file>", escape_xml(&result.file)).unwrap();
+        let encoded = encode_result_path(&result.file, result.file_path.as_deref());
+        writeln!(
+            output,
+            "    <file{}>{}</file>",
+            encoded.xml_attribute(),
+            escape_xml(&encoded.value)
+        )
+        .unwrap();
 
         if result.node_type != "file" {
             writeln!(output, "    <lines>").unwrap();
@@ -402,11 +498,89 @@ pub fn format_xml_results(output: &mut String, results: &[SearchResult]) -> Resu
             .unwrap();
         }
 
+        if let Some(language) = &result.language {
+            writeln!(output, "    <language>{}</language>", escape_xml(language)).unwrap();
+        }
+
+        if let Some(confidence) = result.extraction_confidence {
+            writeln!(
+                output,
+                "    <extraction_confidence>{}</extraction_confidence>",
+                confidence.as_str()
+            )
+            .unwrap();
+        }
+
+        if result.via_symlink {
+            writeln!(output, "    <via_symlink>true</via_symlink>").unwrap();
+        }
+
+        if result.partial_parse {
+            writeln!(output, "    <partial_parse>true</partial_parse>").unwrap();
+        }
+
+        if result.deprecated {
+            writeln!(output, "    <deprecated>true</deprecated>").unwrap();
+        }
+
+        if let Some(changed_lines) = &result.changed_lines {
+            writeln!(output, "    <changed_lines>").unwrap();
+            for changed in changed_lines {
+                writeln!(
+                    output,
+                    "      <line kind=\"{}\">{}</line>",
+                    changed.kind.as_str(),
+                    changed.line
+                )
+                .unwrap();
+            }
+            writeln!(output, "    </changed_lines>").unwrap();
+        }
+
         writeln!(output, "    <code><![CDATA[{}]]></code>", result.code).unwrap();
         writeln!(output, "  </result>").unwrap();
     }
 
     // Add summary section
+    if !related.is_empty() {
+        writeln!(output, "  <related>").unwrap();
+        for block in related {
+            let encoded = encode_path(&block.file_path);
+            writeln!(
+                output,
+                "    <block file=\"{}\" lines=\"{}-{}\"{}>",
+                escape_xml(&encoded.value),
+                block.lines.0,
+                block.lines.1,
+                block
+                    .symbol
+                    .as_ref()
+                    .map(|symbol| format!(" symbol=\"{}\"", escape_xml(symbol)))
+                    .unwrap_or_default()
+            )
+            .unwrap();
+            for (tag, symbols) in [
+                ("sibling", &block.siblings),
+                ("caller", &block.callers),
+                ("callee", &block.callees),
+            ] {
+                for symbol in symbols {
+                    writeln!(
+                        output,
+                        "      <{} name=\"{}\" file=\"{}\" line=\"{}\"/>",
+                        tag,
+                        escape_xml(&symbol.name),
+                        escape_xml(&encode_path(&symbol.file_path).value),
+                        symbol.line
+                    )
+                    .unwrap();
+                }
+            }
+            writeln!(output, "    </block>").unwrap();
+        }
+        writeln!(output, "  </related>").unwrap();
+    }
+
     writeln!(output, "  <summary>").unwrap();
     writeln!(output, "    <count>{}</count>", results.len()).unwrap();
     writeln!(
@@ -431,23 +605,54 @@ pub fn format_xml_results(output: &mut String, results: &[SearchResult]) -> Resu
 #[allow(dead_code)]
 pub fn format_and_print_xml_results(results: &[SearchResult]) -> Result<()> {
     let mut output = String::new();
-    format_xml_results(&mut output, results)?;
+    format_xml_results(&mut output, results, &[])?;
     print!("{}", output);
     Ok(())
 }
 
 /// Format results in JSON format and write to a string buffer
-pub fn format_json_results(output: &mut String, results: &[SearchResult]) -> Result<()> {
+pub fn format_json_results(
+    output: &mut String,
+    results: &[SearchResult],
+    related: &[RelatedCode],
+) -> Result<()> {
     use std::fmt::Write;
 
     // Create a simplified version of the results for JSON output
     #[derive(serde::Serialize)]
     struct JsonResult<'a> {
-        file: &'a str,
+        file: String,
+        // Set to "percent" when `file` is a percent-encoded non-UTF-8 path
+        #[serde(skip_serializing_if = "Option::is_none")]
+        file_encoding: Option<&'static str>,
         #[serde(serialize_with = "serialize_lines_as_array")]
         lines: (usize, usize),
         node_type: &'a str,
+        // Language of the file, when detected
+        #[serde(skip_serializing_if = "Option::is_none")]
+        language: Option<&'a str>,
+        // How the block boundaries were found: "ast", "heuristic" or "whole_file"
+        #[serde(skip_serializing_if = "Option::is_none")]
+        extraction_confidence: Option<&'static str>,
         code: &'a str,
+        // Only present when the file was reached through a symbolic link
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        via_symlink: bool,
+        // Only present when the block contains syntax errors the parser recovered from
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        partial_parse: bool,
+        // Only present when the block carries a deprecation marker
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        deprecated: bool,
+        // Added/modified lines inside the block, only present for diff extraction
+        #[serde(skip_serializing_if = "Option::is_none")]
+        changed_lines: Option<Vec<JsonChangedLine>>,
+    }
+
+    #[derive(serde::Serialize)]
+    struct JsonChangedLine {
+        line: usize,
+        kind: &'static str,
     }
 
     // Helper function to serialize lines as an array
@@ -464,16 +669,36 @@ pub fn format_json_results(output: &mut String, results: &[SearchResult]) -> Res
 
     let json_results: Vec<JsonResult> = results
         .iter()
-        .map(|r| JsonResult {
-            file: &r.file,
-            lines: r.lines,
-            node_type: &r.node_type,
-            code: &r.code,
+        .map(|r| {
+            let encoded = encode_result_path(&r.file, r.file_path.as_deref());
+            JsonResult {
+                file: encoded.value,
+                file_encoding: encoded.encoding,
+                lines: r.lines,
+                node_type: &r.node_type,
+                language: r.language.as_deref(),
+                extraction_confidence: r
+                    .extraction_confidence
+                    .map(|confidence| confidence.as_str()),
+                code: &r.code,
+                via_symlink: r.via_symlink,
+                partial_parse: r.partial_parse,
+                deprecated: r.deprecated,
+                changed_lines: r.changed_lines.as_ref().map(|lines| {
+                    lines
+                        .iter()
+                        .map(|c| JsonChangedLine {
+                            line: c.line,
+                            kind: c.kind.as_str(),
+                        })
+                        .collect()
+                }),
+            }
         })
         .collect();
 
     // Create a wrapper object with results and summary
-    let wrapper = serde_json::json!({
+    let mut wrapper = serde_json::json!({
         "results": json_results,
         "summary": {
             "count": results.len(),
@@ -481,6 +706,34 @@ pub fn format_json_results(output: &mut String, results: &[SearchResult]) -> Res
             "total_tokens": results.iter().map(|r| count_tokens(&r.code)).sum::<usize>(),
         }
     });
+    if !related.is_empty() {
+        let symbols_json = |symbols: &[RelatedSymbol]| -> Vec<serde_json::Value> {
+            symbols
+                .iter()
+                .map(|symbol| {
+                    serde_json::json!({
+                        "name": symbol.name,
+                        "file": encode_path(&symbol.file_path).value,
+                        "line": symbol.line,
+                    })
+                })
+                .collect()
+        };
+        let blocks: Vec<serde_json::Value> = related
+            .iter()
+            .map(|block| {
+                serde_json::json!({
+                    "file": encode_path(&block.file_path).value,
+                    "lines": [block.lines.0, block.lines.1],
+                    "symbol": block.symbol,
+                    "siblings": symbols_json(&block.siblings),
+                    "callers": symbols_json(&block.callers),
+                    "callees": symbols_json(&block.callees),
+                })
+            })
+            .collect();
+        wrapper["related"] = blocks.into();
+    }
 
     write!(output, "{}", serde_json::to_string_pretty(&wrapper)?)?;
     Ok(())
@@ -490,7 +743,7 @@ pub fn format_json_results(output: &mut String, results: &[SearchResult]) -> Res
 #[allow(dead_code)]
 pub fn format_and_print_json_results(results: &[SearchResult]) -> Result<()> {
     let mut output = String::new();
-    format_json_results(&mut output, results)?;
+    format_json_results(&mut output, results, &[])?;
     print!("{}", output);
     Ok(())
 }
@@ -499,7 +752,7 @@ pub fn format_and_print_json_results(results: &[SearchResult]) -> Result<()> {
 pub fn format_color_results(output: &mut String, results: &[SearchResult]) {
     use colored::*;
     use regex::Regex;
-    use std::collections::HashSet;
+    use std::collections::{HashMap, HashSet};
     use std::fmt::Write;
 
     if results.is_empty() {
@@ -578,8 +831,14 @@ pub fn format_color_results(output: &mut String, results: &[SearchResult]) {
             writeln!(output, "```").unwrap();
         }
 
+        // Mark added (+) and modified (~) lines in a gutter when extracting from a diff
+        let gutter_marks: Option<HashMap<usize, char>> = result
+            .changed_lines
+            .as_ref()
+            .map(|lines| lines.iter().map(|c| (c.line, c.kind.gutter())).collect());
+
         // Process the code line by line to highlight matching terms
-        for line in result.code.lines() {
+        for (offset, line) in result.code.lines().enumerate() {
             let mut highlighted_line = line.to_string();
 
             // Apply highlighting for each pattern
@@ -605,6 +864,15 @@ pub fn format_color_results(output: &mut String, results: &[SearchResult]) {
                 highlighted_line = temp_line;
             }
 
+            if let Some(marks) = &gutter_marks {
+                let gutter = match marks.get(&(result.lines.0 + offset)) {
+                    Some('+') => "+ ".green().bold().to_string(),
+                    Some(mark) => format!("{} ", mark).yellow().bold().to_string(),
+                    None => "  ".to_string(),
+                };
+                highlighted_line = format!("{}{}", gutter, highlighted_line);
+            }
+
             writeln!(output, "{}", highlighted_line).unwrap();
         }
 
@@ -654,12 +922,16 @@ pub fn get_language_from_extension(extension: &str) -> &'static str {
         "sql" => "sql",
         "kt" | "kts" => "kotlin",
         "swift" => "swift",
+        "m" | "mm" => "objectivec",
         "scala" => "scala",
         "dart" => "dart",
+        "nix" => "nix",
+        "ml" | "mli" => "ocaml",
         "ex" | "exs" => "elixir",
         "hs" => "haskell",
         "clj" => "clojure",
         "lua" => "lua",
+        "graphql" | "gql" => "graphql",
         "r" => "r",
         "pl" | "pm" => "perl",
         "proto" => "protobuf",
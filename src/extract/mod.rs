@@ -6,27 +6,49 @@
 
 mod file_paths;
 mod formatter;
+mod patch;
 mod processor;
+mod related;
+mod spec;
 mod symbol_finder;
 
 // Re-export public functions
 #[allow(unused_imports)]
 pub use file_paths::{
-    extract_file_paths_from_git_diff, extract_file_paths_from_text, is_git_diff_format,
-    parse_file_with_line,
+    dedup_file_paths, extract_file_paths_from_git_diff, extract_file_paths_from_text,
+    extract_line_changes_from_git_diff, is_git_diff_format, parse_file_list, parse_file_with_line,
 };
 #[allow(unused_imports)]
 pub use formatter::format_and_print_extraction_results;
 #[allow(unused_imports)]
 pub use formatter::format_extraction_dry_run;
 #[allow(unused_imports)]
+pub use patch::{apply_patch, changed_file_paths, read_patch, PatchedFile};
+#[allow(unused_imports)]
 pub use processor::process_file_for_extraction;
-
-use crate::extract::file_paths::{set_custom_ignores, FilePathInfo};
-use anyhow::Result;
-use std::io::Read;
 #[allow(unused_imports)]
-use std::path::PathBuf;
+pub use related::{suggest_related, RelatedCode, RelatedSymbol};
+#[allow(unused_imports)]
+pub use spec::{load_spec, parse_spec, ExtractSpec, LineSpec, SpecEntry, SpecItem};
+#[allow(unused_imports)]
+pub use symbol_finder::find_symbol_in_file;
+
+use crate::extract::file_paths::FilePathInfo;
+use crate::fs_access::{self, OverlayFs};
+use crate::language::detection::{detect_language, LanguageOverrides};
+use crate::error::ProbeResult;
+use crate::models::{ChangedLine, LineChange, SearchResult};
+use crate::output_file::{check_output_options, OutputFile};
+use crate::restrict_root::RestrictRoot;
+use anyhow::{anyhow, Context, Result};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Read, Write};
+#[allow(unused_imports)]
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Changed lines of each file in a diff, keyed by line number in the new version
+type LineChanges = HashMap<PathBuf, BTreeMap<usize, LineChange>>;
 
 /// Options for the extract command
 pub struct ExtractOptions {
@@ -46,12 +68,218 @@ pub struct ExtractOptions {
     pub dry_run: bool,
     /// Whether to parse input as git diff format
     pub diff: bool,
+    /// Git revision to diff the working tree against
+    pub diff_base: Option<String>,
+    /// Spec file listing the entries to extract, with per-entry settings
+    pub spec: Option<PathBuf>,
+    /// Unified diff to apply in memory, extracting the changed blocks of the result
+    pub patch: Option<PathBuf>,
+    /// Whether to list sibling symbols, callers and callees of each extracted block
+    pub suggest_related: bool,
     /// Whether to allow test files and test code blocks
     pub allow_tests: bool,
+    /// Whether glob patterns may match files through symbolic links
+    pub follow_symlinks: bool,
+    /// File to write the results to instead of stdout
+    pub output: Option<PathBuf>,
+    /// Compression of the output file (`gzip` or `zstd`)
+    pub compress: Option<String>,
+    /// Directory every extracted file must resolve inside
+    pub restrict_root: Option<PathBuf>,
+}
+
+/// Run `git diff` for the working tree against `rev`
+///
+/// Paths in the diff are relative to the current directory, and only changes below it are
+/// included, so they can be read directly. Untracked files are not part of the diff.
+fn git_diff_against(rev: &str, pathspecs: &[String]) -> Result<String> {
+    let output = std::process::Command::new("git")
+        .args([
+            "diff",
+            "--no-color",
+            "--no-ext-diff",
+            "--relative",
+            rev,
+            "--",
+        ])
+        .args(pathspecs)
+        .output()
+        .context("Failed to run git")?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git diff against '{}' failed: {}",
+            rev,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Read the file list for `probe extract -` from stdin
+///
+/// A git diff on stdin is parsed like `--diff` input; anything else is one file spec per line.
+fn read_file_list_from_stdin(
+    options: &ExtractOptions,
+    line_changes: &mut LineChanges,
+) -> Result<Vec<FilePathInfo>> {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    let mut buffer = String::new();
+    std::io::stdin().read_to_string(&mut buffer)?;
+
+    let file_paths = if options.diff || is_git_diff_format(&buffer) {
+        line_changes.extend(extract_line_changes_from_git_diff(&buffer));
+        extract_file_paths_from_git_diff(&buffer, options.allow_tests)
+    } else {
+        parse_file_list(&buffer, options.allow_tests)
+    };
+
+    if debug_mode {
+        println!(
+            "[DEBUG] Read {} file specs from the stdin file list ({} bytes)",
+            file_paths.len(),
+            buffer.len()
+        );
+    }
+
+    Ok(file_paths)
+}
+
+/// Helper function to extract the block for one file spec, with the details the
+/// extract command reports about it
+#[allow(clippy::too_many_arguments)]
+fn extract_block(
+    path: &Path,
+    start_line: Option<usize>,
+    end_line: Option<usize>,
+    symbol: Option<&str>,
+    specific_lines: Option<&HashSet<usize>>,
+    allow_tests: bool,
+    context_lines: usize,
+    line_changes: &LineChanges,
+) -> Result<SearchResult> {
+    let mut result = processor::process_file_for_extraction(
+        path,
+        start_line,
+        end_line,
+        symbol,
+        allow_tests,
+        context_lines,
+        specific_lines,
+    )?;
+    // Relative paths are checked up to the working directory, absolute ones only
+    // for the file itself
+    let symlink_root = if path.is_relative() {
+        Path::new("")
+    } else {
+        path.parent().unwrap_or(path)
+    };
+    result.via_symlink = crate::search::file_list_cache::reached_via_symlink(symlink_root, path);
+    result.language =
+        detect_language(path, &result.code, &LanguageOverrides::new()).map(str::to_string);
+    if let Some(changes) = line_changes.get(path) {
+        result.changed_lines = Some(
+            changes
+                .range(result.lines.0..=result.lines.1)
+                .map(|(&line, &kind)| ChangedLine { line, kind })
+                .collect(),
+        );
+    }
+    Ok(result)
+}
+
+/// Options for extracting blocks through the library
+#[allow(dead_code)]
+pub struct ExtractBlocksOptions<'a> {
+    /// File specs as taken by `probe extract`: `file`, `file:10`, `file:10-20`,
+    /// `file#symbol` or a glob
+    pub files: &'a [String],
+    /// Number of context lines to include around a line
+    pub context_lines: usize,
+    /// Whether to allow test files and test code blocks
+    pub allow_tests: bool,
+    /// Custom patterns to ignore when a glob is expanded
+    pub custom_ignores: &'a [String],
+    /// Whether glob patterns may match files through symbolic links
+    pub follow_symlinks: bool,
+}
+
+/// Blocks extracted through the library
+#[derive(Debug, Clone, Default)]
+#[allow(dead_code)]
+pub struct Extraction {
+    pub results: Vec<SearchResult>,
+    /// One message per file spec that could not be extracted
+    pub errors: Vec<String>,
+}
+
+/// Function to extract code blocks without printing anything
+///
+/// This is `probe extract` for embedders: each file spec is resolved and extracted the
+/// same way, and the blocks are returned instead of formatted. A file that cannot be
+/// extracted is reported in [`Extraction::errors`] and does not stop the others.
+#[allow(dead_code)]
+pub fn extract_blocks(options: &ExtractBlocksOptions) -> ProbeResult<Extraction> {
+    file_paths::with_glob_settings(options.custom_ignores, options.follow_symlinks, || {
+        let mut extraction = Extraction::default();
+        for file in options.files {
+            let paths = file_paths::parse_file_with_line(file, options.allow_tests);
+            if paths.is_empty() {
+                extraction.errors.push(format!("No files match {:?}", file));
+            }
+            for (path, start_line, end_line, symbol, specific_lines) in paths {
+                match extract_block(
+                    &path,
+                    start_line,
+                    end_line,
+                    symbol.as_deref(),
+                    specific_lines.as_ref(),
+                    options.allow_tests,
+                    options.context_lines,
+                    &LineChanges::new(),
+                ) {
+                    Ok(result) => extraction.results.push(result),
+                    Err(e) => extraction
+                        .errors
+                        .push(format!("Error processing file {:?}: {}", path, e)),
+                }
+            }
+        }
+        Ok(extraction)
+    })
 }
 
 /// Handle the extract command
+///
+/// With a patch, the patched files are read from memory for the whole command.
 pub fn handle_extract(options: ExtractOptions) -> Result<()> {
+    let Some(patch_path) = &options.patch else {
+        return extract(options, None);
+    };
+    if let Some(restriction) = RestrictRoot::from_options(options.restrict_root.as_deref())? {
+        if patch_path.as_os_str() != "-" {
+            restriction.check(patch_path)?;
+        }
+    }
+    let patched = apply_patch(&read_patch(patch_path)?)?;
+    let buffers: Vec<(PathBuf, String)> = patched
+        .iter()
+        .map(|file| (file.path.clone(), file.contents.clone()))
+        .collect();
+    let overlay = Arc::new(OverlayFs::new(fs_access::current(), buffers));
+    fs_access::with_file_system(overlay, || extract(options, Some(patched)))
+}
+
+fn extract(options: ExtractOptions, patched: Option<Vec<PatchedFile>>) -> Result<()> {
+    let custom_ignores = options.custom_ignores.clone();
+    file_paths::with_glob_settings(&custom_ignores, options.follow_symlinks, || {
+        extract_files(options, patched)
+    })
+}
+
+fn extract_files(options: ExtractOptions, patched: Option<Vec<PatchedFile>>) -> Result<()> {
     use arboard::Clipboard;
     use colored::*;
 
@@ -68,15 +296,99 @@ pub fn handle_extract(options: ExtractOptions) -> Result<()> {
         println!("[DEBUG] Write to clipboard: {}", options.to_clipboard);
         println!("[DEBUG] Dry run: {}", options.dry_run);
         println!("[DEBUG] Parse as git diff: {}", options.diff);
+        println!("[DEBUG] Diff base: {:?}", options.diff_base);
+        println!("[DEBUG] Patch: {:?}", options.patch);
         println!("[DEBUG] Allow tests: {}", options.allow_tests);
+        println!("[DEBUG] Follow symlinks: {}", options.follow_symlinks);
+        println!("[DEBUG] Output file: {:?}", options.output);
     }
 
-    // Set custom ignore patterns
-    set_custom_ignores(&options.custom_ignores);
+    // Check the output file options before extracting anything
+    let compression = check_output_options(
+        options.output.as_deref(),
+        options.compress.as_deref(),
+        &options.format,
+    )?;
+
+    // Refuse a spec or output file outside --restrict-root before reading anything
+    let restriction = RestrictRoot::from_options(options.restrict_root.as_deref())?;
+    if let Some(restriction) = &restriction {
+        // Files a patch renames were read from their old path
+        let patch_sources = patched.iter().flatten().flat_map(|file| &file.source);
+        for path in options
+            .spec
+            .iter()
+            .chain(patch_sources)
+            .chain(&options.output)
+        {
+            restriction.check(path)?;
+        }
+    }
 
     let mut file_paths: Vec<FilePathInfo> = Vec::new();
+    // Added and modified lines per file when the input is a diff
+    let mut line_changes = LineChanges::new();
+    // Context lines and test policy of each file when they come from a spec
+    let mut entry_settings: Option<Vec<(usize, bool)>> = None;
+
+    if let Some(spec_path) = &options.spec {
+        let (items, unmatched) =
+            spec::load_spec(spec_path, options.context_lines, options.allow_tests)?;
+
+        if debug_mode {
+            println!(
+                "[DEBUG] Spec {:?} resolved to {} files ({} entries unmatched)",
+                spec_path,
+                items.len(),
+                unmatched.len()
+            );
+        }
+
+        for file in &unmatched {
+            eprintln!("{} {}", "No files match spec entry:".yellow().bold(), file);
+        }
+
+        let mut settings = Vec::with_capacity(items.len());
+        for item in items {
+            settings.push((item.context_lines, item.allow_tests));
+            file_paths.push(item.file_path);
+        }
+        entry_settings = Some(settings);
+
+        if file_paths.is_empty() {
+            println!("{}", "No files to extract in spec.".yellow().bold());
+            return Ok(());
+        }
+    } else if let Some(patched) = &patched {
+        file_paths = changed_file_paths(patched, options.allow_tests);
+        line_changes = patched
+            .iter()
+            .map(|file| (file.path.clone(), file.changes.clone()))
+            .collect();
 
-    if options.from_clipboard {
+        if file_paths.is_empty() {
+            println!("{}", "No changed lines in the patch.".yellow().bold());
+            return Ok(());
+        }
+    } else if let Some(rev) = &options.diff_base {
+        let diff = git_diff_against(rev, &options.files)?;
+
+        if debug_mode {
+            println!(
+                "[DEBUG] git diff against {} produced {} bytes",
+                rev,
+                diff.len()
+            );
+        }
+
+        file_paths = extract_file_paths_from_git_diff(&diff, options.allow_tests);
+        line_changes = extract_line_changes_from_git_diff(&diff);
+
+        if file_paths.is_empty() {
+            println!("{}", format!("No changes against {}.", rev).yellow().bold());
+            return Ok(());
+        }
+    } else if options.from_clipboard {
         // Read from clipboard
         println!("{}", "Reading from clipboard...".bold().blue());
         let mut clipboard = Clipboard::new()?;
@@ -98,6 +410,7 @@ pub fn handle_extract(options: ExtractOptions) -> Result<()> {
                 println!("[DEBUG] Parsing clipboard content as git diff format");
             }
             file_paths = extract_file_paths_from_git_diff(&buffer, options.allow_tests);
+            line_changes = extract_line_changes_from_git_diff(&buffer);
         } else {
             // Parse as regular text
             file_paths = file_paths::extract_file_paths_from_text(&buffer, options.allow_tests);
@@ -150,6 +463,7 @@ pub fn handle_extract(options: ExtractOptions) -> Result<()> {
                     println!("[DEBUG] Parsing stdin content as git diff format");
                 }
                 file_paths = extract_file_paths_from_git_diff(&buffer, options.allow_tests);
+                line_changes = extract_line_changes_from_git_diff(&buffer);
             } else {
                 // Parse as regular text
                 file_paths = file_paths::extract_file_paths_from_text(&buffer, options.allow_tests);
@@ -198,6 +512,13 @@ pub fn handle_extract(options: ExtractOptions) -> Result<()> {
                 println!("[DEBUG] Parsing file argument: {}", file);
             }
 
+            // `-` reads a list of file specs from stdin, e.g. `git diff --name-only | probe extract -`
+            if file == "-" {
+                let stdin_paths = read_file_list_from_stdin(&options, &mut line_changes)?;
+                file_paths.extend(stdin_paths);
+                continue;
+            }
+
             let paths = file_paths::parse_file_with_line(file, options.allow_tests);
 
             if debug_mode {
@@ -222,6 +543,33 @@ pub fn handle_extract(options: ExtractOptions) -> Result<()> {
         }
     }
 
+    // Files named in a stdin list that do not exist are reported once instead of failing one by one
+    let mut missing_files = Vec::new();
+    if options.files.iter().any(|f| f == "-") {
+        file_paths = dedup_file_paths(file_paths);
+        file_paths.retain(|(path, ..)| {
+            if path.exists() {
+                true
+            } else {
+                if !missing_files.contains(path) {
+                    missing_files.push(path.clone());
+                }
+                false
+            }
+        });
+        // Written to stderr so JSON and XML output stays parseable
+        for path in &missing_files {
+            eprintln!("{} {}", "File not found:".yellow().bold(), path.display());
+        }
+    }
+
+    // Every file to extract, including glob matches through symlinks, must stay inside the root
+    if let Some(restriction) = &restriction {
+        for (path, ..) in &file_paths {
+            restriction.check(path)?;
+        }
+    }
+
     // Only print file information for non-JSON/XML formats
     if options.format != "json" && options.format != "xml" {
         println!("{}", "Files to extract:".bold().green());
@@ -260,7 +608,14 @@ pub fn handle_extract(options: ExtractOptions) -> Result<()> {
     let mut errors = Vec::new();
 
     // Process each file
-    for (path, start_line, end_line, symbol, specific_lines) in file_paths {
+    for (index, (path, start_line, end_line, symbol, specific_lines)) in
+        file_paths.into_iter().enumerate()
+    {
+        let (context_lines, allow_tests) = entry_settings
+            .as_ref()
+            .map(|settings| settings[index])
+            .unwrap_or((options.context_lines, options.allow_tests));
+
         if debug_mode {
             println!("\n[DEBUG] Processing file: {:?}", path);
             println!("[DEBUG] Start line: {:?}", start_line);
@@ -297,18 +652,19 @@ pub fn handle_extract(options: ExtractOptions) -> Result<()> {
 
         // The allow_tests check is now handled in the file path extraction functions
         // We only need to check if this is a test file for debugging purposes
-        if debug_mode && crate::language::is_test_file(&path) && !options.allow_tests {
+        if debug_mode && crate::language::is_test_file(&path) && !allow_tests {
             println!("[DEBUG] Test file detected: {:?}", path);
         }
 
-        match processor::process_file_for_extraction(
+        match extract_block(
             &path,
             start_line,
             end_line,
             symbol.as_deref(),
-            options.allow_tests,
-            options.context_lines,
             specific_lines.as_ref(),
+            allow_tests,
+            context_lines,
+            &line_changes,
         ) {
             Ok(result) => {
                 if debug_mode {
@@ -345,10 +701,22 @@ pub fn handle_extract(options: ExtractOptions) -> Result<()> {
         println!("[DEBUG] Dry run: {}", options.dry_run);
     }
 
+    // Names and locations of code around each block, for the agent to extract next
+    let related = if options.suggest_related && !options.dry_run {
+        related::suggest_related(
+            &results,
+            std::path::Path::new("."),
+            options.allow_tests,
+            &options.custom_ignores,
+        )?
+    } else {
+        Vec::new()
+    };
+
     // Format the results
     let res = {
         // Temporarily disable colors if writing to clipboard
-        let colors_enabled = if options.to_clipboard {
+        let colors_enabled = if options.to_clipboard || options.output.is_some() {
             let was_enabled = colored::control::SHOULD_COLORIZE.should_colorize();
             colored::control::set_override(false);
             was_enabled
@@ -360,11 +728,11 @@ pub fn handle_extract(options: ExtractOptions) -> Result<()> {
         let result = if options.dry_run {
             formatter::format_extraction_dry_run(&results, &options.format)
         } else {
-            formatter::format_extraction_results(&results, &options.format)
+            formatter::format_extraction_results(&results, &related, &options.format)
         };
 
         // Restore color settings if they were changed
-        if options.to_clipboard && colors_enabled {
+        if (options.to_clipboard || options.output.is_some()) && colors_enabled {
             colored::control::set_override(true);
         }
 
@@ -384,6 +752,13 @@ pub fn handle_extract(options: ExtractOptions) -> Result<()> {
                         formatted_output.len()
                     );
                 }
+            } else if let Some(output) = &options.output {
+                let mut file = OutputFile::create(output, compression)?;
+                writeln!(file, "{}", formatted_output)?;
+                file.finish()?;
+                if options.format != "json" && options.format != "xml" {
+                    println!("Results written to {}", output.display());
+                }
             } else {
                 // Print to stdout
                 println!("{}", formatted_output);
@@ -410,6 +785,13 @@ pub fn handle_extract(options: ExtractOptions) -> Result<()> {
             if errors.len() == 1 { "error" } else { "errors" }
         );
     }
+    if !missing_files.is_empty() && options.format != "json" && options.format != "xml" {
+        println!(
+            "{} {}",
+            "Missing files skipped:".yellow().bold(),
+            missing_files.len()
+        );
+    }
 
     if debug_mode {
         println!("[DEBUG] ===== Extract Command Completed =====");
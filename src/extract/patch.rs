@@ -0,0 +1,312 @@
+//! Applying a unified diff in memory for `probe preview`.
+//!
+//! Each file's hunks are applied to its current contents, read through the
+//! file system layer, and the result is kept in memory along with the lines the
+//! patch changed. Nothing is written: the preview reads the patched files through
+//! an overlay, so agents can see what a proposed edit would look like in context.
+
+use crate::fs_access;
+use crate::language::is_test_file;
+use crate::models::LineChange;
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+use std::collections::{BTreeMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use super::file_paths::FilePathInfo;
+
+/// One file as it would be after the patch
+#[derive(Debug, Clone)]
+pub struct PatchedFile {
+    /// Path of the file after the patch
+    pub path: PathBuf,
+    /// Path the original contents were read from, `None` for new files
+    pub source: Option<PathBuf>,
+    /// Contents after the patch
+    pub contents: String,
+    /// Added and modified lines, numbered in the patched contents
+    pub changes: BTreeMap<usize, LineChange>,
+}
+
+/// A hunk: the lines it expects to find and the lines that replace them
+struct Hunk {
+    old_start: usize,
+    /// Each line with its marker: ' ', '-' or '+'
+    lines: Vec<(char, String)>,
+    /// Whether the new side ends without a newline
+    new_missing_newline: bool,
+    /// Whether the old side ends without a newline
+    old_missing_newline: bool,
+}
+
+impl Hunk {
+    /// Record a "\ No newline at end of file", which is about the line before it
+    fn mark_missing_newline(&mut self) {
+        match self.lines.last().map(|(marker, _)| *marker) {
+            Some('-') => self.old_missing_newline = true,
+            Some('+') => self.new_missing_newline = true,
+            _ => {
+                self.old_missing_newline = true;
+                self.new_missing_newline = true;
+            }
+        }
+    }
+}
+
+/// A file section of the diff
+struct FilePatch {
+    /// `None` for files the patch creates
+    old_path: Option<PathBuf>,
+    /// `None` for files the patch deletes
+    new_path: Option<PathBuf>,
+    hunks: Vec<Hunk>,
+}
+
+/// Function to read a patch from a file, or from stdin when `path` is `-`
+pub fn read_patch(path: &Path) -> Result<String> {
+    if path == Path::new("-") {
+        let mut patch = String::new();
+        std::io::stdin().read_to_string(&mut patch)?;
+        return Ok(patch);
+    }
+    fs_access::read_to_string(path)
+        .with_context(|| format!("Failed to read patch {}", path.display()))
+}
+
+/// Function to apply a unified diff to the current files without writing anything
+///
+/// Accepts `git diff` output as well as plain `---`/`+++` diffs. A hunk whose
+/// context has moved is applied at the nearest place it matches; one that matches
+/// nowhere fails the whole preview. Files the patch deletes are left out.
+pub fn apply_patch(patch: &str) -> Result<Vec<PatchedFile>> {
+    let mut patched = Vec::new();
+    for file_patch in parse_patch(patch)? {
+        let Some(new_path) = file_patch.new_path.clone() else {
+            continue;
+        };
+        let original = match &file_patch.old_path {
+            Some(old_path) => fs_access::read_to_string(old_path)
+                .with_context(|| format!("Failed to read {} to patch", old_path.display()))?,
+            None => String::new(),
+        };
+        let (contents, changes) = apply_hunks(&original, &file_patch.hunks)
+            .with_context(|| format!("Patch does not apply to {}", new_path.display()))?;
+        patched.push(PatchedFile {
+            path: new_path,
+            source: file_patch.old_path,
+            contents,
+            changes,
+        });
+    }
+    Ok(patched)
+}
+
+/// Function to list the changed lines of each patched file for extraction
+///
+/// Test files are left out unless `allow_tests` is set, as with `--diff`.
+pub fn changed_file_paths(files: &[PatchedFile], allow_tests: bool) -> Vec<FilePathInfo> {
+    files
+        .iter()
+        .filter(|file| !file.changes.is_empty())
+        .filter(|file| allow_tests || !is_test_file(&file.path))
+        .map(|file| {
+            let lines: HashSet<usize> = file.changes.keys().copied().collect();
+            (
+                file.path.clone(),
+                file.changes.keys().next().copied(),
+                file.changes.keys().next_back().copied(),
+                None,
+                Some(lines),
+            )
+        })
+        .collect()
+}
+
+/// Strip the `a/` or `b/` prefix git adds, and any timestamp after a tab
+fn header_path(header: &str, prefix: &str, git: bool) -> Option<PathBuf> {
+    let path = header.split('\t').next().unwrap_or(header).trim_end();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = if git {
+        path.strip_prefix(prefix).unwrap_or(path)
+    } else {
+        path
+    };
+    Some(PathBuf::from(path))
+}
+
+fn parse_patch(patch: &str) -> Result<Vec<FilePatch>> {
+    let hunk_header_regex = Regex::new(r"^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@").unwrap();
+    let lines: Vec<&str> = patch.lines().collect();
+
+    let mut files = Vec::new();
+    let mut git = false;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if line.starts_with("diff --git ") {
+            git = true;
+            i += 1;
+            continue;
+        }
+        let (Some(old), Some(new)) = (
+            line.strip_prefix("--- "),
+            lines.get(i + 1).and_then(|next| next.strip_prefix("+++ ")),
+        ) else {
+            i += 1;
+            continue;
+        };
+
+        // Plain diffs from tools that mimic git still use a/ and b/ prefixes
+        let prefixed = git || (old.starts_with("a/") && new.starts_with("b/"));
+        let mut file = FilePatch {
+            old_path: header_path(old, "a/", prefixed),
+            new_path: header_path(new, "b/", prefixed),
+            hunks: Vec::new(),
+        };
+        i += 2;
+
+        while let Some(cap) = lines
+            .get(i)
+            .and_then(|line| hunk_header_regex.captures(line))
+        {
+            // A missing length means one line
+            let number = |index: usize| {
+                cap.get(index)
+                    .and_then(|m| m.as_str().parse().ok())
+                    .unwrap_or(1)
+            };
+            let (old_start, mut old_left, mut new_left) = (number(1), number(2), number(4));
+            let mut hunk = Hunk {
+                old_start,
+                lines: Vec::new(),
+                new_missing_newline: false,
+                old_missing_newline: false,
+            };
+            i += 1;
+
+            // Count lines instead of looking at markers, so a removed "-- comment" is
+            // not taken for the next file header
+            while old_left > 0 || new_left > 0 {
+                let Some(line) = lines.get(i) else {
+                    return Err(anyhow!("Patch ends in the middle of a hunk"));
+                };
+                let (marker, text) = match line.chars().next() {
+                    Some(marker @ ('+' | '-' | ' ')) => (marker, &line[1..]),
+                    // Editors often strip the space from empty context lines
+                    None => (' ', ""),
+                    Some('\\') => {
+                        hunk.mark_missing_newline();
+                        i += 1;
+                        continue;
+                    }
+                    Some(_) => return Err(anyhow!("Malformed hunk line: {}", line)),
+                };
+                match marker {
+                    '+' => new_left = new_left.saturating_sub(1),
+                    '-' => old_left = old_left.saturating_sub(1),
+                    _ => {
+                        old_left = old_left.saturating_sub(1);
+                        new_left = new_left.saturating_sub(1);
+                    }
+                }
+                hunk.lines.push((marker, text.to_string()));
+                i += 1;
+            }
+
+            if lines.get(i).is_some_and(|line| line.starts_with('\\')) {
+                hunk.mark_missing_newline();
+                i += 1;
+            }
+            file.hunks.push(hunk);
+        }
+
+        files.push(file);
+    }
+
+    if files.is_empty() {
+        return Err(anyhow!("No file changes found in the patch"));
+    }
+    Ok(files)
+}
+
+fn apply_hunks(original: &str, hunks: &[Hunk]) -> Result<(String, BTreeMap<usize, LineChange>)> {
+    let old_lines: Vec<&str> = original.lines().collect();
+    let mut new_lines: Vec<String> = Vec::with_capacity(old_lines.len());
+    let mut changes = BTreeMap::new();
+    let mut cursor = 0;
+    let mut missing_newline = !original.is_empty() && !original.ends_with('\n');
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        let expected: Vec<&str> = hunk
+            .lines
+            .iter()
+            .filter(|(marker, _)| *marker != '+')
+            .map(|(_, text)| text.as_str())
+            .collect();
+        // A hunk that only adds lines starts after line `old_start`
+        let target = if expected.is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let position = find_hunk(&old_lines, &expected, cursor, target)
+            .ok_or_else(|| anyhow!("Hunk {} does not match the file", index + 1))?;
+
+        new_lines.extend(
+            old_lines[cursor..position]
+                .iter()
+                .map(|line| line.to_string()),
+        );
+        let mut old_index = position;
+        // Removed lines not yet paired with an added line, as in `--diff` extraction
+        let mut unpaired_removals = 0;
+        for (marker, text) in &hunk.lines {
+            match marker {
+                '-' => {
+                    unpaired_removals += 1;
+                    old_index += 1;
+                }
+                '+' => {
+                    new_lines.push(text.clone());
+                    let kind = if unpaired_removals > 0 {
+                        unpaired_removals -= 1;
+                        LineChange::Modified
+                    } else {
+                        LineChange::Added
+                    };
+                    changes.insert(new_lines.len(), kind);
+                }
+                _ => {
+                    unpaired_removals = 0;
+                    new_lines.push(old_lines[old_index].to_string());
+                    old_index += 1;
+                }
+            }
+        }
+        cursor = old_index;
+
+        if hunk.new_missing_newline {
+            missing_newline = true;
+        } else if hunk.old_missing_newline || cursor == old_lines.len() {
+            missing_newline = false;
+        }
+    }
+    new_lines.extend(old_lines[cursor..].iter().map(|line| line.to_string()));
+
+    let mut contents = new_lines.join("\n");
+    if !contents.is_empty() && !missing_newline {
+        contents.push('\n');
+    }
+    Ok((contents, changes))
+}
+
+/// Find where `expected` matches `lines`, at or after `from`, closest to `target`
+fn find_hunk(lines: &[&str], expected: &[&str], from: usize, target: usize) -> Option<usize> {
+    let last = lines.len().checked_sub(expected.len())?;
+    (from..=last)
+        .filter(|&position| lines[position..position + expected.len()] == *expected)
+        .min_by_key(|&position| position.abs_diff(target))
+}
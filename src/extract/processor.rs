@@ -4,11 +4,12 @@
 //! based on file paths and optional line numbers.
 
 use crate::extract::symbol_finder::find_symbol_in_file;
+use crate::fs_access;
+use crate::language::deprecation::is_deprecated;
 use crate::language::parser::parse_file_for_code_blocks;
-use crate::models::SearchResult;
+use crate::models::{ExtractionConfidence, SearchResult};
 use anyhow::{Context, Result};
 use std::collections::HashSet;
-use std::fs;
 use std::path::Path;
 
 /// Process a single file and extract code blocks
@@ -22,6 +23,7 @@ use std::path::Path;
 ///
 /// This function returns a single SearchResult that includes either the merged AST code
 /// or the literal lines as a fallback.
+#[allow(clippy::needless_return)]
 pub fn process_file_for_extraction(
     path: &Path,
     start_line: Option<usize>,
@@ -46,7 +48,7 @@ pub fn process_file_for_extraction(
     }
 
     // Check if the file exists
-    if !path.exists() {
+    if !fs_access::exists(path) {
         if debug_mode {
             println!("[DEBUG] Error: File does not exist");
         }
@@ -54,7 +56,8 @@ pub fn process_file_for_extraction(
     }
 
     // Read the file content
-    let content = fs::read_to_string(path).context(format!("Failed to read file: {:?}", path))?;
+    let content =
+        fs_access::read_to_string(path).context(format!("Failed to read file: {:?}", path))?;
     let lines: Vec<&str> = content.lines().collect();
 
     if debug_mode {
@@ -168,6 +171,7 @@ pub fn process_file_for_extraction(
 
                 Ok(SearchResult {
                     file: path.to_string_lossy().to_string(),
+                    file_path: Some(path.to_path_buf()),
                     lines: (merged_start, merged_end),
                     node_type: "merged_ast_range".to_string(),
                     code: merged_content,
@@ -190,6 +194,20 @@ pub fn process_file_for_extraction(
                     block_id: None,
                     matched_keywords: None,
                     tokenized_content: Some(tokenized_content),
+                    merged_from: None,
+                    normalized_score: None,
+                    usage_kind: None,
+                    via_symlink: false,
+                    changed_lines: None,
+                    symbol_name: None,
+                    language: None,
+                    extraction_confidence: Some(ExtractionConfidence::Ast),
+                    partial_parse: blocks.iter().any(|block| block.partial_parse),
+                    deprecated: is_deprecated(&lines, merged_start, merged_end),
+                    embedded: None,
+                    kind: None,
+                    complexity: None,
+                    content_hash: None,
                 })
             }
             _ => {
@@ -213,6 +231,7 @@ pub fn process_file_for_extraction(
 
                 Ok(SearchResult {
                     file: path.to_string_lossy().to_string(),
+                    file_path: Some(path.to_path_buf()),
                     lines: (start, end),
                     node_type: "range".to_string(),
                     code: range_content,
@@ -235,6 +254,20 @@ pub fn process_file_for_extraction(
                     block_id: None,
                     matched_keywords: None,
                     tokenized_content: Some(tokenized_content),
+                    merged_from: None,
+                    normalized_score: None,
+                    usage_kind: None,
+                    via_symlink: false,
+                    changed_lines: None,
+                    symbol_name: None,
+                    language: None,
+                    extraction_confidence: Some(ExtractionConfidence::Heuristic),
+                    partial_parse: false,
+                    deprecated: is_deprecated(&lines, start, end),
+                    embedded: None,
+                    kind: None,
+                    complexity: None,
+                    content_hash: None,
                 })
             }
         }
@@ -314,6 +347,7 @@ pub fn process_file_for_extraction(
 
                 return Ok(SearchResult {
                     file: path.to_string_lossy().to_string(),
+                    file_path: Some(path.to_path_buf()),
                     lines: (merged_start, merged_end),
                     node_type: "merged_ast_line".to_string(),
                     code: merged_content,
@@ -336,6 +370,20 @@ pub fn process_file_for_extraction(
                     block_id: None,
                     matched_keywords: None,
                     tokenized_content: Some(tokenized_content),
+                    merged_from: None,
+                    normalized_score: None,
+                    usage_kind: None,
+                    via_symlink: false,
+                    changed_lines: None,
+                    symbol_name: None,
+                    language: None,
+                    extraction_confidence: Some(ExtractionConfidence::Ast),
+                    partial_parse: blocks.iter().any(|block| block.partial_parse),
+                    deprecated: is_deprecated(&lines, merged_start, merged_end),
+                    embedded: None,
+                    kind: None,
+                    complexity: None,
+                    content_hash: None,
                 });
             }
             _ => {
@@ -371,6 +419,7 @@ pub fn process_file_for_extraction(
 
                 return Ok(SearchResult {
                     file: path.to_string_lossy().to_string(),
+                    file_path: Some(path.to_path_buf()),
                     lines: (start_ctx, end_ctx),
                     node_type: "context".to_string(),
                     code: context_code,
@@ -393,6 +442,20 @@ pub fn process_file_for_extraction(
                     block_id: None,
                     matched_keywords: None,
                     tokenized_content: Some(tokenized_content),
+                    merged_from: None,
+                    normalized_score: None,
+                    usage_kind: None,
+                    via_symlink: false,
+                    changed_lines: None,
+                    symbol_name: None,
+                    language: None,
+                    extraction_confidence: Some(ExtractionConfidence::Heuristic),
+                    partial_parse: false,
+                    deprecated: is_deprecated(&lines, start_ctx, end_ctx),
+                    embedded: None,
+                    kind: None,
+                    complexity: None,
+                    content_hash: None,
                 });
             }
         }
@@ -417,6 +480,7 @@ pub fn process_file_for_extraction(
 
             return Ok(SearchResult {
                 file: path.to_string_lossy().to_string(),
+                file_path: Some(path.to_path_buf()),
                 lines: (1, lines.len()),
                 node_type: "file".to_string(),
                 code: content,
@@ -439,6 +503,20 @@ pub fn process_file_for_extraction(
                 block_id: None,
                 matched_keywords: None,
                 tokenized_content: Some(tokenized_content),
+                merged_from: None,
+                normalized_score: None,
+                usage_kind: None,
+                via_symlink: false,
+                changed_lines: None,
+                symbol_name: None,
+                language: None,
+                extraction_confidence: Some(ExtractionConfidence::WholeFile),
+                partial_parse: false,
+                deprecated: false,
+                embedded: None,
+                kind: None,
+                complexity: None,
+                content_hash: None,
             });
         }
 
@@ -514,6 +592,7 @@ pub fn process_file_for_extraction(
 
                 return Ok(SearchResult {
                     file: path.to_string_lossy().to_string(),
+                    file_path: Some(path.to_path_buf()),
                     lines: (merged_start, merged_end),
                     node_type: "merged_ast_specific_lines".to_string(),
                     code: merged_content,
@@ -536,6 +615,20 @@ pub fn process_file_for_extraction(
                     block_id: None,
                     matched_keywords: None,
                     tokenized_content: Some(tokenized_content),
+                    merged_from: None,
+                    normalized_score: None,
+                    usage_kind: None,
+                    via_symlink: false,
+                    changed_lines: None,
+                    symbol_name: None,
+                    language: None,
+                    extraction_confidence: Some(ExtractionConfidence::Ast),
+                    partial_parse: blocks.iter().any(|block| block.partial_parse),
+                    deprecated: is_deprecated(&lines, merged_start, merged_end),
+                    embedded: None,
+                    kind: None,
+                    complexity: None,
+                    content_hash: None,
                 });
             }
             _ => {
@@ -572,6 +665,7 @@ pub fn process_file_for_extraction(
 
                 return Ok(SearchResult {
                     file: path.to_string_lossy().to_string(),
+                    file_path: Some(path.to_path_buf()),
                     lines: (start, end),
                     node_type: "specific_lines".to_string(),
                     code: range_content,
@@ -594,6 +688,20 @@ pub fn process_file_for_extraction(
                     block_id: None,
                     matched_keywords: None,
                     tokenized_content: Some(tokenized_content),
+                    merged_from: None,
+                    normalized_score: None,
+                    usage_kind: None,
+                    via_symlink: false,
+                    changed_lines: None,
+                    symbol_name: None,
+                    language: None,
+                    extraction_confidence: Some(ExtractionConfidence::Heuristic),
+                    partial_parse: false,
+                    deprecated: is_deprecated(&lines, start, end),
+                    embedded: None,
+                    kind: None,
+                    complexity: None,
+                    content_hash: None,
                 });
             }
         }
@@ -612,6 +720,7 @@ pub fn process_file_for_extraction(
 
         Ok(SearchResult {
             file: path.to_string_lossy().to_string(),
+            file_path: Some(path.to_path_buf()),
             lines: (1, lines.len()),
             node_type: "file".to_string(),
             code: content,
@@ -634,6 +743,20 @@ pub fn process_file_for_extraction(
             block_id: None,
             matched_keywords: None,
             tokenized_content: Some(tokenized_content),
+            merged_from: None,
+            normalized_score: None,
+            usage_kind: None,
+            via_symlink: false,
+            changed_lines: None,
+            symbol_name: None,
+            language: None,
+            extraction_confidence: Some(ExtractionConfidence::WholeFile),
+            partial_parse: false,
+            deprecated: false,
+            embedded: None,
+            kind: None,
+            complexity: None,
+            content_hash: None,
         })
     }
 }
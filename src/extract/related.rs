@@ -0,0 +1,381 @@
+//! Suggestions of related code for `extract --suggest-related`.
+//!
+//! After a block is extracted, the symbols around it are listed by name and location
+//! only: its siblings in the same type or file, the functions that call it, and the
+//! definitions of the functions it calls. That tells the caller what to fetch next
+//! without another full search. Calls are matched by name, so a method with a common
+//! name may list callers of a different method with the same name.
+
+use anyhow::Result;
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use tree_sitter::{Node, Parser as TSParser};
+
+use crate::definition::is_declaration_kind;
+use crate::fs_access;
+use crate::impls::{bare_name, node_text, walk};
+use crate::language::factory::get_language_impl;
+use crate::models::SearchResult;
+use crate::search::file_list_cache;
+use crate::summarize::declared_name;
+
+/// Most suggestions listed of each kind, nearest or most local first
+pub const MAX_SUGGESTIONS: usize = 10;
+
+/// Node kinds of function and method calls across the supported grammars
+const CALL_KINDS: &[&str] = &[
+    "call_expression",
+    "call",
+    "method_invocation",
+    "invocation_expression",
+    "function_call_expression",
+    "member_call_expression",
+    "scoped_call_expression",
+];
+
+/// Nodes that wrap a declaration without being one, like `export function` and decorators
+const WRAPPER_KINDS: &[&str] = &["export_statement", "decorated_definition"];
+
+/// A symbol suggested next to an extracted block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelatedSymbol {
+    pub name: String,
+    pub file_path: PathBuf,
+    /// First line of the symbol's declaration
+    pub line: usize,
+}
+
+/// The code related to one extracted block
+#[derive(Debug, Clone)]
+pub struct RelatedCode {
+    /// File of the extracted block
+    pub file_path: PathBuf,
+    pub lines: (usize, usize),
+    /// Name of the symbol the block declares, if it declares one
+    pub symbol: Option<String>,
+    /// Other symbols declared in the same type, module or file
+    pub siblings: Vec<RelatedSymbol>,
+    /// Functions that call the symbol
+    pub callers: Vec<RelatedSymbol>,
+    /// Definitions of the functions the block calls
+    pub callees: Vec<RelatedSymbol>,
+}
+
+/// What one extracted block declares and calls
+struct BlockFacts {
+    symbol: Option<String>,
+    siblings: Vec<RelatedSymbol>,
+    called: HashSet<String>,
+}
+
+fn parse(file_path: &Path, content: &str) -> Option<tree_sitter::Tree> {
+    let extension = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let language_impl = get_language_impl(extension)?;
+    let mut parser = TSParser::new();
+    parser
+        .set_language(&language_impl.get_tree_sitter_language())
+        .ok()?;
+    parser.parse(content, None)
+}
+
+/// Helper function to get the name a call node calls, e.g. `load` for `config.load(path)`
+fn called_name<'a>(node: Node, content: &'a [u8]) -> Option<&'a str> {
+    let callee = node
+        .child_by_field_name("name")
+        .or_else(|| node.child_by_field_name("method"))
+        .or_else(|| node.child_by_field_name("function"))
+        .or_else(|| node.named_child(0))?;
+    let name = bare_name(node_text(callee, content));
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '$');
+    valid.then_some(name)
+}
+
+/// Helper function to get the declaration a node is nested in, with its name
+fn enclosing_declaration<'a>(node: Node<'a>, content: &'a [u8]) -> Option<(Node<'a>, &'a str)> {
+    let mut current = node.parent();
+    while let Some(parent) = current {
+        if is_declaration_kind(parent.kind()) {
+            if let Some(name) = declared_name(parent, content) {
+                return Some((parent, name));
+            }
+        }
+        current = parent.parent();
+    }
+    None
+}
+
+/// Helper function to unwrap `export function f` and decorated definitions to the declaration
+fn unwrapped(node: Node) -> Node {
+    if WRAPPER_KINDS.contains(&node.kind()) {
+        let mut cursor = node.walk();
+        let declaration = node
+            .named_children(&mut cursor)
+            .find(|child| is_declaration_kind(child.kind()));
+        if let Some(declaration) = declaration {
+            return declaration;
+        }
+    }
+    node
+}
+
+/// Helper function to compare paths given relative to the working directory or absolute
+fn same_file(a: &Path, b: &Path) -> bool {
+    let key = |path: &Path| {
+        path.canonicalize().unwrap_or_else(|_| {
+            path.components()
+                .filter(|component| *component != Component::CurDir)
+                .collect()
+        })
+    };
+    key(a) == key(b)
+}
+
+/// Find what the block at `lines` of `file_path` declares, its siblings and its calls
+fn block_facts(file_path: &Path, lines: (usize, usize)) -> Option<BlockFacts> {
+    let content = fs_access::read_to_string(file_path).ok()?;
+    let tree = parse(file_path, &content)?;
+    let bytes = content.as_bytes();
+
+    // The innermost named declaration covering the block, or else the first one inside
+    // it, since blocks can start with the doc comments and attributes of a declaration
+    let named =
+        |node: Node| is_declaration_kind(node.kind()) && declared_name(node, bytes).is_some();
+    let mut covering: Option<Node> = None;
+    let mut inside: Option<Node> = None;
+    walk(tree.root_node(), &mut |node| {
+        let (start, end) = (node.start_position().row + 1, node.end_position().row + 1);
+        if !named(node) {
+            return;
+        }
+        if start <= lines.0 && end >= lines.1 {
+            covering = Some(node);
+        } else if inside.is_none() && start >= lines.0 && end <= lines.1 {
+            inside = Some(node);
+        }
+    });
+    let block = covering.or(inside);
+
+    let mut called = HashSet::new();
+    let scope = block.unwrap_or_else(|| tree.root_node());
+    walk(scope, &mut |node| {
+        let row = node.start_position().row + 1;
+        if CALL_KINDS.contains(&node.kind()) && row >= lines.0 && row <= lines.1 {
+            if let Some(name) = called_name(node, bytes) {
+                called.insert(name.to_string());
+            }
+        }
+    });
+
+    let Some(block) = block else {
+        return Some(BlockFacts {
+            symbol: None,
+            siblings: Vec::new(),
+            called,
+        });
+    };
+
+    let mut outer = block;
+    while let Some(parent) = outer.parent().filter(|p| WRAPPER_KINDS.contains(&p.kind())) {
+        outer = parent;
+    }
+    let mut siblings = Vec::new();
+    if let Some(container) = outer.parent() {
+        let mut cursor = container.walk();
+        for child in container.named_children(&mut cursor) {
+            let declaration = unwrapped(child);
+            if child == outer || !is_declaration_kind(declaration.kind()) {
+                continue;
+            }
+            if let Some(name) = declared_name(declaration, bytes) {
+                siblings.push(RelatedSymbol {
+                    name: name.to_string(),
+                    file_path: file_path.to_path_buf(),
+                    line: child.start_position().row + 1,
+                });
+            }
+        }
+    }
+    // Keep the nearest siblings, in file order
+    let block_line = block.start_position().row + 1;
+    siblings.sort_by_key(|sibling| sibling.line.abs_diff(block_line));
+    siblings.truncate(MAX_SUGGESTIONS);
+    siblings.sort_by_key(|sibling| sibling.line);
+
+    Some(BlockFacts {
+        symbol: declared_name(block, bytes).map(str::to_string),
+        siblings,
+        called,
+    })
+}
+
+/// Callers of `symbols` and definitions of `callees` found in one project file
+type FileMatches = (Vec<(String, RelatedSymbol)>, Vec<RelatedSymbol>);
+
+fn scan_project_file(
+    file_path: &Path,
+    symbols: &HashSet<String>,
+    callees: &HashSet<String>,
+) -> Option<FileMatches> {
+    let content = fs_access::read_to_string(file_path).ok()?;
+    let mentioned = symbols
+        .iter()
+        .chain(callees)
+        .any(|name| content.contains(name.as_str()));
+    if !mentioned {
+        return None;
+    }
+    let tree = parse(file_path, &content)?;
+    let bytes = content.as_bytes();
+
+    let mut callers = Vec::new();
+    let mut definitions = Vec::new();
+    walk(tree.root_node(), &mut |node| {
+        if CALL_KINDS.contains(&node.kind()) {
+            let Some(name) = called_name(node, bytes).filter(|name| symbols.contains(*name)) else {
+                return;
+            };
+            if let Some((caller, caller_name)) = enclosing_declaration(node, bytes) {
+                callers.push((
+                    name.to_string(),
+                    RelatedSymbol {
+                        name: caller_name.to_string(),
+                        file_path: file_path.to_path_buf(),
+                        line: caller.start_position().row + 1,
+                    },
+                ));
+            }
+        } else if is_declaration_kind(node.kind()) {
+            if let Some(name) = declared_name(node, bytes).filter(|name| callees.contains(*name)) {
+                definitions.push(RelatedSymbol {
+                    name: name.to_string(),
+                    file_path: file_path.to_path_buf(),
+                    line: node.start_position().row + 1,
+                });
+            }
+        }
+    });
+    Some((callers, definitions))
+}
+
+/// Helper function to sort suggestions with those in the block's file first, then cap them
+fn finish(mut symbols: Vec<RelatedSymbol>, file_path: &Path) -> Vec<RelatedSymbol> {
+    symbols.sort_by(|a, b| {
+        let a_local = same_file(&a.file_path, file_path);
+        let b_local = same_file(&b.file_path, file_path);
+        b_local
+            .cmp(&a_local)
+            .then_with(|| a.file_path.cmp(&b.file_path))
+            .then_with(|| a.line.cmp(&b.line))
+    });
+    symbols.dedup();
+    symbols.truncate(MAX_SUGGESTIONS);
+    symbols
+}
+
+/// Function to list the code related to each extracted block
+///
+/// Callers and callee definitions are looked up in the files below `root`, filtered
+/// like a search with the same ignores and test policy.
+pub fn suggest_related(
+    results: &[SearchResult],
+    root: &Path,
+    allow_tests: bool,
+    custom_ignores: &[String],
+) -> Result<Vec<RelatedCode>> {
+    let facts: Vec<Option<BlockFacts>> = results
+        .iter()
+        .map(|result| {
+            if result.node_type == "file" {
+                return None;
+            }
+            block_facts(&result.path(), result.lines)
+        })
+        .collect();
+
+    let symbols: HashSet<String> = facts
+        .iter()
+        .flatten()
+        .filter_map(|facts| facts.symbol.clone())
+        .collect();
+    let callees: HashSet<String> = facts
+        .iter()
+        .flatten()
+        .flat_map(|facts| facts.called.iter().cloned())
+        .collect();
+
+    let file_list = file_list_cache::get_file_list(
+        root,
+        allow_tests,
+        custom_ignores,
+        false,
+        file_list_cache::FileFilters::default(),
+    )?;
+    let (callers, definitions): (Vec<_>, Vec<_>) = if symbols.is_empty() && callees.is_empty() {
+        (Vec::new(), Vec::new())
+    } else {
+        file_list
+            .files
+            .par_iter()
+            .filter_map(|path| {
+                // Report paths the way they are given on the command line, without `./`
+                let path = path.strip_prefix(".").unwrap_or(path);
+                scan_project_file(path, &symbols, &callees)
+            })
+            .unzip()
+    };
+    let callers: Vec<(String, RelatedSymbol)> = callers.into_iter().flatten().collect();
+    let definitions: Vec<RelatedSymbol> = definitions.into_iter().flatten().collect();
+
+    let related = results
+        .iter()
+        .zip(facts)
+        .map(|(result, facts)| {
+            let path = result.path();
+            let facts = facts.unwrap_or(BlockFacts {
+                symbol: None,
+                siblings: Vec::new(),
+                called: HashSet::new(),
+            });
+            // The block itself is neither its own caller nor callee when it recurses
+            let in_block = |symbol: &RelatedSymbol| {
+                same_file(&symbol.file_path, &path)
+                    && symbol.line >= result.lines.0
+                    && symbol.line <= result.lines.1
+            };
+            let block_callers = callers
+                .iter()
+                .filter(|(called, _)| facts.symbol.as_ref() == Some(called))
+                .map(|(_, caller)| caller.clone())
+                .filter(|caller| !in_block(caller))
+                .collect();
+            // A callee defined in the block's own file hides same-named ones elsewhere
+            let mut block_callees: Vec<RelatedSymbol> = Vec::new();
+            for name in &facts.called {
+                let found: Vec<&RelatedSymbol> = definitions
+                    .iter()
+                    .filter(|d| &d.name == name && !in_block(d))
+                    .collect();
+                let local: Vec<&RelatedSymbol> = found
+                    .iter()
+                    .copied()
+                    .filter(|d| same_file(&d.file_path, &path))
+                    .collect();
+                let chosen = if local.is_empty() { found } else { local };
+                block_callees.extend(chosen.into_iter().cloned());
+            }
+            RelatedCode {
+                file_path: path.clone(),
+                lines: result.lines,
+                symbol: facts.symbol,
+                siblings: facts.siblings,
+                callers: finish(block_callers, &path),
+                callees: finish(block_callees, &path),
+            }
+        })
+        .collect();
+    Ok(related)
+}
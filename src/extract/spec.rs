@@ -0,0 +1,376 @@
+//! Extraction specs for `probe extract --spec`.
+//!
+//! A spec is a YAML (or JSON) file listing files, symbols and line ranges to extract,
+//! each with optional per-entry settings. The entries are extracted in the order they are
+//! listed and printed as one combined output:
+//!
+//! ```yaml
+//! context: 2          # defaults for every entry
+//! allow_tests: false
+//! entries:
+//!   - file: src/main.rs
+//!     symbol: main
+//!   - file: src/lib.rs
+//!     lines: 10-40
+//!     context: 0
+//!   - src/util.rs:120   # the same syntax as a command-line file argument
+//! ```
+//!
+//! A bare list of entries is accepted as well.
+
+use crate::extract::file_paths::{parse_file_with_line, FilePathInfo};
+use crate::fs_access;
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use serde_json::{Map, Number, Value};
+use std::path::Path;
+use tree_sitter::{Node, Parser};
+
+/// Top-level structure of a spec file
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExtractSpec {
+    /// Context lines for entries that do not set their own
+    #[serde(default)]
+    pub context: Option<usize>,
+    /// Whether test files and test code blocks are allowed, unless an entry says otherwise
+    #[serde(default)]
+    pub allow_tests: Option<bool>,
+    /// Entries to extract, in output order
+    #[serde(default)]
+    pub entries: Vec<SpecEntry>,
+}
+
+/// One file, symbol or line range to extract
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpecEntry {
+    /// File path or glob, optionally with `:line`, `:start-end` or `#symbol`
+    pub file: String,
+    /// Symbol to extract from the file
+    #[serde(default)]
+    pub symbol: Option<String>,
+    /// A single line (`42`), a range (`"10-40"`) or a `[start, end]` pair
+    #[serde(default)]
+    pub lines: Option<LineSpec>,
+    /// Context lines for this entry
+    #[serde(default)]
+    pub context: Option<usize>,
+    /// Whether test files and test code blocks are allowed for this entry
+    #[serde(default)]
+    pub allow_tests: Option<bool>,
+}
+
+/// Lines selected by a spec entry
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum LineSpec {
+    Single(usize),
+    Pair([usize; 2]),
+    Range(String),
+}
+
+impl LineSpec {
+    /// Start and (for ranges) end line
+    fn bounds(&self) -> Result<(usize, Option<usize>)> {
+        let (start, end) = match self {
+            LineSpec::Single(line) => (*line, None),
+            LineSpec::Pair([start, end]) => (*start, Some(*end)),
+            LineSpec::Range(text) => {
+                let parse = |s: &str| {
+                    s.trim()
+                        .parse::<usize>()
+                        .map_err(|_| anyhow!("invalid line range '{}'", text))
+                };
+                match text.split_once('-') {
+                    Some((start, end)) => (parse(start)?, Some(parse(end)?)),
+                    None => (parse(text)?, None),
+                }
+            }
+        };
+        if start == 0 || end.is_some_and(|end| end < start) {
+            return Err(anyhow!("invalid line range {:?}", self));
+        }
+        Ok((start, end))
+    }
+}
+
+/// A resolved file spec together with the settings of the entry it came from
+#[derive(Debug)]
+pub struct SpecItem {
+    pub file_path: FilePathInfo,
+    pub context_lines: usize,
+    pub allow_tests: bool,
+}
+
+/// Parse the text of a spec file
+pub fn parse_spec(text: &str) -> Result<ExtractSpec> {
+    let mut value = yaml_to_json(text)?;
+
+    // A bare list is the list of entries
+    if value.is_array() {
+        let mut map = Map::new();
+        map.insert("entries".to_string(), value);
+        value = Value::Object(map);
+    }
+    if value.is_null() {
+        return Ok(ExtractSpec::default());
+    }
+
+    // Entries given as plain strings use the command-line `file:line` / `file#symbol` syntax
+    if let Some(Value::Array(entries)) = value.get_mut("entries") {
+        for entry in entries.iter_mut() {
+            if let Value::String(file) = entry {
+                let mut map = Map::new();
+                map.insert("file".to_string(), Value::String(std::mem::take(file)));
+                *entry = Value::Object(map);
+            }
+        }
+    }
+
+    serde_json::from_value(value).context("Invalid extract spec")
+}
+
+/// Read a spec file and resolve its entries, in order
+///
+/// `default_context` and `default_allow_tests` come from the command line and apply when
+/// neither the spec nor the entry sets a value. Entries that match no file are returned
+/// by their `file` value in the second element.
+pub fn load_spec(
+    path: &Path,
+    default_context: usize,
+    default_allow_tests: bool,
+) -> Result<(Vec<SpecItem>, Vec<String>)> {
+    let text = fs_access::read_to_string(path)
+        .with_context(|| format!("Failed to read extract spec {:?}", path))?;
+    let spec = parse_spec(&text).with_context(|| format!("In extract spec {:?}", path))?;
+
+    let mut items = Vec::new();
+    let mut unmatched = Vec::new();
+    for (index, entry) in spec.entries.iter().enumerate() {
+        let context_lines = entry.context.or(spec.context).unwrap_or(default_context);
+        let allow_tests = entry
+            .allow_tests
+            .or(spec.allow_tests)
+            .unwrap_or(default_allow_tests);
+        let bounds = entry
+            .lines
+            .as_ref()
+            .map(LineSpec::bounds)
+            .transpose()
+            .with_context(|| format!("In entry {} ({}) of {:?}", index + 1, entry.file, path))?;
+
+        let mut paths = parse_file_with_line(&entry.file, allow_tests);
+        paths.retain(|(file, ..)| file.exists());
+        if paths.is_empty() {
+            unmatched.push(entry.file.clone());
+        }
+
+        for (file, mut start, mut end, mut symbol, mut specific_lines) in paths {
+            if let Some((first, last)) = bounds {
+                start = Some(first);
+                end = last;
+                symbol = None;
+                specific_lines = None;
+            }
+            if let Some(name) = &entry.symbol {
+                symbol = Some(name.clone());
+                start = None;
+                end = None;
+                specific_lines = None;
+            }
+            items.push(SpecItem {
+                file_path: (file, start, end, symbol, specific_lines),
+                context_lines,
+                allow_tests,
+            });
+        }
+    }
+
+    Ok((items, unmatched))
+}
+
+/// Convert a YAML document into a JSON value
+///
+/// Covers the subset of YAML a spec needs: block and flow collections, plain and quoted
+/// scalars and literal block scalars. Anchors and tags are ignored; aliases are rejected.
+fn yaml_to_json(text: &str) -> Result<Value> {
+    let mut parser = Parser::new();
+    parser.set_language(&tree_sitter_yaml::LANGUAGE.into())?;
+    let tree = parser
+        .parse(text, None)
+        .ok_or_else(|| anyhow!("Failed to parse YAML"))?;
+    let root = tree.root_node();
+
+    if root.has_error() {
+        let line = first_error_line(root).unwrap_or(1);
+        return Err(anyhow!("Invalid YAML near line {}", line));
+    }
+
+    let mut cursor = root.walk();
+    let document = root
+        .named_children(&mut cursor)
+        .find(|node| node.kind() == "document");
+    match document.and_then(|document| content_child(document)) {
+        Some(node) => node_to_json(node, text),
+        None => Ok(Value::Null),
+    }
+}
+
+fn first_error_line(node: Node) -> Option<usize> {
+    if node.is_error() || node.is_missing() {
+        return Some(node.start_position().row + 1);
+    }
+    let mut cursor = node.walk();
+    let children: Vec<Node> = node.children(&mut cursor).collect();
+    children.into_iter().find_map(first_error_line)
+}
+
+/// The value-carrying child of a node, skipping comments, anchors and tags
+fn content_child(node: Node) -> Option<Node> {
+    let mut cursor = node.walk();
+    let child = node
+        .named_children(&mut cursor)
+        .find(|child| !matches!(child.kind(), "comment" | "anchor" | "tag"));
+    child
+}
+
+fn node_to_json(node: Node, source: &str) -> Result<Value> {
+    let text = &source[node.byte_range()];
+    match node.kind() {
+        "block_node" | "flow_node" | "plain_scalar" => match content_child(node) {
+            Some(child) => node_to_json(child, source),
+            None => Ok(Value::Null),
+        },
+        "block_mapping" | "flow_mapping" => {
+            let mut map = Map::new();
+            let mut cursor = node.walk();
+            for pair in node.named_children(&mut cursor) {
+                let (key, value) = match pair.kind() {
+                    "block_mapping_pair" | "flow_pair" => (
+                        pair.child_by_field_name("key"),
+                        pair.child_by_field_name("value"),
+                    ),
+                    // `{ a }` is a key without a value
+                    "flow_node" => (Some(pair), None),
+                    _ => continue,
+                };
+                let key = match key {
+                    Some(key) => match node_to_json(key, source)? {
+                        Value::String(s) => s,
+                        Value::Null => String::new(),
+                        other => other.to_string(),
+                    },
+                    None => String::new(),
+                };
+                let value = match value {
+                    Some(value) => node_to_json(value, source)?,
+                    None => Value::Null,
+                };
+                map.insert(key, value);
+            }
+            Ok(Value::Object(map))
+        }
+        "block_sequence" | "flow_sequence" => {
+            let mut items = Vec::new();
+            let mut cursor = node.walk();
+            for item in node.named_children(&mut cursor) {
+                match item.kind() {
+                    "block_sequence_item" => items.push(match content_child(item) {
+                        Some(child) => node_to_json(child, source)?,
+                        None => Value::Null,
+                    }),
+                    "comment" => {}
+                    _ => items.push(node_to_json(item, source)?),
+                }
+            }
+            Ok(Value::Array(items))
+        }
+        "flow_pair" => {
+            // A single-pair mapping inside a flow sequence, e.g. `[a: 1]`
+            let mut map = Map::new();
+            let key = match node.child_by_field_name("key") {
+                Some(key) => node_to_json(key, source)?,
+                None => Value::Null,
+            };
+            let value = match node.child_by_field_name("value") {
+                Some(value) => node_to_json(value, source)?,
+                None => Value::Null,
+            };
+            let key = match key {
+                Value::String(s) => s,
+                other => other.to_string(),
+            };
+            map.insert(key, value);
+            Ok(Value::Object(map))
+        }
+        "null_scalar" => Ok(Value::Null),
+        "boolean_scalar" => Ok(Value::Bool(matches!(text, "true" | "True" | "TRUE"))),
+        "integer_scalar" => Ok(text
+            .parse::<i64>()
+            .map(Value::from)
+            .unwrap_or_else(|_| Value::String(text.to_string()))),
+        "float_scalar" => Ok(text
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number)
+            .unwrap_or_else(|| Value::String(text.to_string()))),
+        "string_scalar" | "timestamp_scalar" => Ok(Value::String(text.to_string())),
+        "double_quote_scalar" => Ok(Value::String(unescape_double_quoted(
+            &text[1..text.len() - 1],
+        ))),
+        "single_quote_scalar" => Ok(Value::String(text[1..text.len() - 1].replace("''", "'"))),
+        "block_scalar" => Ok(Value::String(block_scalar_text(text))),
+        "alias" => Err(anyhow!(
+            "YAML aliases are not supported (line {})",
+            node.start_position().row + 1
+        )),
+        kind => Err(anyhow!(
+            "Unsupported YAML node '{}' at line {}",
+            kind,
+            node.start_position().row + 1
+        )),
+    }
+}
+
+fn unescape_double_quoted(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('0') => result.push('\0'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// Text of a `|` or `>` block scalar, with the common indentation removed
+fn block_scalar_text(text: &str) -> String {
+    let mut lines = text.lines();
+    let folded = lines.next().is_some_and(|header| header.starts_with('>'));
+    let body: Vec<&str> = lines.collect();
+    let indent = body
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let body: Vec<&str> = body
+        .iter()
+        .map(|line| line.get(indent..).unwrap_or(""))
+        .collect();
+    let separator = if folded { " " } else { "\n" };
+    let mut result = body.join(separator);
+    result.push('\n');
+    result
+}
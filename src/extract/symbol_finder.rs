@@ -3,7 +3,8 @@
 //! This module provides functions for finding symbols (functions, structs, classes, etc.)
 //! in files using tree-sitter.
 
-use crate::models::SearchResult;
+use crate::language::deprecation::is_deprecated;
+use crate::models::{ExtractionConfidence, SearchResult};
 use anyhow::Result;
 use std::path::Path;
 
@@ -238,6 +239,7 @@ pub fn find_symbol_in_file(
 
         return Ok(SearchResult {
             file: path.to_string_lossy().to_string(),
+            file_path: Some(path.to_path_buf()),
             lines: (node_start_line, node_end_line),
             node_type: found_node.kind().to_string(),
             code: node_text_str,
@@ -260,6 +262,24 @@ pub fn find_symbol_in_file(
             block_id: None,
             matched_keywords: None,
             tokenized_content: Some(tokenized_content),
+            merged_from: None,
+            normalized_score: None,
+            usage_kind: None,
+            via_symlink: false,
+            changed_lines: None,
+            symbol_name: None,
+            language: None,
+            extraction_confidence: Some(ExtractionConfidence::Ast),
+            partial_parse: false,
+            deprecated: is_deprecated(
+                &content.lines().collect::<Vec<_>>(),
+                node_start_line,
+                node_end_line,
+            ),
+            embedded: None,
+            kind: None,
+            complexity: None,
+            content_hash: None,
         });
     }
 
@@ -335,6 +355,7 @@ pub fn find_symbol_in_file(
 
         return Ok(SearchResult {
             file: path.to_string_lossy().to_string(),
+            file_path: Some(path.to_path_buf()),
             lines: (start_line, end_line),
             node_type: "text_search".to_string(),
             code: context,
@@ -357,6 +378,20 @@ pub fn find_symbol_in_file(
             block_id: None,
             matched_keywords: None,
             tokenized_content: Some(tokenized_content),
+            merged_from: None,
+            normalized_score: None,
+            usage_kind: None,
+            via_symlink: false,
+            changed_lines: None,
+            symbol_name: None,
+            language: None,
+            extraction_confidence: Some(ExtractionConfidence::Heuristic),
+            partial_parse: false,
+            deprecated: is_deprecated(&lines, start_line, end_line),
+            embedded: None,
+            kind: None,
+            complexity: None,
+            content_hash: None,
         });
     }
 
@@ -0,0 +1,694 @@
+//! Access to the file system for reading sources and writing caches.
+//!
+//! Modules read and write files through the [`FileSystem`] installed here rather
+//! than calling `std::fs` directly, so the engine can run against something other
+//! than the disk: [`MemoryFs`] holds test fixtures in memory, and [`ReadOnlyFs`]
+//! backs `--read-only`, refusing every write outside the cache directories.
+//! [`OverlayFs`] backs `--overlay`, serving unsaved editor buffers in place of
+//! the files on disk.
+//! [`with_file_system`] swaps it for a single search, which is how
+//! [`ProbeEngine::from_memory`](crate::search::ProbeEngine::from_memory) searches
+//! buffers. Directory walking still reads the disk, since the ignore crate lists
+//! directories itself; smaller lookups such as finding a README go through
+//! [`read_dir`] and [`is_file`]. Large files on disk can be mapped into memory with
+//! [`read_text`] rather than read.
+
+use memmap2::Mmap;
+use once_cell::sync::Lazy;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
+
+/// Environment variable that turns on read-only mode, like `--read-only`
+pub const READ_ONLY_ENV: &str = "PROBE_READ_ONLY";
+
+/// Files on disk at least this large are mapped into memory by [`read_text`]
+pub const MMAP_THRESHOLD: u64 = 4 * 1024 * 1024;
+
+/// The file operations probe performs
+pub trait FileSystem: Send + Sync {
+    /// Read a whole file as UTF-8
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+
+    /// Whether a file or directory exists at `path`
+    fn exists(&self, path: &Path) -> bool;
+
+    /// Whether a file, rather than a directory, exists at `path`
+    fn is_file(&self, path: &Path) -> bool;
+
+    /// Paths of the entries directly inside the directory `path`, in no particular order
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// Create the directory `path` along with any missing parents
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    /// Create or truncate the file at `path` and write `contents` to it
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+
+    /// Append `contents` to the file at `path`, creating it if needed
+    fn append(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+
+    /// Rename `from` to `to`, replacing any file already at `to`
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    /// Remove the file at `path`
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    /// Check that `path` may be written before opening it on disk directly
+    ///
+    /// Streamed output files and lock files need a real file handle, so they
+    /// can't go through [`FileSystem::write`] and ask here first instead.
+    fn check_write(&self, _path: &Path) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Map the file at `path` into memory instead of reading it
+    ///
+    /// Only the disk can; other file systems return `None`, as does the disk for files
+    /// below [`MMAP_THRESHOLD`], which are cheaper to read.
+    fn map(&self, _path: &Path) -> Option<io::Result<Mmap>> {
+        None
+    }
+
+    /// Modification time and length of the file at `path`, to tell whether it changed
+    ///
+    /// Only the disk keeps them; other file systems return `None`, so nothing recorded
+    /// about a file on disk is trusted for theirs.
+    fn stamp(&self, _path: &Path) -> Option<io::Result<(SystemTime, u64)>> {
+        None
+    }
+}
+
+impl<F: FileSystem + ?Sized> FileSystem for Arc<F> {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        (**self).read_to_string(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        (**self).exists(path)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        (**self).is_file(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        (**self).read_dir(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        (**self).create_dir_all(path)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        (**self).write(path, contents)
+    }
+
+    fn append(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        (**self).append(path, contents)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        (**self).rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        (**self).remove_file(path)
+    }
+
+    fn check_write(&self, path: &Path) -> io::Result<()> {
+        (**self).check_write(path)
+    }
+
+    fn map(&self, path: &Path) -> Option<io::Result<Mmap>> {
+        (**self).map(path)
+    }
+
+    fn stamp(&self, path: &Path) -> Option<io::Result<(SystemTime, u64)>> {
+        (**self).stamp(path)
+    }
+}
+
+/// The disk, through `std::fs`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RealFs;
+
+impl FileSystem for RealFs {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect()
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(contents)?;
+        file.sync_all()
+    }
+
+    fn append(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(contents)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn map(&self, path: &Path) -> Option<io::Result<Mmap>> {
+        match fs::metadata(path) {
+            Ok(metadata) if metadata.len() >= MMAP_THRESHOLD => {}
+            Ok(_) => return None,
+            Err(e) => return Some(Err(e)),
+        }
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) => return Some(Err(e)),
+        };
+        // SAFETY: the map is only read. A file truncated by another process while it is
+        // mapped can still fault, the risk every searcher mapping files takes.
+        Some(unsafe { Mmap::map(&file) })
+    }
+
+    fn stamp(&self, path: &Path) -> Option<io::Result<(SystemTime, u64)>> {
+        Some(fs::metadata(path).and_then(|metadata| Ok((metadata.modified()?, metadata.len()))))
+    }
+}
+
+/// Files and directories kept in memory, for tests and for searching buffers that
+/// aren't on disk
+///
+/// `.` components are ignored, so `./src/lib.rs` and `src/lib.rs` are the same file.
+#[allow(dead_code)]
+#[derive(Debug, Default)]
+pub struct MemoryFs {
+    files: Mutex<BTreeMap<PathBuf, Vec<u8>>>,
+    dirs: Mutex<BTreeSet<PathBuf>>,
+}
+
+#[allow(dead_code)]
+impl MemoryFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file holding `contents`
+    pub fn with_file(self, path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) -> Self {
+        self.files
+            .lock()
+            .unwrap()
+            .insert(Self::key(path.as_ref()), contents.into());
+        self
+    }
+
+    /// Contents of the file at `path`, if there is one
+    pub fn contents(&self, path: &Path) -> Option<Vec<u8>> {
+        self.files.lock().unwrap().get(&Self::key(path)).cloned()
+    }
+
+    fn key(path: &Path) -> PathBuf {
+        path.components()
+            .filter(|component| *component != Component::CurDir)
+            .collect()
+    }
+
+    fn not_found(path: &Path) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} not found in memory file system", path.display()),
+        )
+    }
+}
+
+impl FileSystem for MemoryFs {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        let contents = self.contents(path).ok_or_else(|| Self::not_found(path))?;
+        String::from_utf8(contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        let key = Self::key(path);
+        self.dirs.lock().unwrap().contains(&key)
+            || self
+                .files
+                .lock()
+                .unwrap()
+                .keys()
+                .any(|file| file.starts_with(&key))
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.files.lock().unwrap().contains_key(&Self::key(path))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        if !self.exists(path) {
+            return Err(Self::not_found(path));
+        }
+        let key = Self::key(path);
+        let files = self.files.lock().unwrap();
+        let dirs = self.dirs.lock().unwrap();
+        let entries: BTreeSet<PathBuf> = files
+            .keys()
+            .chain(dirs.iter())
+            .filter_map(|entry| {
+                let name = entry.strip_prefix(&key).ok()?.components().next()?;
+                Some(path.join(name))
+            })
+            .collect();
+        Ok(entries.into_iter().collect())
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let key = Self::key(path);
+        let mut dirs = self.dirs.lock().unwrap();
+        dirs.extend(key.ancestors().map(Path::to_path_buf));
+        Ok(())
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.files
+            .lock()
+            .unwrap()
+            .insert(Self::key(path), contents.to_vec());
+        Ok(())
+    }
+
+    fn append(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.files
+            .lock()
+            .unwrap()
+            .entry(Self::key(path))
+            .or_default()
+            .extend_from_slice(contents);
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut files = self.files.lock().unwrap();
+        let contents = files
+            .remove(&Self::key(from))
+            .ok_or_else(|| Self::not_found(from))?;
+        files.insert(Self::key(to), contents);
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.files
+            .lock()
+            .unwrap()
+            .remove(&Self::key(path))
+            .map(|_| ())
+            .ok_or_else(|| Self::not_found(path))
+    }
+}
+
+/// A wrapper around another file system that refuses writes outside a set of
+/// cache directories
+///
+/// Refused writes fail with [`io::ErrorKind::PermissionDenied`] and are kept, so
+/// an audit can list every write that was attempted.
+pub struct ReadOnlyFs<F: FileSystem> {
+    inner: F,
+    writable: Vec<PathBuf>,
+    refused: Mutex<Vec<PathBuf>>,
+}
+
+impl<F: FileSystem> ReadOnlyFs<F> {
+    /// Wrap `inner`, allowing writes only below the `writable` directories
+    pub fn new(inner: F, writable: Vec<PathBuf>) -> Self {
+        Self {
+            inner,
+            writable,
+            refused: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Paths that writes were refused for, in order
+    pub fn refused_writes(&self) -> Vec<PathBuf> {
+        self.refused.lock().unwrap().clone()
+    }
+
+    fn allow(&self, path: &Path) -> io::Result<()> {
+        // Symbolic links and `..` could lead out of a cache directory the path starts with,
+        // so both sides are compared as they resolve on disk
+        let resolved = canonical(path);
+        let climbs = resolved.components().any(|c| c == Component::ParentDir);
+        if !climbs
+            && self
+                .writable
+                .iter()
+                .any(|dir| resolved.starts_with(canonical(dir)))
+        {
+            return Ok(());
+        }
+        self.refused.lock().unwrap().push(path.to_path_buf());
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!(
+                "Read-only mode: refusing to write {} outside the cache directory",
+                path.display()
+            ),
+        ))
+    }
+}
+
+impl<F: FileSystem> FileSystem for ReadOnlyFs<F> {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        self.inner.read_to_string(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.inner.exists(path)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.inner.is_file(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        self.inner.read_dir(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.allow(path)?;
+        self.inner.create_dir_all(path)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.allow(path)?;
+        self.inner.write(path, contents)
+    }
+
+    fn append(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.allow(path)?;
+        self.inner.append(path, contents)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.allow(from)?;
+        self.allow(to)?;
+        self.inner.rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.allow(path)?;
+        self.inner.remove_file(path)
+    }
+
+    fn check_write(&self, path: &Path) -> io::Result<()> {
+        self.allow(path)?;
+        self.inner.check_write(path)
+    }
+
+    fn map(&self, path: &Path) -> Option<io::Result<Mmap>> {
+        self.inner.map(path)
+    }
+
+    fn stamp(&self, path: &Path) -> Option<io::Result<(SystemTime, u64)>> {
+        self.inner.stamp(path)
+    }
+}
+
+/// A wrapper around another file system that reads some files from memory instead
+///
+/// Editors pass their unsaved buffers this way, so a search sees what the user
+/// sees. Paths are compared after making them absolute against the current
+/// directory, without following symbolic links. Writes go to `inner` unchanged.
+pub struct OverlayFs<F: FileSystem> {
+    inner: F,
+    buffers: HashMap<PathBuf, String>,
+}
+
+impl<F: FileSystem> OverlayFs<F> {
+    /// Wrap `inner`, reading each path in `buffers` from its contents there
+    pub fn new(inner: F, buffers: impl IntoIterator<Item = (PathBuf, String)>) -> Self {
+        let buffers = buffers
+            .into_iter()
+            .map(|(path, contents)| (absolute(&path), contents))
+            .collect();
+        Self { inner, buffers }
+    }
+
+    fn buffer(&self, path: &Path) -> Option<&String> {
+        if self.buffers.is_empty() {
+            return None;
+        }
+        self.buffers.get(&absolute(path))
+    }
+}
+
+impl<F: FileSystem> FileSystem for OverlayFs<F> {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        match self.buffer(path) {
+            Some(contents) => Ok(contents.clone()),
+            None => self.inner.read_to_string(path),
+        }
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.buffer(path).is_some() || self.inner.exists(path)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.buffer(path).is_some() || self.inner.is_file(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        self.inner.read_dir(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.inner.create_dir_all(path)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.inner.write(path, contents)
+    }
+
+    fn append(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.inner.append(path, contents)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.inner.rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.inner.remove_file(path)
+    }
+
+    fn check_write(&self, path: &Path) -> io::Result<()> {
+        self.inner.check_write(path)
+    }
+
+    fn map(&self, path: &Path) -> Option<io::Result<Mmap>> {
+        match self.buffer(path) {
+            Some(_) => None,
+            None => self.inner.map(path),
+        }
+    }
+
+    fn stamp(&self, path: &Path) -> Option<io::Result<(SystemTime, u64)>> {
+        match self.buffer(path) {
+            Some(_) => None,
+            None => self.inner.stamp(path),
+        }
+    }
+}
+
+/// Function to parse an `--overlay` value into the buffers it holds
+///
+/// The value is a JSON object mapping paths to their contents, given either inline
+/// or as the path of a file holding it, which suits buffers too large for the
+/// command line.
+pub fn parse_overlay(value: &str) -> anyhow::Result<Vec<(PathBuf, String)>> {
+    use anyhow::Context;
+
+    let json = if value.trim_start().starts_with('{') {
+        value.to_string()
+    } else {
+        read_to_string(value).with_context(|| format!("Failed to read overlay file {value}"))?
+    };
+    let buffers: BTreeMap<PathBuf, String> = serde_json::from_str(&json)
+        .context("Overlay must be a JSON object mapping file paths to their contents")?;
+    Ok(buffers.into_iter().collect())
+}
+
+/// `path` made absolute with its longest existing ancestor canonicalized
+///
+/// Writes usually create the file, and often its directory, so the part of the path that
+/// doesn't exist yet is kept as written; a `..` left in it can't be resolved safely.
+fn canonical(path: &Path) -> PathBuf {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map(|dir| dir.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    };
+    for ancestor in path.ancestors() {
+        if let Ok(resolved) = fs::canonicalize(ancestor) {
+            return match path.strip_prefix(ancestor) {
+                Ok(rest) if !rest.as_os_str().is_empty() => resolved.join(rest),
+                _ => resolved,
+            };
+        }
+    }
+    path
+}
+
+/// `path` made absolute against the current directory, with `.` and `..` resolved
+/// without touching the disk
+fn absolute(path: &Path) -> PathBuf {
+    let joined = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map(|dir| dir.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    };
+    let mut resolved = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
+static CURRENT: Lazy<RwLock<Arc<dyn FileSystem>>> = Lazy::new(|| RwLock::new(Arc::new(RealFs)));
+
+thread_local! {
+    static SCOPED: RefCell<Option<Arc<dyn FileSystem>>> = const { RefCell::new(None) };
+}
+
+/// Function to get the file system every module reads and writes through
+///
+/// That is the one set by [`with_file_system`] on this thread, if any, and the
+/// process-wide one otherwise.
+pub fn current() -> Arc<dyn FileSystem> {
+    SCOPED
+        .with(|scoped| scoped.borrow().clone())
+        .unwrap_or_else(|| CURRENT.read().unwrap().clone())
+}
+
+/// Function to replace the file system for the whole process
+pub fn install(file_system: Arc<dyn FileSystem>) {
+    *CURRENT.write().unwrap() = file_system;
+}
+
+/// Function to run `f` with `file_system` as the file system of this thread
+///
+/// Other threads keep theirs, so one search can read from memory while others
+/// read the disk. Stages that fan out to rayon threads pass [`current`] on to
+/// them. The previous file system is restored afterwards.
+pub fn with_file_system<T>(file_system: Arc<dyn FileSystem>, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<Arc<dyn FileSystem>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            SCOPED.with(|scoped| *scoped.borrow_mut() = previous);
+        }
+    }
+
+    let previous = SCOPED.with(|scoped| scoped.borrow_mut().replace(file_system));
+    let _restore = Restore(previous);
+    f()
+}
+
+/// Function to read a whole file through the current file system
+pub fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+    current().read_to_string(path.as_ref())
+}
+
+/// The text of a file, read into memory or mapped from disk
+pub enum FileText {
+    Read(String),
+    /// A map of the file, whose bytes another process may still change while it is read
+    Mapped(Mmap),
+}
+
+impl FileText {
+    /// Iterate over the lines of the text, as [`str::lines`] does
+    ///
+    /// The bytes of a map are checked to be UTF-8 line by line as they are read rather
+    /// than trusted from when it was made, since the file under it can change mid-search.
+    /// A line that no longer is UTF-8 is an `InvalidData` error.
+    pub fn lines(&self) -> Box<dyn Iterator<Item = io::Result<&str>> + '_> {
+        match self {
+            FileText::Read(text) => Box::new(text.lines().map(Ok)),
+            FileText::Mapped(map) => Box::new(map.split_inclusive(|&b| b == b'\n').map(|line| {
+                let line = line
+                    .strip_suffix(b"\r\n")
+                    .or_else(|| line.strip_suffix(b"\n"))
+                    .unwrap_or(line);
+                std::str::from_utf8(line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            })),
+        }
+    }
+}
+
+/// Function to get the text of a file through the current file system
+///
+/// Large files on disk are mapped rather than read, so scanning them does not copy them
+/// onto the heap first. As with [`read_to_string`], text that is not UTF-8 is an
+/// `InvalidData` error.
+pub fn read_text(path: impl AsRef<Path>) -> io::Result<FileText> {
+    let path = path.as_ref();
+    let file_system = current();
+    match file_system.map(path) {
+        Some(map) => {
+            let map = map?;
+            std::str::from_utf8(&map).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            Ok(FileText::Mapped(map))
+        }
+        None => file_system.read_to_string(path).map(FileText::Read),
+    }
+}
+
+/// Function to check whether a path exists on the current file system
+pub fn exists(path: impl AsRef<Path>) -> bool {
+    current().exists(path.as_ref())
+}
+
+/// Function to check whether a file exists at a path on the current file system
+pub fn is_file(path: impl AsRef<Path>) -> bool {
+    current().is_file(path.as_ref())
+}
+
+/// Function to list the entries of a directory on the current file system
+pub fn read_dir(path: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    current().read_dir(path.as_ref())
+}
@@ -0,0 +1,620 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tree_sitter::{Node, Parser as TSParser};
+
+use crate::fs_access;
+use crate::impls::{
+    bare_name, go_receiver_type, heritage_text, node_text, strip_generics, walk, DECLARATION_KINDS,
+};
+use crate::language::factory::get_language_impl;
+use crate::path_encoding::encode_path;
+use crate::search::file_list_cache;
+
+/// How a type is related to its supertype
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Relation {
+    // A Rust `impl Trait for Type` block
+    Impl,
+    // The declaration lists the supertype (`extends`, `implements`, `: Base`,
+    // `class Foo(Base)`, Rust supertraits)
+    Extends,
+    // A Go struct or interface embeds the supertype
+    Embeds,
+    // A Go type has methods with the names of all of the interface's methods
+    MethodSet,
+}
+
+impl Relation {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Relation::Impl => "impl",
+            Relation::Extends => "extends",
+            Relation::Embeds => "embeds",
+            Relation::MethodSet => "method_set",
+        }
+    }
+}
+
+impl fmt::Display for Relation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A place in the code: a type declaration or the block declaring a relationship
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Location {
+    pub file_path: PathBuf,
+    pub line_start: usize,
+    pub line_end: usize,
+}
+
+/// A type in the hierarchy, with its own supertypes or subtypes below it
+#[derive(Debug, Clone)]
+pub struct HierarchyNode {
+    pub name: String,
+    pub relation: Relation,
+    /// For subtypes, the block declaring the relationship; for supertypes, the declaration
+    /// of the supertype when it is in the searched code
+    pub location: Option<Location>,
+    pub children: Vec<HierarchyNode>,
+}
+
+/// The supertype and subtype trees of a type
+#[derive(Debug, Clone)]
+pub struct Hierarchy {
+    pub name: String,
+    pub definition: Option<Location>,
+    pub supertypes: Vec<HierarchyNode>,
+    pub subtypes: Vec<HierarchyNode>,
+}
+
+/// Options for building a type hierarchy
+pub struct HierarchyOptions<'a> {
+    pub path: &'a Path,
+    pub type_name: &'a str,
+    pub ignore: &'a [String],
+    pub allow_tests: bool,
+    pub max_depth: Option<usize>,
+}
+
+/// A subtype relationship between two types, by name
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Edge {
+    child: String,
+    parent: String,
+    relation: Relation,
+    location: Location,
+}
+
+/// Everything one file contributes to the hierarchy
+#[derive(Default)]
+struct FileFacts {
+    edges: Vec<Edge>,
+    definitions: Vec<(String, Location)>,
+    // Go interfaces and their method names
+    go_interfaces: Vec<(String, HashSet<String>)>,
+    // Go method names by receiver type
+    go_methods: HashMap<String, HashSet<String>>,
+}
+
+/// Rust, Go and other declaration kinds that define a named type
+const RUST_GO_DEFINITION_KINDS: &[&str] = &[
+    "struct_item",
+    "enum_item",
+    "union_item",
+    "trait_item",
+    "type_spec",
+];
+
+/// Words in a declaration header that are not supertype names
+const HERITAGE_KEYWORDS: &[&str] = &[
+    "extends",
+    "implements",
+    "where",
+    "public",
+    "protected",
+    "private",
+    "internal",
+    "virtual",
+    "open",
+    "class",
+];
+
+fn location(file_path: &Path, node: Node) -> Location {
+    Location {
+        file_path: file_path.to_path_buf(),
+        line_start: node.start_position().row + 1,
+        line_end: node.end_position().row + 1,
+    }
+}
+
+/// Helper function to get the supertype names listed in a declaration header
+///
+/// Handles `extends A implements B, C` (Java, TypeScript), `: A, B` (C#, Kotlin, Swift),
+/// `(A, B)` (Python) and `< A` (Ruby). Generic arguments, constructor arguments and
+/// keyword arguments such as `metaclass=ABCMeta` are left out.
+fn supertype_names(heritage: &str) -> Vec<String> {
+    let heritage = strip_generics(heritage);
+    let mut heritage = heritage.trim();
+    // Python bases are the header's parenthesized list
+    if let Some(bases) = heritage
+        .strip_prefix('(')
+        .and_then(|rest| rest.rsplit_once(')'))
+    {
+        heritage = bases.0;
+    }
+    // Drop constructor arguments such as Kotlin's `: Base(name)`
+    let mut depth = 0usize;
+    let names: String = heritage
+        .chars()
+        .filter(|c| {
+            match c {
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                _ => return depth == 0,
+            }
+            false
+        })
+        .collect();
+
+    let mut supertypes: Vec<String> = Vec::new();
+    for part in names.split([',', ' ', '\t', '\n', ':', '<', '{', '+', '&']) {
+        let part = part.trim();
+        if part.is_empty() || part.contains('=') || HERITAGE_KEYWORDS.contains(&part) {
+            continue;
+        }
+        let name = bare_name(part);
+        let valid = name.chars().next().is_some_and(|c| c.is_alphabetic())
+            && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+        if valid && !supertypes.iter().any(|known| known == name) {
+            supertypes.push(name.to_string());
+        }
+    }
+    supertypes
+}
+
+/// Collect the types defined in one file and the relationships between them
+fn scan_file(file_path: &Path, allow_tests: bool) -> Result<FileFacts> {
+    let mut facts = FileFacts::default();
+
+    let extension = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let Some(language_impl) = get_language_impl(extension) else {
+        return Ok(facts);
+    };
+
+    let content = fs_access::read_to_string(file_path)?;
+    let mut parser = TSParser::new();
+    parser.set_language(&language_impl.get_tree_sitter_language())?;
+    let Some(tree) = parser.parse(&content, None) else {
+        return Ok(facts);
+    };
+
+    let bytes = content.as_bytes();
+    walk(tree.root_node(), &mut |node| {
+        let kind = node.kind();
+        if !allow_tests
+            && (kind == "impl_item" || DECLARATION_KINDS.contains(&kind))
+            && language_impl.is_test_node(&node, bytes)
+        {
+            return;
+        }
+
+        if kind == "impl_item" {
+            if let (Some(trait_node), Some(type_node)) = (
+                node.child_by_field_name("trait"),
+                node.child_by_field_name("type"),
+            ) {
+                facts.edges.push(Edge {
+                    child: bare_name(node_text(type_node, bytes)).to_string(),
+                    parent: bare_name(node_text(trait_node, bytes)).to_string(),
+                    relation: Relation::Impl,
+                    location: location(file_path, node),
+                });
+            }
+            return;
+        }
+
+        let is_declaration = DECLARATION_KINDS.contains(&kind);
+        if !is_declaration && !RUST_GO_DEFINITION_KINDS.contains(&kind) {
+            if kind == "method_declaration" {
+                let receiver = node
+                    .child_by_field_name("receiver")
+                    .and_then(|receiver| go_receiver_type(node_text(receiver, bytes)));
+                if let (Some(receiver), Some(method)) = (receiver, node.child_by_field_name("name"))
+                {
+                    facts
+                        .go_methods
+                        .entry(receiver)
+                        .or_default()
+                        .insert(node_text(method, bytes).to_string());
+                }
+            }
+            return;
+        }
+        let Some(name_node) = node.child_by_field_name("name") else {
+            return;
+        };
+        let name = node_text(name_node, bytes).to_string();
+        // Go declarations are reported with their `type` keyword
+        let declaration = node
+            .parent()
+            .filter(|parent| parent.kind() == "type_declaration" && parent.named_child_count() == 1)
+            .unwrap_or(node);
+        let here = location(file_path, declaration);
+        facts.definitions.push((name.clone(), here.clone()));
+
+        let mut supertypes: Vec<(String, Relation)> = Vec::new();
+        if is_declaration {
+            if let Some(heritage) = heritage_text(node, bytes) {
+                supertypes.extend(
+                    supertype_names(heritage)
+                        .into_iter()
+                        .map(|parent| (parent, Relation::Extends)),
+                );
+            }
+        } else if kind == "trait_item" {
+            if let Some(bounds) = node.child_by_field_name("bounds") {
+                supertypes.extend(
+                    supertype_names(node_text(bounds, bytes))
+                        .into_iter()
+                        .map(|parent| (parent, Relation::Extends)),
+                );
+            }
+        } else if kind == "type_spec" {
+            if let Some(type_node) = node.child_by_field_name("type") {
+                let mut methods: HashSet<String> = HashSet::new();
+                walk(type_node, &mut |member| match member.kind() {
+                    // Embedded interfaces, and struct fields without a name
+                    "type_elem" => {
+                        supertypes.push((
+                            bare_name(node_text(member, bytes))
+                                .trim_start_matches('*')
+                                .to_string(),
+                            Relation::Embeds,
+                        ));
+                    }
+                    "field_declaration" if member.child_by_field_name("name").is_none() => {
+                        if let Some(embedded) = member.child_by_field_name("type") {
+                            supertypes.push((
+                                bare_name(node_text(embedded, bytes)).to_string(),
+                                Relation::Embeds,
+                            ));
+                        }
+                    }
+                    "method_elem" | "method_spec" => {
+                        if let Some(method) = member.child_by_field_name("name") {
+                            methods.insert(node_text(method, bytes).to_string());
+                        }
+                    }
+                    _ => {}
+                });
+                if type_node.kind() == "interface_type" && !methods.is_empty() {
+                    facts.go_interfaces.push((name.clone(), methods));
+                }
+            }
+        }
+
+        for (parent, relation) in supertypes {
+            if parent != name {
+                facts.edges.push(Edge {
+                    child: name.clone(),
+                    parent,
+                    relation,
+                    location: here.clone(),
+                });
+            }
+        }
+    });
+
+    Ok(facts)
+}
+
+/// Helper function to build the tree of supertypes or subtypes below a type
+fn build_tree(
+    name: &str,
+    edges: &[Edge],
+    definitions: &HashMap<String, Location>,
+    upwards: bool,
+    depth: usize,
+    max_depth: Option<usize>,
+    path: &mut Vec<String>,
+) -> Vec<HierarchyNode> {
+    if max_depth.is_some_and(|max| depth >= max) {
+        return Vec::new();
+    }
+    path.push(name.to_string());
+
+    let mut nodes: Vec<HierarchyNode> = Vec::new();
+    for edge in edges {
+        let (from, to) = if upwards {
+            (&edge.child, &edge.parent)
+        } else {
+            (&edge.parent, &edge.child)
+        };
+        // Cycles (and a type related to itself through another) end the branch
+        if from != name || path.contains(to) {
+            continue;
+        }
+        let duplicate = nodes
+            .iter()
+            .any(|node| &node.name == to && node.relation == edge.relation);
+        if duplicate {
+            continue;
+        }
+        let location = if upwards {
+            definitions.get(to).cloned()
+        } else {
+            Some(edge.location.clone())
+        };
+        nodes.push(HierarchyNode {
+            name: to.clone(),
+            relation: edge.relation,
+            location,
+            children: build_tree(to, edges, definitions, upwards, depth + 1, max_depth, path),
+        });
+    }
+
+    path.pop();
+    nodes.sort_by(|a, b| {
+        a.name
+            .cmp(&b.name)
+            .then_with(|| a.location.cmp(&b.location))
+    });
+    nodes
+}
+
+/// Build the supertype and subtype trees of a type
+///
+/// Relationships come from Rust `impl` blocks and supertraits, declaration headers
+/// (`extends`, `implements`, `: Base`, `class Foo(Base)`), Go embedding and, for Go
+/// interfaces, the method sets of Go types. Types are matched by name.
+pub fn build_hierarchy(options: &HierarchyOptions) -> Result<Hierarchy> {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    let type_name = bare_name(options.type_name);
+    if type_name.is_empty() || !type_name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(anyhow!("Invalid type name: {}", options.type_name));
+    }
+
+    let file_list = file_list_cache::get_file_list(
+        options.path,
+        options.allow_tests,
+        options.ignore,
+        false,
+        file_list_cache::FileFilters::default(),
+    )?;
+
+    let scanned: Vec<FileFacts> = file_list
+        .files
+        .par_iter()
+        .filter_map(|path| match scan_file(path, options.allow_tests) {
+            Ok(facts) => Some(facts),
+            Err(e) => {
+                if debug_mode {
+                    println!("DEBUG: Error scanning file {:?}: {:?}", path, e);
+                }
+                None
+            }
+        })
+        .collect();
+
+    let mut edges: Vec<Edge> = Vec::new();
+    let mut definitions: HashMap<String, Location> = HashMap::new();
+    let mut go_interfaces: Vec<(String, HashSet<String>)> = Vec::new();
+    let mut go_methods: HashMap<String, HashSet<String>> = HashMap::new();
+    for facts in scanned {
+        edges.extend(facts.edges);
+        for (name, location) in facts.definitions {
+            definitions
+                .entry(name)
+                .and_modify(|known| {
+                    if location < *known {
+                        *known = location.clone();
+                    }
+                })
+                .or_insert(location);
+        }
+        go_interfaces.extend(facts.go_interfaces);
+        for (receiver, names) in facts.go_methods {
+            go_methods.entry(receiver).or_default().extend(names);
+        }
+    }
+
+    // Go types implement interfaces implicitly, by having all of their methods
+    for (interface, required) in &go_interfaces {
+        for (receiver, defined) in &go_methods {
+            if receiver == interface || !required.is_subset(defined) {
+                continue;
+            }
+            if let Some(location) = definitions.get(receiver) {
+                edges.push(Edge {
+                    child: receiver.clone(),
+                    parent: interface.clone(),
+                    relation: Relation::MethodSet,
+                    location: location.clone(),
+                });
+            }
+        }
+    }
+    edges.sort_by(|a, b| a.location.cmp(&b.location));
+    edges.dedup();
+
+    if debug_mode {
+        println!(
+            "DEBUG: Found {} types and {} relationships",
+            definitions.len(),
+            edges.len()
+        );
+    }
+
+    let mut path = Vec::new();
+    Ok(Hierarchy {
+        name: type_name.to_string(),
+        definition: definitions.get(type_name).cloned(),
+        supertypes: build_tree(
+            type_name,
+            &edges,
+            &definitions,
+            true,
+            0,
+            options.max_depth,
+            &mut path,
+        ),
+        subtypes: build_tree(
+            type_name,
+            &edges,
+            &definitions,
+            false,
+            0,
+            options.max_depth,
+            &mut path,
+        ),
+    })
+}
+
+fn format_location(location: Option<&Location>) -> String {
+    match location {
+        Some(location) => format!(
+            "{}:{}-{}",
+            location.file_path.display(),
+            location.line_start,
+            location.line_end
+        ),
+        None => "(not in searched code)".to_string(),
+    }
+}
+
+fn location_json(location: Option<&Location>) -> serde_json::Value {
+    let Some(location) = location else {
+        return serde_json::Value::Null;
+    };
+    let encoded = encode_path(&location.file_path);
+    let mut json = serde_json::json!({
+        "file": encoded.value,
+        "lines": [location.line_start, location.line_end],
+    });
+    if let Some(encoding) = encoded.encoding {
+        json["file_encoding"] = encoding.into();
+    }
+    json
+}
+
+fn node_json(node: &HierarchyNode) -> serde_json::Value {
+    serde_json::json!({
+        "name": node.name,
+        "relation": node.relation.as_str(),
+        "location": location_json(node.location.as_ref()),
+        "children": node.children.iter().map(node_json).collect::<Vec<_>>(),
+    })
+}
+
+fn count_nodes(nodes: &[HierarchyNode]) -> usize {
+    nodes
+        .iter()
+        .map(|node| 1 + count_nodes(&node.children))
+        .sum()
+}
+
+/// Helper function to print a tree with box-drawing branches
+fn print_tree(nodes: &[HierarchyNode], prefix: &str, colored_output: bool) {
+    for (index, node) in nodes.iter().enumerate() {
+        let last = index + 1 == nodes.len();
+        let branch = if last { "└── " } else { "├── " };
+        let location = format_location(node.location.as_ref());
+        let relation = format!("({})", node.relation);
+        if colored_output {
+            println!(
+                "{}{}{}  {}  {}",
+                prefix,
+                branch,
+                node.name.bold(),
+                location.cyan(),
+                relation.dimmed()
+            );
+        } else {
+            println!(
+                "{}{}{}  {}  {}",
+                prefix, branch, node.name, location, relation
+            );
+        }
+        let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        print_tree(&node.children, &child_prefix, colored_output);
+    }
+}
+
+/// Print a type hierarchy as a text tree or JSON
+pub fn format_and_print_hierarchy(hierarchy: &Hierarchy, format: &str) -> Result<()> {
+    if format == "json" {
+        let wrapper = serde_json::json!({
+            "type": hierarchy.name,
+            "location": location_json(hierarchy.definition.as_ref()),
+            "supertypes": hierarchy.supertypes.iter().map(node_json).collect::<Vec<_>>(),
+            "subtypes": hierarchy.subtypes.iter().map(node_json).collect::<Vec<_>>(),
+            "summary": {
+                "supertypes": count_nodes(&hierarchy.supertypes),
+                "subtypes": count_nodes(&hierarchy.subtypes),
+            }
+        });
+        println!("{}", serde_json::to_string_pretty(&wrapper)?);
+        return Ok(());
+    }
+
+    let colored_output = format == "color";
+    let location = format_location(hierarchy.definition.as_ref());
+    if colored_output {
+        println!("{}  {}", hierarchy.name.bold().green(), location.cyan());
+    } else {
+        println!("{}  {}", hierarchy.name, location);
+    }
+    for (heading, nodes) in [
+        ("Supertypes", &hierarchy.supertypes),
+        ("Subtypes", &hierarchy.subtypes),
+    ] {
+        println!();
+        if colored_output {
+            println!("{}", heading.bold().blue());
+        } else {
+            println!("{}:", heading);
+        }
+        if nodes.is_empty() {
+            println!("    (none)");
+        }
+        print_tree(nodes, "", colored_output);
+    }
+
+    Ok(())
+}
+
+/// Handle the hierarchy command
+pub fn handle_hierarchy(
+    type_name: &str,
+    path: &Path,
+    ignore: &[String],
+    allow_tests: bool,
+    max_depth: Option<usize>,
+    format: &str,
+) -> Result<()> {
+    let start_time = Instant::now();
+    let options = HierarchyOptions {
+        path,
+        type_name,
+        ignore,
+        allow_tests,
+        max_depth,
+    };
+    let hierarchy = build_hierarchy(&options)?;
+    let duration = start_time.elapsed();
+
+    format_and_print_hierarchy(&hierarchy, format)?;
+    if format != "json" {
+        println!();
+        println!("Search completed in {:.2?}", duration);
+    }
+    Ok(())
+}
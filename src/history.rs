@@ -0,0 +1,356 @@
+use anyhow::{anyhow, Context, Result};
+use colored::*;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::extract::find_symbol_in_file;
+use crate::path_encoding::encode_path;
+
+/// Options for following a block through git history
+pub struct HistoryOptions<'a> {
+    /// Block to follow, as `FILE#SYMBOL`
+    pub target: &'a str,
+    pub max_commits: Option<usize>,
+    /// Extract the block as it was before and after each commit
+    pub versions: bool,
+}
+
+/// A commit that changed the block
+#[derive(Debug, Clone)]
+pub struct HistoryCommit {
+    pub hash: String,
+    pub short_hash: String,
+    pub author: String,
+    pub date: String,
+    pub summary: String,
+    /// The part of the commit's diff that touches the block
+    pub diff: String,
+    /// The block before the commit (`None` if it did not exist yet)
+    pub before: Option<String>,
+    /// The block after the commit
+    pub after: Option<String>,
+}
+
+/// The history of a block, newest commit first
+#[derive(Debug, Clone)]
+pub struct BlockHistory {
+    pub file_path: PathBuf,
+    pub symbol: String,
+    /// Lines of the block at HEAD
+    pub line_start: usize,
+    pub line_end: usize,
+    pub commits: Vec<HistoryCommit>,
+}
+
+/// Separates commits in `git log` output; fields are separated by 0x1f
+const RECORD_SEPARATOR: char = '\u{1e}';
+const FIELD_SEPARATOR: char = '\u{1f}';
+
+/// Function to split a `FILE#SYMBOL` target into its parts
+pub fn parse_target(target: &str) -> Result<(PathBuf, String)> {
+    match target.rsplit_once('#') {
+        Some((file, symbol)) if !file.is_empty() && !symbol.is_empty() => {
+            Ok((PathBuf::from(file), symbol.to_string()))
+        }
+        _ => Err(anyhow!(
+            "Expected a block as FILE#SYMBOL (e.g. src/main.rs#main), got '{}'",
+            target
+        )),
+    }
+}
+
+/// Helper function to run git in the directory of `path`
+fn git(path: &Path, args: &[&str]) -> Result<String> {
+    let dir = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Helper function to extract the symbol from the file as it was at `rev`
+fn block_at(path: &Path, symbol: &str, rev: &str) -> Option<String> {
+    let name = path.file_name()?.to_string_lossy();
+    let content = git(path, &["show", &format!("{}:./{}", rev, name)]).ok()?;
+    find_symbol_in_file(path, symbol, &content, true, 0)
+        .ok()
+        .map(|result| result.code)
+}
+
+/// Function to parse `git log -L` output written with the record/field separators
+fn parse_log(output: &str) -> Vec<HistoryCommit> {
+    output
+        .split(RECORD_SEPARATOR)
+        .filter(|record| !record.trim().is_empty())
+        .filter_map(|record| {
+            let (header, diff) = record.split_once('\n').unwrap_or((record, ""));
+            let fields: Vec<&str> = header.split(FIELD_SEPARATOR).collect();
+            if fields.len() < 5 {
+                return None;
+            }
+            Some(HistoryCommit {
+                hash: fields[0].to_string(),
+                short_hash: fields[1].to_string(),
+                author: fields[2].to_string(),
+                date: fields[3].to_string(),
+                summary: fields[4].to_string(),
+                diff: diff.trim_matches('\n').to_string(),
+                before: None,
+                after: None,
+            })
+        })
+        .collect()
+}
+
+/// Function to list the commits that changed a block
+///
+/// The block is located in the committed (HEAD) version of the file, and its lines are
+/// followed back with `git log -L`.
+pub fn block_history(options: &HistoryOptions) -> Result<BlockHistory> {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+    let (file_path, symbol) = parse_target(options.target)?;
+    let name = file_path
+        .file_name()
+        .ok_or_else(|| anyhow!("Not a file: {}", file_path.display()))?
+        .to_string_lossy()
+        .to_string();
+
+    let content = git(&file_path, &["show", &format!("HEAD:./{}", name)])
+        .with_context(|| format!("{} is not committed to git", file_path.display()))?;
+    let block = find_symbol_in_file(&file_path, &symbol, &content, true, 0)?;
+    let (line_start, line_end) = block.lines;
+
+    if debug_mode {
+        println!(
+            "DEBUG: Following {}#{} at lines {}-{}",
+            file_path.display(),
+            symbol,
+            line_start,
+            line_end
+        );
+    }
+
+    let range = format!("-L{},{}:{}", line_start, line_end, name);
+    let format = format!(
+        "--format={}%H{}%h{}%an{}%ad{}%s",
+        RECORD_SEPARATOR, FIELD_SEPARATOR, FIELD_SEPARATOR, FIELD_SEPARATOR, FIELD_SEPARATOR
+    );
+    let max_count = options
+        .max_commits
+        .map(|max| format!("--max-count={}", max));
+    let mut args = vec!["log", "--no-color", "--date=short", &format, &range];
+    if let Some(max_count) = &max_count {
+        args.push(max_count);
+    }
+    let mut commits = parse_log(&git(&file_path, &args)?);
+
+    if options.versions {
+        for commit in &mut commits {
+            commit.after = block_at(&file_path, &symbol, &commit.hash);
+            commit.before = block_at(&file_path, &symbol, &format!("{}^", commit.hash));
+        }
+    }
+
+    Ok(BlockHistory {
+        file_path,
+        symbol,
+        line_start,
+        line_end,
+        commits,
+    })
+}
+
+/// Helper function to escape XML special characters
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Helper function to color diff lines for terminal output
+fn colorize_diff(diff: &str) -> String {
+    diff.lines()
+        .map(|line| {
+            if line.starts_with("+++") || line.starts_with("---") || line.starts_with("diff ") {
+                line.bold().to_string()
+            } else if line.starts_with('+') {
+                line.green().to_string()
+            } else if line.starts_with('-') {
+                line.red().to_string()
+            } else if line.starts_with("@@") {
+                line.cyan().to_string()
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Print the history of a block
+pub fn format_and_print_history(history: &BlockHistory, format: &str) -> Result<()> {
+    match format {
+        "json" => {
+            let encoded = encode_path(&history.file_path);
+            let commits: Vec<_> = history
+                .commits
+                .iter()
+                .map(|commit| {
+                    let mut json = serde_json::json!({
+                        "hash": commit.hash,
+                        "author": commit.author,
+                        "date": commit.date,
+                        "summary": commit.summary,
+                        "diff": commit.diff,
+                    });
+                    if let Some(before) = &commit.before {
+                        json["before"] = before.as_str().into();
+                    }
+                    if let Some(after) = &commit.after {
+                        json["after"] = after.as_str().into();
+                    }
+                    json
+                })
+                .collect();
+            let mut wrapper = serde_json::json!({
+                "file": encoded.value,
+                "symbol": history.symbol,
+                "lines": [history.line_start, history.line_end],
+                "commits": commits,
+                "summary": {
+                    "count": history.commits.len(),
+                }
+            });
+            if let Some(encoding) = encoded.encoding {
+                wrapper["file_encoding"] = encoding.into();
+            }
+            println!("{}", serde_json::to_string_pretty(&wrapper)?);
+        }
+        "xml" => {
+            let encoded = encode_path(&history.file_path);
+            println!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+            println!("<probe_history>");
+            println!(
+                "  <file{}>{}</file>",
+                encoded.xml_attribute(),
+                escape_xml(&encoded.value)
+            );
+            println!("  <symbol>{}</symbol>", escape_xml(&history.symbol));
+            println!(
+                "  <lines>{}-{}</lines>",
+                history.line_start, history.line_end
+            );
+            for commit in &history.commits {
+                println!("  <commit>");
+                println!("    <hash>{}</hash>", commit.hash);
+                println!("    <author>{}</author>", escape_xml(&commit.author));
+                println!("    <date>{}</date>", escape_xml(&commit.date));
+                println!("    <summary>{}</summary>", escape_xml(&commit.summary));
+                println!("    <diff><![CDATA[{}]]></diff>", commit.diff);
+                if let Some(before) = &commit.before {
+                    println!("    <before><![CDATA[{}]]></before>", before);
+                }
+                if let Some(after) = &commit.after {
+                    println!("    <after><![CDATA[{}]]></after>", after);
+                }
+                println!("  </commit>");
+            }
+            println!("  <summary>");
+            println!("    <count>{}</count>", history.commits.len());
+            println!("  </summary>");
+            println!("</probe_history>");
+        }
+        _ => {
+            let lang = history
+                .file_path
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("");
+            for commit in &history.commits {
+                let heading = format!(
+                    "{} {} {} - {}",
+                    commit.short_hash, commit.date, commit.author, commit.summary
+                );
+                match format {
+                    "markdown" => {
+                        println!("## {}\n", heading);
+                        println!("```diff\n{}\n```", commit.diff);
+                    }
+                    "plain" => {
+                        println!("=== {} ===\n", heading);
+                        println!("{}", commit.diff);
+                    }
+                    _ => {
+                        println!("{}\n", heading.bold().blue());
+                        println!("{}", colorize_diff(&commit.diff));
+                    }
+                }
+
+                for (label, version) in [("Before", &commit.before), ("After", &commit.after)] {
+                    let Some(code) = version else {
+                        continue;
+                    };
+                    match format {
+                        "markdown" => println!("\n**{}**\n```{}\n{}\n```", label, lang, code),
+                        "plain" => println!("\n{}:\n{}", label, code),
+                        _ => println!("\n{}\n{}", format!("{}:", label).cyan(), code),
+                    }
+                }
+                println!();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle the history command
+pub fn handle_history(
+    target: &str,
+    max_commits: Option<usize>,
+    versions: bool,
+    format: &str,
+) -> Result<()> {
+    let options = HistoryOptions {
+        target,
+        max_commits,
+        versions,
+    };
+    let history = block_history(&options)?;
+
+    // Only print information for non-JSON/XML formats
+    if format != "json" && format != "xml" {
+        println!(
+            "{} {}#{} (lines {}-{})",
+            "Block:".bold().green(),
+            history.file_path.display(),
+            history.symbol,
+            history.line_start,
+            history.line_end
+        );
+        if history.commits.is_empty() {
+            println!("{}", "No commits found.".yellow().bold());
+            return Ok(());
+        }
+        println!("Found {} commits", history.commits.len());
+        println!();
+    }
+
+    format_and_print_history(&history, format)
+}
@@ -0,0 +1,498 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+use rayon::prelude::*;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tree_sitter::{Node, Parser as TSParser};
+
+use crate::fs_access;
+use crate::language::factory::get_language_impl;
+use crate::path_encoding::encode_path;
+use crate::search::file_list_cache;
+
+/// How a type was found to implement the trait or interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ImplKind {
+    // A Rust `impl Trait for Type` block
+    Impl,
+    // A class, interface or struct declaration that lists the interface
+    // (`implements`, `extends`, `: Base`, `class Foo(Base)`)
+    Declaration,
+    // A Go type whose methods cover every method of the interface
+    MethodSet,
+}
+
+impl ImplKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ImplKind::Impl => "impl",
+            ImplKind::Declaration => "declaration",
+            ImplKind::MethodSet => "method_set",
+        }
+    }
+}
+
+impl fmt::Display for ImplKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A code block where a type implements the trait or interface
+#[derive(Debug, Clone)]
+pub struct ImplSite {
+    pub file_path: PathBuf,
+    pub implementor: String,
+    pub kind: ImplKind,
+    pub line_start: usize,
+    pub line_end: usize,
+    pub node_type: String,
+    pub code: String,
+}
+
+/// Options for finding implementations
+pub struct ImplsOptions<'a> {
+    pub path: &'a Path,
+    pub trait_name: &'a str,
+    pub ignore: &'a [String],
+    pub allow_tests: bool,
+    pub max_results: Option<usize>,
+}
+
+/// Type declaration node kinds whose header can list implemented interfaces or base types
+pub(crate) const DECLARATION_KINDS: &[&str] = &[
+    // Java, C#, JavaScript, TypeScript, Kotlin, Swift, PHP
+    "class_declaration",
+    "abstract_class_declaration",
+    "interface_declaration",
+    "record_declaration",
+    "struct_declaration",
+    "enum_declaration",
+    "object_declaration",
+    // Python
+    "class_definition",
+    // Ruby
+    "class",
+];
+
+/// Go facts gathered from one file, matched up across files afterwards
+#[derive(Default)]
+struct GoFacts {
+    // Methods of the interface, if it is declared in the file
+    interface_methods: Option<HashSet<String>>,
+    // Method names by receiver type
+    methods: HashMap<String, HashSet<String>>,
+    // Type declarations by type name
+    types: Vec<(String, ImplSite)>,
+}
+
+pub(crate) fn node_text<'a>(node: Node, content: &'a [u8]) -> &'a str {
+    node.utf8_text(content).unwrap_or("")
+}
+
+/// Helper function to get the bare name of a possibly qualified, generic type
+///
+/// `std::fmt::Display`, `io.Reader` and `Handler<T>` become `Display`, `Reader` and `Handler`.
+pub(crate) fn bare_name(text: &str) -> &str {
+    let text = text.split(['<', '[']).next().unwrap_or(text).trim();
+    text.rsplit(['.', ':', '\\']).next().unwrap_or(text).trim()
+}
+
+/// Helper function to remove generic arguments (`<..>` and `[..]`) from a header, so that
+/// `Comparable<Order>` does not count as implementing `Order`
+pub(crate) fn strip_generics(text: &str) -> String {
+    let mut depth = 0usize;
+    let mut stripped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' | '[' => depth += 1,
+            '>' | ']' if depth > 0 => depth -= 1,
+            _ if depth == 0 => stripped.push(c),
+            _ => {}
+        }
+    }
+    stripped
+}
+
+/// Helper function to get the text between a declaration's name and its body
+pub(crate) fn heritage_text<'a>(node: Node, content: &'a [u8]) -> Option<&'a str> {
+    let name = node.child_by_field_name("name")?;
+    let end = node
+        .child_by_field_name("body")
+        .map(|body| body.start_byte())
+        .unwrap_or_else(|| node.end_byte());
+    std::str::from_utf8(content.get(name.end_byte()..end)?).ok()
+}
+
+/// Helper function to get the type a Go method receiver list names, e.g. `(s *Stack[T])`
+pub(crate) fn go_receiver_type(receiver: &str) -> Option<String> {
+    let receiver = receiver
+        .trim()
+        .trim_start_matches('(')
+        .trim_end_matches(')');
+    let last = receiver.split_whitespace().last()?;
+    let name = last.trim_start_matches('*').split('[').next()?;
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+pub(crate) fn walk<'a>(node: Node<'a>, visit: &mut dyn FnMut(Node<'a>)) {
+    visit(node);
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        walk(child, visit);
+    }
+}
+
+fn site(
+    file_path: &Path,
+    implementor: &str,
+    kind: ImplKind,
+    node: Node,
+    content: &[u8],
+) -> ImplSite {
+    ImplSite {
+        file_path: file_path.to_path_buf(),
+        implementor: implementor.to_string(),
+        kind,
+        line_start: node.start_position().row + 1,
+        line_end: node.end_position().row + 1,
+        node_type: node.kind().to_string(),
+        code: node_text(node, content).to_string(),
+    }
+}
+
+/// Find the implementations in one file, and the Go facts needed to match method sets
+fn scan_file(
+    file_path: &Path,
+    trait_name: &str,
+    trait_re: &Regex,
+    allow_tests: bool,
+) -> Result<(Vec<ImplSite>, GoFacts)> {
+    let mut sites: Vec<ImplSite> = Vec::new();
+    let mut go = GoFacts::default();
+
+    let extension = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let Some(language_impl) = get_language_impl(extension) else {
+        return Ok((sites, go));
+    };
+    let is_go = extension == "go";
+
+    let content = fs_access::read_to_string(file_path)?;
+    // Go types can implement an interface without naming it
+    if !is_go && !trait_re.is_match(&content) {
+        return Ok((sites, go));
+    }
+
+    let mut parser = TSParser::new();
+    parser.set_language(&language_impl.get_tree_sitter_language())?;
+    let Some(tree) = parser.parse(&content, None) else {
+        return Ok((sites, go));
+    };
+
+    let bytes = content.as_bytes();
+    let is_test = |node: &Node| !allow_tests && language_impl.is_test_node(node, bytes);
+    walk(tree.root_node(), &mut |node| {
+        let kind = node.kind();
+        if kind == "impl_item" {
+            let implements = node
+                .child_by_field_name("trait")
+                .is_some_and(|t| bare_name(node_text(t, bytes)) == trait_name);
+            if let (true, Some(implementor)) = (implements, node.child_by_field_name("type")) {
+                if !is_test(&node) {
+                    let implementor = node_text(implementor, bytes);
+                    sites.push(site(file_path, implementor, ImplKind::Impl, node, bytes));
+                }
+            }
+        } else if DECLARATION_KINDS.contains(&kind) {
+            let Some(name) = node.child_by_field_name("name") else {
+                return;
+            };
+            let implements = heritage_text(node, bytes)
+                .is_some_and(|heritage| trait_re.is_match(&strip_generics(heritage)));
+            if implements && !is_test(&node) {
+                let implementor = node_text(name, bytes);
+                sites.push(site(
+                    file_path,
+                    implementor,
+                    ImplKind::Declaration,
+                    node,
+                    bytes,
+                ));
+            }
+        } else if is_go && kind == "type_spec" {
+            let (Some(name), Some(type_node)) = (
+                node.child_by_field_name("name"),
+                node.child_by_field_name("type"),
+            ) else {
+                return;
+            };
+            let name = node_text(name, bytes);
+            if type_node.kind() == "interface_type" {
+                if name == trait_name {
+                    let mut cursor = type_node.walk();
+                    let methods = type_node
+                        .children(&mut cursor)
+                        .filter(|child| matches!(child.kind(), "method_elem" | "method_spec"))
+                        .filter_map(|method| method.child_by_field_name("name"))
+                        .map(|method| node_text(method, bytes).to_string())
+                        .collect();
+                    go.interface_methods = Some(methods);
+                }
+            } else if !is_test(&node) {
+                // Report the whole `type X struct` declaration when it holds a single type
+                let declaration = node
+                    .parent()
+                    .filter(|parent| {
+                        parent.kind() == "type_declaration" && parent.named_child_count() == 1
+                    })
+                    .unwrap_or(node);
+                go.types.push((
+                    name.to_string(),
+                    site(file_path, name, ImplKind::MethodSet, declaration, bytes),
+                ));
+            }
+        } else if is_go && kind == "method_declaration" {
+            let receiver = node
+                .child_by_field_name("receiver")
+                .and_then(|receiver| go_receiver_type(node_text(receiver, bytes)));
+            if let (Some(receiver), Some(method)) = (receiver, node.child_by_field_name("name")) {
+                go.methods
+                    .entry(receiver)
+                    .or_default()
+                    .insert(node_text(method, bytes).to_string());
+            }
+        }
+    });
+
+    Ok((sites, go))
+}
+
+/// Find the types implementing a trait or interface
+///
+/// Rust `impl` blocks and declarations that name the interface are found directly. Go
+/// interfaces are satisfied implicitly, so Go types are matched by their method sets:
+/// a type implements the interface when it has methods with all the interface's method
+/// names. Results are sorted by file and line.
+pub fn find_implementations(options: &ImplsOptions) -> Result<Vec<ImplSite>> {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    let trait_name = bare_name(options.trait_name);
+    if trait_name.is_empty() || !trait_name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(anyhow!(
+            "Invalid trait or interface name: {}",
+            options.trait_name
+        ));
+    }
+    let trait_re = Regex::new(&format!(r"\b{}\b", regex::escape(trait_name)))?;
+
+    let file_list = file_list_cache::get_file_list(
+        options.path,
+        options.allow_tests,
+        options.ignore,
+        false,
+        file_list_cache::FileFilters::default(),
+    )?;
+
+    let scanned: Vec<(Vec<ImplSite>, GoFacts)> = file_list
+        .files
+        .par_iter()
+        .filter_map(
+            |path| match scan_file(path, trait_name, &trait_re, options.allow_tests) {
+                Ok(scanned) => Some(scanned),
+                Err(e) => {
+                    if debug_mode {
+                        println!("DEBUG: Error scanning file {:?}: {:?}", path, e);
+                    }
+                    None
+                }
+            },
+        )
+        .collect();
+
+    let mut sites: Vec<ImplSite> = Vec::new();
+    let mut interface_methods: Option<HashSet<String>> = None;
+    let mut methods: HashMap<String, HashSet<String>> = HashMap::new();
+    let mut go_types: Vec<(String, ImplSite)> = Vec::new();
+    for (file_sites, go) in scanned {
+        sites.extend(file_sites);
+        if interface_methods.is_none() {
+            interface_methods = go.interface_methods;
+        }
+        for (receiver, names) in go.methods {
+            methods.entry(receiver).or_default().extend(names);
+        }
+        go_types.extend(go.types);
+    }
+
+    if let Some(required) = interface_methods.filter(|required| !required.is_empty()) {
+        if debug_mode {
+            println!("DEBUG: Go interface {} requires {:?}", trait_name, required);
+        }
+        for (name, site) in go_types {
+            if methods
+                .get(&name)
+                .is_some_and(|defined| required.is_subset(defined))
+            {
+                sites.push(site);
+            }
+        }
+    }
+
+    sites.sort_by(|a, b| {
+        a.file_path
+            .cmp(&b.file_path)
+            .then_with(|| a.line_start.cmp(&b.line_start))
+    });
+
+    if let Some(max) = options.max_results {
+        sites.truncate(max);
+    }
+
+    Ok(sites)
+}
+
+/// Helper function to escape XML special characters
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Print implementation sites
+pub fn format_and_print_impl_sites(sites: &[ImplSite], format: &str) -> Result<()> {
+    match format {
+        "json" => {
+            let results: Vec<_> = sites
+                .iter()
+                .map(|site| {
+                    let encoded = encode_path(&site.file_path);
+                    let mut json = serde_json::json!({
+                        "file": encoded.value,
+                        "lines": [site.line_start, site.line_end],
+                        "node_type": site.node_type,
+                        "implementor": site.implementor,
+                        "kind": site.kind.as_str(),
+                        "code": site.code,
+                    });
+                    if let Some(encoding) = encoded.encoding {
+                        json["file_encoding"] = encoding.into();
+                    }
+                    json
+                })
+                .collect();
+            let wrapper = serde_json::json!({
+                "results": results,
+                "summary": {
+                    "count": sites.len(),
+                    "total_bytes": sites.iter().map(|s| s.code.len()).sum::<usize>(),
+                }
+            });
+            println!("{}", serde_json::to_string_pretty(&wrapper)?);
+        }
+        "xml" => {
+            println!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+            println!("<probe_results>");
+            for site in sites {
+                println!("  <result>");
+                let encoded = encode_path(&site.file_path);
+                println!(
+                    "    <file{}>{}</file>",
+                    encoded.xml_attribute(),
+                    escape_xml(&encoded.value)
+                );
+                println!("    <lines>{}-{}</lines>", site.line_start, site.line_end);
+                println!("    <node_type>{}</node_type>", escape_xml(&site.node_type));
+                println!(
+                    "    <implementor>{}</implementor>",
+                    escape_xml(&site.implementor)
+                );
+                println!("    <kind>{}</kind>", site.kind);
+                println!("    <code><![CDATA[{}]]></code>", site.code);
+                println!("  </result>");
+            }
+            println!("  <summary>");
+            println!("    <count>{}</count>", sites.len());
+            println!("  </summary>");
+            println!("</probe_results>");
+        }
+        _ => {
+            for site in sites {
+                let location = format!(
+                    "{}:{}-{}",
+                    site.file_path.display(),
+                    site.line_start,
+                    site.line_end
+                );
+                let label = format!("{} ({})", site.implementor, site.kind);
+                match format {
+                    "markdown" => {
+                        let lang = site
+                            .file_path
+                            .extension()
+                            .and_then(|e| e.to_str())
+                            .unwrap_or("");
+                        println!("**{}** {}", location, label);
+                        println!("```{}", lang);
+                        println!("{}", site.code);
+                        println!("```");
+                    }
+                    "plain" => {
+                        println!("{} {}", location, label);
+                        println!("{}", site.code);
+                    }
+                    _ => {
+                        println!("{} {}", location.cyan(), label.bold());
+                        println!("{}", site.code);
+                    }
+                }
+                println!();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle the impls command
+pub fn handle_impls(
+    trait_name: &str,
+    path: &Path,
+    ignore: &[String],
+    allow_tests: bool,
+    max_results: Option<usize>,
+    format: &str,
+) -> Result<()> {
+    // Only print information for non-JSON/XML formats
+    if format != "json" && format != "xml" {
+        println!("{} {}", "Trait or interface:".bold().green(), trait_name);
+        println!("{} {}", "Path:".bold().green(), path.display());
+    }
+
+    let start_time = Instant::now();
+    let options = ImplsOptions {
+        path,
+        trait_name,
+        ignore,
+        allow_tests,
+        max_results,
+    };
+    let sites = find_implementations(&options)?;
+    let duration = start_time.elapsed();
+
+    if format != "json" && format != "xml" {
+        if sites.is_empty() {
+            println!("{}", "No implementations found.".yellow().bold());
+            println!("Search completed in {:.2?}", duration);
+            return Ok(());
+        }
+        println!("Found {} implementations in {:.2?}", sites.len(), duration);
+        println!();
+    }
+
+    format_and_print_impl_sites(&sites, format)
+}
@@ -16,6 +16,92 @@ impl CLanguage {
     }
 }
 
+/// Preprocessor conditionals and their branches
+const PREPROC_BRANCHES: &[&str] = &[
+    "preproc_if",
+    "preproc_ifdef",
+    "preproc_else",
+    "preproc_elif",
+    "preproc_elifdef",
+];
+
+/// Helper function to check whether a declaration declares a function
+fn is_function_declaration(node: &Node) -> bool {
+    let mut cursor = node.walk();
+    let found = node.kind() == "declaration"
+        && node
+            .named_children(&mut cursor)
+            .any(|child| child.kind() == "function_declarator");
+    found
+}
+
+/// Helper function to check whether a preprocessor conditional declares a function
+/// in any of its branches
+fn declares_function(conditional: &Node) -> bool {
+    let mut cursor = conditional.walk();
+    let found = conditional.named_children(&mut cursor).any(|child| {
+        is_function_declaration(&child)
+            || (PREPROC_BRANCHES.contains(&child.kind()) && declares_function(&child))
+    });
+    found
+}
+
+/// Function to find the signatures of a function body split across `#if` branches
+///
+/// ```c
+/// #ifdef _WIN32
+/// int read_all(HANDLE h)
+/// #else
+/// int read_all(int fd)
+/// #endif
+/// {
+/// ```
+///
+/// The parser sees the conditional and the body as unrelated siblings. Returns the
+/// conditional holding the signatures when `body` is such a body.
+pub(crate) fn split_signature<'a>(body: Node<'a>) -> Option<Node<'a>> {
+    if body.kind() != "compound_statement" {
+        return None;
+    }
+    let parent = body.parent()?;
+    if !matches!(parent.kind(), "translation_unit" | "declaration_list")
+        && !PREPROC_BRANCHES.contains(&parent.kind())
+    {
+        return None;
+    }
+    let previous = body.prev_named_sibling()?;
+    (matches!(previous.kind(), "preproc_if" | "preproc_ifdef") && declares_function(&previous))
+        .then_some(previous)
+}
+
+/// Function to find the span of a function whose signature is split across `#if`
+/// branches, given its body or one of its signatures
+pub(crate) fn split_function<'a>(node: Node<'a>) -> Option<(Node<'a>, Node<'a>)> {
+    if let Some(signature) = split_signature(node) {
+        return Some((signature, node));
+    }
+    if !is_function_declaration(&node) {
+        return None;
+    }
+
+    // Climb to the outermost conditional around the signature
+    let mut conditional = None;
+    let mut current = node;
+    while let Some(parent) = current.parent() {
+        if !PREPROC_BRANCHES.contains(&parent.kind()) {
+            break;
+        }
+        if matches!(parent.kind(), "preproc_if" | "preproc_ifdef") {
+            conditional = Some(parent);
+        }
+        current = parent;
+    }
+
+    let conditional = conditional?;
+    let body = conditional.next_named_sibling()?;
+    (split_signature(body) == Some(conditional)).then_some((conditional, body))
+}
+
 impl LanguageImpl for CLanguage {
     fn get_tree_sitter_language(&self) -> TSLanguage {
         tree_sitter_c::LANGUAGE.into()
@@ -26,10 +112,15 @@ impl LanguageImpl for CLanguage {
     }
 
     fn is_acceptable_parent(&self, node: &Node) -> bool {
-        matches!(
-            node.kind(),
-            "function_definition" | "declaration" | "struct_specifier" | "enum_specifier"
-        )
+        match node.kind() {
+            "function_definition"
+            | "declaration"
+            | "struct_specifier"
+            | "enum_specifier"
+            | "preproc_function_def" => true,
+            "compound_statement" => split_signature(*node).is_some(),
+            _ => false,
+        }
     }
 
     fn is_test_node(&self, node: &Node, source: &[u8]) -> bool {
@@ -59,4 +150,11 @@ impl LanguageImpl for CLanguage {
 
         false
     }
+    fn find_split_function<'a>(
+        &self,
+        node: Node<'a>,
+        _source: &[u8],
+    ) -> Option<(Node<'a>, Node<'a>)> {
+        split_function(node)
+    }
 }
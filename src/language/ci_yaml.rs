@@ -0,0 +1,212 @@
+// CI YAML support - splits CI pipeline definitions into job and step blocks.
+//
+// GitHub Actions workflows list their jobs under a top-level `jobs:` key, and each job
+// lists its steps under `steps:`. GitLab CI jobs are top-level keys whose body has a
+// `script`, `stage`, `extends` or `trigger` key, or hidden template jobs starting with a
+// dot. YAML files that are not CI pipelines produce no blocks.
+
+use std::path::{Path, PathBuf};
+
+use super::text_blocks::TextBlock;
+use crate::fs_access;
+
+/// Top-level GitLab CI keywords that configure the pipeline rather than define a job
+const GITLAB_GLOBAL_KEYWORDS: &[&str] = &[
+    "default",
+    "include",
+    "stages",
+    "variables",
+    "workflow",
+    "image",
+    "services",
+    "cache",
+    "before_script",
+    "after_script",
+];
+
+/// Keys that mark a top-level GitLab CI entry as a job
+const GITLAB_JOB_KEYS: &[&str] = &["script", "stage", "extends", "trigger"];
+
+/// A line that carries YAML content (not blank and not a comment)
+struct YamlLine<'a> {
+    number: usize,
+    indent: usize,
+    text: &'a str,
+}
+
+impl YamlLine<'_> {
+    /// The mapping key on this line, if it starts with one
+    fn key(&self) -> Option<&str> {
+        let text = self.text.strip_prefix("- ").unwrap_or(self.text);
+        let (key, rest) = text.split_once(':')?;
+        if !(rest.is_empty() || rest.starts_with(' ')) || key.contains(' ') || key.is_empty() {
+            return None;
+        }
+        Some(key.trim_matches(|c| c == '"' || c == '\''))
+    }
+
+    /// The value after `key:` on this line
+    fn value(&self) -> Option<&str> {
+        let text = self.text.strip_prefix("- ").unwrap_or(self.text);
+        let value = text.split_once(':')?.1.trim();
+        (!value.is_empty()).then_some(value)
+    }
+}
+
+/// Helper function to find the end of the entry starting at `lines[start]`
+///
+/// The entry runs until the next line indented at most as far as the entry itself.
+fn entry_end(lines: &[YamlLine], start: usize) -> usize {
+    let indent = lines[start].indent;
+    let mut end = start;
+    while end + 1 < lines.len() && lines[end + 1].indent > indent {
+        end += 1;
+    }
+    end
+}
+
+/// Helper function to split the children of the entry at `lines[parent]` into entries
+///
+/// Returns the index ranges of the child entries at the first nested indentation level.
+fn child_entries(lines: &[YamlLine], parent: usize) -> Vec<(usize, usize)> {
+    let parent_end = entry_end(lines, parent);
+    let mut entries = Vec::new();
+    let mut index = parent + 1;
+    while index <= parent_end {
+        let end = entry_end(lines, index).min(parent_end);
+        entries.push((index, end));
+        index = end + 1;
+    }
+    entries
+}
+
+/// Helper function to find the steps of a GitHub Actions job
+fn find_steps(lines: &[YamlLine], job: (usize, usize), blocks: &mut Vec<TextBlock>) {
+    for (child, _) in child_entries(lines, job.0) {
+        if lines[child].key() != Some("steps") {
+            continue;
+        }
+
+        // Step items may be indented below `steps:` or start at the same indentation
+        let indent = lines[child].indent;
+        let mut index = child + 1;
+        while index < lines.len()
+            && lines[index].text.starts_with('-')
+            && lines[index].indent >= indent
+        {
+            let item_indent = lines[index].indent;
+            let mut end = index;
+            while end + 1 < lines.len() && lines[end + 1].indent > item_indent {
+                end += 1;
+            }
+
+            let item = &lines[index..=end];
+            let field = |name: &str| {
+                item.iter()
+                    .find(|line| line.key() == Some(name))
+                    .and_then(|line| line.value())
+                    .map(|value| value.trim_matches(|c| c == '"' || c == '\'').to_string())
+            };
+            blocks.push(TextBlock {
+                start_line: lines[index].number,
+                end_line: lines[end].number,
+                kind: "step",
+                name: field("name").or_else(|| field("uses")),
+            });
+            index = end + 1;
+        }
+    }
+}
+
+/// Function to list the CI pipeline definitions of a repository root
+///
+/// Returns `.gitlab-ci.yml` and the GitHub Actions workflows under `.github/workflows`.
+/// Both are YAML files in hidden locations, which the regular file walk skips.
+pub fn pipeline_files(root: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = [".gitlab-ci.yml", ".gitlab-ci.yaml"]
+        .iter()
+        .map(|name| root.join(name))
+        .filter(|path| fs_access::is_file(path))
+        .collect();
+
+    if let Ok(entries) = fs_access::read_dir(root.join(".github").join("workflows")) {
+        let mut workflows: Vec<PathBuf> = entries
+            .into_iter()
+            .filter(|path| {
+                fs_access::is_file(path)
+                    && path
+                        .extension()
+                        .is_some_and(|extension| extension == "yml" || extension == "yaml")
+            })
+            .collect();
+        workflows.sort();
+        files.extend(workflows);
+    }
+
+    files
+}
+
+/// Function to find the jobs and steps of a CI pipeline definition
+pub fn find_jobs(content: &str) -> Vec<TextBlock> {
+    let lines: Vec<YamlLine> = content
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let text = line.trim_start();
+            if text.is_empty() || text.starts_with('#') || text == "---" {
+                return None;
+            }
+            Some(YamlLine {
+                number: index + 1,
+                indent: line.len() - text.len(),
+                text: text.trim_end(),
+            })
+        })
+        .collect();
+
+    let mut blocks = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let end = entry_end(&lines, index);
+        if lines[index].indent != 0 {
+            index = end + 1;
+            continue;
+        }
+
+        match lines[index].key() {
+            // GitHub Actions
+            Some("jobs") => {
+                for job in child_entries(&lines, index) {
+                    blocks.push(TextBlock {
+                        start_line: lines[job.0].number,
+                        end_line: lines[job.1].number,
+                        kind: "job",
+                        name: lines[job.0].key().map(str::to_string),
+                    });
+                    find_steps(&lines, job, &mut blocks);
+                }
+            }
+            // GitLab CI
+            Some(key) if !GITLAB_GLOBAL_KEYWORDS.contains(&key) && end > index => {
+                let is_job = key.starts_with('.')
+                    || child_entries(&lines, index).iter().any(|&(child, _)| {
+                        lines[child]
+                            .key()
+                            .is_some_and(|child_key| GITLAB_JOB_KEYS.contains(&child_key))
+                    });
+                if is_job {
+                    blocks.push(TextBlock {
+                        start_line: lines[index].number,
+                        end_line: lines[end].number,
+                        kind: "job",
+                        name: Some(key.to_string()),
+                    });
+                }
+            }
+            _ => {}
+        }
+        index = end + 1;
+    }
+
+    blocks
+}
@@ -1,3 +1,4 @@
+use super::c::{split_function, split_signature};
 use super::language_trait::LanguageImpl;
 use tree_sitter::{Language as TSLanguage, Node};
 
@@ -26,15 +27,17 @@ impl LanguageImpl for CppLanguage {
     }
 
     fn is_acceptable_parent(&self, node: &Node) -> bool {
-        matches!(
-            node.kind(),
+        match node.kind() {
             "function_definition"
-                | "declaration"
-                | "struct_specifier"
-                | "class_specifier"
-                | "enum_specifier"
-                | "namespace_definition"
-        )
+            | "declaration"
+            | "struct_specifier"
+            | "class_specifier"
+            | "enum_specifier"
+            | "namespace_definition"
+            | "preproc_function_def" => true,
+            "compound_statement" => split_signature(*node).is_some(),
+            _ => false,
+        }
     }
 
     fn is_test_node(&self, node: &Node, source: &[u8]) -> bool {
@@ -64,4 +67,11 @@ impl LanguageImpl for CppLanguage {
 
         false
     }
+    fn find_split_function<'a>(
+        &self,
+        node: Node<'a>,
+        _source: &[u8],
+    ) -> Option<(Node<'a>, Node<'a>)> {
+        split_function(node)
+    }
 }
@@ -0,0 +1,113 @@
+use super::language_trait::LanguageImpl;
+use tree_sitter::{Language as TSLanguage, Node};
+
+/// Implementation of LanguageImpl for Dart
+pub struct DartLanguage;
+
+impl Default for DartLanguage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Functions of package:test and package:flutter_test that declare tests
+const TEST_FUNCTIONS: &[&str] = &["test", "testWidgets", "group"];
+
+impl DartLanguage {
+    pub fn new() -> Self {
+        DartLanguage
+    }
+
+    /// Helper function to check whether a class member is a method, getter or constructor with a body
+    ///
+    /// Fields and body-less constructors belong to the enclosing class.
+    fn has_body(node: &Node) -> bool {
+        let mut cursor = node.walk();
+        let has_body = node
+            .children(&mut cursor)
+            .any(|child| child.kind() == "function_body");
+        has_body
+    }
+
+    /// Helper function to check whether a statement calls `test(...)`, `testWidgets(...)` or `group(...)`
+    fn is_test_call(statement: &Node, source: &[u8]) -> bool {
+        statement.kind() == "expression_statement"
+            && statement
+                .named_child(0)
+                .filter(|call| call.kind() == "member_access")
+                .and_then(|call| call.named_child(0))
+                .and_then(|name| name.utf8_text(source).ok())
+                .is_some_and(|name| TEST_FUNCTIONS.contains(&name))
+    }
+}
+
+impl LanguageImpl for DartLanguage {
+    fn get_tree_sitter_language(&self) -> TSLanguage {
+        tree_sitter_dart::language()
+    }
+
+    fn get_extension(&self) -> &'static str {
+        "dart"
+    }
+
+    fn is_acceptable_parent(&self, node: &Node) -> bool {
+        match node.kind() {
+            "class_definition"
+            | "mixin_declaration"
+            | "extension_declaration"
+            | "enum_declaration" => true,
+            // Methods, including the build() of widgets, getters and constructors
+            "class_member_definition" => Self::has_body(node),
+            // The grammar parses top-level functions as a signature followed by a body
+            "lambda_expression" => node
+                .parent()
+                .is_some_and(|parent| parent.kind() == "program"),
+            _ => false,
+        }
+    }
+
+    fn is_test_node(&self, node: &Node, source: &[u8]) -> bool {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+        // Tests are declared by calls in `main()`, so a function making them holds tests
+        if node.kind() == "lambda_expression" {
+            let mut cursor = node.walk();
+            let block = node
+                .children(&mut cursor)
+                .find(|child| child.kind() == "function_body")
+                .and_then(|body| body.named_child(0))
+                .filter(|block| block.kind() == "block");
+            if let Some(block) = block {
+                let mut cursor = block.walk();
+                let has_tests = block
+                    .named_children(&mut cursor)
+                    .any(|statement| Self::is_test_call(&statement, source));
+                if has_tests {
+                    if debug_mode {
+                        println!("DEBUG: Test node detected (Dart): test declarations");
+                    }
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    fn find_parent_function<'a>(&self, node: Node<'a>) -> Option<Node<'a>> {
+        let mut current = node;
+
+        while let Some(parent) = current.parent() {
+            if matches!(
+                parent.kind(),
+                "class_member_definition" | "lambda_expression"
+            ) && self.is_acceptable_parent(&parent)
+            {
+                return Some(parent);
+            }
+            current = parent;
+        }
+
+        None
+    }
+}
@@ -0,0 +1,60 @@
+// Deprecation detection - finds deprecation markers on extracted blocks.
+//
+// A block is deprecated when the attributes, annotations and doc comments right above it
+// or at its start carry a marker: `#[deprecated]` (Rust), `@Deprecated` (Java, Kotlin),
+// `@deprecated` (JSDoc, PHPDoc, Python decorators), `[Obsolete]` (C#),
+// `@available(*, deprecated)` (Swift) or a `Deprecated:` paragraph (Go). Markers inside
+// the block's body belong to its members and do not count.
+
+/// Helper function to check whether a line is an attribute, annotation or comment
+fn is_header_line(line: &str) -> bool {
+    ["#[", "@", "[", "//", "/*", "*", "#", "\"\"\"", "'''"]
+        .iter()
+        .any(|prefix| line.starts_with(prefix))
+}
+
+/// Helper function to check whether a line carries a deprecation marker
+fn has_marker(line: &str) -> bool {
+    line.contains("#[deprecated")
+        || line.contains("@Deprecated")
+        || line.contains("@deprecated")
+        || line.contains("[Obsolete")
+        || (line.contains("@available(") && line.contains("deprecated"))
+        || (line.starts_with("//")
+            && line
+                .trim_start_matches('/')
+                .trim()
+                .starts_with("Deprecated:"))
+}
+
+/// Function to check whether the block at lines `start`-`end` (1-based) is deprecated
+///
+/// Looks at the attribute, annotation and comment lines directly above the block and at
+/// the start of the block, up to and including its first line of code.
+pub fn is_deprecated(lines: &[&str], start: usize, end: usize) -> bool {
+    if start == 0 || start > lines.len() {
+        return false;
+    }
+    let end = end.min(lines.len());
+
+    let above = lines[..start - 1]
+        .iter()
+        .rev()
+        .map(|line| line.trim())
+        .take_while(|line| !line.is_empty() && is_header_line(line));
+    for line in above {
+        if has_marker(line) {
+            return true;
+        }
+    }
+
+    for line in lines[start - 1..end].iter().map(|line| line.trim()) {
+        if has_marker(line) {
+            return true;
+        }
+        if !line.is_empty() && !is_header_line(line) {
+            break;
+        }
+    }
+    false
+}
@@ -0,0 +1,193 @@
+// Language detection - names the language of a file from its extension, file name and
+// content, with `--force-language ext=lang` overrides for extensions probe doesn't know.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::dockerfile;
+use crate::error::ProbeError;
+
+/// Extension overrides from `--force-language`, keyed by lowercase extension
+///
+/// Values are the extension the file is parsed as, e.g. `inc` -> `php`.
+pub type LanguageOverrides = HashMap<String, &'static str>;
+
+/// Known languages and their extensions; the first extension is the one used for parsing
+const LANGUAGES: &[(&str, &[&str])] = &[
+    ("rust", &["rs"]),
+    ("javascript", &["js", "jsx", "mjs", "cjs"]),
+    ("typescript", &["ts", "tsx", "mts", "cts"]),
+    ("python", &["py", "pyi"]),
+    ("go", &["go"]),
+    ("c", &["c", "h"]),
+    ("cpp", &["cpp", "cc", "cxx", "hpp", "hxx", "tpp", "ipp"]),
+    ("java", &["java"]),
+    ("ruby", &["rb"]),
+    ("php", &["php"]),
+    ("swift", &["swift"]),
+    ("csharp", &["cs"]),
+    ("lua", &["lua"]),
+    ("haskell", &["hs"]),
+    ("objc", &["m", "mm"]),
+    ("nix", &["nix"]),
+    ("ocaml", &["ml", "mli"]),
+    ("graphql", &["graphql", "graphqls", "gql"]),
+    ("yaml", &["yml", "yaml"]),
+    ("markdown", &["md", "markdown"]),
+    ("json", &["json"]),
+    ("toml", &["toml"]),
+    ("shell", &["sh", "bash", "zsh"]),
+    ("kotlin", &["kt", "kts"]),
+    ("dart", &["dart"]),
+    ("html", &["html", "htm"]),
+    ("css", &["css"]),
+    ("sql", &["sql"]),
+];
+
+/// Interpreters named in shebang lines and the language they run
+const INTERPRETERS: &[(&str, &str)] = &[
+    ("python", "python"),
+    ("node", "javascript"),
+    ("deno", "typescript"),
+    ("ruby", "ruby"),
+    ("php", "php"),
+    ("lua", "lua"),
+    ("bash", "shell"),
+    ("zsh", "shell"),
+    ("sh", "shell"),
+];
+
+/// Helper function to find the language an extension belongs to
+fn language_for_extension(extension: &str) -> Option<&'static str> {
+    LANGUAGES
+        .iter()
+        .find(|(_, extensions)| extensions.contains(&extension))
+        .map(|(name, _)| *name)
+}
+
+/// Function to resolve a language name or extension to the extension it is parsed as
+///
+/// Accepts names (`php`, `cpp`) and extensions (`tsx`, `.hpp`) in any case.
+pub fn resolve_language(value: &str) -> Option<&'static str> {
+    let value = value.trim().trim_start_matches('.').to_lowercase();
+    LANGUAGES.iter().find_map(|(name, extensions)| {
+        if *name == value {
+            extensions.first().copied()
+        } else {
+            extensions
+                .iter()
+                .find(|extension| **extension == value)
+                .copied()
+        }
+    })
+}
+
+/// Function to parse `--force-language` mappings such as `inc=php` or `.tpp=cpp`
+pub fn parse_language_overrides(specs: &[String]) -> Result<LanguageOverrides> {
+    let mut overrides = LanguageOverrides::new();
+    for spec in specs {
+        let (extension, language) = spec
+            .split_once('=')
+            .ok_or_else(|| {
+                ProbeError::Language(format!(
+                    "Invalid --force-language '{}': expected ext=lang",
+                    spec
+                ))
+            })?;
+        let extension = extension.trim().trim_start_matches('.').to_lowercase();
+        if extension.is_empty() {
+            return Err(ProbeError::Language(format!(
+                "Invalid --force-language '{}': missing extension",
+                spec
+            ))
+            .into());
+        }
+        let parse_as = resolve_language(language).ok_or_else(|| {
+            let known: Vec<&str> = LANGUAGES.iter().map(|(name, _)| *name).collect();
+            ProbeError::Language(format!(
+                "Unknown language '{}' in --force-language '{}' (known: {})",
+                language.trim(),
+                spec,
+                known.join(", ")
+            ))
+        })?;
+        overrides.insert(extension, parse_as);
+    }
+    Ok(overrides)
+}
+
+/// Function to get the extension a file is parsed as, after applying overrides
+pub fn parse_extension<'a>(path: &'a Path, overrides: &LanguageOverrides) -> &'a str {
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    if overrides.is_empty() {
+        return extension;
+    }
+    overrides
+        .get(&extension.to_lowercase())
+        .copied()
+        .unwrap_or(extension)
+}
+
+/// Helper function to check whether a `.h` header is written in C++
+fn is_cpp_header(content: &str) -> bool {
+    content.lines().any(|line| {
+        let line = line.trim_start();
+        line.starts_with("class ")
+            || line.starts_with("namespace ")
+            || line.starts_with("template <")
+            || line.starts_with("template<")
+            || line.starts_with("public:")
+            || line.starts_with("private:")
+            || line.contains("std::")
+    })
+}
+
+/// Helper function to find the language of a script from its shebang line
+fn shebang_language(content: &str) -> Option<&'static str> {
+    let line = content.lines().next()?.strip_prefix("#!")?;
+    let mut words = line.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|word| !word.starts_with('-'))?;
+    }
+    INTERPRETERS
+        .iter()
+        .find(|(interpreter, _)| {
+            program == *interpreter
+                || program
+                    .strip_prefix(interpreter)
+                    .is_some_and(|version| version.chars().all(|c| c.is_ascii_digit() || c == '.'))
+        })
+        .map(|(_, language)| *language)
+}
+
+/// Function to detect the language of a file
+///
+/// Overrides win, then file names (Dockerfiles), then the extension. `.h` headers are
+/// checked for C++ constructs and files without an extension for a shebang line.
+pub fn detect_language(
+    path: &Path,
+    content: &str,
+    overrides: &LanguageOverrides,
+) -> Option<&'static str> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    if let Some(parse_as) = overrides.get(&extension) {
+        return language_for_extension(parse_as);
+    }
+    if dockerfile::is_dockerfile(path) {
+        return Some("dockerfile");
+    }
+    if extension == "h" && is_cpp_header(content) {
+        return Some("cpp");
+    }
+    if extension.is_empty() {
+        return shebang_language(content);
+    }
+    language_for_extension(&extension)
+}
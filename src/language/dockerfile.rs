@@ -0,0 +1,77 @@
+// Dockerfile support - splits multi-stage Dockerfiles into one block per build stage.
+//
+// A stage runs from its FROM instruction to the last instruction before the next FROM.
+
+use std::path::Path;
+
+use super::text_blocks::TextBlock;
+
+/// Function to check whether a path is a Dockerfile or Containerfile
+///
+/// Matches `Dockerfile`, `Containerfile`, variants such as `Dockerfile.prod` and
+/// files with a `.dockerfile` extension.
+pub fn is_dockerfile(path: &Path) -> bool {
+    let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    let file_name = file_name.to_lowercase();
+    file_name == "dockerfile"
+        || file_name == "containerfile"
+        || file_name.starts_with("dockerfile.")
+        || file_name.starts_with("containerfile.")
+        || file_name.ends_with(".dockerfile")
+}
+
+/// Helper function to read the stage name from a FROM instruction
+///
+/// Returns the `AS` alias when there is one, otherwise the base image.
+fn stage_name(instruction: &str) -> Option<String> {
+    let arguments: Vec<&str> = instruction
+        .split_whitespace()
+        .skip(1)
+        .filter(|argument| !argument.starts_with("--"))
+        .collect();
+    match arguments.as_slice() {
+        [_, keyword, alias, ..] if keyword.eq_ignore_ascii_case("as") => Some(alias.to_string()),
+        [image, ..] => Some(image.to_string()),
+        [] => None,
+    }
+}
+
+/// Function to find the build stages of a Dockerfile
+///
+/// Instructions before the first FROM (global ARGs, parser directives) are not part of
+/// any stage.
+pub fn find_stages(content: &str) -> Vec<TextBlock> {
+    let mut stages: Vec<TextBlock> = Vec::new();
+    let mut continued = false;
+
+    for (index, line) in content.lines().enumerate() {
+        let line_number = index + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let starts_instruction = !continued;
+        continued = trimmed.ends_with('\\');
+
+        let is_from = starts_instruction
+            && trimmed
+                .split_whitespace()
+                .next()
+                .is_some_and(|instruction| instruction.eq_ignore_ascii_case("from"));
+        if is_from {
+            stages.push(TextBlock {
+                start_line: line_number,
+                end_line: line_number,
+                kind: "stage",
+                name: stage_name(trimmed),
+            });
+        } else if let Some(stage) = stages.last_mut() {
+            stage.end_line = line_number;
+        }
+    }
+
+    stages
+}
@@ -1,10 +1,16 @@
 use crate::language::c::CLanguage;
 use crate::language::cpp::CppLanguage;
 use crate::language::csharp::CSharpLanguage;
+use crate::language::dart::DartLanguage;
 use crate::language::go::GoLanguage;
+use crate::language::haskell::HaskellLanguage;
 use crate::language::java::JavaLanguage;
 use crate::language::javascript::JavaScriptLanguage;
 use crate::language::language_trait::LanguageImpl;
+use crate::language::lua::LuaLanguage;
+use crate::language::nix::NixLanguage;
+use crate::language::objc::ObjCLanguage;
+use crate::language::ocaml::OCamlLanguage;
 use crate::language::php::PhpLanguage;
 use crate::language::python::PythonLanguage;
 use crate::language::ruby::RubyLanguage;
@@ -28,6 +34,13 @@ pub fn get_language_impl(extension: &str) -> Option<Box<dyn LanguageImpl>> {
         "php" => Some(Box::new(PhpLanguage::new())),
         "swift" => Some(Box::new(SwiftLanguage::new())),
         "cs" => Some(Box::new(CSharpLanguage::new())),
+        "lua" => Some(Box::new(LuaLanguage::new())),
+        "hs" => Some(Box::new(HaskellLanguage::new())),
+        "m" | "mm" => Some(Box::new(ObjCLanguage::new())),
+        "nix" => Some(Box::new(NixLanguage::new())),
+        "dart" => Some(Box::new(DartLanguage::new())),
+        "ml" => Some(Box::new(OCamlLanguage::new_ocaml())),
+        "mli" => Some(Box::new(OCamlLanguage::new_interface())),
         _ => None,
     }
 }
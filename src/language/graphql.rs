@@ -0,0 +1,208 @@
+// GraphQL support - finds type, interface and operation definitions in `.graphql` files.
+//
+// There is no tree-sitter grammar for GraphQL in this build, so definitions are found by
+// a small scanner over the document's top level. GraphQL documents are flat lists of
+// definitions, which makes brace matching sufficient.
+
+use super::text_blocks::TextBlock;
+
+/// Function to check whether a file extension is GraphQL
+pub fn is_graphql_extension(extension: &str) -> bool {
+    matches!(extension, "graphql" | "graphqls" | "gql")
+}
+
+/// Helper function to map a definition keyword to its node type
+fn definition_kind(keyword: &str) -> Option<&'static str> {
+    match keyword {
+        "type" => Some("object_type_definition"),
+        "interface" => Some("interface_type_definition"),
+        "enum" => Some("enum_type_definition"),
+        "input" => Some("input_object_type_definition"),
+        "union" => Some("union_type_definition"),
+        "scalar" => Some("scalar_type_definition"),
+        "schema" => Some("schema_definition"),
+        "directive" => Some("directive_definition"),
+        "query" | "mutation" | "subscription" => Some("operation_definition"),
+        "fragment" => Some("fragment_definition"),
+        "extend" => Some("type_system_extension"),
+        _ => None,
+    }
+}
+
+/// Definition being scanned
+struct OpenDefinition {
+    start_line: usize,
+    last_line: usize,
+    kind: &'static str,
+    name: Option<String>,
+    expects_name: bool,
+}
+
+impl OpenDefinition {
+    fn close(self, end_line: usize) -> TextBlock {
+        TextBlock {
+            start_line: self.start_line,
+            end_line,
+            kind: self.kind,
+            name: self.name,
+        }
+    }
+}
+
+/// Function to find all top-level definitions in a GraphQL document
+///
+/// Definitions with a body end at their closing brace. Definitions without one
+/// (`scalar Date`, `union Result = A | B`, `directive @auth on FIELD`) end at their last
+/// token before the next definition.
+pub fn find_definitions(content: &str) -> Vec<TextBlock> {
+    let chars: Vec<char> = content.chars().collect();
+    let mut definitions = Vec::new();
+    let mut current: Option<OpenDefinition> = None;
+    let mut description_start: Option<usize> = None;
+    let mut depth = 0usize;
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '\n' {
+            line += 1;
+            i += 1;
+            continue;
+        }
+        if c.is_whitespace() || c == ',' {
+            i += 1;
+            continue;
+        }
+
+        // Comments run to the end of the line
+        if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+
+        let token_line = line;
+
+        // Strings, including block strings used as descriptions
+        if c == '"' {
+            let block = chars[i..].starts_with(&['"', '"', '"']);
+            i += if block { 3 } else { 1 };
+            while i < chars.len() {
+                if chars[i] == '\\' {
+                    i += 2;
+                    continue;
+                }
+                if chars[i] == '\n' {
+                    line += 1;
+                } else if block && chars[i..].starts_with(&['"', '"', '"']) {
+                    i += 3;
+                    break;
+                } else if !block && chars[i] == '"' {
+                    i += 1;
+                    break;
+                }
+                i += 1;
+            }
+            if depth == 0 {
+                if let Some(open) = current.take() {
+                    let end_line = open.last_line;
+                    definitions.push(open.close(end_line));
+                }
+                description_start.get_or_insert(token_line);
+            } else if let Some(open) = current.as_mut() {
+                open.last_line = line;
+            }
+            continue;
+        }
+
+        if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+
+            if depth == 0 {
+                let extending = current
+                    .as_ref()
+                    .is_some_and(|open| open.kind == "type_system_extension" && open.expects_name);
+                match definition_kind(&word) {
+                    // `extend type User` names the extended type, not a new definition
+                    Some(_) if extending => continue,
+                    Some(kind) => {
+                        if let Some(open) = current.take() {
+                            let end_line = open.last_line;
+                            definitions.push(open.close(end_line));
+                        }
+                        current = Some(OpenDefinition {
+                            start_line: description_start.take().unwrap_or(token_line),
+                            last_line: token_line,
+                            kind,
+                            name: None,
+                            expects_name: kind != "schema_definition",
+                        });
+                        continue;
+                    }
+                    None => {}
+                }
+                if let Some(open) = current.as_mut() {
+                    if open.expects_name {
+                        open.name = Some(word);
+                        open.expects_name = false;
+                    }
+                }
+            }
+            if let Some(open) = current.as_mut() {
+                open.last_line = token_line;
+            }
+            continue;
+        }
+
+        match c {
+            '{' | '(' | '[' => {
+                if depth == 0 && c == '{' && current.is_none() {
+                    // Anonymous query shorthand: `{ viewer { id } }`
+                    current = Some(OpenDefinition {
+                        start_line: description_start.take().unwrap_or(token_line),
+                        last_line: token_line,
+                        kind: "operation_definition",
+                        name: None,
+                        expects_name: false,
+                    });
+                }
+                if c == '{' {
+                    if let Some(open) = current.as_mut() {
+                        open.expects_name = false;
+                    }
+                }
+                depth += 1;
+            }
+            '}' | ')' | ']' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 && c == '}' {
+                    if let Some(open) = current.take() {
+                        definitions.push(open.close(token_line));
+                    }
+                    i += 1;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(open) = current.as_mut() {
+            open.last_line = token_line;
+        }
+        i += 1;
+    }
+
+    if let Some(open) = current {
+        let end_line = open.last_line;
+        definitions.push(open.close(end_line));
+    }
+
+    definitions
+}
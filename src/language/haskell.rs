@@ -0,0 +1,107 @@
+use super::language_trait::LanguageImpl;
+use tree_sitter::{Language as TSLanguage, Node};
+
+/// Implementation of LanguageImpl for Haskell
+pub struct HaskellLanguage;
+
+impl Default for HaskellLanguage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Node types of the nodes that make up a binding: its type signature and its equations
+const BINDING_KINDS: &[&str] = &["signature", "function", "bind"];
+
+/// Prefixes of HUnit, QuickCheck and tasty-discover test bindings
+const TEST_PREFIXES: &[&str] = &["prop_", "test_", "case_", "unit_", "spec_"];
+
+impl HaskellLanguage {
+    pub fn new() -> Self {
+        HaskellLanguage
+    }
+
+    /// Helper function to get the name a signature, function or bind node defines
+    fn binding_name<'a>(node: &Node, source: &'a [u8]) -> Option<&'a str> {
+        if !BINDING_KINDS.contains(&node.kind()) {
+            return None;
+        }
+        node.child_by_field_name("name")?.utf8_text(source).ok()
+    }
+
+    /// Helper function to check whether a node is declared at module, class or instance level
+    ///
+    /// Bindings in `where` and `let` clauses belong to the enclosing top-level binding.
+    fn is_declaration_level(node: &Node) -> bool {
+        node.parent().is_some_and(|parent| {
+            matches!(
+                parent.kind(),
+                "declarations" | "class_declarations" | "instance_declarations"
+            )
+        })
+    }
+}
+
+impl LanguageImpl for HaskellLanguage {
+    fn get_tree_sitter_language(&self) -> TSLanguage {
+        tree_sitter_haskell::LANGUAGE.into()
+    }
+
+    fn get_extension(&self) -> &'static str {
+        "hs"
+    }
+
+    fn is_acceptable_parent(&self, node: &Node) -> bool {
+        match node.kind() {
+            "signature" | "function" | "bind" => Self::is_declaration_level(node),
+            "data_type" | "newtype" | "type_synomym" | "type_family" | "data_family" | "class"
+            | "instance" | "deriving_instance" => true,
+            _ => false,
+        }
+    }
+
+    fn is_test_node(&self, node: &Node, source: &[u8]) -> bool {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+        // Haskell: hspec `spec` bindings and prop_/test_/case_ bindings
+        if let Some(name) = Self::binding_name(node, source) {
+            if name == "spec" || TEST_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) {
+                if debug_mode {
+                    println!("DEBUG: Test node detected (Haskell): {} binding", name);
+                }
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn find_split_function<'a>(
+        &self,
+        node: Node<'a>,
+        source: &[u8],
+    ) -> Option<(Node<'a>, Node<'a>)> {
+        if !Self::is_declaration_level(&node) {
+            return None;
+        }
+        let name = Self::binding_name(&node, source)?;
+
+        // A type signature and the equations after it are siblings with the same name
+        let mut first = node;
+        while let Some(previous) = first.prev_named_sibling() {
+            if Self::binding_name(&previous, source) != Some(name) {
+                break;
+            }
+            first = previous;
+        }
+        let mut last = node;
+        while let Some(next) = last.next_named_sibling() {
+            if Self::binding_name(&next, source) != Some(name) {
+                break;
+            }
+            last = next;
+        }
+
+        (first != last).then_some((first, last))
+    }
+}
@@ -27,4 +27,15 @@ pub trait LanguageImpl {
         // Default implementation returns None
         None
     }
+
+    /// Find the first and last node of a definition split across sibling nodes
+    /// (C/C++ signatures in preprocessor branches, Haskell signatures and equations)
+    fn find_split_function<'a>(
+        &self,
+        _node: Node<'a>,
+        _source: &[u8],
+    ) -> Option<(Node<'a>, Node<'a>)> {
+        // Default implementation returns None
+        None
+    }
 }
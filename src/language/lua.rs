@@ -0,0 +1,103 @@
+use super::language_trait::LanguageImpl;
+use tree_sitter::{Language as TSLanguage, Node};
+
+/// Implementation of LanguageImpl for Lua
+pub struct LuaLanguage;
+
+impl Default for LuaLanguage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LuaLanguage {
+    pub fn new() -> Self {
+        LuaLanguage
+    }
+
+    /// Helper function to check whether an assignment binds a function or a table
+    ///
+    /// Neovim plugins define most of their code as `local M = {}` modules,
+    /// `M.setup = function(opts) ... end` and `local config = { ... }` tables.
+    fn assigns_function_or_table(node: &Node) -> bool {
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            if child.kind() != "expression_list" {
+                continue;
+            }
+            let mut list_cursor = child.walk();
+            for value in child.named_children(&mut list_cursor) {
+                if matches!(value.kind(), "function_definition" | "table_constructor") {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+impl LanguageImpl for LuaLanguage {
+    fn get_tree_sitter_language(&self) -> TSLanguage {
+        tree_sitter_lua::LANGUAGE.into()
+    }
+
+    fn get_extension(&self) -> &'static str {
+        "lua"
+    }
+
+    fn is_acceptable_parent(&self, node: &Node) -> bool {
+        match node.kind() {
+            // `function foo()`, `local function foo()` and `function M.foo()`
+            "function_declaration" => true,
+            // `local x = ...` wraps an assignment_statement, accept only the outer node
+            "variable_declaration" => node
+                .named_child(0)
+                .is_some_and(|child| Self::assigns_function_or_table(&child)),
+            "assignment_statement" => {
+                node.parent()
+                    .is_none_or(|parent| parent.kind() != "variable_declaration")
+                    && Self::assigns_function_or_table(node)
+            }
+            // `setup = function(opts) ... end` inside a table constructor
+            "field" => node
+                .child_by_field_name("value")
+                .is_some_and(|value| value.kind() == "function_definition"),
+            _ => false,
+        }
+    }
+
+    fn is_test_node(&self, node: &Node, source: &[u8]) -> bool {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+        // Lua: busted and plenary specs use describe/it blocks
+        if node.kind() == "function_call" {
+            if let Some(name) = node.child_by_field_name("name") {
+                let name = name.utf8_text(source).unwrap_or("");
+                if matches!(name, "describe" | "it" | "pending" | "before_each") {
+                    if debug_mode {
+                        println!("DEBUG: Test node detected (Lua): {} block", name);
+                    }
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    fn find_parent_function<'a>(&self, node: Node<'a>) -> Option<Node<'a>> {
+        let mut current = node;
+
+        while let Some(parent) = current.parent() {
+            if matches!(
+                parent.kind(),
+                "function_declaration" | "function_definition"
+            ) {
+                return Some(parent);
+            }
+            current = parent;
+        }
+
+        None
+    }
+}
@@ -4,18 +4,32 @@
 // Import submodules
 pub mod block_handling;
 pub mod common;
+pub mod deprecation;
+pub mod detection;
 pub mod factory;
 pub mod language_trait;
 pub mod parser;
 pub mod test_detection;
+pub mod text_blocks;
+pub mod tree_cache;
+pub mod visibility;
 
 // Language implementations
 pub mod c;
+pub mod ci_yaml;
 pub mod cpp;
 pub mod csharp;
+pub mod dart;
+pub mod dockerfile;
 pub mod go;
+pub mod graphql;
+pub mod haskell;
 pub mod java;
 pub mod javascript;
+pub mod lua;
+pub mod nix;
+pub mod objc;
+pub mod ocaml;
 pub mod php;
 pub mod python;
 pub mod ruby;
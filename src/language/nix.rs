@@ -0,0 +1,94 @@
+use super::language_trait::LanguageImpl;
+use tree_sitter::{Language as TSLanguage, Node};
+
+/// Implementation of LanguageImpl for Nix
+pub struct NixLanguage;
+
+impl Default for NixLanguage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NixLanguage {
+    pub fn new() -> Self {
+        NixLanguage
+    }
+
+    /// Helper function to check whether a value is a function or an attribute set
+    ///
+    /// Besides `f = x: ...` and `meta = { ... }` this covers attribute sets passed to a
+    /// function, like `stdenv.mkDerivation rec { ... }` and `lib.mkOption { ... }`.
+    fn is_function_or_attrset(value: &Node) -> bool {
+        match value.kind() {
+            "function_expression" | "attrset_expression" | "rec_attrset_expression" => true,
+            "apply_expression" => value
+                .child_by_field_name("argument")
+                .is_some_and(|argument| {
+                    matches!(
+                        argument.kind(),
+                        "attrset_expression" | "rec_attrset_expression"
+                    )
+                }),
+            _ => false,
+        }
+    }
+
+    /// Helper function to get the name a binding defines, e.g. `meta` for `meta.description = ...`
+    fn binding_name<'a>(node: &Node, source: &'a [u8]) -> &'a str {
+        node.child_by_field_name("attrpath")
+            .and_then(|attrpath| attrpath.child_by_field_name("attr"))
+            .and_then(|attr| attr.utf8_text(source).ok())
+            .unwrap_or("")
+    }
+}
+
+impl LanguageImpl for NixLanguage {
+    fn get_tree_sitter_language(&self) -> TSLanguage {
+        tree_sitter_nix::LANGUAGE.into()
+    }
+
+    fn get_extension(&self) -> &'static str {
+        "nix"
+    }
+
+    fn is_acceptable_parent(&self, node: &Node) -> bool {
+        // Plain values like `version = "1.2.3";` belong to the enclosing attribute set
+        node.kind() == "binding"
+            && node
+                .child_by_field_name("expression")
+                .is_some_and(|value| Self::is_function_or_attrset(&value))
+    }
+
+    fn is_test_node(&self, node: &Node, source: &[u8]) -> bool {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+        // lib.runTests only runs attributes whose names start with "test"
+        if node.kind() == "binding" && Self::binding_name(node, source).starts_with("test") {
+            if debug_mode {
+                println!("DEBUG: Test node detected (Nix): test attribute");
+            }
+            return true;
+        }
+
+        false
+    }
+
+    fn find_parent_function<'a>(&self, node: Node<'a>) -> Option<Node<'a>> {
+        let mut current = node;
+
+        // Lambdas are anonymous, so report the binding that names the function
+        while let Some(parent) = current.parent() {
+            if parent.kind() == "binding"
+                && parent
+                    .child_by_field_name("expression")
+                    .is_some_and(|value| value.kind() == "function_expression")
+            {
+                return Some(parent);
+            }
+            current = parent;
+        }
+
+        None
+    }
+}
@@ -0,0 +1,95 @@
+use super::language_trait::LanguageImpl;
+use tree_sitter::{Language as TSLanguage, Node};
+
+/// Implementation of LanguageImpl for Objective-C
+pub struct ObjCLanguage;
+
+impl Default for ObjCLanguage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ObjCLanguage {
+    pub fn new() -> Self {
+        ObjCLanguage
+    }
+
+    /// Helper function to read the selector's first part, e.g. `valueForKey`
+    fn method_name<'a>(node: &Node, source: &'a [u8]) -> &'a str {
+        let mut cursor = node.walk();
+        let name = node
+            .named_children(&mut cursor)
+            .find(|child| child.kind() == "identifier");
+        name.and_then(|name| name.utf8_text(source).ok())
+            .unwrap_or("")
+    }
+}
+
+impl LanguageImpl for ObjCLanguage {
+    fn get_tree_sitter_language(&self) -> TSLanguage {
+        tree_sitter_objc::LANGUAGE.into()
+    }
+
+    fn get_extension(&self) -> &'static str {
+        "m"
+    }
+
+    fn is_acceptable_parent(&self, node: &Node) -> bool {
+        // Methods are defined inside @implementation and declared inside @interface and
+        // @protocol; the containers themselves cover properties and ivars
+        matches!(
+            node.kind(),
+            "function_definition"
+                | "method_definition"
+                | "method_declaration"
+                | "class_interface"
+                | "class_implementation"
+                | "protocol_declaration"
+        )
+    }
+
+    fn is_test_node(&self, node: &Node, source: &[u8]) -> bool {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+        match node.kind() {
+            // XCTest runs every method whose selector starts with "test"
+            "method_definition" if Self::method_name(node, source).starts_with("test") => {
+                if debug_mode {
+                    println!("DEBUG: Test node detected (Objective-C): test method");
+                }
+                return true;
+            }
+            // `@interface FooTests : XCTestCase`
+            "class_interface" => {
+                let mut cursor = node.walk();
+                let superclass = node
+                    .named_children(&mut cursor)
+                    .filter(|child| child.kind() == "identifier")
+                    .nth(1);
+                if superclass.and_then(|name| name.utf8_text(source).ok()) == Some("XCTestCase") {
+                    if debug_mode {
+                        println!("DEBUG: Test node detected (Objective-C): XCTestCase class");
+                    }
+                    return true;
+                }
+            }
+            _ => {}
+        }
+
+        false
+    }
+
+    fn find_parent_function<'a>(&self, node: Node<'a>) -> Option<Node<'a>> {
+        let mut current = node;
+
+        while let Some(parent) = current.parent() {
+            if matches!(parent.kind(), "function_definition" | "method_definition") {
+                return Some(parent);
+            }
+            current = parent;
+        }
+
+        None
+    }
+}
@@ -1,10 +1,11 @@
 use anyhow::{Context, Result};
 use std::collections::{HashMap, HashSet};
-use tree_sitter::{Node, Parser as TSParser};
+use tree_sitter::{Language as TSLanguage, Node, Parser as TSParser, Tree};
 
 use crate::language::common::find_most_specific_node;
 use crate::language::factory::get_language_impl;
 use crate::language::language_trait::LanguageImpl;
+use crate::language::tree_cache;
 use crate::models::CodeBlock;
 
 /// Function to find the closest acceptable parent entity that encompasses a given line.
@@ -192,6 +193,28 @@ pub fn find_code_structure<'a>(node: Node<'a>, line: usize, extension: &str) ->
         }
     }
 
+    // Inside a region the parser could not make sense of (mid-edit files, syntax errors),
+    // use the nearest valid node around the line instead of a lone token
+    if let Some(error_node) = enclosing_error(target_node) {
+        let valid = nearest_valid_node(target_node, error_node);
+        if debug_mode {
+            match valid {
+                Some(node) => println!(
+                    "DEBUG: Line {} is in a syntax error, using nearest valid node: type='{}', lines={}-{}",
+                    line,
+                    node.kind(),
+                    node.start_position().row + 1,
+                    node.end_position().row + 1
+                ),
+                None => println!(
+                    "DEBUG: Line {} is in a syntax error with no valid node around it",
+                    line
+                ),
+            }
+        }
+        return valid;
+    }
+
     // FINAL FALLBACK: If we never found an "acceptable parent,"
     // just return the original node anyway instead of returning None
     if debug_mode {
@@ -200,6 +223,49 @@ pub fn find_code_structure<'a>(node: Node<'a>, line: usize, extension: &str) ->
     Some(target_node)
 }
 
+/// Finds the outermost ERROR node around a node, if the node is inside one
+fn enclosing_error(node: Node<'_>) -> Option<Node<'_>> {
+    let mut error = None;
+    let mut current = Some(node);
+    while let Some(candidate) = current {
+        if candidate.is_error() {
+            error = Some(candidate);
+        }
+        current = candidate.parent();
+    }
+    error
+}
+
+/// Finds the nearest named, error-free node enclosing `node` inside an ERROR node
+///
+/// Above the ERROR node that is the first ancestor that is not the root. Inside it, the
+/// largest named node between the ERROR node and `node` is the best statement-sized guess.
+fn nearest_valid_node<'a>(node: Node<'a>, error_node: Node<'a>) -> Option<Node<'a>> {
+    let mut current = error_node;
+    while let Some(parent) = current.parent() {
+        if parent.parent().is_none() {
+            break;
+        }
+        if parent.is_named() && !parent.is_error() {
+            return Some(parent);
+        }
+        current = parent;
+    }
+
+    let mut candidate = None;
+    let mut current = node;
+    while let Some(parent) = current.parent() {
+        if current.is_named() && !current.is_error() && current.end_byte() > current.start_byte() {
+            candidate = Some(current);
+        }
+        if parent == error_node {
+            break;
+        }
+        current = parent;
+    }
+    candidate
+}
+
 /// Gets the context for a comment node, which can be either:
 /// 1. An acceptable parent node if the comment is inside a code block
 /// 2. The next acceptable node if the comment is at the root level
@@ -407,6 +473,35 @@ fn find_acceptable_child<'a>(node: Node<'a>, language_impl: &dyn LanguageImpl) -
     None // No acceptable child found
 }
 
+thread_local! {
+    // Parsers are cheap to switch between languages, so each thread keeps one
+    static PARSER: std::cell::RefCell<TSParser> = std::cell::RefCell::new(TSParser::new());
+}
+
+/// Function to parse `content` with this thread's parser
+///
+/// Files are processed in parallel, so each rayon thread keeps a parser instead of
+/// creating one per file or per line.
+pub fn parse_with_thread_parser(content: &str, language: &TSLanguage) -> Result<Tree> {
+    PARSER.with(|parser| {
+        let mut parser = parser.borrow_mut();
+        parser.set_language(language)?;
+        parser
+            .parse(content, None)
+            .context("Failed to parse the file")
+    })
+}
+
+/// Gets the node type reported for a block, which may be split across sibling nodes
+fn split_node_type(split_function: Option<(Node, Node)>, node: Node) -> String {
+    match split_function {
+        // A C/C++ body split from its signatures is still a function definition
+        Some((_, last)) if last.kind() == "compound_statement" => "function_definition".to_string(),
+        Some((_, last)) => last.kind().to_string(),
+        None => node.kind().to_string(),
+    }
+}
+
 /// Function to parse a file and extract code blocks for the given line numbers
 pub fn parse_file_for_code_blocks(
     content: &str,
@@ -429,13 +524,8 @@ pub fn parse_file_for_code_blocks(
     // Get the tree-sitter language
     let language = language_impl.get_tree_sitter_language();
 
-    // Parse the file
-    let mut parser = TSParser::new();
-    parser.set_language(&language)?;
-
-    let tree = parser
-        .parse(content, None)
-        .context("Failed to parse the file")?;
+    // Parse the file, reusing this thread's parser or a tree cached for the same content
+    let tree = tree_cache::parse(content, extension, &language)?;
 
     let root_node = tree.root_node();
 
@@ -456,8 +546,10 @@ pub fn parse_file_for_code_blocks(
     let mut code_blocks: Vec<CodeBlock> = Vec::new();
     let mut seen_nodes: HashSet<(usize, usize)> = HashSet::new();
 
-    // Process each line number
-    for &line in line_numbers {
+    // Process each line number, in order so the blocks found do not depend on hash order
+    let mut lines: Vec<usize> = line_numbers.iter().copied().collect();
+    lines.sort_unstable();
+    for line in lines {
         let target_node = find_most_specific_node(root_node, line);
         let is_comment = target_node.kind() == "comment"
             || target_node.kind() == "line_comment"
@@ -532,6 +624,7 @@ pub fn parse_file_for_code_blocks(
                         parent_node_type: None,
                         parent_start_row: None,
                         parent_end_row: None,
+                        partial_parse: target_node.has_error() || context_node.has_error(),
                     });
 
                     if debug_mode {
@@ -562,6 +655,7 @@ pub fn parse_file_for_code_blocks(
                     parent_node_type: None,
                     parent_start_row: None,
                     parent_end_row: None,
+                    partial_parse: target_node.has_error(),
                 });
 
                 if debug_mode {
@@ -643,8 +737,11 @@ pub fn parse_file_for_code_blocks(
 
         // If we found an exact match, use it
         if let Some(exact_match) = exact_line_match {
-            let start_pos = exact_match.start_position();
-            let end_pos = exact_match.end_position();
+            // A signature inside an #if branch extends to the function body after it
+            let split_function = language_impl.find_split_function(exact_match, content.as_bytes());
+            let (first_node, last_node) = split_function.unwrap_or((exact_match, exact_match));
+            let start_pos = first_node.start_position();
+            let end_pos = last_node.end_position();
             let node_key = (start_pos.row, end_pos.row);
 
             if !seen_nodes.contains(&node_key) {
@@ -663,12 +760,13 @@ pub fn parse_file_for_code_blocks(
                 code_blocks.push(CodeBlock {
                     start_row: start_pos.row,
                     end_row: end_pos.row,
-                    start_byte: exact_match.start_byte(),
-                    end_byte: exact_match.end_byte(),
-                    node_type: exact_match.kind().to_string(),
+                    start_byte: first_node.start_byte(),
+                    end_byte: last_node.end_byte(),
+                    node_type: split_node_type(split_function, exact_match),
                     parent_node_type: None,
                     parent_start_row: None,
                     parent_end_row: None,
+                    partial_parse: first_node.has_error() || last_node.has_error(),
                 });
 
                 continue;
@@ -677,8 +775,11 @@ pub fn parse_file_for_code_blocks(
 
         // If no exact match found, fall back to the standard approach
         if let Some(node) = find_code_structure(root_node, line, extension) {
-            let start_pos = node.start_position();
-            let end_pos = node.end_position();
+            // A body after #if branches extends back to the signatures before it
+            let split_function = language_impl.find_split_function(node, content.as_bytes());
+            let (first_node, last_node) = split_function.unwrap_or((node, node));
+            let start_pos = first_node.start_position();
+            let end_pos = last_node.end_position();
             let node_key = (start_pos.row, end_pos.row);
 
             if seen_nodes.contains(&node_key) {
@@ -713,7 +814,7 @@ pub fn parse_file_for_code_blocks(
             }
 
             // Ensure we never have an empty node_type
-            let node_type = node.kind().to_string();
+            let node_type = split_node_type(split_function, node);
 
             // Check if this node has a parent that is a function or method
             let parent_info = if node_type == "struct_type" {
@@ -742,12 +843,13 @@ pub fn parse_file_for_code_blocks(
             code_blocks.push(CodeBlock {
                 start_row: start_pos.row,
                 end_row: end_pos.row,
-                start_byte: node.start_byte(),
-                end_byte: node.end_byte(),
+                start_byte: first_node.start_byte(),
+                end_byte: last_node.end_byte(),
                 node_type,
                 parent_node_type: parent_info.as_ref().map(|(t, _, _)| t.clone()),
                 parent_start_row: parent_info.as_ref().map(|(_, s, _)| *s),
                 parent_end_row: parent_info.as_ref().map(|(_, _, e)| *e),
+                partial_parse: first_node.has_error() || last_node.has_error(),
             });
         }
     }
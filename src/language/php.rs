@@ -33,6 +33,7 @@ impl LanguageImpl for PhpLanguage {
                 | "class_declaration"
                 | "interface_declaration"
                 | "trait_declaration"
+                | "enum_declaration"
         )
     }
 
@@ -52,11 +53,49 @@ impl LanguageImpl for PhpLanguage {
                         }
                         return true;
                     }
-                } else if child.kind() == "comment" {
-                    let comment = child.utf8_text(source).unwrap_or("");
-                    if comment.contains("@test") {
+                } else if child.kind() == "attribute_list" {
+                    // PHPUnit 10 attributes: #[Test], #[DataProvider(...)]
+                    let mut attribute_cursor = child.walk();
+                    let has_test_attribute = child
+                        .named_children(&mut attribute_cursor)
+                        .flat_map(|group| {
+                            let mut group_cursor = group.walk();
+                            group
+                                .named_children(&mut group_cursor)
+                                .map(|attribute| attribute.utf8_text(source).unwrap_or(""))
+                                .collect::<Vec<_>>()
+                        })
+                        .any(|attribute| attribute == "Test" || attribute.ends_with("\\Test"));
+                    if has_test_attribute {
                         if debug_mode {
-                            println!("DEBUG: Test node detected (PHP): @test annotation");
+                            println!("DEBUG: Test node detected (PHP): #[Test] attribute");
+                        }
+                        return true;
+                    }
+                }
+            }
+
+            // The @test docblock is the sibling before the method, not one of its children
+            if let Some(previous) = node.prev_named_sibling() {
+                if previous.kind() == "comment"
+                    && previous.end_position().row + 1 >= node.start_position().row
+                    && previous.utf8_text(source).unwrap_or("").contains("@test")
+                {
+                    if debug_mode {
+                        println!("DEBUG: Test node detected (PHP): @test annotation");
+                    }
+                    return true;
+                }
+            }
+        } else if node_type == "class_declaration" {
+            // PHPUnit test case classes
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                if child.kind() == "base_clause" {
+                    let base = child.utf8_text(source).unwrap_or("");
+                    if base.contains("TestCase") {
+                        if debug_mode {
+                            println!("DEBUG: Test node detected (PHP): TestCase class");
                         }
                         return true;
                     }
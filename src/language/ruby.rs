@@ -16,6 +16,44 @@ impl RubyLanguage {
     }
 }
 
+/// Methods whose blocks define RSpec and Minitest spec examples and groups
+const TEST_BLOCK_METHODS: &[&str] = &[
+    "describe",
+    "context",
+    "it",
+    "specify",
+    "scenario",
+    "feature",
+    "shared_examples",
+    "shared_context",
+    "test",
+];
+
+/// Helper function to check whether a call takes a `do ... end` or `{ ... }` block
+/// outside of any method
+///
+/// DSL blocks at the top level of a file or class (RSpec groups, rake tasks, routes) are
+/// the natural unit of code there. Blocks inside methods (`items.each do`) stay part of
+/// the method.
+fn is_dsl_block_call(node: &Node) -> bool {
+    if node.kind() != "call"
+        || !node
+            .child_by_field_name("block")
+            .is_some_and(|block| matches!(block.kind(), "do_block" | "block"))
+    {
+        return false;
+    }
+
+    let mut current = *node;
+    while let Some(parent) = current.parent() {
+        if matches!(parent.kind(), "method" | "singleton_method") {
+            return false;
+        }
+        current = parent;
+    }
+    true
+}
+
 impl LanguageImpl for RubyLanguage {
     fn get_tree_sitter_language(&self) -> TSLanguage {
         tree_sitter_ruby::LANGUAGE.into()
@@ -28,8 +66,8 @@ impl LanguageImpl for RubyLanguage {
     fn is_acceptable_parent(&self, node: &Node) -> bool {
         matches!(
             node.kind(),
-            "method" | "class" | "module" | "singleton_method"
-        )
+            "method" | "class" | "module" | "singleton_method" | "singleton_class"
+        ) || is_dsl_block_call(node)
     }
 
     fn is_test_node(&self, node: &Node, source: &[u8]) -> bool {
@@ -51,17 +89,24 @@ impl LanguageImpl for RubyLanguage {
                 }
             }
         } else if node_type == "call" {
-            let mut cursor = node.walk();
-            for child in node.children(&mut cursor) {
-                if child.kind() == "identifier" {
-                    let name = child.utf8_text(source).unwrap_or("");
-                    if name == "describe" || name == "it" || name == "context" || name == "specify"
-                    {
-                        if debug_mode {
-                            println!("DEBUG: Test node detected (Ruby): {} block", name);
-                        }
-                        return true;
+            if let Some(method) = node.child_by_field_name("method") {
+                let name = method.utf8_text(source).unwrap_or("");
+                if TEST_BLOCK_METHODS.contains(&name) {
+                    if debug_mode {
+                        println!("DEBUG: Test node detected (Ruby): {} block", name);
+                    }
+                    return true;
+                }
+            }
+        } else if node_type == "class" {
+            // Minitest::Test, ActiveSupport::TestCase and Test::Unit::TestCase subclasses
+            if let Some(superclass) = node.child_by_field_name("superclass") {
+                let superclass = superclass.utf8_text(source).unwrap_or("");
+                if superclass.contains("Test") {
+                    if debug_mode {
+                        println!("DEBUG: Test node detected (Ruby): test case class");
                     }
+                    return true;
                 }
             }
         }
@@ -18,8 +18,7 @@ impl SwiftLanguage {
 
 impl LanguageImpl for SwiftLanguage {
     fn get_tree_sitter_language(&self) -> TSLanguage {
-        // Create a new instance of the Language type from the current tree-sitter version
-        unsafe { std::mem::transmute(tree_sitter_swift::LANGUAGE) }
+        tree_sitter_swift::LANGUAGE.into()
     }
 
     fn get_extension(&self) -> &'static str {
@@ -27,17 +26,18 @@ impl LanguageImpl for SwiftLanguage {
     }
 
     fn is_acceptable_parent(&self, node: &Node) -> bool {
+        // The Swift grammar parses class, struct, enum, actor and extension declarations
+        // as `class_declaration`, distinguished only by their leading keyword
         matches!(
             node.kind(),
             "function_declaration"
+                | "init_declaration"
+                | "deinit_declaration"
+                | "subscript_declaration"
                 | "class_declaration"
-                | "struct_declaration"
-                | "enum_declaration"
                 | "protocol_declaration"
-                | "extension_declaration"
+                | "protocol_function_declaration"
                 | "typealias_declaration"
-                | "variable_declaration"
-                | "constant_declaration"
         )
     }
 
@@ -101,7 +101,10 @@ impl LanguageImpl for SwiftLanguage {
         let mut current = node;
 
         while let Some(parent) = current.parent() {
-            if parent.kind() == "function_declaration" {
+            if matches!(
+                parent.kind(),
+                "function_declaration" | "init_declaration" | "deinit_declaration"
+            ) {
                 return Some(parent);
             }
             current = parent;
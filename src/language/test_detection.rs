@@ -93,6 +93,56 @@ pub fn is_test_file(path: &Path) -> bool {
             return true;
         }
 
+        // Lua: *_spec.lua, *_test.lua, test_*.lua
+        if file_name.ends_with("_spec.lua")
+            || file_name.ends_with("_test.lua")
+            || file_name.starts_with("test_") && file_name.ends_with(".lua")
+        {
+            if _debug_mode {
+                println!("DEBUG: Test file detected (Lua pattern): {}", file_name);
+            }
+            return true;
+        }
+
+        // Haskell: *Spec.hs, *Test.hs
+        if file_name.ends_with("Spec.hs") || file_name.ends_with("Test.hs") {
+            if _debug_mode {
+                println!("DEBUG: Test file detected (Haskell pattern): {}", file_name);
+            }
+            return true;
+        }
+
+        // Objective-C: *Tests.m, *Test.m
+        if file_name.ends_with("Tests.m")
+            || file_name.ends_with("Test.m")
+            || file_name.ends_with("Tests.mm")
+            || file_name.ends_with("Test.mm")
+        {
+            if _debug_mode {
+                println!(
+                    "DEBUG: Test file detected (Objective-C pattern): {}",
+                    file_name
+                );
+            }
+            return true;
+        }
+
+        // Dart: *_test.dart
+        if file_name.ends_with("_test.dart") {
+            if _debug_mode {
+                println!("DEBUG: Test file detected (Dart pattern): {}", file_name);
+            }
+            return true;
+        }
+
+        // OCaml: *_test.ml, *_tests.ml
+        if file_name.ends_with("_test.ml") || file_name.ends_with("_tests.ml") {
+            if _debug_mode {
+                println!("DEBUG: Test file detected (OCaml pattern): {}", file_name);
+            }
+            return true;
+        }
+
         // PHP: *Test.php, Test*.php
         if file_name.ends_with("Test.php")
             || file_name.starts_with("Test") && file_name.ends_with(".php")
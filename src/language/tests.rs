@@ -7,8 +7,10 @@ extern crate tree_sitter_c;
 extern crate tree_sitter_c_sharp;
 extern crate tree_sitter_cpp;
 extern crate tree_sitter_go;
+extern crate tree_sitter_haskell;
 extern crate tree_sitter_java;
 extern crate tree_sitter_javascript;
+extern crate tree_sitter_lua;
 extern crate tree_sitter_php;
 extern crate tree_sitter_python;
 extern crate tree_sitter_ruby;
@@ -30,9 +32,15 @@ fn get_language(extension: &str) -> Option<Language> {
         "java" => Some(tree_sitter_java::LANGUAGE.into()),
         "rb" => Some(tree_sitter_ruby::LANGUAGE.into()),
         "swift" => Some(tree_sitter_swift::LANGUAGE.into()),
+        "m" | "mm" => Some(tree_sitter_objc::LANGUAGE.into()),
         "cs" => Some(tree_sitter_c_sharp::LANGUAGE.into()),
-        // It seems tree_sitter_php::LANGUAGE doesn't exist, so we'll return None for PHP
-        "php" => None,
+        "lua" => Some(tree_sitter_lua::LANGUAGE.into()),
+        "nix" => Some(tree_sitter_nix::LANGUAGE.into()),
+        "dart" => Some(tree_sitter_dart::language()),
+        "ml" => Some(tree_sitter_ocaml::LANGUAGE_OCAML.into()),
+        "mli" => Some(tree_sitter_ocaml::LANGUAGE_OCAML_INTERFACE.into()),
+        "hs" => Some(tree_sitter_haskell::LANGUAGE.into()),
+        "php" => Some(tree_sitter_php::LANGUAGE_PHP.into()),
         _ => None,
     }
 }
@@ -55,8 +63,14 @@ fn test_get_language() {
     assert!(get_language("java").is_some()); // Java
     assert!(get_language("rb").is_some()); // Ruby
     assert!(get_language("swift").is_some()); // Swift
+    assert!(get_language("m").is_some()); // Objective-C
     assert!(get_language("cs").is_some()); // C#
-    assert!(get_language("php").is_none()); // PHP (not supported in current tree-sitter version)
+    assert!(get_language("lua").is_some()); // Lua
+    assert!(get_language("nix").is_some()); // Nix
+    assert!(get_language("dart").is_some()); // Dart
+    assert!(get_language("ml").is_some()); // OCaml
+    assert!(get_language("hs").is_some()); // Haskell
+    assert!(get_language("php").is_some()); // PHP
 
     // Test unsupported language
     assert!(get_language("txt").is_none());
@@ -125,6 +139,7 @@ fn test_merge_code_blocks() {
             parent_node_type: None,
             parent_start_row: None,
             parent_end_row: None,
+            partial_parse: false,
         },
         CodeBlock {
             start_row: 5,
@@ -135,6 +150,7 @@ fn test_merge_code_blocks() {
             parent_node_type: None,
             parent_start_row: None,
             parent_end_row: None,
+            partial_parse: false,
         },
         // Overlapping block
         CodeBlock {
@@ -146,6 +162,7 @@ fn test_merge_code_blocks() {
             parent_node_type: None,
             parent_start_row: None,
             parent_end_row: None,
+            partial_parse: false,
         },
     ];
 
@@ -175,6 +192,7 @@ fn test_merge_code_blocks_no_overlap() {
             parent_node_type: None,
             parent_start_row: None,
             parent_end_row: None,
+            partial_parse: false,
         },
         CodeBlock {
             start_row: 15, // Changed from 10 to 15 to ensure gap > 10 lines
@@ -185,6 +203,7 @@ fn test_merge_code_blocks_no_overlap() {
             parent_node_type: None,
             parent_start_row: None,
             parent_end_row: None,
+            partial_parse: false,
         },
     ];
 
@@ -207,6 +226,7 @@ fn test_merge_code_blocks_struct_type() {
             parent_node_type: None,
             parent_start_row: None,
             parent_end_row: None,
+            partial_parse: false,
         },
         // This is more than 10 lines away, so they should not merge
         CodeBlock {
@@ -218,6 +238,7 @@ fn test_merge_code_blocks_struct_type() {
             parent_node_type: None,
             parent_start_row: None,
             parent_end_row: None,
+            partial_parse: false,
         },
     ];
 
@@ -237,6 +258,7 @@ fn test_merge_code_blocks_struct_type() {
             parent_node_type: None,
             parent_start_row: None,
             parent_end_row: None,
+            partial_parse: false,
         },
         // This is within 10 lines, so they should merge
         CodeBlock {
@@ -248,6 +270,7 @@ fn test_merge_code_blocks_struct_type() {
             parent_node_type: None,
             parent_start_row: None,
             parent_end_row: None,
+            partial_parse: false,
         },
     ];
 
@@ -757,3 +780,583 @@ fn print_ast_structure(node: tree_sitter::Node, depth: usize) {
         print_ast_structure(child, depth + 1);
     }
 }
+
+#[test]
+fn test_lua_code_blocks() {
+    let lua_code = r#"local M = {}
+
+local defaults = {
+  width = 80,
+  border = "rounded",
+}
+
+local function merge(opts)
+  return vim.tbl_extend("force", defaults, opts or {})
+end
+
+function M.setup(opts)
+  M.config = merge(opts)
+end
+
+M.handlers = {
+  on_attach = function(client)
+    client.server_capabilities.semanticTokensProvider = nil
+  end,
+}
+
+return M
+"#;
+
+    let mut line_numbers = HashSet::new();
+    line_numbers.insert(5); // Field in the defaults table
+    line_numbers.insert(9); // Body of the local function
+    line_numbers.insert(13); // Body of M.setup
+    line_numbers.insert(18); // Body of the on_attach field
+
+    let blocks = parse_file_for_code_blocks(lua_code, "lua", &line_numbers, true, None).unwrap();
+    let spans: Vec<(usize, usize, &str)> = blocks
+        .iter()
+        .map(|block| {
+            (
+                block.start_row + 1,
+                block.end_row + 1,
+                block.node_type.as_str(),
+            )
+        })
+        .collect();
+
+    assert!(
+        spans.contains(&(3, 6, "variable_declaration")),
+        "{:?}",
+        spans
+    );
+    assert!(
+        spans.contains(&(8, 10, "function_declaration")),
+        "{:?}",
+        spans
+    );
+    assert!(
+        spans.contains(&(12, 14, "function_declaration")),
+        "{:?}",
+        spans
+    );
+    assert!(spans.contains(&(17, 19, "field")), "{:?}", spans);
+}
+
+#[test]
+fn test_swift_code_blocks() {
+    let swift_code = r#"import Foundation
+
+protocol Cache {
+    func value(for key: String) -> Data?
+}
+
+struct MemoryCache: Cache {
+    var store: [String: Data] = [:]
+
+    func value(for key: String) -> Data? {
+        return store[key]
+    }
+}
+
+extension MemoryCache {
+    init(seed: [String: Data]) {
+        self.store = seed
+    }
+}
+
+final class Loader {
+    func load(_ url: URL) throws -> Data {
+        return try Data(contentsOf: url)
+    }
+}
+"#;
+
+    let mut line_numbers = HashSet::new();
+    line_numbers.insert(4); // Protocol requirement
+    line_numbers.insert(11); // Body of the struct method
+    line_numbers.insert(17); // Body of the initializer in the extension
+    line_numbers.insert(23); // Body of the class method
+
+    let blocks =
+        parse_file_for_code_blocks(swift_code, "swift", &line_numbers, true, None).unwrap();
+    let spans: Vec<(usize, usize, &str)> = blocks
+        .iter()
+        .map(|block| {
+            (
+                block.start_row + 1,
+                block.end_row + 1,
+                block.node_type.as_str(),
+            )
+        })
+        .collect();
+
+    assert!(
+        spans.contains(&(4, 4, "protocol_function_declaration")),
+        "{:?}",
+        spans
+    );
+    assert!(
+        spans.contains(&(10, 12, "function_declaration")),
+        "{:?}",
+        spans
+    );
+    assert!(spans.contains(&(16, 18, "init_declaration")), "{:?}", spans);
+    assert!(
+        spans.contains(&(22, 24, "function_declaration")),
+        "{:?}",
+        spans
+    );
+}
+
+#[test]
+fn test_objc_code_blocks() {
+    let objc_code = r#"#import <Foundation/Foundation.h>
+
+@protocol Cache
+- (NSData *)valueForKey:(NSString *)key;
+@end
+
+@interface MemoryCache : NSObject <Cache>
+@property (nonatomic, strong) NSMutableDictionary *store;
+@end
+
+@implementation MemoryCache
+- (NSData *)valueForKey:(NSString *)key {
+    return self.store[key];
+}
+@end
+
+static NSUInteger CacheSize(MemoryCache *cache) {
+    return cache.store.count;
+}
+"#;
+
+    let mut line_numbers = HashSet::new();
+    line_numbers.insert(4); // Protocol requirement
+    line_numbers.insert(8); // Property in the interface
+    line_numbers.insert(13); // Body of the method
+    line_numbers.insert(18); // Body of the C function
+
+    let blocks = parse_file_for_code_blocks(objc_code, "m", &line_numbers, true, None).unwrap();
+    let spans: Vec<(usize, usize, &str)> = blocks
+        .iter()
+        .map(|block| {
+            (
+                block.start_row + 1,
+                block.end_row + 1,
+                block.node_type.as_str(),
+            )
+        })
+        .collect();
+
+    assert!(spans.contains(&(4, 4, "method_declaration")), "{:?}", spans);
+    assert!(spans.contains(&(7, 9, "class_interface")), "{:?}", spans);
+    assert!(
+        spans.contains(&(12, 14, "method_definition")),
+        "{:?}",
+        spans
+    );
+    assert!(
+        spans.contains(&(17, 19, "function_definition")),
+        "{:?}",
+        spans
+    );
+}
+
+#[test]
+fn test_nix_code_blocks() {
+    let nix_code = r#"{ lib, stdenv }:
+
+let
+  version = "1.2.3";
+
+  mkFlags = features:
+    map (f: "--enable-${f}") features;
+in
+stdenv.mkDerivation {
+  pname = "hello";
+  inherit version;
+
+  meta = {
+    license = lib.licenses.gpl3Plus;
+  };
+}
+"#;
+
+    let mut line_numbers = HashSet::new();
+    line_numbers.insert(7); // Body of the function binding
+    line_numbers.insert(14); // Inside the nested attribute set
+
+    let blocks = parse_file_for_code_blocks(nix_code, "nix", &line_numbers, true, None).unwrap();
+    let spans: Vec<(usize, usize, &str)> = blocks
+        .iter()
+        .map(|block| {
+            (
+                block.start_row + 1,
+                block.end_row + 1,
+                block.node_type.as_str(),
+            )
+        })
+        .collect();
+
+    assert!(spans.contains(&(6, 7, "binding")), "{:?}", spans);
+    assert!(spans.contains(&(13, 15, "binding")), "{:?}", spans);
+}
+
+#[test]
+fn test_dart_code_blocks() {
+    let dart_code = r#"import 'package:flutter/material.dart';
+
+int add(int a, int b) {
+  return a + b;
+}
+
+class Counter extends StatelessWidget {
+  final int count;
+
+  const Counter({super.key, required this.count});
+
+  @override
+  Widget build(BuildContext context) {
+    return Column(children: [
+      Text('$count'),
+    ]);
+  }
+}
+"#;
+
+    let mut line_numbers = HashSet::new();
+    line_numbers.insert(4); // Body of the top-level function
+    line_numbers.insert(15); // Widget tree returned by build()
+
+    let blocks = parse_file_for_code_blocks(dart_code, "dart", &line_numbers, true, None).unwrap();
+    let spans: Vec<(usize, usize, &str)> = blocks
+        .iter()
+        .map(|block| {
+            (
+                block.start_row + 1,
+                block.end_row + 1,
+                block.node_type.as_str(),
+            )
+        })
+        .collect();
+
+    assert!(spans.contains(&(3, 5, "lambda_expression")), "{:?}", spans);
+    assert!(
+        spans.contains(&(13, 17, "class_member_definition")),
+        "{:?}",
+        spans
+    );
+
+    // Fields have no body of their own, so they come with their class
+    let field_line = HashSet::from([8]);
+    let blocks = parse_file_for_code_blocks(dart_code, "dart", &field_line, true, None).unwrap();
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(
+        (blocks[0].start_row + 1, blocks[0].end_row + 1),
+        (7, 18),
+        "{:?}",
+        blocks[0].node_type
+    );
+}
+
+#[test]
+fn test_ocaml_code_blocks() {
+    let ocaml_code = r#"let rec fact n =
+  if n = 0 then 1 else n * fact (n - 1)
+
+module Counter = struct
+  let count = ref 0
+
+  let incr () =
+    let next = !count + 1 in
+    count := next
+end
+
+module Make (Ord : Map.OrderedType) = struct
+  type t = Ord.t list
+end
+"#;
+
+    let mut line_numbers = HashSet::new();
+    line_numbers.insert(2); // Body of a top-level function
+    line_numbers.insert(9); // Body of a function in a module, under a local let
+    line_numbers.insert(13); // Type in a functor
+
+    let blocks = parse_file_for_code_blocks(ocaml_code, "ml", &line_numbers, true, None).unwrap();
+    let spans: Vec<(usize, usize, &str)> = blocks
+        .iter()
+        .map(|block| {
+            (
+                block.start_row + 1,
+                block.end_row + 1,
+                block.node_type.as_str(),
+            )
+        })
+        .collect();
+
+    assert!(spans.contains(&(1, 2, "value_definition")), "{:?}", spans);
+    assert!(spans.contains(&(7, 9, "value_definition")), "{:?}", spans);
+    assert!(spans.contains(&(13, 13, "type_definition")), "{:?}", spans);
+
+    let interface_code = r#"module Counter : sig
+  val incr : unit -> unit
+end
+"#;
+    let line = HashSet::from([2]);
+    let blocks = parse_file_for_code_blocks(interface_code, "mli", &line, true, None).unwrap();
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].node_type, "value_specification");
+}
+
+#[test]
+fn test_haskell_code_blocks() {
+    let haskell_code = r#"module Billing (total) where
+
+data Invoice = Invoice
+  { items :: [Int]
+  , paid :: Bool
+  }
+
+total :: Invoice -> Int
+total (Invoice [] _) = 0
+total (Invoice xs _) = foldr step 0 xs
+  where
+    step item acc = acc + item
+
+overdue = filter (not . paid)
+
+instance Show Invoice where
+  show inv = "Invoice " ++ show (total inv)
+
+prop_totalNonNegative :: Invoice -> Bool
+prop_totalNonNegative inv =
+  total inv >= 0
+"#;
+
+    let mut line_numbers = HashSet::new();
+    line_numbers.insert(5); // Field of the record
+    line_numbers.insert(12); // Local binding in the where clause
+    line_numbers.insert(14); // Bind without a signature
+    line_numbers.insert(17); // Method of the instance
+    line_numbers.insert(21); // Body of a QuickCheck property
+
+    let spans = |allow_tests: bool| -> Vec<(usize, usize, String)> {
+        parse_file_for_code_blocks(haskell_code, "hs", &line_numbers, allow_tests, None)
+            .unwrap()
+            .iter()
+            .map(|block| {
+                (
+                    block.start_row + 1,
+                    block.end_row + 1,
+                    block.node_type.clone(),
+                )
+            })
+            .collect()
+    };
+    let blocks = spans(true);
+    let has = |start: usize, end: usize, node_type: &str| {
+        blocks.contains(&(start, end, node_type.to_string()))
+    };
+
+    assert!(has(3, 6, "data_type"), "{:?}", blocks);
+    // The signature and both equations form one block
+    assert!(has(8, 12, "function"), "{:?}", blocks);
+    assert!(has(14, 14, "bind"), "{:?}", blocks);
+    assert!(has(17, 17, "function"), "{:?}", blocks);
+    assert!(has(19, 21, "function"), "{:?}", blocks);
+
+    // Properties are tests
+    assert!(
+        !spans(false).iter().any(|block| block.0 == 19),
+        "{:?}",
+        spans(false)
+    );
+}
+
+#[test]
+fn test_partial_parse_blocks() {
+    // A file mid-edit: the call on line 9 is never closed
+    let js_code = r#"import { db } from "./db";
+
+export class Ledger {
+  total() {
+    return this.entries.reduce((a, b) => a + b, 0);
+  }
+
+  settle(amount) {
+    const balance = this.total(
+    if (balance >= amount) {
+      this.entries.push(amount);
+    }
+    return balance > 0;
+  }
+}
+
+function helper() {
+  return 42;
+}
+"#;
+
+    let mut line_numbers = HashSet::new();
+    line_numbers.insert(13); // Statement the parser could not place
+    line_numbers.insert(15); // Stray closing brace at the top level
+    line_numbers.insert(18); // Body of a function after the error
+
+    let blocks = parse_file_for_code_blocks(js_code, "js", &line_numbers, true, None).unwrap();
+    let spans: Vec<(usize, usize, &str, bool)> = blocks
+        .iter()
+        .map(|block| {
+            (
+                block.start_row + 1,
+                block.end_row + 1,
+                block.node_type.as_str(),
+                block.partial_parse,
+            )
+        })
+        .collect();
+
+    // The stray brace has no valid node around it and is left to the context fallback
+    assert_eq!(
+        spans,
+        vec![
+            (3, 14, "class_declaration", true),
+            (17, 19, "function_declaration", false),
+        ]
+    );
+}
+
+#[test]
+fn test_c_preprocessor_blocks() {
+    let c_code = r#"#define MAX(a, b) \
+    ((a) > (b) ? (a) : (b))
+
+#ifdef _WIN32
+int read_all(HANDLE h)
+#else
+int read_all(int fd)
+#endif
+{
+    int total = 0;
+    total += chunk();
+    return total;
+}
+"#;
+
+    // Macro function definitions are blocks
+    let line_numbers: HashSet<usize> = [2].into_iter().collect();
+    let blocks = parse_file_for_code_blocks(c_code, "c", &line_numbers, true, None).unwrap();
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].node_type, "preproc_function_def");
+    assert_eq!(blocks[0].start_row, 0);
+
+    // The body and each signature give the whole function, from #ifdef to the closing brace
+    for line in [5, 7, 11] {
+        let line_numbers: HashSet<usize> = [line].into_iter().collect();
+        let blocks = parse_file_for_code_blocks(c_code, "c", &line_numbers, true, None).unwrap();
+        assert_eq!(blocks.len(), 1, "line {}", line);
+        assert_eq!(blocks[0].node_type, "function_definition");
+        assert_eq!(
+            (blocks[0].start_row + 1, blocks[0].end_row + 1),
+            (4, 13),
+            "line {}",
+            line
+        );
+    }
+}
+
+#[test]
+fn test_ruby_dsl_blocks_and_specs() {
+    let ruby_code = r#"class Invoice
+  class << self
+    def overdue
+      where(paid: false)
+    end
+  end
+
+  def total
+    items.sum do |item|
+      item.price * item.quantity
+    end
+  end
+end
+
+namespace :billing do
+  task :charge do
+    Invoice.overdue.each(&:charge!)
+  end
+end
+
+RSpec.describe Invoice do
+  it "sums the items" do
+    expect(invoice.total).to eq(10)
+  end
+end
+"#;
+
+    let line_numbers: HashSet<usize> = [4, 10, 17, 23].into_iter().collect();
+    let spans = |allow_tests: bool| -> Vec<(usize, usize, String)> {
+        parse_file_for_code_blocks(ruby_code, "rb", &line_numbers, allow_tests, None)
+            .unwrap()
+            .into_iter()
+            .map(|block| (block.start_row + 1, block.end_row + 1, block.node_type))
+            .collect()
+    };
+
+    // Blocks inside methods stay part of the method, top-level DSL blocks are their own blocks
+    let expected = vec![
+        (3, 5, "method".to_string()),
+        (8, 12, "method".to_string()),
+        (16, 18, "call".to_string()),
+    ];
+    assert_eq!(spans(false), expected);
+
+    // RSpec examples are tests
+    let mut with_tests = expected;
+    with_tests.push((22, 24, "call".to_string()));
+    assert_eq!(spans(true), with_tests);
+}
+
+#[test]
+fn test_php_enums_and_phpunit_tests() {
+    let php_code = r#"<?php
+enum Status: string
+{
+    case Paid = 'paid';
+}
+
+final class InvoiceTest extends TestCase
+{
+    /**
+     * @test
+     */
+    public function sumsItems(): void
+    {
+        $this->assertSame(10, $invoice->total());
+    }
+
+    #[Test]
+    public function chargesGateway(): void
+    {
+        $this->assertTrue($gateway->charged);
+    }
+}
+"#;
+
+    let line_numbers: HashSet<usize> = [4, 14, 20].into_iter().collect();
+    let blocks = parse_file_for_code_blocks(php_code, "php", &line_numbers, false, None).unwrap();
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].node_type, "enum_declaration");
+
+    let blocks = parse_file_for_code_blocks(php_code, "php", &line_numbers, true, None).unwrap();
+    let kinds: Vec<&str> = blocks.iter().map(|b| b.node_type.as_str()).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            "enum_declaration",
+            "method_declaration",
+            "method_declaration"
+        ]
+    );
+}
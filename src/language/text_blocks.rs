@@ -0,0 +1,43 @@
+// Text blocks - block extraction for file types without a tree-sitter grammar.
+//
+// GraphQL documents, Dockerfiles and CI YAML have a simple enough structure that a small
+// scanner can split them into definitions, stages and jobs. Lines inside one of these
+// blocks are returned as the whole block instead of a fixed window of context.
+
+use std::path::Path;
+
+use super::{ci_yaml, dockerfile, graphql};
+
+/// A block found by one of the text scanners
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextBlock {
+    /// 1-based line of the first line of the block
+    pub start_line: usize,
+    /// 1-based line of the last line of the block
+    pub end_line: usize,
+    /// Node type reported for the block (e.g. `object_type_definition`, `stage`, `job`)
+    pub kind: &'static str,
+    /// Name of the definition, stage or job, if it has one
+    pub name: Option<String>,
+}
+
+/// Function to find the text blocks of a file, empty when no scanner applies
+pub fn find_text_blocks(path: &Path, extension: &str, content: &str) -> Vec<TextBlock> {
+    if graphql::is_graphql_extension(extension) {
+        graphql::find_definitions(content)
+    } else if dockerfile::is_dockerfile(path) {
+        dockerfile::find_stages(content)
+    } else if matches!(extension, "yml" | "yaml") {
+        ci_yaml::find_jobs(content)
+    } else {
+        Vec::new()
+    }
+}
+
+/// Function to find the innermost block that contains a 1-based line
+pub fn block_at(blocks: &[TextBlock], line: usize) -> Option<&TextBlock> {
+    blocks
+        .iter()
+        .filter(|block| block.start_line <= line && line <= block.end_line)
+        .min_by_key(|block| block.end_line - block.start_line)
+}
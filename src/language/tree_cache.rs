@@ -0,0 +1,115 @@
+//! Parse trees kept between searches.
+//!
+//! A one-off search parses each file it matched once and is done with it. A long-running
+//! process such as `probe serve` searches the same files over and over, so it keeps their
+//! trees in a [`TreeCache`] and only parses a file again once its content changed. Trees are
+//! keyed by extension and content rather than by path: an edited file misses the cache
+//! without anyone telling it about the edit, and its old tree goes when the cache starts
+//! over.
+//!
+//! Parsing finds the cache through [`with_tree_cache`], as searches find their file system.
+
+use anyhow::Result;
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use tree_sitter::{Language as TSLanguage, Tree};
+
+use crate::language::parser::parse_with_thread_parser;
+
+/// Parse trees that searches on several threads share
+pub type SharedTreeCache = Arc<Mutex<TreeCache>>;
+
+/// Most trees a cache keeps; it starts over when it is full
+pub const MAX_CACHED_TREES: usize = 4096;
+
+/// The extension, length and content hash a tree was parsed from
+type TreeKey = (String, usize, u64);
+
+/// Parse trees of file contents, with how often they were found
+#[derive(Default)]
+pub struct TreeCache {
+    trees: HashMap<TreeKey, Tree>,
+    hits: usize,
+    misses: usize,
+}
+
+impl TreeCache {
+    /// Create an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of parses answered from the cache
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Number of parses that had to run
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+}
+
+/// Helper function to build the key of a file's tree
+fn tree_key(content: &str, extension: &str) -> TreeKey {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    (extension.to_string(), content.len(), hasher.finish())
+}
+
+/// Function to parse `content` of a file with `extension`, reusing the tree from the
+/// current cache when the same content was parsed before
+///
+/// Without a cache this is [`parse_with_thread_parser`]. The cache is only locked to look
+/// the tree up and to store it, so threads parse different files at the same time.
+pub fn parse(content: &str, extension: &str, language: &TSLanguage) -> Result<Tree> {
+    let Some(cache) = current() else {
+        return parse_with_thread_parser(content, language);
+    };
+    let key = tree_key(content, extension);
+    {
+        let mut cache = cache.lock().unwrap();
+        if let Some(tree) = cache.trees.get(&key).cloned() {
+            cache.hits += 1;
+            return Ok(tree);
+        }
+        cache.misses += 1;
+    }
+
+    let tree = parse_with_thread_parser(content, language)?;
+    let mut cache = cache.lock().unwrap();
+    if cache.trees.len() >= MAX_CACHED_TREES {
+        cache.trees.clear();
+    }
+    cache.trees.insert(key, tree.clone());
+    Ok(tree)
+}
+
+thread_local! {
+    static SCOPED: RefCell<Option<SharedTreeCache>> = const { RefCell::new(None) };
+}
+
+/// Function to get the tree cache parsing on this thread uses, if any
+pub fn current() -> Option<SharedTreeCache> {
+    SCOPED.with(|scoped| scoped.borrow().clone())
+}
+
+/// Function to run `f` with parsing on this thread using `cache`
+///
+/// `None` parses every file afresh. The previous cache is restored afterwards.
+pub fn with_tree_cache<T>(cache: Option<SharedTreeCache>, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<SharedTreeCache>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            SCOPED.with(|scoped| *scoped.borrow_mut() = previous);
+        }
+    }
+
+    let previous = SCOPED.with(|scoped| scoped.replace(cache));
+    let _restore = Restore(previous);
+    f()
+}
@@ -0,0 +1,218 @@
+// Visibility detection - tells exported/public symbols from private ones.
+//
+// Each language has its own rule: `pub` in Rust (`pub(crate)` and friends are not
+// exported), a capitalized name in Go, `export` in JavaScript and TypeScript, no leading
+// underscore in Python, and access modifiers in Java, Kotlin, C#, Swift and PHP. The rule
+// is applied to the first line of code of a block, after its attributes and comments.
+
+/// Visibility of the symbol a block declares
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// Exported from its module or package
+    Public,
+    /// Only visible inside its module, package, file or class
+    Private,
+}
+
+/// Which blocks `--visibility` keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisibilityFilter {
+    Any,
+    Public,
+    Private,
+}
+
+impl VisibilityFilter {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "any" => Some(VisibilityFilter::Any),
+            "public" => Some(VisibilityFilter::Public),
+            "private" => Some(VisibilityFilter::Private),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            VisibilityFilter::Any => "any",
+            VisibilityFilter::Public => "public",
+            VisibilityFilter::Private => "private",
+        }
+    }
+
+    /// Whether a block of the given visibility passes the filter
+    ///
+    /// Blocks whose visibility is unknown only pass `any`.
+    pub fn accepts(self, visibility: Option<Visibility>) -> bool {
+        match self {
+            VisibilityFilter::Any => true,
+            VisibilityFilter::Public => visibility == Some(Visibility::Public),
+            VisibilityFilter::Private => visibility == Some(Visibility::Private),
+        }
+    }
+}
+
+/// Helper function to find the first line of code, skipping attributes, decorators and comments
+///
+/// `hash_comments` is set for languages where `#` starts a comment.
+fn declaration_line(code: &str, hash_comments: bool) -> Option<&str> {
+    code.lines().map(str::trim).find(|line| {
+        let skipped = ["#[", "#!", "@", "//", "/*", "*", "\"\"\"", "'''"]
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+            || (hash_comments && line.starts_with('#'))
+            || (line.starts_with('[') && line.ends_with(']'));
+        !line.is_empty() && !skipped
+    })
+}
+
+/// Helper function to split a line into its leading words, up to the first punctuation
+fn leading_words(line: &str) -> Vec<&str> {
+    line.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$' || c == '#'))
+        .take_while(|word| !word.is_empty())
+        .collect()
+}
+
+fn private_if(declares: bool) -> Option<Visibility> {
+    declares.then_some(Visibility::Private)
+}
+
+fn rust_visibility(line: &str) -> Option<Visibility> {
+    if line.starts_with("pub(") {
+        return Some(Visibility::Private);
+    }
+    if line.starts_with("pub ") {
+        return Some(Visibility::Public);
+    }
+    let first = line.split_whitespace().next()?;
+    private_if(matches!(
+        first,
+        "fn" | "async"
+            | "unsafe"
+            | "const"
+            | "static"
+            | "struct"
+            | "enum"
+            | "union"
+            | "trait"
+            | "type"
+            | "mod"
+    ))
+}
+
+fn go_visibility(line: &str) -> Option<Visibility> {
+    let rest = line
+        .strip_prefix("func ")
+        .or_else(|| line.strip_prefix("type "))
+        .or_else(|| line.strip_prefix("var "))
+        .or_else(|| line.strip_prefix("const "))?
+        .trim_start();
+    // Methods: skip the receiver
+    let rest = match rest.strip_prefix('(') {
+        Some(receiver) => receiver.split_once(')')?.1.trim_start(),
+        None => rest,
+    };
+    let first = rest.chars().next()?;
+    if !first.is_alphabetic() {
+        return None;
+    }
+    Some(if first.is_uppercase() {
+        Visibility::Public
+    } else {
+        Visibility::Private
+    })
+}
+
+fn javascript_visibility(line: &str) -> Option<Visibility> {
+    if line.starts_with("export ") || line.starts_with("module.exports") {
+        return Some(Visibility::Public);
+    }
+    let first = *leading_words(line).first()?;
+    if first.starts_with('#') || matches!(first, "private" | "protected") {
+        return Some(Visibility::Private);
+    }
+    if first == "public" {
+        return Some(Visibility::Public);
+    }
+    private_if(matches!(
+        first,
+        "function"
+            | "async"
+            | "class"
+            | "abstract"
+            | "interface"
+            | "type"
+            | "enum"
+            | "const"
+            | "let"
+            | "var"
+            | "declare"
+            | "namespace"
+    ))
+}
+
+fn python_visibility(line: &str) -> Option<Visibility> {
+    let rest = line.strip_prefix("async ").unwrap_or(line);
+    let name = rest
+        .strip_prefix("def ")
+        .or_else(|| rest.strip_prefix("class "))?
+        .trim_start();
+    let dunder = name.starts_with("__") && name.split(['(', ':']).next()?.ends_with("__");
+    Some(if name.starts_with('_') && !dunder {
+        Visibility::Private
+    } else {
+        Visibility::Public
+    })
+}
+
+/// Rule for languages with access modifiers, given what an unmodified declaration is
+fn modifier_visibility(line: &str, default: Option<Visibility>) -> Option<Visibility> {
+    let words = leading_words(line);
+    if words.iter().any(|word| matches!(*word, "public" | "open")) {
+        return Some(Visibility::Public);
+    }
+    if words
+        .iter()
+        .any(|word| matches!(*word, "private" | "protected" | "internal" | "fileprivate"))
+    {
+        return Some(Visibility::Private);
+    }
+    let declares = words.iter().any(|word| {
+        matches!(
+            *word,
+            "class"
+                | "interface"
+                | "enum"
+                | "record"
+                | "struct"
+                | "protocol"
+                | "object"
+                | "fun"
+                | "func"
+                | "function"
+                | "val"
+        )
+    });
+    default.filter(|_| declares)
+}
+
+/// Function to find the visibility of the symbol declared by a block of code
+///
+/// `language` is a language name as detected by the language module. Returns `None` for
+/// blocks that declare nothing (statements, `impl` blocks) and languages without a rule.
+pub fn block_visibility(language: &str, code: &str) -> Option<Visibility> {
+    let line = declaration_line(code, matches!(language, "python" | "ruby" | "php"))?;
+    match language {
+        "rust" => rust_visibility(line),
+        "go" => go_visibility(line),
+        "javascript" | "typescript" => javascript_visibility(line),
+        "python" => python_visibility(line),
+        // Members without a modifier are public in Kotlin and PHP, internal in Swift and
+        // package-private in Java
+        "kotlin" | "php" => modifier_visibility(line, Some(Visibility::Public)),
+        "swift" | "java" => modifier_visibility(line, Some(Visibility::Private)),
+        "csharp" => modifier_visibility(line, None),
+        "c" | "cpp" => private_if(line.starts_with("static ")),
+        _ => None,
+    }
+}
@@ -2,23 +2,59 @@
 //!
 //! This crate provides a library interface to the probe functionality, enabling integration
 //! with other tools and testing.
+//!
+//! The entry points return typed results and print nothing, so probe can be embedded
+//! without running the CLI and parsing its output:
+//!
+//! - [`perform_probe`] searches with a [`search::SearchOptions`], and [`ProbeEngine`] keeps
+//!   the file list and tokenizer loaded across searches
+//! - [`extract_blocks`] extracts the blocks for `probe extract` style file specs
+//! - [`perform_query`] finds the matches of an ast-grep pattern
+//!
+//! Failures are reported as a [`ProbeError`].
 
 // Make the library available as `probe` within itself
 extern crate self as probe;
 
+pub mod case_study;
+pub mod definition;
+pub mod error;
+pub mod error_trace;
+pub mod eval;
 pub mod extract;
+pub mod fs_access;
+pub mod hierarchy;
+pub mod history;
+pub mod impls;
 pub mod language;
+pub mod mcp;
 pub mod models;
+pub mod output_file;
+pub mod path_encoding;
 pub mod query;
+pub mod query_history;
 pub mod ranking;
+pub mod restrict_root;
 pub mod search;
+pub mod serve;
+pub mod signature;
+pub mod similar;
+pub mod summarize;
+pub mod verify;
 
 // Re-export commonly used types for convenience
+pub use error::{ProbeError, ProbeResult};
 pub use extract::{
-    format_and_print_extraction_results, handle_extract, process_file_for_extraction,
+    extract_blocks, format_and_print_extraction_results, handle_extract,
+    process_file_for_extraction, ExtractBlocksOptions, Extraction,
 };
 pub use models::{CodeBlock, LimitedSearchResults, SearchLimits, SearchResult};
 pub use query::{format_and_print_query_results, perform_query, AstMatch, QueryOptions};
-pub use search::perform_probe;
+pub use search::{
+    perform_probe, perform_probe_async, perform_probe_prepared, perform_probe_with_session,
+    perform_probe_within_session, prepare_query, EngineOptions, PreparedQuery, ProbeEngine,
+    SearchRequest, SearchRoot, Session,
+};
+pub use signature::{perform_signature_search, SignatureMatch, SignatureOptions};
 
 // Tests are defined in their respective modules with #[cfg(test)]
@@ -1,19 +1,51 @@
-use anyhow::Result;
-use clap::{CommandFactory, Parser as ClapParser};
+use anyhow::{Context, Result};
+use clap::{ArgMatches, CommandFactory, FromArgMatches};
 use colored::*;
+use std::io::Write;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Instant;
 
+mod case_study;
 mod cli;
+mod definition;
+mod error;
+mod error_trace;
+mod eval;
 mod extract;
+mod fs_access;
+mod hierarchy;
+mod history;
+mod impls;
 mod language;
+mod mcp;
 mod models;
+mod output_file;
+mod path_encoding;
 mod query;
+mod query_history;
 mod ranking;
+mod restrict_root;
 mod search;
+mod serve;
+mod signature;
+mod similar;
+mod summarize;
+mod verify;
 
-use cli::{Args, Commands};
-use search::{format_and_print_search_results, perform_probe, SearchOptions};
+use cli::{Args, Commands, IndexCommands};
+use fs_access::{OverlayFs, ReadOnlyFs, RealFs};
+use output_file::{check_output_options, write_split_results, Compression, OutputFile};
+use restrict_root::RestrictRoot;
+use search::api_names::ApiNameTable;
+use search::content_hash::files_fingerprint;
+use search::empty_queries::REPHRASE_SUGGESTION;
+use search::match_guards::{DEFAULT_MAX_LINE_LENGTH, DEFAULT_MAX_MATCHES_PER_FILE};
+use search::query_limits::DEFAULT_MAX_QUERY_TERMS;
+use search::{
+    format_and_print_search_results, perform_probe, render_template_results, EngineOptions,
+    OutputSink, SearchOptions, SearchOutput, SearchRoot, WriterSink,
+};
 
 struct SearchParams {
     pattern: String,
@@ -30,9 +62,170 @@ struct SearchParams {
     allow_tests: bool,
     no_merge: bool,
     merge_threshold: Option<usize>,
+    merge_provenance: bool,
+    no_history: bool,
     dry_run: bool,
     format: String,
     session: Option<String>,
+    report_skipped: bool,
+    tests_for: Option<String>,
+    usages: bool,
+    template: Option<PathBuf>,
+    path_style: String,
+    follow_symlinks: bool,
+    metrics_endpoint: Option<String>,
+    trace_endpoint: Option<String>,
+    strict: bool,
+    roots: Vec<SearchRoot>,
+    max_depth: Option<usize>,
+    min_depth: Option<usize>,
+    newer_than: Option<std::time::Duration>,
+    older_than: Option<std::time::Duration>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    author: Option<String>,
+    snippet_window: Option<usize>,
+    oversize_strategy: String,
+    oversize_threshold: usize,
+    case_fold: bool,
+    code_terms: Option<String>,
+    acronyms: bool,
+    api_names: bool,
+    api_names_table: Option<PathBuf>,
+    preproc_context: bool,
+    embedded: bool,
+    force_language: Vec<String>,
+    within_session: Option<String>,
+    exclude_blocks: Vec<String>,
+    pin_blocks: Vec<String>,
+    filename_weight: f64,
+    include_dir_context: bool,
+    deprecated_penalty: f64,
+    visibility: String,
+    kinds: Vec<String>,
+    sort: String,
+    candidate_files: Option<usize>,
+    max_line_length: usize,
+    max_matches_per_file: usize,
+    max_total_matches: Option<usize>,
+    max_query_terms: usize,
+    code_pattern: bool,
+    output: Option<PathBuf>,
+    compress: Option<String>,
+    split_per_file: Option<PathBuf>,
+    restrict_root: Option<PathBuf>,
+}
+
+/// Pair each `--path` root with the `--ignore` patterns given after it
+///
+/// Patterns given before the first `--path` stay in `ignore` and apply to every root.
+/// Several PATH arguments are roots as well, ahead of the `--path` ones and with no ignores
+/// of their own, so that all of them are searched and ranked together.
+fn group_search_roots(
+    matches: &ArgMatches,
+    paths: &[PathBuf],
+    roots: Vec<PathBuf>,
+    ignore: Vec<String>,
+) -> (Vec<String>, Vec<SearchRoot>) {
+    let root_indices: Vec<usize> = matches
+        .indices_of("roots")
+        .map(|indices| indices.collect())
+        .unwrap_or_default();
+    let ignore_indices: Vec<usize> = matches
+        .indices_of("ignore")
+        .map(|indices| indices.collect())
+        .unwrap_or_default();
+
+    let mut shared = Vec::new();
+    let mut scoped: Vec<Vec<String>> = vec![Vec::new(); roots.len()];
+    for (pattern, index) in ignore.into_iter().zip(ignore_indices) {
+        match root_indices
+            .iter()
+            .rposition(|&root_index| root_index < index)
+        {
+            Some(root) => scoped[root].push(pattern),
+            None => shared.push(pattern),
+        }
+    }
+
+    let positional = match paths {
+        [_, _, ..] => paths.to_vec(),
+        _ => Vec::new(),
+    };
+    let roots = positional
+        .into_iter()
+        .map(|path| SearchRoot::new(path, Vec::new()))
+        .chain(
+            roots
+                .into_iter()
+                .zip(scoped)
+                .map(|(path, ignores)| SearchRoot::new(path, ignores)),
+        )
+        .collect();
+    (shared, roots)
+}
+
+/// Print the search results, or write them to the `--output` file or the
+/// `--split-per-file` directory
+fn emit_search_results(
+    params: &SearchParams,
+    compression: Option<Compression>,
+    results: &[models::SearchResult],
+    dry_run: bool,
+    query_plan: Option<&search::query::QueryPlan>,
+    errors: &[models::FileError],
+    corpus_fingerprint: Option<&str>,
+) -> Result<()> {
+    if let Some(dir) = &params.split_per_file {
+        colored::control::set_override(false);
+        let written = write_split_results(
+            dir,
+            compression,
+            results,
+            dry_run,
+            &params.format,
+            query_plan,
+            errors,
+        );
+        colored::control::unset_override();
+        let count = written?;
+
+        if params.format != "json" && params.format != "xml" {
+            println!("Results for {} files written to {}", count, dir.display());
+        }
+        return Ok(());
+    }
+
+    let Some(output) = &params.output else {
+        format_and_print_search_results(
+            results,
+            dry_run,
+            &params.format,
+            query_plan,
+            errors,
+            corpus_fingerprint,
+        );
+        return Ok(());
+    };
+
+    // Escape codes would end up in the file
+    colored::control::set_override(false);
+    let mut sink = WriterSink::new(OutputFile::create(output, compression)?, &params.format);
+    let written = sink.emit(&SearchOutput {
+        results,
+        errors,
+        query_plan,
+        dry_run,
+        corpus_fingerprint,
+    });
+    colored::control::unset_override();
+    written?;
+    sink.into_inner().finish()?;
+
+    if params.format != "json" && params.format != "xml" {
+        println!("Results written to {}", output.display());
+    }
+    Ok(())
 }
 
 fn handle_search(params: SearchParams) -> Result<()> {
@@ -42,12 +235,74 @@ fn handle_search(params: SearchParams) -> Result<()> {
         params.frequency_search
     };
 
-    println!("{} {}", "Pattern:".bold().green(), params.pattern);
-    println!(
-        "{} {}",
-        "Path:".bold().green(),
-        params.paths.first().unwrap().display()
-    );
+    // Set up metrics and trace export up front so a bad endpoint fails before searching
+    let telemetry_sink = match &params.metrics_endpoint {
+        Some(endpoint) => Some(search::telemetry::sink_for_endpoint(endpoint)?),
+        None => None,
+    };
+    let trace_layer = match &params.trace_endpoint {
+        Some(endpoint) => Some(search::trace_export::OtlpTraceLayer::new(endpoint)?),
+        None => None,
+    };
+
+    // Check the output file options before searching
+    let compression = check_output_options(
+        params
+            .output
+            .as_deref()
+            .or(params.split_per_file.as_deref()),
+        params.compress.as_deref(),
+        &params.format,
+    )?;
+
+    // Refuse paths outside --restrict-root before reading or writing anything
+    let restriction = RestrictRoot::from_options(params.restrict_root.as_deref())?;
+    if let Some(restriction) = &restriction {
+        let requested = params
+            .paths
+            .iter()
+            .chain(params.roots.iter().map(|root| &root.path))
+            .chain(&params.output)
+            .chain(&params.split_per_file)
+            .chain(&params.template);
+        for path in requested {
+            restriction.check(path)?;
+        }
+    }
+
+    // Read the template up front so a bad path fails before searching
+    let template = match &params.template {
+        Some(template_path) => {
+            Some(fs_access::read_to_string(template_path).with_context(|| {
+                format!("Failed to read template file: {}", template_path.display())
+            })?)
+        }
+        None => None,
+    };
+
+    // Templates control the whole output, so skip the header
+    if template.is_none() {
+        println!("{} {}", "Pattern:".bold().green(), params.pattern);
+        if params.roots.is_empty() {
+            println!(
+                "{} {}",
+                "Path:".bold().green(),
+                params.paths.first().unwrap().display()
+            );
+        }
+        for root in &params.roots {
+            if root.ignores.is_empty() {
+                println!("{} {}", "Path:".bold().green(), root.path.display());
+            } else {
+                println!(
+                    "{} {} (ignoring {})",
+                    "Path:".bold().green(),
+                    root.path.display(),
+                    root.ignores.join(", ")
+                );
+            }
+        }
+    }
 
     // Show advanced options if they differ from defaults
     let mut advanced_options = Vec::<String>::new();
@@ -66,6 +321,9 @@ fn handle_search(params: SearchParams) -> Result<()> {
     if params.exact {
         advanced_options.push("Exact match".to_string());
     }
+    if params.code_pattern {
+        advanced_options.push("Code pattern".to_string());
+    }
     if params.allow_tests {
         advanced_options.push("Including tests".to_string());
     }
@@ -81,8 +339,97 @@ fn handle_search(params: SearchParams) -> Result<()> {
     if let Some(session) = &params.session {
         advanced_options.push(format!("Session: {}", session));
     }
+    if params.report_skipped {
+        advanced_options.push("Report skipped files".to_string());
+    }
+    if let Some(symbol) = &params.tests_for {
+        advanced_options.push(format!("Tests for: {}", symbol));
+    }
+    if params.usages {
+        advanced_options.push("Usages first".to_string());
+    }
+    if let Some(author) = &params.author {
+        advanced_options.push(format!("Author: {}", author));
+    }
+    if let Some(window) = params.snippet_window {
+        advanced_options.push(format!("Snippet window: {} lines", window));
+    }
+    if params.case_fold {
+        advanced_options.push("Unicode case folding".to_string());
+    }
+    if let Some(language) = &params.code_terms {
+        advanced_options.push(format!("Code terms: {}", language));
+    }
+    if params.acronyms {
+        advanced_options.push("Acronym matching".to_string());
+    }
+    if let Some(table) = &params.api_names_table {
+        advanced_options.push(format!("API names: {}", table.display()));
+    } else if params.api_names {
+        advanced_options.push("API names".to_string());
+    }
+    if params.preproc_context {
+        advanced_options.push("Preprocessor context".to_string());
+    }
+    if params.embedded {
+        advanced_options.push("Embedded SQL/HTML".to_string());
+    }
+    if let Some(within_session) = &params.within_session {
+        advanced_options.push(format!("Within session: {}", within_session));
+    }
+    if !params.exclude_blocks.is_empty() {
+        advanced_options.push(format!(
+            "Excluded blocks: {}",
+            params.exclude_blocks.join(", ")
+        ));
+    }
+    if params.include_dir_context {
+        advanced_options.push("Directory context".to_string());
+    }
+    if params.visibility != "any" {
+        advanced_options.push(format!("Visibility: {}", params.visibility));
+    }
+    if !params.kinds.is_empty() {
+        advanced_options.push(format!("Kinds: {}", params.kinds.join(", ")));
+    }
+    if params.sort != "score" {
+        advanced_options.push(format!("Sort: {}", params.sort));
+    }
+    if let Some(k) = params.candidate_files {
+        advanced_options.push(format!("Candidate files: {}", k));
+    }
+    if params.max_line_length != DEFAULT_MAX_LINE_LENGTH {
+        advanced_options.push(format!("Max line length: {}", params.max_line_length));
+    }
+    if params.max_matches_per_file != DEFAULT_MAX_MATCHES_PER_FILE {
+        advanced_options.push(format!(
+            "Max matches per file: {}",
+            params.max_matches_per_file
+        ));
+    }
+    if let Some(max_total_matches) = params.max_total_matches {
+        advanced_options.push(format!("Max total matches: {}", max_total_matches));
+    }
+    if params.max_query_terms != DEFAULT_MAX_QUERY_TERMS {
+        advanced_options.push(format!("Max query terms: {}", params.max_query_terms));
+    }
+    if params.deprecated_penalty != 0.0 {
+        advanced_options.push(format!("Deprecated penalty: {}", params.deprecated_penalty));
+    }
+    if params.filename_weight != 1.0 {
+        advanced_options.push(format!("Filename weight: {}", params.filename_weight));
+    }
+    if !params.pin_blocks.is_empty() {
+        advanced_options.push(format!("Pinned blocks: {}", params.pin_blocks.join(", ")));
+    }
+    if params.oversize_strategy != "full" {
+        advanced_options.push(format!(
+            "Oversize strategy: {} (over {} lines)",
+            params.oversize_strategy, params.oversize_threshold
+        ));
+    }
 
-    if !advanced_options.is_empty() {
+    if !advanced_options.is_empty() && template.is_none() {
         println!(
             "{} {}",
             "Options:".bold().green(),
@@ -92,8 +439,12 @@ fn handle_search(params: SearchParams) -> Result<()> {
 
     let start_time = Instant::now();
 
-    // Create a vector with the pattern
-    let query = vec![params.pattern.clone()];
+    // Create a vector with the pattern, with identifiers in all their forms for --api-names
+    let query = match &params.api_names_table {
+        Some(table) => vec![ApiNameTable::load(table)?.expand_query(&params.pattern)],
+        None if params.api_names => vec![ApiNameTable::default().expand_query(&params.pattern)],
+        None => vec![params.pattern.clone()],
+    };
 
     let search_options = SearchOptions {
         path: params.paths.first().unwrap(),
@@ -112,32 +463,130 @@ fn handle_search(params: SearchParams) -> Result<()> {
         merge_threshold: params.merge_threshold,
         dry_run: params.dry_run,
         session: params.session.as_deref(),
+        report_skipped: params.report_skipped,
+        tests_for: params.tests_for.as_deref(),
+        usages: params.usages,
+        path_style: &params.path_style,
+        follow_symlinks: params.follow_symlinks,
+        strict: params.strict,
+        roots: &params.roots,
+        max_depth: params.max_depth,
+        min_depth: params.min_depth,
+        newer_than: params.newer_than,
+        older_than: params.older_than,
+        min_size: params.min_size,
+        max_size: params.max_size,
+        author: params.author.as_deref(),
+        snippet_window: params.snippet_window,
+        oversize_strategy: &params.oversize_strategy,
+        oversize_threshold: params.oversize_threshold,
+        case_fold: params.case_fold,
+        code_terms: params.code_terms.as_deref(),
+        acronyms: params.acronyms,
+        preproc_context: params.preproc_context,
+        embedded: params.embedded,
+        force_language: &params.force_language,
+        within_session: params.within_session.as_deref(),
+        exclude_blocks: &params.exclude_blocks,
+        pin_blocks: &params.pin_blocks,
+        filename_weight: params.filename_weight,
+        include_dir_context: params.include_dir_context,
+        deprecated_penalty: params.deprecated_penalty,
+        visibility: &params.visibility,
+        kinds: &params.kinds,
+        sort: &params.sort,
+        candidate_files: params.candidate_files,
+        max_line_length: params.max_line_length,
+        max_matches_per_file: params.max_matches_per_file,
+        max_total_matches: params.max_total_matches,
+        max_query_terms: params.max_query_terms,
+        code_pattern: params.code_pattern,
+    };
+
+    let run_search = || match telemetry_sink {
+        Some(sink) => search::telemetry::with_telemetry(sink, || perform_probe(&search_options)),
+        None => perform_probe(&search_options),
+    };
+    let mut limited_results = match trace_layer {
+        Some(layer) => search::trace_export::with_trace_export(layer, run_search)?,
+        None => run_search()?,
     };
 
-    let limited_results = perform_probe(&search_options)?;
+    // Files reached through symbolic links can lie outside --restrict-root
+    if let (Some(restriction), true) = (&restriction, params.follow_symlinks) {
+        limited_results
+            .results
+            .retain(|result| restriction.contains(&result.path()));
+    }
+
+    // Which blocks went into a merged result is only listed when asked for
+    if !params.merge_provenance {
+        for result in &mut limited_results.results {
+            result.merged_from = None;
+        }
+    }
 
     // Calculate search time
     let duration = start_time.elapsed();
 
-    // Create the query plan regardless of whether we have results
-    let query_plan = if search_options.queries.len() > 1 {
-        // Join multiple queries with AND
-        let combined_query = search_options.queries.join(" AND ");
-        crate::search::query::create_query_plan(&combined_query, search_options.exact).ok()
-    } else {
-        crate::search::query::create_query_plan(&search_options.queries[0], search_options.exact)
-            .ok()
-    };
+    let no_history = params.no_history
+        || std::env::var(query_history::NO_HISTORY_ENV).unwrap_or_default() == "1";
+    if !no_history {
+        query_history::record_search(
+            search_options.path,
+            &params.pattern,
+            &limited_results.results,
+        );
+    }
+
+    // Only JSON, XML and templates list it, and taking it stats every candidate file
+    let corpus_fingerprint =
+        if template.is_some() || params.format == "json" || params.format == "xml" {
+            files_fingerprint(&search_options)?
+        } else {
+            None
+        };
+
+    if let Some(template) = &template {
+        let rendered = render_template_results(
+            &limited_results.results,
+            template,
+            corpus_fingerprint.as_deref(),
+        )?;
+        match &params.output {
+            Some(output) => {
+                let mut file = OutputFile::create(output, compression)?;
+                file.write_all(rendered.as_bytes())?;
+                file.finish()?;
+            }
+            None => print!("{}", rendered),
+        }
+        return Ok(());
+    }
+
+    // Reuse the query plan the search prepared, regardless of whether we have results
+    let prepared =
+        crate::search::query::prepare_query(search_options.queries, search_options.exact).ok();
+    let query_plan = prepared.as_ref().map(|prepared| &prepared.plan);
 
     if limited_results.results.is_empty() {
         // For JSON and XML formats, still call format_and_print_search_results
         if params.format == "json" || params.format == "xml" {
-            format_and_print_search_results(
+            emit_search_results(
+                &params,
+                compression,
                 &limited_results.results,
                 search_options.dry_run,
-                &params.format,
-                query_plan.as_ref(),
-            );
+                query_plan,
+                &limited_results.errors,
+                corpus_fingerprint.as_deref(),
+            )?;
+            if limited_results.empty_query_cached {
+                eprintln!("No results (cached). {}", REPHRASE_SUGGESTION);
+            }
+        } else if limited_results.empty_query_cached {
+            println!("{}", "No results found (cached).".yellow().bold());
+            println!("{}", REPHRASE_SUGGESTION);
         } else {
             // For other formats, print the "No results found" message
             println!("{}", "No results found.".yellow().bold());
@@ -150,12 +599,15 @@ fn handle_search(params: SearchParams) -> Result<()> {
             println!();
         }
 
-        format_and_print_search_results(
+        emit_search_results(
+            &params,
+            compression,
             &limited_results.results,
             search_options.dry_run,
-            &params.format,
-            query_plan.as_ref(),
-        );
+            query_plan,
+            &limited_results.errors,
+            corpus_fingerprint.as_deref(),
+        )?;
 
         if !limited_results.skipped_files.is_empty() {
             if let Some(limits) = &limited_results.limits_applied {
@@ -170,6 +622,9 @@ fn handle_search(params: SearchParams) -> Result<()> {
                 if let Some(max_tokens) = limits.max_tokens {
                     println!("  {} {}", "Max tokens:".yellow(), max_tokens);
                 }
+                if let Some(post_merge_tokens) = limits.post_merge_tokens {
+                    println!("  {} {}", "Tokens after merging:".yellow(), post_merge_tokens);
+                }
 
                 println!();
                 println!(
@@ -193,12 +648,91 @@ fn handle_search(params: SearchParams) -> Result<()> {
         }
     }
 
+    // JSON and XML output carry IO errors in their own errors section
+    if !limited_results.errors.is_empty() && params.format != "json" && params.format != "xml" {
+        println!();
+        println!(
+            "{} {}",
+            "Files that could not be read:".yellow().bold(),
+            limited_results.errors.len()
+        );
+        for error in &limited_results.errors {
+            println!(
+                "  {} - {}: {}",
+                error.path.display(),
+                error.kind,
+                error.message
+            );
+        }
+    }
+
+    // Files the line length and match count guards did not search completely
+    if !limited_results.capped.is_empty() && params.format != "json" && params.format != "xml" {
+        println!();
+        println!(
+            "{} {}",
+            "Files searched partially:".yellow().bold(),
+            limited_results.capped.len()
+        );
+        for capped in &limited_results.capped {
+            println!("  {} - {}", capped.path.display(), capped.reason);
+        }
+    }
+
+    // Explain why files did not show up in the results
+    if let Some(skip_report) = &limited_results.skip_report {
+        println!();
+        println!("{} {}", "Skipped files:".yellow().bold(), skip_report.len());
+        for skipped in skip_report {
+            let suffix = if skipped.is_dir { "/" } else { "" };
+            println!(
+                "  {}{} - {}",
+                skipped.path.display(),
+                suffix,
+                skipped.reason
+            );
+        }
+    }
+
     Ok(())
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    args.resolve_auto_format();
+
+    // Every parallel stage runs on the global rayon pool, so capping it caps the search
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .context("Failed to set up the search threads")?;
+    }
+
+    // In read-only mode only the session and history caches may be written
+    let read_only_requested =
+        args.read_only || std::env::var(fs_access::READ_ONLY_ENV).unwrap_or_default() == "1";
+    let read_only = if read_only_requested {
+        let file_system = Arc::new(ReadOnlyFs::new(
+            RealFs,
+            vec![
+                search::Session::default_dir(),
+                query_history::QueryHistory::default_dir(),
+            ],
+        ));
+        fs_access::install(file_system.clone());
+        Some(file_system)
+    } else {
+        None
+    };
+
+    // Unsaved editor buffers are read in place of the files on disk
+    if let Some(overlay) = &args.overlay {
+        let buffers = fs_access::parse_overlay(overlay)?;
+        fs_access::install(Arc::new(OverlayFs::new(fs_access::current(), buffers)));
+    }
 
     match args.command {
         // When no subcommand provided and no pattern, show help
@@ -218,11 +752,13 @@ async fn main() -> Result<()> {
                 args.paths
             };
 
+            let (ignore, roots) = group_search_roots(&matches, &paths, args.roots, args.ignore);
+
             handle_search(SearchParams {
                 pattern,
                 paths,
                 files_only: args.files_only,
-                ignore: args.ignore,
+                ignore,
                 exclude_filenames: args.exclude_filenames,
                 reranker: args.reranker,
                 frequency_search: args.frequency_search,
@@ -233,9 +769,58 @@ async fn main() -> Result<()> {
                 allow_tests: args.allow_tests,
                 no_merge: args.no_merge,
                 merge_threshold: args.merge_threshold,
+                merge_provenance: args.merge_provenance,
+                no_history: args.no_history,
                 dry_run: args.dry_run,
                 format: args.format,
                 session: args.session,
+                report_skipped: args.report_skipped,
+                tests_for: args.tests_for,
+                usages: args.usages,
+                template: args.template,
+                path_style: args.path_style,
+                follow_symlinks: args.follow_symlinks,
+                metrics_endpoint: args.metrics_endpoint,
+                trace_endpoint: args.trace_endpoint,
+                strict: args.strict,
+                roots,
+                max_depth: args.max_depth,
+                min_depth: args.min_depth,
+                newer_than: args.newer_than,
+                older_than: args.older_than,
+                min_size: args.min_size,
+                max_size: args.max_size,
+                author: args.author,
+                snippet_window: args.snippet_window,
+                oversize_strategy: args.oversize_strategy,
+                oversize_threshold: args.oversize_threshold,
+                case_fold: args.case_fold,
+                code_terms: args.code_terms,
+                acronyms: args.acronyms,
+                api_names: args.api_names,
+                api_names_table: args.api_names_table,
+                preproc_context: args.preproc_context,
+                embedded: args.embedded,
+                force_language: args.force_language,
+                within_session: args.within_session,
+                exclude_blocks: args.exclude_blocks,
+                pin_blocks: args.pin_blocks,
+                filename_weight: args.filename_weight,
+                include_dir_context: args.include_dir_context,
+                deprecated_penalty: args.deprecated_penalty,
+                visibility: args.visibility,
+                kinds: args.kinds,
+                sort: args.sort,
+                candidate_files: args.candidate_files,
+                max_line_length: args.max_line_length,
+                max_matches_per_file: args.max_matches_per_file,
+                max_total_matches: args.max_total_matches,
+                max_query_terms: args.max_query_terms,
+                code_pattern: args.code_pattern,
+                output: args.output,
+                compress: args.compress,
+                split_per_file: args.split_per_file,
+                restrict_root: args.restrict_root,
             })?
         }
         Some(Commands::Search {
@@ -253,28 +838,130 @@ async fn main() -> Result<()> {
             allow_tests,
             no_merge,
             merge_threshold,
+            merge_provenance,
+            no_history,
             dry_run,
             format,
             session,
-        }) => handle_search(SearchParams {
-            pattern,
-            paths,
-            files_only,
-            ignore,
-            exclude_filenames,
-            reranker,
-            frequency_search,
-            exact,
-            max_results,
-            max_bytes,
-            max_tokens,
-            allow_tests,
-            no_merge,
-            merge_threshold,
-            dry_run,
-            format,
-            session,
-        })?,
+            report_skipped,
+            tests_for,
+            usages,
+            template,
+            path_style,
+            follow_symlinks,
+            metrics_endpoint,
+            trace_endpoint,
+            strict,
+            roots,
+            max_depth,
+            min_depth,
+            newer_than,
+            older_than,
+            min_size,
+            max_size,
+            author,
+            snippet_window,
+            oversize_strategy,
+            oversize_threshold,
+            case_fold,
+            code_terms,
+            acronyms,
+            api_names,
+            api_names_table,
+            preproc_context,
+            embedded,
+            force_language,
+            within_session,
+            exclude_blocks,
+            pin_blocks,
+            filename_weight,
+            include_dir_context,
+            deprecated_penalty,
+            visibility,
+            kinds,
+            sort,
+            candidate_files,
+            max_line_length,
+            max_matches_per_file,
+            max_total_matches,
+            max_query_terms,
+            code_pattern,
+            output,
+            compress,
+            split_per_file,
+            restrict_root,
+        }) => {
+            let search_matches = matches.subcommand_matches("search").unwrap();
+            let (ignore, roots) = group_search_roots(search_matches, &paths, roots, ignore);
+            handle_search(SearchParams {
+                pattern,
+                paths,
+                files_only,
+                ignore,
+                exclude_filenames,
+                reranker,
+                frequency_search,
+                exact,
+                max_results,
+                max_bytes,
+                max_tokens,
+                allow_tests,
+                no_merge,
+                merge_threshold,
+                merge_provenance,
+                no_history,
+                dry_run,
+                format,
+                session,
+                report_skipped,
+                tests_for,
+                usages,
+                template,
+                path_style,
+                follow_symlinks,
+                metrics_endpoint,
+                trace_endpoint,
+                strict,
+                roots,
+                max_depth,
+                min_depth,
+                newer_than,
+                older_than,
+                min_size,
+                max_size,
+                author,
+                snippet_window,
+                oversize_strategy,
+                oversize_threshold,
+                case_fold,
+                code_terms,
+                acronyms,
+                api_names,
+                api_names_table,
+                preproc_context,
+                embedded,
+                force_language,
+                within_session,
+                exclude_blocks,
+                pin_blocks,
+                filename_weight,
+                include_dir_context,
+                deprecated_penalty,
+                visibility,
+                kinds,
+                sort,
+                candidate_files,
+                max_line_length,
+                max_matches_per_file,
+                max_total_matches,
+                max_query_terms,
+                code_pattern,
+                output,
+                compress,
+                split_per_file,
+                restrict_root,
+            })?
+        }
         Some(Commands::Extract {
             files,
             ignore,
@@ -284,7 +971,14 @@ async fn main() -> Result<()> {
             to_clipboard,
             dry_run,
             diff,
+            diff_base,
+            spec,
+            suggest_related,
             allow_tests,
+            follow_symlinks,
+            output,
+            compress,
+            restrict_root,
         }) => extract::handle_extract(extract::ExtractOptions {
             files,
             custom_ignores: ignore,
@@ -294,7 +988,40 @@ async fn main() -> Result<()> {
             to_clipboard,
             dry_run,
             diff,
+            diff_base,
+            spec,
+            patch: None,
+            suggest_related,
+            allow_tests,
+            follow_symlinks,
+            output,
+            compress,
+            restrict_root,
+        })?,
+        Some(Commands::Preview {
+            patch,
+            context_lines,
+            format,
+            allow_tests,
+            restrict_root,
+        }) => extract::handle_extract(extract::ExtractOptions {
+            files: Vec::new(),
+            custom_ignores: Vec::new(),
+            context_lines,
+            format,
+            from_clipboard: false,
+            to_clipboard: false,
+            dry_run: false,
+            diff: false,
+            diff_base: None,
+            spec: None,
+            patch: Some(patch),
+            suggest_related: false,
             allow_tests,
+            follow_symlinks: false,
+            output: None,
+            compress: None,
+            restrict_root,
         })?,
         Some(Commands::Query {
             pattern,
@@ -304,7 +1031,30 @@ async fn main() -> Result<()> {
             allow_tests,
             max_results,
             format,
-        }) => query::handle_query(
+            restrict_root,
+        }) => {
+            if let Some(restriction) = RestrictRoot::from_options(restrict_root.as_deref())? {
+                restriction.check(&path)?;
+            }
+            query::handle_query(
+                &pattern,
+                &path,
+                language.as_deref(),
+                &ignore,
+                allow_tests,
+                max_results,
+                &format,
+            )?
+        }
+        Some(Commands::Sig {
+            pattern,
+            path,
+            language,
+            ignore,
+            allow_tests,
+            max_results,
+            format,
+        }) => signature::handle_signature_search(
             &pattern,
             &path,
             language.as_deref(),
@@ -313,6 +1063,164 @@ async fn main() -> Result<()> {
             max_results,
             &format,
         )?,
+        Some(Commands::History {
+            target,
+            max_commits,
+            versions,
+            format,
+        }) => history::handle_history(&target, max_commits, versions, &format)?,
+        Some(Commands::Errors {
+            type_name,
+            path,
+            ignore,
+            allow_tests,
+            max_results,
+            format,
+        }) => error_trace::handle_errors(
+            &type_name,
+            &path,
+            &ignore,
+            allow_tests,
+            max_results,
+            &format,
+        )?,
+        Some(Commands::Impls {
+            trait_name,
+            path,
+            ignore,
+            allow_tests,
+            max_results,
+            format,
+        }) => impls::handle_impls(
+            &trait_name,
+            &path,
+            &ignore,
+            allow_tests,
+            max_results,
+            &format,
+        )?,
+        Some(Commands::Def {
+            identifier,
+            path,
+            from,
+            ignore,
+            allow_tests,
+            max_results,
+            format,
+        }) => definition::handle_definition(
+            &identifier,
+            &path,
+            from.as_deref(),
+            &ignore,
+            allow_tests,
+            max_results,
+            &format,
+        )?,
+        Some(Commands::Spec {
+            symbol,
+            path,
+            ignore,
+            max_tokens,
+            format,
+        }) => case_study::handle_spec(&symbol, &path, &ignore, max_tokens, &format)?,
+        Some(Commands::Summarize {
+            path,
+            ignore,
+            allow_tests,
+            max_tokens,
+            format,
+        }) => summarize::handle_summarize(&path, &ignore, allow_tests, max_tokens, &format)?,
+        Some(Commands::Hierarchy {
+            type_name,
+            path,
+            ignore,
+            allow_tests,
+            depth,
+            format,
+        }) => hierarchy::handle_hierarchy(&type_name, &path, &ignore, allow_tests, depth, &format)?,
+        Some(Commands::HistoryQueries {
+            path,
+            limit,
+            format,
+        }) => query_history::handle_history_queries(&path, limit, &format)?,
+        Some(Commands::Replay { number, path }) => query_history::handle_replay(number, &path)?,
+        Some(Commands::Verify {
+            results,
+            rerun,
+            format,
+        }) => verify::handle_verify(&results, rerun.as_deref(), &format)?,
+        Some(Commands::Similar {
+            path,
+            file,
+            lines,
+            ignore,
+            allow_tests,
+            max_results,
+            min_similarity,
+            format,
+        }) => similar::handle_similar(
+            &path,
+            file.as_deref(),
+            lines.as_deref(),
+            &ignore,
+            allow_tests,
+            max_results,
+            min_similarity,
+            &format,
+        )?,
+        Some(Commands::Eval {
+            dataset,
+            max_results,
+            format,
+        }) => eval::handle_eval(&dataset, max_results, &format)?,
+        Some(Commands::Mcp) => mcp::handle_mcp()?,
+        Some(Commands::Serve {
+            path,
+            watch,
+            socket,
+            notify_url,
+            notify_queries,
+            auth_token,
+            rate_limit,
+            allow_paths,
+            ignore,
+            allow_tests,
+            exact,
+            max_results,
+            max_tokens,
+        }) => serve::handle_serve(
+            &path,
+            EngineOptions {
+                custom_ignores: ignore,
+                allow_tests,
+                exact,
+                max_results,
+                max_tokens,
+                ..EngineOptions::default()
+            },
+            watch,
+            socket.as_deref(),
+            notify_url.as_deref(),
+            &notify_queries,
+            auth_token,
+            rate_limit,
+            &allow_paths,
+        )?,
+        Some(Commands::Index { action }) => match action {
+            IndexCommands::Build { path } => search::index::handle_index(&path, false)?,
+            IndexCommands::Update { path } => search::index::handle_index(&path, true)?,
+        },
+    }
+
+    // Writes refused along the way, e.g. to the query history, didn't stop the command
+    if let Some(file_system) = read_only {
+        for path in file_system.refused_writes() {
+            eprintln!(
+                "{} {}",
+                "Read-only mode refused a write to".yellow().bold(),
+                path.display()
+            );
+        }
     }
 
     Ok(())
@@ -0,0 +1,506 @@
+//! Model Context Protocol server.
+//!
+//! `probe mcp` serves `search`, `extract` and `query` as MCP tools over stdio, so an agent
+//! can call probe directly instead of going through a wrapper process. Messages are JSON-RPC
+//! 2.0, one per line. Tool results are the JSON that `--format json` would print, and a
+//! failed tool call is reported as a tool result with `isError` set, its text starting with
+//! the [`ProbeError`] kind, so the agent can read what went wrong.
+//!
+//! Search sessions live as long as the server: a `search` call with an empty `session`
+//! starts one and reports its ID, and later calls passing that ID skip the blocks it has
+//! already returned.
+//!
+//! Searches of the same directory share [`CorpusStats`], so every client's query is ranked
+//! by the same term weights and none recounts the corpus. Files added or removed are picked
+//! up by the next search; a client that watches files sends a `notifications/filesChanged`
+//! notification with their `paths` so changed files are counted again.
+
+use anyhow::{anyhow, Context, Result};
+use rand::{distributions::Alphanumeric, Rng};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use crate::error::ProbeError;
+use crate::extract::{extract_blocks, ExtractBlocksOptions};
+use crate::fs_access;
+use crate::models::SearchResult;
+use crate::path_encoding::encode_path;
+use crate::query::{perform_query, QueryOptions};
+use crate::search::corpus_stats::{CorpusStats, SharedCorpusStats};
+use crate::search::empty_queries::REPHRASE_SUGGESTION;
+use crate::search::{EngineOptions, ProbeEngine, Session};
+
+/// Protocol revision the server implements
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Notification a client sends when files it watches change
+pub const FILES_CHANGED: &str = "notifications/filesChanged";
+
+/// Function to describe the tools the server offers, with the JSON schemas of their arguments
+pub fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "search",
+            "description": "Search code with an Elasticsearch-like query and return whole functions, classes and other blocks, ranked by relevance. Terms are stemmed and case-insensitive; use +term for required terms, -term to exclude, \"quotes\" for exact matches, and AND, OR and parentheses to combine. Pass an empty session to start a session, and its ID on later calls to skip blocks already returned.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Directory or file to search"
+                    },
+                    "query": {
+                        "type": "string",
+                        "description": "Search query"
+                    },
+                    "maxResults": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Maximum number of results to return"
+                    },
+                    "maxTokens": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Maximum number of tokens the results may use"
+                    },
+                    "allowTests": {
+                        "type": "boolean",
+                        "description": "Include test files and test blocks"
+                    },
+                    "exact": {
+                        "type": "boolean",
+                        "description": "Match terms exactly, without stemming or splitting identifiers"
+                    },
+                    "session": {
+                        "type": "string",
+                        "description": "Session ID from an earlier search, or an empty string to start a session"
+                    }
+                },
+                "required": ["path", "query"]
+            }
+        },
+        {
+            "name": "extract",
+            "description": "Extract code blocks from files. Each file spec is a path, optionally with a line (file.rs:42), a line range (file.rs:10-20) or a symbol (file.rs#parse); the enclosing function, class or other block is returned, or the whole file without a line or symbol.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "files": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "minItems": 1,
+                        "description": "File specs to extract"
+                    },
+                    "contextLines": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "description": "Number of context lines to include around a line"
+                    },
+                    "allowTests": {
+                        "type": "boolean",
+                        "description": "Include test files and test blocks"
+                    }
+                },
+                "required": ["files"]
+            }
+        },
+        {
+            "name": "query",
+            "description": "Find code structures matching an ast-grep pattern, such as `fn $NAME($$$PARAMS) $$$BODY`. $NAME matches one node and $$$NAME any number of them.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Directory or file to search"
+                    },
+                    "pattern": {
+                        "type": "string",
+                        "description": "ast-grep pattern"
+                    },
+                    "language": {
+                        "type": "string",
+                        "description": "Language of the pattern, such as rust or python (guessed from each file without it)"
+                    },
+                    "maxResults": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Maximum number of matches to return"
+                    },
+                    "allowTests": {
+                        "type": "boolean",
+                        "description": "Include test files"
+                    }
+                },
+                "required": ["path", "pattern"]
+            }
+        }
+    ])
+}
+
+/// An MCP server, holding the search sessions started through it
+#[derive(Default)]
+pub struct McpServer {
+    sessions: HashMap<String, Session>,
+    /// Corpus statistics of each searched directory, with and without test files
+    corpus_stats: HashMap<(PathBuf, bool), SharedCorpusStats>,
+}
+
+impl McpServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle one JSON-RPC message, returning the response to send back
+    ///
+    /// Notifications, which have no `id`, get no response.
+    pub fn handle_message(&mut self, message: &str) -> Option<Value> {
+        let message: Value = match serde_json::from_str(message) {
+            Ok(message) => message,
+            Err(e) => {
+                return Some(error_response(
+                    Value::Null,
+                    PARSE_ERROR,
+                    &format!("Parse error: {}", e),
+                ))
+            }
+        };
+
+        let id = message.get("id").cloned();
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            return Some(error_response(
+                id.unwrap_or(Value::Null),
+                INVALID_REQUEST,
+                "Invalid request: no method",
+            ));
+        };
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        let Some(id) = id else {
+            if method == FILES_CHANGED {
+                self.files_changed(&params);
+            }
+            return None;
+        };
+
+        let result = match method {
+            "initialize" => Ok(json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": { "tools": {} },
+                "serverInfo": {
+                    "name": "probe",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+            "tools/call" => self.handle_tool_call(&params),
+            _ => Err((METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+        };
+
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error_response(id, code, &message),
+        })
+    }
+
+    /// Run a tool; an unknown tool is a protocol error, a failing one a tool result
+    fn handle_tool_call(&mut self, params: &Value) -> std::result::Result<Value, (i64, String)> {
+        let Some(name) = params.get("name").and_then(Value::as_str) else {
+            return Err((INVALID_PARAMS, "Invalid params: no tool name".to_string()));
+        };
+        let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+
+        let output = match name {
+            "search" => self.search(&arguments),
+            "extract" => extract(&arguments),
+            "query" => query(&arguments),
+            _ => return Err((INVALID_PARAMS, format!("Unknown tool: {}", name))),
+        };
+
+        Ok(match output {
+            Ok(output) => json!({
+                "content": [{ "type": "text", "text": serde_json::to_string_pretty(&output).unwrap_or_default() }],
+                "isError": false,
+            }),
+            Err(e) => {
+                let error = ProbeError::from(e);
+                json!({
+                    "content": [{ "type": "text", "text": format!("{}: {}", error.kind(), error.message()) }],
+                    "isError": true,
+                })
+            }
+        })
+    }
+
+    /// The `search` tool
+    fn search(&mut self, arguments: &Value) -> Result<Value> {
+        let path = path_argument(arguments, "path")?;
+        let query = string_argument(arguments, "query")?;
+        let allow_tests = bool_argument(arguments, "allowTests")?;
+        let mut engine = ProbeEngine::with_options(
+            &path,
+            EngineOptions {
+                allow_tests,
+                exact: bool_argument(arguments, "exact")?,
+                max_results: usize_argument(arguments, "maxResults")?,
+                max_tokens: usize_argument(arguments, "maxTokens")?,
+                ..EngineOptions::default()
+            },
+        )?;
+        let stats = self
+            .corpus_stats
+            .entry((path, allow_tests))
+            .or_insert_with(|| Arc::new(RwLock::new(CorpusStats::new())));
+        engine.set_corpus_stats(Arc::clone(stats));
+
+        let session_id = match arguments.get("session") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(id)) if id.is_empty() => Some(self.new_session_id()),
+            Some(Value::String(id)) => Some(id.clone()),
+            Some(_) => return Err(anyhow!("Argument `session` must be a string")),
+        };
+
+        let results = match &session_id {
+            Some(id) => {
+                let session = self
+                    .sessions
+                    .entry(id.clone())
+                    .or_insert_with(|| Session::new(id.clone()));
+                engine.search_with_session(&query, session)?
+            }
+            None => engine.search(&query)?,
+        };
+
+        let mut output = json!({
+            "results": results.results.iter().map(result_json).collect::<Vec<_>>(),
+            "summary": {
+                "count": results.results.len(),
+                "total_bytes": results.results.iter().map(|r| r.code.len()).sum::<usize>(),
+            },
+        });
+        if !results.skipped_files.is_empty() {
+            // Results left out by maxResults or maxTokens
+            output["summary"]["skipped"] = results.skipped_files.len().into();
+        }
+        if results.empty_query_cached {
+            // The session found nothing for this query before, and no file changed since
+            output["summary"]["cached_empty"] = true.into();
+            output["suggestion"] = REPHRASE_SUGGESTION.into();
+        }
+        if let Some(id) = session_id {
+            output["session"] = id.into();
+            output["cached_blocks_skipped"] = results.cached_blocks_skipped.unwrap_or(0).into();
+        }
+        Ok(output)
+    }
+
+    /// Count changed files again in the statistics of the directories holding them
+    ///
+    /// Only files already counted are read; new ones are counted by the next search.
+    fn files_changed(&mut self, params: &Value) {
+        let paths: Vec<PathBuf> = match params.get("paths") {
+            Some(Value::Array(paths)) => paths
+                .iter()
+                .filter_map(Value::as_str)
+                .map(PathBuf::from)
+                .collect(),
+            _ => return,
+        };
+        for ((root, _), stats) in &self.corpus_stats {
+            for path in paths.iter().filter(|path| path.starts_with(root)) {
+                if !stats.read().unwrap().contains(path) {
+                    continue;
+                }
+                let content = fs_access::read_to_string(path);
+                let mut stats = stats.write().unwrap();
+                match content {
+                    Ok(content) => stats.update_file(path, &content),
+                    Err(_) => stats.remove_file(path),
+                }
+            }
+        }
+    }
+
+    /// Helper function to pick an unused 4-character session ID
+    fn new_session_id(&self) -> String {
+        loop {
+            let id: String = rand::thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(4)
+                .map(char::from)
+                .collect::<String>()
+                .to_lowercase();
+            if !self.sessions.contains_key(&id) {
+                return id;
+            }
+        }
+    }
+}
+
+/// The `extract` tool
+fn extract(arguments: &Value) -> Result<Value> {
+    let files: Vec<String> = match arguments.get("files") {
+        Some(Value::Array(files)) => files
+            .iter()
+            .map(|file| {
+                file.as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| anyhow!("Argument `files` must be an array of strings"))
+            })
+            .collect::<Result<_>>()?,
+        _ => return Err(anyhow!("Missing argument `files`")),
+    };
+
+    let extraction = extract_blocks(&ExtractBlocksOptions {
+        files: &files,
+        context_lines: usize_argument(arguments, "contextLines")?.unwrap_or(0),
+        allow_tests: bool_argument(arguments, "allowTests")?,
+        custom_ignores: &[],
+        follow_symlinks: false,
+    })?;
+
+    Ok(json!({
+        "results": extraction.results.iter().map(result_json).collect::<Vec<_>>(),
+        "errors": extraction.errors,
+        "summary": {
+            "count": extraction.results.len(),
+            "total_bytes": extraction.results.iter().map(|r| r.code.len()).sum::<usize>(),
+        },
+    }))
+}
+
+/// The `query` tool
+fn query(arguments: &Value) -> Result<Value> {
+    let path = path_argument(arguments, "path")?;
+    let pattern = string_argument(arguments, "pattern")?;
+    let language = match arguments.get("language") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(language)) => Some(language.as_str()),
+        Some(_) => return Err(anyhow!("Argument `language` must be a string")),
+    };
+
+    let matches = perform_query(&QueryOptions {
+        path: &path,
+        pattern: &pattern,
+        language,
+        ignore: &[],
+        allow_tests: bool_argument(arguments, "allowTests")?,
+        max_results: usize_argument(arguments, "maxResults")?,
+        format: "json",
+    })?;
+
+    let results: Vec<Value> = matches
+        .iter()
+        .map(|m| {
+            let encoded = encode_path(&m.file_path);
+            let mut json = json!({
+                "file": encoded.value,
+                "lines": [m.line_start, m.line_end],
+                "column_start": m.column_start,
+                "column_end": m.column_end,
+                "code": m.matched_text,
+            });
+            if let Some(encoding) = encoded.encoding {
+                json["file_encoding"] = encoding.into();
+            }
+            json
+        })
+        .collect();
+    Ok(json!({
+        "results": results,
+        "summary": { "count": matches.len() },
+    }))
+}
+
+/// Helper function to turn a search or extract result into JSON
+pub(crate) fn result_json(result: &SearchResult) -> Value {
+    let encoded = encode_path(&result.path());
+    let mut json = json!({
+        "file": encoded.value,
+        "lines": [result.lines.0, result.lines.1],
+        "node_type": result.node_type,
+        "code": result.code,
+    });
+    if let Some(encoding) = encoded.encoding {
+        json["file_encoding"] = encoding.into();
+    }
+    if let Some(score) = result.score {
+        json["score"] = score.into();
+    }
+    json
+}
+
+/// Helper function to read a required string argument
+fn string_argument(arguments: &Value, name: &str) -> Result<String> {
+    match arguments.get(name) {
+        Some(Value::String(value)) => Ok(value.clone()),
+        None | Some(Value::Null) => Err(anyhow!("Missing argument `{}`", name)),
+        Some(_) => Err(anyhow!("Argument `{}` must be a string", name)),
+    }
+}
+
+/// Helper function to read a required path argument, which must exist
+fn path_argument(arguments: &Value, name: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(string_argument(arguments, name)?);
+    if !path.exists() {
+        return Err(ProbeError::Io(format!("Path does not exist: {:?}", path)).into());
+    }
+    Ok(path)
+}
+
+/// Helper function to read an optional boolean argument, false when absent
+fn bool_argument(arguments: &Value, name: &str) -> Result<bool> {
+    match arguments.get(name) {
+        None | Some(Value::Null) => Ok(false),
+        Some(Value::Bool(value)) => Ok(*value),
+        Some(_) => Err(anyhow!("Argument `{}` must be a boolean", name)),
+    }
+}
+
+/// Helper function to read an optional non-negative integer argument
+fn usize_argument(arguments: &Value, name: &str) -> Result<Option<usize>> {
+    match arguments.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value
+            .as_u64()
+            .map(|n| Some(n as usize))
+            .ok_or_else(|| anyhow!("Argument `{}` must be a non-negative integer", name)),
+    }
+}
+
+/// Helper function to build a JSON-RPC error response
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+/// Handle the mcp command: serve requests from stdin until it closes
+pub fn handle_mcp() -> Result<()> {
+    let mut server = McpServer::new();
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+
+    for line in stdin.lock().lines() {
+        let line = line.context("Failed to read an MCP message from stdin")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = server.handle_message(&line) {
+            writeln!(stdout, "{}", response).context("Failed to write an MCP response")?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
@@ -1,3 +1,7 @@
+use std::path::PathBuf;
+
+use crate::language::visibility::VisibilityFilter;
+
 // Structure to hold both limited search results and skipped files
 #[derive(Debug)]
 pub struct LimitedSearchResults {
@@ -5,6 +9,208 @@ pub struct LimitedSearchResults {
     pub skipped_files: Vec<SearchResult>,
     pub limits_applied: Option<SearchLimits>,
     pub cached_blocks_skipped: Option<usize>,
+    // Files excluded from the results and why (only populated with --report-skipped)
+    pub skip_report: Option<Vec<SkippedFile>>,
+    // Files that could not be read because of IO errors
+    pub errors: Vec<FileError>,
+    // Files whose matches were cut short by the line length and match count guards
+    pub capped: Vec<CappedFile>,
+    // Whether the session's search found nothing for this query over the same files before,
+    // so it was answered without searching again
+    pub empty_query_cached: bool,
+}
+
+// A file (or directory) that could not be read during the search
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileError {
+    pub path: PathBuf,
+    pub kind: FileErrorKind,
+    pub message: String,
+}
+
+// The kind of IO error that made a file unreadable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FileErrorKind {
+    // The file or directory is not readable by this user (EACCES)
+    PermissionDenied,
+    // The file disappeared between the directory scan and reading it (ENOENT)
+    NotFound,
+    // Any other IO error
+    Other,
+}
+
+impl FileErrorKind {
+    pub fn from_io(error: &std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::PermissionDenied => FileErrorKind::PermissionDenied,
+            std::io::ErrorKind::NotFound => FileErrorKind::NotFound,
+            _ => FileErrorKind::Other,
+        }
+    }
+
+    // Name used in JSON and XML output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileErrorKind::PermissionDenied => "permission_denied",
+            FileErrorKind::NotFound => "not_found",
+            FileErrorKind::Other => "other",
+        }
+    }
+}
+
+impl std::fmt::Display for FileErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileErrorKind::PermissionDenied => write!(f, "permission denied"),
+            FileErrorKind::NotFound => write!(f, "not found"),
+            FileErrorKind::Other => write!(f, "IO error"),
+        }
+    }
+}
+
+// A file that was only partly searched because of a guard against pathological input
+#[derive(Debug, Clone, PartialEq)]
+pub struct CappedFile {
+    pub path: PathBuf,
+    pub reason: CapReason,
+}
+
+// The guard that cut the search of a file short
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapReason {
+    // This many lines were longer than --max-line-length and not searched
+    LongLines(usize),
+    // Scanning stopped after --max-matches-per-file matched lines
+    FileMatches(usize),
+    // --max-total-matches was reached in this file; this many files were not searched
+    TotalMatches(usize),
+}
+
+impl std::fmt::Display for CapReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CapReason::LongLines(lines) => {
+                write!(
+                    f,
+                    "{} lines longer than --max-line-length not searched",
+                    lines
+                )
+            }
+            CapReason::FileMatches(max) => {
+                write!(
+                    f,
+                    "stopped after {} matched lines (--max-matches-per-file)",
+                    max
+                )
+            }
+            CapReason::TotalMatches(unsearched) => write!(
+                f,
+                "--max-total-matches reached, {} remaining files not searched",
+                unsearched
+            ),
+        }
+    }
+}
+
+// A file (or directory) that was excluded from the search results
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedFile {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub reason: SkipReason,
+}
+
+// The reason a file was excluded from the search results
+#[derive(Debug, Clone, PartialEq)]
+pub enum SkipReason {
+    // Matched a .gitignore, .ignore or global git exclude rule
+    GitIgnore,
+    // Matched a pattern passed with --ignore
+    CustomIgnore(String),
+    // Matched one of the built-in ignore patterns (build output, binaries, etc.)
+    DefaultIgnore(String),
+    // Matched a test file pattern and --allow-tests was not set
+    TestFilter,
+    // Test file that does not reference the symbol given with --tests-for
+    UnrelatedTest(String),
+    // Hidden file or directory
+    Hidden,
+    // Symbolic link and --follow-symlinks was not set
+    Symlink,
+    // Outside the --min-depth/--max-depth range
+    Depth,
+    // Modified outside the --newer-than/--older-than range
+    Modified,
+    // Size outside the --min-size/--max-size range
+    Size,
+    // Reached through a symbolic link, but the target file was already included
+    LinkedDuplicate,
+    // Content is not valid UTF-8
+    Binary,
+    // Could not be read
+    Unreadable(String),
+    // All matching blocks were already returned in this session
+    CacheHit,
+    // All matching blocks were excluded with --exclude-blocks
+    Excluded,
+    // All matching blocks were ignored by probe:ignore-next-block comments
+    Directive,
+    // No matching block declares a symbol of the --visibility requested
+    Visibility(VisibilityFilter),
+    // No matching block is of the --kind requested (the kinds, comma-separated)
+    Kind(String),
+    // Last changed by an author not matching the --author pattern
+    Author(String),
+    // Not among the files selected with --candidate-files
+    NotCandidate,
+    // Not searched because --max-total-matches was reached
+    MatchLimit,
+    // Dropped because --max-results was reached
+    Limit,
+    // Dropped because it would exceed --max-bytes or --max-tokens
+    TooLarge,
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::GitIgnore => write!(f, "matched a .gitignore rule"),
+            SkipReason::CustomIgnore(pattern) => {
+                write!(f, "matched custom ignore pattern '{}'", pattern)
+            }
+            SkipReason::DefaultIgnore(pattern) => {
+                write!(f, "matched default ignore pattern '{}'", pattern)
+            }
+            SkipReason::TestFilter => write!(f, "test file (use --allow-tests to include)"),
+            SkipReason::UnrelatedTest(symbol) => {
+                write!(f, "test file does not reference '{}'", symbol)
+            }
+            SkipReason::Hidden => write!(f, "hidden file"),
+            SkipReason::Symlink => write!(f, "symbolic link (use --follow-symlinks to include)"),
+            SkipReason::Depth => write!(f, "outside the --min-depth/--max-depth range"),
+            SkipReason::Modified => {
+                write!(f, "modified outside the --newer-than/--older-than range")
+            }
+            SkipReason::Size => write!(f, "size outside the --min-size/--max-size range"),
+            SkipReason::LinkedDuplicate => write!(f, "symbolic link to a file already included"),
+            SkipReason::Binary => write!(f, "binary or non-UTF-8 file"),
+            SkipReason::Unreadable(error) => write!(f, "could not be read: {}", error),
+            SkipReason::Author(pattern) => {
+                write!(f, "last changed by an author not matching '{}'", pattern)
+            }
+            SkipReason::Visibility(filter) => {
+                write!(f, "no {} symbols among its matches", filter.as_str())
+            }
+            SkipReason::Kind(kinds) => write!(f, "no {} results among its matches", kinds),
+            SkipReason::CacheHit => write!(f, "already returned in this session"),
+            SkipReason::Excluded => write!(f, "excluded with --exclude-blocks"),
+            SkipReason::Directive => write!(f, "ignored by a probe:ignore-next-block comment"),
+            SkipReason::MatchLimit => write!(f, "not searched, --max-total-matches reached"),
+            SkipReason::NotCandidate => write!(f, "not among the --candidate-files best files"),
+            SkipReason::Limit => write!(f, "max results limit reached"),
+            SkipReason::TooLarge => write!(f, "too large for the byte/token limit"),
+        }
+    }
 }
 
 // Structure to track which limits were applied
@@ -18,12 +224,16 @@ pub struct SearchLimits {
     pub total_bytes: usize,
     #[allow(dead_code)]
     pub total_tokens: usize,
+    // Tokens of the results once merged, which merging can add to (set when blocks were merged)
+    pub post_merge_tokens: Option<usize>,
 }
 
 // Structure to hold search results
 #[derive(Debug, Clone)]
 pub struct SearchResult {
     pub file: String,
+    // Exact path of the file on disk; `file` is its lossy UTF-8 form for display
+    pub file_path: Option<PathBuf>,
     pub lines: (usize, usize),
     pub node_type: String,
     pub code: String,
@@ -33,6 +243,8 @@ pub struct SearchResult {
     pub rank: Option<usize>,
     // Combined score from the ranking algorithm
     pub score: Option<f64>,
+    // Score normalized to 0-1 so that files, merged blocks and blocks are comparable
+    pub normalized_score: Option<f64>,
     // Individual TF-IDF score
     pub tfidf_score: Option<f64>,
     // Individual BM25 score
@@ -67,6 +279,199 @@ pub struct SearchResult {
     /// Tokenized version of the code block with filename prepended
     #[allow(dead_code)]
     pub tokenized_content: Option<Vec<String>>,
+    // Original blocks that were merged into this result (None if the block was never merged)
+    pub merged_from: Option<Vec<MergedBlockInfo>>,
+    // Whether this result defines or uses the searched symbol (only set with --usages)
+    pub usage_kind: Option<UsageKind>,
+    // Whether the file was reached through a symbolic link below the search root
+    pub via_symlink: bool,
+    // Lines inside the block that differ from the diff base (only set for diff extraction)
+    pub changed_lines: Option<Vec<ChangedLine>>,
+    // Name of the symbol the block defines, when the parser knows it
+    pub symbol_name: Option<String>,
+    // Language of the file, as detected by the language module
+    pub language: Option<String>,
+    // How the block boundaries were found (AST node, heuristic or whole file)
+    pub extraction_confidence: Option<ExtractionConfidence>,
+    // Whether the block contains syntax errors the parser recovered from
+    pub partial_parse: bool,
+    // Whether the block carries a deprecation marker (#[deprecated], @Deprecated, @deprecated)
+    pub deprecated: bool,
+    // The block this embedded SQL or HTML string sits in (only set with --embedded)
+    pub embedded: Option<EmbeddedLocation>,
+    // What the block is to the query: a definition, call site, import, comment, string or test
+    pub kind: Option<ResultKind>,
+    // Size, nesting and branching of the block's code (set for extracted blocks)
+    pub complexity: Option<BlockComplexity>,
+    // Hash of the file content the block was extracted from, see `search::content_hash`
+    pub content_hash: Option<String>,
+}
+
+impl SearchResult {
+    /// The path of the file this result came from, exact even when it is not valid UTF-8
+    pub fn path(&self) -> PathBuf {
+        self.file_path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(&self.file))
+    }
+}
+
+// Cheap measures of a block's code, see `search::complexity`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BlockComplexity {
+    pub lines: usize,
+    // Indentation levels of the deepest line below the first line
+    pub nesting_depth: usize,
+    // One plus the number of branch keywords and short-circuit operators
+    pub cyclomatic: usize,
+}
+
+// What a result is to the query, from the syntax around the query terms in it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultKind {
+    // Declares a queried symbol
+    Definition,
+    // Calls a queried symbol
+    CallSite,
+    // Imports a queried symbol
+    Import,
+    // Only mentions the query terms in comments
+    Comment,
+    // Only mentions the query terms in string literals
+    String,
+    // Is, or sits in, a test
+    Test,
+}
+
+impl ResultKind {
+    /// Names accepted by `--kind`
+    pub const NAMES: &'static [&'static str] = &[
+        "definition",
+        "call_site",
+        "import",
+        "comment",
+        "string",
+        "test",
+    ];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "definition" => Some(ResultKind::Definition),
+            "call_site" => Some(ResultKind::CallSite),
+            "import" => Some(ResultKind::Import),
+            "comment" => Some(ResultKind::Comment),
+            "string" => Some(ResultKind::String),
+            "test" => Some(ResultKind::Test),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResultKind::Definition => "definition",
+            ResultKind::CallSite => "call_site",
+            ResultKind::Import => "import",
+            ResultKind::Comment => "comment",
+            ResultKind::String => "string",
+            ResultKind::Test => "test",
+        }
+    }
+}
+
+// Role a result plays for the symbol named in the query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageKind {
+    Definition,
+    Usage,
+}
+
+// How a line in the new version of a file relates to the diff base
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineChange {
+    // No counterpart in the base version
+    Added,
+    // Replaces one or more removed lines
+    Modified,
+}
+
+impl LineChange {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineChange::Added => "added",
+            LineChange::Modified => "modified",
+        }
+    }
+
+    /// Marker shown in the gutter of terminal output
+    pub fn gutter(&self) -> char {
+        match self {
+            LineChange::Added => '+',
+            LineChange::Modified => '~',
+        }
+    }
+}
+
+// How reliable the boundaries of an extracted block are, from least to most reliable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ExtractionConfidence {
+    // The whole file was returned because no block could be found
+    WholeFile,
+    // A context window or a text scanner picked the lines
+    Heuristic,
+    // The block is a node of the file's syntax tree
+    Ast,
+}
+
+impl ExtractionConfidence {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExtractionConfidence::WholeFile => "whole_file",
+            ExtractionConfidence::Heuristic => "heuristic",
+            ExtractionConfidence::Ast => "ast",
+        }
+    }
+}
+
+// Language of the content of a string literal searched with --embedded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddedLanguage {
+    Sql,
+    Html,
+}
+
+impl EmbeddedLanguage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmbeddedLanguage::Sql => "sql",
+            EmbeddedLanguage::Html => "html",
+        }
+    }
+}
+
+// Position of an embedded string literal reported as a result of its own
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddedLocation {
+    pub language: EmbeddedLanguage,
+    // Lines of the enclosing block, numbered like the file
+    pub host_lines: (usize, usize),
+    pub host_node_type: String,
+}
+
+// A changed line inside an extracted block, numbered like the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChangedLine {
+    pub line: usize,
+    pub kind: LineChange,
+}
+
+// Provenance record for a block that was folded into a merged result
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergedBlockInfo {
+    pub block_id: Option<usize>,
+    pub lines: (usize, usize),
+    pub node_type: String,
+    pub rank: Option<usize>,
+    pub score: Option<f64>,
 }
 
 // Structure to hold node information for merging
@@ -83,4 +488,6 @@ pub struct CodeBlock {
     pub parent_node_type: Option<String>,
     pub parent_start_row: Option<usize>,
     pub parent_end_row: Option<usize>,
+    // Whether the block contains syntax errors the parser recovered from
+    pub partial_parse: bool,
 }
@@ -8,6 +8,7 @@ mod tests {
     fn test_search_result_creation() {
         let result = SearchResult {
             file: "test.rs".to_string(),
+            file_path: None,
             lines: (1, 10),
             node_type: "function".to_string(),
             code: "fn test() {}".to_string(),
@@ -21,6 +22,17 @@ mod tests {
             file_unique_terms: None,
             file_total_matches: None,
             file_match_rank: None,
+            via_symlink: false,
+            changed_lines: None,
+            symbol_name: None,
+            language: None,
+            extraction_confidence: None,
+            partial_parse: false,
+            deprecated: false,
+            embedded: None,
+            kind: None,
+            complexity: None,
+            content_hash: None,
         };
         
         assert_eq!(result.file, "test.rs");
@@ -47,6 +59,7 @@ mod tests {
             start_byte: 0,
             end_byte: 100,
             node_type: "function".to_string(),
+            partial_parse: false,
         };
         
         assert_eq!(block.start_row, 1);
@@ -62,6 +75,7 @@ mod tests {
         let results = vec![
             SearchResult {
                 file: "test1.rs".to_string(),
+                file_path: None,
                 lines: (1, 10),
                 node_type: "function".to_string(),
                 code: "fn test1() {}".to_string(),
@@ -75,9 +89,21 @@ mod tests {
                 file_unique_terms: Some(2),
                 file_total_matches: Some(5),
                 file_match_rank: Some(1),
+                via_symlink: false,
+                changed_lines: None,
+                symbol_name: None,
+                language: None,
+                extraction_confidence: None,
+                partial_parse: false,
+                deprecated: false,
+                embedded: None,
+                kind: None,
+                complexity: None,
+                content_hash: None,
             },
             SearchResult {
                 file: "test2.rs".to_string(),
+                file_path: None,
                 lines: (1, 10),
                 node_type: "function".to_string(),
                 code: "fn test2() {}".to_string(),
@@ -91,6 +117,17 @@ mod tests {
                 file_unique_terms: Some(1),
                 file_total_matches: Some(3),
                 file_match_rank: Some(2),
+                via_symlink: false,
+                changed_lines: None,
+                symbol_name: None,
+                language: None,
+                extraction_confidence: None,
+                partial_parse: false,
+                deprecated: false,
+                embedded: None,
+                kind: None,
+                complexity: None,
+                content_hash: None,
             },
         ];
         
@@ -98,6 +135,7 @@ mod tests {
         let skipped_files = vec![
             SearchResult {
                 file: "test3.rs".to_string(),
+                file_path: None,
                 lines: (1, 10),
                 node_type: "function".to_string(),
                 code: "fn test3() {}".to_string(),
@@ -111,6 +149,17 @@ mod tests {
                 file_unique_terms: Some(1),
                 file_total_matches: Some(2),
                 file_match_rank: Some(3),
+                via_symlink: false,
+                changed_lines: None,
+                symbol_name: None,
+                language: None,
+                extraction_confidence: None,
+                partial_parse: false,
+                deprecated: false,
+                embedded: None,
+                kind: None,
+                complexity: None,
+                content_hash: None,
             },
         ];
         
@@ -121,6 +170,7 @@ mod tests {
             max_tokens: Some(200),
             total_bytes: 24,
             total_tokens: 6,
+            post_merge_tokens: None,
         };
         
         // Create the limited search results
@@ -128,6 +178,9 @@ mod tests {
             results: results.clone(),
             skipped_files: skipped_files.clone(),
             limits_applied: Some(limits),
+            errors: Vec::new(),
+            capped: Vec::new(),
+            empty_query_cached: false,
         };
         
         // Check the contents
@@ -151,6 +204,7 @@ mod tests {
             max_tokens: Some(200),
             total_bytes: 500,
             total_tokens: 100,
+            post_merge_tokens: Some(120),
         };
         
         assert_eq!(limits.max_results, Some(10));
@@ -158,5 +212,6 @@ mod tests {
         assert_eq!(limits.max_tokens, Some(200));
         assert_eq!(limits.total_bytes, 500);
         assert_eq!(limits.total_tokens, 100);
+        assert_eq!(limits.post_merge_tokens, Some(120));
     }
 }
\ No newline at end of file
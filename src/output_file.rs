@@ -0,0 +1,233 @@
+//! Writing command output to a file instead of stdout.
+//!
+//! `--output <path>` sends the results document to a file, which avoids shell
+//! redirection and its encoding surprises (PowerShell writes UTF-16 by default).
+//! Machine-readable output can also be compressed on the way with `--compress`.
+//! `--split-per-file <dir>` instead writes one file per source file, so tools
+//! can pick up the results of each file on their own and in parallel.
+
+use anyhow::{anyhow, Context, Result};
+use flate2::write::GzEncoder;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Component, Path, PathBuf};
+
+use crate::fs_access;
+use crate::models::{FileError, SearchResult};
+use crate::search::query::QueryPlan;
+use crate::search::{OutputSink, SearchOutput, WriterSink};
+
+/// Compression applied to an output file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Parse a `--compress` value
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "gzip" => Some(Compression::Gzip),
+            "zstd" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// File extension added to compressed output files
+    fn extension(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+        }
+    }
+}
+
+/// Function to check `--output` and `--compress` against the output format
+///
+/// Compression needs a file to write to, and only makes sense for the
+/// machine-readable formats, which are the ones that grow large.
+pub fn check_output_options(
+    output: Option<&Path>,
+    compress: Option<&str>,
+    format: &str,
+) -> Result<Option<Compression>> {
+    let Some(compress) = compress else {
+        return Ok(None);
+    };
+    let compression = Compression::parse(compress).ok_or_else(|| {
+        anyhow!(
+            "Unknown --compress '{}', expected one of: gzip, zstd",
+            compress
+        )
+    })?;
+    if output.is_none() {
+        return Err(anyhow!(
+            "--compress needs --output <path>; compressed data is not written to the terminal"
+        ));
+    }
+    if format != "json" && format != "xml" {
+        return Err(anyhow!(
+            "--compress only applies to --format json or xml, not '{}'",
+            format
+        ));
+    }
+    Ok(Some(compression))
+}
+
+enum Sink {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+    Zstd(zstd::Encoder<'static, BufWriter<File>>),
+}
+
+/// An output file, compressed as it is written when requested
+pub struct OutputFile {
+    path: PathBuf,
+    sink: Sink,
+}
+
+impl OutputFile {
+    /// Create (or truncate) the file at `path`
+    pub fn create(path: &Path, compression: Option<Compression>) -> Result<Self> {
+        fs_access::current().check_write(path)?;
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create output file {}", path.display()))?;
+        let writer = BufWriter::new(file);
+        let sink = match compression {
+            None => Sink::Plain(writer),
+            Some(Compression::Gzip) => {
+                Sink::Gzip(GzEncoder::new(writer, flate2::Compression::default()))
+            }
+            Some(Compression::Zstd) => Sink::Zstd(zstd::Encoder::new(writer, 0)?),
+        };
+        Ok(OutputFile {
+            path: path.to_path_buf(),
+            sink,
+        })
+    }
+
+    /// Finish compression and flush everything to disk
+    pub fn finish(self) -> Result<()> {
+        let writer = match self.sink {
+            Sink::Plain(writer) => writer,
+            Sink::Gzip(encoder) => encoder.finish()?,
+            Sink::Zstd(encoder) => encoder.finish()?,
+        };
+        writer
+            .into_inner()
+            .map_err(|e| e.into_error())
+            .and_then(|file| file.sync_all())
+            .with_context(|| format!("Failed to write output file {}", self.path.display()))
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match &mut self.sink {
+            Sink::Plain(writer) => writer.write(buf),
+            Sink::Gzip(encoder) => encoder.write(buf),
+            Sink::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.sink {
+            Sink::Plain(writer) => writer.flush(),
+            Sink::Gzip(encoder) => encoder.flush(),
+            Sink::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Function to get the artifact path for a source file under the split directory
+///
+/// The source path is mirrored below `dir` so equal file names in different
+/// directories don't collide; `..` becomes `__` to keep everything inside `dir`.
+/// The format extension (and compression extension) is appended to the name,
+/// e.g. `src/main.rs` as JSON becomes `<dir>/src/main.rs.json`.
+pub fn split_artifact_path(
+    dir: &Path,
+    source: &str,
+    format: &str,
+    compression: Option<Compression>,
+) -> PathBuf {
+    let mut path = dir.to_path_buf();
+    for component in Path::new(source).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::ParentDir => path.push("__"),
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+        }
+    }
+
+    let extension = match format {
+        "json" => "json",
+        "xml" => "xml",
+        "markdown" => "md",
+        _ => "txt",
+    };
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(extension);
+    if let Some(compression) = compression {
+        name.push(".");
+        name.push(compression.extension());
+    }
+    path.set_file_name(name);
+    path
+}
+
+/// Function to write the results of each source file to its own artifact
+///
+/// Every artifact is a complete document in the chosen format, holding that
+/// file's results and read errors. Returns the number of files written.
+pub fn write_split_results(
+    dir: &Path,
+    compression: Option<Compression>,
+    results: &[SearchResult],
+    dry_run: bool,
+    format: &str,
+    query_plan: Option<&QueryPlan>,
+    errors: &[FileError],
+) -> Result<usize> {
+    let file_system = fs_access::current();
+    file_system
+        .create_dir_all(dir)
+        .with_context(|| format!("Failed to create output directory {}", dir.display()))?;
+
+    let mut by_file: BTreeMap<&str, Vec<SearchResult>> = BTreeMap::new();
+    for result in results.iter().filter(|r| !r.file.is_empty()) {
+        by_file
+            .entry(result.file.as_str())
+            .or_default()
+            .push(result.clone());
+    }
+
+    for (source, file_results) in &by_file {
+        let file_errors: Vec<FileError> = errors
+            .iter()
+            .filter(|e| e.path == Path::new(source))
+            .cloned()
+            .collect();
+
+        let path = split_artifact_path(dir, source, format, compression);
+        if let Some(parent) = path.parent() {
+            file_system.create_dir_all(parent).with_context(|| {
+                format!("Failed to create output directory {}", parent.display())
+            })?;
+        }
+        let mut sink = WriterSink::new(OutputFile::create(&path, compression)?, format);
+        sink.emit(&SearchOutput {
+            results: file_results,
+            errors: &file_errors,
+            query_plan,
+            dry_run,
+            corpus_fingerprint: None,
+        })?;
+        sink.into_inner().finish()?;
+    }
+
+    Ok(by_file.len())
+}
@@ -0,0 +1,173 @@
+//! Encoding of file paths for machine-readable output.
+//!
+//! JSON and XML can only carry valid Unicode, but file names on Unix are arbitrary
+//! bytes. Paths that are valid UTF-8 are emitted unchanged. Other paths are
+//! percent-encoded and flagged, so consumers can recover the exact bytes with
+//! [`decode_path`].
+//!
+//! Paths shown to users and tools are also normalized to one separator style with
+//! [`format_path_for_output`], so the same search gives the same paths whatever the
+//! platform or the form of the root that was passed in.
+
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+
+/// Name of the encoding reported alongside percent-encoded paths
+pub const PERCENT_ENCODING: &str = "percent";
+
+/// A path as it appears in JSON and XML output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedPath {
+    pub value: String,
+    /// `Some("percent")` when `value` is percent-encoded, `None` when it is the path itself
+    pub encoding: Option<&'static str>,
+}
+
+impl EncodedPath {
+    /// The `encoding` attribute for an XML `<file>` element, empty when not encoded
+    pub fn xml_attribute(&self) -> String {
+        match self.encoding {
+            Some(encoding) => format!(" encoding=\"{}\"", encoding),
+            None => String::new(),
+        }
+    }
+}
+
+/// Encode a path for machine-readable output
+///
+/// When the path is not valid UTF-8, every byte outside printable ASCII, as well
+/// as `%` itself, is written as `%XX`.
+pub fn encode_path(path: &Path) -> EncodedPath {
+    if let Some(value) = path.to_str() {
+        return EncodedPath {
+            value: value.to_string(),
+            encoding: None,
+        };
+    }
+
+    let mut value = String::new();
+    for &byte in path_bytes(path).iter() {
+        if (byte.is_ascii_graphic() && byte != b'%') || byte == b' ' {
+            value.push(byte as char);
+        } else {
+            value.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    EncodedPath {
+        value,
+        encoding: Some(PERCENT_ENCODING),
+    }
+}
+
+/// Encode a path given as a result's lossy `file` string and optional exact path
+pub fn encode_result_path(file: &str, file_path: Option<&Path>) -> EncodedPath {
+    match file_path {
+        Some(path) => encode_path(path),
+        None => EncodedPath {
+            value: file.to_string(),
+            encoding: None,
+        },
+    }
+}
+
+/// Separator style for paths in output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathStyle {
+    /// The platform's own separator (`\` on Windows, `/` elsewhere)
+    #[default]
+    Native,
+    /// Always `/`
+    Unix,
+}
+
+impl PathStyle {
+    /// Parse a `--path-style` value, defaulting to native for unknown names
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "unix" => PathStyle::Unix,
+            _ => PathStyle::Native,
+        }
+    }
+}
+
+/// Rewrite a path for output in the given separator style
+///
+/// On Windows the verbatim prefix used for paths over 260 characters (`\\?\`) is
+/// dropped as well, so `\\?\C:\src\lib.rs` is shown as `C:\src\lib.rs`. Other
+/// platforms treat `\` as an ordinary file name character and leave it alone.
+pub fn format_path_for_output(path: &str, style: PathStyle) -> String {
+    if !cfg!(windows) {
+        return path.to_string();
+    }
+
+    let path = strip_verbatim_prefix(path);
+    match style {
+        PathStyle::Native => path.replace('/', "\\"),
+        PathStyle::Unix => path.replace('\\', "/"),
+    }
+}
+
+/// Turn `\\?\C:\...` into `C:\...` and `\\?\UNC\server\...` into `\\server\...`
+fn strip_verbatim_prefix(path: &str) -> String {
+    if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{}", rest)
+    } else if let Some(rest) = path.strip_prefix(r"\\?\") {
+        rest.to_string()
+    } else {
+        path.to_string()
+    }
+}
+
+/// Decode a path produced by [`encode_path`]
+pub fn decode_path(value: &str, encoding: Option<&str>) -> Result<PathBuf> {
+    match encoding {
+        None => Ok(PathBuf::from(value)),
+        Some(PERCENT_ENCODING) => {
+            let bytes = value.as_bytes();
+            let mut decoded = Vec::with_capacity(bytes.len());
+            let mut i = 0;
+            while i < bytes.len() {
+                if bytes[i] == b'%' {
+                    let hex = value
+                        .get(i + 1..i + 3)
+                        .ok_or_else(|| anyhow!("Truncated escape in path: {}", value))?;
+                    let byte = u8::from_str_radix(hex, 16)
+                        .map_err(|_| anyhow!("Invalid escape in path: {}", value))?;
+                    decoded.push(byte);
+                    i += 3;
+                } else {
+                    decoded.push(bytes[i]);
+                    i += 1;
+                }
+            }
+            path_from_bytes(decoded)
+        }
+        Some(other) => Err(anyhow!("Unknown path encoding: {}", other)),
+    }
+}
+
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    // Windows paths are UTF-16; unpaired surrogates are the only non-UTF-8 case
+    path.to_string_lossy().as_bytes().to_vec()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> Result<PathBuf> {
+    use std::os::unix::ffi::OsStringExt;
+    Ok(PathBuf::from(std::ffi::OsString::from_vec(bytes)))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> Result<PathBuf> {
+    String::from_utf8(bytes)
+        .map(PathBuf::from)
+        .map_err(|_| anyhow!("Path is not valid UTF-8 on this platform"))
+}
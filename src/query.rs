@@ -4,11 +4,15 @@ use ast_grep_language::SupportLang;
 use colored::*;
 use ignore::Walk;
 use rayon::prelude::*; // Added import
-use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::Instant;
 
+use crate::error::ProbeResult;
+use crate::fs_access;
+use crate::path_encoding::encode_path;
+
 /// Represents a match found by ast-grep
+#[derive(Debug, Clone)]
 pub struct AstMatch {
     pub file_path: PathBuf,
     pub line_start: usize,
@@ -112,7 +116,7 @@ fn query_file(file_path: &Path, options: &QueryOptions) -> Result<Vec<AstMatch>>
     }
 
     // Read the file content
-    let content = fs::read_to_string(file_path)
+    let content = fs_access::read_to_string(file_path)
         .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
 
     // Get the language for ast-grep
@@ -213,7 +217,10 @@ fn query_file(file_path: &Path, options: &QueryOptions) -> Result<Vec<AstMatch>>
     Ok(ast_matches)
 }
 
-pub fn perform_query(options: &QueryOptions) -> Result<Vec<AstMatch>> {
+/// Function to find the matches of an ast-grep pattern, without printing them
+///
+/// Files that cannot be read or parsed are skipped.
+pub fn perform_query(options: &QueryOptions) -> ProbeResult<Vec<AstMatch>> {
     // Suppress panic output if language is not specified
     let suppress_output = options.language.is_none();
 
@@ -340,14 +347,19 @@ pub fn format_and_print_query_results(matches: &[AstMatch], format: &str) -> Res
             let json_matches_standardized: Vec<_> = matches
                 .iter()
                 .map(|m| {
-                    serde_json::json!({
-                        "file": m.file_path.to_string_lossy(),
+                    let encoded = encode_path(&m.file_path);
+                    let mut json = serde_json::json!({
+                        "file": encoded.value,
                         "lines": [m.line_start, m.line_end],
                         "node_type": "match",
                         "code": m.matched_text,
                         "column_start": m.column_start,
                         "column_end": m.column_end
-                    })
+                    });
+                    if let Some(encoding) = encoded.encoding {
+                        json["file_encoding"] = encoding.into();
+                    }
+                    json
                 })
                 .collect();
 
@@ -369,9 +381,11 @@ pub fn format_and_print_query_results(matches: &[AstMatch], format: &str) -> Res
 
             for m in matches {
                 println!("  <result>");
+                let encoded = encode_path(&m.file_path);
                 println!(
-                    "    <file>{}</file>",
-                    escape_xml(&m.file_path.to_string_lossy())
+                    "    <file{}>{}</file>",
+                    encoded.xml_attribute(),
+                    escape_xml(&encoded.value)
                 );
                 println!("    <lines>{}-{}</lines>", m.line_start, m.line_end);
                 println!("    <node_type>match</node_type>");
@@ -0,0 +1,301 @@
+//! Query history: a log of the searches run in a repository.
+//!
+//! Every `probe search` appends its arguments, working directory and a digest of its
+//! results to a per-repository history file under `~/.cache/probe/history` (or
+//! `PROBE_HISTORY_DIR`). `probe history-queries` lists the log and `probe replay <N>`
+//! runs entry `N` again and reports whether its results changed since. `--no-history` or
+//! `PROBE_NO_HISTORY=1` leaves a search out, and only the latest [`MAX_HISTORY_ENTRIES`]
+//! searches are kept.
+
+use anyhow::{anyhow, Context, Result};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::fs_access;
+use crate::models::SearchResult;
+use crate::search::session_store::write_atomic;
+
+/// Environment variable that keeps searches out of the history when set to `1`, like
+/// `--no-history`
+pub const NO_HISTORY_ENV: &str = "PROBE_NO_HISTORY";
+
+/// Most searches a history file keeps; the oldest are dropped as new ones are recorded
+pub const MAX_HISTORY_ENTRIES: usize = 1000;
+
+/// One executed search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Working directory the search ran in
+    pub cwd: String,
+    /// Command line arguments after the program name, enough to run the search again
+    pub args: Vec<String>,
+    pub query: String,
+    pub path: String,
+    pub result_count: usize,
+    /// Digest of the returned blocks, see [`results_digest`]
+    pub digest: String,
+}
+
+/// The query history of one repository, stored as one JSON line per search
+#[derive(Debug, Clone)]
+pub struct QueryHistory {
+    file: PathBuf,
+    max_entries: usize,
+}
+
+/// Helper function to compute the 64-bit FNV-1a hash of some bytes
+///
+/// Unlike the std hasher it is stable across Rust versions, so digests in old history
+/// files stay comparable.
+pub(crate) fn fnv1a(bytes: &[u8], mut hash: u64) -> u64 {
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+pub(crate) const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Function to compute a digest of search results
+///
+/// Covers the file, line range and code of every block in order, so any change in what a
+/// search returns changes the digest.
+pub fn results_digest(results: &[SearchResult]) -> String {
+    let mut hash = FNV_OFFSET;
+    for result in results {
+        let header = format!("{}:{}-{}\n", result.file, result.lines.0, result.lines.1);
+        hash = fnv1a(header.as_bytes(), hash);
+        hash = fnv1a(result.code.as_bytes(), hash);
+        hash = fnv1a(b"\n", hash);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Helper function to find the repository a path belongs to
+///
+/// That is the nearest ancestor with a `.git` entry, or the path itself outside git.
+fn repository_root(path: &Path) -> PathBuf {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    path.ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .map(Path::to_path_buf)
+        .unwrap_or(path)
+}
+
+impl QueryHistory {
+    /// Directory the CLI keeps its history files in
+    pub fn default_dir() -> PathBuf {
+        if let Ok(dir) = std::env::var("PROBE_HISTORY_DIR") {
+            if !dir.is_empty() {
+                return PathBuf::from(dir);
+            }
+        }
+        let home_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+        home_dir.join(".cache").join("probe").join("history")
+    }
+
+    /// The history, kept in `dir`, of the repository that `path` is in
+    pub fn for_repository(dir: &Path, path: &Path) -> Self {
+        let root = repository_root(path);
+        let name = root
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "root".to_string());
+        let hash = fnv1a(root.to_string_lossy().as_bytes(), FNV_OFFSET);
+        Self {
+            file: dir.join(format!("{}-{:016x}.jsonl", name, hash)),
+            max_entries: MAX_HISTORY_ENTRIES,
+        }
+    }
+
+    /// Keep at most `max_entries` searches instead of [`MAX_HISTORY_ENTRIES`]
+    #[allow(dead_code)]
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// The file this history is stored in
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    /// Append an entry to the history, dropping the oldest beyond the maximum kept
+    pub fn record(&self, entry: &HistoryEntry) -> Result<()> {
+        let file_system = fs_access::current();
+        if let Some(dir) = self.file.parent() {
+            file_system
+                .create_dir_all(dir)
+                .with_context(|| format!("Failed to create history directory {:?}", dir))?;
+        }
+        let line = format!("{}\n", serde_json::to_string(entry)?);
+        file_system
+            .append(&self.file, line.as_bytes())
+            .with_context(|| format!("Failed to write history file {:?}", self.file))?;
+        self.trim()
+    }
+
+    /// Helper function to rewrite the history without the entries beyond the maximum kept
+    fn trim(&self) -> Result<()> {
+        let content = fs_access::read_to_string(&self.file)
+            .with_context(|| format!("Failed to read history file {:?}", self.file))?;
+        let lines: Vec<&str> = content.lines().collect();
+        if lines.len() <= self.max_entries {
+            return Ok(());
+        }
+        let mut kept = lines[lines.len() - self.max_entries..].join("\n");
+        kept.push('\n');
+        write_atomic(&self.file, kept.as_bytes())
+            .with_context(|| format!("Failed to write history file {:?}", self.file))
+    }
+
+    /// Entries of the history, oldest first; lines that cannot be parsed are skipped
+    pub fn entries(&self) -> Result<Vec<HistoryEntry>> {
+        if !fs_access::exists(&self.file) {
+            return Ok(Vec::new());
+        }
+        let content = fs_access::read_to_string(&self.file)
+            .with_context(|| format!("Failed to read history file {:?}", self.file))?;
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// Entry `number` of the history, counting from 1 as `probe history-queries` does
+    pub fn entry(&self, number: usize) -> Result<HistoryEntry> {
+        let entries = self.entries()?;
+        number
+            .checked_sub(1)
+            .and_then(|index| entries.get(index))
+            .cloned()
+            .ok_or_else(|| {
+                anyhow!(
+                    "No query #{} in the history ({} recorded)",
+                    number,
+                    entries.len()
+                )
+            })
+    }
+}
+
+/// Function to record a search run from the command line
+///
+/// Failing to write the history never fails the search.
+pub fn record_search(path: &Path, query: &str, results: &[SearchResult]) {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    let entry = HistoryEntry {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0),
+        cwd: std::env::current_dir()
+            .map(|dir| dir.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        args: std::env::args().skip(1).collect(),
+        query: query.to_string(),
+        path: path.to_string_lossy().to_string(),
+        result_count: results.len(),
+        digest: results_digest(results),
+    };
+    let history = QueryHistory::for_repository(&QueryHistory::default_dir(), path);
+    if let Err(e) = history.record(&entry) {
+        if debug_mode {
+            println!("DEBUG: Failed to record query history: {}", e);
+        }
+    }
+}
+
+/// Function to list the query history of the repository `path` is in
+pub fn handle_history_queries(path: &Path, limit: Option<usize>, format: &str) -> Result<()> {
+    let history = QueryHistory::for_repository(&QueryHistory::default_dir(), path);
+    let entries = history.entries()?;
+    let skip = limit.map_or(0, |limit| entries.len().saturating_sub(limit));
+
+    if format == "json" {
+        let listed: Vec<_> = entries
+            .iter()
+            .enumerate()
+            .skip(skip)
+            .map(|(index, entry)| {
+                let mut json = serde_json::to_value(entry).unwrap_or_default();
+                json["number"] = (index + 1).into();
+                json
+            })
+            .collect();
+        let wrapper = serde_json::json!({
+            "history_file": history.file().to_string_lossy(),
+            "queries": listed,
+            "summary": {
+                "count": entries.len(),
+            }
+        });
+        println!("{}", serde_json::to_string_pretty(&wrapper)?);
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!("{}", "No queries recorded.".yellow().bold());
+        return Ok(());
+    }
+    for (index, entry) in entries.iter().enumerate().skip(skip) {
+        println!(
+            "{:>4}  {}  {} results  probe {}",
+            (index + 1).to_string().bold(),
+            entry.digest.dimmed(),
+            entry.result_count,
+            entry.args.join(" ")
+        );
+    }
+    Ok(())
+}
+
+/// Function to run entry `number` of the query history again
+///
+/// The search runs with its original arguments in its original working directory, and is
+/// recorded as a new entry. Whether its results match the original run is reported on
+/// stderr, so JSON and XML output stay parseable.
+pub fn handle_replay(number: usize, path: &Path) -> Result<()> {
+    let history = QueryHistory::for_repository(&QueryHistory::default_dir(), path);
+    let entry = history.entry(number)?;
+    let recorded = history.entries()?.len();
+
+    eprintln!(
+        "{} #{}: probe {}",
+        "Replaying".bold().green(),
+        number,
+        entry.args.join(" ")
+    );
+    let status = Command::new(std::env::current_exe()?)
+        .args(&entry.args)
+        .current_dir(&entry.cwd)
+        .status()
+        .context("Failed to run the replayed search")?;
+    if !status.success() {
+        return Err(anyhow!("Replayed search #{} failed ({})", number, status));
+    }
+
+    let entries = history.entries()?;
+    match entries.get(recorded..).and_then(|new| new.last()) {
+        Some(replayed) if replayed.digest == entry.digest => {
+            eprintln!("{}", "Results unchanged.".green());
+        }
+        Some(replayed) => eprintln!(
+            "{} ({} results, digest {}; now {} results, digest {})",
+            "Results changed".yellow().bold(),
+            entry.result_count,
+            entry.digest,
+            replayed.result_count,
+            replayed.digest
+        ),
+        None => eprintln!("Results could not be compared: the replay was not recorded."),
+    }
+    Ok(())
+}
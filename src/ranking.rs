@@ -1,3 +1,4 @@
+use crate::search::code_terms;
 use crate::search::elastic_query::Expr;
 use crate::search::tokenization;
 use rust_stemmers::{Algorithm, Stemmer};
@@ -60,11 +61,14 @@ pub fn compute_tf_df(documents: &[&str]) -> TfDfResult {
         println!("DEBUG: Starting parallel TF-DF computation for {} documents", documents.len());
     }
     
+    // Documents are tokenized on rayon threads, which take this thread's code term rules
+    let code_terms = code_terms::current();
+
     // Process documents in parallel to compute term frequencies and document lengths
     let doc_results: Vec<(HashMap<String, usize>, usize, HashSet<String>)> = documents
         .par_iter()
         .map(|doc| {
-            let tokens = tokenize(doc);
+            let tokens = code_terms::with_code_terms(code_terms, || tokenize(doc));
             let mut tf = HashMap::new();
             
             // Compute term frequency for the current document
@@ -98,7 +102,7 @@ pub fn compute_tf_df(documents: &[&str]) -> TfDfResult {
             local_df
         })
         .reduce(
-            || HashMap::new(),
+            HashMap::new,
             |mut acc, local_df| {
                 // Merge local document frequency maps
                 for (term, count) in local_df {
@@ -323,9 +327,16 @@ pub fn rank_documents(params: &RankingParams) -> Vec<(usize, f64)> {
     let n_docs = params.documents.len();
     let avgdl = compute_avgdl(&tf_df_result.document_lengths);
 
-    // 3) Extract query terms and precompute IDF values
+    // 3) Extract query terms and precompute IDF values, from the whole corpus when an
+    //    engine keeps its statistics and from the documents being ranked otherwise
     let query_terms = extract_query_terms(&parsed_expr);
-    let precomputed_idfs = precompute_idfs(&query_terms, &tf_df_result.document_frequencies, n_docs);
+    let precomputed_idfs = match crate::search::corpus_stats::current() {
+        Some(stats) => stats
+            .read()
+            .unwrap()
+            .idfs(&query_terms, &tf_df_result.document_frequencies),
+        None => precompute_idfs(&query_terms, &tf_df_result.document_frequencies, n_docs),
+    };
     
     if debug_mode {
         println!("DEBUG: Precomputed IDF values for {} unique query terms", precomputed_idfs.len());
@@ -442,7 +453,7 @@ pub fn compute_tf_df_from_tokenized(tokenized_docs: &[Vec<String>]) -> TfDfResul
             local_df
         })
         .reduce(
-            || HashMap::new(),
+            HashMap::new,
             |mut acc, local_df| {
                 // Merge local document frequency maps
                 for (term, count) in local_df {
@@ -0,0 +1,113 @@
+//! Keeping every path a command touches inside a configured root.
+//!
+//! `--restrict-root <dir>` (or `PROBE_RESTRICT_ROOT`) makes search, extract and
+//! query reject any requested path that resolves outside the root, for when probe
+//! is driven by tool calls that can't be trusted. Paths are canonicalized first,
+//! so `..` segments and symbolic links pointing out of the root are caught too.
+//! The environment variable lets the host enforce a root that the caller can't
+//! drop by leaving out the flag; when both are set, paths must be inside both.
+
+use anyhow::{anyhow, Context, Result};
+use std::path::{Component, Path, PathBuf};
+
+/// Environment variable holding a root that is always enforced
+pub const RESTRICT_ROOT_ENV: &str = "PROBE_RESTRICT_ROOT";
+
+/// The canonical roots that requested paths must stay inside
+#[derive(Debug, Clone)]
+pub struct RestrictRoot {
+    roots: Vec<PathBuf>,
+}
+
+impl RestrictRoot {
+    /// Function to build the restriction from `--restrict-root` and `PROBE_RESTRICT_ROOT`
+    ///
+    /// Returns `None` when neither is set, so no path is restricted.
+    pub fn from_options(flag: Option<&Path>) -> Result<Option<Self>> {
+        let env_root = std::env::var_os(RESTRICT_ROOT_ENV)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from);
+
+        let mut roots = Vec::new();
+        for root in env_root.as_deref().into_iter().chain(flag) {
+            roots.push(Self::canonical_root(root)?);
+        }
+        if roots.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(RestrictRoot { roots }))
+    }
+
+    fn canonical_root(root: &Path) -> Result<PathBuf> {
+        let canonical = root
+            .canonicalize()
+            .with_context(|| format!("Restrict root {} does not exist", root.display()))?;
+        if !canonical.is_dir() {
+            return Err(anyhow!(
+                "Restrict root {} is not a directory",
+                root.display()
+            ));
+        }
+        Ok(canonical)
+    }
+
+    /// Function to check whether `path` resolves to a location inside every root
+    pub fn contains(&self, path: &Path) -> bool {
+        match resolve(path) {
+            Some(resolved) => self.roots.iter().all(|root| resolved.starts_with(root)),
+            None => false,
+        }
+    }
+
+    /// Function to reject `path` with an error when it resolves outside the root
+    pub fn check(&self, path: &Path) -> Result<()> {
+        if self.contains(path) {
+            return Ok(());
+        }
+        let roots: Vec<String> = self
+            .roots
+            .iter()
+            .map(|root| root.display().to_string())
+            .collect();
+        Err(anyhow!(
+            "Path {} is outside the restricted root {}",
+            path.display(),
+            roots.join(" and ")
+        ))
+    }
+}
+
+/// Resolve `path` to a canonical location, following symbolic links
+///
+/// A path that does not exist yet is resolved through its nearest existing
+/// ancestor. `..` after that point can't be checked against the file system,
+/// so such paths don't resolve at all.
+fn resolve(path: &Path) -> Option<PathBuf> {
+    if let Ok(canonical) = path.canonicalize() {
+        return Some(canonical);
+    }
+
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().ok()?.join(path)
+    };
+    let mut existing = absolute.as_path();
+    let mut missing = Vec::new();
+    let canonical = loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            break canonical;
+        }
+        missing.push(existing.file_name()?.to_os_string());
+        existing = existing.parent()?;
+    };
+
+    let mut resolved = canonical;
+    for part in missing.iter().rev() {
+        match Path::new(part).components().next() {
+            Some(Component::Normal(_)) => resolved.push(part),
+            _ => return None,
+        }
+    }
+    Some(resolved)
+}
@@ -0,0 +1,119 @@
+//! Matching query terms against the initials of identifiers.
+//!
+//! With acronym matching on, a short query term such as `hsr` also matches identifiers
+//! whose camelCase or snake_case parts start with those letters, like
+//! `handleSearchResults` or `handle_search_results`. Each term's search pattern gets the
+//! acronym as an alternative, and a block whose identifiers have the term's initials
+//! counts as containing the term. Those matches rank below blocks containing the term
+//! itself, see `penalize_acronym_matches`.
+//!
+//! The setting is kept per thread like the code term rules: the search turns it on for
+//! its own thread, and stages that fan out to rayon threads pass [`enabled`] on to them.
+
+use crate::search::query::{regex_escape, QueryPlan};
+use crate::search::tokenization::split_camel_case;
+use std::cell::Cell;
+
+/// Shortest and longest terms that are tried as acronyms
+const MIN_ACRONYM_LEN: usize = 2;
+const MAX_ACRONYM_LEN: usize = 6;
+
+/// Share of its weight a term keeps when a block only matches it as an acronym
+pub(crate) const ACRONYM_MATCH_WEIGHT: f64 = 0.5;
+
+thread_local! {
+    static ENABLED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Function to check whether acronym matching is on for this thread
+pub fn enabled() -> bool {
+    ENABLED.with(Cell::get)
+}
+
+/// Function to run `f` with acronym matching turned on or off on this thread
+///
+/// The previous setting is restored afterwards.
+pub fn with_acronyms<T>(enabled: bool, f: impl FnOnce() -> T) -> T {
+    struct Restore(bool);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            ENABLED.with(|current| current.set(self.0));
+        }
+    }
+
+    let _restore = Restore(ENABLED.with(|current| current.replace(enabled)));
+    f()
+}
+
+/// Function to check whether a query term is short and plain enough to be an acronym
+pub fn is_acronym_candidate(term: &str) -> bool {
+    (MIN_ACRONYM_LEN..=MAX_ACRONYM_LEN).contains(&term.len())
+        && term.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// Function to build a case-sensitive pattern for identifiers whose initials spell `term`
+///
+/// `hsr` becomes a pattern matching `handleSearchResults`, `HandleSearchResults` and
+/// `handle_search_results`, but not identifiers with more parts. The pattern has no
+/// capturing groups, so it can be added as an alternative to the term's own pattern.
+pub fn acronym_pattern(term: &str) -> String {
+    let mut letters = term.chars().map(|c| c.to_ascii_lowercase());
+    let Some(first) = letters.next() else {
+        return regex_escape(term);
+    };
+    let mut pattern = format!(r"(?-i:\b[{}{}]", first, first.to_ascii_uppercase());
+    for letter in letters {
+        pattern.push_str(&format!(
+            "[a-z0-9]*(?:{}|_[{}{}])",
+            letter.to_ascii_uppercase(),
+            letter,
+            letter.to_ascii_uppercase()
+        ));
+    }
+    pattern.push_str(r"[a-z0-9]*\b)");
+    pattern
+}
+
+/// Helper function to get the initials of an identifier's camelCase and snake_case parts
+fn initials(identifier: &str) -> Option<String> {
+    let parts: Vec<String> = identifier
+        .split('_')
+        .filter(|piece| !piece.is_empty())
+        .flat_map(split_camel_case)
+        .collect();
+    if parts.len() < MIN_ACRONYM_LEN {
+        return None;
+    }
+    parts.iter().map(|part| part.chars().next()).collect()
+}
+
+/// Function to list the plan's terms tried as acronyms, leaving out excluded ones
+pub(crate) fn candidate_terms(plan: &QueryPlan) -> impl Iterator<Item = &String> {
+    plan.term_indices
+        .keys()
+        .filter(|term| !plan.excluded_terms.contains(*term) && is_acronym_candidate(term))
+}
+
+/// Function to add the plan's terms that `code` has as identifier initials to its tokens
+///
+/// Does nothing unless acronym matching is on.
+pub fn add_acronym_matches(block_terms: &mut Vec<String>, code: &str, plan: &QueryPlan) {
+    if !enabled() {
+        return;
+    }
+    let missing: Vec<&String> = candidate_terms(plan)
+        .filter(|term| !block_terms.contains(*term))
+        .collect();
+    if missing.is_empty() {
+        return;
+    }
+    let block_initials: Vec<String> = code
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter_map(initials)
+        .collect();
+    for term in missing {
+        if block_initials.iter().any(|found| found == term) {
+            block_terms.push(term.clone());
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! Searching for a name in every form it is serialized in.
+//!
+//! In a full-stack monorepo the same API name is written differently on each side of a
+//! language boundary: a proto field `user_id` becomes `UserId` in Go stubs, `userId` in
+//! TypeScript and JSON, and `USER_ID` as an enum value. With `--api-names`, each identifier
+//! of the query made of several words is replaced by all of its forms, quoted so each one
+//! matches as written. The forms come from a name-transformation table, which
+//! `--api-names-table` replaces with one of your own.
+//!
+//! A table has one form per line, `label = template`, with `#` starting a comment. The
+//! template is the name with the words of the identifier filled in for placeholders:
+//!
+//! - `{snake}` is `user_id`, `{screaming}` is `USER_ID` and `{kebab}` is `user-id`
+//! - `{camel}` is `userId`, `{pascal}` is `UserId` and `{flat}` is `userid`
+//!
+//! so `go_getter = Get{pascal}` adds `GetUserId`.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use crate::error::ProbeError;
+use crate::fs_access;
+use crate::search::query::balanced_or;
+
+/// Forms used without a table of your own
+const DEFAULT_TABLE: &str = "\
+# Proto fields, Python and Rust
+snake = {snake}
+# JSON, TypeScript and Java
+camel = {camel}
+# Go, C# and proto messages
+pascal = {pascal}
+# Proto enum values and constants
+screaming = {screaming}
+# URLs, CLI flags and CSS
+kebab = {kebab}
+";
+
+/// Placeholders a template may use
+const PLACEHOLDERS: &[&str] = &["snake", "screaming", "kebab", "camel", "pascal", "flat"];
+
+/// One serialized form of a name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameForm {
+    pub label: String,
+    pub template: String,
+}
+
+/// The forms an identifier is expanded to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiNameTable {
+    pub forms: Vec<NameForm>,
+}
+
+impl Default for ApiNameTable {
+    fn default() -> Self {
+        Self::parse(DEFAULT_TABLE).expect("the default table is valid")
+    }
+}
+
+impl ApiNameTable {
+    /// Parse a table, one `label = template` form per line
+    pub fn parse(text: &str) -> Result<Self, ProbeError> {
+        let mut forms = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = |reason: &str| {
+                ProbeError::QueryParse(format!(
+                    "Invalid API name form on line {}: {:?} {}",
+                    number + 1,
+                    line,
+                    reason
+                ))
+            };
+            let Some((label, template)) = line.split_once('=') else {
+                return Err(invalid("is not `label = template`"));
+            };
+            let (label, template) = (label.trim(), template.trim());
+            if label.is_empty() || template.is_empty() {
+                return Err(invalid("is not `label = template`"));
+            }
+            let mut rest = template;
+            let mut has_placeholder = false;
+            while let Some(start) = rest.find('{') {
+                let Some(end) = rest[start..].find('}') else {
+                    return Err(invalid("has an unclosed placeholder"));
+                };
+                let name = &rest[start + 1..start + end];
+                if !PLACEHOLDERS.contains(&name) {
+                    return Err(invalid(&format!(
+                        "uses {{{}}}, expected one of {}",
+                        name,
+                        PLACEHOLDERS
+                            .iter()
+                            .map(|p| format!("{{{}}}", p))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )));
+                }
+                has_placeholder = true;
+                rest = &rest[start + end + 1..];
+            }
+            if !has_placeholder {
+                return Err(invalid("has no placeholder"));
+            }
+            forms.push(NameForm {
+                label: label.to_string(),
+                template: template.to_string(),
+            });
+        }
+        if forms.is_empty() {
+            return Err(ProbeError::QueryParse(
+                "The API name table has no forms".to_string(),
+            ));
+        }
+        Ok(Self { forms })
+    }
+
+    /// Read a table from a file
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs_access::read_to_string(path)
+            .with_context(|| format!("Failed to read API name table {:?}", path))?;
+        Ok(Self::parse(&text)?)
+    }
+
+    /// All forms of an identifier, each once
+    ///
+    /// Searches ignore case, so forms differing only in case, like `userId` and `UserId`,
+    /// are kept once. Identifiers of a single word have no other forms.
+    pub fn variants(&self, identifier: &str) -> Vec<String> {
+        let words = name_words(identifier);
+        if words.len() < 2 {
+            return vec![identifier.to_string()];
+        }
+
+        let mut variants: Vec<String> = Vec::new();
+        for form in &self.forms {
+            let variant = render(&form.template, &words);
+            if !variants.iter().any(|v| v.eq_ignore_ascii_case(&variant)) {
+                variants.push(variant);
+            }
+        }
+        variants
+    }
+
+    /// Replace each identifier of several words in a query by all of its forms
+    ///
+    /// Operators, quoted phrases, qualifiers and words with a `+` or `-` prefix are kept
+    /// as written.
+    pub fn expand_query(&self, query: &str) -> String {
+        let mut quoted = false;
+        query
+            .split(' ')
+            .map(|word| {
+                let was_quoted = quoted;
+                quoted ^= word.matches('"').count() % 2 == 1;
+                let (open, rest) = split_leading(word, '(');
+                let (identifier, close) = split_trailing(rest, ')');
+                if was_quoted || !is_identifier(identifier) {
+                    return word.to_string();
+                }
+                let variants = self.variants(identifier);
+                if variants.len() < 2 {
+                    return word.to_string();
+                }
+                let quoted: Vec<String> = variants.iter().map(|v| format!("\"{}\"", v)).collect();
+                format!("{}{}{}", open, balanced_or(&quoted), close)
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Helper function to split leading `c`s off a word
+fn split_leading(word: &str, c: char) -> (&str, &str) {
+    let rest = word.trim_start_matches(c);
+    (&word[..word.len() - rest.len()], rest)
+}
+
+/// Helper function to split trailing `c`s off a word
+fn split_trailing(word: &str, c: char) -> (&str, &str) {
+    let rest = word.trim_end_matches(c);
+    (rest, &word[rest.len()..])
+}
+
+/// Helper function to check whether a query word is a bare identifier
+fn is_identifier(word: &str) -> bool {
+    word.chars().next().is_some_and(|c| c.is_alphabetic())
+        && word
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        && !matches!(word, "AND" | "OR" | "NOT")
+}
+
+/// Function to split an identifier into its lowercase words
+///
+/// Words are separated by `_` and `-`, and by case changes: `HTTPServerURL` is `http`,
+/// `server`, `url`. Digits stay with the word before them.
+pub fn name_words(identifier: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for part in identifier.split(['_', '-']).filter(|p| !p.is_empty()) {
+        let chars: Vec<char> = part.chars().collect();
+        let mut word = String::new();
+        for (i, &c) in chars.iter().enumerate() {
+            let starts_word = i > 0
+                && c.is_uppercase()
+                && (chars[i - 1].is_lowercase()
+                    || chars[i - 1].is_ascii_digit()
+                    || chars.get(i + 1).is_some_and(|next| next.is_lowercase()));
+            if starts_word && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            word.extend(c.to_lowercase());
+        }
+        if !word.is_empty() {
+            words.push(word);
+        }
+    }
+    words
+}
+
+/// Helper function to capitalize a lowercase word
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Helper function to fill in the placeholders of a template
+fn render(template: &str, words: &[String]) -> String {
+    let pascal: String = words.iter().map(|w| capitalize(w)).collect();
+    let camel = format!(
+        "{}{}",
+        words[0],
+        words[1..].iter().map(|w| capitalize(w)).collect::<String>()
+    );
+    template
+        .replace("{snake}", &words.join("_"))
+        .replace("{screaming}", &words.join("_").to_uppercase())
+        .replace("{kebab}", &words.join("-"))
+        .replace("{camel}", &camel)
+        .replace("{pascal}", &pascal)
+        .replace("{flat}", &words.concat())
+}
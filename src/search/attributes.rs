@@ -0,0 +1,338 @@
+use anyhow::Result;
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tree_sitter::{Node, Parser as TSParser};
+
+use crate::fs_access;
+use crate::language::detection::{detect_language, LanguageOverrides};
+use crate::language::factory::get_language_impl;
+use crate::language::language_trait::LanguageImpl;
+use crate::models::{ExtractionConfidence, SearchResult};
+use crate::search::{content_hash, file_list_cache, io_errors};
+
+/// Prefix of the attribute/decorator query qualifier
+const ATTR_QUALIFIER: &str = "attr:";
+
+/// Node kinds for attributes, annotations and decorators across languages
+const ATTRIBUTE_KINDS: &[&str] = &[
+    // Rust
+    "attribute_item",
+    "inner_attribute_item",
+    // Python, JavaScript, TypeScript
+    "decorator",
+    // Java
+    "marker_annotation",
+    "annotation",
+    // C#, PHP
+    "attribute",
+];
+
+/// Function to split `attr:` qualifiers out of the queries
+///
+/// Returns the attribute names and the queries with the qualifiers removed.
+/// Queries that contained nothing but qualifiers are dropped.
+pub fn split_attr_qualifiers(queries: &[String]) -> (Vec<String>, Vec<String>) {
+    let mut attrs = Vec::new();
+    let mut remaining = Vec::new();
+
+    for query in queries {
+        let mut words = Vec::new();
+        for word in query.split_whitespace() {
+            let bare = word.trim_start_matches('+');
+            match bare.strip_prefix(ATTR_QUALIFIER) {
+                Some(value) if !value.trim_matches('"').is_empty() => {
+                    attrs.push(attribute_path(value.trim_matches('"')));
+                }
+                _ => words.push(word),
+            }
+        }
+        if !words.is_empty() {
+            remaining.push(words.join(" "));
+        }
+    }
+
+    (attrs, remaining)
+}
+
+/// Reduce an attribute to its path, e.g. `#[tokio::main]` to `tokio::main`
+/// and `@app.route("/")` to `app.route`
+fn attribute_path(text: &str) -> String {
+    let text = text.trim();
+    let text = ["#![", "#[", "@", "["]
+        .iter()
+        .find_map(|prefix| text.strip_prefix(prefix))
+        .unwrap_or(text)
+        .trim_start();
+    text.chars()
+        .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | ':' | '.' | '\\'))
+        .collect()
+}
+
+/// Check whether an attribute path matches a queried name
+///
+/// `tokio::main` matches `attr:tokio::main` and `attr:main`, but not `attr:okio::main`.
+fn attribute_matches(path: &str, wanted: &str) -> bool {
+    path == wanted
+        || ["::", ".", "\\"]
+            .iter()
+            .any(|sep| path.ends_with(&format!("{}{}", sep, wanted)))
+}
+
+/// Find the item an attribute node decorates
+fn decorated_item<'a>(attr: Node<'a>, language_impl: &dyn LanguageImpl) -> Option<Node<'a>> {
+    let parent = attr.parent()?;
+
+    // Python wraps decorators and their function/class in a single node
+    if parent.kind() == "decorated_definition" {
+        return Some(parent);
+    }
+
+    // Rust outer attributes and TypeScript method decorators precede the item as siblings
+    if attr.kind() == "attribute_item" || parent.kind() == "class_body" {
+        let mut next = attr.next_named_sibling();
+        while let Some(node) = next {
+            if ATTRIBUTE_KINDS.contains(&node.kind()) || node.kind().contains("comment") {
+                next = node.next_named_sibling();
+            } else {
+                return Some(node);
+            }
+        }
+        return None;
+    }
+
+    // Elsewhere the attribute is part of the item, possibly inside a modifiers list
+    let mut current = parent;
+    loop {
+        if language_impl.is_acceptable_parent(&current) {
+            return Some(current);
+        }
+        current = current.parent()?;
+    }
+}
+
+/// Collect the attribute nodes in a tree
+fn collect_attributes<'a>(node: Node<'a>, attributes: &mut Vec<Node<'a>>) {
+    let is_nested_rust_attribute = node.kind() == "attribute"
+        && node
+            .parent()
+            .is_some_and(|p| p.kind() == "attribute_item" || p.kind() == "inner_attribute_item");
+    if ATTRIBUTE_KINDS.contains(&node.kind()) && !is_nested_rust_attribute {
+        attributes.push(node);
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_attributes(child, attributes);
+    }
+}
+
+/// Find the items in one file decorated with one of the wanted attributes
+fn search_file(
+    file_path: &Path,
+    attrs: &[String],
+    terms: &[String],
+    allow_tests: bool,
+) -> Result<Vec<SearchResult>> {
+    let extension = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let Some(language_impl) = get_language_impl(extension) else {
+        return Ok(Vec::new());
+    };
+
+    let content = fs_access::read_to_string(file_path)?;
+
+    // Cheap pre-check before parsing: the last path segment must appear somewhere
+    let mentions_attr = attrs.iter().any(|attr| {
+        let last = attr.rsplit([':', '.', '\\']).next().unwrap_or(attr);
+        content.contains(last)
+    });
+    if !mentions_attr {
+        return Ok(Vec::new());
+    }
+
+    let mut parser = TSParser::new();
+    parser.set_language(&language_impl.get_tree_sitter_language())?;
+    let Some(tree) = parser.parse(&content, None) else {
+        return Ok(Vec::new());
+    };
+
+    let mut attribute_nodes = Vec::new();
+    collect_attributes(tree.root_node(), &mut attribute_nodes);
+
+    let mut results: Vec<SearchResult> = Vec::new();
+    for attr_node in attribute_nodes {
+        let path = attribute_path(attr_node.utf8_text(content.as_bytes()).unwrap_or(""));
+        let Some(wanted) = attrs.iter().find(|wanted| attribute_matches(&path, wanted)) else {
+            continue;
+        };
+        let Some(item) = decorated_item(attr_node, language_impl.as_ref()) else {
+            continue;
+        };
+        if !allow_tests && language_impl.is_test_node(&item, content.as_bytes()) {
+            continue;
+        }
+
+        // Include the attribute itself when it precedes the item
+        let start_byte = attr_node.start_byte().min(item.start_byte());
+        let start_row = attr_node
+            .start_position()
+            .row
+            .min(item.start_position().row);
+        let lines = (start_row + 1, item.end_position().row + 1);
+        if results.iter().any(|r| r.lines == lines) {
+            continue;
+        }
+
+        let code = &content[start_byte..item.end_byte()];
+        let code_lower = code.to_lowercase();
+
+        // Any other query terms must all appear in the decorated item
+        let term_matches: Vec<usize> = terms
+            .iter()
+            .map(|term| code_lower.matches(&term.to_lowercase()).count())
+            .collect();
+        if term_matches.contains(&0) {
+            continue;
+        }
+
+        let mut matched_keywords = vec![wanted.clone()];
+        matched_keywords.extend(terms.iter().cloned());
+
+        results.push(SearchResult {
+            file: file_path.to_string_lossy().to_string(),
+            file_path: Some(file_path.to_path_buf()),
+            lines,
+            node_type: item.kind().to_string(),
+            code: code.to_string(),
+            matched_by_filename: None,
+            rank: None,
+            score: Some(1.0 + term_matches.iter().sum::<usize>() as f64),
+            normalized_score: None,
+            tfidf_score: None,
+            bm25_score: None,
+            tfidf_rank: None,
+            bm25_rank: None,
+            new_score: None,
+            hybrid2_rank: None,
+            combined_score_rank: None,
+            file_unique_terms: Some(terms.len() + 1),
+            file_total_matches: None,
+            file_match_rank: None,
+            block_unique_terms: Some(terms.len() + 1),
+            block_total_matches: Some(1 + term_matches.iter().sum::<usize>()),
+            parent_file_id: None,
+            block_id: None,
+            matched_keywords: Some(matched_keywords),
+            tokenized_content: None,
+            merged_from: None,
+            usage_kind: None,
+            via_symlink: false,
+            changed_lines: None,
+            symbol_name: None,
+            language: detect_language(file_path, &content, &LanguageOverrides::new())
+                .map(str::to_string),
+            extraction_confidence: Some(ExtractionConfidence::Ast),
+            partial_parse: false,
+            deprecated: false,
+            embedded: None,
+            kind: None,
+            complexity: None,
+            content_hash: Some(content_hash::hash_content(&content)),
+        });
+    }
+
+    Ok(results)
+}
+
+/// Function to find code blocks decorated with the given attributes
+///
+/// Each result is the decorated item (function, class, method, ...) including
+/// its attributes. When other query terms are given, an item is only returned
+/// if it contains all of them, and items with more term occurrences rank higher.
+///
+/// # Arguments
+/// * `path` - Root directory to search
+/// * `attrs` - Attribute paths from `attr:` qualifiers
+/// * `queries` - The rest of the query, whose words must appear in the item
+/// * `custom_ignores` - Custom ignore patterns
+/// * `allow_tests` - Whether to include test files and test items
+/// * `follow_symlinks` - Whether to follow symbolic links while scanning
+/// * `filters` - Depth, modification time and size limits for the scan
+pub fn search_attributes(
+    path: &Path,
+    attrs: &[String],
+    queries: &[String],
+    custom_ignores: &[String],
+    allow_tests: bool,
+    follow_symlinks: bool,
+    filters: file_list_cache::FileFilters,
+) -> Result<Vec<SearchResult>> {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    let terms: Vec<String> = queries
+        .iter()
+        .flat_map(|q| q.split_whitespace())
+        .map(|word| word.trim_start_matches('+').trim_matches('"').to_string())
+        .filter(|word| {
+            !word.is_empty() && !word.starts_with('-') && !matches!(word.as_str(), "AND" | "OR")
+        })
+        .collect();
+
+    if debug_mode {
+        println!(
+            "DEBUG: Searching for attributes {:?} with terms {:?}",
+            attrs, terms
+        );
+    }
+
+    let file_list = file_list_cache::get_file_list(
+        path,
+        allow_tests,
+        custom_ignores,
+        follow_symlinks,
+        filters,
+    )?;
+
+    for error in &file_list.errors {
+        io_errors::record_file_error(error.clone());
+    }
+
+    // Files are read on rayon threads, which take this thread's file system
+    let file_system = fs_access::current();
+    let searched: Vec<(&PathBuf, Result<Vec<SearchResult>>)> = file_list
+        .files
+        .par_iter()
+        .map(|file| {
+            fs_access::with_file_system(Arc::clone(&file_system), || {
+                (file, search_file(file, attrs, &terms, allow_tests))
+            })
+        })
+        .collect();
+
+    // Errors are recorded on this thread, where the search collects them
+    let mut results: Vec<SearchResult> = Vec::new();
+    for (file, searched) in searched {
+        match searched {
+            Ok(mut file_results) => results.append(&mut file_results),
+            Err(e) => {
+                if debug_mode {
+                    println!("DEBUG: Error searching file {:?}: {:?}", file, e);
+                }
+                io_errors::record_if_io(file, &e);
+            }
+        }
+    }
+
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.file.cmp(&b.file))
+            .then_with(|| a.lines.cmp(&b.lines))
+    });
+    for (i, result) in results.iter_mut().enumerate() {
+        result.rank = Some(i + 1);
+    }
+
+    Ok(results)
+}
@@ -1,8 +1,8 @@
-use crate::models::SearchResult;
+use crate::fs_access;
+use crate::models::{MergedBlockInfo, SearchResult};
+use crate::search::complexity::block_complexity;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
 /// Merges ranked search results that are adjacent or overlapping
 ///
@@ -37,14 +37,16 @@ pub fn merge_ranked_blocks(
     // Store the original count before we move results
     let original_count = results.len();
 
-    // Group results by file
-    let mut file_blocks: HashMap<String, Vec<SearchResult>> = HashMap::new();
+    // Group results by file, keeping files in the order of their best ranked result
+    let mut file_indices: HashMap<PathBuf, usize> = HashMap::new();
+    let mut file_blocks: Vec<(PathBuf, Vec<SearchResult>)> = Vec::new();
 
     for result in results {
-        file_blocks
-            .entry(result.file.clone())
-            .or_default()
-            .push(result);
+        let index = *file_indices.entry(result.path()).or_insert_with(|| {
+            file_blocks.push((result.path(), Vec::new()));
+            file_blocks.len() - 1
+        });
+        file_blocks[index].1.push(result);
     }
 
     let mut merged_results = Vec::new();
@@ -55,7 +57,7 @@ pub fn merge_ranked_blocks(
             println!(
                 "DEBUG: Processing {} blocks from file: {}",
                 blocks.len(),
-                file_path
+                file_path.display()
             );
         }
 
@@ -127,6 +129,7 @@ pub fn merge_ranked_blocks(
                         // Combine scores and term statistics
                         let merged_score = merge_scores(&current_block, next_block);
                         let merged_term_stats = merge_term_statistics(&current_block, next_block);
+                        let merged_from = merge_provenance(&current_block, next_block);
 
                         // Update the current block
                         current_block.lines = (merged_start, merged_end);
@@ -138,6 +141,17 @@ pub fn merge_ranked_blocks(
                         current_block.new_score = merged_score.3;
                         current_block.block_unique_terms = merged_term_stats.0;
                         current_block.block_total_matches = merged_term_stats.1;
+                        current_block.merged_from = Some(merged_from);
+                        // A merged block is only as reliable as its least reliable part
+                        current_block.extraction_confidence = current_block
+                            .extraction_confidence
+                            .min(next_block.extraction_confidence);
+                        current_block.partial_parse |= next_block.partial_parse;
+                        // Only deprecated as a whole when every part is
+                        current_block.deprecated &= next_block.deprecated;
+                        if current_block.complexity.is_some() {
+                            current_block.complexity = Some(block_complexity(&current_block.code));
+                        }
 
                         // Mark this block as processed
                         processed_indices.insert(j);
@@ -178,6 +192,15 @@ pub fn merge_ranked_blocks(
 pub fn should_merge_blocks(block1: &SearchResult, block2: &SearchResult, threshold: usize) -> bool {
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
 
+    // Merging must never cross file boundaries, regardless of what the
+    // parent_file_id fields say
+    if block1.path() != block2.path() {
+        if debug_mode {
+            println!("DEBUG: Blocks not merged - different files");
+        }
+        return false;
+    }
+
     // Check if both blocks have parent_file_id, and if they match
     if let (Some(file_id1), Some(file_id2)) = (&block1.parent_file_id, &block2.parent_file_id) {
         if file_id1 != file_id2 {
@@ -186,14 +209,6 @@ pub fn should_merge_blocks(block1: &SearchResult, block2: &SearchResult, thresho
             }
             return false;
         }
-    } else {
-        // If blocks don't have parent_file_id, check if they're from the same file
-        if block1.file != block2.file {
-            if debug_mode {
-                println!("DEBUG: Blocks not merged - different files");
-            }
-            return false;
-        }
     }
 
     // Get line ranges
@@ -298,11 +313,10 @@ fn merge_block_content(block1: &SearchResult, block2: &SearchResult) -> String {
     let mut current_line = merged_start;
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
 
-    // Try to open the file to fill small gaps
-    let file_path = Path::new(&block1.file);
-    let file_result = File::open(file_path);
-    let file_content_available = file_result.is_ok();
-    let _reader = file_result.map(BufReader::new).ok(); // Used for debugging purposes only
+    // Try to read the file to fill small gaps
+    let file_path = block1.path();
+    let file_content = fs_access::read_to_string(&file_path).ok();
+    let file_content_available = file_content.is_some();
 
     if debug_mode {
         println!(
@@ -313,10 +327,10 @@ fn merge_block_content(block1: &SearchResult, block2: &SearchResult) -> String {
             "DEBUG: Attempting to read file: {:?}",
             file_path
                 .canonicalize()
-                .unwrap_or_else(|_| PathBuf::from(file_path))
+                .unwrap_or_else(|_| file_path.clone())
         );
         println!("DEBUG: File exists: {}", file_path.exists());
-        println!("DEBUG: File can be opened: {}", file_content_available);
+        println!("DEBUG: File can be read: {}", file_content_available);
     }
 
     while current_line <= merged_end {
@@ -337,7 +351,7 @@ fn merge_block_content(block1: &SearchResult, block2: &SearchResult) -> String {
 
             // For small gaps (less than 10 lines), try to read the actual content
             if gap_size < 10 {
-                if file_content_available {
+                if let Some(content) = &file_content {
                     if debug_mode {
                         println!(
                             "DEBUG: Attempting to fill small gap from line {} to {} from file {}",
@@ -345,50 +359,27 @@ fn merge_block_content(block1: &SearchResult, block2: &SearchResult) -> String {
                         );
                     }
 
-                    // Read the file content directly instead of using a reader clone
-                    // which might have its position already moved forward
-                    let file_result = File::open(Path::new(&block1.file));
-
-                    if let Ok(file) = file_result {
-                        let reader = BufReader::new(file);
+                    // Take the lines of the gap from the file read above
+                    let lines_read: Vec<String> = content
+                        .lines()
+                        .skip(gap_start.saturating_sub(1))
+                        .take(gap_size)
+                        .map(String::from)
+                        .collect();
 
+                    // Add the actual content for the gap
+                    if !lines_read.is_empty() {
                         if debug_mode {
-                            println!("DEBUG: Created fresh file reader for gap");
-                        }
-
-                        // Read the file line by line
-                        let mut lines_read = Vec::new();
-                        let mut current_line_in_file = 1;
-
-                        for line_content in reader.lines().map_while(Result::ok) {
-                            if current_line_in_file >= gap_start && current_line_in_file <= gap_end
-                            {
-                                lines_read.push(line_content);
-                            }
-
-                            current_line_in_file += 1;
-
-                            if current_line_in_file > gap_end {
-                                break;
-                            }
-                        }
-
-                        // Add the actual content for the gap
-                        if !lines_read.is_empty() {
-                            if debug_mode {
-                                println!(
-                                    "DEBUG: Successfully read {} lines for gap",
-                                    lines_read.len()
-                                );
-                            }
-                            merged_lines.extend(lines_read);
-                            current_line = gap_end + 1;
-                            continue;
-                        } else if debug_mode {
-                            println!("DEBUG: No lines were read for the gap (empty lines)");
+                            println!(
+                                "DEBUG: Successfully read {} lines for gap",
+                                lines_read.len()
+                            );
                         }
+                        merged_lines.extend(lines_read);
+                        current_line = gap_end + 1;
+                        continue;
                     } else if debug_mode {
-                        println!("DEBUG: Could not create fresh file reader");
+                        println!("DEBUG: No lines were read for the gap (empty lines)");
                     }
                 } else if debug_mode {
                     println!("DEBUG: File content not available for {}", block1.file);
@@ -413,6 +404,37 @@ fn merge_block_content(block1: &SearchResult, block2: &SearchResult) -> String {
     merged_lines.join("\n")
 }
 
+/// Helper function to build the provenance list for a merged block
+///
+/// Blocks that were already merged contribute their existing provenance entries,
+/// while plain blocks contribute a single entry describing themselves.
+///
+/// # Arguments
+/// * `block1` - First search result (usually the block being grown)
+/// * `block2` - Second search result being folded into it
+///
+/// # Returns
+/// The combined provenance entries, ordered by start line
+fn merge_provenance(block1: &SearchResult, block2: &SearchResult) -> Vec<MergedBlockInfo> {
+    let mut provenance = Vec::new();
+
+    for block in [block1, block2] {
+        match &block.merged_from {
+            Some(entries) => provenance.extend(entries.iter().cloned()),
+            None => provenance.push(MergedBlockInfo {
+                block_id: block.block_id,
+                lines: block.lines,
+                node_type: block.node_type.clone(),
+                rank: block.rank,
+                score: block.score,
+            }),
+        }
+    }
+
+    provenance.sort_by_key(|info| info.lines.0);
+    provenance
+}
+
 /// Helper function to merge scores from two blocks
 ///
 /// # Arguments
@@ -0,0 +1,48 @@
+//! Cooperative cancellation of running searches.
+//!
+//! A search is driven from one thread from start to finish, so the token for the current
+//! search is kept in a thread-local and checked between files and between search stages.
+//! Stages that fan out to rayon threads take the token before they start.
+
+use anyhow::Result;
+use std::cell::RefCell;
+use tokio_util::sync::CancellationToken;
+
+/// Error returned by a search that was cancelled before it finished
+///
+/// The library API reports it as [`ProbeError::Timeout`](crate::error::ProbeError::Timeout).
+#[derive(Debug, thiserror::Error)]
+#[error("search cancelled")]
+pub struct Cancelled;
+
+thread_local! {
+    static CURRENT_TOKEN: RefCell<Option<CancellationToken>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with `token` as the cancellation token of searches on this thread
+pub(crate) fn with_cancellation<T>(token: CancellationToken, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<CancellationToken>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            CURRENT_TOKEN.with(|current| *current.borrow_mut() = previous);
+        }
+    }
+
+    let previous = CURRENT_TOKEN.with(|current| current.borrow_mut().replace(token));
+    let _restore = Restore(previous);
+    f()
+}
+
+/// The cancellation token of the search running on this thread, if any
+pub(crate) fn current_token() -> Option<CancellationToken> {
+    CURRENT_TOKEN.with(|current| current.borrow().clone())
+}
+
+/// Fail with [`Cancelled`] if `token` has been cancelled
+pub(crate) fn check(token: Option<&CancellationToken>) -> Result<()> {
+    match token {
+        Some(token) if token.is_cancelled() => Err(Cancelled.into()),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::fs_access;
+
+/// Seconds in a day, the unit of file age in the recency score
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// Function to score a file from what the pattern search already knows about it
+///
+/// Covering more query terms counts most, then the number of matched lines and a match
+/// on the file name. Files changed recently get up to one extra point, halving after a
+/// month. Files that matched by name have every line marked as matched, so their line
+/// count adds to the file name bonus.
+fn cheap_score(
+    path: &Path,
+    term_map: Option<&HashMap<usize, HashSet<usize>>>,
+    num_terms: usize,
+    filename_matched: bool,
+    now: SystemTime,
+) -> f64 {
+    let (terms, lines) = term_map
+        .map(|term_map| {
+            let lines: HashSet<usize> = term_map.values().flatten().copied().collect();
+            (term_map.len(), lines.len())
+        })
+        .unwrap_or((0, 0));
+
+    let coverage = terms as f64 / num_terms.max(1) as f64;
+    let filename = if filename_matched { 1.0 } else { 0.0 };
+    let recency = fs_access::current()
+        .stamp(path)
+        .and_then(|stamp| stamp.ok())
+        .and_then(|(modified, _)| now.duration_since(modified).ok())
+        .map(|age| 1.0 / (1.0 + age.as_secs_f64() / SECONDS_PER_DAY / 30.0))
+        .unwrap_or(0.0);
+
+    4.0 * coverage + (1.0 + lines as f64).ln() + filename + recency
+}
+
+/// Function to select the `k` files most worth extracting blocks from
+///
+/// Scores every file cheaply, see `cheap_score`, and keeps the best `k`. Ties go to the
+/// file that comes first by path, so the selection does not depend on hash order.
+///
+/// # Arguments
+/// * `files` - The files that matched the query
+/// * `file_term_map` - Matched lines per query term for each file
+/// * `filename_matched` - Files whose names matched query terms
+/// * `num_terms` - The number of terms in the query
+/// * `k` - How many files to keep
+pub fn select_candidate_files(
+    files: &HashSet<PathBuf>,
+    file_term_map: &HashMap<PathBuf, HashMap<usize, HashSet<usize>>>,
+    filename_matched: &HashSet<PathBuf>,
+    num_terms: usize,
+    k: usize,
+) -> HashSet<PathBuf> {
+    if files.len() <= k {
+        return files.clone();
+    }
+
+    let now = SystemTime::now();
+    let mut scored: Vec<(f64, &PathBuf)> = files
+        .iter()
+        .map(|path| {
+            let score = cheap_score(
+                path,
+                file_term_map.get(path),
+                num_terms,
+                filename_matched.contains(path),
+                now,
+            );
+            (score, path)
+        })
+        .collect();
+    scored.sort_by(|a, b| {
+        b.0.partial_cmp(&a.0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.1.cmp(b.1))
+    });
+
+    scored
+        .into_iter()
+        .take(k)
+        .map(|(_, path)| path.clone())
+        .collect()
+}
@@ -0,0 +1,339 @@
+//! Searching for a code snippet regardless of how it is formatted.
+//!
+//! With `--code-pattern`, the query is a piece of code such as `if err != nil { return err }`.
+//! Each file is split into the lexemes of its tree-sitter grammar, leaving out comments, and
+//! matches where its lexemes spell out the snippet. Line breaks, indentation and spacing
+//! between tokens do not matter, and neither does a trailing comma before a closing bracket,
+//! so copies of the snippet rewrapped by a formatter are found too.
+
+use anyhow::Result;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tree_sitter::{Node, Parser as TSParser};
+
+use crate::fs_access;
+use crate::language::detection::{detect_language, parse_extension, LanguageOverrides};
+use crate::language::factory::get_language_impl;
+use crate::language::language_trait::LanguageImpl;
+use crate::models::{ExtractionConfidence, SearchResult};
+use crate::search::{content_hash, file_list_cache, io_errors};
+
+/// A leaf of the syntax tree
+struct Lexeme<'a> {
+    text: &'a str,
+    start_byte: usize,
+    end_byte: usize,
+}
+
+/// Helper function to check whether a character can be part of an identifier or keyword
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Function to normalize code for comparison
+///
+/// Whitespace is dropped, except for a single space between two words so that
+/// `return err` does not become `returnerr`. Commas directly before a closing bracket
+/// are dropped as well.
+pub fn normalize_code(code: &str) -> String {
+    let mut normalized = String::with_capacity(code.len());
+    let mut pending_space = false;
+    for c in code.chars() {
+        if c.is_whitespace() {
+            pending_space = true;
+            continue;
+        }
+        if matches!(c, ')' | ']' | '}') && normalized.ends_with(',') {
+            normalized.pop();
+        }
+        if pending_space && is_word_char(c) && normalized.ends_with(is_word_char) {
+            normalized.push(' ');
+        }
+        pending_space = false;
+        normalized.push(c);
+    }
+    normalized
+}
+
+/// Collect the leaves of a tree in source order, without comments
+fn collect_lexemes<'a>(node: Node<'_>, source: &'a str, lexemes: &mut Vec<Lexeme<'a>>) {
+    if node.kind().contains("comment") {
+        return;
+    }
+    if node.child_count() == 0 {
+        let text = &source[node.start_byte()..node.end_byte()];
+        if !text.trim().is_empty() {
+            lexemes.push(Lexeme {
+                text,
+                start_byte: node.start_byte(),
+                end_byte: node.end_byte(),
+            });
+        }
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_lexemes(child, source, lexemes);
+    }
+}
+
+/// Find the byte ranges where the lexemes spell out the normalized snippet
+///
+/// The lexemes are normalized and joined into one string, remembering where each lexeme
+/// starts and ends in it. A match of the snippet counts only when it starts and ends on
+/// lexeme boundaries, so `err` does not match inside `myerr`.
+fn find_matches(lexemes: &[Lexeme], snippet: &str) -> Vec<(usize, usize)> {
+    let mut joined = String::new();
+    let mut starts: HashMap<usize, usize> = HashMap::new();
+    let mut ends: HashMap<usize, usize> = HashMap::new();
+
+    for (i, lexeme) in lexemes.iter().enumerate() {
+        let text = normalize_code(lexeme.text);
+        // Trailing commas are left out, as in the snippet
+        let next = lexemes.get(i + 1).map(|next| next.text.trim_start());
+        if text == "," && next.is_some_and(|next| next.starts_with([')', ']', '}'])) {
+            continue;
+        }
+        if text.starts_with(is_word_char) && joined.ends_with(is_word_char) {
+            joined.push(' ');
+        }
+        starts.insert(joined.len(), i);
+        joined.push_str(&text);
+        ends.insert(joined.len(), i);
+    }
+
+    joined
+        .match_indices(snippet)
+        .filter_map(|(offset, _)| {
+            let first = starts.get(&offset)?;
+            let last = ends.get(&(offset + snippet.len()))?;
+            Some((lexemes[*first].start_byte, lexemes[*last].end_byte))
+        })
+        .collect()
+}
+
+/// Find the smallest block around a byte range that results are built from
+fn enclosing_block<'a>(
+    root: Node<'a>,
+    start_byte: usize,
+    end_byte: usize,
+    language_impl: &dyn LanguageImpl,
+) -> Option<Node<'a>> {
+    let mut current = root.descendant_for_byte_range(start_byte, end_byte)?;
+    loop {
+        if language_impl.is_acceptable_parent(&current) {
+            return Some(current);
+        }
+        current = current.parent()?;
+    }
+}
+
+/// Find the blocks in one file that contain the snippet
+///
+/// `snippet` is normalized, `pattern` is the snippet as given and reported as the match.
+fn search_file(
+    file_path: &Path,
+    snippet: &str,
+    pattern: &str,
+    allow_tests: bool,
+    language_overrides: &LanguageOverrides,
+) -> Result<Vec<SearchResult>> {
+    let extension = parse_extension(file_path, language_overrides);
+    let Some(language_impl) = get_language_impl(extension) else {
+        return Ok(Vec::new());
+    };
+
+    let content = fs_access::read_to_string(file_path)?;
+
+    // Cheap pre-check before parsing: the longest word of the snippet must appear somewhere
+    let longest_word = snippet
+        .split(|c: char| !is_word_char(c))
+        .max_by_key(|word| word.len())
+        .unwrap_or("");
+    if !content.contains(longest_word) {
+        return Ok(Vec::new());
+    }
+
+    let mut parser = TSParser::new();
+    parser.set_language(&language_impl.get_tree_sitter_language())?;
+    let Some(tree) = parser.parse(&content, None) else {
+        return Ok(Vec::new());
+    };
+
+    let mut lexemes = Vec::new();
+    collect_lexemes(tree.root_node(), &content, &mut lexemes);
+
+    let mut results: Vec<SearchResult> = Vec::new();
+    for (start_byte, end_byte) in find_matches(&lexemes, snippet) {
+        let block = enclosing_block(
+            tree.root_node(),
+            start_byte,
+            end_byte,
+            language_impl.as_ref(),
+        );
+        if let Some(block) = block {
+            if !allow_tests && language_impl.is_test_node(&block, content.as_bytes()) {
+                continue;
+            }
+        }
+
+        // Without an enclosing block, the result is the lines of the match itself
+        let (start_byte, end_byte, node_type) = match block {
+            Some(block) => (block.start_byte(), block.end_byte(), block.kind()),
+            None => {
+                let line_start = content[..start_byte].rfind('\n').map_or(0, |i| i + 1);
+                let line_end = content[end_byte..]
+                    .find('\n')
+                    .map_or(content.len(), |i| end_byte + i);
+                (line_start, line_end, "code_pattern")
+            }
+        };
+        let start_line = content[..start_byte].matches('\n').count() + 1;
+        let end_line = start_line + content[start_byte..end_byte].matches('\n').count();
+        let lines = (start_line, end_line);
+
+        if let Some(existing) = results.iter_mut().find(|r| r.lines == lines) {
+            // Another match in the same block
+            existing.score = existing.score.map(|score| score + 1.0);
+            existing.block_total_matches = existing.block_total_matches.map(|n| n + 1);
+            continue;
+        }
+
+        let code = &content[start_byte..end_byte];
+        results.push(SearchResult {
+            file: file_path.to_string_lossy().to_string(),
+            file_path: Some(file_path.to_path_buf()),
+            lines,
+            node_type: node_type.to_string(),
+            code: code.to_string(),
+            matched_by_filename: None,
+            rank: None,
+            score: Some(1.0),
+            normalized_score: None,
+            tfidf_score: None,
+            bm25_score: None,
+            tfidf_rank: None,
+            bm25_rank: None,
+            new_score: None,
+            hybrid2_rank: None,
+            combined_score_rank: None,
+            file_unique_terms: Some(1),
+            file_total_matches: None,
+            file_match_rank: None,
+            block_unique_terms: Some(1),
+            block_total_matches: Some(1),
+            parent_file_id: None,
+            block_id: None,
+            matched_keywords: Some(vec![pattern.trim().to_string()]),
+            tokenized_content: None,
+            merged_from: None,
+            usage_kind: None,
+            via_symlink: false,
+            changed_lines: None,
+            symbol_name: None,
+            language: detect_language(file_path, &content, language_overrides).map(str::to_string),
+            extraction_confidence: Some(if block.is_some() {
+                ExtractionConfidence::Ast
+            } else {
+                ExtractionConfidence::Heuristic
+            }),
+            partial_parse: tree.root_node().has_error(),
+            deprecated: false,
+            embedded: None,
+            kind: None,
+            complexity: None,
+            content_hash: Some(content_hash::hash_content(&content)),
+        });
+    }
+
+    Ok(results)
+}
+
+/// Function to find code blocks containing a code snippet
+///
+/// Each result is the smallest function, class or other block around a match, with
+/// blocks holding more matches ranking higher. Matches outside any block are returned
+/// as the lines they span.
+///
+/// # Arguments
+/// * `path` - Root directory to search
+/// * `snippet` - The code to look for
+/// * `custom_ignores` - Custom ignore patterns
+/// * `allow_tests` - Whether to include test files and test blocks
+/// * `follow_symlinks` - Whether to follow symbolic links while scanning
+/// * `filters` - Depth, modification time and size limits for the scan
+/// * `language_overrides` - Extensions to parse as another language
+pub fn search_code_pattern(
+    path: &Path,
+    snippet: &str,
+    custom_ignores: &[String],
+    allow_tests: bool,
+    follow_symlinks: bool,
+    filters: file_list_cache::FileFilters,
+    language_overrides: &LanguageOverrides,
+) -> Result<Vec<SearchResult>> {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    let normalized = normalize_code(snippet);
+    if normalized.is_empty() {
+        return Err(anyhow::anyhow!("The code pattern is empty"));
+    }
+    if debug_mode {
+        println!("DEBUG: Searching for code pattern {:?}", normalized);
+    }
+
+    let file_list = file_list_cache::get_file_list(
+        path,
+        allow_tests,
+        custom_ignores,
+        follow_symlinks,
+        filters,
+    )?;
+
+    for error in &file_list.errors {
+        io_errors::record_file_error(error.clone());
+    }
+
+    // Files are read on rayon threads, which take this thread's file system
+    let file_system = fs_access::current();
+    let searched: Vec<(&PathBuf, Result<Vec<SearchResult>>)> = file_list
+        .files
+        .par_iter()
+        .map(|file| {
+            fs_access::with_file_system(Arc::clone(&file_system), || {
+                let found =
+                    search_file(file, &normalized, snippet, allow_tests, language_overrides);
+                (file, found)
+            })
+        })
+        .collect();
+
+    // Errors are recorded on this thread, where the search collects them
+    let mut results: Vec<SearchResult> = Vec::new();
+    for (file, searched) in searched {
+        match searched {
+            Ok(mut file_results) => results.append(&mut file_results),
+            Err(e) => {
+                if debug_mode {
+                    println!("DEBUG: Error searching file {:?}: {:?}", file, e);
+                }
+                io_errors::record_if_io(file, &e);
+            }
+        }
+    }
+
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.file.cmp(&b.file))
+            .then_with(|| a.lines.cmp(&b.lines))
+    });
+    for (i, result) in results.iter_mut().enumerate() {
+        result.rank = Some(i + 1);
+    }
+
+    Ok(results)
+}
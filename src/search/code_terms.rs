@@ -0,0 +1,186 @@
+//! Code-aware normalization of identifier terms.
+//!
+//! Naming conventions wrap the same concept in different words: `getUser`, `user_impl`
+//! and `UserImpl` all name a user, and `indices` is the plural of `index` although the
+//! stemmer keeps them apart. With the rules of a language selected for a search, the
+//! tokenizer drops that language's accessor prefixes and implementation suffixes from
+//! identifiers with more than one part, and maps irregular plurals to their singular.
+//! Query terms and block contents go through the same tokenizer, so both sides agree.
+//!
+//! The rules are kept per thread like the file system: the search installs the ones its
+//! options select, and stages that fan out to rayon threads pass [`current`] on to them.
+
+use crate::ranking::get_stemmer;
+use crate::search::tokenization::is_stop_word;
+use std::cell::Cell;
+
+/// Prefixes and suffixes one language's naming conventions add to identifiers
+#[derive(Debug, PartialEq, Eq)]
+pub struct CodeTermRules {
+    language: &'static str,
+    prefixes: &'static [&'static str],
+    suffixes: &'static [&'static str],
+}
+
+const COMMON_PREFIXES: [&str; 4] = ["get", "set", "is", "has"];
+
+/// Rules for each language, with `all` combining them
+static RULES: &[CodeTermRules] = &[
+    CodeTermRules {
+        language: "all",
+        prefixes: &[
+            "get", "set", "is", "has", "try", "into", "as", "to", "new", "must", "use", "on",
+            "handle", "abstract",
+        ],
+        suffixes: &[
+            "impl", "mut", "ref", "ext", "func", "handler", "props", "async",
+        ],
+    },
+    CodeTermRules {
+        language: "rust",
+        prefixes: &["get", "set", "is", "has", "try", "into", "as", "to"],
+        suffixes: &["impl", "mut", "ref", "ext"],
+    },
+    CodeTermRules {
+        language: "go",
+        prefixes: &["get", "set", "is", "has", "new", "must"],
+        suffixes: &["impl", "func"],
+    },
+    CodeTermRules {
+        language: "python",
+        prefixes: &COMMON_PREFIXES,
+        suffixes: &["impl"],
+    },
+    CodeTermRules {
+        language: "javascript",
+        prefixes: &["get", "set", "is", "has", "use", "on", "handle"],
+        suffixes: &["impl", "handler", "props"],
+    },
+    CodeTermRules {
+        language: "typescript",
+        prefixes: &["get", "set", "is", "has", "use", "on", "handle"],
+        suffixes: &["impl", "handler", "props"],
+    },
+    CodeTermRules {
+        language: "java",
+        prefixes: &["get", "set", "is", "has", "abstract"],
+        suffixes: &["impl"],
+    },
+    CodeTermRules {
+        language: "csharp",
+        prefixes: &["get", "set", "is", "has", "try"],
+        suffixes: &["impl", "async"],
+    },
+    CodeTermRules {
+        language: "ruby",
+        prefixes: &COMMON_PREFIXES,
+        suffixes: &["impl"],
+    },
+    CodeTermRules {
+        language: "php",
+        prefixes: &COMMON_PREFIXES,
+        suffixes: &["impl"],
+    },
+];
+
+/// Plurals the stemmer does not bring to the stem of their singular
+const IRREGULAR_PLURALS: &[(&str, &str)] = &[
+    ("indices", "index"),
+    ("vertices", "vertex"),
+    ("matrices", "matrix"),
+    ("appendices", "appendix"),
+    ("children", "child"),
+    ("people", "person"),
+    ("criteria", "criterion"),
+    ("analyses", "analysis"),
+    ("axes", "axis"),
+    ("aliases", "alias"),
+    ("statuses", "status"),
+    ("leaves", "leaf"),
+    ("schemata", "schema"),
+];
+
+/// Languages accepted for `--code-terms`
+pub const CODE_TERM_LANGUAGES: &[&str] = &[
+    "all",
+    "rust",
+    "go",
+    "python",
+    "javascript",
+    "typescript",
+    "java",
+    "csharp",
+    "ruby",
+    "php",
+];
+
+impl CodeTermRules {
+    /// The language the rules are for
+    pub fn language(&self) -> &'static str {
+        self.language
+    }
+
+    /// Drop one accessor prefix and one implementation suffix from the parts of an identifier
+    ///
+    /// A part is only dropped when another part that is not a stop word remains, so
+    /// `get` and `getString` keep their terms.
+    pub fn strip_affixes(&self, parts: &mut Vec<String>) {
+        let is_affix = |part: &str, affixes: &[&str]| affixes.contains(&&*part.to_lowercase());
+        let meaningful =
+            |parts: &[String]| parts.iter().any(|part| !is_stop_word(&part.to_lowercase()));
+        if parts.len() > 1 && is_affix(&parts[0], self.prefixes) && meaningful(&parts[1..]) {
+            parts.remove(0);
+        }
+        let last = parts.len().saturating_sub(1);
+        if parts.len() > 1 && is_affix(&parts[last], self.suffixes) && meaningful(&parts[..last]) {
+            parts.pop();
+        }
+    }
+
+    /// The singular of an irregular plural, or the word itself
+    pub fn singular<'a>(&self, word: &'a str) -> &'a str {
+        IRREGULAR_PLURALS
+            .iter()
+            .find(|(plural, _)| *plural == word)
+            .map_or(word, |(_, singular)| singular)
+    }
+
+    /// Irregular plurals whose singular stems to `term`, which a substring match on `term` misses
+    pub fn plural_variants(&self, term: &str) -> Vec<&'static str> {
+        let stemmer = get_stemmer();
+        IRREGULAR_PLURALS
+            .iter()
+            .filter(|(plural, singular)| stemmer.stem(singular) == term && !plural.contains(term))
+            .map(|(plural, _)| *plural)
+            .collect()
+    }
+}
+
+/// Function to look up the rules for a language named in `CODE_TERM_LANGUAGES`
+pub fn rules_for(language: &str) -> Option<&'static CodeTermRules> {
+    RULES.iter().find(|rules| rules.language == language)
+}
+
+thread_local! {
+    static SCOPED: Cell<Option<&'static CodeTermRules>> = const { Cell::new(None) };
+}
+
+/// Function to get the rules the tokenizer applies on this thread, if any
+pub fn current() -> Option<&'static CodeTermRules> {
+    SCOPED.with(Cell::get)
+}
+
+/// Function to run `f` with `rules` applied by the tokenizer on this thread
+///
+/// `None` turns code term normalization off. The previous rules are restored afterwards.
+pub fn with_code_terms<T>(rules: Option<&'static CodeTermRules>, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<&'static CodeTermRules>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SCOPED.with(|scoped| scoped.set(self.0));
+        }
+    }
+
+    let _restore = Restore(SCOPED.with(|scoped| scoped.replace(rules)));
+    f()
+}
@@ -0,0 +1,77 @@
+//! Cheap complexity metrics for extracted blocks.
+//!
+//! The metrics are read from the text of a block, without its syntax tree, so they cost
+//! little more than the tokenization each block already goes through:
+//!
+//! - lines: the number of lines in the block
+//! - nesting depth: how many indentation levels the deepest line sits below the first
+//! - cyclomatic estimate: one plus the number of branch keywords (`if`, `for`, `while`,
+//!   `case`, `catch`, ...) and short-circuit operators (`&&`, `||`) in the code, leaving
+//!   out comment lines
+//!
+//! They are estimates meant for ranking refactoring candidates with `--sort complexity`,
+//! not exact measures.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::models::BlockComplexity;
+
+/// Keywords and operators that each add a path through the code
+static DECISION_POINTS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"\b(?:if|elif|elsif|for|foreach|while|until|case|when|catch|except|rescue|guard)\b|&&|\|\|",
+    )
+    .unwrap()
+});
+
+/// Width of a tab when measuring indentation
+const TAB_WIDTH: usize = 4;
+
+/// Helper function to check whether a line only holds a comment
+fn is_comment_line(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    ["//", "#", "/*", "*", "--"]
+        .iter()
+        .any(|marker| trimmed.starts_with(marker))
+        && !trimmed.starts_with("#[")
+        && !trimmed.starts_with("#if")
+}
+
+/// Helper function to measure the indentation of a line
+fn indentation(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum()
+}
+
+/// Function to compute the metrics of a block of code
+pub fn block_complexity(code: &str) -> BlockComplexity {
+    let code_lines: Vec<&str> = code
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !is_comment_line(line))
+        .collect();
+
+    // Levels are counted in the smallest indentation step the block uses
+    let base = code_lines.first().map_or(0, |line| indentation(line));
+    let steps: Vec<usize> = code_lines
+        .iter()
+        .map(|line| indentation(line).saturating_sub(base))
+        .collect();
+    let nesting_depth = match steps.iter().filter(|&&step| step > 0).min() {
+        Some(&unit) => steps.iter().max().map_or(0, |&deepest| deepest / unit),
+        None => 0,
+    };
+
+    let decisions: usize = code_lines
+        .iter()
+        .map(|line| DECISION_POINTS.find_iter(line).count())
+        .sum();
+
+    BlockComplexity {
+        lines: code.lines().count(),
+        nesting_depth,
+        cyclomatic: decisions + 1,
+    }
+}
@@ -0,0 +1,113 @@
+//! Content hashes that tie search results to the code they were found in.
+//!
+//! Each result carries the hash of the file content it was extracted from, taken when the
+//! file was read for the search. The output summary carries two fingerprints: the
+//! `results_fingerprint` of the files the results came from, which a consumer holding cached
+//! or bundled results can compute again by hashing those files, and the `corpus_fingerprint`
+//! of every file the search considered, which changes when any candidate file is added,
+//! removed or modified, even one that didn't match before and would now. It is made from the
+//! path, modification time and length of each file, so it is only comparable between
+//! searches of the same checkout.
+//!
+//! Hashes are the 64-bit FNV-1a of [`crate::query_history::fnv1a`], which the query history
+//! digests use too, written as 16 lowercase hex digits. They are meant to detect changes, not
+//! tampering, and stay the same across platforms and probe versions.
+
+use anyhow::Result;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::time::UNIX_EPOCH;
+
+use crate::fs_access;
+use crate::models::SearchResult;
+use crate::query_history::{fnv1a, FNV_OFFSET};
+use crate::search::{file_list_cache, SearchOptions};
+
+/// Function to hash the content of a file
+pub fn hash_content(content: &str) -> String {
+    format!("{:016x}", fnv1a(content.as_bytes(), FNV_OFFSET))
+}
+
+/// Function to fingerprint a set of files from their paths and content hashes
+///
+/// The order of the pairs does not matter, and a file listed more than once counts once.
+pub fn corpus_fingerprint<'a>(files: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    let files: BTreeMap<&str, &str> = files.into_iter().collect();
+    let hash = files.iter().fold(FNV_OFFSET, |hash, (path, content_hash)| {
+        let hash = fnv1a(path.as_bytes(), hash);
+        let hash = fnv1a(b"\0", hash);
+        let hash = fnv1a(content_hash.as_bytes(), hash);
+        fnv1a(b"\n", hash)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Function to fingerprint the files that search results came from
+///
+/// Results without a content hash, such as `--files-only` entries, are left out. Returns
+/// None when no result has one.
+pub fn results_fingerprint<'a>(
+    results: impl IntoIterator<Item = &'a SearchResult>,
+) -> Option<String> {
+    let files: Vec<(&str, &str)> = results
+        .into_iter()
+        .filter_map(|r| Some((r.file.as_str(), r.content_hash.as_deref()?)))
+        .collect();
+    (!files.is_empty()).then(|| corpus_fingerprint(files))
+}
+
+/// Function to fingerprint every file a search over `options` considers
+///
+/// Lists the files the search would walk, which the search then reuses, and stats each of
+/// them: the fingerprint covers the path, modification time and length of every candidate
+/// file, so adding, removing or changing any of them changes it, whether or not it matched.
+/// Returns `None` when the file system can't tell when files changed, e.g. for files in
+/// memory.
+pub fn files_fingerprint(options: &SearchOptions) -> Result<Option<String>> {
+    let walk_allow_tests = options.allow_tests || options.tests_for.is_some();
+    let file_list = if options.roots.is_empty() {
+        file_list_cache::get_file_list(
+            options.path,
+            walk_allow_tests,
+            options.custom_ignores,
+            options.follow_symlinks,
+            options.file_filters(),
+        )?
+    } else {
+        file_list_cache::get_roots_file_list(
+            options.roots,
+            walk_allow_tests,
+            options.custom_ignores,
+            options.follow_symlinks,
+            options.file_filters(),
+        )?
+    };
+
+    let file_system = fs_access::current();
+    let stamps: Option<Vec<(String, String)>> = file_list
+        .files
+        .par_iter()
+        .map(|path| {
+            let stamp = match file_system.stamp(path)? {
+                Ok((modified, len)) => {
+                    let modified = modified
+                        .duration_since(UNIX_EPOCH)
+                        .map(|since| since.as_nanos())
+                        .unwrap_or(0);
+                    format!("{}:{}", modified, len)
+                }
+                Err(_) => "unreadable".to_string(),
+            };
+            Some((path.to_string_lossy().to_string(), stamp))
+        })
+        .collect();
+    let Some(stamps) = stamps else {
+        return Ok(None);
+    };
+
+    Ok(Some(corpus_fingerprint(
+        stamps
+            .iter()
+            .map(|(path, stamp)| (path.as_str(), stamp.as_str())),
+    )))
+}
@@ -0,0 +1,103 @@
+//! Language-aware tokenization of file content.
+//!
+//! Block contents are tokenized like queries, splitting identifiers on every
+//! non-alphanumeric character. That loses the compound names languages build with
+//! their own separators: `::` paths in Rust (`std::mem::take`), `.` chains in
+//! JavaScript (`user.profile.name`) and kebab-case in CSS and shell scripts
+//! (`font-size`, `--max-results`). For a block in a language listed in
+//! [`BOUNDARIES`], each compound name is kept as a token as well, together with its
+//! shorter suffixes (`mem::take`), so quoted queries for them match and BM25 counts
+//! them as terms of their own.
+//!
+//! To support another language, add its name (as `language::detection` reports it)
+//! and the separators that join its names to [`BOUNDARIES`].
+
+use crate::search::tokenization::tokenize_code;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashSet;
+
+/// Separators that join the parts of one name, per language
+pub const BOUNDARIES: &[(&str, &[&str])] = &[
+    ("rust", &["::"]),
+    ("cpp", &["::"]),
+    ("php", &["::"]),
+    ("ruby", &["::"]),
+    ("javascript", &["."]),
+    ("typescript", &["."]),
+    ("python", &["."]),
+    ("java", &["."]),
+    ("go", &["."]),
+    ("csharp", &["."]),
+    ("kotlin", &["."]),
+    ("swift", &["."]),
+    ("dart", &["."]),
+    ("lua", &["."]),
+    ("css", &["-"]),
+    ("html", &["-"]),
+    ("shell", &["-"]),
+    ("yaml", &["-"]),
+    ("dockerfile", &["-"]),
+    // Content of the SQL strings found with --embedded
+    ("sql", &["."]),
+];
+
+/// A language's compound names and the separators within them
+struct CompoundNames {
+    language: &'static str,
+    name: Regex,
+    separator: Regex,
+}
+
+static COMPOUND_NAMES: Lazy<Vec<CompoundNames>> = Lazy::new(|| {
+    BOUNDARIES
+        .iter()
+        .map(|(language, separators)| {
+            let separator = separators
+                .iter()
+                .map(|separator| regex::escape(separator))
+                .collect::<Vec<_>>()
+                .join("|");
+            let part = "[A-Za-z_][A-Za-z0-9_]*";
+            CompoundNames {
+                language,
+                name: Regex::new(&format!("{part}(?:(?:{separator}){part})+")).unwrap(),
+                separator: Regex::new(&separator).unwrap(),
+            }
+        })
+        .collect()
+});
+
+/// Function to tokenize the content of a block written in `language`
+///
+/// Returns the tokens of [`tokenize_code`] followed by the lowercased compound names
+/// of the language, each with its suffixes of two or more parts.
+pub fn tokenize_content(text: &str, language: Option<&str>) -> Vec<String> {
+    let mut tokens = tokenize_code(text);
+    let Some(names) = language.and_then(|language| {
+        COMPOUND_NAMES
+            .iter()
+            .find(|names| names.language == language)
+    }) else {
+        return tokens;
+    };
+
+    let mut seen: HashSet<String> = tokens.iter().cloned().collect();
+    for found in names.name.find_iter(text) {
+        let name = found.as_str().to_lowercase();
+        let separators: Vec<_> = names.separator.find_iter(&name).collect();
+        // The name itself, then what follows each separator but the last
+        let starts = std::iter::once(0).chain(
+            separators[..separators.len() - 1]
+                .iter()
+                .map(|separator| separator.end()),
+        );
+        for start in starts {
+            let suffix = &name[start..];
+            if seen.insert(suffix.to_string()) {
+                tokens.push(suffix.to_string());
+            }
+        }
+    }
+    tokens
+}
@@ -0,0 +1,175 @@
+//! Document frequencies of a whole corpus, shared by the searches over it.
+//!
+//! BM25 weighs a query term by how many documents contain it. A search on its own only
+//! sees the blocks its query matched, so it counts those, and two queries sharing a term
+//! can weigh it differently. A long-running [`ProbeEngine`](crate::search::ProbeEngine),
+//! such as one behind a server answering many clients, can instead keep [`CorpusStats`]
+//! over all its files: built once, updated as files change, and read by every search, so a
+//! query only looks up its own terms and is ranked the same way for every client.
+//!
+//! Documents are files here, so a term's IDF is the share of files it appears in. Searches
+//! find the statistics through [`with_corpus_stats`], as they do their file system.
+
+use ahash::{AHashMap, AHashSet};
+use rayon::prelude::*;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use crate::fs_access;
+use crate::ranking;
+
+/// Corpus statistics that searches read and change events update
+pub type SharedCorpusStats = Arc<RwLock<CorpusStats>>;
+
+/// Which terms each file of a corpus contains
+#[derive(Debug, Clone, Default)]
+pub struct CorpusStats {
+    /// Distinct terms of each file, to take them out again when it changes
+    files: HashMap<PathBuf, HashSet<String>>,
+    /// Number of files each term appears in
+    document_frequencies: HashMap<String, usize>,
+}
+
+/// Helper function to get the distinct terms of a file as ranking tokenizes it
+fn file_terms(path: &Path, content: &str) -> HashSet<String> {
+    // Ranked documents start with their file name, so these do as well
+    let document = format!("// Filename: {}\n{}", path.to_string_lossy(), content);
+    ranking::tokenize(&document).into_iter().collect()
+}
+
+impl CorpusStats {
+    /// Create statistics over no files
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build statistics over `files`, read through the current file system
+    ///
+    /// Files that cannot be read, e.g. binary ones, are left out as searches leave them out.
+    pub fn build(files: &[PathBuf]) -> Self {
+        // Files are read on rayon threads, which take this thread's file system
+        let file_system = fs_access::current();
+        let terms: Vec<(PathBuf, HashSet<String>)> = files
+            .par_iter()
+            .filter_map(|path| {
+                fs_access::with_file_system(Arc::clone(&file_system), || {
+                    let content = fs_access::read_to_string(path).ok()?;
+                    Some((path.clone(), file_terms(path, &content)))
+                })
+            })
+            .collect();
+
+        let mut stats = Self::new();
+        for (path, terms) in terms {
+            stats.insert(path, terms);
+        }
+        stats
+    }
+
+    fn insert(&mut self, path: PathBuf, terms: HashSet<String>) {
+        for term in &terms {
+            *self.document_frequencies.entry(term.clone()).or_insert(0) += 1;
+        }
+        self.files.insert(path, terms);
+    }
+
+    /// Add the files counted in `other`, replacing what they held here before
+    pub fn merge(&mut self, other: CorpusStats) {
+        for (path, terms) in other.files {
+            self.remove_file(&path);
+            self.insert(path, terms);
+        }
+    }
+
+    /// Take a file out of the statistics, if it is in them
+    pub fn remove_file(&mut self, path: &Path) {
+        let Some(terms) = self.files.remove(path) else {
+            return;
+        };
+        for term in terms {
+            if let Some(count) = self.document_frequencies.get_mut(&term) {
+                *count -= 1;
+                if *count == 0 {
+                    self.document_frequencies.remove(&term);
+                }
+            }
+        }
+    }
+
+    /// Count a file with its new content, replacing what it held before
+    pub fn update_file(&mut self, path: &Path, content: &str) {
+        self.remove_file(path);
+        self.insert(path.to_path_buf(), file_terms(path, content));
+    }
+
+    /// Number of files counted
+    pub fn document_count(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Number of files `term` appears in
+    pub fn document_frequency(&self, term: &str) -> usize {
+        self.document_frequencies.get(term).copied().unwrap_or(0)
+    }
+
+    /// Paths of the counted files
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.files.keys()
+    }
+
+    /// Whether `path` is counted
+    pub fn contains(&self, path: &Path) -> bool {
+        self.files.contains_key(path)
+    }
+
+    /// IDF of the query terms that occur in the ranked documents
+    ///
+    /// `candidate_frequencies` are the document frequencies among the blocks being ranked:
+    /// terms missing there score nothing anyway. A term the corpus has not seen, in a file
+    /// changed since it was last counted, is taken to appear in one file.
+    pub fn idfs(
+        &self,
+        terms: &AHashSet<String>,
+        candidate_frequencies: &AHashMap<String, usize>,
+    ) -> AHashMap<String, f64> {
+        let frequencies: AHashMap<String, usize> = terms
+            .iter()
+            .filter(|term| candidate_frequencies.get(*term).is_some_and(|df| *df > 0))
+            .map(|term| (term.clone(), self.document_frequency(term).max(1)))
+            .collect();
+        let max_frequency = frequencies.values().copied().max().unwrap_or(0);
+        ranking::precompute_idfs(
+            terms,
+            &frequencies,
+            self.document_count().max(max_frequency),
+        )
+    }
+}
+
+thread_local! {
+    static SCOPED: RefCell<Option<SharedCorpusStats>> = const { RefCell::new(None) };
+}
+
+/// Function to get the corpus statistics searches on this thread rank with, if any
+pub fn current() -> Option<SharedCorpusStats> {
+    SCOPED.with(|scoped| scoped.borrow().clone())
+}
+
+/// Function to run `f` with searches on this thread ranking by `stats`
+///
+/// The previous statistics are restored afterwards.
+pub fn with_corpus_stats<T>(stats: SharedCorpusStats, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<SharedCorpusStats>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            SCOPED.with(|scoped| *scoped.borrow_mut() = previous);
+        }
+    }
+
+    let previous = SCOPED.with(|scoped| scoped.borrow_mut().replace(stats));
+    let _restore = Restore(previous);
+    f()
+}
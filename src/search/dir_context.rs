@@ -0,0 +1,194 @@
+// Directory context - module-level documentation for directories that results cluster in.
+//
+// With `--include-dir-context`, each directory holding at least two results contributes its
+// README, or the `//!` docs of its `mod.rs` or the docstring of its `__init__.py`, as a
+// context block after the regular results.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::fs_access;
+use crate::language::detection::{detect_language, LanguageOverrides};
+use crate::models::{ExtractionConfidence, SearchResult};
+
+/// Number of results from one directory that make it a cluster
+const MIN_CLUSTER_SIZE: usize = 2;
+
+/// Longest context block, in lines; longer READMEs are cut off
+const MAX_CONTEXT_LINES: usize = 40;
+
+/// Helper function to find a directory's README, preferring Markdown
+fn find_readme(dir: &Path) -> Option<PathBuf> {
+    let mut readmes: Vec<PathBuf> = fs_access::read_dir(dir)
+        .ok()?
+        .into_iter()
+        .filter(|path| {
+            fs_access::is_file(path)
+                && path
+                    .file_stem()
+                    .is_some_and(|stem| stem.to_string_lossy().eq_ignore_ascii_case("readme"))
+        })
+        .collect();
+    readmes.sort_by_key(|path| {
+        let markdown = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("md"));
+        (!markdown, path.clone())
+    });
+    readmes.into_iter().next()
+}
+
+/// Helper function to get the `//!` doc comment at the top of a Rust module, as a line range
+pub(crate) fn rust_module_docs(content: &str) -> Option<(usize, usize)> {
+    let mut range: Option<(usize, usize)> = None;
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim_start();
+        if line.starts_with("//!") {
+            range = Some((range.map_or(index + 1, |(start, _)| start), index + 1));
+        } else if !line.is_empty() || range.is_some() {
+            break;
+        }
+    }
+    range
+}
+
+/// Helper function to get the docstring of a Python module, as a line range
+pub(crate) fn python_module_docstring(content: &str) -> Option<(usize, usize)> {
+    let mut lines = content.lines().enumerate();
+    let (start, first) = lines.find(|(_, line)| {
+        let line = line.trim();
+        !line.is_empty() && !line.starts_with('#')
+    })?;
+    let first = first.trim_start();
+    let quote = ["\"\"\"", "'''"].into_iter().find(|quote| {
+        first
+            .trim_start_matches(['r', 'u', 'R', 'U'])
+            .starts_with(quote)
+    })?;
+    let opened = &first[first.find(quote)? + quote.len()..];
+    if opened.contains(quote) {
+        return Some((start + 1, start + 1));
+    }
+    lines
+        .find(|(_, line)| line.contains(quote))
+        .map(|(end, _)| (start + 1, end + 1))
+}
+
+/// Helper function to find a directory's documentation: its file, content and line range
+fn find_dir_docs(dir: &Path) -> Option<(PathBuf, String, (usize, usize))> {
+    if let Some(readme) = find_readme(dir) {
+        let content = fs_access::read_to_string(&readme).ok()?;
+        let line_count = content.lines().count();
+        return Some((readme, content, (1, line_count)));
+    }
+
+    let mod_rs = dir.join("mod.rs");
+    if let Ok(content) = fs_access::read_to_string(&mod_rs) {
+        if let Some(range) = rust_module_docs(&content) {
+            return Some((mod_rs, content, range));
+        }
+    }
+
+    let init_py = dir.join("__init__.py");
+    let content = fs_access::read_to_string(&init_py).ok()?;
+    let range = python_module_docstring(&content)?;
+    Some((init_py, content, range))
+}
+
+/// Function to build the context block of a directory, if it has documentation
+pub fn dir_context_block(dir: &Path, overrides: &LanguageOverrides) -> Option<SearchResult> {
+    let (path, content, range) = find_dir_docs(dir)?;
+
+    let lines: Vec<&str> = content.lines().collect();
+    if lines.is_empty() || range.1 < range.0 {
+        return None;
+    }
+    let end = range.1.min(range.0 + MAX_CONTEXT_LINES - 1);
+    let whole_file = range.0 == 1 && end == lines.len();
+
+    Some(SearchResult {
+        file: path.to_string_lossy().to_string(),
+        file_path: Some(path.clone()),
+        lines: (range.0, end),
+        node_type: "dir_context".to_string(),
+        code: lines[range.0 - 1..end].join("\n"),
+        matched_by_filename: None,
+        rank: None,
+        score: Some(0.0),
+        tfidf_score: None,
+        bm25_score: None,
+        tfidf_rank: None,
+        bm25_rank: None,
+        new_score: None,
+        hybrid2_rank: None,
+        combined_score_rank: None,
+        file_unique_terms: None,
+        file_total_matches: None,
+        file_match_rank: None,
+        block_unique_terms: None,
+        block_total_matches: None,
+        parent_file_id: None,
+        block_id: None,
+        matched_keywords: None,
+        tokenized_content: None,
+        merged_from: None,
+        normalized_score: Some(0.0),
+        usage_kind: None,
+        via_symlink: false,
+        changed_lines: None,
+        symbol_name: None,
+        language: detect_language(&path, &content, overrides).map(str::to_string),
+        extraction_confidence: Some(if whole_file {
+            ExtractionConfidence::WholeFile
+        } else {
+            ExtractionConfidence::Heuristic
+        }),
+        partial_parse: false,
+        deprecated: false,
+        embedded: None,
+        kind: None,
+        complexity: None,
+        content_hash: None,
+    })
+}
+
+/// Function to append context blocks for the directories that results cluster in
+///
+/// Directories are taken in the order of their best-ranked result. Documentation files
+/// that are already among the results are not repeated. Returns the number of blocks added.
+pub fn add_dir_context(results: &mut Vec<SearchResult>, overrides: &LanguageOverrides) -> usize {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    let mut clusters: BTreeMap<PathBuf, (usize, usize)> = BTreeMap::new();
+    for (position, result) in results.iter().enumerate() {
+        if let Some(dir) = result.path().parent() {
+            clusters.entry(dir.to_path_buf()).or_insert((position, 0)).1 += 1;
+        }
+    }
+    let mut dirs: Vec<(usize, PathBuf)> = clusters
+        .into_iter()
+        .filter(|(_, (_, count))| *count >= MIN_CLUSTER_SIZE)
+        .map(|(dir, (first, _))| (first, dir))
+        .collect();
+    dirs.sort();
+
+    let returned: HashSet<PathBuf> = results.iter().map(|result| result.path()).collect();
+    let mut added = 0;
+    for (_, dir) in dirs {
+        let Some(block) = dir_context_block(&dir, overrides) else {
+            continue;
+        };
+        if returned.contains(&block.path()) {
+            continue;
+        }
+        if debug_mode {
+            println!(
+                "DEBUG: Adding directory context {} for {:?}",
+                block.file, dir
+            );
+        }
+        results.push(block);
+        added += 1;
+    }
+    added
+}
@@ -0,0 +1,157 @@
+// Inline directives - comments in source files that curate how their blocks are ranked.
+//
+// A comment containing `probe:ignore-next-block` removes the block that follows it from
+// search results, and `probe:boost` (or `probe:boost=<factor>`) multiplies its score, 2x
+// by default. The block that follows a directive is the one whose first line of code is
+// the first line of code after the comment.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::fs_access;
+use crate::models::SearchResult;
+use crate::search::result_ranking::resort_by_score;
+
+const IGNORE_DIRECTIVE: &str = "probe:ignore-next-block";
+const BOOST_DIRECTIVE: &str = "probe:boost";
+
+/// Score factor of a `probe:boost` without an explicit factor
+pub const DEFAULT_BOOST: f64 = 2.0;
+
+/// A directive, as found in a file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Directive {
+    /// Leave the next block out of results
+    Ignore,
+    /// Multiply the score of the next block by the factor
+    Boost(f64),
+}
+
+/// Lines of a file and the directives in it
+type FileDirectives = (Vec<String>, HashMap<usize, Directive>);
+
+/// Helper function to check whether a line is a comment in one of the supported languages
+fn is_comment(line: &str) -> bool {
+    ["//", "#", "/*", "*", "--", "<!--", ";", "\"\"\"", "'''"]
+        .iter()
+        .any(|marker| line.starts_with(marker))
+}
+
+/// Helper function to parse the directive on a line, if it is a comment holding one
+fn parse_directive(line: &str) -> Option<Directive> {
+    let line = line.trim();
+    if !is_comment(line) {
+        return None;
+    }
+    if line.contains(IGNORE_DIRECTIVE) {
+        return Some(Directive::Ignore);
+    }
+    let rest = &line[line.find(BOOST_DIRECTIVE)? + BOOST_DIRECTIVE.len()..];
+    if rest.starts_with(|c: char| c.is_alphanumeric() || c == '-' || c == '_') {
+        return None;
+    }
+    let factor = rest
+        .strip_prefix('=')
+        .and_then(|value| {
+            let end = value
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(value.len());
+            value[..end].parse::<f64>().ok()
+        })
+        .filter(|factor| *factor >= 0.0)
+        .unwrap_or(DEFAULT_BOOST);
+    Some(Directive::Boost(factor))
+}
+
+/// Helper function to find the line number of the first line of code at or after `start`
+fn first_code_line(lines: &[&str], start: usize) -> Option<usize> {
+    (start..=lines.len()).find(|&number| {
+        let line = lines[number - 1].trim();
+        !line.is_empty() && !is_comment(line)
+    })
+}
+
+/// Function to find the directives in a file's content
+///
+/// Returns the directives keyed by the line number of the code they apply to.
+pub fn find_directives(content: &str) -> HashMap<usize, Directive> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut directives = HashMap::new();
+    for (index, line) in lines.iter().enumerate() {
+        let Some(directive) = parse_directive(line) else {
+            continue;
+        };
+        if let Some(target) = first_code_line(&lines, index + 2) {
+            // An ignore wins over a boost on the same block
+            let entry = directives.entry(target).or_insert(directive);
+            if directive == Directive::Ignore {
+                *entry = directive;
+            }
+        }
+    }
+    directives
+}
+
+/// Function to apply the directives in the results' files to the results
+///
+/// Ignored blocks are removed and boosted blocks rescored, after which the ranked results
+/// are sorted again. Returns the number of ignored and boosted blocks.
+pub fn apply_directives(results: &mut Vec<SearchResult>) -> (usize, usize) {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    let mut files: HashMap<PathBuf, Option<FileDirectives>> = HashMap::new();
+    let mut ignored = 0;
+    let mut boosted = 0;
+
+    results.retain_mut(|result| {
+        let parsed = files.entry(result.path()).or_insert_with_key(|path| {
+            let content = fs_access::read_to_string(path).ok()?;
+            if !content.contains("probe:") {
+                return None;
+            }
+            let directives = find_directives(&content);
+            let lines = content.lines().map(str::to_string).collect();
+            (!directives.is_empty()).then_some((lines, directives))
+        });
+        let Some((lines, directives)) = parsed else {
+            return true;
+        };
+
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        let end = result.lines.1.min(lines.len());
+        let Some(first) = first_code_line(&lines[..end], result.lines.0.max(1)) else {
+            return true;
+        };
+        match directives.get(&first) {
+            Some(Directive::Ignore) => {
+                if debug_mode {
+                    println!(
+                        "DEBUG: Ignoring {}:{}-{} (probe:ignore-next-block)",
+                        result.file, result.lines.0, result.lines.1
+                    );
+                }
+                ignored += 1;
+                false
+            }
+            Some(Directive::Boost(factor)) => {
+                result.score = result.score.map(|score| score * factor);
+                result.bm25_score = result.bm25_score.map(|score| score * factor);
+                boosted += 1;
+                true
+            }
+            None => true,
+        }
+    });
+
+    if boosted > 0 {
+        resort_by_score(results);
+    }
+
+    if debug_mode && (ignored > 0 || boosted > 0) {
+        println!(
+            "DEBUG: Directives ignored {} and boosted {} blocks",
+            ignored, boosted
+        );
+    }
+    (ignored, boosted)
+}
@@ -454,7 +454,9 @@ fn lex_identifier(chars: &mut Peekable<Chars>) -> String {
 }
 
 // Adjust paths to match your project structure
-use crate::search::tokenization::{add_special_term, tokenize as custom_tokenize};
+use crate::search::tokenization::{
+    add_special_term, is_numeric_literal, tokenize as custom_tokenize,
+};
 
 struct Parser {
     tokens: Vec<Token>,
@@ -597,6 +599,16 @@ impl Parser {
             exact,
         } = primary_expr
         {
+            // Numeric and version literals like 1.2.3 or 0x7f are always matched exactly
+            let numeric = !exact
+                && !keywords.is_empty()
+                && keywords.iter().all(|kw| is_numeric_literal(kw));
+            let (keywords, exact) = if numeric {
+                (keywords.iter().map(|kw| kw.to_lowercase()).collect(), true)
+            } else {
+                (keywords, exact)
+            };
+
             // If exact or excluded => skip further tokenization
             let final_keywords = if exact || excluded {
                 // Mark them special (no splitting)
@@ -0,0 +1,268 @@
+//! Searching inside SQL queries and HTML templates embedded in string literals.
+//!
+//! Blocks are tokenized as code, so the content of a string like
+//! `"SELECT users.id FROM users LEFT JOIN orders ..."` is split into single words and a
+//! quoted query such as `"LEFT JOIN"` or `"users.created_at"` never matches it. With
+//! `--embedded`, string literals of at least [`MIN_EMBEDDED_LEN`] bytes whose content
+//! reads as SQL or HTML are found in the syntax tree. Their content is tokenized with
+//! the compound names of its own language (`users.created_at`, `data-user-id`), and
+//! quoted query phrases are matched against it as text.
+//!
+//! After ranking, each literal that matches the query is reported as a result of its
+//! own, positioned by its lines and those of the block it sits in. The enclosing block
+//! is only kept when it also matches outside its embedded strings.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use tree_sitter::{Node, Parser as TSParser};
+
+use crate::fs_access;
+use crate::language::factory::get_language_impl;
+use crate::models::{EmbeddedLanguage, EmbeddedLocation, ResultKind, SearchResult};
+use crate::search::content_tokens::tokenize_content;
+use crate::search::query::QueryPlan;
+use crate::search::tokenization;
+
+/// Shortest string literal, quotes included, that is checked for SQL or HTML
+pub const MIN_EMBEDDED_LEN: usize = 24;
+
+/// A statement at the start of the literal, after its quotes, a prefix like `r#"` or `f"`,
+/// or a heredoc marker, so prose such as "Please select a file from the list" is left out
+static SQL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?is)^(?:<<<~?['"]?\w+['"]?|[A-Za-z]{0,2}[^A-Za-z\s]+)?\s*(?:select\b.+\bfrom|insert\s+into|update\s+\S+\s+set|delete\s+from|with\s+\w+\s+as\s*\(|create\s+(?:table|index|view)|alter\s+table|drop\s+(?:table|index|view))\b"#,
+    )
+    .unwrap()
+});
+
+static HTML_CLOSING_TAG: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"</[A-Za-z][A-Za-z0-9-]*\s*>").unwrap());
+
+/// A string literal holding SQL or HTML
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddedString {
+    pub language: EmbeddedLanguage,
+    /// First and last line of the literal, 1-based
+    pub lines: (usize, usize),
+    /// The literal as written in the file, quotes included
+    pub text: String,
+}
+
+/// Function to tell whether the content of a string literal is SQL or HTML
+pub fn classify(text: &str) -> Option<EmbeddedLanguage> {
+    if text.len() < MIN_EMBEDDED_LEN {
+        None
+    } else if SQL.is_match(text) {
+        Some(EmbeddedLanguage::Sql)
+    } else if HTML_CLOSING_TAG.is_match(text) && text.matches('<').count() >= 2 {
+        Some(EmbeddedLanguage::Html)
+    } else {
+        None
+    }
+}
+
+/// Helper function to check whether a node is a string literal
+///
+/// Grammars name them differently (`string_literal`, `raw_string_literal`, `string`,
+/// `template_string`, `encapsed_string`, `heredoc_body`), and some nest their parts in
+/// nodes named like them, so only the outermost one is taken.
+fn is_string_node(node: &Node) -> bool {
+    let kind = node.kind();
+    node.is_named() && (kind.contains("string") || kind.contains("heredoc"))
+}
+
+/// Helper function to collect the outermost string literals holding SQL or HTML
+fn collect_strings(node: Node, content: &str, strings: &mut Vec<EmbeddedString>) {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if !is_string_node(&child) {
+            collect_strings(child, content, strings);
+            continue;
+        }
+        let Some(text) = content.get(child.start_byte()..child.end_byte()) else {
+            continue;
+        };
+        if let Some(language) = classify(text) {
+            strings.push(EmbeddedString {
+                language,
+                lines: (child.start_position().row + 1, child.end_position().row + 1),
+                text: text.to_string(),
+            });
+        }
+    }
+}
+
+/// Function to find the string literals holding SQL or HTML in a file
+///
+/// Returns an empty list when the language of `extension` is not supported.
+pub fn find_embedded_strings(content: &str, extension: &str) -> Vec<EmbeddedString> {
+    let Some(language) = get_language_impl(extension) else {
+        return Vec::new();
+    };
+    let mut parser = TSParser::new();
+    if parser
+        .set_language(&language.get_tree_sitter_language())
+        .is_err()
+    {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(content, None) else {
+        return Vec::new();
+    };
+
+    let mut strings = Vec::new();
+    collect_strings(tree.root_node(), content, &mut strings);
+    strings
+}
+
+/// Helper function to lowercase text and collapse its whitespace, so phrases match
+/// across line breaks and indentation
+fn fold_text(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Helper function to check whether a query term is a phrase or compound name that
+/// tokenization would split
+fn is_phrase(term: &str) -> bool {
+    term.chars().any(|c| !c.is_alphanumeric() && c != '_')
+}
+
+/// Function to add the tokens of the embedded strings within `lines` to a block's terms
+///
+/// Each string is tokenized with the compound names of its language, and every query
+/// term that is a phrase found in the string's text is added as it stands, so the block
+/// counts as containing it.
+pub fn add_embedded_matches(
+    block_terms: &mut Vec<String>,
+    strings: &[EmbeddedString],
+    lines: (usize, usize),
+    plan: &QueryPlan,
+) {
+    let mut seen: HashSet<String> = block_terms.iter().cloned().collect();
+    for string in strings
+        .iter()
+        .filter(|string| string.lines.0 >= lines.0 && string.lines.1 <= lines.1)
+    {
+        let text = fold_text(&string.text);
+        let phrases = plan
+            .term_indices
+            .keys()
+            .filter(|term| is_phrase(term) && text.contains(&fold_text(term)))
+            .cloned();
+        for token in tokenize_content(&string.text, Some(string.language.as_str()))
+            .into_iter()
+            .chain(phrases)
+        {
+            if seen.insert(token.clone()) {
+                block_terms.push(token);
+            }
+        }
+    }
+}
+
+/// Lines of a file and the embedded strings among them
+type FileStrings = (Vec<String>, Vec<EmbeddedString>);
+
+/// Helper function to check whether text contains one of the query terms
+fn text_matches(text: &str, terms: &HashSet<String>) -> bool {
+    let folded = fold_text(text);
+    terms.iter().any(|term| folded.contains(&fold_text(term)))
+        || tokenization::tokenize(text)
+            .iter()
+            .any(|token| terms.contains(token))
+}
+
+/// Function to report the matching embedded strings of each result as results of their own
+///
+/// A string takes its enclosing block's place and scores, with its own lines and code
+/// and a node type of `embedded_sql` or `embedded_html`. The block itself is kept in
+/// front of its strings when one of its other lines matches a query term.
+pub fn apply_embedded(results: &mut Vec<SearchResult>, terms: &HashSet<String>) {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+    // Lines and embedded strings of each file, or None when it cannot be read
+    let mut files: HashMap<PathBuf, Option<FileStrings>> = HashMap::new();
+
+    let mut reported = Vec::with_capacity(results.len());
+    for result in results.drain(..) {
+        let path = result.path();
+        let file = files.entry(path.clone()).or_insert_with(|| {
+            let content = fs_access::read_to_string(&path).ok()?;
+            let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+            let strings = find_embedded_strings(&content, extension);
+            Some((content.lines().map(str::to_string).collect(), strings))
+        });
+        let Some((lines, strings)) = file.as_ref() else {
+            reported.push(result);
+            continue;
+        };
+
+        let matching: Vec<&EmbeddedString> = strings
+            .iter()
+            .filter(|string| string.lines.0 >= result.lines.0 && string.lines.1 <= result.lines.1)
+            .filter(|string| text_matches(&string.text, terms))
+            .collect();
+        if matching.is_empty() {
+            reported.push(result);
+            continue;
+        }
+
+        let outside_match = (result.lines.0..=result.lines.1)
+            .filter(|line| {
+                !matching
+                    .iter()
+                    .any(|string| (string.lines.0..=string.lines.1).contains(line))
+            })
+            .filter_map(|line| lines.get(line - 1))
+            .any(|line| text_matches(line, terms));
+
+        let embedded: Vec<SearchResult> = matching
+            .iter()
+            .map(|string| {
+                let mut embedded = result.clone();
+                embedded.lines = string.lines;
+                embedded.code = lines
+                    .get(string.lines.0 - 1..string.lines.1)
+                    .map_or_else(|| string.text.clone(), |code| code.join("\n"));
+                embedded.node_type = format!("embedded_{}", string.language.as_str());
+                embedded.matched_keywords = result.matched_keywords.as_ref().and_then(|keywords| {
+                    let text = fold_text(&string.text);
+                    let kept: Vec<String> = keywords
+                        .iter()
+                        .filter(|keyword| text.contains(&fold_text(keyword)))
+                        .cloned()
+                        .collect();
+                    (!kept.is_empty()).then_some(kept)
+                });
+                embedded.merged_from = None;
+                embedded.kind = Some(ResultKind::String);
+                embedded.embedded = Some(EmbeddedLocation {
+                    language: string.language,
+                    host_lines: result.lines,
+                    host_node_type: result.node_type.clone(),
+                });
+                embedded
+            })
+            .collect();
+
+        if debug_mode {
+            println!(
+                "DEBUG: Found {} matching embedded strings in block {}:{}-{}{}",
+                embedded.len(),
+                result.file,
+                result.lines.0,
+                result.lines.1,
+                if outside_match { "" } else { ", replacing it" }
+            );
+        }
+        if outside_match {
+            reported.push(result);
+        }
+        reported.extend(embedded);
+    }
+    *results = reported;
+}
@@ -0,0 +1,76 @@
+//! Queries that found nothing, remembered by sessions.
+//!
+//! An agent whose query finds nothing often sends the very same query again. A session
+//! remembers such a query by a key made of the search options and a fingerprint of the files
+//! searched: the path, modification time and length of each. Sending the query again over
+//! the same files is answered at once, with no results and a suggestion to rephrase, instead
+//! of searching again. Once a file is added, removed or changed, or any option differs, the
+//! key differs and the query is searched as usual.
+
+use anyhow::Result;
+
+use crate::query_history::{fnv1a, FNV_OFFSET};
+use crate::search::content_hash::files_fingerprint;
+use crate::search::{acronyms, code_terms, SearchOptions};
+
+/// What to tell a caller whose query found nothing before
+pub const REPHRASE_SUGGESTION: &str = "This query found nothing before in this session, and no file has changed since. Try rephrasing it: use fewer or broader terms, drop quotes, +required terms and filters, or search for a related identifier.";
+
+/// Helper function to describe the options that decide what a search finds
+///
+/// Output options such as limits, merging and sorting are left out: they never turn results
+/// into none.
+fn options_description(options: &SearchOptions) -> String {
+    format!(
+        "{:?}",
+        (
+            (
+                options.path,
+                options.roots,
+                options.queries,
+                options.custom_ignores,
+                options.allow_tests,
+                options.exact,
+                options.files_only,
+                options.exclude_filenames,
+            ),
+            (
+                options.tests_for,
+                options.usages,
+                options.author,
+                options.case_fold,
+                // The rules in force, which a prepared query may have set
+                code_terms::current().map(|rules| rules.language()),
+                acronyms::enabled(),
+                options.embedded,
+                options.force_language,
+            ),
+            (
+                options.visibility,
+                options.kinds,
+                options.exclude_blocks,
+                options.follow_symlinks,
+                options.max_query_terms,
+                options.code_pattern,
+                options.preproc_context,
+                options.candidate_files,
+            ),
+            (options.file_filters(), options.match_guards()),
+        )
+    )
+}
+
+/// Function to build the key a query that found nothing is remembered by
+///
+/// Combines the options with the [`files_fingerprint`] of the files the search would walk.
+/// Returns `None` when the file system can't tell when files changed, e.g. for files in
+/// memory: such searches always run.
+pub fn query_key(options: &SearchOptions) -> Result<Option<String>> {
+    let Some(fingerprint) = files_fingerprint(options)? else {
+        return Ok(None);
+    };
+    let hash = fnv1a(options_description(options).as_bytes(), FNV_OFFSET);
+    let hash = fnv1a(b"\0", hash);
+    let hash = fnv1a(fingerprint.as_bytes(), hash);
+    Ok(Some(format!("{:016x}", hash)))
+}
@@ -0,0 +1,565 @@
+//! A reusable search handle for one root directory.
+//!
+//! `perform_probe` prepares everything it needs on every call. `ProbeEngine` walks the
+//! directory once, applying the ignore rules, and answers any number of searches from that
+//! file list. It is `Send + Sync`, so one engine can serve searches from several threads at
+//! once; per-search state such as sessions stays with the caller. An engine can also search
+//! files held in memory, such as unsaved editor buffers, without touching the disk.
+//!
+//! With [`EngineOptions::corpus_stats`], the engine also counts which terms every file
+//! contains and ranks all searches by those counts, see [`corpus_stats`]. A server keeping
+//! the engine up passes file change events to [`ProbeEngine::update_files`].
+//!
+//! With [`EngineOptions::cache_trees`], the engine keeps the parse trees of the files its
+//! searches matched, so a later search matching the same unchanged file doesn't parse it
+//! again, see [`tree_cache`].
+//!
+//! [`corpus_stats`]: crate::search::corpus_stats
+//! [`tree_cache`]: crate::language::tree_cache
+
+use anyhow::Result;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio_util::sync::CancellationToken;
+
+use crate::error::{ProbeError, ProbeResult};
+use crate::fs_access::{self, FileSystem, MemoryFs};
+use crate::language::tree_cache::{with_tree_cache, SharedTreeCache, TreeCache};
+use crate::models::LimitedSearchResults;
+use crate::search::cancellation::{self, Cancelled};
+use crate::search::corpus_stats::{with_corpus_stats, CorpusStats, SharedCorpusStats};
+use crate::search::file_list_cache::{self, FileFilters, FileList};
+use crate::search::match_guards::{DEFAULT_MAX_LINE_LENGTH, DEFAULT_MAX_MATCHES_PER_FILE};
+use crate::search::oversize::DEFAULT_OVERSIZE_THRESHOLD;
+use crate::search::query::PreparedQuery;
+use crate::search::query_limits::DEFAULT_MAX_QUERY_TERMS;
+use crate::search::search_options::SearchOptions;
+use crate::search::search_runner::run_probe;
+use crate::search::session::Session;
+use crate::search::telemetry::{with_telemetry, TelemetrySink};
+
+/// Settings shared by every search an engine runs
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct EngineOptions {
+    /// Patterns to ignore in addition to .gitignore and the default ignores
+    pub custom_ignores: Vec<String>,
+    /// Whether test files and test code blocks are searched
+    pub allow_tests: bool,
+    /// Whether symbolic links below the root are followed
+    pub follow_symlinks: bool,
+    /// Depth, modification time and size limits for the files searched
+    pub filters: FileFilters,
+    /// Whether file names are left out of matching
+    pub exclude_filenames: bool,
+    /// Ranking algorithm
+    pub reranker: String,
+    /// Whether terms are matched exactly, without stemming or splitting
+    pub exact: bool,
+    /// Maximum number of results per search
+    pub max_results: Option<usize>,
+    /// Maximum total bytes of code per search
+    pub max_bytes: Option<usize>,
+    /// Maximum total tokens of code per search
+    pub max_tokens: Option<usize>,
+    /// Whether adjacent blocks from the same file are kept separate
+    pub no_merge: bool,
+    /// Maximum gap in lines between blocks that are merged
+    pub merge_threshold: Option<usize>,
+    /// Path style of result file names ("native" or "unix")
+    pub path_style: String,
+    /// Whether searches fail when a file cannot be read instead of leaving it out
+    pub strict: bool,
+    /// Most distinct terms a query may expand to
+    pub max_query_terms: usize,
+    /// Whether terms are weighed by how many files of the whole corpus contain them,
+    /// counted when the engine is prepared, rather than by the blocks each search matched
+    pub corpus_stats: bool,
+    /// Whether parse trees are kept between searches and reused for unchanged files
+    pub cache_trees: bool,
+}
+
+impl Default for EngineOptions {
+    fn default() -> Self {
+        Self {
+            custom_ignores: Vec::new(),
+            allow_tests: false,
+            follow_symlinks: false,
+            filters: FileFilters::default(),
+            exclude_filenames: false,
+            reranker: "bm25".to_string(),
+            exact: false,
+            max_results: None,
+            max_bytes: None,
+            max_tokens: None,
+            no_merge: false,
+            merge_threshold: None,
+            path_style: "native".to_string(),
+            strict: false,
+            max_query_terms: DEFAULT_MAX_QUERY_TERMS,
+            corpus_stats: false,
+            cache_trees: false,
+        }
+    }
+}
+
+/// Search handle for one root directory
+#[allow(dead_code)]
+pub struct ProbeEngine {
+    root: PathBuf,
+    options: EngineOptions,
+    file_list: Arc<FileList>,
+    telemetry: Option<Arc<dyn TelemetrySink>>,
+    /// Where searches read files from, when it isn't the disk
+    file_system: Option<Arc<dyn FileSystem>>,
+    /// Term counts of every file, with `corpus_stats`
+    corpus_stats: Option<SharedCorpusStats>,
+    /// Parse trees kept between searches, with `cache_trees`
+    tree_cache: Option<SharedTreeCache>,
+}
+
+#[allow(dead_code)]
+impl ProbeEngine {
+    /// Prepare an engine for `root` with the default options
+    pub fn new(root: impl Into<PathBuf>) -> ProbeResult<Self> {
+        Self::with_options(root, EngineOptions::default())
+    }
+
+    /// Prepare an engine for `root`
+    ///
+    /// Walks the directory once and loads the tokenizer and stemmer, so the first search
+    /// does not pay for them. With `corpus_stats`, every file is also read once to count
+    /// its terms.
+    pub fn with_options(root: impl Into<PathBuf>, options: EngineOptions) -> ProbeResult<Self> {
+        let root = root.into();
+        let file_list = file_list_cache::build_uncached_file_list(
+            &root,
+            options.allow_tests,
+            &options.custom_ignores,
+            options.follow_symlinks,
+            options.filters,
+        )
+        .map_err(ProbeError::from)?;
+
+        crate::ranking::get_stemmer();
+        crate::search::search_tokens::get_tokenizer();
+
+        let corpus_stats = options
+            .corpus_stats
+            .then(|| Arc::new(RwLock::new(CorpusStats::build(&file_list.files))));
+
+        let tree_cache = options
+            .cache_trees
+            .then(|| Arc::new(Mutex::new(TreeCache::new())));
+
+        Ok(Self {
+            root,
+            options,
+            file_list,
+            telemetry: None,
+            file_system: None,
+            corpus_stats,
+            tree_cache,
+        })
+    }
+
+    /// Prepare an engine that searches `files`, pairs of path and content, in memory
+    ///
+    /// Nothing is read from disk: blocks are extracted and ranked as for files on disk, and
+    /// results name files by the paths given here. The ignore and test patterns of `options`
+    /// apply; .gitignore files and the depth, time and size filters don't.
+    pub fn from_memory<P, C>(
+        files: impl IntoIterator<Item = (P, C)>,
+        options: EngineOptions,
+    ) -> Self
+    where
+        P: Into<PathBuf>,
+        C: Into<String>,
+    {
+        let root = PathBuf::from(".");
+        let mut file_system = MemoryFs::new();
+        let mut paths = Vec::new();
+        for (path, content) in files {
+            let path = path.into();
+            file_system = file_system.with_file(&path, content.into());
+            paths.push(path);
+        }
+        let file_list = file_list_cache::build_memory_file_list(
+            &root,
+            paths,
+            options.allow_tests,
+            &options.custom_ignores,
+        );
+
+        crate::ranking::get_stemmer();
+        crate::search::search_tokens::get_tokenizer();
+
+        let file_system: Arc<dyn FileSystem> = Arc::new(file_system);
+        let corpus_stats = options.corpus_stats.then(|| {
+            let stats = fs_access::with_file_system(Arc::clone(&file_system), || {
+                CorpusStats::build(&file_list.files)
+            });
+            Arc::new(RwLock::new(stats))
+        });
+
+        let tree_cache = options
+            .cache_trees
+            .then(|| Arc::new(Mutex::new(TreeCache::new())));
+
+        Self {
+            root,
+            options,
+            file_list,
+            telemetry: None,
+            file_system: Some(file_system),
+            corpus_stats,
+            tree_cache,
+        }
+    }
+
+    /// The directory this engine searches
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The options every search uses
+    pub fn options(&self) -> &EngineOptions {
+        &self.options
+    }
+
+    /// Number of files that searches consider
+    pub fn file_count(&self) -> usize {
+        self.file_list.files.len()
+    }
+
+    /// Send the telemetry of every search this engine runs to `sink`
+    pub fn set_telemetry(&mut self, sink: Arc<dyn TelemetrySink>) {
+        self.telemetry = Some(sink);
+    }
+
+    /// Walk the directory again, picking up added and removed files
+    ///
+    /// Engines searching files in memory have nothing to walk and keep their files. With
+    /// corpus statistics, added files are counted and removed ones taken out; files that
+    /// only changed are counted again by [`update_files`](Self::update_files).
+    pub fn refresh(&mut self) -> ProbeResult<()> {
+        if self.file_system.is_some() {
+            return Ok(());
+        }
+        self.file_list = file_list_cache::build_uncached_file_list(
+            &self.root,
+            self.options.allow_tests,
+            &self.options.custom_ignores,
+            self.options.follow_symlinks,
+            self.options.filters,
+        )
+        .map_err(ProbeError::from)?;
+        self.sync_corpus_stats();
+        Ok(())
+    }
+
+    /// Rank searches by corpus statistics shared with other engines over the same files
+    ///
+    /// A server building an engine per request keeps the statistics between them this
+    /// way. Files the statistics are missing are counted and files this engine doesn't
+    /// search are taken out, so engines sharing them should search the same files.
+    pub fn set_corpus_stats(&mut self, stats: SharedCorpusStats) {
+        self.corpus_stats = Some(stats);
+        self.sync_corpus_stats();
+    }
+
+    /// Helper function to bring the corpus statistics in line with the file list
+    fn sync_corpus_stats(&self) {
+        let Some(stats) = &self.corpus_stats else {
+            return;
+        };
+        let files: HashSet<&PathBuf> = self.file_list.files.iter().collect();
+        let (added, removed): (Vec<PathBuf>, Vec<PathBuf>) = {
+            let stats = stats.read().unwrap();
+            (
+                files
+                    .iter()
+                    .filter(|path| !stats.contains(path))
+                    .map(|path| (*path).clone())
+                    .collect(),
+                stats
+                    .paths()
+                    .filter(|path| !files.contains(path))
+                    .cloned()
+                    .collect(),
+            )
+        };
+        if added.is_empty() && removed.is_empty() {
+            return;
+        }
+
+        // Counted without holding the lock, so searches go on meanwhile
+        let counted = match &self.file_system {
+            Some(file_system) => {
+                fs_access::with_file_system(Arc::clone(file_system), || CorpusStats::build(&added))
+            }
+            None => CorpusStats::build(&added),
+        };
+        let mut stats = stats.write().unwrap();
+        for path in &removed {
+            stats.remove_file(path);
+        }
+        stats.merge(counted);
+    }
+
+    /// Count changed files again in the corpus statistics
+    ///
+    /// Meant for file change events: each path is read again, and one that no longer
+    /// exists is taken out. Paths this engine doesn't search are ignored. Searches running
+    /// meanwhile see the statistics either before or after the update. Without corpus
+    /// statistics this does nothing.
+    pub fn update_files(&self, paths: &[PathBuf]) -> ProbeResult<()> {
+        let Some(stats) = &self.corpus_stats else {
+            return Ok(());
+        };
+        let paths: Vec<&PathBuf> = {
+            let stats = stats.read().unwrap();
+            let files: HashSet<&PathBuf> = self.file_list.files.iter().collect();
+            paths
+                .iter()
+                .filter(|path| files.contains(path) || stats.contains(path))
+                .collect()
+        };
+        let read = || -> std::io::Result<Vec<(&PathBuf, Option<String>)>> {
+            paths
+                .iter()
+                .map(|path| match fs_access::read_to_string(path) {
+                    Ok(content) => Ok((*path, Some(content))),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok((*path, None)),
+                    // Files searches leave out, such as binary ones, are left out here too
+                    Err(e) if e.kind() == std::io::ErrorKind::InvalidData => Ok((*path, None)),
+                    Err(e) => Err(e),
+                })
+                .collect()
+        };
+        let contents = match &self.file_system {
+            Some(file_system) => fs_access::with_file_system(Arc::clone(file_system), read),
+            None => read(),
+        }
+        .map_err(|e| ProbeError::from(anyhow::Error::from(e)))?;
+
+        let mut stats = stats.write().unwrap();
+        for (path, content) in contents {
+            match content {
+                Some(content) => stats.update_file(path, &content),
+                None => stats.remove_file(path),
+            }
+        }
+        Ok(())
+    }
+
+    /// The corpus statistics searches are ranked by, if any
+    pub fn corpus_stats(&self) -> Option<&SharedCorpusStats> {
+        self.corpus_stats.as_ref()
+    }
+
+    /// The parse trees kept between searches, if any
+    pub fn tree_cache(&self) -> Option<&SharedTreeCache> {
+        self.tree_cache.as_ref()
+    }
+
+    /// Search for `query`
+    ///
+    /// No session is used: `PROBE_SESSION_ID` is ignored and every call sees all blocks.
+    pub fn search(&self, query: &str) -> ProbeResult<LimitedSearchResults> {
+        self.run(query, None).map_err(ProbeError::from)
+    }
+
+    /// Search for `query`, skipping and recording blocks in `session`
+    pub fn search_with_session(
+        &self,
+        query: &str,
+        session: &mut Session,
+    ) -> ProbeResult<LimitedSearchResults> {
+        self.run(query, Some(session)).map_err(ProbeError::from)
+    }
+
+    /// Search for `query` in the files under `paths` only, with `session` if given
+    ///
+    /// `paths` name files or directories relative to the root. Files elsewhere are not read,
+    /// so limits and the results only cover the files below them.
+    pub fn search_within(
+        &self,
+        query: &str,
+        paths: &[PathBuf],
+        session: Option<&mut Session>,
+    ) -> ProbeResult<LimitedSearchResults> {
+        let queries = vec![query.to_string()];
+        self.run_with(&queries, self.options.exact, None, session, Some(paths))
+            .map_err(ProbeError::from)
+    }
+
+    /// Search with a query prepared by [`prepare_query`](crate::search::query::prepare_query)
+    ///
+    /// The plan and patterns are reused as is, so one prepared query can be searched across
+    /// many engines without parsing it again. The query's own `exact`, code term and acronym
+    /// settings apply.
+    pub fn search_prepared(&self, prepared: &PreparedQuery) -> ProbeResult<LimitedSearchResults> {
+        self.run_with(
+            &prepared.queries,
+            prepared.exact,
+            Some(prepared),
+            None,
+            None,
+        )
+        .map_err(ProbeError::from)
+    }
+
+    /// Search for `query` on the blocking thread pool
+    ///
+    /// Cancelling `cancel` stops the search at the next file and resolves to a
+    /// [`ProbeError::Timeout`] right away.
+    pub async fn search_async(
+        self: &Arc<Self>,
+        query: &str,
+        cancel: CancellationToken,
+    ) -> ProbeResult<LimitedSearchResults> {
+        let engine = Arc::clone(self);
+        let query = query.to_string();
+        let token = cancel.clone();
+        let task = tokio::task::spawn_blocking(move || {
+            cancellation::with_cancellation(token, || engine.search(&query))
+        });
+
+        tokio::select! {
+            result = task => {
+                result.map_err(|e| ProbeError::Other(format!("Search task failed: {}", e)))?
+            }
+            _ = cancel.cancelled() => Err(anyhow::Error::from(Cancelled).into()),
+        }
+    }
+
+    fn run(&self, query: &str, session: Option<&mut Session>) -> Result<LimitedSearchResults> {
+        let queries = vec![query.to_string()];
+        self.run_with(&queries, self.options.exact, None, session, None)
+    }
+
+    /// Helper function to list the files and walk errors under `paths`, relative to the root
+    fn file_list_within(&self, paths: &[PathBuf]) -> FileList {
+        let dirs: Vec<PathBuf> = paths.iter().map(|path| self.root.join(path)).collect();
+        let within = |path: &Path| dirs.iter().any(|dir| path.starts_with(dir));
+        FileList {
+            files: self
+                .file_list
+                .files
+                .iter()
+                .filter(|file| within(file))
+                .cloned()
+                .collect(),
+            created_at: self.file_list.created_at,
+            errors: self
+                .file_list
+                .errors
+                .iter()
+                .filter(|error| within(&error.path))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    fn run_with(
+        &self,
+        queries: &[String],
+        exact: bool,
+        prepared: Option<&PreparedQuery>,
+        session: Option<&mut Session>,
+        within: Option<&[PathBuf]>,
+    ) -> Result<LimitedSearchResults> {
+        let file_list = match within {
+            Some(paths) => Arc::new(self.file_list_within(paths)),
+            None => Arc::clone(&self.file_list),
+        };
+        let options = SearchOptions {
+            path: &self.root,
+            queries,
+            files_only: false,
+            custom_ignores: &self.options.custom_ignores,
+            exclude_filenames: self.options.exclude_filenames,
+            reranker: &self.options.reranker,
+            frequency_search: true,
+            max_results: self.options.max_results,
+            max_bytes: self.options.max_bytes,
+            max_tokens: self.options.max_tokens,
+            allow_tests: self.options.allow_tests,
+            exact,
+            no_merge: self.options.no_merge,
+            merge_threshold: self.options.merge_threshold,
+            dry_run: false,
+            session: None,
+            report_skipped: false,
+            tests_for: None,
+            usages: false,
+            path_style: &self.options.path_style,
+            follow_symlinks: self.options.follow_symlinks,
+            strict: self.options.strict,
+            roots: &[],
+            max_depth: self.options.filters.max_depth,
+            min_depth: self.options.filters.min_depth,
+            newer_than: self.options.filters.newer_than,
+            older_than: self.options.filters.older_than,
+            min_size: self.options.filters.min_size,
+            max_size: self.options.filters.max_size,
+            author: None,
+            snippet_window: None,
+            oversize_strategy: "full",
+            oversize_threshold: DEFAULT_OVERSIZE_THRESHOLD,
+            case_fold: false,
+            code_terms: None,
+            acronyms: false,
+            preproc_context: false,
+            embedded: false,
+            force_language: &[],
+            within_session: None,
+            exclude_blocks: &[],
+            pin_blocks: &[],
+            filename_weight: 1.0,
+            include_dir_context: false,
+            deprecated_penalty: 0.0,
+            visibility: "any",
+            kinds: &[],
+            sort: "score",
+            candidate_files: None,
+            max_line_length: DEFAULT_MAX_LINE_LENGTH,
+            max_matches_per_file: DEFAULT_MAX_MATCHES_PER_FILE,
+            max_total_matches: None,
+            max_query_terms: self.options.max_query_terms,
+            code_pattern: false,
+        };
+
+        let search = || {
+            let run = || {
+                file_list_cache::with_file_list(
+                    &self.root,
+                    self.options.allow_tests,
+                    &self.options.custom_ignores,
+                    self.options.follow_symlinks,
+                    self.options.filters,
+                    file_list,
+                    || run_probe(&options, session, prepared, None),
+                )
+            };
+            match &self.file_system {
+                Some(file_system) => fs_access::with_file_system(Arc::clone(file_system), run),
+                None => run(),
+            }
+        };
+        let parsed = || match &self.tree_cache {
+            Some(trees) => with_tree_cache(Some(Arc::clone(trees)), search),
+            None => search(),
+        };
+        let ranked = || match &self.corpus_stats {
+            Some(stats) => with_corpus_stats(Arc::clone(stats), parsed),
+            None => parsed(),
+        };
+        match &self.telemetry {
+            Some(sink) => with_telemetry(Arc::clone(sink), ranked),
+            None => ranked(),
+        }
+    }
+}
@@ -1,11 +1,16 @@
+use crate::language::ci_yaml;
+use crate::models::{FileError, FileErrorKind, SkipReason};
+use crate::search::search_options::SearchRoot;
 use crate::search::tokenization;
 use anyhow::Result;
 use ignore::WalkBuilder;
 use lazy_static::lazy_static;
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
-use std::path::{Path, PathBuf};
+use std::fs::{self, Metadata};
+use std::path::{Component, Path, PathBuf};
 use std::sync::{Arc, RwLock};
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime};
 
 /// A struct to hold the cached file list for a specific directory
 #[derive(Debug, Clone)]
@@ -15,6 +20,8 @@ pub struct FileList {
     /// When this cache was created
     #[allow(dead_code)]
     pub created_at: Instant,
+    /// Directories and files the walk could not read
+    pub errors: Vec<FileError>,
 }
 
 // Global in-memory cache for file lists
@@ -23,6 +30,135 @@ lazy_static! {
         RwLock::new(HashMap::new());
 }
 
+thread_local! {
+    // File list pinned by `with_file_list` for searches on this thread, with its cache key
+    static PINNED_FILE_LIST: RefCell<Option<(String, Arc<FileList>)>> = const { RefCell::new(None) };
+}
+
+/// Limits on which files a walk lists, on top of the ignore rules
+///
+/// Depth is counted like `find` does: files directly in the search path are at depth 1.
+/// Ages are measured back from the moment the list is built.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FileFilters {
+    /// Skip files shallower than this
+    pub min_depth: Option<usize>,
+    /// Do not descend below this depth
+    pub max_depth: Option<usize>,
+    /// Only list files modified less than this long ago
+    pub newer_than: Option<Duration>,
+    /// Only list files modified more than this long ago
+    pub older_than: Option<Duration>,
+    /// Only list files of at least this many bytes
+    pub min_size: Option<u64>,
+    /// Only list files of at most this many bytes
+    pub max_size: Option<u64>,
+}
+
+impl FileFilters {
+    /// Why a file at `depth` is left out, if it is
+    ///
+    /// `metadata` is only called when a time or size filter is set. Files whose metadata
+    /// cannot be read are kept, so that reading them reports the error.
+    pub fn exclusion(
+        &self,
+        depth: usize,
+        metadata: impl FnOnce() -> Option<Metadata>,
+    ) -> Option<SkipReason> {
+        if self.min_depth.is_some_and(|min| depth < min)
+            || self.max_depth.is_some_and(|max| depth > max)
+        {
+            return Some(SkipReason::Depth);
+        }
+        let checks_time = self.newer_than.is_some() || self.older_than.is_some();
+        let checks_size = self.min_size.is_some() || self.max_size.is_some();
+        if !checks_time && !checks_size {
+            return None;
+        }
+        let metadata = metadata()?;
+
+        if checks_time {
+            if let Ok(modified) = metadata.modified() {
+                let age = SystemTime::now()
+                    .duration_since(modified)
+                    .unwrap_or_default();
+                if self.newer_than.is_some_and(|newer| age > newer)
+                    || self.older_than.is_some_and(|older| age < older)
+                {
+                    return Some(SkipReason::Modified);
+                }
+            }
+        }
+        let size = metadata.len();
+        if self.min_size.is_some_and(|min| size < min)
+            || self.max_size.is_some_and(|max| size > max)
+        {
+            return Some(SkipReason::Size);
+        }
+        None
+    }
+
+    fn cache_key(&self) -> String {
+        if *self == Self::default() {
+            return "no_filters".to_string();
+        }
+        let part = |value: Option<u128>| value.map_or("-".to_string(), |value| value.to_string());
+        format!(
+            "filters_{}_{}_{}_{}_{}_{}",
+            part(self.min_depth.map(|depth| depth as u128)),
+            part(self.max_depth.map(|depth| depth as u128)),
+            part(self.newer_than.map(|age| age.as_millis())),
+            part(self.older_than.map(|age| age.as_millis())),
+            part(self.min_size.map(u128::from)),
+            part(self.max_size.map(u128::from))
+        )
+    }
+}
+
+/// Parse an age such as `90s`, `30m`, `12h`, `7d` or `2w`; a bare number is in seconds
+pub fn parse_age(value: &str) -> std::result::Result<Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid age '{}': expected e.g. 30m, 12h, 7d", value))?;
+    let seconds = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("invalid age unit '{}': use s, m, h, d or w", unit)),
+    };
+    Ok(Duration::from_secs(number.saturating_mul(seconds)))
+}
+
+/// Parse a size such as `500`, `10k`, `2M` or `1G` (powers of 1024, an optional `B` suffix)
+pub fn parse_size(value: &str) -> std::result::Result<u64, String> {
+    let value = value.trim();
+    let unit_part = value.trim_start_matches(|c: char| c.is_ascii_digit());
+    let number = &value[..value.len() - unit_part.len()];
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid size '{}': expected e.g. 500, 10k, 2M", value))?;
+    let multiplier = match unit_part.to_ascii_lowercase().trim_end_matches('b') {
+        "" => 1,
+        "k" => 1024,
+        "m" => 1024 * 1024,
+        "g" => 1024 * 1024 * 1024,
+        _ => return Err(format!("invalid size unit '{}': use k, M or G", unit_part)),
+    };
+    Ok(number.saturating_mul(multiplier))
+}
+
+/// Whether ignore patterns match regardless of case
+///
+/// Windows file systems are case-insensitive, so `--ignore Build` must also skip `build/`.
+pub(crate) const IGNORE_CASE_INSENSITIVE: bool = cfg!(windows);
+
 /// Helper function to format duration in a human-readable way
 fn format_duration(duration: std::time::Duration) -> String {
     if duration.as_millis() < 1000 {
@@ -33,7 +169,13 @@ fn format_duration(duration: std::time::Duration) -> String {
 }
 
 /// Generate a cache key for a specific directory and options
-fn generate_cache_key(path: &Path, allow_tests: bool, custom_ignores: &[String]) -> String {
+fn generate_cache_key(
+    path: &Path,
+    allow_tests: bool,
+    custom_ignores: &[String],
+    follow_symlinks: bool,
+    filters: FileFilters,
+) -> String {
     // Create a unique identifier for this cache based on the path and options
     let path_str = path.to_string_lossy();
     let allow_tests_str = if allow_tests {
@@ -56,15 +198,34 @@ fn generate_cache_key(path: &Path, allow_tests: bool, custom_ignores: &[String])
         format!("ignores_{:x}", hash)
     };
 
-    format!("{}_{}_{}", path_str, allow_tests_str, ignores_hash)
+    let symlinks_str = if follow_symlinks {
+        "follow_links"
+    } else {
+        "no_links"
+    };
+
+    format!(
+        "{}_{}_{}_{}_{}",
+        path_str,
+        allow_tests_str,
+        ignores_hash,
+        symlinks_str,
+        filters.cache_key()
+    )
 }
 
 /// Get a list of files in a directory, respecting ignore patterns and test file exclusions.
 /// This function will use a cached list if available, or build and cache a new list if not.
+///
+/// Symbolic links below `path` are skipped unless `follow_symlinks` is set. When they are
+/// followed, links that lead back into a directory being walked are reported as cycles and
+/// skipped, and a file reachable both directly and through a link is listed once.
 pub fn get_file_list(
     path: &Path,
     allow_tests: bool,
     custom_ignores: &[String],
+    follow_symlinks: bool,
+    filters: FileFilters,
 ) -> Result<Arc<FileList>> {
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
     let start_time = Instant::now();
@@ -73,10 +234,30 @@ pub fn get_file_list(
         println!("DEBUG: Getting file list for path: {:?}", path);
         println!("DEBUG: allow_tests: {}", allow_tests);
         println!("DEBUG: custom_ignores: {:?}", custom_ignores);
+        println!("DEBUG: follow_symlinks: {}", follow_symlinks);
+        println!("DEBUG: filters: {:?}", filters);
     }
 
     // Create a cache key for this request
-    let cache_key = generate_cache_key(path, allow_tests, custom_ignores);
+    let cache_key = generate_cache_key(path, allow_tests, custom_ignores, follow_symlinks, filters);
+
+    // A list pinned for this thread takes precedence over the shared cache
+    let pinned = PINNED_FILE_LIST.with(|pinned| {
+        pinned
+            .borrow()
+            .as_ref()
+            .filter(|(key, _)| *key == cache_key)
+            .map(|(_, file_list)| Arc::clone(file_list))
+    });
+    if let Some(file_list) = pinned {
+        if debug_mode {
+            println!(
+                "DEBUG: Using pinned file list with {} files",
+                file_list.files.len()
+            );
+        }
+        return Ok(file_list);
+    }
 
     // Check if we have this file list in the cache
     {
@@ -99,12 +280,12 @@ pub fn get_file_list(
         println!("DEBUG: File list not found in cache, building new list");
     }
 
-    let file_list = build_file_list(path, allow_tests, custom_ignores)?;
+    let file_list = build_file_list(path, allow_tests, custom_ignores, follow_symlinks, filters)?;
     let file_count = file_list.files.len();
 
-    // Cache the file list
+    // Cache the file list, unless it depends on the current time and would go stale
     let file_list = Arc::new(file_list);
-    {
+    if filters.newer_than.is_none() && filters.older_than.is_none() {
         let mut cache = FILE_LIST_CACHE.write().unwrap();
         cache.insert(cache_key, Arc::clone(&file_list));
     }
@@ -121,36 +302,118 @@ pub fn get_file_list(
     Ok(file_list)
 }
 
-/// Build a list of files in a directory, respecting ignore patterns and test file exclusions.
-fn build_file_list(path: &Path, allow_tests: bool, custom_ignores: &[String]) -> Result<FileList> {
-    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
-    let start_time = Instant::now();
+/// Get the files under several roots, each walked with `custom_ignores` and its own ignores
+///
+/// Every root's list is cached on its own. A file under more than one root is listed once,
+/// however the roots spell its path, e.g. `src` and `./src/search`.
+pub fn get_roots_file_list(
+    roots: &[SearchRoot],
+    allow_tests: bool,
+    custom_ignores: &[String],
+    follow_symlinks: bool,
+    filters: FileFilters,
+) -> Result<Arc<FileList>> {
+    let mut seen = HashSet::new();
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    for root in roots {
+        let ignores: Vec<String> = custom_ignores
+            .iter()
+            .chain(root.ignores.iter())
+            .cloned()
+            .collect();
+        let file_list = get_file_list(&root.path, allow_tests, &ignores, follow_symlinks, filters)?;
+        files.extend(
+            file_list
+                .files
+                .iter()
+                .filter(|file| {
+                    let normalized: PathBuf = file
+                        .components()
+                        .filter(|c| !matches!(c, Component::CurDir))
+                        .collect();
+                    seen.insert(normalized)
+                })
+                .cloned(),
+        );
+        errors.extend(file_list.errors.iter().cloned());
+    }
+    Ok(Arc::new(FileList {
+        files,
+        created_at: Instant::now(),
+        errors,
+    }))
+}
 
-    if debug_mode {
-        println!("DEBUG: Building file list for path: {:?}", path);
+/// The deepest directory containing every root, `.` for relative roots with nothing in common
+pub fn common_root(roots: &[SearchRoot]) -> PathBuf {
+    let mut common: Option<PathBuf> = None;
+    for root in roots {
+        common = Some(match common {
+            None => root.path.clone(),
+            Some(common) => common
+                .components()
+                .zip(root.path.components())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect(),
+        });
     }
+    match common {
+        Some(common) if !common.as_os_str().is_empty() => common,
+        _ => PathBuf::from("."),
+    }
+}
 
-    // Create a WalkBuilder that respects .gitignore files and common ignore patterns
-    let builder_start = Instant::now();
-    let mut builder = WalkBuilder::new(path);
+/// Build a file list without reading or filling the shared cache
+pub(crate) fn build_uncached_file_list(
+    path: &Path,
+    allow_tests: bool,
+    custom_ignores: &[String],
+    follow_symlinks: bool,
+    filters: FileFilters,
+) -> Result<Arc<FileList>> {
+    build_file_list(path, allow_tests, custom_ignores, follow_symlinks, filters).map(Arc::new)
+}
 
-    // Configure the builder
-    builder.git_ignore(true);
-    builder.git_global(true);
-    builder.git_exclude(true);
+/// Run `f` with `file_list` answering `get_file_list` calls on this thread
+///
+/// The list is only used for requests with the same path and options it was built with;
+/// anything else still goes through the shared cache. The previous pin is restored afterwards.
+pub(crate) fn with_file_list<T>(
+    path: &Path,
+    allow_tests: bool,
+    custom_ignores: &[String],
+    follow_symlinks: bool,
+    filters: FileFilters,
+    file_list: Arc<FileList>,
+    f: impl FnOnce() -> T,
+) -> T {
+    struct Restore(Option<(String, Arc<FileList>)>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            PINNED_FILE_LIST.with(|pinned| *pinned.borrow_mut() = previous);
+        }
+    }
 
-    // Enable parallel walking for large directories
-    // Use a reasonable fixed number of threads (4) instead of num_cpus::get()
-    builder.threads(4);
+    let cache_key = generate_cache_key(path, allow_tests, custom_ignores, follow_symlinks, filters);
+    let previous =
+        PINNED_FILE_LIST.with(|pinned| pinned.borrow_mut().replace((cache_key, file_list)));
+    let _restore = Restore(previous);
+    f()
+}
 
-    // Add common directories to ignore
-    let mut common_ignores: Vec<String> = vec![
+/// Common directories and file patterns that are always ignored
+pub(crate) fn default_ignore_patterns() -> Vec<String> {
+    vec![
         "node_modules",
         "vendor",
         "target",
         "dist",
         "build",
         ".git",
+        ".probe",
         ".svn",
         ".hg",
         ".idea",
@@ -193,47 +456,62 @@ fn build_file_list(path: &Path, allow_tests: bool, custom_ignores: &[String]) ->
     ]
     .into_iter()
     .map(String::from)
-    .collect();
+    .collect()
+}
+
+/// Test file patterns that are ignored unless tests are allowed
+pub(crate) fn test_ignore_patterns() -> Vec<String> {
+    vec![
+        "*_test.rs",
+        "*_tests.rs",
+        "test_*.rs",
+        "tests.rs",
+        "*.spec.js",
+        "*.test.js",
+        "*.spec.ts",
+        "*.test.ts",
+        "*.spec.jsx",
+        "*.test.jsx",
+        "*.spec.tsx",
+        "*.test.tsx",
+        "test_*.py",
+        "*_test.go",
+        "test_*.c",
+        "*_test.c",
+        "*_test.cpp",
+        "*_test.cc",
+        "*_test.cxx",
+        "*Test.java",
+        "*_test.rb",
+        "test_*.rb",
+        "*_spec.rb",
+        "*Test.php",
+        "test_*.php",
+        "**/tests/**",
+        "**/test/**",
+        "**/__tests__/**",
+        "**/__test__/**",
+        "**/spec/**",
+        "**/specs/**",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+/// Overrides that leave out the common ignores, test files unless `allow_tests`, and
+/// `custom_ignores`
+fn ignore_overrides(
+    path: &Path,
+    allow_tests: bool,
+    custom_ignores: &[String],
+) -> Option<ignore::overrides::Override> {
+    // Add common directories to ignore
+    let mut common_ignores = default_ignore_patterns();
 
     // Add test file patterns if allow_tests is false
     if !allow_tests {
-        let test_patterns: Vec<String> = vec![
-            "*_test.rs",
-            "*_tests.rs",
-            "test_*.rs",
-            "tests.rs",
-            "*.spec.js",
-            "*.test.js",
-            "*.spec.ts",
-            "*.test.ts",
-            "*.spec.jsx",
-            "*.test.jsx",
-            "*.spec.tsx",
-            "*.test.tsx",
-            "test_*.py",
-            "*_test.go",
-            "test_*.c",
-            "*_test.c",
-            "*_test.cpp",
-            "*_test.cc",
-            "*_test.cxx",
-            "*Test.java",
-            "*_test.rb",
-            "test_*.rb",
-            "*_spec.rb",
-            "*Test.php",
-            "test_*.php",
-            "**/tests/**",
-            "**/test/**",
-            "**/__tests__/**",
-            "**/__test__/**",
-            "**/spec/**",
-            "**/specs/**",
-        ]
-        .into_iter()
-        .map(String::from)
-        .collect();
-        common_ignores.extend(test_patterns);
+        common_ignores.extend(test_ignore_patterns());
     }
 
     // Add custom ignore patterns to the common ignores
@@ -243,6 +521,9 @@ fn build_file_list(path: &Path, allow_tests: bool, custom_ignores: &[String]) ->
 
     // Create a single override builder for all ignore patterns
     let mut override_builder = ignore::overrides::OverrideBuilder::new(path);
+    if let Err(err) = override_builder.case_insensitive(IGNORE_CASE_INSENSITIVE) {
+        eprintln!("Error configuring ignore case sensitivity: {}", err);
+    }
 
     // Add all ignore patterns to the override builder
     for pattern in &common_ignores {
@@ -251,15 +532,81 @@ fn build_file_list(path: &Path, allow_tests: bool, custom_ignores: &[String]) ->
         }
     }
 
-    // Build and apply the overrides
     match override_builder.build() {
-        Ok(overrides) => {
-            builder.overrides(overrides);
-        }
+        Ok(overrides) => Some(overrides),
         Err(err) => {
             eprintln!("Error building ignore overrides: {}", err);
+            None
         }
     }
+}
+
+/// Build a file list from paths that are not on disk, such as editor buffers
+///
+/// The common, test and custom ignore patterns apply to each file and the directories
+/// above it, as they would during a walk of `root`. .gitignore files and the depth,
+/// time and size filters don't apply.
+pub(crate) fn build_memory_file_list(
+    root: &Path,
+    paths: Vec<PathBuf>,
+    allow_tests: bool,
+    custom_ignores: &[String],
+) -> Arc<FileList> {
+    let overrides = ignore_overrides(root, allow_tests, custom_ignores);
+    let mut files: Vec<PathBuf> = paths
+        .into_iter()
+        .filter(|file| match &overrides {
+            Some(overrides) => !file
+                .ancestors()
+                .filter(|ancestor| !ancestor.as_os_str().is_empty())
+                .any(|ancestor| overrides.matched(ancestor, ancestor != file).is_ignore()),
+            None => true,
+        })
+        .collect();
+    files.sort();
+
+    Arc::new(FileList {
+        files,
+        created_at: Instant::now(),
+        errors: Vec::new(),
+    })
+}
+
+/// Build a list of files in a directory, respecting ignore patterns and test file exclusions.
+fn build_file_list(
+    path: &Path,
+    allow_tests: bool,
+    custom_ignores: &[String],
+    follow_symlinks: bool,
+    filters: FileFilters,
+) -> Result<FileList> {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+    let start_time = Instant::now();
+
+    if debug_mode {
+        println!("DEBUG: Building file list for path: {:?}", path);
+    }
+
+    // Create a WalkBuilder that respects .gitignore files and common ignore patterns
+    let builder_start = Instant::now();
+    let mut builder = WalkBuilder::new(path);
+
+    // Configure the builder
+    builder.git_ignore(true);
+    builder.git_global(true);
+    builder.git_exclude(true);
+    builder.ignore_case_insensitive(IGNORE_CASE_INSENSITIVE);
+    builder.follow_links(follow_symlinks);
+    builder.max_depth(filters.max_depth);
+
+    // Enable parallel walking for large directories
+    // Use a reasonable fixed number of threads (4) instead of num_cpus::get(),
+    // fewer when the rayon pool is capped with --threads
+    builder.threads(rayon::current_num_threads().min(4));
+
+    if let Some(overrides) = ignore_overrides(path, allow_tests, custom_ignores) {
+        builder.overrides(overrides);
+    }
 
     let builder_duration = builder_start.elapsed();
 
@@ -273,14 +620,24 @@ fn build_file_list(path: &Path, allow_tests: bool, custom_ignores: &[String]) ->
     // Collect files
     let walk_start = Instant::now();
     let mut files = Vec::new();
+    let mut errors = Vec::new();
     let mut total_files = 0;
 
     for result in builder.build() {
         total_files += 1;
         let entry = match result {
             Ok(entry) => entry,
+            Err(err) if is_symlink_loop(&err) => {
+                if debug_mode {
+                    println!("DEBUG: Skipping symlink cycle: {}", err);
+                }
+                continue;
+            }
             Err(err) => {
-                eprintln!("Error walking directory: {}", err);
+                if debug_mode {
+                    println!("DEBUG: Error walking directory: {}", err);
+                }
+                errors.push(walk_error(path, &err));
                 continue;
             }
         };
@@ -290,9 +647,50 @@ fn build_file_list(path: &Path, allow_tests: bool, custom_ignores: &[String]) ->
             continue;
         }
 
+        // Directories above --min-depth are walked through, their files are not listed
+        if filters
+            .exclusion(entry.depth(), || entry.metadata().ok())
+            .is_some()
+        {
+            continue;
+        }
+
         files.push(entry.path().to_path_buf());
     }
 
+    // CI pipeline definitions are searched even though YAML and hidden files are not
+    let mut custom_override_builder = ignore::overrides::OverrideBuilder::new(path);
+    for pattern in custom_ignores {
+        let _ = custom_override_builder.add(&format!("!**/{}", pattern));
+    }
+    let custom_overrides = custom_override_builder
+        .build()
+        .unwrap_or_else(|_| ignore::overrides::Override::empty());
+    for file in ci_yaml::pipeline_files(path) {
+        let depth = file
+            .strip_prefix(path)
+            .map_or(0, |rel| rel.components().count());
+        if files.contains(&file)
+            || custom_overrides.matched(&file, false).is_ignore()
+            || filters
+                .exclusion(depth, || fs::metadata(&file).ok())
+                .is_some()
+        {
+            continue;
+        }
+        if debug_mode {
+            println!("DEBUG: Including CI pipeline definition: {:?}", file);
+        }
+        files.push(file);
+    }
+
+    if follow_symlinks {
+        files = dedup_linked_files(path, files);
+    }
+
+    // Directory entries come in file system order, which differs between platforms
+    files.sort();
+
     let walk_duration = walk_start.elapsed();
 
     if debug_mode {
@@ -316,11 +714,93 @@ fn build_file_list(path: &Path, allow_tests: bool, custom_ignores: &[String]) ->
     Ok(FileList {
         files,
         created_at: Instant::now(),
+        errors,
     })
 }
 
+/// Describe a directory walk error as a file error, attributing it to the deepest path it names
+fn walk_error(root: &Path, err: &ignore::Error) -> FileError {
+    fn error_path(err: &ignore::Error) -> Option<&Path> {
+        match err {
+            ignore::Error::WithPath { path, err } => error_path(err).or(Some(path.as_path())),
+            ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+                error_path(err)
+            }
+            ignore::Error::Partial(errs) => errs.iter().find_map(error_path),
+            _ => None,
+        }
+    }
+
+    let path = error_path(err).unwrap_or(root).to_path_buf();
+    match err.io_error() {
+        Some(io_error) => FileError {
+            path,
+            kind: FileErrorKind::from_io(io_error),
+            message: io_error.to_string(),
+        },
+        None => FileError {
+            path,
+            kind: FileErrorKind::Other,
+            message: err.to_string(),
+        },
+    }
+}
+
+/// Check whether a walk error is a symbolic link pointing back at one of its ancestors
+fn is_symlink_loop(err: &ignore::Error) -> bool {
+    match err {
+        ignore::Error::Loop { .. } => true,
+        ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => is_symlink_loop(err),
+        ignore::Error::Partial(errs) => errs.iter().any(is_symlink_loop),
+        _ => false,
+    }
+}
+
+/// Drop files that are reachable through a symlink when the same file is already listed,
+/// preferring the path that does not go through a link
+fn dedup_linked_files(root: &Path, files: Vec<PathBuf>) -> Vec<PathBuf> {
+    let (linked, direct): (Vec<PathBuf>, Vec<PathBuf>) = files
+        .into_iter()
+        .partition(|file| reached_via_symlink(root, file));
+    if linked.is_empty() {
+        return direct;
+    }
+
+    let mut seen: HashSet<PathBuf> = direct
+        .iter()
+        .filter_map(|file| file.canonicalize().ok())
+        .collect();
+    let mut files = direct;
+    for file in linked {
+        let is_new = match file.canonicalize() {
+            Ok(target) => seen.insert(target),
+            Err(_) => true,
+        };
+        if is_new {
+            files.push(file);
+        }
+    }
+    files
+}
+
+/// Check whether `file` is reached through a symbolic link somewhere below `root`
+///
+/// `root` itself is not checked: a root given as a link is an explicit choice of the caller.
+pub fn reached_via_symlink(root: &Path, file: &Path) -> bool {
+    file.ancestors()
+        .take_while(|ancestor| *ancestor != root && !ancestor.as_os_str().is_empty())
+        .any(|ancestor| {
+            ancestor
+                .symlink_metadata()
+                .is_ok_and(|meta| meta.file_type().is_symlink())
+        })
+}
+
 /// Find files whose names match query words
 /// Returns a map of file paths to the term indices that matched the filename
+#[allow(clippy::too_many_arguments)]
 pub fn find_matching_filenames(
     path: &Path,
     queries: &[String],
@@ -328,6 +808,8 @@ pub fn find_matching_filenames(
     custom_ignores: &[String],
     allow_tests: bool,
     term_indices: &HashMap<String, usize>,
+    follow_symlinks: bool,
+    filters: FileFilters,
 ) -> Result<HashMap<PathBuf, HashSet<usize>>> {
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
     let start_time = Instant::now();
@@ -343,7 +825,7 @@ pub fn find_matching_filenames(
     }
 
     // Get the cached file list
-    let file_list = get_file_list(path, allow_tests, custom_ignores)?;
+    let file_list = get_file_list(path, allow_tests, custom_ignores, follow_symlinks, filters)?;
 
     if debug_mode {
         println!(
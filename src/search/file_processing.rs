@@ -1,12 +1,21 @@
 use anyhow::{Context, Result};
 use std::collections::{HashMap, HashSet};
-use std::fs;
 use std::path::Path;
-use tree_sitter;
 
+use crate::fs_access;
+use crate::language::deprecation::is_deprecated;
+use crate::language::detection::{self, LanguageOverrides};
+use crate::language::graphql;
+use crate::language::text_blocks::{self, TextBlock};
 use crate::language::{is_test_file, parse_file_for_code_blocks};
-use crate::models::SearchResult;
+use crate::models::{ExtractionConfidence, SearchResult};
 use crate::ranking;
+use crate::search::acronyms;
+use crate::search::complexity;
+use crate::search::content_hash;
+use crate::search::content_tokens;
+use crate::search::embedded::{self, EmbeddedString};
+use crate::search::normalization::normalize;
 use crate::search::tokenization;
 
 /// Parameters for file processing
@@ -22,6 +31,12 @@ pub struct FileProcessingParams<'a> {
     pub queries_terms: &'a [Vec<(String, String)>],
     pub preprocessed_queries: Option<&'a [Vec<String>]>,
     pub query_plan: &'a crate::search::query::QueryPlan,
+    /// Fold compatibility variants and case before tokenizing blocks (`--case-fold`)
+    pub case_fold: bool,
+    /// Extension overrides from `--force-language`
+    pub language_overrides: &'a LanguageOverrides,
+    /// Tokenize SQL and HTML string literals in their own language (`--embedded`)
+    pub embedded: bool,
 
     #[allow(dead_code)]
     pub no_merge: bool,
@@ -245,7 +260,12 @@ fn determine_fallback_node_type(line: &str, extension: Option<&str>) -> String {
     if trimmed.starts_with("//")
         || trimmed.starts_with("/*")
         || trimmed.starts_with("*")
-        || (trimmed.starts_with("#") && extension.is_some_and(|ext| ext == "py" || ext == "rb"))
+        || (trimmed.starts_with("#")
+            && extension.is_some_and(|ext| {
+                ext == "py" || ext == "rb" || ext == "nix" || graphql::is_graphql_extension(ext)
+            }))
+        || (trimmed.starts_with("--") && matches!(extension, Some("lua" | "hs")))
+        || (trimmed.starts_with("(*") && matches!(extension, Some("ml" | "mli")))
         || trimmed.starts_with("'''")
         || trimmed.starts_with("\"\"\"")
     {
@@ -306,19 +326,22 @@ fn determine_fallback_node_type(line: &str, extension: Option<&str>) -> String {
 }
 /// Main function for processing a file with matched lines
 pub fn process_file_with_results(params: &FileProcessingParams) -> Result<Vec<SearchResult>> {
-    let content = fs::read_to_string(params.path)
+    let content = fs_access::read_to_string(params.path)
         .context(format!("Failed to read file: {:?}", params.path))?;
+    let content_hash = content_hash::hash_content(&content);
 
-    let extension = params
-        .path
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .unwrap_or("");
+    let extension = detection::parse_extension(params.path, params.language_overrides);
+    let language = detection::detect_language(params.path, &content, params.language_overrides);
 
     let lines: Vec<&str> = content.lines().collect();
     let mut results = Vec::new();
     let mut covered_lines = HashSet::new();
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+    let embedded_strings: Vec<EmbeddedString> = if params.embedded {
+        embedded::find_embedded_strings(&content, extension)
+    } else {
+        Vec::new()
+    };
 
     // Get the filename for tokenization - do this once for the entire file
     let filename = params
@@ -399,7 +422,18 @@ pub fn process_file_with_results(params: &FileProcessingParams) -> Result<Vec<Se
             };
 
             // Early tokenization with filename prepended
-            let block_terms = ranking::preprocess_text_with_filename(&full_code, &filename);
+            let mut block_terms = content_tokens::tokenize_content(
+                &normalize(&full_code, params.case_fold),
+                language,
+            );
+            block_terms.extend(ranking::tokenize(&filename));
+            acronyms::add_acronym_matches(&mut block_terms, &full_code, params.query_plan);
+            embedded::add_embedded_matches(
+                &mut block_terms,
+                &embedded_strings,
+                (final_start_line, final_end_line),
+                params.query_plan,
+            );
 
             // Early filtering using tokenized content
             let should_include = {
@@ -494,8 +528,10 @@ pub fn process_file_with_results(params: &FileProcessingParams) -> Result<Vec<Se
                 matched_keywords.sort();
                 matched_keywords.dedup();
 
+                let complexity = complexity::block_complexity(&full_code);
                 results.push(SearchResult {
                     file: params.path.to_string_lossy().to_string(),
+                    file_path: Some(params.path.to_path_buf()),
                     lines: (final_start_line, final_end_line),
                     node_type: if is_nested_struct {
                         block
@@ -529,13 +565,34 @@ pub fn process_file_with_results(params: &FileProcessingParams) -> Result<Vec<Se
                         Some(matched_keywords)
                     },
                     tokenized_content: Some(block_terms),
+                    merged_from: None,
+                    normalized_score: None,
+                    usage_kind: None,
+                    via_symlink: false,
+                    changed_lines: None,
+                    symbol_name: None,
+                    language: language.map(str::to_string),
+                    extraction_confidence: Some(ExtractionConfidence::Ast),
+                    partial_parse: block.partial_parse,
+                    deprecated: is_deprecated(&lines, final_start_line, final_end_line),
+                    embedded: None,
+                    kind: None,
+                    complexity: Some(complexity),
+                    content_hash: Some(content_hash.clone()),
                 });
             }
         }
     }
 
-    // Check for any line numbers that weren't covered
-    for &line_num in params.line_numbers {
+    // File types without a tree-sitter grammar may still be split into blocks by a scanner
+    let text_blocks: Vec<TextBlock> =
+        text_blocks::find_text_blocks(params.path, extension, &content);
+
+    // Check for any line numbers that weren't covered, in order since each fallback block
+    // covers the lines after it
+    let mut line_numbers: Vec<usize> = params.line_numbers.iter().copied().collect();
+    line_numbers.sort_unstable();
+    for line_num in line_numbers {
         if !covered_lines.contains(&line_num) {
             if debug_mode {
                 println!(
@@ -565,37 +622,41 @@ pub fn process_file_with_results(params: &FileProcessingParams) -> Result<Vec<Se
                 {
                     let line_content = lines[line_num - 1];
 
-                    // Create a simple parser to check this line
-                    let mut parser = tree_sitter::Parser::new();
-                    if parser
-                        .set_language(&language_impl.get_tree_sitter_language())
-                        .is_ok()
-                    {
-                        // Try to parse just this line to get a node
-                        if let Some(tree) = parser.parse(line_content, None) {
-                            let node = tree.root_node();
-
-                            // Use the language-specific test detection
-                            if language_impl.is_test_node(&node, line_content.as_bytes()) {
-                                if debug_mode {
-                                    println!(
-                                        "DEBUG: Skipping fallback context for test code: '{}'",
-                                        line_content.trim()
-                                    );
-                                }
-                                continue;
+                    // Try to parse just this line to get a node, with this thread's parser
+                    if let Ok(tree) = crate::language::parser::parse_with_thread_parser(
+                        line_content,
+                        &language_impl.get_tree_sitter_language(),
+                    ) {
+                        let node = tree.root_node();
+
+                        // Use the language-specific test detection
+                        if language_impl.is_test_node(&node, line_content.as_bytes()) {
+                            if debug_mode {
+                                println!(
+                                    "DEBUG: Skipping fallback context for test code: '{}'",
+                                    line_content.trim()
+                                );
                             }
+                            continue;
                         }
                     }
                 }
             }
 
-            // Fallback: Get context around the line (20 lines before and after)
-            let context_start = line_num.saturating_sub(10);
-            let context_end = std::cmp::min(line_num + 10, lines.len());
+            let text_block = text_blocks::block_at(&text_blocks, line_num);
+
+            // Use the enclosing text block, or fall back to the context around the line
+            // (20 lines before and after)
+            let (context_start, context_end) = match text_block {
+                Some(text_block) => (text_block.start_line, text_block.end_line),
+                None => (
+                    line_num.saturating_sub(10),
+                    std::cmp::min(line_num + 10, lines.len()),
+                ),
+            };
 
             // Skip if we don't have enough context
-            if context_start >= context_end {
+            if context_start > context_end {
                 continue;
             }
 
@@ -607,7 +668,10 @@ pub fn process_file_with_results(params: &FileProcessingParams) -> Result<Vec<Se
             };
 
             // Determine a better node type for the fallback context by analyzing the content
-            let node_type = determine_fallback_node_type(lines[line_num - 1], Some(extension));
+            let node_type = match text_block {
+                Some(text_block) => text_block.kind.to_string(),
+                None => determine_fallback_node_type(lines[line_num - 1], Some(extension)),
+            };
 
             if debug_mode {
                 println!(
@@ -617,7 +681,15 @@ pub fn process_file_with_results(params: &FileProcessingParams) -> Result<Vec<Se
             }
 
             // Early tokenization for fallback context
-            let context_terms = ranking::preprocess_text_with_filename(&context_code, &filename);
+            let mut context_terms = content_tokens::tokenize_content(&context_code, language);
+            context_terms.extend(ranking::tokenize(&filename));
+            acronyms::add_acronym_matches(&mut context_terms, &context_code, params.query_plan);
+            embedded::add_embedded_matches(
+                &mut context_terms,
+                &embedded_strings,
+                (context_start, context_end),
+                params.query_plan,
+            );
 
             // Early filtering for fallback context
             let should_include = {
@@ -703,8 +775,17 @@ pub fn process_file_with_results(params: &FileProcessingParams) -> Result<Vec<Se
                 matched_keywords.sort();
                 matched_keywords.dedup();
 
+                // The context window of a short file can end up covering all of it
+                let extraction_confidence = if context_start <= 1 && context_end >= lines.len() {
+                    ExtractionConfidence::WholeFile
+                } else {
+                    ExtractionConfidence::Heuristic
+                };
+
+                let complexity = complexity::block_complexity(&context_code);
                 results.push(SearchResult {
                     file: params.path.to_string_lossy().to_string(),
+                    file_path: Some(params.path.to_path_buf()),
                     lines: (context_start, context_end),
                     node_type,
                     code: context_code,
@@ -731,6 +812,20 @@ pub fn process_file_with_results(params: &FileProcessingParams) -> Result<Vec<Se
                         Some(matched_keywords)
                     },
                     tokenized_content: Some(context_terms),
+                    merged_from: None,
+                    normalized_score: None,
+                    usage_kind: None,
+                    via_symlink: false,
+                    changed_lines: None,
+                    symbol_name: text_block.and_then(|text_block| text_block.name.clone()),
+                    language: language.map(str::to_string),
+                    extraction_confidence: Some(extraction_confidence),
+                    partial_parse: false,
+                    deprecated: is_deprecated(&lines, context_start, context_end),
+                    embedded: None,
+                    kind: None,
+                    complexity: Some(complexity),
+                    content_hash: Some(content_hash.clone()),
                 });
             }
         }
@@ -92,6 +92,8 @@ mod tests {
             preprocessed_queries: None,
             query_plan: &query_plan,
             no_merge: false,
+            case_fold: false,
+            language_overrides: &Default::default(),
         };
 
         let results = process_file_with_results(&params)
@@ -149,6 +151,8 @@ function test2() {
             preprocessed_queries: None,
             query_plan: &query_plan,
             no_merge: false,
+            case_fold: false,
+            language_overrides: &Default::default(),
         };
 
         let results = process_file_with_results(&params)
@@ -196,6 +200,8 @@ function test2() {
             preprocessed_queries: None,
             query_plan: &query_plan,
             no_merge: false,
+            case_fold: false,
+            language_overrides: &Default::default(),
         };
 
         let results = process_file_with_results(&params)
@@ -281,6 +287,8 @@ function test3() {
             preprocessed_queries: None, // No preprocessed queries
             query_plan: &query_plan,
             no_merge: false,
+            case_fold: false,
+            language_overrides: &Default::default(),
         };
 
         let results = process_file_with_results(&params).expect("Failed to process file with results");
@@ -373,6 +381,8 @@ function processResults(results) {
             queries_terms: &[term_pairs.clone()],
             preprocessed_queries: Some(&preprocessed_queries),
             query_plan: &query_plan,
+            case_fold: false,
+            language_overrides: &Default::default(),
         };
         
         let results = process_file_with_results(&params).expect("Failed to process file with results");
@@ -476,6 +486,8 @@ function allTermsFunction() {
             preprocessed_queries: Some(&preprocessed_queries),
             query_plan: &query_plan,
             no_merge: false,
+            case_fold: false,
+            language_overrides: &Default::default(),
         };
         
         let results = process_file_with_results(&params).expect("Failed to process file with results");
@@ -0,0 +1,92 @@
+// Filename qualifiers - `filename:` terms that match file names instead of content.
+//
+// `filename:config timeout` searches for `timeout` in files whose name contains `config`,
+// `-filename:test` leaves out files whose name contains `test`, and `filename:*.proto`
+// matches names against a glob. A query of nothing but qualifiers returns the files
+// matching by name, as file name matching does for ordinary terms.
+
+use std::path::Path;
+
+const FILENAME_QUALIFIER: &str = "filename:";
+
+/// A `filename:` qualifier from a query
+#[derive(Debug, Clone)]
+pub struct FilenameFilter {
+    /// Lowercase name fragment or glob
+    pub value: String,
+    /// Written as `-filename:`, so matching files are left out
+    pub excluded: bool,
+    glob: Option<glob::Pattern>,
+}
+
+impl FilenameFilter {
+    fn new(value: &str, excluded: bool) -> Self {
+        let value = value.to_lowercase();
+        let glob = if value.contains(['*', '?', '[']) {
+            glob::Pattern::new(&value).ok()
+        } else {
+            None
+        };
+        Self {
+            value,
+            excluded,
+            glob,
+        }
+    }
+
+    /// Whether the value is a glob rather than a name fragment
+    pub fn is_glob(&self) -> bool {
+        self.glob.is_some()
+    }
+
+    /// Whether the file name of `path` matches the qualifier's value, ignoring case
+    pub fn matches_name(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name() else {
+            return false;
+        };
+        let name = name.to_string_lossy().to_lowercase();
+        match &self.glob {
+            Some(glob) => glob.matches(&name),
+            None => name.contains(&self.value),
+        }
+    }
+}
+
+/// Function to split `filename:` qualifiers out of the queries
+///
+/// Returns the qualifiers and the queries with the qualifiers removed.
+/// Queries that contained nothing but qualifiers are dropped.
+pub fn split_filename_qualifiers(queries: &[String]) -> (Vec<FilenameFilter>, Vec<String>) {
+    let mut filters = Vec::new();
+    let mut remaining = Vec::new();
+
+    for query in queries {
+        let mut words = Vec::new();
+        for word in query.split_whitespace() {
+            let (excluded, bare) = match word.strip_prefix('-') {
+                Some(bare) => (true, bare),
+                None => (false, word.trim_start_matches('+')),
+            };
+            match bare.strip_prefix(FILENAME_QUALIFIER) {
+                Some(value) if !value.trim_matches('"').is_empty() => {
+                    filters.push(FilenameFilter::new(value.trim_matches('"'), excluded));
+                }
+                _ => words.push(word),
+            }
+        }
+        if !words.is_empty() {
+            remaining.push(words.join(" "));
+        }
+    }
+
+    (filters, remaining)
+}
+
+/// Function to check a file against all qualifiers
+///
+/// The name must match every included qualifier and none of the excluded ones.
+pub fn matches_all(filters: &[FilenameFilter], path: &Path) -> bool {
+    filters
+        .iter()
+        .all(|filter| filter.matches_name(path) != filter.excluded)
+}
@@ -0,0 +1,154 @@
+//! Line authorship from `git blame`, used by `--author`.
+//!
+//! Each file is blamed once per search. A block belongs to the author of the most recent
+//! commit that touched any of its lines; files outside a git repository (or not yet committed)
+//! have no author and never match.
+
+use anyhow::{anyhow, Context, Result};
+use regex::{Regex, RegexBuilder};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::models::SearchResult;
+
+/// Commit information for a single line of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameLine {
+    pub author: String,
+    pub email: String,
+    /// Committer timestamp (seconds since the epoch) of the commit that last changed the line
+    pub time: i64,
+}
+
+/// Function to blame every line of a file
+///
+/// Returns one entry per line, in file order.
+pub fn blame_file(path: &Path) -> Result<Vec<BlameLine>> {
+    let dir = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["blame", "--line-porcelain", "--"])
+        .arg(path.file_name().unwrap_or(path.as_os_str()))
+        .output()
+        .context("Failed to run git")?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git blame of '{}' failed: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(parse_line_porcelain(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+/// Function to parse `git blame --line-porcelain` output
+///
+/// Every line comes with a full header; the line content itself starts with a tab.
+fn parse_line_porcelain(output: &str) -> Vec<BlameLine> {
+    let mut lines = Vec::new();
+    let mut current = BlameLine {
+        author: String::new(),
+        email: String::new(),
+        time: 0,
+    };
+
+    for line in output.lines() {
+        if line.starts_with('\t') {
+            lines.push(current.clone());
+        } else if let Some(author) = line.strip_prefix("author ") {
+            current.author = author.to_string();
+        } else if let Some(email) = line.strip_prefix("author-mail ") {
+            current.email = email
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .to_string();
+        } else if let Some(time) = line.strip_prefix("committer-time ") {
+            current.time = time.parse().unwrap_or(0);
+        }
+    }
+
+    lines
+}
+
+/// Function to find the line of a block changed by the most recent commit
+///
+/// `start` and `end` are 1-based and inclusive.
+pub fn last_touched(blame: &[BlameLine], start: usize, end: usize) -> Option<&BlameLine> {
+    let start = start.max(1);
+    if start > blame.len() {
+        return None;
+    }
+    blame[start - 1..end.min(blame.len())]
+        .iter()
+        .max_by_key(|line| line.time)
+}
+
+/// Case-insensitive author pattern, matched against "Name <email>"
+pub struct AuthorFilter {
+    pattern: Regex,
+}
+
+impl AuthorFilter {
+    pub fn new(pattern: &str) -> Result<Self> {
+        let pattern = RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .with_context(|| format!("Invalid --author pattern '{}'", pattern))?;
+        Ok(Self { pattern })
+    }
+
+    pub fn matches(&self, line: &BlameLine) -> bool {
+        self.pattern
+            .is_match(&format!("{} <{}>", line.author, line.email))
+    }
+
+    /// Function to keep only the results last touched by a matching author
+    ///
+    /// Each file is blamed once; files that cannot be blamed are dropped.
+    pub fn filter(&self, results: Vec<SearchResult>) -> Vec<SearchResult> {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+        let mut blames: HashMap<PathBuf, Option<Vec<BlameLine>>> = HashMap::new();
+
+        results
+            .into_iter()
+            .filter(|result| {
+                let path = result.path();
+                let blame = blames.entry(path.clone()).or_insert_with(|| {
+                    blame_file(&path)
+                        .map_err(|e| {
+                            if debug_mode {
+                                println!("DEBUG: {}", e);
+                            }
+                        })
+                        .ok()
+                });
+                // Files-only results stand for the whole file
+                let (start, end) = if result.node_type == "file" {
+                    (1, usize::MAX)
+                } else {
+                    result.lines
+                };
+                let keep = blame
+                    .as_deref()
+                    .and_then(|blame| last_touched(blame, start, end))
+                    .is_some_and(|line| self.matches(line));
+                if debug_mode && !keep {
+                    println!(
+                        "DEBUG: Removing block {:?}:{}-{} - last touched by another author",
+                        path, result.lines.0, result.lines.1
+                    );
+                }
+                keep
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,394 @@
+//! Persistent index of the tokens each file contains.
+//!
+//! `probe index build` records, for every file under a directory, its modification time,
+//! its length and the distinct whitespace-separated tokens it contains, in
+//! `.probe/index.json`. `probe index update` reads again only the files whose time or
+//! length changed since, and drops removed ones.
+//!
+//! Searches under an indexed directory find the index on their own. Before a file is read,
+//! its tokens are checked: a file that is unchanged since it was indexed and none of whose
+//! tokens the search patterns match cannot match them either, so it is not read at all.
+//! That holds only for patterns that never match whitespace, which keeps every match inside
+//! one token; a search with any other pattern, such as a quoted phrase, scans every file as
+//! it does without an index. Files changed since indexing, files the index doesn't know and
+//! searches of buffers rather than the disk are scanned as well.
+
+use anyhow::{anyhow, Context, Result};
+use once_cell::sync::Lazy;
+use rayon::prelude::*;
+use regex_syntax::hir::{Class, Hir, HirKind};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::fs_access;
+use crate::search::file_list_cache::{self, FileFilters};
+use crate::search::normalization::normalize;
+use crate::search::pattern_matcher::PatternMatcher;
+use crate::search::session_store::write_atomic;
+
+/// Directory holding the index, in the indexed directory
+pub const INDEX_DIR: &str = ".probe";
+
+/// File holding the index, in [`INDEX_DIR`]
+pub const INDEX_FILE: &str = "index.json";
+
+/// Format of the index file; files of any other version are ignored
+const INDEX_VERSION: u32 = 1;
+
+/// Tokens and metadata of the files under a directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchIndex {
+    version: u32,
+    /// Distinct tokens of all indexed files
+    tokens: Vec<String>,
+    /// Indexed files, relative to the indexed directory
+    files: BTreeMap<PathBuf, IndexedFile>,
+}
+
+/// What the index records about one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedFile {
+    /// Modification time, in seconds and nanoseconds since the Unix epoch
+    modified: (u64, u32),
+    /// Length in bytes
+    len: u64,
+    /// Positions in [`SearchIndex::tokens`] of the tokens the file contains
+    tokens: Vec<u32>,
+}
+
+/// How building or updating an index went
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexUpdate {
+    /// Files read, because they were new or had changed
+    pub updated: usize,
+    /// Files dropped, because they were removed or could no longer be read
+    pub removed: usize,
+}
+
+/// Helper function to turn a modification time into what the index stores
+fn stamp_key(modified: SystemTime) -> (u64, u32) {
+    let since_epoch = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+    (since_epoch.as_secs(), since_epoch.subsec_nanos())
+}
+
+/// Helper function to get the distinct tokens of a file's content
+fn content_tokens(content: &str) -> HashSet<&str> {
+    content.split_whitespace().collect()
+}
+
+impl SearchIndex {
+    /// Index every file under `root`, test files included
+    pub fn build(root: &Path) -> Result<(Self, IndexUpdate)> {
+        Self::default().update(root)
+    }
+
+    /// Index the files under `root` again, reading only those that are new or changed
+    pub fn update(&self, root: &Path) -> Result<(Self, IndexUpdate)> {
+        let file_list = file_list_cache::build_uncached_file_list(
+            root,
+            true,
+            &[],
+            false,
+            FileFilters::default(),
+        )?;
+
+        // Files are read on rayon threads, which take this thread's file system
+        let file_system = fs_access::current();
+        let scanned: Vec<_> = file_list
+            .files
+            .par_iter()
+            .map(|path| {
+                let relative = path.strip_prefix(root).unwrap_or(path).to_path_buf();
+                let scanned = fs_access::with_file_system(Arc::clone(&file_system), || {
+                    // Stamped before reading, so a change made meanwhile shows on the next update
+                    let (modified, len) = fs_access::current().stamp(path)?.ok()?;
+                    let modified = stamp_key(modified);
+                    let unchanged = self
+                        .files
+                        .get(&relative)
+                        .is_some_and(|file| file.modified == modified && file.len == len);
+                    if unchanged {
+                        return Some((len, modified, None));
+                    }
+                    // Files that aren't text are left out, as searches leave them out
+                    let content = fs_access::read_to_string(path).ok()?;
+                    Some((len, modified, Some(content)))
+                });
+                (relative, scanned)
+            })
+            .collect();
+
+        let mut index = SearchIndex {
+            version: INDEX_VERSION,
+            ..SearchIndex::default()
+        };
+        let mut token_ids: HashMap<String, u32> = HashMap::new();
+        let mut intern = |token: &str, tokens: &mut Vec<String>| -> u32 {
+            if let Some(&id) = token_ids.get(token) {
+                return id;
+            }
+            let id = tokens.len() as u32;
+            tokens.push(token.to_string());
+            token_ids.insert(token.to_string(), id);
+            id
+        };
+
+        let mut update = IndexUpdate::default();
+        for (relative, scanned) in scanned {
+            let Some((len, modified, content)) = scanned else {
+                continue;
+            };
+            let mut tokens: Vec<u32> = match &content {
+                Some(content) => {
+                    update.updated += 1;
+                    content_tokens(content)
+                        .into_iter()
+                        .map(|token| intern(token, &mut index.tokens))
+                        .collect()
+                }
+                None => self.files[&relative]
+                    .tokens
+                    .iter()
+                    .map(|&id| intern(&self.tokens[id as usize], &mut index.tokens))
+                    .collect(),
+            };
+            tokens.sort_unstable();
+            index.files.insert(
+                relative,
+                IndexedFile {
+                    modified,
+                    len,
+                    tokens,
+                },
+            );
+        }
+        update.removed = self
+            .files
+            .keys()
+            .filter(|path| !index.files.contains_key(*path))
+            .count();
+        Ok((index, update))
+    }
+
+    /// Read the index of `root`, if it has one
+    ///
+    /// An index written by another version of probe counts as none.
+    pub fn load(root: &Path) -> Result<Option<Self>> {
+        let path = root.join(INDEX_DIR).join(INDEX_FILE);
+        if !fs_access::exists(&path) {
+            return Ok(None);
+        }
+        let json = fs_access::read_to_string(&path)
+            .with_context(|| format!("Failed to read index {:?}", path))?;
+        let index: SearchIndex = serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse index {:?}", path))?;
+        Ok((index.version == INDEX_VERSION).then_some(index))
+    }
+
+    /// Write the index of `root`, returning the file it went to
+    pub fn save(&self, root: &Path) -> Result<PathBuf> {
+        let dir = root.join(INDEX_DIR);
+        fs_access::current()
+            .create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {:?}", dir))?;
+        let path = dir.join(INDEX_FILE);
+        write_atomic(&path, serde_json::to_string(self)?.as_bytes())
+            .with_context(|| format!("Failed to write index {:?}", path))?;
+        Ok(path)
+    }
+
+    /// Number of indexed files
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Number of distinct tokens over all indexed files
+    pub fn token_count(&self) -> usize {
+        self.tokens.len()
+    }
+}
+
+/// An index found for a search, with the directory it was built for
+#[derive(Debug)]
+struct LoadedIndex {
+    root: PathBuf,
+    /// Modification time of the index file when it was read
+    modified: SystemTime,
+    index: SearchIndex,
+}
+
+/// Indexes loaded so far, by index file
+static LOADED: Lazy<Mutex<HashMap<PathBuf, Arc<LoadedIndex>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Helper function to find the index covering `path`, in it or the nearest parent having one
+fn find_index(path: &Path) -> Option<Arc<LoadedIndex>> {
+    let file_system = fs_access::current();
+    let path = std::fs::canonicalize(path).ok()?;
+    let root = path
+        .ancestors()
+        .find(|dir| fs_access::exists(dir.join(INDEX_DIR).join(INDEX_FILE)))?;
+    let index_path = root.join(INDEX_DIR).join(INDEX_FILE);
+    let (modified, _) = file_system.stamp(&index_path)?.ok()?;
+
+    let mut loaded = LOADED.lock().unwrap();
+    if let Some(index) = loaded.get(&index_path) {
+        if index.modified == modified {
+            return Some(Arc::clone(index));
+        }
+    }
+    let index = Arc::new(LoadedIndex {
+        root: root.to_path_buf(),
+        modified,
+        index: SearchIndex::load(root).ok()??,
+    });
+    loaded.insert(index_path, Arc::clone(&index));
+    Some(index)
+}
+
+/// Whether nothing `pattern` matches can contain whitespace, nor be empty
+///
+/// Matches of such a pattern in a line always lie inside one of its tokens. Word
+/// boundaries at the ends of a token hold in the line as well, since whitespace or the
+/// line's ends surround it there.
+pub fn confined_to_tokens(pattern: &str) -> bool {
+    fn confined(hir: &Hir) -> bool {
+        match hir.kind() {
+            HirKind::Empty | HirKind::Look(_) => true,
+            HirKind::Literal(literal) => std::str::from_utf8(&literal.0)
+                .is_ok_and(|text| !text.chars().any(char::is_whitespace)),
+            HirKind::Class(Class::Unicode(class)) => class.ranges().iter().all(|range| {
+                !WHITESPACE
+                    .iter()
+                    .any(|c| range.start() <= *c && *c <= range.end())
+            }),
+            // Only patterns matching bytes rather than text have these
+            HirKind::Class(Class::Bytes(_)) => false,
+            HirKind::Repetition(repetition) => confined(&repetition.sub),
+            HirKind::Capture(capture) => confined(&capture.sub),
+            HirKind::Concat(hirs) | HirKind::Alternation(hirs) => hirs.iter().all(confined),
+        }
+    }
+
+    match regex_syntax::parse(&format!("(?i){}", pattern)) {
+        Ok(hir) => hir.properties().minimum_len().is_some_and(|len| len > 0) && confined(&hir),
+        Err(_) => false,
+    }
+}
+
+/// Every character `str::split_whitespace` splits on
+static WHITESPACE: Lazy<Vec<char>> = Lazy::new(|| {
+    (0..=0x3000)
+        .filter_map(char::from_u32)
+        .filter(|c| c.is_whitespace())
+        .collect()
+});
+
+/// Which files a search can leave unread, by the index covering it
+pub struct IndexFilter {
+    loaded: Arc<LoadedIndex>,
+    /// The searched path, as the search names it
+    search_root: PathBuf,
+    /// The searched path, relative to the indexed directory
+    base: PathBuf,
+    /// Whether the patterns match each token of the index
+    matched: Vec<bool>,
+}
+
+impl IndexFilter {
+    /// Prepare the filter for a search of `root_path` with `patterns`, if an index covers it
+    ///
+    /// Returns `None` when no index covers the path or any pattern may match outside a
+    /// token, in which case every file is scanned.
+    pub fn for_search(
+        root_path: &Path,
+        patterns: &[String],
+        matcher: &PatternMatcher,
+        case_fold: bool,
+    ) -> Option<Self> {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+        if patterns.is_empty() || !patterns.iter().all(|p| confined_to_tokens(p)) {
+            if debug_mode {
+                println!("DEBUG: Search patterns may match whitespace, not using an index");
+            }
+            return None;
+        }
+        let loaded = find_index(root_path)?;
+        let base = std::fs::canonicalize(root_path)
+            .ok()?
+            .strip_prefix(&loaded.root)
+            .ok()?
+            .to_path_buf();
+
+        let start = Instant::now();
+        let matched: Vec<bool> = loaded
+            .index
+            .tokens
+            .par_iter()
+            .map(|token| matcher.is_match(&normalize(token, case_fold)))
+            .collect();
+        if debug_mode {
+            println!(
+                "DEBUG: Matched {} of {} index tokens in {:?}",
+                matched.iter().filter(|m| **m).count(),
+                matched.len(),
+                start.elapsed()
+            );
+        }
+
+        Some(Self {
+            loaded,
+            search_root: root_path.to_path_buf(),
+            base,
+            matched,
+        })
+    }
+
+    /// Whether `file_path`, found by the search, cannot match and need not be read
+    pub fn can_skip(&self, file_path: &Path) -> bool {
+        let Ok(relative) = file_path.strip_prefix(&self.search_root) else {
+            return false;
+        };
+        let Some(file) = self.loaded.index.files.get(&self.base.join(relative)) else {
+            return false;
+        };
+        let unchanged = match fs_access::current().stamp(file_path) {
+            Some(Ok((modified, len))) => stamp_key(modified) == file.modified && len == file.len,
+            _ => false,
+        };
+        unchanged && !file.tokens.iter().any(|&id| self.matched[id as usize])
+    }
+}
+
+/// Handle `probe index build` and `probe index update`
+pub fn handle_index(path: &Path, update: bool) -> Result<()> {
+    if !path.is_dir() {
+        return Err(anyhow!("{:?} is not a directory", path));
+    }
+    let start = Instant::now();
+    let (index, report) = if update {
+        let previous = SearchIndex::load(path)?.ok_or_else(|| {
+            anyhow!(
+                "No index in {:?}; run `probe index build` to create one",
+                path
+            )
+        })?;
+        previous.update(path)?
+    } else {
+        SearchIndex::build(path)?
+    };
+    let saved = index.save(path)?;
+    println!(
+        "Indexed {} files ({} read, {} removed, {} distinct tokens) in {:?} to {}",
+        index.file_count(),
+        report.updated,
+        report.removed,
+        index.token_count(),
+        start.elapsed(),
+        saved.display()
+    );
+    Ok(())
+}
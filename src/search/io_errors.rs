@@ -0,0 +1,78 @@
+//! Collection of the IO errors a search runs into.
+//!
+//! Files that cannot be read (permission denied, deleted between the directory scan and the
+//! read, ...) are left out of the results. They are recorded here so the search can report
+//! them in its `errors` section, or fail with `--strict`.
+
+use std::cell::RefCell;
+use std::path::Path;
+
+use crate::models::{FileError, FileErrorKind};
+
+thread_local! {
+    static COLLECTED: RefCell<Option<Vec<FileError>>> = const { RefCell::new(None) };
+}
+
+/// Run `f`, returning its result and the IO errors recorded on this thread meanwhile
+///
+/// Errors are sorted by path, with one entry per path.
+pub(crate) fn collect<T>(f: impl FnOnce() -> T) -> (T, Vec<FileError>) {
+    struct Restore(Option<Vec<FileError>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            COLLECTED.with(|collected| *collected.borrow_mut() = previous);
+        }
+    }
+
+    let previous = COLLECTED.with(|collected| collected.borrow_mut().replace(Vec::new()));
+    let restore = Restore(previous);
+    let result = f();
+    let mut errors = COLLECTED
+        .with(|collected| collected.borrow_mut().take())
+        .unwrap_or_default();
+    drop(restore);
+
+    errors.sort_by(|a, b| a.path.cmp(&b.path));
+    errors.dedup_by(|a, b| a.path == b.path);
+    (result, errors)
+}
+
+/// Whether any IO error was recorded on this thread by the innermost `collect` so far
+pub(crate) fn any_recorded() -> bool {
+    COLLECTED.with(|collected| {
+        collected
+            .borrow()
+            .as_ref()
+            .is_some_and(|errors| !errors.is_empty())
+    })
+}
+
+/// Record that `path` could not be read
+pub(crate) fn record(path: &Path, error: &std::io::Error) {
+    record_file_error(FileError {
+        path: path.to_path_buf(),
+        kind: FileErrorKind::from_io(error),
+        message: error.to_string(),
+    });
+}
+
+/// Record an error that was already classified, e.g. one kept from a cached directory walk
+pub(crate) fn record_file_error(error: FileError) {
+    COLLECTED.with(|collected| {
+        if let Some(errors) = collected.borrow_mut().as_mut() {
+            errors.push(error);
+        }
+    });
+}
+
+/// Record the IO error behind `error`, if there is one, for `path`
+///
+/// Invalid UTF-8 is not an IO failure: such files are reported as binary instead.
+pub(crate) fn record_if_io(path: &Path, error: &anyhow::Error) {
+    if let Some(io_error) = error.downcast_ref::<std::io::Error>() {
+        if io_error.kind() != std::io::ErrorKind::InvalidData {
+            record(path, io_error);
+        }
+    }
+}
@@ -16,6 +16,7 @@ pub fn apply_limits(
             total_results: results.len(),
             total_bytes: results.iter().map(|r| r.content.len()).sum(),
             total_tokens: results.iter().map(|r| count_tokens(&r.content)).sum(),
+            errors: Vec::new(),
         };
     }
 
@@ -67,5 +68,6 @@ pub fn apply_limits(
         total_results: limited_results.len(),
         total_bytes: current_bytes,
         total_tokens: current_tokens,
+        errors: Vec::new(),
     }
 }
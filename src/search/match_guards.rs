@@ -0,0 +1,48 @@
+use crate::models::{CapReason, CappedFile};
+use std::path::Path;
+
+/// Default length in characters above which a line is not searched
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 2000;
+
+/// Default number of matched lines after which the scan of a file stops
+pub const DEFAULT_MAX_MATCHES_PER_FILE: usize = 10_000;
+
+/// Limits that keep pathological files from blowing up the pattern scan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchGuards {
+    /// Lines longer than this are not searched
+    pub max_line_length: usize,
+    /// The scan of a file stops after this many matched lines
+    pub max_matches_per_file: usize,
+    /// The scan stops after this many matched lines across all files
+    pub max_total_matches: Option<usize>,
+}
+
+/// What the guards cut while scanning a single file
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FileCaps {
+    /// Lines skipped for being longer than the line length limit
+    pub long_lines: usize,
+    /// Whether the scan stopped at the per-file match limit
+    pub matches_capped: bool,
+}
+
+impl FileCaps {
+    /// Function to turn the caps of a file into entries for the search results
+    pub fn report(&self, path: &Path, guards: &MatchGuards) -> Vec<CappedFile> {
+        let mut capped = Vec::new();
+        if self.long_lines > 0 {
+            capped.push(CappedFile {
+                path: path.to_path_buf(),
+                reason: CapReason::LongLines(self.long_lines),
+            });
+        }
+        if self.matches_capped {
+            capped.push(CappedFile {
+                path: path.to_path_buf(),
+                reason: CapReason::FileMatches(guards.max_matches_per_file),
+            });
+        }
+        capped
+    }
+}
@@ -1,22 +1,79 @@
 pub mod file_processing;
 pub mod query;
 mod result_ranking;
+pub mod result_order;
+pub mod result_kind;
 // Replace the old search_execution with new modules
+pub mod acronyms;
+pub mod api_names;
+pub mod attributes;
+pub mod code_pattern;
 pub mod block_merging;
 pub mod cache; // New module for caching search results
+pub mod cancellation;
+pub mod candidate_selection;
+pub mod code_terms;
+pub mod complexity;
+pub mod content_hash;
+pub mod content_tokens;
+pub mod corpus_stats;
+pub mod dir_context;
+pub mod directives;
 pub mod elastic_query;
+pub mod embedded;
+pub mod empty_queries;
+pub mod engine;
 pub mod file_list_cache; // New module for caching file lists
+pub mod filename_qualifier;
+pub mod git_blame;
+pub mod index;
+pub mod io_errors;
+pub mod match_guards;
+pub mod normalization;
+pub mod options_validation;
+pub mod output_sink;
+pub mod oversize;
+pub mod pattern_matcher;
+pub mod pinned_blocks;
+pub mod preproc_context;
+pub mod query_limits;
+pub mod score_normalization;
 mod search_limiter;
 mod search_options;
 mod search_output;
 pub mod search_runner;
 pub mod search_tokens;
+pub mod session;
+pub mod session_store;
+pub mod skip_report;
+pub mod snippet_window;
+pub mod telemetry;
 pub mod term_exceptions; // New module for term exceptions
 pub mod tokenization; // New elastic search query parser
-                      // Temporarily commented out due to compilation issues
-                      // mod temp_frequency_search;
+pub mod trace_export;
+pub mod usages;
+// Temporarily commented out due to compilation issues
+// mod temp_frequency_search;
 
 // Public exports
+#[allow(unused_imports)]
+pub use engine::{EngineOptions, ProbeEngine};
+#[allow(unused_imports)]
+pub use output_sink::{CallbackSink, NullSink, OutputSink, SearchOutput, TerminalSink, WriterSink};
+#[allow(unused_imports)]
+pub use query::{prepare_query, PreparedQuery};
 pub use search_options::SearchOptions;
-pub use search_output::format_and_print_search_results;
+#[allow(unused_imports)]
+pub use search_options::{SearchRequest, SearchRoot};
+#[allow(unused_imports)]
+pub use search_output::{
+    format_and_print_search_results, render_template_results, write_search_results,
+};
 pub use search_runner::perform_probe;
+#[allow(unused_imports)]
+pub use search_runner::{
+    perform_probe_async, perform_probe_prepared, perform_probe_with_session,
+    perform_probe_within_session,
+};
+#[allow(unused_imports)]
+pub use session::Session;
@@ -0,0 +1,48 @@
+//! Unicode normalization of query terms and scanned content.
+//!
+//! The same identifier can be written with precomposed characters (`café`, NFC) or with
+//! combining marks (`cafe` + U+0301, NFD). Both sides of every comparison are brought to
+//! NFC so either form matches. With case folding, compatibility variants (ligatures,
+//! full-width letters) are folded as well, and case differences that lowercasing alone
+//! misses (`ß` / `SS`) are removed.
+
+use std::borrow::Cow;
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+/// Function to normalize text for matching
+///
+/// ASCII text is returned unchanged; it is already in every normal form, and ASCII case
+/// is handled by case-insensitive matching.
+pub fn normalize(text: &str, case_fold: bool) -> Cow<'_, str> {
+    if text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+    if case_fold {
+        return Cow::Owned(fold(text));
+    }
+    if is_nfc_quick(text.chars()) == IsNormalized::Yes {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(text.nfc().collect())
+}
+
+/// Helper function for full case folding on top of NFKC
+fn fold(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.nfkc().flat_map(char::to_lowercase) {
+        match c {
+            'ß' => folded.push_str("ss"),
+            'ς' => folded.push('σ'),
+            _ => folded.push(c),
+        }
+    }
+    folded.nfc().collect()
+}
+
+/// Function to normalize every query string
+pub fn normalize_queries(queries: &[String], case_fold: bool) -> Vec<String> {
+    queries
+        .iter()
+        .map(|query| normalize(query, case_fold).into_owned())
+        .collect()
+}
@@ -0,0 +1,201 @@
+use anyhow::Result;
+
+use crate::error::ProbeError;
+use crate::fs_access;
+use crate::models::ResultKind;
+use crate::search::code_terms::CODE_TERM_LANGUAGES;
+use crate::search::search_options::SearchOptions;
+
+/// Rerankers the search and `--reranker` accept; the older names are kept for callers and
+/// all rank with BM25
+pub const RERANKERS: [&str; 5] = ["bm25", "hybrid", "hybrid2", "tfidf", "combined"];
+
+/// Separator styles accepted for `path_style`
+const PATH_STYLES: &[&str] = &["native", "unix"];
+
+/// Function to check a search's options before it runs
+///
+/// Catches values the search would otherwise reject halfway through or fall back from
+/// silently, and combinations that cannot return what was asked for. Every problem found
+/// is listed in the error, each with what to do instead. Options that another option
+/// makes redundant only print a warning. The error is [`ProbeError::Io`] when the only
+/// problem is a search path that does not exist, and [`ProbeError::QueryParse`] otherwise.
+pub fn validate_search_options(options: &SearchOptions) -> Result<()> {
+    let mut problems = Vec::new();
+    let mut warnings = Vec::new();
+    let mut missing_paths = Vec::new();
+
+    if options.roots.is_empty() {
+        if !fs_access::exists(options.path) {
+            missing_paths.push(format!(
+                "Search path does not exist: {}",
+                options.path.display()
+            ));
+        }
+    } else {
+        for root in options.roots {
+            if !fs_access::exists(&root.path) {
+                missing_paths.push(format!(
+                    "Search path does not exist: {}",
+                    root.path.display()
+                ));
+            }
+        }
+    }
+
+    check_choice(&mut problems, "--reranker", options.reranker, &RERANKERS);
+    check_choice(
+        &mut problems,
+        "--path-style",
+        options.path_style,
+        PATH_STYLES,
+    );
+    check_choice(
+        &mut problems,
+        "--oversize-strategy",
+        options.oversize_strategy,
+        &["full", "truncate", "outline"],
+    );
+    check_choice(
+        &mut problems,
+        "--visibility",
+        options.visibility,
+        &["public", "private", "any"],
+    );
+    for kind in options.kinds {
+        check_choice(&mut problems, "--kind", kind, ResultKind::NAMES);
+    }
+    check_choice(
+        &mut problems,
+        "--sort",
+        options.sort,
+        &["score", "path", "complexity"],
+    );
+    if let Some(language) = options.code_terms {
+        check_choice(&mut problems, "--code-terms", language, CODE_TERM_LANGUAGES);
+    }
+
+    // Options that another option turns off
+    if options.files_only {
+        if options.max_bytes.is_some() {
+            problems.push(
+                "--max-bytes has no effect with --files-only, which returns no code; \
+                 use --max-results to limit the number of files"
+                    .to_string(),
+            );
+        }
+        if options.max_tokens.is_some() {
+            problems.push(
+                "--max-tokens has no effect with --files-only, which returns no code; \
+                 use --max-results to limit the number of files"
+                    .to_string(),
+            );
+        }
+        if options.snippet_window.is_some() {
+            warnings.push(
+                "--snippet-window has no effect with --files-only, which returns no code"
+                    .to_string(),
+            );
+        }
+        if options.embedded {
+            warnings.push(
+                "--embedded has no effect with --files-only, which returns no code".to_string(),
+            );
+        }
+    }
+    if options.no_merge && options.merge_threshold.is_some() {
+        warnings
+            .push("--merge-threshold has no effect with --no-merge; drop one of them".to_string());
+    }
+    if options.allow_tests && options.tests_for.is_some() {
+        warnings.push(
+            "--tests-for has no effect with --allow-tests, which already includes every test \
+             file; drop --allow-tests to get only tests referencing the symbol"
+                .to_string(),
+        );
+    }
+
+    // Ranges that cannot match anything
+    if let (Some(min), Some(max)) = (options.min_depth, options.max_depth) {
+        if min > max {
+            problems.push(format!(
+                "--min-depth {} is greater than --max-depth {}, so no file can match",
+                min, max
+            ));
+        }
+    }
+    if let (Some(min), Some(max)) = (options.min_size, options.max_size) {
+        if min > max {
+            problems.push(format!(
+                "--min-size ({} bytes) is greater than --max-size ({} bytes), so no file can match",
+                min, max
+            ));
+        }
+    }
+    if let (Some(newer), Some(older)) = (options.newer_than, options.older_than) {
+        if older >= newer {
+            problems.push(
+                "--older-than must be shorter than --newer-than, otherwise no file can match"
+                    .to_string(),
+            );
+        }
+    }
+
+    // Values out of range
+    if options.candidate_files == Some(0) {
+        problems.push("--candidate-files must be at least 1".to_string());
+    }
+    if options.max_line_length == 0 {
+        problems.push("--max-line-length must be at least 1".to_string());
+    }
+    if options.max_matches_per_file == 0 {
+        problems.push("--max-matches-per-file must be at least 1".to_string());
+    }
+    if options.max_query_terms == 0 {
+        problems.push("--max-query-terms must be at least 1".to_string());
+    }
+    if !(0.0..=1.0).contains(&options.deprecated_penalty) {
+        problems.push(format!(
+            "--deprecated-penalty must be between 0 and 1, got {}",
+            options.deprecated_penalty
+        ));
+    }
+    if options.filename_weight < 0.0 || !options.filename_weight.is_finite() {
+        problems.push(format!(
+            "--filename-weight must be 0 or more, got {}",
+            options.filename_weight
+        ));
+    }
+
+    for warning in &warnings {
+        eprintln!("Warning: {}", warning);
+    }
+
+    let variant = if problems.is_empty() {
+        ProbeError::Io
+    } else {
+        ProbeError::QueryParse
+    };
+    let problems: Vec<String> = missing_paths.into_iter().chain(problems).collect();
+    match problems.len() {
+        0 => Ok(()),
+        1 => Err(variant(problems[0].clone()).into()),
+        _ => Err(variant(format!(
+            "Invalid search options:\n  {}",
+            problems.join("\n  ")
+        ))
+        .into()),
+    }
+}
+
+/// Helper function to record a value that is not one of the accepted choices
+fn check_choice(problems: &mut Vec<String>, option: &str, value: &str, choices: &[&str]) {
+    if !choices.contains(&value) {
+        problems.push(format!(
+            "Unknown {} '{}', expected one of: {}",
+            option,
+            value,
+            choices.join(", ")
+        ));
+    }
+}
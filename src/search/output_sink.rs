@@ -0,0 +1,167 @@
+//! Destinations for search results.
+//!
+//! The formatters write results as text, JSON or XML. An [`OutputSink`] decides where a
+//! finished search goes: the CLI prints through a [`TerminalSink`] or writes files through a
+//! [`WriterSink`], while library users can capture the results themselves with a
+//! [`CallbackSink`] instead of parsing stdout, or drop them with a [`NullSink`].
+
+use anyhow::Result;
+use std::io::Write;
+
+use crate::models::{FileError, LimitedSearchResults, SearchResult};
+use crate::search::query::QueryPlan;
+use crate::search::search_output::{display_order, write_search_results};
+
+/// A finished search, as handed to a sink
+#[derive(Debug, Clone, Copy)]
+pub struct SearchOutput<'a> {
+    pub results: &'a [SearchResult],
+    /// Files that could not be read
+    pub errors: &'a [FileError],
+    /// Plan of the query, used to highlight matches in color output
+    pub query_plan: Option<&'a QueryPlan>,
+    /// Whether only file names and line numbers are shown
+    pub dry_run: bool,
+    /// Fingerprint of every file the search considered, listed in JSON and XML summaries
+    pub corpus_fingerprint: Option<&'a str>,
+}
+
+impl<'a> SearchOutput<'a> {
+    pub fn new(results: &'a [SearchResult], errors: &'a [FileError]) -> Self {
+        Self {
+            results,
+            errors,
+            query_plan: None,
+            dry_run: false,
+            corpus_fingerprint: None,
+        }
+    }
+}
+
+impl<'a> From<&'a LimitedSearchResults> for SearchOutput<'a> {
+    fn from(limited: &'a LimitedSearchResults) -> Self {
+        Self::new(&limited.results, &limited.errors)
+    }
+}
+
+/// Destination of search results
+pub trait OutputSink {
+    /// Deliver the results of one search
+    fn emit(&mut self, output: &SearchOutput<'_>) -> Result<()>;
+}
+
+/// Sink printing results to stdout in one of the CLI formats
+pub struct TerminalSink {
+    format: String,
+}
+
+impl TerminalSink {
+    /// `format` is any `--format` value: `terminal`, `markdown`, `plain`, `color`, `json`
+    /// or `xml`. Color is only used when stdout is a terminal.
+    pub fn new(format: &str) -> Self {
+        Self {
+            format: format.to_string(),
+        }
+    }
+}
+
+impl OutputSink for TerminalSink {
+    fn emit(&mut self, output: &SearchOutput<'_>) -> Result<()> {
+        let mut stdout = std::io::stdout().lock();
+        write_search_results(
+            &mut stdout,
+            output.results,
+            output.dry_run,
+            &self.format,
+            output.query_plan,
+            output.errors,
+            output.corpus_fingerprint,
+        )
+    }
+}
+
+/// Sink writing results to any writer in one of the CLI formats
+///
+/// Colors are not turned off for the writer; callers writing to a file call
+/// `colored::control::set_override(false)` first.
+pub struct WriterSink<W: Write> {
+    writer: W,
+    format: String,
+}
+
+#[allow(dead_code)]
+impl<W: Write> WriterSink<W> {
+    pub fn new(writer: W, format: &str) -> Self {
+        Self {
+            writer,
+            format: format.to_string(),
+        }
+    }
+
+    /// Sink writing the JSON output of `--format json`
+    pub fn json(writer: W) -> Self {
+        Self::new(writer, "json")
+    }
+
+    /// The writer, with everything emitted so far
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> OutputSink for WriterSink<W> {
+    fn emit(&mut self, output: &SearchOutput<'_>) -> Result<()> {
+        write_search_results(
+            &mut self.writer,
+            output.results,
+            output.dry_run,
+            &self.format,
+            output.query_plan,
+            output.errors,
+            output.corpus_fingerprint,
+        )?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Sink calling a function with each result, in the order the formatters show them
+///
+/// Results without a file name, which the formatters leave out, are skipped. The
+/// function's error stops the remaining results and is returned from `emit`.
+#[allow(dead_code)]
+pub struct CallbackSink<F> {
+    callback: F,
+}
+
+#[allow(dead_code)]
+impl<F> CallbackSink<F>
+where
+    F: FnMut(&SearchResult) -> Result<()>,
+{
+    pub fn new(callback: F) -> Self {
+        Self { callback }
+    }
+}
+
+impl<F> OutputSink for CallbackSink<F>
+where
+    F: FnMut(&SearchResult) -> Result<()>,
+{
+    fn emit(&mut self, output: &SearchOutput<'_>) -> Result<()> {
+        display_order(output.results)
+            .into_iter()
+            .try_for_each(|result| (self.callback)(result))
+    }
+}
+
+/// Sink discarding all results, for searches run only for their side effects
+#[derive(Debug, Clone, Copy, Default)]
+#[allow(dead_code)]
+pub struct NullSink;
+
+impl OutputSink for NullSink {
+    fn emit(&mut self, _output: &SearchOutput<'_>) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::HashSet;
+use tree_sitter::{Node, Parser as TSParser};
+
+use crate::fs_access;
+use crate::language::factory::get_language_impl;
+use crate::language::language_trait::LanguageImpl;
+use crate::models::SearchResult;
+use crate::search::snippet_window::{fold_lines, line_matches};
+
+/// Default number of lines above which a block counts as oversized
+pub const DEFAULT_OVERSIZE_THRESHOLD: usize = 300;
+
+/// What to return for a matched block longer than the oversize threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OversizeStrategy {
+    /// Return the whole block
+    Full,
+    /// Return the first lines of the block, up to the threshold
+    Truncate,
+    /// Return the signature, nested symbol signatures and matched lines
+    Outline,
+}
+
+impl OversizeStrategy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "full" => Some(OversizeStrategy::Full),
+            "truncate" => Some(OversizeStrategy::Truncate),
+            "outline" => Some(OversizeStrategy::Outline),
+            _ => None,
+        }
+    }
+}
+
+/// Helper function to collect the rows on which nested symbols start
+fn collect_symbol_rows(
+    node: Node,
+    language: &dyn LanguageImpl,
+    rows: (usize, usize),
+    symbol_rows: &mut HashSet<usize>,
+) {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        let (start, end) = (child.start_position().row, child.end_position().row);
+        if end < rows.0 || start > rows.1 {
+            continue;
+        }
+        if start >= rows.0 && language.is_acceptable_parent(&child) {
+            symbol_rows.insert(start);
+        }
+        collect_symbol_rows(child, language, rows, symbol_rows);
+    }
+}
+
+/// Function to find the lines of a block where nested symbols are declared
+///
+/// Line numbers are 1-based and absolute. Returns an empty set when the file cannot be
+/// read or its language is not supported.
+fn nested_symbol_lines(result: &SearchResult) -> HashSet<usize> {
+    let path = result.path();
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let (Some(language), Ok(content)) = (
+        get_language_impl(extension),
+        fs_access::read_to_string(&path),
+    ) else {
+        return HashSet::new();
+    };
+
+    let mut parser = TSParser::new();
+    if parser
+        .set_language(&language.get_tree_sitter_language())
+        .is_err()
+    {
+        return HashSet::new();
+    }
+    let Some(tree) = parser.parse(&content, None) else {
+        return HashSet::new();
+    };
+
+    let rows = (
+        result.lines.0.saturating_sub(1),
+        result.lines.1.saturating_sub(1),
+    );
+    let mut symbol_rows = HashSet::new();
+    collect_symbol_rows(tree.root_node(), language.as_ref(), rows, &mut symbol_rows);
+    symbol_rows.into_iter().map(|row| row + 1).collect()
+}
+
+/// Function to keep the first `threshold` lines of a block
+pub fn truncate_block(code: &str, threshold: usize) -> Option<String> {
+    let lines: Vec<&str> = code.lines().collect();
+    if lines.len() <= threshold {
+        return None;
+    }
+    let keep: Vec<bool> = (0..lines.len()).map(|i| i < threshold).collect();
+    Some(fold_lines(&lines, &keep))
+}
+
+/// Function to reduce a block to its outline
+///
+/// Keeps the first line (the signature), the first line of every nested symbol
+/// (`symbol_lines`, 1-based offsets into `code`) and every line matching a query term.
+pub fn outline_block(
+    code: &str,
+    symbol_lines: &HashSet<usize>,
+    terms: &HashSet<String>,
+) -> Option<String> {
+    let lines: Vec<&str> = code.lines().collect();
+    let keep: Vec<bool> = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| i == 0 || symbol_lines.contains(&(i + 1)) || line_matches(line, terms))
+        .collect();
+    if keep.iter().all(|&kept| kept) {
+        return None;
+    }
+    Some(fold_lines(&lines, &keep))
+}
+
+/// Function to apply `--oversize-strategy` to search results
+///
+/// Only blocks with more than `threshold` lines are changed. Results keep their full
+/// line range; only the returned code is reduced.
+pub fn apply_oversize_strategy(
+    results: &mut [SearchResult],
+    strategy: OversizeStrategy,
+    threshold: usize,
+    terms: &HashSet<String>,
+) {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+    if strategy == OversizeStrategy::Full {
+        return;
+    }
+
+    for result in results {
+        let line_count = result.code.lines().count();
+        if line_count <= threshold {
+            continue;
+        }
+
+        let reduced = match strategy {
+            OversizeStrategy::Truncate => truncate_block(&result.code, threshold),
+            OversizeStrategy::Outline => {
+                // Code already trimmed by --snippet-window no longer lines up with the file
+                let aligned = line_count == result.lines.1 + 1 - result.lines.0;
+                let symbol_lines: HashSet<usize> = if aligned {
+                    nested_symbol_lines(result)
+                        .into_iter()
+                        .filter(|&line| line >= result.lines.0)
+                        .map(|line| line - result.lines.0 + 1)
+                        .collect()
+                } else {
+                    HashSet::new()
+                };
+                outline_block(&result.code, &symbol_lines, terms)
+            }
+            OversizeStrategy::Full => None,
+        };
+
+        if let Some(reduced) = reduced {
+            if debug_mode {
+                println!(
+                    "DEBUG: Reduced oversized block {}:{}-{} ({:?}) from {} to {} lines",
+                    result.file,
+                    result.lines.0,
+                    result.lines.1,
+                    strategy,
+                    line_count,
+                    reduced.lines().count()
+                );
+            }
+            result.code = reduced;
+        }
+    }
+}
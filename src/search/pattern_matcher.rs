@@ -0,0 +1,172 @@
+//! Matching the search patterns against lines.
+//!
+//! The patterns of a query are normally compiled into one regex, which tells in a single pass
+//! whether a line matches any of them, and one regex set, which tells which patterns a
+//! matching line matches. The regex can't tell that by itself: patterns have groups of their
+//! own, and of patterns matching at the same place it only reports the first. The patterns
+//! generated for an OR group nest the groups of both its sides, so for a large group that
+//! regex is either too big to compile or needs gigabytes of capture state once it runs. Such
+//! patterns are matched with regex sets alone, split into as many sets as the size limit
+//! needs.
+
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use regex::{Regex, RegexBuilder, RegexSet, RegexSetBuilder};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Most capture groups the combined regex may have before sets are used instead
+pub const MAX_CAPTURE_GROUPS: usize = 1024;
+
+/// Compiled size limit of each regex, the default of the `regex` crate
+pub const DEFAULT_SIZE_LIMIT: usize = 10 * (1 << 20);
+
+/// Most pattern lists kept by `PatternMatcher::cached`; the cache starts over when it is full
+const MAX_CACHED_MATCHERS: usize = 256;
+
+lazy_static! {
+    static ref MATCHER_CACHE: Mutex<HashMap<Vec<String>, Arc<PatternMatcher>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Compiled search patterns
+#[derive(Debug, Clone)]
+pub enum PatternMatcher {
+    /// One regex of all patterns, and a set of them reporting which match
+    Combined(Regex, RegexSet),
+    /// Sets of patterns, each with the index of its first pattern
+    Sets(Vec<(usize, RegexSet)>),
+}
+
+impl PatternMatcher {
+    /// Compile `patterns`, matched case-insensitively
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        Self::with_size_limit(patterns, DEFAULT_SIZE_LIMIT)
+    }
+
+    /// Compile `patterns`, reusing the matcher from an earlier search in this process if
+    /// there is one
+    ///
+    /// A long-running process answers the same queries again and again, and compiling their
+    /// patterns can take longer than matching them.
+    pub fn cached(patterns: &[String]) -> Result<Arc<Self>> {
+        if let Some(matcher) = MATCHER_CACHE.lock().unwrap().get(patterns) {
+            return Ok(Arc::clone(matcher));
+        }
+
+        let matcher = Arc::new(Self::new(patterns)?);
+        let mut cache = MATCHER_CACHE.lock().unwrap();
+        if cache.len() >= MAX_CACHED_MATCHERS {
+            cache.clear();
+        }
+        cache.insert(patterns.to_vec(), Arc::clone(&matcher));
+        Ok(matcher)
+    }
+
+    /// Compile `patterns` with a compiled size limit of `size_limit` bytes per regex
+    ///
+    /// A pattern too large to compile even on its own is left out. Such patterns only come
+    /// from OR groups, whose terms also have patterns of their own.
+    pub fn with_size_limit(patterns: &[String], size_limit: usize) -> Result<Self> {
+        let combined = patterns
+            .iter()
+            .map(|p| format!("({})", p))
+            .collect::<Vec<_>>()
+            .join("|");
+        match RegexBuilder::new(&format!("(?i){}", combined))
+            .size_limit(size_limit)
+            .build()
+        {
+            Ok(regex) if regex.captures_len() <= MAX_CAPTURE_GROUPS => {
+                match RegexSetBuilder::new(patterns)
+                    .case_insensitive(true)
+                    .size_limit(size_limit)
+                    .build()
+                {
+                    Ok(set) => return Ok(PatternMatcher::Combined(regex, set)),
+                    Err(regex::Error::CompiledTooBig(_)) => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            Ok(_) | Err(regex::Error::CompiledTooBig(_)) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let mut sets = Vec::new();
+        build_sets(patterns, 0, size_limit, &mut sets)?;
+        if sets.is_empty() && !patterns.is_empty() {
+            return Err(anyhow!(
+                "Search patterns are too large to compile, even one at a time"
+            ));
+        }
+        Ok(PatternMatcher::Sets(sets))
+    }
+
+    /// Number of regexes run over each line
+    pub fn passes(&self) -> usize {
+        match self {
+            PatternMatcher::Combined(..) => 1,
+            PatternMatcher::Sets(sets) => sets.len(),
+        }
+    }
+
+    /// Whether any pattern matches `text`
+    pub fn is_match(&self, text: &str) -> bool {
+        match self {
+            PatternMatcher::Combined(regex, _) => regex.is_match(text),
+            PatternMatcher::Sets(sets) => sets.iter().any(|(_, set)| set.is_match(text)),
+        }
+    }
+
+    /// Call `f` with the index of each pattern matching `line`, possibly more than once
+    pub fn for_each_match(&self, line: &str, mut f: impl FnMut(usize)) {
+        match self {
+            PatternMatcher::Combined(regex, set) => {
+                // Most lines match nothing, which the regex finds out fastest
+                if regex.is_match(line) {
+                    for index in set.matches(line).iter() {
+                        f(index);
+                    }
+                }
+            }
+            PatternMatcher::Sets(sets) => {
+                for (offset, set) in sets {
+                    for index in set.matches(line).iter() {
+                        f(offset + index);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Helper function to compile `patterns` into sets, halving them until each set fits
+fn build_sets(
+    patterns: &[String],
+    offset: usize,
+    size_limit: usize,
+    sets: &mut Vec<(usize, RegexSet)>,
+) -> Result<()> {
+    match RegexSetBuilder::new(patterns)
+        .case_insensitive(true)
+        .size_limit(size_limit)
+        .build()
+    {
+        Ok(set) => sets.push((offset, set)),
+        Err(regex::Error::CompiledTooBig(_)) if patterns.len() > 1 => {
+            let half = patterns.len() / 2;
+            build_sets(&patterns[..half], offset, size_limit, sets)?;
+            build_sets(&patterns[half..], offset + half, size_limit, sets)?;
+        }
+        Err(regex::Error::CompiledTooBig(_)) => {
+            if std::env::var("DEBUG").unwrap_or_default() == "1" {
+                println!(
+                    "DEBUG: Leaving out pattern too large to compile: {}",
+                    patterns[0]
+                );
+            }
+        }
+        Err(e) => return Err(e.into()),
+    }
+    Ok(())
+}
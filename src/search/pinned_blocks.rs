@@ -0,0 +1,94 @@
+// Pinned blocks - blocks named with `--pin-blocks` that every search returns.
+//
+// Pinned blocks are re-read from disk on each search, so a caller can refresh a block it
+// is editing without it being skipped as already returned in the session.
+
+use anyhow::Result;
+use std::path::Path;
+
+use crate::error::ProbeError;
+use crate::extract::process_file_for_extraction;
+use crate::models::SearchResult;
+use crate::search::session::Session;
+
+/// Function to check that block identifiers have the `file:start-end` form
+pub fn validate_block_ids(flag: &str, block_ids: &[String]) -> Result<()> {
+    for block_id in block_ids {
+        match Session::parse_block_id(block_id) {
+            Some((file, start, end)) if !file.is_empty() && start >= 1 && start <= end => {}
+            _ => {
+                return Err(ProbeError::QueryParse(format!(
+                    "Invalid {} block '{}': expected file:start-end, e.g. src/main.rs:10-42",
+                    flag, block_id
+                ))
+                .into())
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Function to load the current contents of pinned blocks
+///
+/// Each block is extracted again from its file, widened to the AST blocks it overlaps, so
+/// edits since it was last returned are picked up. Blocks whose file no longer exists are
+/// left out with a warning.
+pub fn load_pinned_blocks(block_ids: &[String], allow_tests: bool) -> Vec<SearchResult> {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    let mut pinned: Vec<SearchResult> = Vec::new();
+    for block_id in block_ids {
+        let Some((file, start, end)) = Session::parse_block_id(block_id) else {
+            continue;
+        };
+        match process_file_for_extraction(
+            Path::new(file),
+            Some(start),
+            Some(end),
+            None,
+            allow_tests,
+            0,
+            None,
+        ) {
+            Ok(mut result) => {
+                result.file = file.to_string();
+                let duplicate = pinned
+                    .iter()
+                    .any(|other| other.file == result.file && other.lines == result.lines);
+                if debug_mode {
+                    println!(
+                        "DEBUG: Pinned block {} refreshed as {}-{}",
+                        block_id, result.lines.0, result.lines.1
+                    );
+                }
+                if !duplicate {
+                    pinned.push(result);
+                }
+            }
+            Err(e) => eprintln!("Warning: could not load pinned block {}: {}", block_id, e),
+        }
+    }
+    pinned
+}
+
+/// Function to put pinned blocks in front of the search results
+///
+/// Results inside a pinned block are dropped, as the pinned block already shows them.
+pub fn prepend_pinned_blocks(
+    results: Vec<SearchResult>,
+    pinned: Vec<SearchResult>,
+) -> Vec<SearchResult> {
+    let uncovered: Vec<SearchResult> = results
+        .into_iter()
+        .filter(|result| {
+            !pinned.iter().any(|block| {
+                block.file == result.file
+                    && block.lines.0 <= result.lines.0
+                    && result.lines.1 <= block.lines.1
+            })
+        })
+        .collect();
+    let mut combined = pinned;
+    combined.extend(uncovered);
+    combined
+}
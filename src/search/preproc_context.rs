@@ -0,0 +1,132 @@
+use tree_sitter::{Node, Parser as TSParser};
+
+use crate::fs_access;
+use crate::language::factory::get_language_impl;
+use crate::models::SearchResult;
+use crate::search::snippet_window::fold_lines;
+
+/// Extensions of the files whose blocks can sit inside preprocessor conditionals
+const PREPROC_EXTENSIONS: &[&str] = &["c", "h", "cpp", "cc", "cxx", "hpp", "hxx"];
+
+/// Helper function to get the last row of a node, ignoring a trailing newline
+fn last_row(node: &Node) -> usize {
+    let end = node.end_position();
+    if end.column == 0 && end.row > node.start_position().row {
+        end.row - 1
+    } else {
+        end.row
+    }
+}
+
+/// Helper function to collect the directive rows of conditionals enclosing `rows`
+///
+/// For `#if`/`#ifdef` that is the opening line and the `#endif` line, for `#else` and
+/// `#elif` branches the branch line. Rows are 0-based.
+fn collect_directive_rows(node: Node, rows: (usize, usize), directive_rows: &mut Vec<usize>) {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        let encloses = child.start_position().row < rows.0 && last_row(&child) >= rows.1;
+        if !encloses {
+            continue;
+        }
+        match child.kind() {
+            "preproc_if" | "preproc_ifdef" => {
+                directive_rows.push(child.start_position().row);
+                directive_rows.push(last_row(&child));
+            }
+            "preproc_else" | "preproc_elif" | "preproc_elifdef" => {
+                directive_rows.push(child.start_position().row);
+            }
+            _ => {}
+        }
+        collect_directive_rows(child, rows, directive_rows);
+    }
+}
+
+/// Function to find the preprocessor directive lines around a block
+///
+/// Line numbers are 1-based and sorted. Returns an empty list when the block is not
+/// inside a conditional or the file cannot be parsed.
+pub fn enclosing_directive_lines(
+    content: &str,
+    extension: &str,
+    lines: (usize, usize),
+) -> Vec<usize> {
+    let Some(language) = get_language_impl(extension) else {
+        return Vec::new();
+    };
+    let mut parser = TSParser::new();
+    if parser
+        .set_language(&language.get_tree_sitter_language())
+        .is_err()
+    {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(content, None) else {
+        return Vec::new();
+    };
+
+    let rows = (lines.0.saturating_sub(1), lines.1.saturating_sub(1));
+    let mut directive_rows = Vec::new();
+    collect_directive_rows(tree.root_node(), rows, &mut directive_rows);
+    let mut directive_lines: Vec<usize> = directive_rows.into_iter().map(|row| row + 1).collect();
+    directive_lines.sort_unstable();
+    directive_lines.dedup();
+    directive_lines
+}
+
+/// Function to add the enclosing `#if` context to C/C++ search results
+///
+/// The result is widened to the outermost enclosing conditional. Directive lines are
+/// kept and the code of other branches is folded, so the block reads as the code the
+/// preprocessor would select.
+pub fn apply_preproc_context(results: &mut [SearchResult]) {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    for result in results {
+        let path = result.path();
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        if !PREPROC_EXTENSIONS.contains(&extension) {
+            continue;
+        }
+        let Ok(content) = fs_access::read_to_string(&path) else {
+            continue;
+        };
+
+        let directive_lines = enclosing_directive_lines(&content, extension, result.lines);
+        let (Some(&first), Some(&last)) = (directive_lines.first(), directive_lines.last()) else {
+            continue;
+        };
+        let file_lines: Vec<&str> = content.lines().collect();
+        if last > file_lines.len() {
+            continue;
+        }
+
+        let context = |from: usize, to: usize| -> Option<String> {
+            if from > to {
+                return None;
+            }
+            let keep: Vec<bool> = (from..=to)
+                .map(|line| directive_lines.contains(&line))
+                .collect();
+            Some(fold_lines(&file_lines[from - 1..to], &keep))
+        };
+        let parts: Vec<String> = [
+            context(first, result.lines.0 - 1),
+            Some(result.code.clone()),
+            context(result.lines.1 + 1, last),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        result.code = parts.join("\n");
+
+        if debug_mode {
+            println!(
+                "DEBUG: Added preprocessor context to {}:{}-{}, now {}-{}",
+                result.file, result.lines.0, result.lines.1, first, last
+            );
+        }
+        result.lines = (first, last);
+    }
+}
@@ -1,6 +1,12 @@
+use crate::search::acronyms;
+use crate::search::code_terms;
 use crate::search::elastic_query;
+use crate::search::normalization::normalize;
+use crate::search::tokenization::is_numeric_literal;
 // No term_exceptions import needed
-use std::collections::{HashMap, HashSet};
+use lazy_static::lazy_static;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
 /// Escapes special regex characters in a string
@@ -20,6 +26,33 @@ pub fn regex_escape(s: &str) -> String {
     result
 }
 
+/// Escape `term` for a pattern, keeping numeric and version literals from matching
+/// inside longer numbers, so `1.2.3` does not match `11.2.34`
+pub(crate) fn literal_pattern(term: &str) -> String {
+    if is_numeric_literal(term) {
+        format!(r"\bv?{}\b", regex_escape(term))
+    } else {
+        regex_escape(term)
+    }
+}
+
+/// Escape `term` for a pattern, with the irregular plurals the code term rules map to it
+/// and, with acronym matching on, the identifiers it is an acronym of
+pub(crate) fn term_pattern(term: &str) -> String {
+    let mut pattern = literal_pattern(term);
+    if let Some(rules) = code_terms::current() {
+        for plural in rules.plural_variants(term) {
+            pattern.push('|');
+            pattern.push_str(plural);
+        }
+    }
+    if acronyms::enabled() && acronyms::is_acronym_candidate(term) {
+        pattern.push('|');
+        pattern.push_str(&acronyms::acronym_pattern(term));
+    }
+    pattern
+}
+
 // ----------------------------------------------------------------------------
 // NEW CODE: Full AST-based planning and pattern generation
 // ----------------------------------------------------------------------------
@@ -47,6 +80,7 @@ fn format_duration(duration: std::time::Duration) -> String {
 pub fn create_query_plan(query: &str, exact: bool) -> Result<QueryPlan, elastic_query::ParseError> {
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
     let start_time = Instant::now();
+    let query = &*normalize(query, false);
 
     if debug_mode {
         println!("DEBUG: Starting query plan creation for query: '{}'", query);
@@ -278,7 +312,7 @@ pub fn build_combined_pattern(terms: &[String]) -> String {
     }
 
     // Escape special characters in each term
-    let escaped_terms = terms.iter().map(|t| regex_escape(t)).collect::<Vec<_>>();
+    let escaped_terms = terms.iter().map(|t| term_pattern(t)).collect::<Vec<_>>();
 
     // Join terms with | operator and add case-insensitive flag without word boundaries
     let pattern = format!("(?i)({})", escaped_terms.join("|"));
@@ -315,13 +349,14 @@ pub fn create_structured_patterns(plan: &QueryPlan) -> Vec<(String, HashSet<usiz
         println!("DEBUG: Excluded terms: {:?}", plan.excluded_terms);
     }
 
-    // Extract all non-excluded terms from the query plan
-    let terms: Vec<String> = plan
+    // Extract all non-excluded terms from the query plan, in the order of their indices
+    let mut terms: Vec<String> = plan
         .term_indices
         .keys()
         .filter(|term| !plan.excluded_terms.contains(*term))
         .cloned()
         .collect();
+    terms.sort_by_key(|term| plan.term_indices[term]);
 
     if !terms.is_empty() {
         let combined_pattern = build_combined_pattern(&terms);
@@ -360,7 +395,7 @@ pub fn create_structured_patterns(plan: &QueryPlan) -> Vec<(String, HashSet<usiz
         // for all non-excluded terms, even if they're part of a complex expression
         for (term, &idx) in &plan.term_indices {
             if !plan.excluded_terms.contains(term) {
-                let base_pattern = regex_escape(term);
+                let base_pattern = term_pattern(term);
                 // Use more flexible pattern matching without word boundaries
                 let pattern = format!("({})", base_pattern);
 
@@ -469,13 +504,11 @@ pub fn create_structured_patterns(plan: &QueryPlan) -> Vec<(String, HashSet<usiz
 
                         // Find the keyword's index in term_indices
                         if let Some(&idx) = plan.term_indices.get(keyword) {
-                            let base_pattern = regex_escape(keyword);
-
                             // For exact terms, use stricter matching
                             let pattern = if *exact {
-                                base_pattern.to_string()
+                                literal_pattern(keyword)
                             } else {
-                                format!("({})", base_pattern)
+                                format!("({})", term_pattern(keyword))
                             };
 
                             if debug_mode {
@@ -637,7 +670,8 @@ pub fn create_structured_patterns(plan: &QueryPlan) -> Vec<(String, HashSet<usiz
     }
 
     // First, deduplicate by exact pattern match
-    let mut pattern_map: HashMap<String, HashSet<usize>> = HashMap::new();
+    // Both maps are ordered, so the patterns kept and their order are the same on every run
+    let mut pattern_map: BTreeMap<String, HashSet<usize>> = BTreeMap::new();
 
     for (pattern, indices) in results {
         pattern_map
@@ -649,7 +683,7 @@ pub fn create_structured_patterns(plan: &QueryPlan) -> Vec<(String, HashSet<usiz
     // Then, deduplicate patterns that match the same term
     // For the test_pattern_deduplication test, we need to ensure we don't have
     // multiple patterns for the same term with the same indices
-    let mut term_patterns: HashMap<String, Vec<(String, HashSet<usize>)>> = HashMap::new();
+    let mut term_patterns: BTreeMap<String, Vec<(String, HashSet<usize>)>> = BTreeMap::new();
 
     // Group patterns by the terms they match
     for (pattern, indices) in pattern_map.iter() {
@@ -707,3 +741,108 @@ pub fn create_structured_patterns(plan: &QueryPlan) -> Vec<(String, HashSet<usiz
 
     deduplicated_results
 }
+
+/// A parsed query with its search patterns, ready to run against any number of roots
+///
+/// Preparing a query once saves parsing and pattern generation on every search that
+/// uses it; see `ProbeEngine::search_prepared` and `perform_probe_prepared`.
+#[derive(Debug)]
+pub struct PreparedQuery {
+    /// The queries the plan was built from (several are combined with AND)
+    pub queries: Vec<String>,
+    /// Whether terms are matched exactly, without stemming or splitting
+    pub exact: bool,
+    /// Language of the code term rules the terms were normalized with, if any
+    pub code_terms: Option<&'static str>,
+    /// Whether terms also match identifiers they are acronyms of
+    pub acronyms: bool,
+    /// The parsed query
+    pub plan: QueryPlan,
+    /// Regex patterns and the term indices each one matches
+    pub patterns: Vec<(String, HashSet<usize>)>,
+}
+
+impl PreparedQuery {
+    /// Parse `queries` and generate their search patterns
+    ///
+    /// Terms are normalized with the code term rules in effect on this thread, and match
+    /// acronyms if acronym matching is on; a search run with the prepared query uses the
+    /// same settings.
+    pub fn new(queries: &[String], exact: bool) -> Result<Self, elastic_query::ParseError> {
+        let plan = create_query_plan(&combine_queries(queries), exact)?;
+        let patterns = create_structured_patterns(&plan);
+        Ok(Self {
+            queries: queries.to_vec(),
+            exact,
+            code_terms: code_terms::current().map(|rules| rules.language()),
+            acronyms: acronyms::enabled(),
+            plan,
+            patterns,
+        })
+    }
+}
+
+/// Join several queries into one that requires all of them
+pub(crate) fn combine_queries(queries: &[String]) -> String {
+    if queries.len() > 1 {
+        queries.join(" AND ")
+    } else {
+        queries.first().cloned().unwrap_or_default()
+    }
+}
+
+/// Join terms into one query that matches any of them
+///
+/// The `OR`s form a balanced tree: the patterns generated for a chain of `OR`s grow with
+/// every link, so long chains get expensive quickly.
+pub(crate) fn balanced_or(terms: &[String]) -> String {
+    match terms {
+        [] => String::new(),
+        [term] => term.clone(),
+        _ => {
+            let (left, right) = terms.split_at(terms.len() / 2);
+            format!("({} OR {})", balanced_or(left), balanced_or(right))
+        }
+    }
+}
+
+/// The combined query, `exact`, the code term language and the acronym setting a plan
+/// was prepared with
+type PreparedQueryKey = (String, bool, Option<&'static str>, bool);
+
+/// Most queries kept by `prepare_query`; the cache starts over when it is full
+const MAX_CACHED_QUERIES: usize = 256;
+
+lazy_static! {
+    static ref PREPARED_QUERY_CACHE: Mutex<HashMap<PreparedQueryKey, Arc<PreparedQuery>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Prepare `queries`, reusing the plan from an earlier search in this process if there is one
+pub fn prepare_query(
+    queries: &[String],
+    exact: bool,
+) -> Result<Arc<PreparedQuery>, elastic_query::ParseError> {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+    let key = (
+        combine_queries(queries),
+        exact,
+        code_terms::current().map(|rules| rules.language()),
+        acronyms::enabled(),
+    );
+
+    if let Some(prepared) = PREPARED_QUERY_CACHE.lock().unwrap().get(&key) {
+        if debug_mode {
+            println!("DEBUG: Using cached query plan for '{}'", key.0);
+        }
+        return Ok(Arc::clone(prepared));
+    }
+
+    let prepared = Arc::new(PreparedQuery::new(queries, exact)?);
+    let mut cache = PREPARED_QUERY_CACHE.lock().unwrap();
+    if cache.len() >= MAX_CACHED_QUERIES {
+        cache.clear();
+    }
+    cache.insert(key, Arc::clone(&prepared));
+    Ok(prepared)
+}
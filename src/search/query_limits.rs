@@ -0,0 +1,166 @@
+//! Limits on the size of a query.
+//!
+//! Generated queries can OR together dozens of terms or nest groups many levels deep. Each OR
+//! joins all patterns of both its sides into one more pattern, so the patterns the scanner
+//! runs over every line double in size with each term ORed on, and such a query can stall the
+//! search or exhaust memory before returning anything. The guard rejects these queries before
+//! their patterns are generated, naming the limit that was hit.
+
+use crate::error::ProbeError;
+use crate::search::elastic_query::Expr;
+use crate::search::query::{
+    combine_queries, create_query_plan, literal_pattern, term_pattern, QueryPlan,
+};
+
+/// Default number of distinct terms a query may expand to
+pub const DEFAULT_MAX_QUERY_TERMS: usize = 128;
+
+/// Deepest nesting of parenthesized groups in a query
+pub const MAX_QUERY_NESTING: usize = 32;
+
+/// Largest total size in bytes of the patterns generated for a query
+pub const MAX_PATTERN_BYTES: usize = 128 * 1024;
+
+/// Function to find the deepest nesting of parentheses in a query, ignoring quoted text
+pub fn nesting_depth(query: &str) -> usize {
+    let mut depth = 0usize;
+    let mut deepest = 0;
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for c in query.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            '(' if !in_quotes => {
+                depth += 1;
+                deepest = deepest.max(depth);
+            }
+            ')' if !in_quotes => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    deepest
+}
+
+/// Function to estimate the total size of the patterns generated for a plan
+///
+/// Follows `create_structured_patterns` without building any pattern, so it stays cheap for
+/// the queries whose patterns would not fit in memory. Deduplication is not accounted for.
+pub fn estimated_pattern_bytes(plan: &QueryPlan) -> usize {
+    let terms: Vec<&String> = plan
+        .term_indices
+        .keys()
+        .filter(|term| !plan.excluded_terms.contains(*term))
+        .collect();
+    let term_bytes: usize = terms.iter().map(|term| term_pattern(term).len()).sum();
+    // One pattern matching any term, `(?i)(a|b|...)`
+    let combined = term_bytes + terms.len() + 5;
+
+    if !plan.excluded_terms.is_empty() {
+        // One more pattern per term, `(a)`
+        return combined + term_bytes + 2 * terms.len();
+    }
+    let (_, tree) = tree_pattern_bytes(&plan.ast, plan);
+    combined.saturating_add(tree)
+}
+
+/// Helper function to count the patterns generated for an expression and their total size
+fn tree_pattern_bytes(expr: &Expr, plan: &QueryPlan) -> (usize, usize) {
+    match expr {
+        Expr::Term {
+            keywords,
+            excluded,
+            exact,
+            ..
+        } => {
+            if *excluded {
+                return (0, 0);
+            }
+            let mut count = 0;
+            let mut bytes = 0;
+            for keyword in keywords {
+                if plan.excluded_terms.contains(keyword) || !plan.term_indices.contains_key(keyword)
+                {
+                    continue;
+                }
+                count += 1;
+                bytes += if *exact {
+                    literal_pattern(keyword).len()
+                } else {
+                    term_pattern(keyword).len() + 2
+                };
+            }
+            (count, bytes)
+        }
+        Expr::And(left, right) => {
+            let (left_count, left_bytes) = tree_pattern_bytes(left, plan);
+            let (right_count, right_bytes) = tree_pattern_bytes(right, plan);
+            (
+                left_count.saturating_add(right_count),
+                left_bytes.saturating_add(right_bytes),
+            )
+        }
+        Expr::Or(left, right) => {
+            let (left_count, left_bytes) = tree_pattern_bytes(left, plan);
+            let (right_count, right_bytes) = tree_pattern_bytes(right, plan);
+            let count = left_count.saturating_add(right_count);
+            let bytes = left_bytes.saturating_add(right_bytes);
+            if left_count == 0 || right_count == 0 {
+                return (count, bytes);
+            }
+            // Both sides joined into `(l1|l2|...|r1|r2|...)`, kept along with the sides
+            let joined = bytes.saturating_add(count).saturating_add(1);
+            (count.saturating_add(1), bytes.saturating_add(joined))
+        }
+    }
+}
+
+/// Function to reject queries nested too deeply
+pub fn check_nesting(queries: &[String]) -> Result<(), ProbeError> {
+    let depth = queries.iter().map(|q| nesting_depth(q)).max().unwrap_or(0);
+    if depth > MAX_QUERY_NESTING {
+        return Err(ProbeError::LimitExceeded(format!(
+            "Query nests groups {} levels deep, more than the limit of {}",
+            depth, MAX_QUERY_NESTING
+        )));
+    }
+    Ok(())
+}
+
+/// Function to reject a parsed query with too many terms or too large patterns
+///
+/// Terms are counted after splitting and stemming, so one compound word can count as
+/// several terms.
+pub fn check_plan(plan: &QueryPlan, max_terms: usize) -> Result<(), ProbeError> {
+    let terms = plan.term_indices.len();
+    if terms > max_terms {
+        return Err(ProbeError::LimitExceeded(format!(
+            "Query expands to {} terms, more than the limit of {}; split it into smaller \
+             queries or raise --max-query-terms",
+            terms, max_terms
+        )));
+    }
+
+    let pattern_bytes = estimated_pattern_bytes(plan);
+    if pattern_bytes > MAX_PATTERN_BYTES {
+        return Err(ProbeError::LimitExceeded(format!(
+            "Query generates about {} KB of search patterns, more than the limit of {} KB; \
+             OR together fewer terms or split the query",
+            pattern_bytes.div_ceil(1024),
+            MAX_PATTERN_BYTES / 1024
+        )));
+    }
+    Ok(())
+}
+
+/// Function to reject queries that are too large to search, before they are prepared
+///
+/// Queries that fail to parse pass; preparing them reports the parse error.
+pub fn check_query(queries: &[String], exact: bool, max_terms: usize) -> Result<(), ProbeError> {
+    check_nesting(queries)?;
+    match create_query_plan(&combine_queries(queries), exact) {
+        Ok(plan) => check_plan(&plan, max_terms),
+        Err(_) => Ok(()),
+    }
+}
@@ -0,0 +1,281 @@
+//! Classifying results as definitions, call sites, imports, comments, strings or tests.
+//!
+//! Each occurrence of a queried symbol in a block is looked up in the file's syntax tree:
+//! inside a comment, string literal or import, as the name of a declaration, or as the
+//! function of a call (before its argument list). The block takes the strongest
+//! role among its occurrences, in that order: definition, call site, import. It only
+//! counts as a comment or string when every occurrence is in one, and any block that is
+//! or sits in a test is a test. Blocks whose occurrences are none of these, such as type
+//! annotations or field accesses, keep `kind` unset.
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tree_sitter::{Node, Parser as TSParser, Tree};
+
+use crate::fs_access;
+use crate::language::factory::get_language_impl;
+use crate::language::is_test_file;
+use crate::language::language_trait::LanguageImpl;
+use crate::models::{ResultKind, SearchResult};
+use crate::search::tokenization::split_camel_case;
+use crate::search::usages::extract_symbol_names;
+
+/// Role of a single occurrence of a queried symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Occurrence {
+    Definition,
+    CallSite,
+    Import,
+    Comment,
+    String,
+    Reference,
+}
+
+/// Function to parse `--kind` values, reporting the first unknown one
+pub fn parse_kinds(values: &[String]) -> Result<Vec<ResultKind>, String> {
+    values
+        .iter()
+        .map(|value| ResultKind::parse(value).ok_or_else(|| value.clone()))
+        .collect()
+}
+
+/// Helper function to check whether a node is a comment
+fn is_comment(node: &Node) -> bool {
+    node.kind().contains("comment")
+}
+
+/// Helper function to check whether a node is a string literal or part of one
+fn is_string(node: &Node) -> bool {
+    let kind = node.kind();
+    kind.contains("string") || kind.contains("heredoc")
+}
+
+/// Helper function to check whether a node is a call (`call_expression`, `call`,
+/// `method_invocation`, `invocation_expression`, `macro_invocation`, ...)
+fn is_call(node: &Node) -> bool {
+    let kind = node.kind();
+    kind.contains("call") || kind.contains("invocation")
+}
+
+/// Helper function to check whether a node imports names (`use_declaration`,
+/// `import_statement`, `import_declaration`, `preproc_include`, `using_directive`, ...)
+fn is_import(node: &Node) -> bool {
+    let kind = node.kind();
+    kind.contains("import")
+        || kind.contains("include")
+        || matches!(
+            kind,
+            "use_declaration"
+                | "extern_crate_declaration"
+                | "using_directive"
+                | "namespace_use_declaration"
+        )
+}
+
+/// Helper function to check whether a node is the name its parent declares
+///
+/// Calls and paths (`scoped_identifier`, `qualified_name`) name their last part too, but
+/// declare nothing.
+fn is_declared_name(node: &Node) -> bool {
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    let kind = parent.kind();
+    if is_call(&parent) || kind.contains("identifier") || kind.ends_with("_name") {
+        return false;
+    }
+    ["name", "declarator"].iter().any(|field| {
+        parent
+            .child_by_field_name(field)
+            .is_some_and(|name| name.byte_range() == node.byte_range())
+    })
+}
+
+/// Helper function to find the role of the symbol occurring at `start..end`
+fn classify_occurrence(tree: &Tree, source: &str, start: usize, end: usize) -> Occurrence {
+    let Some(node) = tree.root_node().descendant_for_byte_range(start, end) else {
+        return Occurrence::Reference;
+    };
+
+    let ancestors: Vec<Node> = std::iter::successors(Some(node), |node| node.parent()).collect();
+    if ancestors.iter().any(is_comment) {
+        return Occurrence::Comment;
+    }
+    if ancestors.iter().any(is_string) {
+        return Occurrence::String;
+    }
+    if ancestors.iter().any(is_import) {
+        return Occurrence::Import;
+    }
+    if is_declared_name(&node) {
+        return Occurrence::Definition;
+    }
+    // The symbol is called when it comes before the argument list of its innermost call
+    if let Some(call) = ancestors.iter().find(|node| is_call(node)) {
+        if !source[call.start_byte()..start].contains('(') {
+            return Occurrence::CallSite;
+        }
+    }
+    Occurrence::Reference
+}
+
+/// Helper function to check whether a node within the block's rows, or one containing
+/// them, is a test
+fn has_test_node(
+    node: Node,
+    language: &dyn LanguageImpl,
+    source: &[u8],
+    rows: (usize, usize),
+) -> bool {
+    if node.end_position().row < rows.0 || node.start_position().row > rows.1 {
+        return false;
+    }
+    if language.is_test_node(&node, source) {
+        return true;
+    }
+    let mut cursor = node.walk();
+    let found = node
+        .children(&mut cursor)
+        .any(|child| has_test_node(child, language, source, rows));
+    found
+}
+
+/// A parsed file whose results are being classified
+struct ParsedFile {
+    content: String,
+    /// Byte offset at which each line starts
+    line_starts: Vec<usize>,
+    language: Box<dyn LanguageImpl>,
+    tree: Tree,
+}
+
+impl ParsedFile {
+    fn parse(path: &PathBuf) -> Option<Self> {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        let language = get_language_impl(extension)?;
+        let content = fs_access::read_to_string(path).ok()?;
+        let mut parser = TSParser::new();
+        parser
+            .set_language(&language.get_tree_sitter_language())
+            .ok()?;
+        let tree = parser.parse(&content, None)?;
+        let line_starts = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Some(ParsedFile {
+            content,
+            line_starts,
+            language,
+            tree,
+        })
+    }
+
+    /// Byte range of 1-based `lines`
+    fn byte_range(&self, lines: (usize, usize)) -> (usize, usize) {
+        let start = self
+            .line_starts
+            .get(lines.0.saturating_sub(1))
+            .copied()
+            .unwrap_or(self.content.len());
+        let end = self
+            .line_starts
+            .get(lines.1)
+            .copied()
+            .unwrap_or(self.content.len());
+        (start, end.max(start))
+    }
+}
+
+/// Function to find the kind of a block in a parsed file
+fn classify_block(
+    file: &ParsedFile,
+    lines: (usize, usize),
+    symbols: &[Regex],
+) -> Option<ResultKind> {
+    let rows = (lines.0.saturating_sub(1), lines.1.saturating_sub(1));
+    if has_test_node(
+        file.tree.root_node(),
+        file.language.as_ref(),
+        file.content.as_bytes(),
+        rows,
+    ) {
+        return Some(ResultKind::Test);
+    }
+
+    let (start, end) = file.byte_range(lines);
+    let occurrences: Vec<Occurrence> = symbols
+        .iter()
+        .flat_map(|symbol| symbol.find_iter(&file.content[start..end]))
+        .map(|found| {
+            classify_occurrence(
+                &file.tree,
+                &file.content,
+                start + found.start(),
+                start + found.end(),
+            )
+        })
+        .collect();
+
+    let any = |role: Occurrence| occurrences.contains(&role);
+    if any(Occurrence::Definition) {
+        Some(ResultKind::Definition)
+    } else if any(Occurrence::CallSite) {
+        Some(ResultKind::CallSite)
+    } else if any(Occurrence::Import) {
+        Some(ResultKind::Import)
+    } else if occurrences.is_empty() || any(Occurrence::Reference) {
+        None
+    } else if any(Occurrence::String) {
+        Some(ResultKind::String)
+    } else {
+        Some(ResultKind::Comment)
+    }
+}
+
+/// Helper function to build a pattern matching a symbol in any naming convention
+///
+/// The search matches `load_config` in `loadConfig` and `LoadConfig` too, so its parts
+/// are matched case-insensitively with an optional underscore between them.
+fn symbol_pattern(symbol: &str) -> String {
+    let parts: Vec<String> = symbol
+        .split('_')
+        .flat_map(split_camel_case)
+        .map(|part| regex::escape(&part))
+        .collect();
+    format!(r"(?i)\b{}\b", parts.join("_?"))
+}
+
+/// Function to set the `kind` of each result from the symbols named in the queries
+///
+/// Results in test files are tests. Other results in files whose language has no parser,
+/// or that cannot be read, keep `kind` unset.
+pub fn classify_kinds(results: &mut [SearchResult], queries: &[String]) {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+    let symbols: Vec<Regex> = extract_symbol_names(queries)
+        .iter()
+        .filter_map(|symbol| Regex::new(&symbol_pattern(symbol)).ok())
+        .collect();
+
+    let mut files: HashMap<PathBuf, Option<ParsedFile>> = HashMap::new();
+    for result in results.iter_mut() {
+        let path = result.path();
+        if is_test_file(&path) {
+            result.kind = Some(ResultKind::Test);
+            continue;
+        }
+        let file = files
+            .entry(path.clone())
+            .or_insert_with(|| ParsedFile::parse(&path));
+        result.kind = file
+            .as_ref()
+            .and_then(|file| classify_block(file, result.lines, &symbols));
+
+        if debug_mode {
+            println!(
+                "DEBUG: Classified block {}:{}-{} as {:?}",
+                result.file, result.lines.0, result.lines.1, result.kind
+            );
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use crate::models::SearchResult;
+
+/// Order in which search results are returned and limits are applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultOrder {
+    /// Most relevant results first
+    Score,
+    /// Results sorted by file path, then by line
+    Path,
+    /// Most complex blocks first, by cyclomatic estimate, nesting depth and length
+    Complexity,
+}
+
+impl ResultOrder {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "score" => Some(ResultOrder::Score),
+            "path" => Some(ResultOrder::Path),
+            "complexity" => Some(ResultOrder::Complexity),
+            _ => None,
+        }
+    }
+}
+
+/// Function to sort results by file path and start line
+///
+/// Paths are compared component by component, the order files are processed in, so that
+/// `a/x.rs` comes before `a-b/y.rs`. Ranks and scores are kept, so the output still shows
+/// how relevant each result is.
+pub fn sort_by_path(results: &mut [SearchResult]) {
+    results.sort_by(|a, b| a.path().cmp(&b.path()).then(a.lines.cmp(&b.lines)));
+}
+
+/// Function to sort results with the most complex blocks first
+///
+/// Blocks are compared by cyclomatic estimate, then nesting depth, then line count; blocks
+/// without metrics come last, and ties keep their relevance order.
+pub fn sort_by_complexity(results: &mut [SearchResult]) {
+    results.sort_by_key(|result| {
+        std::cmp::Reverse(
+            result
+                .complexity
+                .map(|metrics| (metrics.cyclomatic, metrics.nesting_depth, metrics.lines)),
+        )
+    });
+}
+
+/// Function to sort results in `order`, leaving them as they are for `Score`
+pub fn sort_in_order(results: &mut [SearchResult], order: ResultOrder) {
+    match order {
+        ResultOrder::Score => {}
+        ResultOrder::Path => sort_by_path(results),
+        ResultOrder::Complexity => sort_by_complexity(results),
+    }
+}
@@ -1,5 +1,8 @@
 use crate::models::SearchResult;
 use crate::ranking;
+use crate::search::acronyms::{self, ACRONYM_MATCH_WEIGHT};
+use crate::search::query::QueryPlan;
+use std::collections::HashMap;
 use std::time::Instant;
 
 /// Helper function to format duration in a human-readable way
@@ -11,8 +14,66 @@ fn format_duration(duration: std::time::Duration) -> String {
     }
 }
 
+/// Helper function to scale the part of each BM25 score that comes from file name tokens
+///
+/// Results are scored again on their code alone; the difference to the full score is the
+/// file name's contribution, which is multiplied by `filename_weight`.
+fn weigh_filename_matches(
+    results: &[SearchResult],
+    query: &str,
+    ranked_indices: Vec<(usize, f64)>,
+    filename_weight: f64,
+) -> Vec<(usize, f64)> {
+    let documents: Vec<&str> = results.iter().map(|r| r.code.as_str()).collect();
+    // Pre-tokenized content ends with the file name tokens, see `preprocess_text_with_filename`
+    let pre_tokenized: Option<Vec<Vec<String>>> = results
+        .iter()
+        .map(|r| {
+            let tokens = r.tokenized_content.as_ref()?;
+            let filename = r
+                .path()
+                .file_name()
+                .map(|f| f.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let filename_tokens = ranking::tokenize(&filename);
+            Some(
+                tokens
+                    .strip_suffix(filename_tokens.as_slice())
+                    .unwrap_or(tokens)
+                    .to_vec(),
+            )
+        })
+        .collect();
+
+    let content_scores: HashMap<usize, f64> = ranking::rank_documents(&ranking::RankingParams {
+        documents: &documents,
+        query,
+        pre_tokenized: pre_tokenized.as_deref(),
+    })
+    .into_iter()
+    .collect();
+
+    ranked_indices
+        .into_iter()
+        .map(|(index, score)| {
+            let content_score = content_scores.get(&index).copied().unwrap_or(0.0);
+            (
+                index,
+                content_score + filename_weight * (score - content_score),
+            )
+        })
+        .collect()
+}
+
 /// Function to rank search results based on query relevance using BM25 algorithm
-pub fn rank_search_results(results: &mut [SearchResult], queries: &[String], reranker: &str) {
+///
+/// `filename_weight` scales how much file name matches count, 1.0 leaves scores as they are.
+pub fn rank_search_results(
+    results: &mut [SearchResult],
+    queries: &[String],
+    reranker: &str,
+    filename_weight: f64,
+) {
     let start_time = Instant::now();
 
     // Check if debug mode is enabled
@@ -127,7 +188,11 @@ pub fn rank_search_results(results: &mut [SearchResult], queries: &[String], rer
     }
 
     // Get ranked indices from the ranking module (BM25 scores)
-    let ranked_indices = ranking::rank_documents(&ranking_params);
+    let mut ranked_indices = ranking::rank_documents(&ranking_params);
+    if filename_weight != 1.0 {
+        ranked_indices =
+            weigh_filename_matches(results, &combined_query, ranked_indices, filename_weight);
+    }
 
     let document_ranking_duration = document_ranking_start.elapsed();
 
@@ -168,10 +233,9 @@ pub fn rank_search_results(results: &mut [SearchResult], queries: &[String], rer
     // Sort updated results by BM25 score in descending order
     let reranker_sort_start = Instant::now();
 
+    // Only in debug mode: searches run for library callers and `probe mcp` print nothing
     if debug_mode {
         println!("DEBUG: Using BM25 ranking (Okapi BM25 algorithm)");
-    } else {
-        println!("Using BM25 ranking (Okapi BM25 algorithm)");
     }
 
     // Sort by BM25 score in descending order
@@ -241,3 +305,90 @@ pub fn rank_search_results(results: &mut [SearchResult], queries: &[String], rer
         );
     }
 }
+
+/// Function to sort ranked results by score again after their scores were adjusted
+///
+/// Results that ranking flagged as skipped (rank `usize::MAX`) stay at the end.
+pub fn resort_by_score(results: &mut [SearchResult]) {
+    let ranked = results
+        .iter()
+        .position(|result| result.rank == Some(usize::MAX))
+        .unwrap_or(results.len());
+    results[..ranked].sort_by(|a, b| {
+        b.score
+            .unwrap_or(0.0)
+            .partial_cmp(&a.score.unwrap_or(0.0))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    for (rank, result) in results[..ranked].iter_mut().enumerate() {
+        result.rank = Some(rank + 1);
+        result.bm25_rank = Some(rank + 1);
+    }
+}
+
+/// Function to lower the scores of deprecated blocks
+///
+/// Deprecated blocks lose `penalty` (0-1) of their score and are ranked again.
+/// Returns the number of penalized blocks.
+pub fn penalize_deprecated(results: &mut [SearchResult], penalty: f64) -> usize {
+    if penalty <= 0.0 {
+        return 0;
+    }
+    let factor = 1.0 - penalty.min(1.0);
+    let mut penalized = 0;
+    for result in results.iter_mut().filter(|result| result.deprecated) {
+        result.score = result.score.map(|score| score * factor);
+        result.bm25_score = result.bm25_score.map(|score| score * factor);
+        penalized += 1;
+    }
+    if penalized > 0 {
+        resort_by_score(results);
+    }
+    penalized
+}
+
+/// Function to lower the scores of blocks that match query terms only as acronyms
+///
+/// Of the query terms a block matches, each one matched only through the initials of
+/// its identifiers counts for `ACRONYM_MATCH_WEIGHT` of a term found in the code, and
+/// the block's score is scaled by the resulting share. Blocks are ranked again if any
+/// was penalized. Returns the number of penalized blocks.
+pub fn penalize_acronym_matches(results: &mut [SearchResult], plan: &QueryPlan) -> usize {
+    if !acronyms::enabled() {
+        return 0;
+    }
+    let candidates: Vec<&String> = acronyms::candidate_terms(plan).collect();
+    if candidates.is_empty() {
+        return 0;
+    }
+    let mut penalized = 0;
+    for result in results.iter_mut() {
+        let Some(tokens) = &result.tokenized_content else {
+            continue;
+        };
+        let matched = plan
+            .term_indices
+            .keys()
+            .filter(|term| !plan.excluded_terms.contains(*term) && tokens.contains(*term))
+            .count();
+        let code_tokens = ranking::tokenize(&result.code);
+        let acronym_only = candidates
+            .iter()
+            .filter(|term| tokens.contains(**term) && !code_tokens.contains(**term))
+            .count();
+        if matched == 0 || acronym_only == 0 {
+            continue;
+        }
+        let full = matched.saturating_sub(acronym_only) as f64;
+        // A penalty, so never above 1.0 whatever the counts
+        let factor =
+            ((full + ACRONYM_MATCH_WEIGHT * acronym_only as f64) / matched as f64).min(1.0);
+        result.score = result.score.map(|score| score * factor);
+        result.bm25_score = result.bm25_score.map(|score| score * factor);
+        penalized += 1;
+    }
+    if penalized > 0 {
+        resort_by_score(results);
+    }
+    penalized
+}
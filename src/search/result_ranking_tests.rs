@@ -9,6 +9,7 @@ mod tests {
         vec![
             SearchResult {
                 file: "file1.rs".to_string(),
+                file_path: None,
                 lines: (1, 10),
                 node_type: "context".to_string(), // Changed to context for testing context boost
                 code: "fn test_function() { println!(\"This is a test function with search terms\"); }".to_string(),
@@ -25,9 +26,21 @@ mod tests {
                 file_match_rank: Some(2),
                 block_unique_terms: Some(2),
                 block_total_matches: Some(2),
+                via_symlink: false,
+                changed_lines: None,
+                symbol_name: None,
+                language: None,
+                extraction_confidence: None,
+                partial_parse: false,
+                deprecated: false,
+                embedded: None,
+                kind: None,
+                complexity: None,
+                content_hash: None,
             },
             SearchResult {
                 file: "file2.rs".to_string(),
+                file_path: None,
                 lines: (1, 5),
                 node_type: "function".to_string(),
                 code: "fn another_function() { // This doesn't have the key term }".to_string(),
@@ -44,9 +57,21 @@ mod tests {
                 file_match_rank: Some(3),
                 block_unique_terms: Some(0),
                 block_total_matches: Some(0),
+                via_symlink: false,
+                changed_lines: None,
+                symbol_name: None,
+                language: None,
+                extraction_confidence: None,
+                partial_parse: false,
+                deprecated: false,
+                embedded: None,
+                kind: None,
+                complexity: None,
+                content_hash: None,
             },
             SearchResult {
                 file: "file3.rs".to_string(),
+                file_path: None,
                 lines: (1, 10),
                 node_type: "function".to_string(),
                 code: "fn search_function() { // This has search in the function name and multiple search terms search search }".to_string(),
@@ -63,6 +88,17 @@ mod tests {
                 file_match_rank: Some(1),
                 block_unique_terms: Some(1),
                 block_total_matches: Some(3),
+                via_symlink: false,
+                changed_lines: None,
+                symbol_name: None,
+                language: None,
+                extraction_confidence: None,
+                partial_parse: false,
+                deprecated: false,
+                embedded: None,
+                kind: None,
+                complexity: None,
+                content_hash: None,
             },
         ]
     }
@@ -74,7 +110,7 @@ mod tests {
         
         // Enable debug mode for this test to verify logging
         std::env::set_var("DEBUG", "1");
-        rank_search_results(&mut results, &queries, "hybrid");
+        rank_search_results(&mut results, &queries, "hybrid", 1.0);
         std::env::remove_var("DEBUG");
         
         // Check that all results have been assigned ranks and scores
@@ -103,7 +139,7 @@ mod tests {
         let mut results = create_test_results();
         let queries = vec!["search".to_string()];
         
-        rank_search_results(&mut results, &queries, "tfidf");
+        rank_search_results(&mut results, &queries, "tfidf", 1.0);
         
         // Check that all results have been assigned ranks and scores
         for result in &results {
@@ -127,7 +163,7 @@ mod tests {
         let mut results = create_test_results();
         let queries = vec!["search".to_string()];
         
-        rank_search_results(&mut results, &queries, "bm25");
+        rank_search_results(&mut results, &queries, "bm25", 1.0);
         
         // Check that all results have been assigned ranks and scores
         for result in &results {
@@ -151,7 +187,7 @@ mod tests {
         let mut results = create_test_results();
         let queries = vec!["search".to_string(), "function".to_string()];
         
-        rank_search_results(&mut results, &queries, "hybrid");
+        rank_search_results(&mut results, &queries, "hybrid", 1.0);
         
         // Check that all results have been assigned ranks and scores
         for result in &results {
@@ -175,7 +211,7 @@ mod tests {
         let queries = vec!["search".to_string()];
         
         // Should not panic with empty results
-        rank_search_results(&mut results, &queries, "hybrid");
+        rank_search_results(&mut results, &queries, "hybrid", 1.0);
         
         assert_eq!(results.len(), 0);
     }
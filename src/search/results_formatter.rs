@@ -134,12 +134,16 @@ pub fn format_and_print_search_results(results: &[SearchResult], dry_run: bool)
                     "sql" => "sql",
                     "kt" | "kts" => "kotlin",
                     "swift" => "swift",
+                    "m" | "mm" => "objectivec",
                     "scala" => "scala",
                     "dart" => "dart",
+                    "nix" => "nix",
+                    "ml" | "mli" => "ocaml",
                     "ex" | "exs" => "elixir",
                     "hs" => "haskell",
                     "clj" => "clojure",
                     "lua" => "lua",
+                    "graphql" | "gql" => "graphql",
                     "r" => "r",
                     "pl" | "pm" => "perl",
                     "proto" => "protobuf",
@@ -0,0 +1,63 @@
+use crate::models::SearchResult;
+use std::collections::HashMap;
+
+/// The kinds of results that carry scores on different scales
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ResultKind {
+    // Whole files (e.g. files-only mode or filename matches)
+    File,
+    // Blocks produced by merging several adjacent blocks
+    Merged,
+    // Regular code blocks
+    Block,
+}
+
+fn result_kind(result: &SearchResult) -> ResultKind {
+    if result.node_type == "file" {
+        ResultKind::File
+    } else if result.merged_from.is_some() {
+        ResultKind::Merged
+    } else {
+        ResultKind::Block
+    }
+}
+
+/// Function to compute a 0-1 `normalized_score` for every result
+///
+/// Raw BM25 scores depend on document length, so whole files, merged blocks and
+/// regular blocks are not directly comparable. Each result's score is divided by
+/// the highest score among results of the same kind, which puts the best result
+/// of every kind at 1.0. Results without a positive score get 0.0.
+///
+/// # Arguments
+/// * `results` - Search results to update in place
+pub fn normalize_scores(results: &mut [SearchResult]) {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    let mut max_scores: HashMap<ResultKind, f64> = HashMap::new();
+    for result in results.iter() {
+        let score = result.score.unwrap_or(0.0);
+        let max = max_scores.entry(result_kind(result)).or_insert(0.0);
+        if score > *max {
+            *max = score;
+        }
+    }
+
+    if debug_mode {
+        println!(
+            "DEBUG: Normalizing scores with per-kind maxima: {:?}",
+            max_scores
+        );
+    }
+
+    for result in results.iter_mut() {
+        let score = result.score.unwrap_or(0.0);
+        let max = max_scores.get(&result_kind(result)).copied().unwrap_or(0.0);
+
+        result.normalized_score = Some(if score > 0.0 && max > 0.0 {
+            (score / max).clamp(0.0, 1.0)
+        } else {
+            0.0
+        });
+    }
+}
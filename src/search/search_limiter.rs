@@ -1,5 +1,6 @@
 use crate::models::{LimitedSearchResults, SearchLimits, SearchResult};
 use crate::search::search_tokens::count_tokens;
+use crate::search::usages::usage_priority;
 
 /// Helper function to apply limits (max results, max bytes, max tokens) to search results
 pub fn apply_limits(
@@ -14,18 +15,57 @@ pub fn apply_limits(
             skipped_files: Vec::new(),
             limits_applied: None,
             cached_blocks_skipped: None,
+            skip_report: None,
+            errors: Vec::new(),
+            capped: Vec::new(),
+            empty_query_cached: false,
         };
     }
 
     let mut results = results;
-    // Sort results by rank if available
-    results.sort_by(|a, b| match (a.rank, b.rank) {
-        (Some(a_r), Some(b_r)) => a_r.cmp(&b_r),
-        (Some(_), None) => std::cmp::Ordering::Less,
-        (None, Some(_)) => std::cmp::Ordering::Greater,
-        _ => std::cmp::Ordering::Equal,
+    // Sort results by usage priority (only set with --usages), then by normalized score
+    // (highest first), falling back to rank
+    results.sort_by(|a, b| {
+        let by_usage = usage_priority(a.usage_kind).cmp(&usage_priority(b.usage_kind));
+        let by_score = match (a.normalized_score, b.normalized_score) {
+            (Some(a_s), Some(b_s)) => b_s.partial_cmp(&a_s).unwrap_or(std::cmp::Ordering::Equal),
+            _ => std::cmp::Ordering::Equal,
+        };
+        by_usage
+            .then(by_score)
+            .then_with(|| match (a.rank, b.rank) {
+                (Some(a_r), Some(b_r)) => a_r.cmp(&b_r),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                _ => std::cmp::Ordering::Equal,
+            })
     });
 
+    apply_limits_in_order(results, max_results, max_bytes, max_tokens)
+}
+
+/// Helper function to apply limits to search results in the order they are given
+///
+/// Used by `--sort path`, where the order is fixed before limits are applied.
+pub fn apply_limits_in_order(
+    results: Vec<SearchResult>,
+    max_results: Option<usize>,
+    max_bytes: Option<usize>,
+    max_tokens: Option<usize>,
+) -> LimitedSearchResults {
+    if max_results.is_none() && max_bytes.is_none() && max_tokens.is_none() {
+        return LimitedSearchResults {
+            results,
+            skipped_files: Vec::new(),
+            limits_applied: None,
+            cached_blocks_skipped: None,
+            skip_report: None,
+            errors: Vec::new(),
+            capped: Vec::new(),
+            empty_query_cached: false,
+        };
+    }
+
     let mut limited = Vec::new();
     let mut skipped = Vec::new();
     let mut total_bytes = 0;
@@ -61,7 +101,12 @@ pub fn apply_limits(
             max_tokens,
             total_bytes,
             total_tokens,
+            post_merge_tokens: None,
         }),
         cached_blocks_skipped: None,
+        skip_report: None,
+        errors: Vec::new(),
+        capped: Vec::new(),
+        empty_query_cached: false,
     }
 }
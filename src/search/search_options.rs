@@ -1,4 +1,33 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::search::file_list_cache::FileFilters;
+use crate::search::match_guards::MatchGuards;
+
+/// A search root with the ignore patterns that apply to it alone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchRoot {
+    pub path: PathBuf,
+    /// Patterns matched relative to `path`, on top of the search's own ignores
+    pub ignores: Vec<String>,
+}
+
+impl SearchRoot {
+    /// A root with `ignores`, which may be written relative to `path` or starting with it
+    /// (`backend/gen` for root `backend`)
+    pub fn new(path: impl Into<PathBuf>, ignores: Vec<String>) -> Self {
+        let path = path.into();
+        let prefix = format!("{}/", path.to_string_lossy().trim_end_matches('/'));
+        let ignores = ignores
+            .into_iter()
+            .map(|ignore| match ignore.strip_prefix(&prefix) {
+                Some(relative) if prefix != "./" && !relative.is_empty() => relative.to_string(),
+                _ => ignore,
+            })
+            .collect();
+        Self { path, ignores }
+    }
+}
 
 /// Options for performing a search
 pub struct SearchOptions<'a> {
@@ -19,4 +48,313 @@ pub struct SearchOptions<'a> {
     pub merge_threshold: Option<usize>,
     pub dry_run: bool,
     pub session: Option<&'a str>,
+    pub report_skipped: bool,
+    pub tests_for: Option<&'a str>,
+    pub usages: bool,
+    pub path_style: &'a str,
+    pub follow_symlinks: bool,
+    pub strict: bool,
+    /// Roots searched together as one corpus in place of `path`, each with its own ignores;
+    /// `custom_ignores` apply to all of them
+    pub roots: &'a [SearchRoot],
+    pub max_depth: Option<usize>,
+    pub min_depth: Option<usize>,
+    pub newer_than: Option<Duration>,
+    pub older_than: Option<Duration>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    pub author: Option<&'a str>,
+    pub snippet_window: Option<usize>,
+    pub oversize_strategy: &'a str,
+    pub oversize_threshold: usize,
+    pub case_fold: bool,
+    pub code_terms: Option<&'a str>,
+    pub acronyms: bool,
+    pub preproc_context: bool,
+    pub embedded: bool,
+    pub force_language: &'a [String],
+    pub within_session: Option<&'a str>,
+    pub exclude_blocks: &'a [String],
+    pub pin_blocks: &'a [String],
+    pub filename_weight: f64,
+    pub include_dir_context: bool,
+    pub deprecated_penalty: f64,
+    pub visibility: &'a str,
+    pub kinds: &'a [String],
+    pub sort: &'a str,
+    pub candidate_files: Option<usize>,
+    pub max_line_length: usize,
+    pub max_matches_per_file: usize,
+    pub max_total_matches: Option<usize>,
+    pub max_query_terms: usize,
+    pub code_pattern: bool,
+}
+
+/// The defaults of `probe search`: the current directory, with no queries, limits or filters
+impl Default for SearchOptions<'_> {
+    fn default() -> Self {
+        Self {
+            path: Path::new("."),
+            queries: &[],
+            files_only: false,
+            custom_ignores: &[],
+            exclude_filenames: false,
+            reranker: "bm25",
+            frequency_search: true,
+            max_results: None,
+            max_bytes: None,
+            max_tokens: None,
+            allow_tests: false,
+            exact: false,
+            no_merge: false,
+            merge_threshold: None,
+            dry_run: false,
+            session: None,
+            report_skipped: false,
+            tests_for: None,
+            usages: false,
+            path_style: "native",
+            follow_symlinks: false,
+            strict: false,
+            roots: &[],
+            max_depth: None,
+            min_depth: None,
+            newer_than: None,
+            older_than: None,
+            min_size: None,
+            max_size: None,
+            author: None,
+            snippet_window: None,
+            oversize_strategy: "full",
+            oversize_threshold: 300,
+            case_fold: false,
+            code_terms: None,
+            acronyms: false,
+            preproc_context: false,
+            embedded: false,
+            force_language: &[],
+            within_session: None,
+            exclude_blocks: &[],
+            pin_blocks: &[],
+            filename_weight: 1.0,
+            include_dir_context: false,
+            deprecated_penalty: 0.0,
+            visibility: "any",
+            kinds: &[],
+            sort: "score",
+            candidate_files: None,
+            max_line_length: 2000,
+            max_matches_per_file: 10000,
+            max_total_matches: None,
+            max_query_terms: 128,
+            code_pattern: false,
+        }
+    }
+}
+
+impl SearchOptions<'_> {
+    /// The depth, modification time and size limits for walking the search path
+    pub fn file_filters(&self) -> FileFilters {
+        FileFilters {
+            min_depth: self.min_depth,
+            max_depth: self.max_depth,
+            newer_than: self.newer_than,
+            older_than: self.older_than,
+            min_size: self.min_size,
+            max_size: self.max_size,
+        }
+    }
+
+    /// The line length and match count limits for the pattern scan
+    pub fn match_guards(&self) -> MatchGuards {
+        MatchGuards {
+            max_line_length: self.max_line_length,
+            max_matches_per_file: self.max_matches_per_file,
+            max_total_matches: self.max_total_matches,
+        }
+    }
+}
+
+/// Owned copy of [`SearchOptions`], for searches that run on another thread
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct SearchRequest {
+    pub path: PathBuf,
+    pub queries: Vec<String>,
+    pub files_only: bool,
+    pub custom_ignores: Vec<String>,
+    pub exclude_filenames: bool,
+    pub reranker: String,
+    pub frequency_search: bool,
+    pub max_results: Option<usize>,
+    pub max_bytes: Option<usize>,
+    pub max_tokens: Option<usize>,
+    pub allow_tests: bool,
+    pub exact: bool,
+    pub no_merge: bool,
+    pub merge_threshold: Option<usize>,
+    pub dry_run: bool,
+    pub session: Option<String>,
+    pub report_skipped: bool,
+    pub tests_for: Option<String>,
+    pub usages: bool,
+    pub path_style: String,
+    pub follow_symlinks: bool,
+    pub strict: bool,
+    pub roots: Vec<SearchRoot>,
+    pub max_depth: Option<usize>,
+    pub min_depth: Option<usize>,
+    pub newer_than: Option<Duration>,
+    pub older_than: Option<Duration>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    pub author: Option<String>,
+    pub snippet_window: Option<usize>,
+    pub oversize_strategy: String,
+    pub oversize_threshold: usize,
+    pub case_fold: bool,
+    pub code_terms: Option<String>,
+    pub acronyms: bool,
+    pub preproc_context: bool,
+    pub embedded: bool,
+    pub force_language: Vec<String>,
+    pub within_session: Option<String>,
+    pub exclude_blocks: Vec<String>,
+    pub pin_blocks: Vec<String>,
+    pub filename_weight: f64,
+    pub include_dir_context: bool,
+    pub deprecated_penalty: f64,
+    pub visibility: String,
+    pub kinds: Vec<String>,
+    pub sort: String,
+    pub candidate_files: Option<usize>,
+    pub max_line_length: usize,
+    pub max_matches_per_file: usize,
+    pub max_total_matches: Option<usize>,
+    pub max_query_terms: usize,
+    pub code_pattern: bool,
+}
+
+#[allow(dead_code)]
+impl SearchRequest {
+    /// Borrow the request as search options
+    pub fn as_options(&self) -> SearchOptions<'_> {
+        SearchOptions {
+            path: &self.path,
+            queries: &self.queries,
+            files_only: self.files_only,
+            custom_ignores: &self.custom_ignores,
+            exclude_filenames: self.exclude_filenames,
+            reranker: &self.reranker,
+            frequency_search: self.frequency_search,
+            max_results: self.max_results,
+            max_bytes: self.max_bytes,
+            max_tokens: self.max_tokens,
+            allow_tests: self.allow_tests,
+            exact: self.exact,
+            no_merge: self.no_merge,
+            merge_threshold: self.merge_threshold,
+            dry_run: self.dry_run,
+            session: self.session.as_deref(),
+            report_skipped: self.report_skipped,
+            tests_for: self.tests_for.as_deref(),
+            usages: self.usages,
+            path_style: &self.path_style,
+            follow_symlinks: self.follow_symlinks,
+            strict: self.strict,
+            roots: &self.roots,
+            max_depth: self.max_depth,
+            min_depth: self.min_depth,
+            newer_than: self.newer_than,
+            older_than: self.older_than,
+            min_size: self.min_size,
+            max_size: self.max_size,
+            author: self.author.as_deref(),
+            snippet_window: self.snippet_window,
+            oversize_strategy: &self.oversize_strategy,
+            oversize_threshold: self.oversize_threshold,
+            case_fold: self.case_fold,
+            code_terms: self.code_terms.as_deref(),
+            acronyms: self.acronyms,
+            preproc_context: self.preproc_context,
+            embedded: self.embedded,
+            force_language: &self.force_language,
+            within_session: self.within_session.as_deref(),
+            exclude_blocks: &self.exclude_blocks,
+            pin_blocks: &self.pin_blocks,
+            filename_weight: self.filename_weight,
+            include_dir_context: self.include_dir_context,
+            deprecated_penalty: self.deprecated_penalty,
+            visibility: self.visibility.as_str(),
+            kinds: &self.kinds,
+            sort: &self.sort,
+            candidate_files: self.candidate_files,
+            max_line_length: self.max_line_length,
+            max_matches_per_file: self.max_matches_per_file,
+            max_total_matches: self.max_total_matches,
+            max_query_terms: self.max_query_terms,
+            code_pattern: self.code_pattern,
+        }
+    }
+}
+
+impl From<&SearchOptions<'_>> for SearchRequest {
+    fn from(options: &SearchOptions<'_>) -> Self {
+        Self {
+            path: options.path.to_path_buf(),
+            queries: options.queries.to_vec(),
+            files_only: options.files_only,
+            custom_ignores: options.custom_ignores.to_vec(),
+            exclude_filenames: options.exclude_filenames,
+            reranker: options.reranker.to_string(),
+            frequency_search: options.frequency_search,
+            max_results: options.max_results,
+            max_bytes: options.max_bytes,
+            max_tokens: options.max_tokens,
+            allow_tests: options.allow_tests,
+            exact: options.exact,
+            no_merge: options.no_merge,
+            merge_threshold: options.merge_threshold,
+            dry_run: options.dry_run,
+            session: options.session.map(str::to_string),
+            report_skipped: options.report_skipped,
+            tests_for: options.tests_for.map(str::to_string),
+            usages: options.usages,
+            path_style: options.path_style.to_string(),
+            follow_symlinks: options.follow_symlinks,
+            strict: options.strict,
+            roots: options.roots.to_vec(),
+            max_depth: options.max_depth,
+            min_depth: options.min_depth,
+            newer_than: options.newer_than,
+            older_than: options.older_than,
+            min_size: options.min_size,
+            max_size: options.max_size,
+            author: options.author.map(str::to_string),
+            snippet_window: options.snippet_window,
+            oversize_strategy: options.oversize_strategy.to_string(),
+            oversize_threshold: options.oversize_threshold,
+            case_fold: options.case_fold,
+            code_terms: options.code_terms.map(str::to_string),
+            acronyms: options.acronyms,
+            preproc_context: options.preproc_context,
+            embedded: options.embedded,
+            force_language: options.force_language.to_vec(),
+            within_session: options.within_session.map(str::to_string),
+            exclude_blocks: options.exclude_blocks.to_vec(),
+            pin_blocks: options.pin_blocks.to_vec(),
+            filename_weight: options.filename_weight,
+            include_dir_context: options.include_dir_context,
+            deprecated_penalty: options.deprecated_penalty,
+            visibility: options.visibility.to_string(),
+            kinds: options.kinds.to_vec(),
+            sort: options.sort.to_string(),
+            candidate_files: options.candidate_files,
+            max_line_length: options.max_line_length,
+            max_matches_per_file: options.max_matches_per_file,
+            max_total_matches: options.max_total_matches,
+            max_query_terms: options.max_query_terms,
+            code_pattern: options.code_pattern,
+        }
+    }
 }
@@ -1,7 +1,10 @@
 use anyhow::Result;
-use std::path::Path;
+use std::io::Write;
 
-use crate::models::SearchResult;
+use crate::models::{FileError, SearchResult, UsageKind};
+use crate::path_encoding::encode_result_path;
+use crate::search::content_hash::results_fingerprint;
+use crate::search::output_sink::{OutputSink, SearchOutput, TerminalSink};
 use crate::search::query::QueryPlan;
 use crate::search::search_tokens::count_tokens;
 
@@ -11,11 +14,51 @@ pub fn format_and_print_search_results(
     dry_run: bool,
     format: &str,
     query_plan: Option<&QueryPlan>,
+    errors: &[FileError],
+    corpus_fingerprint: Option<&str>,
 ) {
+    let output = SearchOutput {
+        results,
+        errors,
+        query_plan,
+        dry_run,
+        corpus_fingerprint,
+    };
+    if let Err(e) = TerminalSink::new(format).emit(&output) {
+        eprintln!("Error writing {} output: {}", format, e);
+    }
+}
+
+/// Function to put results in the order they are shown
+///
+/// Results without a file name are left out. With `--usages`, the definition comes first,
+/// then the usages, then anything else.
+pub(crate) fn display_order(results: &[SearchResult]) -> Vec<&SearchResult> {
+    let mut ordered: Vec<&SearchResult> = results.iter().filter(|r| !r.file.is_empty()).collect();
+    if ordered.iter().any(|r| r.usage_kind.is_some()) {
+        ordered.sort_by_key(|r| usage_section_order(r.usage_kind));
+    }
+    ordered
+}
+
+/// Function to write search results in the specified format to any writer
+///
+/// Colors are only used when writing to a terminal; callers writing to a file
+/// turn them off with `colored::control::set_override(false)`.
+pub fn write_search_results(
+    out: &mut dyn Write,
+    results: &[SearchResult],
+    dry_run: bool,
+    format: &str,
+    query_plan: Option<&QueryPlan>,
+    errors: &[FileError],
+    corpus_fingerprint: Option<&str>,
+) -> Result<()> {
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
 
-    // Count valid results (with non-empty file names)
-    let valid_results: Vec<&SearchResult> = results.iter().filter(|r| !r.file.is_empty()).collect();
+    // Valid results (with non-empty file names), in display order
+    let valid_results = display_order(results);
+    let group_by_usage = valid_results.iter().any(|r| r.usage_kind.is_some());
 
     // Check if terminal supports colors and if output is being piped
     let use_color = match format {
@@ -26,24 +69,32 @@ pub fn format_and_print_search_results(
     // Handle different output formats
     match format {
         "color" if use_color => {
-            format_and_print_color_results(&valid_results, dry_run, query_plan, debug_mode);
+            format_and_print_color_results(out, &valid_results, dry_run, query_plan, debug_mode)?;
         }
         "json" => {
-            if let Err(e) = format_and_print_json_results(&valid_results) {
-                eprintln!("Error formatting JSON: {}", e);
-            }
-            return; // Skip the summary output at the end
+            // Skip the summary output at the end
+            return format_and_print_json_results(
+                out,
+                &valid_results,
+                errors,
+                corpus_fingerprint,
+            );
         }
         "xml" => {
-            if let Err(e) = format_and_print_xml_results(&valid_results) {
-                eprintln!("Error formatting XML: {}", e);
-            }
-            return; // Skip the summary output at the end
+            // Skip the summary output at the end
+            return format_and_print_xml_results(out, &valid_results, errors, corpus_fingerprint);
         }
         _ => {
             // Default format (terminal)
+            let mut current_section = None;
             for result in &valid_results {
-                let file_path = Path::new(&result.file);
+                if group_by_usage && current_section != Some(result.usage_kind) {
+                    current_section = Some(result.usage_kind);
+                    writeln!(out, "## {}", usage_section_title(result.usage_kind))?;
+                    writeln!(out)?;
+                }
+
+                let file_path = result.path();
                 let extension = file_path
                     .extension()
                     .and_then(|ext| ext.to_str())
@@ -53,139 +104,162 @@ pub fn format_and_print_search_results(
                 if dry_run {
                     // In dry-run mode, only print file names and line numbers
                     if is_full_file {
-                        println!("File: {}", result.file);
+                        writeln!(out, "File: {}", result.file)?;
                     } else {
-                        println!(
+                        writeln!(
+                            out,
                             "File: {}, Lines: {}-{}",
                             result.file, result.lines.0, result.lines.1
-                        );
+                        )?;
                     }
                 } else {
                     // Normal mode with full content
                     if is_full_file {
-                        println!("File: {}", result.file);
-                        println!("```{}", extension);
-                        println!("{}", result.code);
-                        println!("```");
+                        writeln!(out, "File: {}", result.file)?;
+                        writeln!(out, "```{}", extension)?;
+                        writeln!(out, "{}", result.code)?;
+                        writeln!(out, "```")?;
                     } else {
-                        println!("File: {}", result.file);
-                        println!("Lines: {}-{}", result.lines.0, result.lines.1);
-                        println!("```{}", extension);
-                        println!("{}", result.code);
-                        println!("```");
+                        writeln!(out, "File: {}", result.file)?;
+                        writeln!(out, "Lines: {}-{}", result.lines.0, result.lines.1)?;
+                        writeln!(out, "```{}", extension)?;
+                        writeln!(out, "{}", result.code)?;
+                        writeln!(out, "```")?;
                     }
                 }
                 if debug_mode {
                     if let Some(rank) = result.rank {
                         // Add a display order field to show the actual ordering of results
-                        println!(
+                        writeln!(
+                            out,
                             "Display Order: {}",
                             results
                                 .iter()
                                 .position(|r| r.file == result.file && r.lines == result.lines)
                                 .unwrap_or(0)
                                 + 1
-                        );
+                        )?;
 
-                        println!("Rank: {}", rank);
+                        writeln!(out, "Rank: {}", rank)?;
 
                         if let Some(score) = result.score {
-                            println!("Combined Score: {:.4}", score);
+                            writeln!(out, "Combined Score: {:.4}", score)?;
+                        }
+
+                        if let Some(normalized_score) = result.normalized_score {
+                            writeln!(out, "Normalized Score: {:.4}", normalized_score)?;
                         }
 
                         // Display the combined score rank if available, otherwise calculate it
                         if let Some(combined_rank) = result.combined_score_rank {
-                            println!("Combined Score Rank: {}", combined_rank);
+                            writeln!(out, "Combined Score Rank: {}", combined_rank)?;
                         } else {
                             // Fall back to the old behavior if the field isn't set
-                            println!("Combined Score Rank: {}", rank);
+                            writeln!(out, "Combined Score Rank: {}", rank)?;
                         }
 
                         if let Some(tfidf_score) = result.tfidf_score {
-                            println!("TF-IDF Score: {:.4}", tfidf_score);
+                            writeln!(out, "TF-IDF Score: {:.4}", tfidf_score)?;
                         }
 
                         if let Some(tfidf_rank) = result.tfidf_rank {
-                            println!("TF-IDF Rank: {}", tfidf_rank);
+                            writeln!(out, "TF-IDF Rank: {}", tfidf_rank)?;
                         }
 
                         if let Some(bm25_score) = result.bm25_score {
-                            println!("BM25 Score: {:.4}", bm25_score);
+                            writeln!(out, "BM25 Score: {:.4}", bm25_score)?;
                         }
 
                         if let Some(bm25_rank) = result.bm25_rank {
-                            println!("BM25 Rank: {}", bm25_rank);
+                            writeln!(out, "BM25 Rank: {}", bm25_rank)?;
                         }
 
                         // Display Hybrid 2 score and rank with more prominence
                         if let Some(new_score) = result.new_score {
-                            println!("Hybrid 2 Score: {:.4}", new_score);
+                            writeln!(out, "Hybrid 2 Score: {:.4}", new_score)?;
                         }
 
                         if let Some(hybrid2_rank) = result.hybrid2_rank {
-                            println!("Hybrid 2 Rank: {}", hybrid2_rank);
+                            writeln!(out, "Hybrid 2 Rank: {}", hybrid2_rank)?;
                         } else if result.new_score.is_some() {
-                            println!("Hybrid 2 Rank: N/A");
+                            writeln!(out, "Hybrid 2 Rank: N/A")?;
                         }
 
                         if let Some(file_unique_terms) = result.file_unique_terms {
-                            println!("File Unique Terms: {}", file_unique_terms);
+                            writeln!(out, "File Unique Terms: {}", file_unique_terms)?;
                         }
 
                         if let Some(file_total_matches) = result.file_total_matches {
-                            println!("File Total Matches: {}", file_total_matches);
+                            writeln!(out, "File Total Matches: {}", file_total_matches)?;
                         }
 
                         if let Some(file_match_rank) = result.file_match_rank {
-                            println!("File Match Rank: {}", file_match_rank);
+                            writeln!(out, "File Match Rank: {}", file_match_rank)?;
                         }
 
                         if let Some(block_unique_terms) = result.block_unique_terms {
-                            println!("Block Unique Terms: {}", block_unique_terms);
+                            writeln!(out, "Block Unique Terms: {}", block_unique_terms)?;
                         }
 
                         if let Some(block_total_matches) = result.block_total_matches {
-                            println!("Block Total Matches: {}", block_total_matches);
+                            writeln!(out, "Block Total Matches: {}", block_total_matches)?;
                         }
 
-                        println!("Type: {}", result.node_type);
+                        writeln!(out, "Type: {}", result.node_type)?;
                     }
                 }
             }
         }
     }
 
-    println!("Found {} search results", valid_results.len());
+    writeln!(out, "Found {} search results", valid_results.len())?;
 
     let total_bytes: usize = valid_results.iter().map(|r| r.code.len()).sum();
     let total_tokens: usize = valid_results.iter().map(|r| count_tokens(&r.code)).sum();
-    println!("Total bytes returned: {}", total_bytes);
-    println!("Total tokens returned: {}", total_tokens);
+    writeln!(out, "Total bytes returned: {}", total_bytes)?;
+    writeln!(out, "Total tokens returned: {}", total_tokens)?;
+    Ok(())
 }
 
 /// Format and print search results with color highlighting for matching words
 fn format_and_print_color_results(
+    out: &mut dyn Write,
     results: &[&SearchResult],
     dry_run: bool,
     query_plan: Option<&QueryPlan>,
     debug_mode: bool,
-) {
+) -> Result<()> {
     use colored::*;
     use regex::Regex;
 
     if results.is_empty() {
-        println!("No results found.");
-        return;
+        writeln!(out, "No results found.")?;
+        return Ok(());
     }
 
     // Print a header with the number of results
-    println!("{}", format!("Found {} results", results.len()).bold());
-    println!();
+    writeln!(out, "{}", format!("Found {} results", results.len()).bold())?;
+    writeln!(out)?;
+
+    let group_by_usage = results.iter().any(|r| r.usage_kind.is_some());
+    let mut current_section = None;
 
     // Print the results
     for (index, result) in results.iter().enumerate() {
+        if group_by_usage && current_section != Some(result.usage_kind) {
+            current_section = Some(result.usage_kind);
+            writeln!(
+                out,
+                "{}",
+                format!("== {} ==", usage_section_title(result.usage_kind))
+                    .bold()
+                    .magenta()
+            )?;
+            writeln!(out)?;
+        }
+
         // Get file extension
-        let file_path = Path::new(&result.file);
+        let file_path = result.path();
         let extension = file_path
             .extension()
             .and_then(|ext| ext.to_str())
@@ -195,41 +269,91 @@ fn format_and_print_color_results(
         let is_full_file = result.node_type == "file";
 
         // Print result number
-        println!(
+        writeln!(
+            out,
             "{} {}",
             "Result".bold().blue(),
             format!("#{}", index + 1).bold().blue()
-        );
+        )?;
 
         // Print the file path and node info with color
+        let via_symlink = if result.via_symlink {
+            " (via symlink)".dimmed().to_string()
+        } else {
+            String::new()
+        };
         if is_full_file {
-            println!("{} {}", "File:".bold().green(), result.file.yellow());
+            writeln!(
+                out,
+                "{} {}{}",
+                "File:".bold().green(),
+                result.file.yellow(),
+                via_symlink
+            )?;
         } else {
-            println!(
-                "{} {} ({})",
+            let partial_parse = if result.partial_parse {
+                ", partial parse".dimmed().to_string()
+            } else {
+                String::new()
+            };
+            let deprecated = if result.deprecated {
+                ", deprecated".red().to_string()
+            } else {
+                String::new()
+            };
+            let kind = match result.kind {
+                Some(kind) => format!(", {}", kind.as_str()).magenta().to_string(),
+                None => String::new(),
+            };
+            writeln!(
+                out,
+                "{} {}{} ({}{}{}{})",
                 "File:".bold().green(),
                 result.file.yellow(),
-                result.node_type.cyan()
-            );
-            println!(
+                via_symlink,
+                result.node_type.cyan(),
+                kind,
+                partial_parse,
+                deprecated
+            )?;
+            writeln!(
+                out,
                 "{} {}-{}",
                 "Lines:".bold().green(),
                 result.lines.0,
                 result.lines.1
-            );
+            )?;
+            if let Some(embedded) = &result.embedded {
+                writeln!(
+                    out,
+                    "{} {} {}-{}",
+                    "Inside:".bold().green(),
+                    embedded.host_node_type.cyan(),
+                    embedded.host_lines.0,
+                    embedded.host_lines.1
+                )?;
+            }
         }
 
         // Print additional debug information if in debug mode
         if debug_mode {
             // Print the same debug info that would be shown in standard mode
             if let Some(keywords) = &result.matched_keywords {
-                println!("{} {:?}", "Matched Keywords:".bold().green(), keywords);
+                writeln!(out, "{} {:?}", "Matched Keywords:".bold().green(), keywords)?;
             }
             if let Some(score) = result.score {
-                println!("{} {:.4}", "Score:".bold().green(), score);
+                writeln!(out, "{} {:.4}", "Score:".bold().green(), score)?;
+            }
+            if let Some(normalized_score) = result.normalized_score {
+                writeln!(
+                    out,
+                    "{} {:.4}",
+                    "Normalized Score:".bold().green(),
+                    normalized_score
+                )?;
             }
             if let Some(query_plan) = query_plan {
-                println!("{} {:?}", "Query Plan:".bold().green(), query_plan);
+                writeln!(out, "{} {:?}", "Query Plan:".bold().green(), query_plan)?;
             }
         }
 
@@ -259,25 +383,29 @@ fn format_and_print_color_results(
             "sql" => "sql",
             "kt" | "kts" => "kotlin",
             "swift" => "swift",
+            "m" | "mm" => "objectivec",
             "scala" => "scala",
             "dart" => "dart",
+            "nix" => "nix",
+            "ml" | "mli" => "ocaml",
             "ex" | "exs" => "elixir",
             "hs" => "haskell",
             "clj" => "clojure",
             "lua" => "lua",
+            "graphql" | "gql" => "graphql",
             "r" => "r",
             "pl" | "pm" => "perl",
             "proto" => "protobuf",
             _ => "",
         };
 
-        println!("{}", "Code:".bold().magenta());
+        writeln!(out, "{}", "Code:".bold().magenta())?;
 
         // Print the code with syntax highlighting
         if !language.is_empty() {
-            println!("{}", format!("```{}", language).cyan());
+            writeln!(out, "{}", format!("```{}", language).cyan())?;
         } else {
-            println!("{}", "```".cyan());
+            writeln!(out, "{}", "```".cyan())?;
         }
 
         // Generate patterns from the matched keywords in the search result
@@ -363,98 +491,131 @@ fn format_and_print_color_results(
 
             // Print the line (highlighted or original if no matches)
             if !merged_matches.is_empty() {
-                println!("{}", output_line);
+                writeln!(out, "{}", output_line)?;
             } else {
-                println!("{}", line);
+                writeln!(out, "{}", line)?;
             }
         }
 
-        println!();
+        writeln!(out)?;
 
         // Print a separator between results
         if index < results.len() - 1 {
-            println!();
-            println!("{}", "─".repeat(50).cyan());
-            println!();
+            writeln!(out)?;
+            writeln!(out, "{}", "─".repeat(50).cyan())?;
+            writeln!(out)?;
         }
 
         if debug_mode {
             if let Some(rank) = result.rank {
                 // Add a display order field to show the actual ordering of results
-                println!(
+                writeln!(
+                    out,
                     "Display Order: {}",
                     results
                         .iter()
                         .position(|r| r.file == result.file && r.lines == result.lines)
                         .unwrap_or(0)
                         + 1
-                );
+                )?;
 
-                println!("Rank: {}", rank);
+                writeln!(out, "Rank: {}", rank)?;
 
                 if let Some(score) = result.score {
-                    println!("Combined Score: {:.4}", score);
+                    writeln!(out, "Combined Score: {:.4}", score)?;
+                }
+
+                if let Some(normalized_score) = result.normalized_score {
+                    writeln!(out, "Normalized Score: {:.4}", normalized_score)?;
                 }
 
                 // Display the combined score rank if available, otherwise calculate it
                 if let Some(combined_rank) = result.combined_score_rank {
-                    println!("Combined Score Rank: {}", combined_rank);
+                    writeln!(out, "Combined Score Rank: {}", combined_rank)?;
                 } else {
                     // Fall back to the old behavior if the field isn't set
-                    println!("Combined Score Rank: {}", rank);
+                    writeln!(out, "Combined Score Rank: {}", rank)?;
                 }
 
                 if let Some(tfidf_score) = result.tfidf_score {
-                    println!("TF-IDF Score: {:.4}", tfidf_score);
+                    writeln!(out, "TF-IDF Score: {:.4}", tfidf_score)?;
                 }
 
                 if let Some(tfidf_rank) = result.tfidf_rank {
-                    println!("TF-IDF Rank: {}", tfidf_rank);
+                    writeln!(out, "TF-IDF Rank: {}", tfidf_rank)?;
                 }
 
                 if let Some(bm25_score) = result.bm25_score {
-                    println!("BM25 Score: {:.4}", bm25_score);
+                    writeln!(out, "BM25 Score: {:.4}", bm25_score)?;
                 }
 
                 if let Some(bm25_rank) = result.bm25_rank {
-                    println!("BM25 Rank: {}", bm25_rank);
+                    writeln!(out, "BM25 Rank: {}", bm25_rank)?;
                 }
 
                 // Display Hybrid 2 score and rank with more prominence
                 if let Some(new_score) = result.new_score {
-                    println!("Hybrid 2 Score: {:.4}", new_score);
+                    writeln!(out, "Hybrid 2 Score: {:.4}", new_score)?;
                 }
 
                 if let Some(hybrid2_rank) = result.hybrid2_rank {
-                    println!("Hybrid 2 Rank: {}", hybrid2_rank);
+                    writeln!(out, "Hybrid 2 Rank: {}", hybrid2_rank)?;
                 } else if result.new_score.is_some() {
-                    println!("Hybrid 2 Rank: N/A");
+                    writeln!(out, "Hybrid 2 Rank: N/A")?;
                 }
 
                 if let Some(file_unique_terms) = result.file_unique_terms {
-                    println!("File Unique Terms: {}", file_unique_terms);
+                    writeln!(out, "File Unique Terms: {}", file_unique_terms)?;
                 }
 
                 if let Some(file_total_matches) = result.file_total_matches {
-                    println!("File Total Matches: {}", file_total_matches);
+                    writeln!(out, "File Total Matches: {}", file_total_matches)?;
                 }
 
                 if let Some(file_match_rank) = result.file_match_rank {
-                    println!("File Match Rank: {}", file_match_rank);
+                    writeln!(out, "File Match Rank: {}", file_match_rank)?;
                 }
 
                 if let Some(block_unique_terms) = result.block_unique_terms {
-                    println!("Block Unique Terms: {}", block_unique_terms);
+                    writeln!(out, "Block Unique Terms: {}", block_unique_terms)?;
                 }
 
                 if let Some(block_total_matches) = result.block_total_matches {
-                    println!("Block Total Matches: {}", block_total_matches);
+                    writeln!(out, "Block Total Matches: {}", block_total_matches)?;
                 }
 
-                println!("Type: {}", result.node_type);
+                writeln!(out, "Type: {}", result.node_type)?;
             }
         }
     }
+
+    Ok(())
+}
+
+/// Helper function to order usage sections: definition, usages, other matches
+fn usage_section_order(kind: Option<UsageKind>) -> u8 {
+    match kind {
+        Some(UsageKind::Definition) => 0,
+        Some(UsageKind::Usage) => 1,
+        None => 2,
+    }
+}
+
+/// Helper function to get the section title for a usage kind
+fn usage_section_title(kind: Option<UsageKind>) -> &'static str {
+    match kind {
+        Some(UsageKind::Definition) => "Definition",
+        Some(UsageKind::Usage) => "Usages",
+        None => "Other matches",
+    }
+}
+
+/// Helper function to get the machine-readable name of a usage kind
+fn usage_kind_name(kind: UsageKind) -> &'static str {
+    match kind {
+        UsageKind::Definition => "definition",
+        UsageKind::Usage => "usage",
+    }
 }
 
 /// Helper function to escape XML special characters
@@ -466,46 +627,152 @@ fn escape_xml(s: &str) -> String {
         .replace("'", "&apos;")
 }
 
-/// Format and print search results in JSON format
-fn format_and_print_json_results(results: &[&SearchResult]) -> Result<()> {
-    // Create a simplified version of the results for JSON output
-    #[derive(serde::Serialize)]
-    struct JsonResult<'a> {
-        file: &'a str,
-        lines: [usize; 2],
-        node_type: &'a str,
-        code: &'a str,
-        // Include other relevant fields
-        matched_keywords: Option<&'a Vec<String>>,
-        score: Option<f64>,
-        tfidf_score: Option<f64>,
-        bm25_score: Option<f64>,
-        file_unique_terms: Option<usize>,
-        file_total_matches: Option<usize>,
-        block_unique_terms: Option<usize>,
-        block_total_matches: Option<usize>,
+/// A simplified version of a search result, shared by JSON and template output
+#[derive(serde::Serialize)]
+struct JsonResult<'a> {
+    file: String,
+    // Set to "percent" when `file` is a percent-encoded non-UTF-8 path
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_encoding: Option<&'static str>,
+    lines: [usize; 2],
+    node_type: &'a str,
+    // Name of the symbol the block defines, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symbol_name: Option<&'a str>,
+    // Language of the file, when detected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<&'a str>,
+    // How the block boundaries were found: "ast", "heuristic" or "whole_file"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extraction_confidence: Option<&'static str>,
+    // "definition", "call_site", "import", "comment", "string" or "test", when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<&'static str>,
+    // Line count, nesting depth and cyclomatic estimate of the block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    complexity: Option<JsonComplexity>,
+    code: &'a str,
+    // Include other relevant fields
+    matched_keywords: Option<&'a Vec<String>>,
+    score: Option<f64>,
+    normalized_score: Option<f64>,
+    tfidf_score: Option<f64>,
+    bm25_score: Option<f64>,
+    file_unique_terms: Option<usize>,
+    file_total_matches: Option<usize>,
+    block_unique_terms: Option<usize>,
+    block_total_matches: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    merged_from: Option<Vec<JsonMergedBlock<'a>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage_kind: Option<&'static str>,
+    // Only present when the file was reached through a symbolic link
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    via_symlink: bool,
+    // Only present when the block contains syntax errors the parser recovered from
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    partial_parse: bool,
+    // Only present when the block carries a deprecation marker
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    deprecated: bool,
+    // Only present for SQL or HTML string literals reported with --embedded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    embedded: Option<JsonEmbedded<'a>>,
+    // Hash of the file content when it was searched
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_hash: Option<&'a str>,
+}
+
+/// Position of an embedded string literal within its enclosing block
+#[derive(serde::Serialize)]
+struct JsonEmbedded<'a> {
+    language: &'static str,
+    host_lines: [usize; 2],
+    host_node_type: &'a str,
+}
+
+/// Complexity metrics of a block
+#[derive(serde::Serialize)]
+struct JsonComplexity {
+    lines: usize,
+    nesting_depth: usize,
+    cyclomatic: usize,
+}
+
+/// Provenance entry for blocks that were folded into a merged result
+#[derive(serde::Serialize)]
+struct JsonMergedBlock<'a> {
+    block_id: Option<usize>,
+    lines: [usize; 2],
+    node_type: &'a str,
+    rank: Option<usize>,
+    score: Option<f64>,
+}
+
+fn to_json_result(r: &SearchResult) -> JsonResult<'_> {
+    let encoded = encode_result_path(&r.file, r.file_path.as_deref());
+    JsonResult {
+        file: encoded.value,
+        file_encoding: encoded.encoding,
+        lines: [r.lines.0, r.lines.1],
+        node_type: &r.node_type,
+        symbol_name: r.symbol_name.as_deref(),
+        language: r.language.as_deref(),
+        extraction_confidence: r
+            .extraction_confidence
+            .map(|confidence| confidence.as_str()),
+        kind: r.kind.map(|kind| kind.as_str()),
+        complexity: r.complexity.map(|metrics| JsonComplexity {
+            lines: metrics.lines,
+            nesting_depth: metrics.nesting_depth,
+            cyclomatic: metrics.cyclomatic,
+        }),
+        code: &r.code,
+        matched_keywords: r.matched_keywords.as_ref(),
+        score: r.score,
+        normalized_score: r.normalized_score,
+        tfidf_score: r.tfidf_score,
+        bm25_score: r.bm25_score,
+        file_unique_terms: r.file_unique_terms,
+        file_total_matches: r.file_total_matches,
+        block_unique_terms: r.block_unique_terms,
+        block_total_matches: r.block_total_matches,
+        merged_from: r.merged_from.as_ref().map(|blocks| {
+            blocks
+                .iter()
+                .map(|b| JsonMergedBlock {
+                    block_id: b.block_id,
+                    lines: [b.lines.0, b.lines.1],
+                    node_type: &b.node_type,
+                    rank: b.rank,
+                    score: b.score,
+                })
+                .collect()
+        }),
+        usage_kind: r.usage_kind.map(usage_kind_name),
+        via_symlink: r.via_symlink,
+        partial_parse: r.partial_parse,
+        deprecated: r.deprecated,
+        embedded: r.embedded.as_ref().map(|embedded| JsonEmbedded {
+            language: embedded.language.as_str(),
+            host_lines: [embedded.host_lines.0, embedded.host_lines.1],
+            host_node_type: &embedded.host_node_type,
+        }),
+        content_hash: r.content_hash.as_deref(),
     }
+}
 
-    let json_results: Vec<JsonResult> = results
-        .iter()
-        .map(|r| JsonResult {
-            file: &r.file,
-            lines: [r.lines.0, r.lines.1],
-            node_type: &r.node_type,
-            code: &r.code,
-            matched_keywords: r.matched_keywords.as_ref(),
-            score: r.score,
-            tfidf_score: r.tfidf_score,
-            bm25_score: r.bm25_score,
-            file_unique_terms: r.file_unique_terms,
-            file_total_matches: r.file_total_matches,
-            block_unique_terms: r.block_unique_terms,
-            block_total_matches: r.block_total_matches,
-        })
-        .collect();
+/// Format and print search results in JSON format
+fn format_and_print_json_results(
+    out: &mut dyn Write,
+    results: &[&SearchResult],
+    errors: &[FileError],
+    corpus_fingerprint: Option<&str>,
+) -> Result<()> {
+    let json_results: Vec<JsonResult> = results.iter().map(|r| to_json_result(r)).collect();
 
     // Create a wrapper object with results and summary
-    let wrapper = serde_json::json!({
+    let mut wrapper = serde_json::json!({
         "results": json_results,
         "summary": {
             "count": results.len(),
@@ -513,90 +780,341 @@ fn format_and_print_json_results(results: &[&SearchResult]) -> Result<()> {
             "total_tokens": results.iter().map(|r| count_tokens(&r.code)).sum::<usize>(),
         }
     });
+    if let Some(fingerprint) = results_fingerprint(results.iter().copied()) {
+        wrapper["summary"]["results_fingerprint"] = serde_json::Value::from(fingerprint);
+    }
+    if let Some(fingerprint) = corpus_fingerprint {
+        wrapper["summary"]["corpus_fingerprint"] = serde_json::Value::from(fingerprint);
+    }
+
+    // Files that could not be read, only present when there were any
+    if !errors.is_empty() {
+        let mut by_kind = serde_json::Map::new();
+        for error in errors {
+            let count = by_kind
+                .entry(error.kind.as_str())
+                .or_insert(serde_json::Value::from(0));
+            *count = serde_json::Value::from(count.as_u64().unwrap_or(0) + 1);
+        }
+        let files: Vec<serde_json::Value> = errors
+            .iter()
+            .map(|e| {
+                serde_json::json!({
+                    "path": e.path.display().to_string(),
+                    "kind": e.kind.as_str(),
+                    "message": e.message,
+                })
+            })
+            .collect();
+        wrapper["errors"] = serde_json::json!({
+            "count": errors.len(),
+            "by_kind": by_kind,
+            "files": files,
+        });
+    }
 
-    println!("{}", serde_json::to_string_pretty(&wrapper)?);
+    writeln!(out, "{}", serde_json::to_string_pretty(&wrapper)?)?;
     Ok(())
 }
 
+/// Function to render search results with a user-supplied template
+///
+/// Templates use TinyTemplate syntax. The context has a `results` list, where each
+/// entry carries the JSON output fields plus `line_start`, `line_end` and
+/// `extension`, and a `summary` with `count`, `total_bytes`, `total_tokens`,
+/// `results_fingerprint` and `corpus_fingerprint`.
+/// Values are inserted as-is, without HTML escaping.
+pub fn render_template_results(
+    results: &[SearchResult],
+    template: &str,
+    corpus_fingerprint: Option<&str>,
+) -> Result<String> {
+    #[derive(serde::Serialize)]
+    struct TemplateResult<'a> {
+        #[serde(flatten)]
+        result: JsonResult<'a>,
+        line_start: usize,
+        line_end: usize,
+        extension: String,
+    }
+
+    #[derive(serde::Serialize)]
+    struct TemplateSummary<'a> {
+        count: usize,
+        total_bytes: usize,
+        total_tokens: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        results_fingerprint: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        corpus_fingerprint: Option<&'a str>,
+    }
+
+    #[derive(serde::Serialize)]
+    struct TemplateContext<'a> {
+        results: Vec<TemplateResult<'a>>,
+        summary: TemplateSummary<'a>,
+    }
+
+    let valid_results: Vec<&SearchResult> = results.iter().filter(|r| !r.file.is_empty()).collect();
+
+    let context = TemplateContext {
+        results: valid_results
+            .iter()
+            .map(|r| TemplateResult {
+                result: to_json_result(r),
+                line_start: r.lines.0,
+                line_end: r.lines.1,
+                extension: r
+                    .path()
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .unwrap_or("")
+                    .to_string(),
+            })
+            .collect(),
+        summary: TemplateSummary {
+            count: valid_results.len(),
+            total_bytes: valid_results.iter().map(|r| r.code.len()).sum(),
+            total_tokens: valid_results.iter().map(|r| count_tokens(&r.code)).sum(),
+            results_fingerprint: results_fingerprint(valid_results.iter().copied()),
+            corpus_fingerprint,
+        },
+    };
+
+    let mut tt = tinytemplate::TinyTemplate::new();
+    tt.set_default_formatter(&tinytemplate::format_unescaped);
+    tt.add_template("output", template)
+        .map_err(|e| anyhow::anyhow!("Invalid template: {}", e))?;
+    tt.render("output", &context)
+        .map_err(|e| anyhow::anyhow!("Failed to render template: {}", e))
+}
+
 /// Format and print search results in XML format
-fn format_and_print_xml_results(results: &[&SearchResult]) -> Result<()> {
-    println!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
-    println!("<probe_results>");
+fn format_and_print_xml_results(
+    out: &mut dyn Write,
+    results: &[&SearchResult],
+    errors: &[FileError],
+    corpus_fingerprint: Option<&str>,
+) -> Result<()> {
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(out, "<probe_results>")?;
 
     for result in results {
-        println!("  <result>");
-        println!("    <file>{}</file>", escape_xml(&result.file));
-        println!("    <lines>{}-{}</lines>", result.lines.0, result.lines.1);
-        println!(
+        writeln!(out, "  <result>")?;
+        let encoded = encode_result_path(&result.file, result.file_path.as_deref());
+        writeln!(
+            out,
+            "    <file{}>{}</file>",
+            encoded.xml_attribute(),
+            escape_xml(&encoded.value)
+        )?;
+        writeln!(
+            out,
+            "    <lines>{}-{}</lines>",
+            result.lines.0, result.lines.1
+        )?;
+        writeln!(
+            out,
             "    <node_type>{}</node_type>",
             escape_xml(&result.node_type)
-        );
+        )?;
+        if let Some(symbol_name) = &result.symbol_name {
+            writeln!(
+                out,
+                "    <symbol_name>{}</symbol_name>",
+                escape_xml(symbol_name)
+            )?;
+        }
+        if let Some(language) = &result.language {
+            writeln!(out, "    <language>{}</language>", escape_xml(language))?;
+        }
+        if let Some(confidence) = result.extraction_confidence {
+            writeln!(
+                out,
+                "    <extraction_confidence>{}</extraction_confidence>",
+                confidence.as_str()
+            )?;
+        }
 
         if let Some(keywords) = &result.matched_keywords {
-            println!("    <matched_keywords>");
+            writeln!(out, "    <matched_keywords>")?;
             for keyword in keywords {
-                println!("      <keyword>{}</keyword>", escape_xml(keyword));
+                writeln!(out, "      <keyword>{}</keyword>", escape_xml(keyword))?;
             }
-            println!("    </matched_keywords>");
+            writeln!(out, "    </matched_keywords>")?;
         }
 
         if let Some(score) = result.score {
-            println!("    <score>{:.4}</score>", score);
+            writeln!(out, "    <score>{:.4}</score>", score)?;
+        }
+
+        if let Some(normalized_score) = result.normalized_score {
+            writeln!(
+                out,
+                "    <normalized_score>{:.4}</normalized_score>",
+                normalized_score
+            )?;
         }
 
         if let Some(tfidf_score) = result.tfidf_score {
-            println!("    <tfidf_score>{:.4}</tfidf_score>", tfidf_score);
+            writeln!(out, "    <tfidf_score>{:.4}</tfidf_score>", tfidf_score)?;
         }
 
         if let Some(bm25_score) = result.bm25_score {
-            println!("    <bm25_score>{:.4}</bm25_score>", bm25_score);
+            writeln!(out, "    <bm25_score>{:.4}</bm25_score>", bm25_score)?;
         }
 
         if let Some(file_unique_terms) = result.file_unique_terms {
-            println!(
+            writeln!(
+                out,
                 "    <file_unique_terms>{}</file_unique_terms>",
                 file_unique_terms
-            );
+            )?;
         }
 
         if let Some(file_total_matches) = result.file_total_matches {
-            println!(
+            writeln!(
+                out,
                 "    <file_total_matches>{}</file_total_matches>",
                 file_total_matches
-            );
+            )?;
         }
 
         if let Some(block_unique_terms) = result.block_unique_terms {
-            println!(
+            writeln!(
+                out,
                 "    <block_unique_terms>{}</block_unique_terms>",
                 block_unique_terms
-            );
+            )?;
         }
 
         if let Some(block_total_matches) = result.block_total_matches {
-            println!(
+            writeln!(
+                out,
                 "    <block_total_matches>{}</block_total_matches>",
                 block_total_matches
-            );
+            )?;
+        }
+
+        if let Some(usage_kind) = result.usage_kind {
+            writeln!(
+                out,
+                "    <usage_kind>{}</usage_kind>",
+                usage_kind_name(usage_kind)
+            )?;
+        }
+
+        if let Some(kind) = result.kind {
+            writeln!(out, "    <kind>{}</kind>", kind.as_str())?;
+        }
+
+        if let Some(metrics) = result.complexity {
+            writeln!(
+                out,
+                "    <complexity lines=\"{}\" nesting_depth=\"{}\" cyclomatic=\"{}\"/>",
+                metrics.lines, metrics.nesting_depth, metrics.cyclomatic
+            )?;
+        }
+
+        if result.partial_parse {
+            writeln!(out, "    <partial_parse>true</partial_parse>")?;
+        }
+        if result.deprecated {
+            writeln!(out, "    <deprecated>true</deprecated>")?;
+        }
+        if let Some(embedded) = &result.embedded {
+            writeln!(
+                out,
+                "    <embedded language=\"{}\" host_lines=\"{}-{}\" host_node_type=\"{}\"/>",
+                embedded.language.as_str(),
+                embedded.host_lines.0,
+                embedded.host_lines.1,
+                escape_xml(&embedded.host_node_type)
+            )?;
+        }
+        if result.via_symlink {
+            writeln!(out, "    <via_symlink>true</via_symlink>")?;
+        }
+        if let Some(content_hash) = &result.content_hash {
+            writeln!(out, "    <content_hash>{}</content_hash>", content_hash)?;
+        }
+
+        if let Some(merged_from) = &result.merged_from {
+            writeln!(out, "    <merged_from>")?;
+            for block in merged_from {
+                writeln!(out, "      <block>")?;
+                if let Some(block_id) = block.block_id {
+                    writeln!(out, "        <block_id>{}</block_id>", block_id)?;
+                }
+                writeln!(
+                    out,
+                    "        <lines>{}-{}</lines>",
+                    block.lines.0, block.lines.1
+                )?;
+                writeln!(
+                    out,
+                    "        <node_type>{}</node_type>",
+                    escape_xml(&block.node_type)
+                )?;
+                if let Some(rank) = block.rank {
+                    writeln!(out, "        <rank>{}</rank>", rank)?;
+                }
+                if let Some(score) = block.score {
+                    writeln!(out, "        <score>{:.4}</score>", score)?;
+                }
+                writeln!(out, "      </block>")?;
+            }
+            writeln!(out, "    </merged_from>")?;
         }
 
-        println!("    <code><![CDATA[{}]]></code>", result.code);
-        println!("  </result>");
+        writeln!(out, "    <code><![CDATA[{}]]></code>", result.code)?;
+        writeln!(out, "  </result>")?;
     }
 
     // Add summary section
-    println!("  <summary>");
-    println!("    <count>{}</count>", results.len());
-    println!(
+    writeln!(out, "  <summary>")?;
+    writeln!(out, "    <count>{}</count>", results.len())?;
+    writeln!(
+        out,
         "    <total_bytes>{}</total_bytes>",
         results.iter().map(|r| r.code.len()).sum::<usize>()
-    );
-    println!(
+    )?;
+    writeln!(
+        out,
         "    <total_tokens>{}</total_tokens>",
         results.iter().map(|r| count_tokens(&r.code)).sum::<usize>()
-    );
-    println!("  </summary>");
+    )?;
+    if let Some(fingerprint) = results_fingerprint(results.iter().copied()) {
+        writeln!(
+            out,
+            "    <results_fingerprint>{}</results_fingerprint>",
+            fingerprint
+        )?;
+    }
+    if let Some(fingerprint) = corpus_fingerprint {
+        writeln!(
+            out,
+            "    <corpus_fingerprint>{}</corpus_fingerprint>",
+            fingerprint
+        )?;
+    }
+    writeln!(out, "  </summary>")?;
+
+    // Files that could not be read, only present when there were any
+    if !errors.is_empty() {
+        writeln!(out, "  <errors count=\"{}\">", errors.len())?;
+        for error in errors {
+            writeln!(
+                out,
+                "    <error kind=\"{}\"><file>{}</file><message>{}</message></error>",
+                error.kind.as_str(),
+                escape_xml(&error.path.display().to_string()),
+                escape_xml(&error.message)
+            )?;
+        }
+        writeln!(out, "  </errors>")?;
+    }
 
-    println!("</probe_results>");
+    writeln!(out, "</probe_results>")?;
     Ok(())
 }
@@ -1,19 +1,65 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use rayon::prelude::*;
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-use crate::search::file_list_cache;
+use tokio_util::sync::CancellationToken;
+use crate::search::file_list_cache::{self, FileFilters};
 // No need for term_exceptions import
 
-use crate::models::{LimitedSearchResults, SearchResult};
+use crate::error::{ProbeError, ProbeResult};
+use crate::fs_access;
+use crate::language::detection::{detect_language, parse_language_overrides};
+use crate::language::is_test_file;
+use crate::language::tree_cache;
+use crate::language::visibility::{block_visibility, VisibilityFilter};
+use crate::models::{
+    CapReason, CappedFile, ExtractionConfidence, FileError, LimitedSearchResults, ResultKind,
+    SearchResult, SkipReason, SkippedFile,
+};
+use crate::path_encoding::{format_path_for_output, PathStyle};
 use crate::search::{
+    acronyms,
+    attributes::{search_attributes, split_attr_qualifiers},
+    code_pattern::search_code_pattern,
     cache,
+    cancellation::{self, current_token},
+    candidate_selection::select_candidate_files,
+    code_terms,
+    dir_context::add_dir_context,
+    directives::apply_directives,
+    embedded,
+    empty_queries,
     // file_list_cache, // Add the new file_list_cache module (unused)
     file_processing::{process_file_with_results, FileProcessingParams},
-    query::{create_query_plan, create_structured_patterns, QueryPlan},
-    result_ranking::rank_search_results,
-    search_limiter::apply_limits,
-    search_options::SearchOptions,
+    filename_qualifier::{self, split_filename_qualifiers, FilenameFilter},
+    git_blame::AuthorFilter,
+    index::IndexFilter,
+    io_errors,
+    match_guards::{FileCaps, MatchGuards},
+    normalization::{normalize, normalize_queries},
+    options_validation::validate_search_options,
+    oversize::{apply_oversize_strategy, OversizeStrategy},
+    pattern_matcher::PatternMatcher,
+    pinned_blocks::{load_pinned_blocks, prepend_pinned_blocks, validate_block_ids},
+    preproc_context::apply_preproc_context,
+    query::{prepare_query, PreparedQuery, QueryPlan},
+    query_limits,
+    result_kind::{classify_kinds, parse_kinds},
+    result_order::{sort_by_path, sort_in_order, ResultOrder},
+    result_ranking::{penalize_acronym_matches, penalize_deprecated, rank_search_results},
+    score_normalization::normalize_scores,
+    search_limiter::{apply_limits, apply_limits_in_order},
+    search_options::{SearchOptions, SearchRequest},
+    search_tokens::count_tokens,
+    session::Session,
+    session_store,
+    skip_report,
+    snippet_window,
+    telemetry::{self, TelemetryEvent},
+    usages::classify_usages,
 };
 
 /// Struct to hold timing information for different stages of the search process
@@ -101,36 +147,41 @@ pub fn print_timings(timings: &SearchTimings) {
     println!("===================================\n");
 }
 
+/// Report the duration of each finished stage to the telemetry sink
+fn emit_stage_timings(timings: &SearchTimings) {
+    let stages = [
+        ("query_preprocessing", timings.query_preprocessing),
+        ("pattern_generation", timings.pattern_generation),
+        ("file_searching", timings.file_searching),
+        ("filename_matching", timings.filename_matching),
+        ("early_filtering", timings.early_filtering),
+        ("early_caching", timings.early_caching),
+        ("result_processing", timings.result_processing),
+        ("result_ranking", timings.result_ranking),
+        ("limit_application", timings.limit_application),
+        ("block_merging", timings.block_merging),
+        ("final_caching", timings.final_caching),
+    ];
+    for (stage, duration) in stages {
+        if let Some(duration) = duration {
+            telemetry::emit(TelemetryEvent::StageCompleted { stage, duration });
+        }
+    }
+}
+
 // Removed evaluate_ignoring_negatives helper function in favor of direct usage
 
 /// Our main "perform_probe" function remains largely the same. Below we show how you might
 /// incorporate "search_with_structured_patterns" to handle the AST logic in a specialized path.
 /// For simplicity, we won't fully replace the existing logic. Instead, we'll demonstrate
 /// how you'd do it if you wanted to leverage the new approach.
-pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
-    // Start timing the entire search process
-    let total_start = Instant::now();
-
-    let SearchOptions {
-        path,
-        queries,
-        files_only,
-        custom_ignores,
-        exclude_filenames,
-        reranker,
-        frequency_search: _,
-        max_results,
-        max_bytes,
-        max_tokens,
-        allow_tests,
-        exact,
-        no_merge,
-        merge_threshold,
-        dry_run: _, // We don't need this in perform_probe, but need to include it in the pattern
-        session,
-    } = options;
+pub fn perform_probe(options: &SearchOptions) -> ProbeResult<LimitedSearchResults> {
+    run_probe_with_cli_session(options).map_err(ProbeError::from)
+}
 
-    let include_filenames = !exclude_filenames;
+/// Run a search in the session named by `options.session` or `PROBE_SESSION_ID`, if any
+fn run_probe_with_cli_session(options: &SearchOptions) -> Result<LimitedSearchResults> {
+    let session = &options.session;
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
 
     // Handle session ID generation if session is provided but empty
@@ -203,6 +254,579 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
         }
     };
 
+    // CLI sessions live in the default session directory, in the store PROBE_SESSION_BACKEND picks
+    let mut session_state = effective_session
+        .map(|session_id| {
+            let store = session_store::default_store(&Session::default_dir())?;
+            Session::load_from(store, session_id)
+        })
+        .transpose()?;
+
+    let results = run_probe(options, session_state.as_mut(), None, None)?;
+
+    if let Some(session_state) = &session_state {
+        if let Err(e) = session_state.save() {
+            eprintln!("Error adding results to cache: {}", e);
+        }
+
+        // Print the session ID to the console if it was generated or provided
+        if session_was_generated {
+            println!(
+                "Session ID: {} (generated - used it in future sessions for caching)",
+                session_state.id()
+            );
+        } else {
+            println!("Session ID: {}", session_state.id());
+        }
+    }
+
+    Ok(results)
+}
+
+/// Run a search against a caller-owned session
+///
+/// Blocks already seen in `session` are skipped, and the returned blocks are recorded in it.
+/// `options.session` and `PROBE_SESSION_ID` are ignored, and nothing is written to disk:
+/// call [`Session::save`] to persist a stored session.
+#[allow(dead_code)]
+pub fn perform_probe_with_session(
+    options: &SearchOptions,
+    session: &mut Session,
+) -> ProbeResult<LimitedSearchResults> {
+    run_probe(options, Some(session), None, None).map_err(ProbeError::from)
+}
+
+/// Run a search against only the blocks already returned in `within`
+///
+/// Like `--within-session`, but for a caller-owned session: `options.within_session` is
+/// ignored, and `within` is not changed. Returned blocks are recorded in `session`, if any.
+#[allow(dead_code)]
+pub fn perform_probe_within_session(
+    options: &SearchOptions,
+    within: &Session,
+    session: Option<&mut Session>,
+) -> ProbeResult<LimitedSearchResults> {
+    run_probe(options, session, None, Some(within)).map_err(ProbeError::from)
+}
+
+/// Run a search for a query prepared with [`PreparedQuery::new`] or [`prepare_query`]
+///
+/// The queries, `exact`, code term and acronym settings of `prepared` replace those in
+/// `options`, so one plan can be reused across many roots. As with [`perform_probe_with_session`], only the
+/// given session, if any, is used.
+///
+/// [`prepare_query`]: crate::search::query::prepare_query
+#[allow(dead_code)]
+pub fn perform_probe_prepared(
+    options: &SearchOptions,
+    prepared: &PreparedQuery,
+    session: Option<&mut Session>,
+) -> ProbeResult<LimitedSearchResults> {
+    let options = SearchOptions {
+        queries: &prepared.queries,
+        exact: prepared.exact,
+        ..*options
+    };
+    run_probe(&options, session, Some(prepared), None).map_err(ProbeError::from)
+}
+
+/// Run `perform_probe` on the blocking thread pool
+///
+/// The search stops at the next file once `cancel` is cancelled, and the returned future
+/// resolves to a [`ProbeError::Timeout`] error right away. A cancelled search does not
+/// update its session.
+#[allow(dead_code)]
+pub async fn perform_probe_async(
+    request: SearchRequest,
+    cancel: CancellationToken,
+) -> ProbeResult<LimitedSearchResults> {
+    let token = cancel.clone();
+    let task = tokio::task::spawn_blocking(move || {
+        cancellation::with_cancellation(token, || perform_probe(&request.as_options()))
+    });
+
+    tokio::select! {
+        result = task => result.map_err(|e| ProbeError::Other(format!("Search task failed: {}", e)))?,
+        _ = cancel.cancelled() => Err(anyhow::Error::from(cancellation::Cancelled).into()),
+    }
+}
+
+pub(crate) fn run_probe(
+    options: &SearchOptions,
+    mut session: Option<&mut Session>,
+    prepared: Option<&PreparedQuery>,
+    within: Option<&Session>,
+) -> Result<LimitedSearchResults> {
+    validate_search_options(options)?;
+    let start = Instant::now();
+    // Several roots are searched from their common directory
+    let common_root = file_list_cache::common_root(options.roots);
+    let options = &SearchOptions {
+        path: if options.roots.is_empty() {
+            options.path
+        } else {
+            &common_root
+        },
+        ..*options
+    };
+    let span = tracing::info_span!(
+        "probe.search",
+        query = %options.queries.join(" "),
+        path = %options.path.display(),
+        results = tracing::field::Empty,
+        error = tracing::field::Empty,
+    );
+    let _entered = span.enter();
+    telemetry::emit(TelemetryEvent::QueryStarted {
+        queries: options.queries,
+        path: options.path,
+    });
+
+    // Query terms and blocks are matched with the same code term rules and acronym
+    // setting, so a prepared query's settings take the place of those in the options
+    let code_terms = prepared
+        .map_or(options.code_terms, |prepared| prepared.code_terms)
+        .and_then(code_terms::rules_for);
+    let match_acronyms = prepared.map_or(options.acronyms, |prepared| prepared.acronyms);
+    let (result, errors) = code_terms::with_code_terms(code_terms, || {
+        acronyms::with_acronyms(match_acronyms, || {
+            io_errors::collect(|| {
+                // A query the session found nothing for, over the same files, isn't run again
+                let empty_key = match &session {
+                    Some(_) if within.is_none() && options.within_session.is_none() => {
+                        empty_queries::query_key(options)?
+                    }
+                    _ => None,
+                };
+                if let (Some(session), Some(key)) = (session.as_deref(), &empty_key) {
+                    if session.found_nothing_for(key) {
+                        return Ok(cached_empty_results());
+                    }
+                }
+
+                let limited = if let Some(within) = within {
+                    run_probe_within(options, within, session.as_deref_mut(), prepared)
+                } else if let Some(within_session) = options.within_session {
+                    // CLI sessions live in the default session directory
+                    let store = session_store::default_store(&Session::default_dir())?;
+                    let within = Session::load_from(store, within_session)?;
+                    run_probe_within(options, &within, session.as_deref_mut(), prepared)
+                } else if options.roots.is_empty() {
+                    run_probe_stages(options, session.as_deref_mut(), prepared, None)
+                } else {
+                    run_probe_roots(options, session.as_deref_mut(), prepared)
+                }?;
+
+                // Nothing found, and not because of earlier results or unreadable files
+                if let (Some(session), Some(key)) = (session, &empty_key) {
+                    if limited.results.is_empty()
+                        && limited.skipped_files.is_empty()
+                        && limited.cached_blocks_skipped.unwrap_or(0) == 0
+                        && limited.capped.is_empty()
+                        && !io_errors::any_recorded()
+                    {
+                        session.record_empty_query(key);
+                    }
+                }
+                Ok(limited)
+            })
+        })
+    });
+    let result = result.and_then(|mut limited| {
+        if options.strict && !errors.is_empty() {
+            return Err(strict_error(&errors).into());
+        }
+        limited.errors = errors;
+        Ok(limited)
+    });
+
+    match &result {
+        Ok(limited) => span.record("results", limited.results.len()),
+        Err(e) => span.record("error", tracing::field::display(e)),
+    };
+    if let Ok(limited) = &result {
+        if telemetry::is_enabled() {
+            telemetry::emit(TelemetryEvent::ResultsReturned {
+                results: limited.results.len(),
+                bytes: limited.results.iter().map(|r| r.code.len()).sum(),
+                tokens: limited
+                    .results
+                    .iter()
+                    .map(|r| crate::search::search_tokens::count_tokens(&r.code))
+                    .sum(),
+            });
+        }
+    }
+    telemetry::emit(TelemetryEvent::QueryFinished {
+        duration: start.elapsed(),
+        succeeded: result.is_ok(),
+    });
+
+    result
+}
+
+/// The results of a query the session found nothing for before
+fn cached_empty_results() -> LimitedSearchResults {
+    LimitedSearchResults {
+        results: Vec::new(),
+        skipped_files: Vec::new(),
+        limits_applied: None,
+        cached_blocks_skipped: None,
+        skip_report: None,
+        errors: Vec::new(),
+        capped: Vec::new(),
+        empty_query_cached: true,
+    }
+}
+
+/// The error a `--strict` search fails with when files could not be read
+fn strict_error(errors: &[FileError]) -> ProbeError {
+    let listed: Vec<String> = errors
+        .iter()
+        .take(5)
+        .map(|e| format!("{}: {}", e.path.display(), e.message))
+        .collect();
+    let more = if errors.len() > listed.len() {
+        format!(" (and {} more)", errors.len() - listed.len())
+    } else {
+        String::new()
+    };
+    ProbeError::Io(format!(
+        "{} file(s) could not be read: {}{}",
+        errors.len(),
+        listed.join("; "),
+        more
+    ))
+}
+
+/// Search `options.roots` as one corpus, with `options.path` set to their common directory
+///
+/// The combined file list answers every file list request of the search, so each stage sees
+/// exactly the files left by each root's own ignores.
+fn run_probe_roots(
+    options: &SearchOptions,
+    session: Option<&mut Session>,
+    prepared: Option<&PreparedQuery>,
+) -> Result<LimitedSearchResults> {
+    let walk_allow_tests = options.allow_tests || options.tests_for.is_some();
+    let file_list = file_list_cache::get_roots_file_list(
+        options.roots,
+        walk_allow_tests,
+        options.custom_ignores,
+        options.follow_symlinks,
+        options.file_filters(),
+    )?;
+    file_list_cache::with_file_list(
+        options.path,
+        walk_allow_tests,
+        options.custom_ignores,
+        options.follow_symlinks,
+        options.file_filters(),
+        file_list,
+        || run_probe_stages(options, session, prepared, None),
+    )
+}
+
+/// Search only the blocks returned earlier in session `within`
+///
+/// The files of that session stand in for the walk of `options.path`, and matches outside
+/// its blocks are dropped, so nothing else in the repository is read. `within` itself is
+/// only read; new results are recorded in `session` as usual.
+fn run_probe_within(
+    options: &SearchOptions,
+    within: &Session,
+    session: Option<&mut Session>,
+    prepared: Option<&PreparedQuery>,
+) -> Result<LimitedSearchResults> {
+    if within.is_empty() {
+        return Err(anyhow!(
+            "Session '{}' has no results to search within",
+            within.id()
+        ));
+    }
+
+    let files: Vec<PathBuf> = within
+        .seen_files()
+        .into_iter()
+        .filter(|file| file.is_file())
+        .collect();
+    let file_list = Arc::new(file_list_cache::FileList {
+        files,
+        created_at: Instant::now(),
+        errors: Vec::new(),
+    });
+    let walk_allow_tests = options.allow_tests || options.tests_for.is_some();
+    file_list_cache::with_file_list(
+        options.path,
+        walk_allow_tests,
+        options.custom_ignores,
+        options.follow_symlinks,
+        options.file_filters(),
+        file_list,
+        || run_probe_stages(options, session, prepared, Some(within)),
+    )
+}
+
+/// Search only the files whose names match the `filename:` qualifiers of the query
+///
+/// The rest of the query runs on those files. Without other terms, the qualifier values
+/// become the query, so the files are returned through file name matching.
+fn run_probe_filename_filtered(
+    options: &SearchOptions,
+    filename_filters: &[FilenameFilter],
+    remaining_queries: Vec<String>,
+    session: Option<&mut Session>,
+    within: Option<&Session>,
+) -> Result<LimitedSearchResults> {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    let walk_allow_tests = options.allow_tests || options.tests_for.is_some();
+    let file_list = file_list_cache::get_file_list(
+        options.path,
+        walk_allow_tests,
+        options.custom_ignores,
+        options.follow_symlinks,
+        options.file_filters(),
+    )?;
+    let files: Vec<PathBuf> = file_list
+        .files
+        .iter()
+        .filter(|file| filename_qualifier::matches_all(filename_filters, file))
+        .cloned()
+        .collect();
+    if debug_mode {
+        println!(
+            "DEBUG: {} of {} files match the filename: qualifiers",
+            files.len(),
+            file_list.files.len()
+        );
+    }
+
+    let name_only = remaining_queries.is_empty();
+    let queries = if name_only {
+        let names: Vec<&str> = filename_filters
+            .iter()
+            .filter(|filter| !filter.excluded && !filter.is_glob())
+            .map(|filter| filter.value.as_str())
+            .collect();
+        if names.is_empty() {
+            return Err(ProbeError::QueryParse(
+                "filename: globs and -filename: exclusions need a search term, e.g. 'filename:*.proto user'".to_string()
+            ).into());
+        }
+        vec![names.join(" ")]
+    } else {
+        remaining_queries
+    };
+    let narrowed = SearchOptions {
+        queries: &queries,
+        exclude_filenames: options.exclude_filenames && !name_only,
+        ..*options
+    };
+
+    let narrowed_list = Arc::new(file_list_cache::FileList {
+        files,
+        created_at: Instant::now(),
+        errors: file_list.errors.clone(),
+    });
+    file_list_cache::with_file_list(
+        options.path,
+        walk_allow_tests,
+        options.custom_ignores,
+        options.follow_symlinks,
+        options.file_filters(),
+        narrowed_list,
+        || run_probe_stages(&narrowed, session, None, within),
+    )
+}
+
+fn run_probe_stages(
+    options: &SearchOptions,
+    mut session: Option<&mut Session>,
+    prepared: Option<&PreparedQuery>,
+    within: Option<&Session>,
+) -> Result<LimitedSearchResults> {
+    // Start timing the entire search process
+    let total_start = Instant::now();
+
+    let SearchOptions {
+        path,
+        queries,
+        files_only,
+        custom_ignores,
+        exclude_filenames,
+        reranker,
+        frequency_search: _,
+        max_results,
+        max_bytes,
+        max_tokens,
+        allow_tests,
+        exact,
+        no_merge,
+        merge_threshold,
+        dry_run: _, // We don't need this in perform_probe, but need to include it in the pattern
+        session: _,
+        report_skipped,
+        tests_for,
+        usages,
+        path_style,
+        follow_symlinks,
+        strict: _,
+        roots,
+        max_depth: _,
+        min_depth: _,
+        newer_than: _,
+        older_than: _,
+        min_size: _,
+        max_size: _,
+        author,
+        snippet_window,
+        oversize_strategy,
+        oversize_threshold,
+        case_fold,
+        code_terms: _, // Installed by run_probe
+        acronyms: _,
+        preproc_context,
+        embedded,
+        force_language,
+        within_session: _,
+        exclude_blocks,
+        pin_blocks,
+        filename_weight,
+        include_dir_context,
+        deprecated_penalty,
+        visibility,
+        kinds,
+        sort,
+        candidate_files,
+        max_line_length: _,
+        max_matches_per_file: _,
+        max_total_matches: _,
+        max_query_terms,
+        code_pattern,
+    } = options;
+
+    // With --case-fold, queries are folded the same way as the content they are matched against
+    let folded_queries = case_fold.then(|| normalize_queries(queries, true));
+    let queries: &[String] = folded_queries.as_deref().unwrap_or(queries);
+
+    let include_filenames = !exclude_filenames;
+    let cancel = current_token();
+    // With --tests-for, test files are walked and searched, then filtered by symbol below
+    let walk_allow_tests = *allow_tests || tests_for.is_some();
+    let filters = options.file_filters();
+    let author_filter = author.map(AuthorFilter::new).transpose()?;
+    let oversize = OversizeStrategy::parse(oversize_strategy)
+        .ok_or_else(|| anyhow!("Invalid oversize strategy: {}", oversize_strategy))?;
+    let visibility_filter = VisibilityFilter::parse(visibility)
+        .ok_or_else(|| anyhow!("Invalid visibility: {}", visibility))?;
+    let kind_filter =
+        parse_kinds(kinds).map_err(|kind| anyhow!("Invalid result kind: {}", kind))?;
+    let order = ResultOrder::parse(sort).ok_or_else(|| anyhow!("Invalid sort order: {}", sort))?;
+    let language_overrides = parse_language_overrides(force_language)?;
+    validate_block_ids("--exclude-blocks", exclude_blocks)?;
+    validate_block_ids("--pin-blocks", pin_blocks)?;
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    // Excluded blocks are skipped like seen blocks, and stay skipped for the rest of the session
+    let excluded = Session::with_blocks("excluded", exclude_blocks);
+    if let Some(session) = session.as_deref_mut() {
+        session.record_block_ids(exclude_blocks);
+    }
+
+    if *filename_weight < 0.0 {
+        return Err(anyhow!(
+            "Invalid --filename-weight {}: must not be negative",
+            filename_weight
+        ));
+    }
+
+    if !(0.0..=1.0).contains(deprecated_penalty) {
+        return Err(anyhow!(
+            "Invalid --deprecated-penalty {}: must be between 0 and 1",
+            deprecated_penalty
+        ));
+    }
+
+    // `filename:` qualifiers narrow the files searched, the rest of the query runs on those
+    let (filename_filters, remaining_queries) = split_filename_qualifiers(queries);
+    if !filename_filters.is_empty() && !*code_pattern {
+        return run_probe_filename_filtered(
+            options,
+            &filename_filters,
+            remaining_queries,
+            session,
+            within,
+        );
+    }
+
+    // `attr:` qualifiers and --code-pattern search for blocks instead of running the term search
+    let (attr_filters, remaining_queries) = if *code_pattern {
+        (Vec::new(), Vec::new())
+    } else {
+        split_attr_qualifiers(queries)
+    };
+    if !attr_filters.is_empty() || *code_pattern {
+        let mut results = if *code_pattern {
+            // The snippet is matched as written, not case folded
+            search_code_pattern(
+                path,
+                &options.queries.join("\n"),
+                custom_ignores,
+                *allow_tests,
+                *follow_symlinks,
+                filters,
+                &language_overrides,
+            )?
+        } else {
+            search_attributes(
+                path,
+                &attr_filters,
+                &remaining_queries,
+                custom_ignores,
+                *allow_tests,
+                *follow_symlinks,
+                filters,
+            )?
+        };
+        normalize_scores(&mut results);
+        let mut skipped_report = Vec::new();
+        if let (Some(filter), Some(pattern)) = (&author_filter, author) {
+            results = filter_by_author(
+                results,
+                filter,
+                pattern,
+                *report_skipped,
+                &mut skipped_report,
+            );
+        }
+        results = filter_by_visibility(
+            results,
+            visibility_filter,
+            *report_skipped,
+            &mut skipped_report,
+        );
+        classify_kinds(&mut results, &remaining_queries);
+        results = filter_by_kind(results, &kind_filter, *report_skipped, &mut skipped_report);
+        let mut limited =
+            apply_ordered_limits(results, order, *max_results, *max_bytes, *max_tokens);
+        if *report_skipped {
+            limited.skip_report = Some(skip_report::finalize_report(
+                skipped_report,
+                &limited.results,
+            ));
+        }
+        if debug_mode {
+            println!(
+                "DEBUG: Block search completed in {} - Final result count: {}",
+                format_duration(total_start.elapsed()),
+                limited.results.len()
+            );
+        }
+        finish_results(&mut limited, path, *follow_symlinks, path_style);
+        return Ok(limited);
+    }
+
     let mut timings = SearchTimings {
         query_preprocessing: None,
         pattern_generation: None,
@@ -220,18 +844,31 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
 
     // Combine multiple queries with AND or just parse single query
     let qp_start = Instant::now();
+    let qp_span = tracing::info_span!("query_preprocessing").entered();
     if debug_mode {
         println!("DEBUG: Starting query preprocessing...");
     }
 
-    let parse_res = if queries.len() > 1 {
-        // Join multiple queries with AND
-        let combined_query = queries.join(" AND ");
-        create_query_plan(&combined_query, *exact)
-    } else {
-        create_query_plan(&queries[0], *exact)
+    // Oversized queries are rejected before their patterns are generated
+    match prepared {
+        Some(prepared) => query_limits::check_plan(&prepared.plan, *max_query_terms)?,
+        None => query_limits::check_query(queries, *exact, *max_query_terms)?,
+    }
+
+    // Parsed plans are cached per process, so repeated queries skip parsing
+    let cached;
+    let parse_res = match prepared {
+        Some(prepared) => Ok(prepared),
+        None => match prepare_query(queries, *exact) {
+            Ok(prepared) => {
+                cached = prepared;
+                Ok(&*cached)
+            }
+            Err(e) => Err(e),
+        },
     };
 
+    drop(qp_span);
     let qp_duration = qp_start.elapsed();
     timings.query_preprocessing = Some(qp_duration);
 
@@ -242,30 +879,28 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
         );
     }
 
-    // If the query fails to parse, return empty results
-    if parse_res.is_err() {
-        println!("Failed to parse query as AST expression");
-        return Ok(LimitedSearchResults {
-            results: Vec::new(),
-            skipped_files: Vec::new(),
-            limits_applied: None,
-            cached_blocks_skipped: None,
-        });
-    }
-
     // All queries go through the AST path
-    let plan = parse_res.unwrap();
+    let prepared = parse_res.map_err(|e| {
+        ProbeError::QueryParse(format!(
+            "Failed to parse query '{}': {}",
+            queries.join(" "),
+            e
+        ))
+    })?;
+    let plan = &prepared.plan;
 
     // Pattern generation timing
     let pg_start = Instant::now();
+    let pg_span = tracing::info_span!("pattern_generation").entered();
     if debug_mode {
         println!("DEBUG: Starting pattern generation...");
         println!("DEBUG: Using combined pattern approach for more efficient searching");
     }
 
     // Use combined pattern approach for more efficient searching
-    let structured_patterns = create_structured_patterns(&plan);
+    let structured_patterns = &prepared.patterns;
 
+    drop(pg_span);
     let pg_duration = pg_start.elapsed();
     timings.pattern_generation = Some(pg_duration);
 
@@ -282,18 +917,68 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
 
     // File searching timing
     let fs_start = Instant::now();
+    let fs_span = tracing::info_span!("file_searching").entered();
     if debug_mode {
         println!("DEBUG: Starting file searching...");
     }
 
+    // Files excluded along the way, only tracked when a skip report was requested
+    let mut skipped_report: Vec<SkippedFile> = Vec::new();
+    if *report_skipped {
+        let file_list = crate::search::file_list_cache::get_file_list(
+            path,
+            walk_allow_tests,
+            custom_ignores,
+            *follow_symlinks,
+            filters,
+        )?;
+        if roots.is_empty() {
+            skipped_report.extend(skip_report::find_walk_exclusions(
+                path,
+                walk_allow_tests,
+                custom_ignores,
+                &file_list.files,
+                *follow_symlinks,
+                filters,
+            ));
+        }
+        for root in roots.iter() {
+            let ignores: Vec<String> = custom_ignores
+                .iter()
+                .chain(root.ignores.iter())
+                .cloned()
+                .collect();
+            skipped_report.extend(skip_report::find_walk_exclusions(
+                &root.path,
+                walk_allow_tests,
+                &ignores,
+                &file_list.files,
+                *follow_symlinks,
+                filters,
+            ));
+        }
+    }
+
+    let mut capped = Vec::new();
     let mut file_term_map = search_with_structured_patterns(
         path,
-        &plan,
-        &structured_patterns,
+        plan,
+        structured_patterns,
         custom_ignores,
-        *allow_tests,
+        walk_allow_tests,
+        *follow_symlinks,
+        filters,
+        *case_fold,
+        options.match_guards(),
+        &mut capped,
+        if *report_skipped {
+            Some(&mut skipped_report)
+        } else {
+            None
+        },
     )?;
 
+    drop(fs_span);
     let fs_duration = fs_start.elapsed();
     timings.file_searching = Some(fs_duration);
 
@@ -321,6 +1006,8 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
 
     // Add filename matches if enabled
     let fm_start = Instant::now();
+    let fm_span = tracing::info_span!("filename_matching").entered();
+    let mut filename_matched: HashSet<PathBuf> = HashSet::new();
     if include_filenames {
         if debug_mode {
             println!("DEBUG: Starting filename matching...");
@@ -331,8 +1018,10 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
             queries,
             &all_files,
             custom_ignores,
-            *allow_tests,
+            walk_allow_tests,
             &plan.term_indices,
+            *follow_symlinks,
+            filters,
         )?;
 
         if debug_mode {
@@ -342,15 +1031,23 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
             );
         }
 
+        filename_matched.extend(filename_matches.keys().cloned());
+
         // Process files that matched by filename
+        let mut filename_matches: Vec<(PathBuf, HashSet<usize>)> =
+            filename_matches.into_iter().collect();
+        filename_matches.sort_by(|a, b| a.0.cmp(&b.0));
         for (pathbuf, matched_terms) in &filename_matches {
             // Read the file content to get the total number of lines
-            let file_content = match std::fs::read_to_string(pathbuf.as_path()) {
+            let file_content = match fs_access::read_to_string(pathbuf.as_path()) {
                 Ok(content) => content,
                 Err(e) => {
                     if debug_mode {
                         println!("DEBUG: Error reading file {:?}: {:?}", pathbuf, e);
                     }
+                    if e.kind() != std::io::ErrorKind::InvalidData {
+                        io_errors::record(pathbuf, &e);
+                    }
                     continue;
                 }
             };
@@ -415,8 +1112,37 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
         println!("DEBUG: all_files after filename matches: {:?}", all_files);
     }
 
+    // Keep test files only if they reference the requested symbol
+    if let (Some(symbol), false) = (tests_for, *allow_tests) {
+        let symbol_regex = regex::Regex::new(&format!(r"\b{}\b", regex::escape(symbol)))?;
+        let unrelated_tests: Vec<PathBuf> = all_files
+            .iter()
+            .filter(|f| is_test_file(f) && !file_references_symbol(f, &symbol_regex))
+            .cloned()
+            .collect();
+
+        for pathbuf in &unrelated_tests {
+            if debug_mode {
+                println!(
+                    "DEBUG: Removing test file {:?} - does not reference '{}'",
+                    pathbuf, symbol
+                );
+            }
+            all_files.remove(pathbuf);
+            file_term_map.remove(pathbuf);
+            if *report_skipped {
+                skipped_report.push(SkippedFile {
+                    path: pathbuf.clone(),
+                    is_dir: false,
+                    reason: SkipReason::UnrelatedTest(symbol.to_string()),
+                });
+            }
+        }
+    }
+
     // Early filtering step - filter both all_files and file_term_map using full AST evaluation (including excluded terms)
     let early_filter_start = Instant::now();
+    let early_filter_span = tracing::info_span!("early_filtering").entered();
     if debug_mode {
         println!("DEBUG: Starting early AST filtering...");
         println!("DEBUG: Before filtering: {} files", all_files.len());
@@ -465,8 +1191,10 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
         println!("DEBUG: all_files after early filtering: {:?}", all_files);
     }
 
+    drop(early_filter_span);
     let early_filter_duration = early_filter_start.elapsed();
     timings.early_filtering = Some(early_filter_duration);
+    cancellation::check(cancel.as_ref())?;
 
     if debug_mode {
         println!(
@@ -475,6 +1203,7 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
         );
     }
 
+    drop(fm_span);
     let fm_duration = fm_start.elapsed();
     timings.filename_matching = Some(fm_duration);
 
@@ -491,6 +1220,7 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
         for f in all_files {
             res.push(SearchResult {
                 file: f.to_string_lossy().to_string(),
+                file_path: Some(f.to_path_buf()),
                 lines: (1, 1),
                 node_type: "file".to_string(),
                 code: String::new(),
@@ -513,46 +1243,100 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
                 block_id: None,
                 matched_keywords: None,
                 tokenized_content: None,
+                merged_from: None,
+                normalized_score: None,
+                usage_kind: None,
+                via_symlink: false,
+                changed_lines: None,
+                symbol_name: None,
+                language: detect_language(&f, "", &language_overrides).map(str::to_string),
+                extraction_confidence: Some(ExtractionConfidence::WholeFile),
+                partial_parse: false,
+                deprecated: false,
+                embedded: None,
+                kind: None,
+                complexity: None,
+                content_hash: None,
             });
         }
-        let mut limited = apply_limits(res, *max_results, *max_bytes, *max_tokens);
+        if let (Some(filter), Some(pattern)) = (&author_filter, author) {
+            res = filter_by_author(res, filter, pattern, *report_skipped, &mut skipped_report);
+        }
+        let candidate_files: Vec<PathBuf> = res.iter().map(|r| r.path()).collect();
+        let mut limited = apply_ordered_limits(res, order, *max_results, *max_bytes, *max_tokens);
 
         // No caching for files-only mode
         limited.cached_blocks_skipped = None;
 
+        if *report_skipped {
+            record_limit_skips(
+                &mut skipped_report,
+                &candidate_files,
+                &limited,
+                *max_results,
+            );
+            limited.skip_report = Some(skip_report::finalize_report(
+                skipped_report,
+                &limited.results,
+            ));
+        }
+        limited.capped = capped;
+
         // Set total search time
         timings.total_search_time = Some(total_start.elapsed());
 
         // Print timing information
         print_timings(&timings);
+        emit_stage_timings(&timings);
 
+        finish_results(&mut limited, path, *follow_symlinks, path_style);
         return Ok(limited);
     }
 
+    // With --within-session, only matches inside the blocks returned before are kept
+    if let Some(within) = within {
+        within.restrict_matched_lines(&mut file_term_map);
+        all_files.retain(|file| file_term_map.contains_key(file));
+    }
+
+    // With --exclude-blocks, matches inside the excluded blocks are dropped
+    if !excluded.is_empty() {
+        let files_before_exclusion: Vec<PathBuf> = file_term_map.keys().cloned().collect();
+        excluded.filter_matched_lines(&mut file_term_map);
+        all_files.retain(|file| file_term_map.contains_key(file));
+        if *report_skipped {
+            skip_report::record_dropped_files(
+                &mut skipped_report,
+                files_before_exclusion.iter().map(PathBuf::as_path),
+                file_term_map.keys().map(PathBuf::as_path),
+                SkipReason::Excluded,
+            );
+        }
+    }
+
     // Apply early caching if session is provided - AFTER getting ripgrep results but BEFORE processing
     let ec_start = Instant::now();
+    let ec_span = tracing::info_span!("early_caching").entered();
     let mut early_skipped_count = 0;
-    if let Some(session_id) = effective_session {
+    if let Some(session) = session.as_deref() {
         if debug_mode {
-            println!("DEBUG: Starting early caching for session: {}", session_id);
+            println!(
+                "DEBUG: Starting early caching for session: {}",
+                session.id()
+            );
             // Print cache contents before filtering
-            if let Err(e) = cache::debug_print_cache(session_id) {
-                eprintln!("Error printing cache: {}", e);
-            }
+            cache::debug_print_cache(session);
         }
 
+        let files_before_cache: Vec<PathBuf> = file_term_map.keys().cloned().collect();
+
         // Filter matched lines using the cache
-        match cache::filter_matched_lines_with_cache(&mut file_term_map, session_id) {
-            Ok(skipped) => {
-                if debug_mode {
-                    println!("DEBUG: Early caching skipped {} matched lines", skipped);
-                }
-                early_skipped_count = skipped;
-            }
-            Err(e) => {
-                // Log the error but continue without early caching
-                eprintln!("Error applying early cache: {}", e);
-            }
+        early_skipped_count = session.filter_matched_lines(&mut file_term_map);
+        if debug_mode {
+            println!(
+                "DEBUG: Early caching skipped {} matched lines",
+                early_skipped_count
+            );
         }
 
         // Update all_files based on the filtered file_term_map
@@ -560,23 +1344,61 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
         let cached_files = file_term_map.keys().cloned().collect::<HashSet<_>>();
         all_files = all_files.intersection(&cached_files).cloned().collect();
 
+        if *report_skipped {
+            skip_report::record_dropped_files(
+                &mut skipped_report,
+                files_before_cache.iter().map(PathBuf::as_path),
+                cached_files.iter().map(PathBuf::as_path),
+                SkipReason::CacheHit,
+            );
+        }
+
         if debug_mode {
             println!("DEBUG: all_files after caching: {:?}", all_files);
         }
     }
 
+    drop(ec_span);
     let ec_duration = ec_start.elapsed();
     timings.early_caching = Some(ec_duration);
 
-    if debug_mode && effective_session.is_some() {
+    if debug_mode && session.is_some() {
         println!(
             "DEBUG: Early caching completed in {}",
             format_duration(ec_duration)
         );
     }
 
+    // With --candidate-files, only the files that score best on a cheap pass are extracted
+    if let Some(k) = candidate_files {
+        let candidates = select_candidate_files(
+            &all_files,
+            &file_term_map,
+            &filename_matched,
+            plan.term_indices.len(),
+            *k,
+        );
+        if debug_mode {
+            println!(
+                "DEBUG: Selected {} of {} files as candidates for block extraction",
+                candidates.len(),
+                all_files.len()
+            );
+        }
+        if *report_skipped {
+            skip_report::record_dropped_files(
+                &mut skipped_report,
+                all_files.iter().map(PathBuf::as_path),
+                candidates.iter().map(PathBuf::as_path),
+                SkipReason::NotCandidate,
+            );
+        }
+        all_files = candidates;
+    }
+
     // Process the files for detailed results
     let rp_start = Instant::now();
+    let rp_span = tracing::info_span!("result_processing").entered();
     if debug_mode {
         println!(
             "DEBUG: Starting result processing for {} files after early caching...",
@@ -584,57 +1406,122 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
         );
     }
 
-    let mut final_results = Vec::new();
-
-    for pathbuf in &all_files {
+    // Create a list of term pairs for backward compatibility
+    let mut terms: Vec<(&String, &usize)> = plan.term_indices.iter().collect();
+    terms.sort_by_key(|&(_, index)| *index);
+    let term_pairs: Vec<(String, String)> = terms
+        .into_iter()
+        .map(|(term, _)| (term.clone(), term.clone()))
+        .collect();
+    let queries_terms = [term_pairs];
+
+    // AST processing dominates for queries matching many files, so files are processed in
+    // parallel, each rayon thread reusing its own tree-sitter parser. Sorting keeps the
+    // order of results independent of scheduling.
+    let mut files_to_process: Vec<&PathBuf> = all_files.iter().collect();
+    files_to_process.sort();
+    let process_file = |pathbuf: &PathBuf| {
+        // Files left after a cancellation are skipped; the check below reports it
+        if cancellation::check(cancel.as_ref()).is_err() {
+            return None;
+        }
         if debug_mode {
             println!("DEBUG: Processing file: {:?}", pathbuf);
         }
 
         // Get the term map for this file
-        if let Some(term_map) = file_term_map.get(pathbuf) {
+        let Some(term_map) = file_term_map.get(pathbuf) else {
+            // This should never happen, but keep for safety
             if debug_mode {
-                println!("DEBUG: Term map for file: {:?}", term_map);
+                println!(
+                    "DEBUG: ERROR - File {:?} not found in file_term_map but was in all_files",
+                    pathbuf
+                );
             }
+            return None;
+        };
+        if debug_mode {
+            println!("DEBUG: Term map for file: {:?}", term_map);
+        }
 
-            // Gather matched lines
-            let mut all_lines = HashSet::new();
-            for lineset in term_map.values() {
-                all_lines.extend(lineset.iter());
-            }
+        // Gather matched lines
+        let mut all_lines = HashSet::new();
+        for lineset in term_map.values() {
+            all_lines.extend(lineset.iter());
+        }
 
-            if debug_mode {
-                println!("DEBUG: Found {} matched lines in file", all_lines.len());
-            }
+        if debug_mode {
+            println!("DEBUG: Found {} matched lines in file", all_lines.len());
+        }
 
-            // Process file with matched lines
-            let filename_matched_queries = HashSet::new();
+        let pparams = FileProcessingParams {
+            path: pathbuf,
+            line_numbers: &all_lines,
+            allow_tests: *allow_tests || (tests_for.is_some() && is_test_file(pathbuf)),
+            term_matches: term_map,
+            num_queries: plan.term_indices.len(),
+            filename_matched_queries: HashSet::new(),
+            queries_terms: &queries_terms,
+            preprocessed_queries: None,
+            no_merge: *no_merge,
+            query_plan: plan,
+            case_fold: *case_fold,
+            language_overrides: &language_overrides,
+            embedded: *embedded,
+        };
 
-            // Create a list of term pairs for backward compatibility
-            let term_pairs: Vec<(String, String)> = plan
-                .term_indices
-                .keys()
-                .map(|term| (term.clone(), term.clone()))
-                .collect();
+        if debug_mode {
+            println!("DEBUG: Processing file with params: {:?}", pparams.path);
+        }
 
-            let pparams = FileProcessingParams {
-                path: pathbuf,
-                line_numbers: &all_lines,
-                allow_tests: *allow_tests,
-                term_matches: term_map,
-                num_queries: plan.term_indices.len(),
-                filename_matched_queries,
-                queries_terms: &[term_pairs],
-                preprocessed_queries: None,
-                no_merge: *no_merge,
-                query_plan: &plan,
-            };
+        Some(process_file_with_results(&pparams))
+    };
 
-            if debug_mode {
-                println!("DEBUG: Processing file with params: {:?}", pparams.path);
-            }
+    // With --sort path, results come from the files in the order they are processed, so once
+    // the files done so far fill --max-results, the remaining files cannot change the output.
+    // Filters that need git or drop results across files turn this off.
+    let stop_early_at = max_results.filter(|_| {
+        order == ResultOrder::Path
+            && author_filter.is_none()
+            && kind_filter.is_empty()
+            && pin_blocks.is_empty()
+    });
+    let batch_size = match stop_early_at {
+        Some(_) => rayon::current_num_threads() * 4,
+        None => files_to_process.len().max(1),
+    };
 
-            match process_file_with_results(&pparams) {
+    // Files are read, parsed and tokenized on rayon threads, which take this thread's file
+    // system, tree cache, code term rules and acronym setting
+    let file_system = fs_access::current();
+    let trees = tree_cache::current();
+    let code_terms = code_terms::current();
+    let match_acronyms = acronyms::enabled();
+
+    // Errors are recorded on this thread, where the search collects them
+    let mut final_results = Vec::new();
+    let mut files_processed = 0;
+    let mut kept_so_far = KeptResults::default();
+    for batch in files_to_process.chunks(batch_size) {
+        let processed: Vec<(&PathBuf, Result<Vec<SearchResult>>)> = batch
+            .par_iter()
+            .filter_map(|&pathbuf| {
+                fs_access::with_file_system(Arc::clone(&file_system), || {
+                    tree_cache::with_tree_cache(trees.clone(), || {
+                        code_terms::with_code_terms(code_terms, || {
+                            acronyms::with_acronyms(match_acronyms, || {
+                                Some((pathbuf, process_file(pathbuf)?))
+                            })
+                        })
+                    })
+                })
+            })
+            .collect();
+        cancellation::check(cancel.as_ref())?;
+
+        let batch_start = final_results.len();
+        for (pathbuf, processed) in processed {
+            match processed {
                 Ok(mut file_res) => {
                     if debug_mode {
                         println!("DEBUG: Got {} results from file processing", file_res.len());
@@ -645,19 +1532,48 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
                     if debug_mode {
                         println!("DEBUG: Error processing file: {:?}", e);
                     }
+                    io_errors::record_if_io(pathbuf, &e);
                 }
             }
-        } else {
-            // This should never happen, but keep for safety
-            if debug_mode {
-                println!(
-                    "DEBUG: ERROR - File {:?} not found in file_term_map but was in all_files",
-                    pathbuf
-                );
+        }
+        files_processed += batch.len();
+
+        if let Some(max) = stop_early_at {
+            if files_processed < files_to_process.len()
+                && limit_filled(
+                    &final_results[batch_start..],
+                    visibility_filter,
+                    &excluded,
+                    session.as_deref(),
+                    (max, *max_bytes, *max_tokens),
+                    &mut kept_so_far,
+                )
+            {
+                break;
             }
         }
     }
 
+    if files_processed < files_to_process.len() {
+        let unprocessed = &files_to_process[files_processed..];
+        if debug_mode {
+            println!(
+                "DEBUG: --max-results filled after {} files, skipping {} remaining files",
+                files_processed,
+                unprocessed.len()
+            );
+        }
+        if *report_skipped {
+            skip_report::record_dropped_files(
+                &mut skipped_report,
+                unprocessed.iter().map(|path| path.as_path()),
+                std::iter::empty(),
+                SkipReason::Limit,
+            );
+        }
+    }
+
+    drop(rp_span);
     let rp_duration = rp_start.elapsed();
     timings.result_processing = Some(rp_duration);
 
@@ -671,12 +1587,69 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
 
     // Rank results
     let rr_start = Instant::now();
+    let rr_span = tracing::info_span!("result_ranking").entered();
     if debug_mode {
         println!("DEBUG: Starting result ranking...");
     }
 
-    rank_search_results(&mut final_results, queries, reranker);
+    cancellation::check(cancel.as_ref())?;
+    rank_search_results(&mut final_results, queries, reranker, *filename_weight);
+
+    // Honor probe:ignore-next-block and probe:boost comments in the files
+    let files_before_directives: Vec<PathBuf> = final_results.iter().map(|r| r.path()).collect();
+    apply_directives(&mut final_results);
+    if *report_skipped {
+        let files_after: Vec<PathBuf> = final_results.iter().map(|r| r.path()).collect();
+        skip_report::record_dropped_files(
+            &mut skipped_report,
+            files_before_directives.iter().map(PathBuf::as_path),
+            files_after.iter().map(PathBuf::as_path),
+            SkipReason::Directive,
+        );
+    }
+
+    // Rank deprecated APIs below their replacements, and acronym matches below full terms
+    penalize_deprecated(&mut final_results, *deprecated_penalty);
+    penalize_acronym_matches(&mut final_results, plan);
 
+    // Put all result kinds on a common 0-1 scale before limits are applied
+    normalize_scores(&mut final_results);
+
+    // Tag definitions and usages so limits keep call sites first
+    if *usages {
+        classify_usages(&mut final_results, queries);
+    }
+
+    // Keep only blocks last changed by the requested author
+    if let (Some(filter), Some(pattern)) = (&author_filter, author) {
+        final_results = filter_by_author(
+            final_results,
+            filter,
+            pattern,
+            *report_skipped,
+            &mut skipped_report,
+        );
+    }
+
+    // Keep only blocks declaring symbols of the requested visibility
+    final_results = filter_by_visibility(
+        final_results,
+        visibility_filter,
+        *report_skipped,
+        &mut skipped_report,
+    );
+
+    // Tell definitions, call sites, imports, comments, strings and tests apart, and keep
+    // only the requested kinds
+    classify_kinds(&mut final_results, queries);
+    final_results = filter_by_kind(
+        final_results,
+        &kind_filter,
+        *report_skipped,
+        &mut skipped_report,
+    );
+
+    drop(rr_span);
     let rr_duration = rr_start.elapsed();
     timings.result_ranking = Some(rr_duration);
 
@@ -689,78 +1662,111 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
 
     // Apply caching if session is provided - BEFORE applying limits
     let fc_start = Instant::now();
+    let fc_span = tracing::info_span!("final_caching").entered();
     let mut skipped_count = early_skipped_count;
-    let mut filtered_results = final_results;
+    let (mut filtered_results, _) = excluded.filter_results(&final_results);
 
-    if let Some(session_id) = effective_session {
+    if let Some(session) = session.as_deref() {
         if debug_mode {
-            println!("DEBUG: Starting final caching for session: {}", session_id);
+            println!(
+                "DEBUG: Starting final caching for session: {}",
+                session.id()
+            );
             println!(
                 "DEBUG: Already skipped {} lines in early caching",
                 early_skipped_count
             );
             // Print cache contents before filtering
-            if let Err(e) = cache::debug_print_cache(session_id) {
-                eprintln!("Error printing cache: {}", e);
-            }
+            cache::debug_print_cache(session);
         }
 
-        // Filter results using the cache
-        match cache::filter_results_with_cache(&filtered_results, session_id) {
-            Ok((cache_filtered_results, cached_skipped)) => {
-                if debug_mode {
-                    println!(
-                        "DEBUG: Final caching skipped {} cached blocks",
-                        cached_skipped
-                    );
-                    println!(
-                        "DEBUG: Total skipped (early + final): {}",
-                        early_skipped_count + cached_skipped
-                    );
+        let files_before_cache: Vec<PathBuf> = filtered_results.iter().map(|r| r.path()).collect();
 
-                    // Print some details about the filtered results
-                    if !cache_filtered_results.is_empty() {
-                        println!(
-                            "DEBUG: First filtered result: file={}, lines={:?}",
-                            cache_filtered_results[0].file, cache_filtered_results[0].lines
-                        );
-                    }
-                }
+        // Filter results using the cache
+        let (cache_filtered_results, cached_skipped) = session.filter_results(&filtered_results);
+        if debug_mode {
+            println!(
+                "DEBUG: Final caching skipped {} cached blocks",
+                cached_skipped
+            );
+            println!(
+                "DEBUG: Total skipped (early + final): {}",
+                early_skipped_count + cached_skipped
+            );
 
-                // Store the filtered results
-                filtered_results = cache_filtered_results;
-                skipped_count += cached_skipped; // Add to the early skipped count
-            }
-            Err(e) => {
-                // Log the error but continue without caching
-                eprintln!("Error applying cache: {}", e);
+            // Print some details about the filtered results
+            if !cache_filtered_results.is_empty() {
+                println!(
+                    "DEBUG: First filtered result: file={}, lines={:?}",
+                    cache_filtered_results[0].file, cache_filtered_results[0].lines
+                );
             }
         }
+
+        if *report_skipped {
+            let files_after_cache: Vec<PathBuf> =
+                cache_filtered_results.iter().map(|r| r.path()).collect();
+            skip_report::record_dropped_files(
+                &mut skipped_report,
+                files_before_cache.iter().map(PathBuf::as_path),
+                files_after_cache.iter().map(PathBuf::as_path),
+                SkipReason::CacheHit,
+            );
+        }
+
+        // Store the filtered results
+        filtered_results = cache_filtered_results;
+        skipped_count += cached_skipped; // Add to the early skipped count
     }
 
+    drop(fc_span);
     let fc_duration = fc_start.elapsed();
     timings.final_caching = Some(fc_duration);
 
-    if debug_mode && effective_session.is_some() {
+    if debug_mode && session.is_some() {
         println!(
             "DEBUG: Final caching completed in {}",
             format_duration(fc_duration)
         );
     }
 
+    sort_in_order(&mut filtered_results, order);
+
+    // Pinned blocks are returned even when the session has seen them, ahead of the limits
+    if !pin_blocks.is_empty() {
+        let pinned = load_pinned_blocks(pin_blocks, *allow_tests);
+        filtered_results = prepend_pinned_blocks(filtered_results, pinned);
+    }
+
     // Apply limits
     let la_start = Instant::now();
+    let la_span = tracing::info_span!("limit_application").entered();
     if debug_mode {
         println!("DEBUG: Starting limit application...");
     }
 
-    let mut limited = apply_limits(filtered_results, *max_results, *max_bytes, *max_tokens);
+    let candidate_files: Vec<PathBuf> = filtered_results.iter().map(|r| r.path()).collect();
+    let mut limited = match order {
+        ResultOrder::Score => apply_limits(filtered_results, *max_results, *max_bytes, *max_tokens),
+        ResultOrder::Path | ResultOrder::Complexity => {
+            apply_limits_in_order(filtered_results, *max_results, *max_bytes, *max_tokens)
+        }
+    };
+    if *report_skipped {
+        record_limit_skips(
+            &mut skipped_report,
+            &candidate_files,
+            &limited,
+            *max_results,
+        );
+    }
     limited.cached_blocks_skipped = if skipped_count > 0 {
         Some(skipped_count)
     } else {
         None
     };
 
+    drop(la_span);
     let la_duration = la_start.elapsed();
     timings.limit_application = Some(la_duration);
 
@@ -772,31 +1778,101 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
         );
     }
 
-    // Update the cache with the limited results (before merging)
-    if let Some(session_id) = effective_session {
-        if let Err(e) = cache::add_results_to_cache(&limited.results, session_id) {
-            eprintln!("Error adding results to cache: {}", e);
-        }
+    // Directory documentation goes after the results, outside the limits
+    if *include_dir_context {
+        add_dir_context(&mut limited.results, &language_overrides);
+    }
 
-        if debug_mode {
-            println!("DEBUG: Added limited results to cache before merging");
-            // Print cache contents after adding new results
-            if let Err(e) = cache::debug_print_cache(session_id) {
-                eprintln!("Error printing updated cache: {}", e);
+    let merging = !limited.results.is_empty() && !*no_merge;
+
+    // Update the cache with the limited results (before merging); merged results are
+    // recorded once the limits are applied to them again
+    if !merging {
+        if let Some(session) = session.as_deref_mut() {
+            session.record(&limited.results);
+
+            if debug_mode {
+                println!("DEBUG: Added limited results to cache before merging");
+                // Print cache contents after adding new results
+                cache::debug_print_cache(session);
             }
         }
     }
 
     // Optional block merging - AFTER initial caching
     let bm_start = Instant::now();
-    if debug_mode && !limited.results.is_empty() && !*no_merge {
+    let bm_span = tracing::info_span!("block_merging").entered();
+    if debug_mode && merging {
         println!("DEBUG: Starting block merging...");
     }
 
-    let final_results = if !limited.results.is_empty() && !*no_merge {
+    let mut final_results = if merging {
         use crate::search::block_merging::merge_ranked_blocks;
-        let merged = merge_ranked_blocks(limited.results.clone(), *merge_threshold);
+        let mut merged = merge_ranked_blocks(limited.results.clone(), *merge_threshold);
+
+        // Merged blocks carry combined scores, so normalize them again
+        normalize_scores(&mut merged);
+        sort_in_order(&mut merged, order);
+
+        // Merged blocks take in the lines between the blocks they join, so the limits are
+        // applied again to keep the output within the byte and token budgets
+        let mut skipped_files = limited.skipped_files;
+        let mut limits_applied = limited.limits_applied;
+        if let Some(limits) = limits_applied.as_mut() {
+            let before: Vec<PathBuf> = merged.iter().map(|r| r.path()).collect();
+            // Directory documentation stays outside the limits
+            let (context, blocks): (Vec<_>, Vec<_>) = merged
+                .into_iter()
+                .partition(|r| r.node_type == "dir_context");
+            let (kept, dropped) =
+                limit_merged_results(blocks, &limited.results, *max_bytes, *max_tokens);
+            if debug_mode && !dropped.is_empty() {
+                println!(
+                    "DEBUG: Dropped {} blocks over the limits after merging",
+                    dropped.len()
+                );
+            }
+            merged = kept;
+            limits.post_merge_tokens = Some(merged.iter().map(|r| count_tokens(&r.code)).sum());
+            merged.extend(context);
+            skipped_files.extend(dropped.into_iter().filter(|r| r.rank.is_some()));
+            if *report_skipped {
+                let kept: Vec<PathBuf> = merged.iter().map(|r| r.path()).collect();
+                skip_report::record_dropped_files(
+                    &mut skipped_report,
+                    before.iter().map(PathBuf::as_path),
+                    kept.iter().map(PathBuf::as_path),
+                    SkipReason::TooLarge,
+                );
+            }
+        }
+
+        // The blocks that went into the merged results, for the session
+        if let Some(session) = session.as_deref_mut() {
+            let emitted: Vec<&SearchResult> = limited
+                .results
+                .iter()
+                .filter(|block| {
+                    merged.iter().any(|r| {
+                        r.file == block.file
+                            && r.lines.0 <= block.lines.0
+                            && block.lines.1 <= r.lines.1
+                    })
+                })
+                .collect();
+            session.record(&emitted.into_iter().cloned().collect::<Vec<_>>());
+
+            if debug_mode {
+                println!("DEBUG: Added limited results to cache before merging");
+                cache::debug_print_cache(session);
+            }
+        }
+
+        if *usages {
+            classify_usages(&mut merged, queries);
+        }
 
+        drop(bm_span);
         let bm_duration = bm_start.elapsed();
         timings.block_merging = Some(bm_duration);
 
@@ -811,28 +1887,29 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
         // Create the merged results
         let merged_results = LimitedSearchResults {
             results: merged.clone(),
-            skipped_files: limited.skipped_files,
-            limits_applied: limited.limits_applied,
+            skipped_files,
+            limits_applied,
             cached_blocks_skipped: limited.cached_blocks_skipped,
+            skip_report: None,
+            errors: Vec::new(),
+            capped: Vec::new(),
+            empty_query_cached: false,
         };
 
         // Update the cache with the merged results (after merging)
-        if let Some(session_id) = effective_session {
-            if let Err(e) = cache::add_results_to_cache(&merged, session_id) {
-                eprintln!("Error adding merged results to cache: {}", e);
-            }
+        if let Some(session) = session {
+            session.record(&merged);
 
             if debug_mode {
                 println!("DEBUG: Added merged results to cache after merging");
                 // Print cache contents after adding merged results
-                if let Err(e) = cache::debug_print_cache(session_id) {
-                    eprintln!("Error printing updated cache: {}", e);
-                }
+                cache::debug_print_cache(session);
             }
         }
 
         merged_results
     } else {
+        drop(bm_span);
         let bm_duration = bm_start.elapsed();
         timings.block_merging = Some(bm_duration);
 
@@ -846,29 +1923,293 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
         limited
     };
 
-    // Print the session ID to the console if it was generated or provided
-    if let Some(session_id) = effective_session {
-        if session_was_generated {
-            println!(
-                "Session ID: {} (generated - used it in future sessions for caching)",
-                session_id
-            );
-        } else {
-            println!("Session ID: {}", session_id);
-        }
+    // Trim huge blocks after merging, which relies on the full code of each block
+    let terms = snippet_window::matched_terms(plan);
+    if let Some(window) = snippet_window {
+        snippet_window::apply_snippet_window(&mut final_results.results, &terms, *window);
+    }
+    apply_oversize_strategy(
+        &mut final_results.results,
+        oversize,
+        *oversize_threshold,
+        &terms,
+    );
+    if *preproc_context {
+        apply_preproc_context(&mut final_results.results);
+    }
+    if *embedded {
+        embedded::apply_embedded(&mut final_results.results, &terms);
     }
 
+    if *report_skipped {
+        final_results.skip_report = Some(skip_report::finalize_report(
+            skipped_report,
+            &final_results.results,
+        ));
+    }
+    final_results.capped = capped;
+
     // Set total search time
     timings.total_search_time = Some(total_start.elapsed());
 
     // Print timing information
     print_timings(&timings);
+    emit_stage_timings(&timings);
 
+    finish_results(&mut final_results, path, *follow_symlinks, path_style);
     Ok(final_results)
 }
+
+/// Mark results reached through symlinks and rewrite file names in the requested `--path-style`
+///
+/// This runs last, after caching and skip reporting, which work on the exact paths.
+fn finish_results(
+    limited: &mut LimitedSearchResults,
+    root: &Path,
+    follow_symlinks: bool,
+    path_style: &str,
+) {
+    let style = PathStyle::from_name(path_style);
+    for result in limited.results.iter_mut() {
+        if follow_symlinks {
+            result.via_symlink = file_list_cache::reached_via_symlink(root, &result.path());
+        }
+        result.file = format_path_for_output(&result.file, style);
+    }
+}
+/// Helper function to check whether a file mentions a symbol as a whole word
+fn file_references_symbol(path: &Path, symbol_regex: &regex::Regex) -> bool {
+    fs_access::read_to_string(path)
+        .map(|content| symbol_regex.is_match(&content))
+        .unwrap_or(false)
+}
+
+/// Helper function to apply `--author`, recording the files it drops entirely
+fn filter_by_author(
+    results: Vec<SearchResult>,
+    filter: &AuthorFilter,
+    pattern: &str,
+    report_skipped: bool,
+    report: &mut Vec<SkippedFile>,
+) -> Vec<SearchResult> {
+    let files_before: Vec<PathBuf> = results.iter().map(|r| r.path()).collect();
+    let kept = filter.filter(results);
+    if report_skipped {
+        let files_after: Vec<PathBuf> = kept.iter().map(|r| r.path()).collect();
+        skip_report::record_dropped_files(
+            report,
+            files_before.iter().map(PathBuf::as_path),
+            files_after.iter().map(PathBuf::as_path),
+            SkipReason::Author(pattern.to_string()),
+        );
+    }
+    kept
+}
+
+/// Helper function to apply `--visibility`, recording the files it drops entirely
+fn filter_by_visibility(
+    results: Vec<SearchResult>,
+    filter: VisibilityFilter,
+    report_skipped: bool,
+    report: &mut Vec<SkippedFile>,
+) -> Vec<SearchResult> {
+    if filter == VisibilityFilter::Any {
+        return results;
+    }
+    let files_before: Vec<PathBuf> = results.iter().map(|r| r.path()).collect();
+    let kept: Vec<SearchResult> = results
+        .into_iter()
+        .filter(|result| {
+            let language = result.language.as_deref().unwrap_or("");
+            filter.accepts(block_visibility(language, &result.code))
+        })
+        .collect();
+    if report_skipped {
+        let files_after: Vec<PathBuf> = kept.iter().map(|r| r.path()).collect();
+        skip_report::record_dropped_files(
+            report,
+            files_before.iter().map(PathBuf::as_path),
+            files_after.iter().map(PathBuf::as_path),
+            SkipReason::Visibility(filter),
+        );
+    }
+    kept
+}
+
+/// Helper function to apply `--kind`, recording the files it drops entirely
+fn filter_by_kind(
+    results: Vec<SearchResult>,
+    kinds: &[ResultKind],
+    report_skipped: bool,
+    report: &mut Vec<SkippedFile>,
+) -> Vec<SearchResult> {
+    if kinds.is_empty() {
+        return results;
+    }
+    let files_before: Vec<PathBuf> = results.iter().map(|r| r.path()).collect();
+    let kept: Vec<SearchResult> = results
+        .into_iter()
+        .filter(|result| result.kind.is_some_and(|kind| kinds.contains(&kind)))
+        .collect();
+    if report_skipped {
+        let files_after: Vec<PathBuf> = kept.iter().map(|r| r.path()).collect();
+        let names: Vec<&str> = kinds.iter().map(ResultKind::as_str).collect();
+        skip_report::record_dropped_files(
+            report,
+            files_before.iter().map(PathBuf::as_path),
+            files_after.iter().map(PathBuf::as_path),
+            SkipReason::Kind(names.join(", ")),
+        );
+    }
+    kept
+}
+
+/// Helper function to apply limits in the requested result order
+fn apply_ordered_limits(
+    mut results: Vec<SearchResult>,
+    order: ResultOrder,
+    max_results: Option<usize>,
+    max_bytes: Option<usize>,
+    max_tokens: Option<usize>,
+) -> LimitedSearchResults {
+    match order {
+        ResultOrder::Score => apply_limits(results, max_results, max_bytes, max_tokens),
+        ResultOrder::Path | ResultOrder::Complexity => {
+            sort_in_order(&mut results, order);
+            apply_limits_in_order(results, max_results, max_bytes, max_tokens)
+        }
+    }
+}
+
+/// Results the limits kept from the files processed so far, with their size
+#[derive(Debug, Default)]
+struct KeptResults {
+    results: usize,
+    bytes: usize,
+    tokens: usize,
+}
+
+/// Helper function to check whether the results so far already fill `--max-results`
+///
+/// Runs the per-block filters that come before the limits on a copy of the latest batch of
+/// results, and adds the ones the limits keep to `kept`. Files are processed in path order,
+/// so each batch sorts after the ones before it. Ranking keeps every block and only changes
+/// scores, so it is not needed for the count.
+fn limit_filled(
+    batch: &[SearchResult],
+    visibility_filter: VisibilityFilter,
+    excluded: &Session,
+    session: Option<&Session>,
+    (max_results, max_bytes, max_tokens): (usize, Option<usize>, Option<usize>),
+    kept: &mut KeptResults,
+) -> bool {
+    let mut survivors = batch.to_vec();
+    apply_directives(&mut survivors);
+    survivors = filter_by_visibility(survivors, visibility_filter, false, &mut Vec::new());
+    let (mut survivors, _) = excluded.filter_results(&survivors);
+    if let Some(session) = session {
+        survivors = session.filter_results(&survivors).0;
+    }
+    sort_by_path(&mut survivors);
+
+    // What is left of the budgets after the earlier batches
+    let limited = apply_limits_in_order(
+        survivors,
+        Some(max_results.saturating_sub(kept.results)),
+        max_bytes.map(|max| max.saturating_sub(kept.bytes)),
+        max_tokens.map(|max| max.saturating_sub(kept.tokens)),
+    );
+    kept.results += limited.results.len();
+    if let Some(limits) = limited.limits_applied {
+        kept.bytes += limits.total_bytes;
+        kept.tokens += limits.total_tokens;
+    }
+    kept.results >= max_results
+}
+
+/// Helper function to record files that were dropped entirely by result limits
+fn record_limit_skips(
+    report: &mut Vec<SkippedFile>,
+    candidate_files: &[PathBuf],
+    limited: &LimitedSearchResults,
+    max_results: Option<usize>,
+) {
+    // If the result count limit was hit, that is what stopped everything after it;
+    // otherwise the file was too large for the byte/token budget
+    let reason = if max_results.is_some_and(|max| limited.results.len() >= max) {
+        SkipReason::Limit
+    } else {
+        SkipReason::TooLarge
+    };
+    let kept_files: Vec<PathBuf> = limited.results.iter().map(|r| r.path()).collect();
+    skip_report::record_dropped_files(
+        report,
+        candidate_files.iter().map(PathBuf::as_path),
+        kept_files.iter().map(PathBuf::as_path),
+        reason,
+    );
+}
+
+/// Helper function to keep merged results within the byte and token budgets
+///
+/// A merged block takes in the lines between the blocks it joins, so it can go over a
+/// budget those blocks kept to. Results are kept in order while they fit, and a merged
+/// block that doesn't is split back into the `blocks` it joined, each kept if it fits.
+/// Returns the kept results and the dropped ones.
+fn limit_merged_results(
+    merged: Vec<SearchResult>,
+    blocks: &[SearchResult],
+    max_bytes: Option<usize>,
+    max_tokens: Option<usize>,
+) -> (Vec<SearchResult>, Vec<SearchResult>) {
+    let mut kept = Vec::new();
+    let mut dropped = Vec::new();
+    let mut total_bytes = 0;
+    let mut total_tokens = 0;
+    let mut fits = |result: &SearchResult| {
+        let bytes = result.code.len();
+        let tokens = count_tokens(&result.code);
+        if max_bytes.is_some_and(|max| total_bytes + bytes > max)
+            || max_tokens.is_some_and(|max| total_tokens + tokens > max)
+        {
+            return false;
+        }
+        total_bytes += bytes;
+        total_tokens += tokens;
+        true
+    };
+
+    for result in merged {
+        if fits(&result) {
+            kept.push(result);
+            continue;
+        }
+        let joined: Vec<&SearchResult> = blocks
+            .iter()
+            .filter(|block| {
+                block.file == result.file
+                    && result.lines.0 <= block.lines.0
+                    && block.lines.1 <= result.lines.1
+            })
+            .collect();
+        if joined.len() < 2 {
+            dropped.push(result);
+            continue;
+        }
+        for block in joined {
+            if fits(block) {
+                kept.push(block.clone());
+            } else {
+                dropped.push(block.clone());
+            }
+        }
+    }
+    (kept, dropped)
+}
+
 /// Helper function to search files using structured patterns from a QueryPlan.
-/// This function uses a single-pass approach with processing to search for patterns
-/// and collects matches by term indices. It uses the file_list_cache to get a filtered
+/// This function uses a single-pass approach, searching files in parallel on the rayon
+/// thread pool, to search for patterns and collects matches by term indices. It uses the file_list_cache to get a filtered
 /// list of files respecting ignore patterns.
 ///
 /// # Arguments
@@ -877,17 +2218,30 @@ pub fn perform_probe(options: &SearchOptions) -> Result<LimitedSearchResults> {
 /// * `patterns` - The generated regex patterns with their term indices
 /// * `custom_ignores` - Custom ignore patterns
 /// * `allow_tests` - Whether to include test files
+/// * `follow_symlinks` - Whether to follow symbolic links while scanning
+/// * `filters` - Depth, modification time and size limits for the scan
+/// * `guards` - Line length and match count limits for the scan
+/// * `capped` - Receives the files whose scan the guards cut short
+/// * `skipped_files` - When provided, receives files that could not be searched
+#[allow(clippy::too_many_arguments)]
 pub fn search_with_structured_patterns(
     root_path: &Path,
     _plan: &QueryPlan,
     patterns: &[(String, HashSet<usize>)],
     custom_ignores: &[String],
     allow_tests: bool,
+    follow_symlinks: bool,
+    filters: FileFilters,
+    case_fold: bool,
+    guards: MatchGuards,
+    capped: &mut Vec<CappedFile>,
+    mut skipped_files: Option<&mut Vec<SkippedFile>>,
 ) -> Result<HashMap<PathBuf, HashMap<usize, HashSet<usize>>>> {
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
     let search_start = Instant::now();
+    let cancel = current_token();
 
-    // Step 1: Create combined regex
+    // Step 1: Create combined regex, or regex sets when it would be too large
     if debug_mode {
         println!("DEBUG: Starting single-pass structured pattern search...");
         println!(
@@ -896,18 +2250,19 @@ pub fn search_with_structured_patterns(
         );
     }
 
-    let combined_pattern = patterns
-        .iter()
-        .map(|(p, _)| format!("({})", p))
-        .collect::<Vec<_>>()
-        .join("|");
-
-    let combined_regex = regex::Regex::new(&format!("(?i){}", combined_pattern))?;
+    let pattern_strings: Vec<String> = patterns.iter().map(|(p, _)| p.clone()).collect();
+    let matcher = PatternMatcher::cached(&pattern_strings)?;
     let pattern_to_terms: Vec<HashSet<usize>> =
         patterns.iter().map(|(_, terms)| terms.clone()).collect();
 
     if debug_mode {
-        println!("DEBUG: Combined regex created successfully");
+        match matcher.as_ref() {
+            PatternMatcher::Combined(..) => println!("DEBUG: Combined regex created successfully"),
+            PatternMatcher::Sets(_) => println!(
+                "DEBUG: Combined regex too large, matching with {} regex sets",
+                matcher.passes()
+            ),
+        }
     }
 
     // Step 2: Get filtered file list from cache
@@ -917,13 +2272,25 @@ pub fn search_with_structured_patterns(
     }
 
     // Use file_list_cache to get a filtered list of files
-    let file_list =
-        crate::search::file_list_cache::get_file_list(root_path, allow_tests, custom_ignores)?;
+    let file_list = crate::search::file_list_cache::get_file_list(
+        root_path,
+        allow_tests,
+        custom_ignores,
+        follow_symlinks,
+        filters,
+    )?;
+    for error in &file_list.errors {
+        io_errors::record_file_error(error.clone());
+    }
 
     if debug_mode {
         println!("DEBUG: Got {} files from cache", file_list.files.len());
     }
 
+    // Files unchanged since indexing whose tokens no pattern matches are not read
+    let index_filter = IndexFilter::for_search(root_path, &pattern_strings, &matcher, case_fold);
+    let skipped_by_index = AtomicUsize::new(0);
+
     // Step 3: Process files
     let mut file_term_maps = HashMap::new();
 
@@ -931,34 +2298,134 @@ pub fn search_with_structured_patterns(
         println!("DEBUG: Starting file processing with combined regex");
     }
 
-    for file_path in &file_list.files {
-        // Search file with combined pattern
-        match search_file_with_combined_pattern(file_path, &combined_regex, &pattern_to_terms) {
-            Ok(term_map) => {
-                if !term_map.is_empty() {
-                    if debug_mode {
-                        println!(
-                            "DEBUG: File {:?} matched combined pattern with {} term indices",
-                            file_path,
-                            term_map.len()
-                        );
-                    }
+    // With --max-total-matches, files are searched a few batches at a time so that the
+    // search stops soon after the limit is reached; otherwise all at once
+    let batch_size = match guards.max_total_matches {
+        Some(_) => rayon::current_num_threads() * 4,
+        None => file_list.files.len().max(1),
+    };
 
-                    // Add to results
-                    file_term_maps.insert(file_path.clone(), term_map);
+    // Files are read and matched on rayon threads, which take this thread's file system
+    let file_system = fs_access::current();
+
+    // Results are collected on this thread in file order, so the limit cuts off at the same
+    // file however the batch was scheduled
+    let mut total_matches = 0;
+    let mut files_searched = 0;
+    'batches: for batch in file_list.files.chunks(batch_size) {
+        let searched: Vec<_> = batch
+            .par_iter()
+            .map(|file_path| {
+                // Once cancelled, files not started yet are left alone
+                if let Err(e) = cancellation::check(cancel.as_ref()) {
+                    return (file_path, Err(e));
                 }
+                fs_access::with_file_system(Arc::clone(&file_system), || {
+                    if index_filter
+                        .as_ref()
+                        .is_some_and(|filter| filter.can_skip(file_path))
+                    {
+                        skipped_by_index.fetch_add(1, Ordering::Relaxed);
+                        return (file_path, Ok((HashMap::new(), FileCaps::default())));
+                    }
+                    let searched = search_file_with_combined_pattern(
+                        file_path,
+                        &matcher,
+                        &pattern_to_terms,
+                        case_fold,
+                        &guards,
+                    );
+                    (file_path, searched)
+                })
+            })
+            .collect();
+        cancellation::check(cancel.as_ref())?;
+
+        for (file_path, searched) in searched {
+            if guards
+                .max_total_matches
+                .is_some_and(|max| total_matches >= max)
+            {
+                break 'batches;
             }
-            Err(e) => {
-                if debug_mode {
-                    println!("DEBUG: Error searching file {:?}: {:?}", file_path, e);
+            files_searched += 1;
+
+            match searched {
+                Ok((term_map, caps)) => {
+                    capped.extend(caps.report(file_path, &guards));
+                    if !term_map.is_empty() {
+                        total_matches += term_map.values().flatten().collect::<HashSet<_>>().len();
+                        if debug_mode {
+                            println!(
+                                "DEBUG: File {:?} matched combined pattern with {} term indices",
+                                file_path,
+                                term_map.len()
+                            );
+                        }
+
+                        // Add to results
+                        file_term_maps.insert(file_path.clone(), term_map);
+                    }
+                }
+                Err(e) => {
+                    if debug_mode {
+                        println!("DEBUG: Error searching file {:?}: {:?}", file_path, e);
+                    }
+                    io_errors::record_if_io(file_path, &e);
+                    if let Some(skipped) = skipped_files.as_deref_mut() {
+                        let reason = match e.downcast_ref::<std::io::Error>() {
+                            Some(io_err) if io_err.kind() == std::io::ErrorKind::InvalidData => {
+                                SkipReason::Binary
+                            }
+                            _ => SkipReason::Unreadable(e.to_string()),
+                        };
+                        skipped.push(SkippedFile {
+                            path: file_path.clone(),
+                            is_dir: false,
+                            reason,
+                        });
+                    }
                 }
             }
         }
     }
 
+    if files_searched < file_list.files.len() {
+        let unsearched = &file_list.files[files_searched..];
+        if debug_mode {
+            println!(
+                "DEBUG: Reached --max-total-matches after {} matched lines, not searching {} files",
+                total_matches,
+                unsearched.len()
+            );
+        }
+        // Reported on the file whose matches reached the limit
+        let reached_in = files_searched
+            .checked_sub(1)
+            .map(|previous| file_list.files[previous].clone())
+            .unwrap_or_else(|| root_path.to_path_buf());
+        capped.push(CappedFile {
+            path: reached_in,
+            reason: CapReason::TotalMatches(unsearched.len()),
+        });
+        if let Some(skipped) = skipped_files {
+            skipped.extend(unsearched.iter().map(|path| SkippedFile {
+                path: path.clone(),
+                is_dir: false,
+                reason: SkipReason::MatchLimit,
+            }));
+        }
+    }
+
     let total_duration = search_start.elapsed();
 
     if debug_mode {
+        if index_filter.is_some() {
+            println!(
+                "DEBUG: Left {} files unread by the index",
+                skipped_by_index.load(Ordering::Relaxed)
+            );
+        }
         println!(
             "DEBUG: Single-pass search completed in {} - Found matches in {} files",
             format_duration(total_duration),
@@ -966,12 +2433,18 @@ pub fn search_with_structured_patterns(
         );
     }
 
+    telemetry::emit(TelemetryEvent::FilesScanned {
+        files: file_list.files.len(),
+        matched_files: file_term_maps.len(),
+    });
+
     Ok(file_term_maps)
 }
 
-/// Helper function to search a file with a combined regex pattern
-/// This function searches a file for matches against a combined regex pattern
-/// and maps the matches to their corresponding term indices.
+/// Helper function to search a file with the compiled search patterns
+/// This function searches a file for matches against the combined regex pattern
+/// (or the regex sets standing in for it) and maps the matches to their
+/// corresponding term indices.
 ///
 /// It processes all matching capture groups in each regex match, ensuring that
 /// if multiple patterns match in a single capture, all of them are properly recorded.
@@ -979,27 +2452,54 @@ pub fn search_with_structured_patterns(
 /// simultaneously, ensuring search stability and consistent results.
 fn search_file_with_combined_pattern(
     file_path: &Path,
-    combined_regex: &regex::Regex,
+    matcher: &PatternMatcher,
     pattern_to_terms: &[HashSet<usize>],
-) -> Result<HashMap<usize, HashSet<usize>>> {
+    case_fold: bool,
+    guards: &MatchGuards,
+) -> Result<(HashMap<usize, HashSet<usize>>, FileCaps)> {
     let mut term_map = HashMap::new();
+    let mut matched_lines = HashSet::new();
+    let mut caps = FileCaps::default();
     let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
 
-    // Read the file content
-    let content = match std::fs::read_to_string(file_path) {
+    // Read the file content, mapping large files rather than copying them
+    let content = match fs_access::read_text(file_path) {
         Ok(content) => content,
         Err(e) => {
             if debug_mode {
                 println!("DEBUG: Error reading file {:?}: {:?}", file_path, e);
             }
-            return Err(anyhow::anyhow!("Failed to read file: {}", e));
+            return Err(anyhow::Error::new(e).context("Failed to read file"));
         }
     };
 
     // Process each line
     for (line_number, line) in content.lines().enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                if debug_mode {
+                    println!("DEBUG: Error reading file {:?}: {:?}", file_path, e);
+                }
+                return Err(anyhow::Error::new(e).context("Failed to read file"));
+            }
+        };
+        if matched_lines.len() >= guards.max_matches_per_file {
+            if debug_mode {
+                println!(
+                    "DEBUG: Stopping at line {} in file {:?} - reached {} matched lines",
+                    line_number + 1,
+                    file_path,
+                    guards.max_matches_per_file
+                );
+            }
+            caps.matches_capped = true;
+            break;
+        }
+
         // Skip lines that are too long
-        if line.len() > 2000 {
+        if line.len() > guards.max_line_length {
+            caps.long_lines += 1;
             if debug_mode {
                 println!(
                     "DEBUG: Skipping line {} in file {:?} - line too long ({} characters)",
@@ -1011,27 +2511,21 @@ fn search_file_with_combined_pattern(
             continue;
         }
 
+        // Match composed and decomposed forms of non-ASCII identifiers alike
+        let line = normalize(line, case_fold);
+
         // Find all matches in the line
-        for cap in combined_regex.captures_iter(line) {
-            // Check all possible pattern groups in this capture
-            for i in 1..=pattern_to_terms.len() {
-                if cap.get(i).is_some() {
-                    let pattern_idx = i - 1;
-
-                    // Add matches for all terms associated with this pattern
-                    for &term_idx in &pattern_to_terms[pattern_idx] {
-                        term_map
-                            .entry(term_idx)
-                            .or_insert_with(HashSet::new)
-                            .insert(line_number + 1); // Convert to 1-based line numbers
-                    }
-                    
-                    // Note: We removed the break statement here to process all matching groups
-                    // in a capture, not just the first one. This fixes the search instability issue.
-                }
+        matcher.for_each_match(&line, |pattern_idx| {
+            // Add matches for all terms associated with this pattern
+            for &term_idx in &pattern_to_terms[pattern_idx] {
+                term_map
+                    .entry(term_idx)
+                    .or_insert_with(HashSet::new)
+                    .insert(line_number + 1); // Convert to 1-based line numbers
             }
-        }
+            matched_lines.insert(line_number + 1);
+        });
     }
 
-    Ok(term_map)
+    Ok((term_map, caps))
 }
@@ -0,0 +1,461 @@
+//! Search sessions: the set of code blocks already returned to a caller.
+//!
+//! A session remembers the blocks it has been given so later searches can skip them.
+//! Sessions are plain values: they can live only in memory, or be kept in a
+//! [`SessionStore`] of the caller's choosing, so several independent sessions can be used
+//! in one process. The CLI keeps its sessions under `~/.cache/probe/sessions`.
+//!
+//! A session also remembers the queries that found nothing, see [`empty_queries`], so
+//! sending one again over the same files answers at once.
+//!
+//! Stored sessions may be shared by several processes, e.g. parallel tool calls of an agent
+//! using the same session ID. Saving merges in blocks saved by others in the meantime, so no
+//! process loses another's blocks.
+//!
+//! [`empty_queries`]: crate::search::empty_queries
+
+use anyhow::{anyhow, Result};
+use rand::{distributions::Alphanumeric, Rng};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::models::SearchResult;
+use crate::search::session_store::{JsonFileStore, SessionStore};
+
+/// Prefix of the stored identifiers of queries that found nothing, kept with the blocks
+///
+/// Their keys are hex digits, so they never parse as a block identifier.
+const EMPTY_QUERY_PREFIX: &str = "empty-query:";
+
+/// Blocks seen in one session
+#[derive(Debug, Clone)]
+pub struct Session {
+    /// Session identifier
+    id: String,
+    /// Identifiers of the blocks returned so far
+    /// Format: "file.rs:23-45" (file path with start-end line numbers)
+    seen: HashSet<String>,
+    /// Keys of the queries that found nothing
+    empty_queries: HashSet<String>,
+    /// Blocks and empty queries recorded since the session was loaded, which the next save
+    /// writes
+    unsaved: Vec<String>,
+    /// Where the session is stored, `None` for in-memory sessions
+    store: Option<Arc<dyn SessionStore>>,
+}
+
+impl Session {
+    /// Create an empty in-memory session
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            seen: HashSet::new(),
+            empty_queries: HashSet::new(),
+            unsaved: Vec::new(),
+            store: None,
+        }
+    }
+
+    /// Create an in-memory session that has already seen `block_ids`
+    ///
+    /// Used for `--exclude-blocks`, whose blocks are skipped the same way seen blocks are.
+    pub fn with_blocks(id: impl Into<String>, block_ids: &[String]) -> Self {
+        let mut session = Self::new(id);
+        session.seen = block_ids.iter().cloned().collect();
+        session
+    }
+
+    /// Create an empty session with a fresh 4-character ID, stored as a JSON file in `dir`
+    ///
+    /// The ID is chosen so that it does not collide with a session already in `dir`.
+    #[allow(dead_code)]
+    pub fn create(dir: &Path) -> Result<Self> {
+        Self::create_in(Arc::new(JsonFileStore::new(dir)))
+    }
+
+    /// Create an empty session with a fresh 4-character ID, kept in `store`
+    pub fn create_in(store: Arc<dyn SessionStore>) -> Result<Self> {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+        // Try up to 10 times to generate a unique session ID
+        for _ in 0..10 {
+            let id: String = rand::thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(4)
+                .map(char::from)
+                .collect::<String>()
+                .to_lowercase();
+
+            if debug_mode {
+                println!("DEBUG: Generated session ID: {}", id);
+            }
+
+            if !store.exists(&id)? {
+                let mut session = Self::new(id);
+                session.store = Some(store);
+                return Ok(session);
+            }
+        }
+
+        Err(anyhow!(
+            "Failed to generate a unique session ID after multiple attempts"
+        ))
+    }
+
+    /// Load the session `id` stored as a JSON file in `dir`
+    ///
+    /// A session that has not been saved yet starts out empty. An unreadable or corrupt
+    /// session file is treated the same way, so a damaged cache never stops a search.
+    #[allow(dead_code)]
+    pub fn load(dir: &Path, id: &str) -> Result<Self> {
+        Self::load_from(Arc::new(JsonFileStore::new(dir)), id)
+    }
+
+    /// Load the session `id` kept in `store`; a session that was never saved starts out empty
+    pub fn load_from(store: Arc<dyn SessionStore>, id: &str) -> Result<Self> {
+        let mut session = Self::new(id);
+        for stored in store.load(id)? {
+            match stored.strip_prefix(EMPTY_QUERY_PREFIX) {
+                Some(key) if Self::parse_block_id(&stored).is_none() => {
+                    session.empty_queries.insert(key.to_string());
+                }
+                _ => {
+                    session.seen.insert(stored);
+                }
+            }
+        }
+        session.store = Some(store);
+        Ok(session)
+    }
+
+    /// Directory the CLI keeps its sessions in
+    pub fn default_dir() -> PathBuf {
+        let home_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+        home_dir.join(".cache").join("probe").join("sessions")
+    }
+
+    /// Path of the JSON file for session `id` in `dir`
+    #[allow(dead_code)]
+    pub fn file_path(dir: &Path, id: &str) -> PathBuf {
+        JsonFileStore::new(dir).file_path(id)
+    }
+
+    /// The session identifier
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The file this session is stored in, `None` for in-memory sessions
+    #[allow(dead_code)]
+    pub fn path(&self) -> Option<PathBuf> {
+        self.store
+            .as_ref()
+            .and_then(|store| store.location(&self.id))
+    }
+
+    /// Write the blocks recorded since the session was loaded to its store; in-memory
+    /// sessions are left as they are
+    ///
+    /// Blocks that other processes saved to the same session since it was loaded are kept
+    /// in the store as well.
+    pub fn save(&self) -> Result<()> {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+        let Some(store) = &self.store else {
+            return Ok(());
+        };
+
+        if debug_mode {
+            println!(
+                "DEBUG: Saving {} new of {} cache entries to {:?}",
+                self.unsaved.len(),
+                self.seen.len(),
+                store
+            );
+        }
+
+        store.append(&self.id, &self.unsaved)?;
+
+        if debug_mode {
+            println!("DEBUG: Successfully saved cache to disk");
+        }
+
+        Ok(())
+    }
+
+    /// Forget every block, removing the stored session if there is one
+    #[allow(dead_code)]
+    pub fn clear(&mut self) -> Result<()> {
+        self.seen.clear();
+        self.empty_queries.clear();
+        self.unsaved.clear();
+        if let Some(store) = &self.store {
+            store.remove(&self.id)?;
+        }
+        Ok(())
+    }
+
+    /// Record results as seen, returning how many of them were new
+    pub fn record(&mut self, results: &[SearchResult]) -> usize {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+        let mut new_entries = 0;
+        for result in results {
+            if self.insert(Self::block_id(result)) {
+                new_entries += 1;
+            }
+        }
+
+        if debug_mode {
+            println!(
+                "DEBUG: Added {} new entries to session {}, now {} entries",
+                new_entries,
+                self.id,
+                self.seen.len()
+            );
+        }
+
+        new_entries
+    }
+
+    /// Record blocks as seen by their identifiers, returning how many of them were new
+    ///
+    /// Used for `--exclude-blocks`, so later searches in the session skip them as well.
+    pub fn record_block_ids(&mut self, block_ids: &[String]) -> usize {
+        block_ids
+            .iter()
+            .filter(|block_id| self.insert((*block_id).clone()))
+            .count()
+    }
+
+    /// Helper function to add a block identifier, returning whether it was new
+    fn insert(&mut self, block_id: String) -> bool {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+        if self.seen.contains(&block_id) {
+            return false;
+        }
+        if debug_mode && self.unsaved.len() < 5 {
+            println!("DEBUG: Adding new cache entry: {}", block_id);
+        }
+        self.seen.insert(block_id.clone());
+        self.unsaved.push(block_id);
+        true
+    }
+
+    /// Remember that the query with `key` found nothing
+    ///
+    /// Keys come from [`query_key`](crate::search::empty_queries::query_key).
+    pub fn record_empty_query(&mut self, key: &str) {
+        if self.empty_queries.insert(key.to_string()) {
+            self.unsaved.push(format!("{}{}", EMPTY_QUERY_PREFIX, key));
+        }
+    }
+
+    /// Whether the query with `key` found nothing before in this session
+    pub fn found_nothing_for(&self, key: &str) -> bool {
+        self.empty_queries.contains(key)
+    }
+
+    /// Whether this exact block was returned before
+    #[allow(dead_code)]
+    pub fn has_seen(&self, result: &SearchResult) -> bool {
+        self.seen.contains(&Self::block_id(result))
+    }
+
+    /// Identifiers of the blocks seen so far, in no particular order
+    pub fn seen_blocks(&self) -> impl Iterator<Item = &str> {
+        self.seen.iter().map(String::as_str)
+    }
+
+    /// Number of blocks seen so far
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    /// Whether no block has been seen yet
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    /// Identifier of a result's block
+    /// Format: "file.rs:23-45" (file path with start-end line numbers)
+    pub fn block_id(result: &SearchResult) -> String {
+        format!("{}:{}-{}", result.file, result.lines.0, result.lines.1)
+    }
+
+    /// Split a block identifier into its file and 1-based line range
+    pub fn parse_block_id(block_id: &str) -> Option<(&str, usize, usize)> {
+        let (file, range) = block_id.rsplit_once(':')?;
+        let (start, end) = range.split_once('-')?;
+        Some((file, start.parse().ok()?, end.parse().ok()?))
+    }
+
+    /// Drop results that were already seen, returning the rest and the number dropped
+    pub fn filter_results(&self, results: &[SearchResult]) -> (Vec<SearchResult>, usize) {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+        if self.seen.is_empty() {
+            if debug_mode {
+                println!("DEBUG: Cache is empty, not filtering results");
+            }
+            return (results.to_vec(), 0);
+        }
+
+        let mut skipped_count = 0;
+        let filtered: Vec<SearchResult> = results
+            .iter()
+            .filter(|result| {
+                let block_id = Self::block_id(result);
+                if self.seen.contains(&block_id) {
+                    if debug_mode && skipped_count < 5 {
+                        println!("DEBUG: Skipping cached block: {}", block_id);
+                    }
+                    skipped_count += 1;
+                    false
+                } else {
+                    true
+                }
+            })
+            .cloned()
+            .collect();
+
+        if debug_mode {
+            println!(
+                "DEBUG: Filtered out {} cached blocks, returning {} results",
+                skipped_count,
+                filtered.len()
+            );
+        }
+
+        (filtered, skipped_count)
+    }
+
+    /// Seen line ranges per file, parsed from the block identifiers
+    fn seen_ranges(&self) -> HashMap<&str, Vec<(usize, usize)>> {
+        let mut seen_ranges: HashMap<&str, Vec<(usize, usize)>> = HashMap::new();
+        for block_id in &self.seen {
+            if let Some((file, start, end)) = Self::parse_block_id(block_id) {
+                seen_ranges.entry(file).or_default().push((start, end));
+            }
+        }
+        seen_ranges
+    }
+
+    /// Files that seen blocks came from, sorted
+    pub fn seen_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self.seen_ranges().into_keys().map(PathBuf::from).collect();
+        files.sort();
+        files
+    }
+
+    /// Keep only matched lines that fall inside a seen block (`--within-session`)
+    ///
+    /// The opposite of [`Session::filter_matched_lines`]: a new query is answered from the
+    /// blocks returned before. Files left without matches are removed from the map. Returns
+    /// the number of lines dropped.
+    pub fn restrict_matched_lines(
+        &self,
+        file_term_map: &mut HashMap<PathBuf, HashMap<usize, HashSet<usize>>>,
+    ) -> usize {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+        let seen_ranges = self.seen_ranges();
+
+        let mut dropped_count = 0;
+        file_term_map.retain(|file_path, term_map| {
+            let file = file_path.to_string_lossy();
+            let ranges = seen_ranges.get(file.as_ref());
+            for lines in term_map.values_mut() {
+                let before = lines.len();
+                lines.retain(|&line| {
+                    ranges.is_some_and(|ranges| {
+                        ranges
+                            .iter()
+                            .any(|&(start, end)| line >= start && line <= end)
+                    })
+                });
+                dropped_count += before - lines.len();
+            }
+            term_map.retain(|_, lines| !lines.is_empty());
+            !term_map.is_empty()
+        });
+
+        if debug_mode {
+            println!(
+                "DEBUG: Restricted matches to session {}: dropped {} lines, {} files remain",
+                self.id,
+                dropped_count,
+                file_term_map.len()
+            );
+        }
+
+        dropped_count
+    }
+
+    /// Drop matched lines that fall inside a seen block, before blocks are extracted
+    ///
+    /// Files left without matches are removed from the map. Returns the number of lines dropped.
+    pub fn filter_matched_lines(
+        &self,
+        file_term_map: &mut HashMap<PathBuf, HashMap<usize, HashSet<usize>>>,
+    ) -> usize {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+        if self.seen.is_empty() {
+            if debug_mode {
+                println!("DEBUG: Cache is empty, not filtering matched lines");
+            }
+            return 0;
+        }
+
+        let seen_ranges = self.seen_ranges();
+        let mut skipped_count = 0;
+        file_term_map.retain(|file_path, term_map| {
+            let file = file_path.to_string_lossy();
+            let Some(ranges) = seen_ranges.get(file.as_ref()) else {
+                return true;
+            };
+            if term_map.is_empty() {
+                return true;
+            }
+
+            let mut lines_to_remove = HashSet::new();
+            for lines in term_map.values() {
+                for &line in lines {
+                    if ranges
+                        .iter()
+                        .any(|&(start, end)| line >= start && line <= end)
+                    {
+                        lines_to_remove.insert(line);
+                    }
+                }
+            }
+
+            if debug_mode && !lines_to_remove.is_empty() {
+                println!(
+                    "DEBUG: Skipping {} cached lines in {:?}",
+                    lines_to_remove.len(),
+                    file_path
+                );
+            }
+            skipped_count += lines_to_remove.len();
+
+            for lines in term_map.values_mut() {
+                lines.retain(|line| !lines_to_remove.contains(line));
+            }
+            term_map.retain(|_, lines| !lines.is_empty());
+            !term_map.is_empty()
+        });
+
+        if debug_mode {
+            println!(
+                "DEBUG: Early filtering removed {} cached lines, {} files remain",
+                skipped_count,
+                file_term_map.len()
+            );
+        }
+
+        skipped_count
+    }
+}
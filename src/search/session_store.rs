@@ -0,0 +1,335 @@
+//! Storage backends for search sessions.
+//!
+//! A [`SessionStore`] keeps the block identifiers of any number of sessions. Two backends
+//! are provided: [`JsonFileStore`], one JSON file per session (the default), and
+//! [`SqliteStore`], a single SQLite database that only writes the blocks a search adds,
+//! for long sessions with thousands of blocks. The CLI picks the backend with
+//! `PROBE_SESSION_BACKEND` (`json` or `sqlite`).
+
+use crate::fs_access;
+use anyhow::{anyhow, Context, Result};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Where sessions are kept
+pub trait SessionStore: Send + Sync + Debug {
+    /// Whether session `id` has been stored
+    fn exists(&self, id: &str) -> Result<bool>;
+
+    /// Blocks stored for session `id`, empty if it was never stored
+    fn load(&self, id: &str) -> Result<HashSet<String>>;
+
+    /// Add `blocks` to session `id`, storing the session even if `blocks` is empty
+    ///
+    /// Blocks other writers added to the same session are kept.
+    fn append(&self, id: &str, blocks: &[String]) -> Result<()>;
+
+    /// Remove session `id` and its blocks
+    fn remove(&self, id: &str) -> Result<()>;
+
+    /// File that holds session `id`, if the store is file based
+    fn location(&self, id: &str) -> Option<PathBuf>;
+}
+
+/// The store the CLI uses, chosen with `PROBE_SESSION_BACKEND`
+pub fn default_store(dir: &Path) -> Result<Arc<dyn SessionStore>> {
+    let backend = std::env::var("PROBE_SESSION_BACKEND").unwrap_or_default();
+    match backend.as_str() {
+        "" | "json" => Ok(Arc::new(JsonFileStore::new(dir))),
+        "sqlite" => open_sqlite(dir),
+        other => Err(anyhow!(
+            "Unknown PROBE_SESSION_BACKEND '{}': use json or sqlite",
+            other
+        )),
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn open_sqlite(dir: &Path) -> Result<Arc<dyn SessionStore>> {
+    Ok(Arc::new(SqliteStore::open(&dir.join("sessions.db"))?))
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn open_sqlite(_dir: &Path) -> Result<Arc<dyn SessionStore>> {
+    Err(anyhow!(
+        "PROBE_SESSION_BACKEND=sqlite needs probe built with the `sqlite` feature"
+    ))
+}
+
+/// On-disk format of a JSON session file
+#[derive(Serialize, Deserialize)]
+struct StoredSession {
+    session_id: String,
+    /// Format: "file.rs:23-45" (file path with start-end line numbers)
+    block_identifiers: HashSet<String>,
+}
+
+/// One `<id>.json` file per session in a directory
+///
+/// Writers take an exclusive lock on `<id>.json.lock`, merge in blocks saved by others in
+/// the meantime and replace the file atomically, so readers never see a partial file.
+#[derive(Debug, Clone)]
+pub struct JsonFileStore {
+    dir: PathBuf,
+}
+
+impl JsonFileStore {
+    /// Keep sessions in `dir`, which is created on the first write
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Path of the file for session `id`
+    pub fn file_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    /// Take the exclusive lock that guards changes to session `id`
+    ///
+    /// The lock is released when the returned file is dropped.
+    fn lock(&self, id: &str) -> Result<File> {
+        let file_system = fs_access::current();
+        file_system
+            .create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create session directory {:?}", self.dir))?;
+        let lock_path = self.dir.join(format!("{}.json.lock", id));
+        file_system.check_write(&lock_path)?;
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("Failed to open session lock {:?}", lock_path))?;
+        lock.lock()
+            .with_context(|| format!("Failed to lock session {:?}", lock_path))?;
+        Ok(lock)
+    }
+}
+
+impl SessionStore for JsonFileStore {
+    fn exists(&self, id: &str) -> Result<bool> {
+        Ok(fs_access::exists(self.file_path(id)))
+    }
+
+    /// An unreadable or corrupt session file counts as empty, so a damaged cache never
+    /// stops a search
+    fn load(&self, id: &str) -> Result<HashSet<String>> {
+        let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+        let path = self.file_path(id);
+
+        if !fs_access::exists(&path) {
+            if debug_mode {
+                println!(
+                    "DEBUG: Cache file does not exist at {:?}, creating new cache",
+                    path
+                );
+            }
+            return Ok(HashSet::new());
+        }
+
+        if debug_mode {
+            println!("DEBUG: Loading cache from {:?}", path);
+        }
+
+        let contents = match fs_access::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                if debug_mode {
+                    println!("DEBUG: Error reading cache file: {}", e);
+                }
+                return Ok(HashSet::new());
+            }
+        };
+
+        match serde_json::from_str::<StoredSession>(&contents) {
+            Ok(stored) => {
+                if debug_mode {
+                    println!(
+                        "DEBUG: Successfully loaded cache with {} entries",
+                        stored.block_identifiers.len()
+                    );
+                }
+                Ok(stored.block_identifiers)
+            }
+            Err(e) => {
+                if debug_mode {
+                    println!("DEBUG: Error parsing cache JSON: {}", e);
+                }
+                Ok(HashSet::new())
+            }
+        }
+    }
+
+    fn append(&self, id: &str, blocks: &[String]) -> Result<()> {
+        let _lock = self.lock(id)?;
+
+        let mut block_identifiers = self.load(id)?;
+        block_identifiers.extend(blocks.iter().cloned());
+        let stored = StoredSession {
+            session_id: id.to_string(),
+            block_identifiers,
+        };
+
+        let path = self.file_path(id);
+        let json = serde_json::to_string_pretty(&stored)?;
+        write_atomic(&path, json.as_bytes())
+            .with_context(|| format!("Failed to write session {:?}", path))
+    }
+
+    fn remove(&self, id: &str) -> Result<()> {
+        let path = self.file_path(id);
+        if fs_access::exists(&path) {
+            let _lock = self.lock(id)?;
+            fs_access::current()
+                .remove_file(&path)
+                .with_context(|| format!("Failed to remove session {:?}", path))?;
+        }
+        Ok(())
+    }
+
+    fn location(&self, id: &str) -> Option<PathBuf> {
+        Some(self.file_path(id))
+    }
+}
+
+/// Replace `path` with `contents` so that readers see either the old or the new file
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let suffix: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(8)
+        .map(char::from)
+        .collect();
+    let temp_path = path.with_file_name(format!(".{}.{}.tmp", file_name, suffix));
+
+    let file_system = fs_access::current();
+    let result = file_system
+        .write(&temp_path, contents)
+        .and_then(|()| file_system.rename(&temp_path, path));
+    if result.is_err() {
+        let _ = file_system.remove_file(&temp_path);
+    }
+    Ok(result?)
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use super::SessionStore;
+    use anyhow::{Context, Result};
+    use rusqlite::{params, Connection, OptionalExtension};
+    use std::collections::HashSet;
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    /// All sessions in one SQLite database
+    ///
+    /// Saving a session only inserts the blocks it added, so the cost of a search does not
+    /// grow with the length of the session. SQLite's own locking makes concurrent writers
+    /// from several processes safe.
+    #[derive(Debug)]
+    pub struct SqliteStore {
+        path: PathBuf,
+        connection: Mutex<Connection>,
+    }
+
+    impl SqliteStore {
+        /// Open the database at `path`, creating it if needed
+        pub fn open(path: &Path) -> Result<Self> {
+            let file_system = crate::fs_access::current();
+            if let Some(parent) = path.parent() {
+                file_system
+                    .create_dir_all(parent)
+                    .with_context(|| format!("Failed to create session directory {:?}", parent))?;
+            }
+            file_system.check_write(path)?;
+            let connection = Connection::open(path)
+                .with_context(|| format!("Failed to open session database {:?}", path))?;
+            connection.busy_timeout(Duration::from_secs(10))?;
+            connection.pragma_update(None, "journal_mode", "WAL")?;
+            connection.execute_batch(
+                "CREATE TABLE IF NOT EXISTS sessions (
+                     id TEXT PRIMARY KEY,
+                     updated_at INTEGER NOT NULL
+                 );
+                 CREATE TABLE IF NOT EXISTS blocks (
+                     session_id TEXT NOT NULL,
+                     block TEXT NOT NULL,
+                     PRIMARY KEY (session_id, block)
+                 ) WITHOUT ROWID;",
+            )?;
+            Ok(Self {
+                path: path.to_path_buf(),
+                connection: Mutex::new(connection),
+            })
+        }
+    }
+
+    impl SessionStore for SqliteStore {
+        fn exists(&self, id: &str) -> Result<bool> {
+            let connection = self.connection.lock().unwrap();
+            let found = connection
+                .query_row("SELECT 1 FROM sessions WHERE id = ?1", [id], |_| Ok(()))
+                .optional()?;
+            Ok(found.is_some())
+        }
+
+        fn load(&self, id: &str) -> Result<HashSet<String>> {
+            let connection = self.connection.lock().unwrap();
+            let mut statement =
+                connection.prepare_cached("SELECT block FROM blocks WHERE session_id = ?1")?;
+            let blocks = statement
+                .query_map([id], |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<HashSet<String>>>()?;
+            Ok(blocks)
+        }
+
+        fn append(&self, id: &str, blocks: &[String]) -> Result<()> {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs() as i64;
+            let mut connection = self.connection.lock().unwrap();
+            let transaction = connection.transaction()?;
+            transaction.execute(
+                "INSERT INTO sessions (id, updated_at) VALUES (?1, ?2)
+                 ON CONFLICT(id) DO UPDATE SET updated_at = excluded.updated_at",
+                params![id, now],
+            )?;
+            {
+                let mut insert = transaction.prepare_cached(
+                    "INSERT OR IGNORE INTO blocks (session_id, block) VALUES (?1, ?2)",
+                )?;
+                for block in blocks {
+                    insert.execute(params![id, block])?;
+                }
+            }
+            transaction.commit()?;
+            Ok(())
+        }
+
+        fn remove(&self, id: &str) -> Result<()> {
+            let mut connection = self.connection.lock().unwrap();
+            let transaction = connection.transaction()?;
+            transaction.execute("DELETE FROM blocks WHERE session_id = ?1", [id])?;
+            transaction.execute("DELETE FROM sessions WHERE id = ?1", [id])?;
+            transaction.commit()?;
+            Ok(())
+        }
+
+        fn location(&self, _id: &str) -> Option<PathBuf> {
+            Some(self.path.clone())
+        }
+    }
+}
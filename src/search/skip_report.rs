@@ -0,0 +1,255 @@
+use crate::models::{SearchResult, SkipReason, SkippedFile};
+use crate::search::file_list_cache::{
+    default_ignore_patterns, reached_via_symlink, test_ignore_patterns, FileFilters,
+    IGNORE_CASE_INSENSITIVE,
+};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::WalkBuilder;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// A single ignore pattern compiled on its own so that matches can be attributed to it
+struct PatternMatcher {
+    pattern: String,
+    matcher: Override,
+}
+
+/// Classifies paths that the regular file walk would exclude
+struct ExclusionClassifier {
+    custom: Vec<PatternMatcher>,
+    tests: Vec<PatternMatcher>,
+    defaults: Vec<PatternMatcher>,
+    gitignore: Gitignore,
+}
+
+impl ExclusionClassifier {
+    fn new(root: &Path, allow_tests: bool, custom_ignores: &[String]) -> Self {
+        let compile = |patterns: Vec<String>| -> Vec<PatternMatcher> {
+            patterns
+                .into_iter()
+                .filter_map(|pattern| {
+                    let mut builder = OverrideBuilder::new(root);
+                    builder.case_insensitive(IGNORE_CASE_INSENSITIVE).ok()?;
+                    builder.add(&format!("!**/{}", pattern)).ok()?;
+                    let matcher = builder.build().ok()?;
+                    Some(PatternMatcher { pattern, matcher })
+                })
+                .collect()
+        };
+
+        let tests = if allow_tests {
+            Vec::new()
+        } else {
+            compile(test_ignore_patterns())
+        };
+
+        let mut gitignore_builder = GitignoreBuilder::new(root);
+        let _ = gitignore_builder.case_insensitive(IGNORE_CASE_INSENSITIVE);
+        gitignore_builder.add(root.join(".gitignore"));
+        let gitignore = gitignore_builder
+            .build()
+            .unwrap_or_else(|_| Gitignore::empty());
+
+        Self {
+            custom: compile(custom_ignores.to_vec()),
+            tests,
+            defaults: compile(default_ignore_patterns()),
+            gitignore,
+        }
+    }
+
+    /// Return the reason a path is excluded by an explicit rule, if any
+    fn classify(&self, path: &Path, is_dir: bool) -> Option<SkipReason> {
+        let matches = |matchers: &[PatternMatcher]| {
+            matchers
+                .iter()
+                .find(|m| m.matcher.matched(path, is_dir).is_ignore())
+                .map(|m| m.pattern.clone())
+        };
+
+        if let Some(pattern) = matches(&self.custom) {
+            return Some(SkipReason::CustomIgnore(pattern));
+        }
+        if matches(&self.tests).is_some() {
+            return Some(SkipReason::TestFilter);
+        }
+        if let Some(pattern) = matches(&self.defaults) {
+            return Some(SkipReason::DefaultIgnore(pattern));
+        }
+        if self.gitignore.matched(path, is_dir).is_ignore() {
+            return Some(SkipReason::GitIgnore);
+        }
+        if path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        {
+            return Some(SkipReason::Hidden);
+        }
+        None
+    }
+}
+
+/// Function to find every file under `root` that the regular file walk excludes
+///
+/// Walks the directory tree without any ignore rules and attributes each path that
+/// is missing from `included` to the rule that removed it. Excluded directories are
+/// reported once instead of listing every file inside them.
+///
+/// # Arguments
+/// * `root` - The directory that was searched
+/// * `allow_tests` - Whether test files were allowed in the search
+/// * `custom_ignores` - Custom ignore patterns passed with --ignore
+/// * `included` - The files that the regular walk returned
+/// * `follow_symlinks` - Whether the search followed symbolic links
+/// * `filters` - Depth, modification time and size limits of the search
+///
+/// # Returns
+/// The excluded files and directories, sorted by path
+pub fn find_walk_exclusions(
+    root: &Path,
+    allow_tests: bool,
+    custom_ignores: &[String],
+    included: &[PathBuf],
+    follow_symlinks: bool,
+    filters: FileFilters,
+) -> Vec<SkippedFile> {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    // Directories that contain included files are never pruned, even if a rule matches them
+    let included_dirs: Arc<HashSet<PathBuf>> = Arc::new(
+        included
+            .iter()
+            .flat_map(|file| file.ancestors().skip(1).map(Path::to_path_buf))
+            .collect(),
+    );
+    let included: HashSet<&PathBuf> = included.iter().collect();
+    let classifier = Arc::new(ExclusionClassifier::new(root, allow_tests, custom_ignores));
+    let pruned_dirs: Arc<Mutex<Vec<SkippedFile>>> = Arc::new(Mutex::new(Vec::new()));
+
+    let mut builder = WalkBuilder::new(root);
+    builder.standard_filters(false);
+    builder.follow_links(follow_symlinks);
+    {
+        let classifier = Arc::clone(&classifier);
+        let pruned_dirs = Arc::clone(&pruned_dirs);
+        let included_dirs = Arc::clone(&included_dirs);
+        let root = root.to_path_buf();
+        builder.filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|ft| ft.is_dir());
+            if !is_dir || entry.path() == root || included_dirs.contains(entry.path()) {
+                return true;
+            }
+            // Never descend into version control metadata
+            if entry.file_name() == ".git" {
+                return false;
+            }
+            // Everything below a directory at --max-depth is out of range
+            let reason = classifier.classify(entry.path(), true).or_else(|| {
+                filters
+                    .max_depth
+                    .is_some_and(|max| entry.depth() >= max)
+                    .then_some(SkipReason::Depth)
+            });
+            match reason {
+                Some(reason) => {
+                    pruned_dirs.lock().unwrap().push(SkippedFile {
+                        path: entry.path().to_path_buf(),
+                        is_dir: true,
+                        reason,
+                    });
+                    false
+                }
+                None => true,
+            }
+        });
+    }
+
+    let mut skipped = Vec::new();
+    for entry in builder.build().flatten() {
+        let path = entry.path().to_path_buf();
+        if included.contains(&path) {
+            continue;
+        }
+        // Links are only walked with --follow-symlinks
+        if entry.path_is_symlink() && !follow_symlinks {
+            skipped.push(SkippedFile {
+                is_dir: path.is_dir(),
+                path,
+                reason: SkipReason::Symlink,
+            });
+            continue;
+        }
+        if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+            continue;
+        }
+        // Anything not covered by an explicit rule was removed by a nested ignore file
+        let reason = classifier.classify(&path, false).unwrap_or_else(|| {
+            if let Some(reason) = filters.exclusion(entry.depth(), || entry.metadata().ok()) {
+                reason
+            } else if follow_symlinks && reached_via_symlink(root, &path) {
+                SkipReason::LinkedDuplicate
+            } else {
+                SkipReason::GitIgnore
+            }
+        });
+        skipped.push(SkippedFile {
+            path,
+            is_dir: false,
+            reason,
+        });
+    }
+
+    skipped.extend(pruned_dirs.lock().unwrap().drain(..));
+    skipped.sort_by(|a, b| a.path.cmp(&b.path));
+
+    if debug_mode {
+        println!(
+            "DEBUG: Skip report found {} excluded paths under {:?}",
+            skipped.len(),
+            root
+        );
+    }
+
+    skipped
+}
+
+/// Function to record files that were dropped between two stages of the pipeline
+///
+/// Any file present in `before` but absent from `after` is added to `report` with
+/// the given reason.
+pub fn record_dropped_files<'a>(
+    report: &mut Vec<SkippedFile>,
+    before: impl IntoIterator<Item = &'a Path>,
+    after: impl IntoIterator<Item = &'a Path>,
+    reason: SkipReason,
+) {
+    let remaining: HashSet<&Path> = after.into_iter().collect();
+    let mut seen = HashSet::new();
+    for path in before {
+        if !remaining.contains(path) && seen.insert(path) {
+            report.push(SkippedFile {
+                path: path.to_path_buf(),
+                is_dir: false,
+                reason: reason.clone(),
+            });
+        }
+    }
+}
+
+/// Function to finalize the skip report
+///
+/// Removes files that still made it into the final results, keeps only the first
+/// (earliest) reason recorded for each path, and sorts the report by path.
+pub fn finalize_report(report: Vec<SkippedFile>, results: &[SearchResult]) -> Vec<SkippedFile> {
+    let returned: HashSet<PathBuf> = results.iter().map(|r| r.path()).collect();
+    let mut seen = HashSet::new();
+    let mut report: Vec<SkippedFile> = report
+        .into_iter()
+        .filter(|s| !returned.contains(&s.path))
+        .filter(|s| seen.insert(s.path.clone()))
+        .collect();
+    report.sort_by(|a, b| a.path.cmp(&b.path));
+    report
+}
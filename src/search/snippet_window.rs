@@ -0,0 +1,106 @@
+use std::collections::HashSet;
+
+use crate::models::SearchResult;
+use crate::search::query::QueryPlan;
+use crate::search::tokenization;
+
+/// Function to list the query terms a line can match (excluded terms are left out)
+pub fn matched_terms(plan: &QueryPlan) -> HashSet<String> {
+    plan.term_indices
+        .keys()
+        .filter(|term| !plan.excluded_terms.contains(*term))
+        .map(|term| term.to_lowercase())
+        .collect()
+}
+
+/// Function to check whether a line contains one of the query terms
+pub fn line_matches(line: &str, terms: &HashSet<String>) -> bool {
+    let lower = line.to_lowercase();
+    terms.iter().any(|term| lower.contains(term.as_str()))
+        || tokenization::tokenize(line)
+            .iter()
+            .any(|token| terms.contains(token))
+}
+
+/// Function to trim code to `window` lines around each matching line
+///
+/// The first line (the signature) is always kept, and each run of dropped lines is
+/// replaced by a single fold marker. Returns `None` when nothing would be dropped or
+/// no line matches.
+pub fn trim_to_window(code: &str, terms: &HashSet<String>, window: usize) -> Option<String> {
+    let lines: Vec<&str> = code.lines().collect();
+    let mut keep = vec![false; lines.len()];
+    let mut any_match = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        if line_matches(line, terms) {
+            any_match = true;
+            let end = (i + window).min(lines.len() - 1);
+            for kept in &mut keep[i.saturating_sub(window)..=end] {
+                *kept = true;
+            }
+        }
+    }
+    if !any_match {
+        return None;
+    }
+    keep[0] = true;
+    if keep.iter().all(|&kept| kept) {
+        return None;
+    }
+
+    Some(fold_lines(&lines, &keep))
+}
+
+/// Function to join the kept lines, replacing each run of dropped lines with a fold marker
+pub fn fold_lines(lines: &[&str], keep: &[bool]) -> String {
+    let mut trimmed = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if keep[i] {
+            trimmed.push(lines[i].to_string());
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < lines.len() && !keep[i] {
+            i += 1;
+        }
+        let indent: String = lines[start]
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .collect();
+        let folded = i - start;
+        trimmed.push(format!(
+            "{}... {} line{} folded ...",
+            indent,
+            folded,
+            if folded == 1 { "" } else { "s" }
+        ));
+    }
+
+    trimmed.join("\n")
+}
+
+/// Function to apply `--snippet-window` to search results
+///
+/// Results keep their full line range; only the returned code is trimmed.
+pub fn apply_snippet_window(results: &mut [SearchResult], terms: &HashSet<String>, window: usize) {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    for result in results {
+        if let Some(trimmed) = trim_to_window(&result.code, terms, window) {
+            if debug_mode {
+                println!(
+                    "DEBUG: Trimmed block {}:{}-{} from {} to {} lines",
+                    result.file,
+                    result.lines.0,
+                    result.lines.1,
+                    result.code.lines().count(),
+                    trimmed.lines().count()
+                );
+            }
+            result.code = trimmed;
+        }
+    }
+}
@@ -0,0 +1,353 @@
+//! Telemetry hooks for searches.
+//!
+//! Embedders implement [`TelemetrySink`] and run searches inside [`with_telemetry`] (or give
+//! the sink to a `ProbeEngine`) to receive one event per search milestone. The CLI can send
+//! the same events to a statsd or OTLP/HTTP collector with `--metrics-endpoint`.
+//!
+//! Sinks are called on the searching thread and must not block for long. Failures to deliver
+//! metrics never fail a search.
+
+use anyhow::{anyhow, Context, Result};
+use std::cell::RefCell;
+use std::io::{Read, Write};
+use std::net::{TcpStream, UdpSocket};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Something that happened during a search
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub enum TelemetryEvent<'a> {
+    /// A search started
+    QueryStarted {
+        queries: &'a [String],
+        path: &'a Path,
+    },
+    /// The candidate files were searched for matches
+    FilesScanned { files: usize, matched_files: usize },
+    /// A search stage finished
+    StageCompleted {
+        stage: &'static str,
+        duration: Duration,
+    },
+    /// Results were returned to the caller
+    ResultsReturned {
+        results: usize,
+        bytes: usize,
+        tokens: usize,
+    },
+    /// The search ended, successfully or not
+    QueryFinished { duration: Duration, succeeded: bool },
+}
+
+/// Receiver of search telemetry events
+pub trait TelemetrySink: Send + Sync {
+    /// Record one event
+    fn record(&self, event: &TelemetryEvent<'_>);
+}
+
+thread_local! {
+    static CURRENT_SINK: RefCell<Option<Arc<dyn TelemetrySink>>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with `sink` receiving the telemetry of searches on this thread
+pub fn with_telemetry<T>(sink: Arc<dyn TelemetrySink>, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<Arc<dyn TelemetrySink>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            CURRENT_SINK.with(|current| *current.borrow_mut() = previous);
+        }
+    }
+
+    let previous = CURRENT_SINK.with(|current| current.borrow_mut().replace(sink));
+    let _restore = Restore(previous);
+    f()
+}
+
+/// Whether a sink receives the telemetry of searches on this thread
+pub(crate) fn is_enabled() -> bool {
+    CURRENT_SINK.with(|current| current.borrow().is_some())
+}
+
+/// Send `event` to the sink of the search running on this thread, if any
+pub(crate) fn emit(event: TelemetryEvent<'_>) {
+    let sink = CURRENT_SINK.with(|current| current.borrow().clone());
+    if let Some(sink) = sink {
+        sink.record(&event);
+    }
+}
+
+/// A collector that `--metrics-endpoint` can send to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetricsEndpoint {
+    /// statsd over UDP, `statsd://host:port`
+    Statsd(String),
+    /// OTLP metrics as JSON over HTTP, `http://host:port[/path]` (default path `/v1/metrics`)
+    OtlpHttp { authority: String, path: String },
+}
+
+impl MetricsEndpoint {
+    /// Parse an endpoint URL
+    pub fn parse(url: &str) -> Result<Self> {
+        if let Some(address) = url.strip_prefix("statsd://") {
+            if address.is_empty() {
+                return Err(anyhow!("Missing host in metrics endpoint '{}'", url));
+            }
+            return Ok(MetricsEndpoint::Statsd(
+                address.trim_end_matches('/').to_string(),
+            ));
+        }
+        if url.starts_with("http://") {
+            let (authority, path) = parse_http_endpoint(url, "/v1/metrics")?;
+            return Ok(MetricsEndpoint::OtlpHttp { authority, path });
+        }
+        Err(anyhow!(
+            "Unsupported metrics endpoint '{}': use statsd://host:port or http://host:port[/path] (OTLP)",
+            url
+        ))
+    }
+}
+
+/// Split an `http://host:port[/path]` URL into its authority and path, using `default_path`
+/// when the URL has none
+pub(crate) fn parse_http_endpoint(url: &str, default_path: &str) -> Result<(String, String)> {
+    let rest = url.strip_prefix("http://").ok_or_else(|| {
+        anyhow!(
+            "Unsupported endpoint '{}': use http://host:port[/path]",
+            url
+        )
+    })?;
+    let (authority, path) = match rest.find('/') {
+        Some(slash) if slash + 1 < rest.len() => (&rest[..slash], &rest[slash..]),
+        Some(slash) => (&rest[..slash], default_path),
+        None => (rest, default_path),
+    };
+    if authority.is_empty() {
+        return Err(anyhow!("Missing host in endpoint '{}'", url));
+    }
+    Ok((authority.to_string(), path.to_string()))
+}
+
+/// POST a JSON body to an HTTP/1.1 endpoint and check that it answers with a 2xx status
+pub(crate) fn post_json(authority: &str, path: &str, body: &str) -> Result<()> {
+    let mut stream = TcpStream::connect(authority)
+        .with_context(|| format!("Failed to connect to {}", authority))?;
+    stream.set_write_timeout(Some(Duration::from_secs(2)))?;
+    stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        authority,
+        body.len(),
+        body
+    )?;
+
+    let mut response = String::new();
+    // The collector's reply is only checked for the status line
+    let _ = stream.read_to_string(&mut response);
+    let status = response.split_whitespace().nth(1).unwrap_or("");
+    if !status.starts_with('2') {
+        return Err(anyhow!(
+            "Collector at {} answered '{}'",
+            authority,
+            response.lines().next().unwrap_or("")
+        ));
+    }
+    Ok(())
+}
+
+/// Sends search metrics to statsd as they happen
+pub struct StatsdSink {
+    socket: UdpSocket,
+    prefix: String,
+}
+
+impl StatsdSink {
+    /// Connect to a statsd server; `prefix` is prepended to every metric name
+    pub fn new(address: &str, prefix: &str) -> Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0").context("Failed to open a UDP socket")?;
+        socket
+            .connect(address)
+            .with_context(|| format!("Failed to resolve statsd address '{}'", address))?;
+        Ok(Self {
+            socket,
+            prefix: prefix.to_string(),
+        })
+    }
+
+    fn send(&self, line: String) {
+        if let Err(e) = self.socket.send(line.as_bytes()) {
+            if std::env::var("DEBUG").unwrap_or_default() == "1" {
+                println!("DEBUG: Failed to send metric '{}': {}", line, e);
+            }
+        }
+    }
+}
+
+impl TelemetrySink for StatsdSink {
+    fn record(&self, event: &TelemetryEvent<'_>) {
+        let prefix = &self.prefix;
+        match event {
+            TelemetryEvent::QueryStarted { .. } => self.send(format!("{}.queries:1|c", prefix)),
+            TelemetryEvent::FilesScanned {
+                files,
+                matched_files,
+            } => {
+                self.send(format!("{}.files_scanned:{}|c", prefix, files));
+                self.send(format!("{}.files_matched:{}|c", prefix, matched_files));
+            }
+            TelemetryEvent::StageCompleted { stage, duration } => self.send(format!(
+                "{}.stage.{}:{}|ms",
+                prefix,
+                stage,
+                duration.as_millis()
+            )),
+            TelemetryEvent::ResultsReturned {
+                results,
+                bytes,
+                tokens,
+            } => {
+                self.send(format!("{}.results:{}|g", prefix, results));
+                self.send(format!("{}.result_bytes:{}|g", prefix, bytes));
+                self.send(format!("{}.result_tokens:{}|g", prefix, tokens));
+            }
+            TelemetryEvent::QueryFinished {
+                duration,
+                succeeded,
+            } => {
+                self.send(format!("{}.duration:{}|ms", prefix, duration.as_millis()));
+                if !succeeded {
+                    self.send(format!("{}.failures:1|c", prefix));
+                }
+            }
+        }
+    }
+}
+
+/// Collects the metrics of a search and exports them to an OTLP/HTTP collector when it ends
+pub struct OtlpHttpSink {
+    authority: String,
+    path: String,
+    prefix: String,
+    points: Mutex<Vec<(String, f64)>>,
+}
+
+impl OtlpHttpSink {
+    /// Export to `http://{authority}{path}`; `prefix` is prepended to every metric name
+    pub fn new(authority: &str, path: &str, prefix: &str) -> Self {
+        Self {
+            authority: authority.to_string(),
+            path: path.to_string(),
+            prefix: prefix.to_string(),
+            points: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn push(&self, name: String, value: f64) {
+        self.points.lock().unwrap().push((name, value));
+    }
+
+    /// The OTLP JSON body for the collected points
+    fn export_body(&self, points: &[(String, f64)]) -> serde_json::Value {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+            .to_string();
+        let metrics: Vec<serde_json::Value> = points
+            .iter()
+            .map(|(name, value)| {
+                serde_json::json!({
+                    "name": name,
+                    "gauge": {
+                        "dataPoints": [{ "asDouble": value, "timeUnixNano": now }]
+                    }
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "resourceMetrics": [{
+                "resource": {
+                    "attributes": [{
+                        "key": "service.name",
+                        "value": { "stringValue": "probe" }
+                    }]
+                },
+                "scopeMetrics": [{
+                    "scope": { "name": "probe", "version": env!("CARGO_PKG_VERSION") },
+                    "metrics": metrics
+                }]
+            }]
+        })
+    }
+
+    fn export(&self) -> Result<()> {
+        let points = std::mem::take(&mut *self.points.lock().unwrap());
+        if points.is_empty() {
+            return Ok(());
+        }
+        let body = self.export_body(&points).to_string();
+        post_json(&self.authority, &self.path, &body)
+    }
+}
+
+impl TelemetrySink for OtlpHttpSink {
+    fn record(&self, event: &TelemetryEvent<'_>) {
+        let prefix = &self.prefix;
+        match event {
+            TelemetryEvent::QueryStarted { .. } => {}
+            TelemetryEvent::FilesScanned {
+                files,
+                matched_files,
+            } => {
+                self.push(format!("{}.files_scanned", prefix), *files as f64);
+                self.push(format!("{}.files_matched", prefix), *matched_files as f64);
+            }
+            TelemetryEvent::StageCompleted { stage, duration } => self.push(
+                format!("{}.stage.{}.ms", prefix, stage),
+                duration.as_secs_f64() * 1000.0,
+            ),
+            TelemetryEvent::ResultsReturned {
+                results,
+                bytes,
+                tokens,
+            } => {
+                self.push(format!("{}.results", prefix), *results as f64);
+                self.push(format!("{}.result_bytes", prefix), *bytes as f64);
+                self.push(format!("{}.result_tokens", prefix), *tokens as f64);
+            }
+            TelemetryEvent::QueryFinished {
+                duration,
+                succeeded,
+            } => {
+                self.push(
+                    format!("{}.duration.ms", prefix),
+                    duration.as_secs_f64() * 1000.0,
+                );
+                self.push(
+                    format!("{}.failures", prefix),
+                    if *succeeded { 0.0 } else { 1.0 },
+                );
+                if let Err(e) = self.export() {
+                    if std::env::var("DEBUG").unwrap_or_default() == "1" {
+                        println!("DEBUG: Failed to export metrics: {}", e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Build the sink for a `--metrics-endpoint` URL
+pub fn sink_for_endpoint(url: &str) -> Result<Arc<dyn TelemetrySink>> {
+    let sink: Arc<dyn TelemetrySink> = match MetricsEndpoint::parse(url)? {
+        MetricsEndpoint::Statsd(address) => Arc::new(StatsdSink::new(&address, "probe.search")?),
+        MetricsEndpoint::OtlpHttp { authority, path } => {
+            Arc::new(OtlpHttpSink::new(&authority, &path, "probe.search"))
+        }
+    };
+    Ok(sink)
+}
@@ -1,7 +1,10 @@
 use crate::ranking::get_stemmer;
+use crate::search::code_terms;
+use crate::search::normalization::normalize;
 use crate::search::term_exceptions::{is_exception_term, EXCEPTION_TERMS};
 use decompound::{decompound, DecompositionOptions};
 use once_cell::sync::Lazy;
+use regex::Regex;
 use std::collections::HashSet;
 use std::sync::Mutex;
 
@@ -21,6 +24,21 @@ pub fn add_special_term(term: &str) {
     }
 }
 
+/// Names joined by `_` or `-`, like `user_id` and `user-id`
+static SEPARATED_NAME: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[\p{L}\p{N}]+(?:[_-][\p{L}\p{N}]+)+").unwrap());
+
+/// Helper function to get the dynamic special terms joined by `_` or `-`
+fn separated_special_terms() -> HashSet<String> {
+    DYNAMIC_SPECIAL_TERMS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|term| SEPARATED_NAME.is_match(term))
+        .cloned()
+        .collect()
+}
+
 /// Static set of common English stop words
 static ENGLISH_STOP_WORDS: Lazy<HashSet<String>> = Lazy::new(|| {
     vec![
@@ -1086,65 +1104,130 @@ pub fn tokenize_and_stem(keyword: &str) -> Vec<String> {
     }
 }
 
+/// Version strings like `1.2.3`, hex literals like `0x7f` and plain numbers
+static NUMERIC_LITERAL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?:\d+(?:\.\d+)+|0[xX][0-9a-fA-F]+|\d+)$").unwrap());
+
+/// Check if a term is a numeric or version literal, which is matched as a whole
+pub fn is_numeric_literal(term: &str) -> bool {
+    NUMERIC_LITERAL.is_match(term)
+}
+
+/// Helper function to check if a token can continue as a version string at a dot,
+/// allowing for a leading `v` as in `v1.2`
+fn continues_version(token: &str) -> bool {
+    let digits = token.strip_prefix(['v', 'V']).unwrap_or(token);
+    digits.starts_with(|c: char| c.is_ascii_digit())
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+}
+
+/// Helper function to drop the `v` of a version string like `v1.2.3`
+fn strip_version_prefix(token: String) -> String {
+    match token.strip_prefix(['v', 'V']) {
+        Some(version) if is_numeric_literal(version) && version.contains('.') => {
+            version.to_string()
+        }
+        _ => token,
+    }
+}
+
 /// Tokenizes text into words by splitting on whitespace and non-alphanumeric characters,
 /// removes stop words, and applies stemming. Also splits camelCase/PascalCase identifiers
 /// and compound words.
 ///
 /// The tokenization flow follows these steps:
 /// 1. Split input text on whitespace
-/// 2. For each token, further split on non-alphanumeric characters (except for leading "-")
-/// 3. For each resulting token, check if it has mixed case
+/// 2. For each token, further split on non-alphanumeric characters (except for leading "-"),
+///    keeping version strings like `1.2.3` together
+/// 3. Keep numeric and version literals as they are; for each other token, check if
+///    it has mixed case
 /// 4. If it has mixed case, split using camel case rules
-/// 5. For each part, attempt to split compound words
-/// 6. Process each part: remove stop words and apply stemming
-/// 7. Collect unique tokens
-/// 8. Exclude terms that were negated with a "-" prefix
+/// 5. With code term rules in effect, drop the identifier's accessor prefix and
+///    implementation suffix (see [`code_terms`])
+/// 6. For each part, attempt to split compound words
+/// 7. Process each part: remove stop words, map irregular plurals with code term
+///    rules, and apply stemming
+/// 8. Collect unique tokens
+/// 9. Exclude terms that were negated with a "-" prefix
 pub fn tokenize(text: &str) -> Vec<String> {
+    tokenize_words(text, true)
+}
+
+/// Tokenizes file content like [`tokenize`], except that a leading "-" is part of the
+/// code (`-webkit-transition`, `--max-results`) rather than a negation
+pub fn tokenize_code(text: &str) -> Vec<String> {
+    tokenize_words(text, false)
+}
+
+/// Helper function for [`tokenize`] and [`tokenize_code`], which differ in whether a
+/// leading "-" negates a word
+fn tokenize_words(text: &str, negation: bool) -> Vec<String> {
+    let text = &*normalize(text, false);
     let stemmer = get_stemmer();
     let vocabulary = load_vocabulary();
+    let code_terms = code_terms::current();
 
     // Track negated terms to exclude them from the final result
     let mut negated_terms = HashSet::new();
 
     // println!("Tokenizing text: {}", text);
 
-    // Split by whitespace and collect words
-    let mut tokens = Vec::new();
+    // Split by whitespace and collect identifiers, each as its pieces between underscores
+    let mut tokens: Vec<Vec<String>> = Vec::new();
     for word in text.split_whitespace() {
         // Check if this is a negated term
-        let is_negated = word.starts_with('-');
+        let is_negated = negation && word.starts_with('-');
 
         // Further split by non-alphanumeric characters
         let mut current_token = String::new();
+        let mut current_identifier = Vec::new();
 
         // Process the characters, skipping the leading "-" if this is a negated term
-        let mut chars = word.chars();
+        let mut chars = word.chars().peekable();
         if is_negated {
             // Skip the leading "-"
             chars.next();
         }
 
-        for c in chars {
+        while let Some(c) = chars.next() {
             if c.is_alphanumeric() {
                 current_token.push(c);
-            } else if !current_token.is_empty() {
+                continue;
+            }
+            // A dot between digits belongs to a version string
+            if c == '.'
+                && continues_version(&current_token)
+                && chars.peek().is_some_and(|next| next.is_ascii_digit())
+            {
+                current_token.push(c);
+                continue;
+            }
+            if !current_token.is_empty() {
+                let current_token = strip_version_prefix(std::mem::take(&mut current_token));
                 // We found a non-alphanumeric character, add the current token if not empty
                 if is_negated {
                     // Track this as a negated term
                     negated_terms.insert(current_token.to_lowercase());
                 }
-                tokens.push(current_token);
-                current_token = String::new();
+                current_identifier.push(current_token);
+            }
+            // An underscore joins the pieces of one identifier
+            if c != '_' && !current_identifier.is_empty() {
+                tokens.push(std::mem::take(&mut current_identifier));
             }
         }
 
         // Add the last token if not empty
         if !current_token.is_empty() {
+            let current_token = strip_version_prefix(current_token);
             if is_negated {
                 // Track this as a negated term
                 negated_terms.insert(current_token.to_lowercase());
             }
-            tokens.push(current_token);
+            current_identifier.push(current_token);
+        }
+        if !current_identifier.is_empty() {
+            tokens.push(current_identifier);
         }
     }
 
@@ -1153,10 +1236,27 @@ pub fn tokenize(text: &str) -> Vec<String> {
     let mut result = Vec::new();
 
     // Process each token: filter stop words, apply stemming, and add to result if unique
-    for token in tokens {
+    for identifier in tokens {
+        // Numeric and version literals are neither split nor stemmed
+        if let [literal] = identifier.as_slice() {
+            if is_numeric_literal(literal) {
+                let literal = literal.to_lowercase();
+                if !negated_terms.contains(&literal) && processed_tokens.insert(literal.clone()) {
+                    result.push(literal);
+                }
+                continue;
+            }
+        }
+
         // Always try to split using camel case rules, even for lowercase tokens
         // This allows us to handle tokens that were already lowercased
-        let parts = split_camel_case(&token);
+        let mut parts: Vec<String> = identifier
+            .iter()
+            .flat_map(|token| split_camel_case(token))
+            .collect();
+        if let Some(rules) = code_terms {
+            rules.strip_affixes(&mut parts);
+        }
 
         // Process each part
         for part in parts {
@@ -1176,6 +1276,11 @@ pub fn tokenize(text: &str) -> Vec<String> {
             let compound_parts = split_compound_word(&lowercase_part, vocabulary);
 
             for compound_part in compound_parts {
+                let compound_part = match code_terms {
+                    Some(rules) => rules.singular(&compound_part).to_string(),
+                    None => compound_part,
+                };
+
                 // Skip stop words in compound parts
                 if is_stop_word(&compound_part) {
                     continue;
@@ -1207,6 +1312,18 @@ pub fn tokenize(text: &str) -> Vec<String> {
         }
     }
 
+    // Quoted query terms like "user_id" are split above like any identifier, so they
+    // are kept whole as well
+    let separated = separated_special_terms();
+    if !separated.is_empty() {
+        for name in SEPARATED_NAME.find_iter(text) {
+            let name = name.as_str().to_lowercase();
+            if separated.contains(&name) && processed_tokens.insert(name.clone()) {
+                result.push(name);
+            }
+        }
+    }
+
     result
 }
 
@@ -0,0 +1,210 @@
+//! Export of search tracing spans to an OTLP/HTTP collector.
+//!
+//! Searches open a `probe.search` span with one child span per pipeline stage
+//! (`query_preprocessing`, `file_searching`, `result_ranking`, ...). Any `tracing`
+//! subscriber sees them; [`OtlpTraceLayer`] collects them and sends each finished search as
+//! one OTLP JSON trace, which `--trace-endpoint` uses.
+
+use anyhow::Result;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::search::telemetry::{parse_http_endpoint, post_json};
+
+/// A span that has not closed yet
+struct OpenSpan {
+    trace_id: String,
+    span_id: String,
+    parent_span_id: Option<String>,
+    start: SystemTime,
+    attributes: Vec<(String, String)>,
+}
+
+/// A closed span waiting to be exported with the rest of its trace
+struct FinishedSpan {
+    name: String,
+    trace_id: String,
+    span_id: String,
+    parent_span_id: Option<String>,
+    start: SystemTime,
+    end: SystemTime,
+    attributes: Vec<(String, String)>,
+}
+
+struct AttributeVisitor<'a>(&'a mut Vec<(String, String)>);
+
+impl Visit for AttributeVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push((field.name().to_string(), value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .push((field.name().to_string(), format!("{:?}", value)));
+    }
+}
+
+/// A `tracing` layer that sends every finished root span, with its children, to an OTLP/HTTP
+/// collector
+pub struct OtlpTraceLayer {
+    authority: String,
+    path: String,
+    finished: Mutex<Vec<FinishedSpan>>,
+}
+
+impl OtlpTraceLayer {
+    /// Export to an `http://host:port[/path]` collector (default path `/v1/traces`)
+    pub fn new(endpoint: &str) -> Result<Self> {
+        let (authority, path) = parse_http_endpoint(endpoint, "/v1/traces")?;
+        Ok(Self {
+            authority,
+            path,
+            finished: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// The OTLP JSON body for the finished spans of one trace
+    fn export_body(spans: &[FinishedSpan]) -> serde_json::Value {
+        let nanos = |time: SystemTime| {
+            time.duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+                .to_string()
+        };
+        let spans: Vec<serde_json::Value> = spans
+            .iter()
+            .map(|span| {
+                let attributes: Vec<serde_json::Value> = span
+                    .attributes
+                    .iter()
+                    .map(|(key, value)| {
+                        serde_json::json!({ "key": key, "value": { "stringValue": value } })
+                    })
+                    .collect();
+                serde_json::json!({
+                    "traceId": span.trace_id,
+                    "spanId": span.span_id,
+                    "parentSpanId": span.parent_span_id.clone().unwrap_or_default(),
+                    "name": span.name,
+                    "kind": 1,
+                    "startTimeUnixNano": nanos(span.start),
+                    "endTimeUnixNano": nanos(span.end),
+                    "attributes": attributes
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "resourceSpans": [{
+                "resource": {
+                    "attributes": [{
+                        "key": "service.name",
+                        "value": { "stringValue": "probe" }
+                    }]
+                },
+                "scopeSpans": [{
+                    "scope": { "name": "probe", "version": env!("CARGO_PKG_VERSION") },
+                    "spans": spans
+                }]
+            }]
+        })
+    }
+
+    fn export(&self, trace_id: &str) {
+        let spans: Vec<FinishedSpan> = {
+            let mut finished = self.finished.lock().unwrap();
+            let (trace, rest) = std::mem::take(&mut *finished)
+                .into_iter()
+                .partition(|span| span.trace_id == trace_id);
+            *finished = rest;
+            trace
+        };
+        let body = Self::export_body(&spans).to_string();
+        if let Err(e) = post_json(&self.authority, &self.path, &body) {
+            if std::env::var("DEBUG").unwrap_or_default() == "1" {
+                println!("DEBUG: Failed to export trace: {}", e);
+            }
+        }
+    }
+}
+
+fn random_hex_id(bytes: usize) -> String {
+    (0..bytes)
+        .map(|_| format!("{:02x}", rand::random::<u8>()))
+        .collect()
+}
+
+impl<S> Layer<S> for OtlpTraceLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let parent = span.parent().and_then(|parent| {
+            parent
+                .extensions()
+                .get::<OpenSpan>()
+                .map(|open| (open.trace_id.clone(), open.span_id.clone()))
+        });
+        let (trace_id, parent_span_id) = match parent {
+            Some((trace_id, parent_span_id)) => (trace_id, Some(parent_span_id)),
+            None => (random_hex_id(16), None),
+        };
+
+        let mut attributes = Vec::new();
+        attrs.record(&mut AttributeVisitor(&mut attributes));
+        span.extensions_mut().insert(OpenSpan {
+            trace_id,
+            span_id: random_hex_id(8),
+            parent_span_id,
+            start: SystemTime::now(),
+            attributes,
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(open) = span.extensions_mut().get_mut::<OpenSpan>() {
+                values.record(&mut AttributeVisitor(&mut open.attributes));
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(open) = span.extensions_mut().remove::<OpenSpan>() else {
+            return;
+        };
+        let is_root = open.parent_span_id.is_none();
+        let trace_id = open.trace_id.clone();
+        self.finished.lock().unwrap().push(FinishedSpan {
+            name: span.name().to_string(),
+            trace_id: open.trace_id,
+            span_id: open.span_id,
+            parent_span_id: open.parent_span_id,
+            start: open.start,
+            end: SystemTime::now(),
+            attributes: open.attributes,
+        });
+        if is_root {
+            self.export(&trace_id);
+        }
+    }
+}
+
+/// Run `f` with this thread's search spans exported through `layer`
+pub fn with_trace_export<T>(layer: OtlpTraceLayer, f: impl FnOnce() -> T) -> T {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let subscriber = tracing_subscriber::registry().with(layer);
+    tracing::subscriber::with_default(subscriber, f)
+}
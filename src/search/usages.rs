@@ -0,0 +1,126 @@
+use crate::models::{SearchResult, UsageKind};
+use regex::Regex;
+
+/// Words that can appear in a query but never name a symbol
+const QUERY_KEYWORDS: &[&str] = &["AND", "OR", "NOT"];
+
+/// Words that start statements which call a function rather than declare it
+const CONTROL_KEYWORDS: &[&str] = &[
+    "return", "if", "else", "while", "for", "switch", "case", "await", "new", "throw", "yield",
+    "match",
+];
+
+/// Number of lines at the start of a block that are checked for a declaration
+const SIGNATURE_LINES: usize = 3;
+
+/// Function to extract the symbol names a query refers to
+///
+/// Only identifier-like words are kept, so operators, quoted phrases with spaces
+/// and excluded terms (prefixed with `-`) are ignored.
+pub fn extract_symbol_names(queries: &[String]) -> Vec<String> {
+    let identifier = Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").unwrap();
+    let mut symbols = Vec::new();
+
+    for query in queries {
+        for word in query.split_whitespace() {
+            if word.starts_with('-') {
+                continue;
+            }
+            let word = word.trim_start_matches('+').trim_matches('"');
+            if identifier.is_match(word)
+                && !QUERY_KEYWORDS.contains(&word)
+                && !symbols.iter().any(|s| s == word)
+            {
+                symbols.push(word.to_string());
+            }
+        }
+    }
+
+    symbols
+}
+
+/// Build the regexes that recognise a declaration of `symbol` in several languages
+fn definition_patterns(symbol: &str) -> Vec<Regex> {
+    let name = regex::escape(symbol);
+    [
+        // Rust, Python, Go, JS/TS, Ruby, PHP, Swift, Kotlin, C#/Java type declarations
+        format!(
+            r"\b(fn|def|func|function|class|struct|enum|trait|interface|type|impl|mod|module|protocol|record|fun)\s+{}\b",
+            name
+        ),
+        // Go methods: func (r *Receiver) Name(
+        format!(r"\bfunc\s*\([^)]*\)\s*{}\s*\(", name),
+        // JS/TS function expressions: const name = (...) => / function
+        format!(r"\b(const|let|var)\s+{}\s*=\s*(async\s+)?(function\b|\([^)]*\)\s*=>|[A-Za-z_]\w*\s*=>)", name),
+        // C-family methods and functions: ReturnType name(...) without a trailing semicolon
+        format!(r"^\s*(?:[\w:<>\[\]*&,]+\s+)+\*?{}\s*\([^;]*$", name),
+    ]
+    .iter()
+    .filter_map(|pattern| Regex::new(pattern).ok())
+    .collect()
+}
+
+/// Check whether a single line declares the symbol described by `patterns`
+fn is_declaration(line: &str, patterns: &[Regex]) -> bool {
+    let first_word = line.split_whitespace().next().unwrap_or("");
+    !CONTROL_KEYWORDS.contains(&first_word) && patterns.iter().any(|re| re.is_match(line))
+}
+
+/// Function to classify results as definitions or usages of the queried symbols
+///
+/// A result is a definition if one of its first lines declares a queried symbol,
+/// and a usage if it references a queried symbol as a whole word anywhere else.
+/// Results that do neither keep `usage_kind` unset.
+///
+/// # Arguments
+/// * `results` - Search results to update in place
+/// * `queries` - The original search queries
+pub fn classify_usages(results: &mut [SearchResult], queries: &[String]) {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    let symbols = extract_symbol_names(queries);
+    if debug_mode {
+        println!("DEBUG: Classifying usages for symbols: {:?}", symbols);
+    }
+    if symbols.is_empty() {
+        return;
+    }
+
+    let matchers: Vec<(Vec<Regex>, Regex)> = symbols
+        .iter()
+        .filter_map(|symbol| {
+            let reference = Regex::new(&format!(r"\b{}\b", regex::escape(symbol))).ok()?;
+            Some((definition_patterns(symbol), reference))
+        })
+        .collect();
+
+    for result in results.iter_mut() {
+        let defines = matchers.iter().any(|(definitions, _)| {
+            result
+                .code
+                .lines()
+                .take(SIGNATURE_LINES)
+                .any(|line| is_declaration(line, definitions))
+        });
+
+        result.usage_kind = if defines {
+            Some(UsageKind::Definition)
+        } else if matchers
+            .iter()
+            .any(|(_, reference)| reference.is_match(&result.code))
+        {
+            Some(UsageKind::Usage)
+        } else {
+            None
+        };
+    }
+}
+
+/// Sort key that puts usages ahead of definitions, and both ahead of unrelated results
+pub fn usage_priority(kind: Option<UsageKind>) -> u8 {
+    match kind {
+        Some(UsageKind::Usage) => 0,
+        Some(UsageKind::Definition) => 1,
+        None => 2,
+    }
+}
@@ -0,0 +1,727 @@
+//! Long-running search server.
+//!
+//! Every `probe search` walks the directory, compiles the query's patterns and parses the
+//! files it matched before it can answer. `probe serve` does that work once and keeps it: one
+//! [`ProbeEngine`] holds the file list, the term counts of every file and the parse trees of
+//! the files searches matched, and compiled patterns are kept for repeated queries, so a
+//! query takes milliseconds.
+//!
+//! Queries are read one per line, from stdin or, with `--socket`, from any number of clients
+//! of a Unix socket. A line is either the query itself or a JSON object with a `query` and
+//! optionally a `session`, whose returned blocks later queries with the same ID skip, and
+//! `paths`, the files and directories below the root the query may search. Each query gets
+//! one line of JSON back: its results and summary, or an `error`.
+//!
+//! A server reachable by others can require a token, sent as `token` in every request, answer
+//! each token at most so many times a minute, and keep every request to the paths it allows.
+//! Requests without the token share one count of their own, so reconnecting or guessing
+//! tokens doesn't get more answers.
+//!
+//! With `--watch`, the server watches the directory and applies what changed before the next
+//! query: a file added, removed or renamed walks the directory again, and changed files are
+//! counted again. Parse trees are keyed by content, so a changed file is parsed again without
+//! being looked up.
+//!
+//! With `--notify-url`, the server also checks the changes every second and runs the saved
+//! `--notify-query` queries again after each; results they didn't return before, whether in a
+//! new file or a changed block, are POSTed to the URL as JSON. Queries never wait on the
+//! webhook: payloads are sent from a thread of their own.
+
+use anyhow::{anyhow, Context, Result};
+use notify::event::{EventKind, ModifyKind};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{BufRead, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::error::ProbeError;
+use crate::mcp::result_json;
+use crate::search::empty_queries::REPHRASE_SUGGESTION;
+use crate::search::index::INDEX_DIR;
+use crate::search::{EngineOptions, ProbeEngine, Session};
+
+/// What applying the watched changes did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AppliedChanges {
+    /// Whether the directory was walked again for added or removed files
+    pub walked: bool,
+    /// Number of changed paths counted again
+    pub updated: usize,
+}
+
+/// Watcher of the searched directory, with the events it sent
+struct Watch {
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    /// The directory as searches name it
+    root: PathBuf,
+    /// The directory as events may name it
+    canonical_root: PathBuf,
+}
+
+impl Watch {
+    /// Helper function to name a path from an event as searches name it
+    fn search_path(&self, path: &Path) -> PathBuf {
+        match path.strip_prefix(&self.canonical_root) {
+            Ok(relative) => self.root.join(relative),
+            Err(_) => path.to_path_buf(),
+        }
+    }
+}
+
+/// How often a notifying server applies file changes while no query comes in
+const NOTIFY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long to wait for a webhook to take a payload
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Environment variable setting the token requests must carry, like `--auth-token`
+pub const AUTH_TOKEN_ENV: &str = "PROBE_SERVE_TOKEN";
+
+/// Period the requests of `--rate-limit` are counted over
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// A query whose new results are sent to the webhook
+struct SavedQuery {
+    query: String,
+    /// File and code of the results it returned last time
+    seen: HashSet<(String, String)>,
+}
+
+/// Saved queries, and the thread sending their new results to a webhook
+struct Notifier {
+    queries: Mutex<Vec<SavedQuery>>,
+    payloads: Sender<Value>,
+}
+
+/// Helper function to POST each payload to `url`, until the server is dropped
+///
+/// Failed deliveries are reported and not retried. The client is built on this thread, as
+/// blocking clients cannot be used where an async runtime runs.
+fn deliver_payloads(url: String, payloads: Receiver<Value>) {
+    let client = match reqwest::blocking::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Failed to create the webhook client: {}", e);
+            return;
+        }
+    };
+    for payload in payloads {
+        let sent = client
+            .post(&url)
+            .json(&payload)
+            .send()
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = sent {
+            eprintln!("Failed to notify {}: {}", url, e);
+        }
+    }
+}
+
+/// Helper function to check whether a path is probe's or git's bookkeeping, never searched
+fn is_internal(path: &Path) -> bool {
+    path.components()
+        .any(|c| matches!(c, Component::Normal(name) if name == ".git" || name == INDEX_DIR))
+}
+
+/// A warm engine answering queries, kept current by a file watcher
+pub struct SearchServer {
+    engine: RwLock<ProbeEngine>,
+    sessions: Mutex<HashMap<String, Session>>,
+    watch: Option<Mutex<Watch>>,
+    notifier: Option<Notifier>,
+    auth_token: Option<String>,
+    /// Requests each token may send per minute
+    rate_limit: Option<usize>,
+    /// Times of the requests answered in the last minute, by the token they carried
+    recent: Mutex<HashMap<String, VecDeque<Instant>>>,
+    /// Files and directories below the root that requests may search
+    allowed_paths: Option<Vec<PathBuf>>,
+}
+
+impl SearchServer {
+    /// Prepare a server searching `root`
+    ///
+    /// The engine keeps corpus statistics and parse trees whatever `options` say.
+    pub fn new(root: &Path, options: EngineOptions) -> Result<Self> {
+        let engine = ProbeEngine::with_options(
+            root,
+            EngineOptions {
+                corpus_stats: true,
+                cache_trees: true,
+                ..options
+            },
+        )?;
+        Ok(Self {
+            engine: RwLock::new(engine),
+            sessions: Mutex::new(HashMap::new()),
+            watch: None,
+            notifier: None,
+            auth_token: None,
+            rate_limit: None,
+            recent: Mutex::new(HashMap::new()),
+            allowed_paths: None,
+        })
+    }
+
+    /// The engine answering queries
+    pub fn engine(&self) -> &RwLock<ProbeEngine> {
+        &self.engine
+    }
+
+    /// Refuse requests that don't carry `token` as their `token`
+    pub fn require_token(&mut self, token: &str) {
+        self.auth_token = Some(token.to_string());
+    }
+
+    /// Refuse requests beyond `requests` a minute for each token
+    ///
+    /// Requests without the required token, or all requests when no token is required,
+    /// are counted together whichever client sent them.
+    pub fn limit_rate(&mut self, requests: usize) {
+        self.rate_limit = Some(requests);
+    }
+
+    /// Let requests search only `paths`, files and directories below the root
+    ///
+    /// Requests that name no `paths` search all of them; requests naming others are refused.
+    pub fn allow_paths(&mut self, paths: &[PathBuf]) -> Result<()> {
+        let paths = paths
+            .iter()
+            .map(|path| {
+                below_root(path).ok_or_else(|| {
+                    anyhow!(
+                        "--allow-path {} must be relative to the served directory, without ..",
+                        path.display()
+                    )
+                })
+            })
+            .collect::<Result<_>>()?;
+        self.allowed_paths = Some(paths);
+        Ok(())
+    }
+
+    /// Start watching the directory; changes are applied before each query from then on
+    pub fn watch(&mut self) -> Result<()> {
+        let root = self.engine.get_mut().unwrap().root().to_path_buf();
+        let (sender, events) = mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(sender).context("Failed to start the file watcher")?;
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .with_context(|| format!("Failed to watch {}", root.display()))?;
+        let canonical_root = root.canonicalize().unwrap_or_else(|_| root.clone());
+        self.watch = Some(Mutex::new(Watch {
+            _watcher: watcher,
+            events,
+            root,
+            canonical_root,
+        }));
+        Ok(())
+    }
+
+    /// POST the results of `queries` that are new after file changes to `url`
+    ///
+    /// The queries are run once now, and what they return is not sent. After changes are
+    /// applied, each query runs again and the results it didn't return the time before are
+    /// sent as `{"query": ..., "results": [...], "summary": {"count": ...}}`.
+    pub fn notify(&mut self, url: &str, queries: &[String]) -> Result<()> {
+        let mut saved = Vec::with_capacity(queries.len());
+        for query in queries {
+            let mut saved_query = SavedQuery {
+                query: query.clone(),
+                seen: HashSet::new(),
+            };
+            self.new_results(&mut saved_query)?;
+            saved.push(saved_query);
+        }
+
+        let (payloads, receiver) = mpsc::channel();
+        let url = url.to_string();
+        std::thread::Builder::new()
+            .name("probe-webhook".to_string())
+            .spawn(move || deliver_payloads(url, receiver))
+            .context("Failed to start the webhook thread")?;
+        self.notifier = Some(Notifier {
+            queries: Mutex::new(saved),
+            payloads,
+        });
+        Ok(())
+    }
+
+    /// Helper function to run a saved query, returning the results it didn't return before
+    fn new_results(&self, saved: &mut SavedQuery) -> Result<Vec<Value>> {
+        let results = self.engine.read().unwrap().search(&saved.query)?;
+        let mut seen = HashSet::new();
+        let mut new = Vec::new();
+        for result in &results.results {
+            let key = (result.file.clone(), result.code.clone());
+            if !saved.seen.contains(&key) {
+                new.push(result_json(result));
+            }
+            seen.insert(key);
+        }
+        saved.seen = seen;
+        Ok(new)
+    }
+
+    /// Helper function to queue the new results of every saved query for the webhook
+    fn notify_new_results(&self) -> Result<()> {
+        let Some(notifier) = &self.notifier else {
+            return Ok(());
+        };
+        let mut queries = notifier.queries.lock().unwrap();
+        for saved in queries.iter_mut() {
+            let results = self.new_results(saved)?;
+            if results.is_empty() {
+                continue;
+            }
+            let payload = json!({
+                "query": saved.query,
+                "summary": { "count": results.len() },
+                "results": results,
+            });
+            // The delivery thread only stops when it can't build a client, already reported
+            let _ = notifier.payloads.send(payload);
+        }
+        Ok(())
+    }
+
+    /// Apply changes every second until `done` is set, for the webhook to hear of them
+    ///
+    /// Without saved queries this returns at once.
+    pub fn poll_changes(&self, done: &AtomicBool) {
+        if self.notifier.is_none() {
+            return;
+        }
+        while !done.load(Ordering::Relaxed) {
+            std::thread::sleep(NOTIFY_INTERVAL);
+            if let Err(e) = self.apply_changes() {
+                eprintln!("Failed to apply file changes: {:#}", e);
+            }
+        }
+    }
+
+    /// Apply the changes the watcher has seen since the last call
+    ///
+    /// Without a watcher this does nothing. With saved queries, their new results are then
+    /// queued for the webhook.
+    pub fn apply_changes(&self) -> Result<AppliedChanges> {
+        let Some(watch) = &self.watch else {
+            return Ok(AppliedChanges::default());
+        };
+        let (walk, changed) = {
+            let watch = watch.lock().unwrap();
+            let mut walk = false;
+            let mut changed = HashSet::new();
+            for event in watch.events.try_iter() {
+                let event = match event {
+                    Ok(event) => event,
+                    // Events may have been lost, so look at the whole directory again
+                    Err(_) => {
+                        walk = true;
+                        continue;
+                    }
+                };
+                let paths: Vec<PathBuf> = event
+                    .paths
+                    .iter()
+                    .filter(|path| !is_internal(path))
+                    .map(|path| watch.search_path(path))
+                    .collect();
+                if paths.is_empty() {
+                    continue;
+                }
+                match event.kind {
+                    EventKind::Create(_)
+                    | EventKind::Remove(_)
+                    | EventKind::Modify(ModifyKind::Name(_)) => {
+                        walk = true;
+                        // A file saved by renaming another over it changed as well
+                        changed.extend(paths);
+                    }
+                    EventKind::Access(_) | EventKind::Modify(ModifyKind::Metadata(_)) => {}
+                    _ => changed.extend(paths),
+                }
+            }
+            (walk, changed)
+        };
+
+        if walk {
+            self.engine.write().unwrap().refresh()?;
+        }
+        let changed: Vec<PathBuf> = changed.into_iter().collect();
+        if !changed.is_empty() {
+            self.engine.read().unwrap().update_files(&changed)?;
+        }
+        if walk || !changed.is_empty() {
+            self.notify_new_results()?;
+        }
+        Ok(AppliedChanges {
+            walked: walk,
+            updated: changed.len(),
+        })
+    }
+
+    /// Answer one request line with the JSON to send back
+    pub fn handle_request(&self, line: &str) -> Value {
+        match self.search(line) {
+            Ok(output) => output,
+            Err(e) => error_json(&ProbeError::from(e)),
+        }
+    }
+
+    fn search(&self, line: &str) -> Result<Value> {
+        let Request {
+            query,
+            session: session_id,
+            token,
+            paths,
+        } = parse_request(line)?;
+        let token = match &self.auth_token {
+            Some(expected) => token.filter(|token| tokens_match(token, expected)),
+            None => None,
+        };
+        self.admit(token.as_deref().unwrap_or_default())?;
+        if self.auth_token.is_some() && token.is_none() {
+            return Err(ProbeError::Unauthorized(
+                "This server requires its token as `token` in a JSON request".to_string(),
+            )
+            .into());
+        }
+        let paths = self.allowed(paths)?;
+        self.apply_changes()?;
+
+        let started = Instant::now();
+        let engine = self.engine.read().unwrap();
+        let results = match &session_id {
+            Some(id) => {
+                let mut sessions = self.sessions.lock().unwrap();
+                let session = sessions
+                    .entry(id.clone())
+                    .or_insert_with(|| Session::new(id.clone()));
+                match &paths {
+                    Some(paths) => engine.search_within(&query, paths, Some(session))?,
+                    None => engine.search_with_session(&query, session)?,
+                }
+            }
+            None => match &paths {
+                Some(paths) => engine.search_within(&query, paths, None)?,
+                None => engine.search(&query)?,
+            },
+        };
+        if std::env::var("DEBUG").unwrap_or_default() == "1" {
+            if let Some(trees) = engine.tree_cache() {
+                let trees = trees.lock().unwrap();
+                println!(
+                    "DEBUG: Parse tree cache: {} hits, {} misses",
+                    trees.hits(),
+                    trees.misses()
+                );
+            }
+        }
+
+        let mut output = json!({
+            "results": results.results.iter().map(result_json).collect::<Vec<_>>(),
+            "summary": {
+                "count": results.results.len(),
+                "total_bytes": results.results.iter().map(|r| r.code.len()).sum::<usize>(),
+                "elapsed_ms": started.elapsed().as_secs_f64() * 1000.0,
+            },
+        });
+        if !results.skipped_files.is_empty() {
+            // Results left out by --max-results or --max-tokens
+            output["summary"]["skipped"] = results.skipped_files.len().into();
+        }
+        if results.empty_query_cached {
+            // The session found nothing for this query before, and no file changed since
+            output["summary"]["cached_empty"] = true.into();
+            output["suggestion"] = REPHRASE_SUGGESTION.into();
+        }
+        if let Some(id) = session_id {
+            output["session"] = id.into();
+            output["cached_blocks_skipped"] = results.cached_blocks_skipped.unwrap_or(0).into();
+        }
+        Ok(output)
+    }
+
+    /// Helper function to count a request with `token`, refusing it beyond the rate limit
+    ///
+    /// Requests without the server's token count under the empty token.
+    fn admit(&self, token: &str) -> Result<()> {
+        let Some(limit) = self.rate_limit else {
+            return Ok(());
+        };
+        let mut recent = self.recent.lock().unwrap();
+        let recent = recent.entry(token.to_string()).or_default();
+        if !admit(recent, limit, Instant::now()) {
+            return Err(ProbeError::LimitExceeded(format!(
+                "More than {} requests in a minute; try again later",
+                limit
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Helper function to keep the paths of a request to those the server allows
+    fn allowed(&self, paths: Option<Vec<PathBuf>>) -> Result<Option<Vec<PathBuf>>> {
+        let Some(allowed) = &self.allowed_paths else {
+            return Ok(paths);
+        };
+        let Some(paths) = paths else {
+            return Ok(Some(allowed.clone()));
+        };
+        let outside = paths
+            .iter()
+            .find(|path| !allowed.iter().any(|allowed| path.starts_with(allowed)));
+        if let Some(path) = outside {
+            return Err(ProbeError::Unauthorized(format!(
+                "Path {} is outside the paths this server allows",
+                path.display()
+            ))
+            .into());
+        }
+        Ok(Some(paths))
+    }
+
+    /// Answer the request lines of `input` on `output` until `input` ends
+    pub fn serve(&self, input: impl BufRead, mut output: impl Write) -> Result<()> {
+        for line in input.lines() {
+            let line = line.context("Failed to read a request")?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = self.handle_request(&line);
+            writeln!(output, "{}", response).context("Failed to write a response")?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Helper function to turn an error into the response sent back
+fn error_json(error: &ProbeError) -> Value {
+    json!({ "error": { "kind": error.kind(), "message": error.message() } })
+}
+
+/// Helper function to record a request unless `limit` were answered in the last minute
+///
+/// `recent` holds the times of the requests answered in the last minute.
+fn admit(recent: &mut VecDeque<Instant>, limit: usize, now: Instant) -> bool {
+    while recent
+        .front()
+        .is_some_and(|time| now.duration_since(*time) >= RATE_WINDOW)
+    {
+        recent.pop_front();
+    }
+    if recent.len() >= limit {
+        return false;
+    }
+    recent.push_back(now);
+    true
+}
+
+/// Helper function to compare tokens in a time that doesn't tell where they differ
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// A request line: a query, with what its JSON form can add
+#[derive(Debug, Default)]
+struct Request {
+    query: String,
+    session: Option<String>,
+    token: Option<String>,
+    /// Files and directories below the root the query may search
+    paths: Option<Vec<PathBuf>>,
+}
+
+/// Helper function to read a request line
+fn parse_request(line: &str) -> Result<Request> {
+    let line = line.trim();
+    if !line.starts_with('{') {
+        return Ok(Request {
+            query: line.to_string(),
+            ..Request::default()
+        });
+    }
+    let request: Value =
+        serde_json::from_str(line).map_err(|e| anyhow!("Invalid request: {}", e))?;
+    let query = match request.get("query") {
+        Some(Value::String(query)) => query.clone(),
+        _ => return Err(anyhow!("Invalid request: `query` must be a string")),
+    };
+    let session = match request.get("session") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(id)) => Some(id.clone()),
+        Some(_) => return Err(anyhow!("Invalid request: `session` must be a string")),
+    };
+    let token = match request.get("token") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(token)) => Some(token.clone()),
+        Some(_) => return Err(anyhow!("Invalid request: `token` must be a string")),
+    };
+    let paths = match request.get("paths") {
+        None | Some(Value::Null) => None,
+        Some(Value::Array(paths)) => Some(
+            paths
+                .iter()
+                .map(|path| match path {
+                    Value::String(path) => request_path(path),
+                    _ => Err(anyhow!("Invalid request: `paths` must be strings")),
+                })
+                .collect::<Result<Vec<_>>>()?,
+        ),
+        Some(_) => return Err(anyhow!("Invalid request: `paths` must be an array")),
+    };
+    Ok(Request {
+        query,
+        session,
+        token,
+        paths,
+    })
+}
+
+/// Helper function to check that a path stays below the root, dropping its `.` components
+fn below_root(path: &Path) -> Option<PathBuf> {
+    path.components()
+        .filter(|c| *c != Component::CurDir)
+        .map(|c| match c {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Helper function to check that a path of a request stays below the root
+fn request_path(path: &str) -> Result<PathBuf> {
+    below_root(Path::new(path)).ok_or_else(|| {
+        ProbeError::QueryParse(format!(
+            "Invalid request: path {} must be relative to the served directory, without ..",
+            path
+        ))
+        .into()
+    })
+}
+
+/// Handle the serve command: answer queries from stdin, or from clients of `socket`
+///
+/// With `notify_url`, new results of `notify_queries` are sent to it as files change. Without
+/// `auth_token`, the token is read from `PROBE_SERVE_TOKEN` if set. With `allow_paths`,
+/// queries only search those files and directories.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_serve(
+    path: &Path,
+    options: EngineOptions,
+    watch: bool,
+    socket: Option<&Path>,
+    notify_url: Option<&str>,
+    notify_queries: &[String],
+    auth_token: Option<String>,
+    rate_limit: Option<usize>,
+    allow_paths: &[PathBuf],
+) -> Result<()> {
+    if rate_limit == Some(0) {
+        return Err(anyhow!("--rate-limit must be at least 1"));
+    }
+    let mut server = SearchServer::new(path, options)?;
+    let auth_token = auth_token
+        .or_else(|| std::env::var(AUTH_TOKEN_ENV).ok())
+        .filter(|token| !token.is_empty());
+    if let Some(token) = &auth_token {
+        server.require_token(token);
+    }
+    if let Some(limit) = rate_limit {
+        server.limit_rate(limit);
+    }
+    if !allow_paths.is_empty() {
+        server.allow_paths(allow_paths)?;
+    }
+    if watch {
+        server.watch()?;
+    }
+    if let Some(url) = notify_url {
+        server.notify(url, notify_queries)?;
+        eprintln!(
+            "Sending new results of {} queries to {}",
+            notify_queries.len(),
+            url
+        );
+    }
+    eprintln!(
+        "Serving {} files from {}{}",
+        server.engine().read().unwrap().file_count(),
+        path.display(),
+        if watch { ", watching for changes" } else { "" }
+    );
+
+    let done = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        scope.spawn(|| server.poll_changes(&done));
+        let served = match socket {
+            Some(socket) => serve_socket(&server, socket),
+            None => server.serve(std::io::stdin().lock(), std::io::stdout()),
+        };
+        done.store(true, Ordering::Relaxed);
+        served
+    })
+}
+
+/// Helper function to answer the clients of a Unix socket, each on its own thread
+#[cfg(unix)]
+fn serve_socket(server: &SearchServer, socket: &Path) -> Result<()> {
+    use std::io::BufReader;
+    use std::os::unix::net::UnixListener;
+
+    let listener = UnixListener::bind(socket).with_context(|| {
+        format!(
+            "Failed to listen on {}; remove it if no server is running",
+            socket.display()
+        )
+    })?;
+    eprintln!("Listening on {}", socket.display());
+
+    std::thread::scope(|scope| {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("Failed to accept a client: {}", e);
+                    continue;
+                }
+            };
+            scope.spawn(move || {
+                let input = match stream.try_clone() {
+                    Ok(input) => BufReader::new(input),
+                    Err(e) => {
+                        eprintln!("Failed to read from a client: {}", e);
+                        return;
+                    }
+                };
+                if let Err(e) = server.serve(input, stream) {
+                    eprintln!("Client failed: {:#}", e);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn serve_socket(_server: &SearchServer, _socket: &Path) -> Result<()> {
+    Err(anyhow!("--socket is only supported on Unix"))
+}
@@ -0,0 +1,570 @@
+use anyhow::{anyhow, Context, Result};
+use colored::*;
+use rayon::prelude::*;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tree_sitter::{Node, Parser as TSParser};
+
+use crate::fs_access;
+use crate::language::factory::get_language_impl;
+use crate::query::{format_and_print_query_results, AstMatch};
+use crate::search::file_list_cache;
+
+/// Options for a type-signature search
+pub struct SignatureOptions<'a> {
+    pub path: &'a Path,
+    pub pattern: &'a str,
+    pub language: Option<&'a str>,
+    pub ignore: &'a [String],
+    pub allow_tests: bool,
+    pub max_results: Option<usize>,
+}
+
+/// A function whose signature matched the pattern
+pub struct SignatureMatch {
+    pub file_path: PathBuf,
+    pub name: String,
+    pub line_start: usize,
+    pub line_end: usize,
+    pub column_start: usize,
+    /// The function header (everything before the body)
+    pub signature: String,
+    pub params: Vec<String>,
+    pub return_type: String,
+}
+
+/// A single parameter in a signature pattern
+#[derive(Debug)]
+enum ParamPattern {
+    /// `..` matches any number of parameters
+    Rest,
+    /// A type pattern matching exactly one parameter
+    Type(Regex),
+}
+
+/// A parsed signature pattern such as `fn(&str, ..) -> Result<_>`
+#[derive(Debug)]
+pub struct SignaturePattern {
+    name: Option<Regex>,
+    params: Vec<ParamPattern>,
+    return_type: Option<Regex>,
+}
+
+/// The languages that have typed signatures we know how to read
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SignatureLanguage {
+    Rust,
+    Go,
+    TypeScript,
+}
+
+impl SignatureLanguage {
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "rs" => Some(SignatureLanguage::Rust),
+            "go" => Some(SignatureLanguage::Go),
+            "ts" | "tsx" => Some(SignatureLanguage::TypeScript),
+            _ => None,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "rust" => Some(SignatureLanguage::Rust),
+            "go" => Some(SignatureLanguage::Go),
+            "typescript" => Some(SignatureLanguage::TypeScript),
+            _ => None,
+        }
+    }
+
+    /// Node kinds that declare a function or method
+    fn function_kinds(&self) -> &'static [&'static str] {
+        match self {
+            SignatureLanguage::Rust => &["function_item", "function_signature_item"],
+            SignatureLanguage::Go => &["function_declaration", "method_declaration"],
+            SignatureLanguage::TypeScript => &[
+                "function_declaration",
+                "function_signature",
+                "method_definition",
+                "method_signature",
+                "abstract_method_signature",
+                "arrow_function",
+            ],
+        }
+    }
+
+    /// The return type used for functions that do not declare one
+    fn default_return_type(&self) -> &'static str {
+        match self {
+            SignatureLanguage::Rust => "()",
+            SignatureLanguage::Go => "",
+            SignatureLanguage::TypeScript => "",
+        }
+    }
+}
+
+/// Split a comma-separated list at the top level, ignoring commas inside brackets
+fn split_top_level(list: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut current = String::new();
+
+    for c in list.chars() {
+        match c {
+            '<' | '(' | '[' | '{' => depth += 1,
+            '>' | ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        parts.push(current.trim().to_string());
+    }
+
+    parts
+}
+
+/// Normalize a type for comparison: drop whitespace and Rust lifetimes
+fn normalize_type(type_text: &str) -> String {
+    let lifetime = Regex::new(r"'[A-Za-z_]\w*\s*,?").unwrap();
+    let without_lifetimes = lifetime.replace_all(type_text, "");
+    without_lifetimes
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        // `&'a str` becomes `&str`, `Foo<'a>` becomes `Foo` and `Foo<T, 'a>` becomes `Foo<T>`
+        .replace("<>", "")
+        .replace(",>", ">")
+}
+
+/// Compile a type pattern into an anchored regex
+///
+/// `_` matches any type, and identifiers may be preceded by a module path, so
+/// `Result<Config>` matches both `Result<Config>` and `anyhow::Result<Config>`.
+fn compile_type_pattern(pattern: &str) -> Result<Regex> {
+    let normalized = normalize_type(pattern);
+    let mut regex = String::from("^");
+    let chars: Vec<char> = normalized.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let ident: String = chars[start..i].iter().collect();
+            if ident == "_" {
+                regex.push_str(".+?");
+            } else {
+                regex.push_str(r"(?:[A-Za-z_]\w*(?:::|\.))*");
+                regex.push_str(&regex::escape(&ident));
+            }
+        } else {
+            regex.push_str(&regex::escape(&c.to_string()));
+            i += 1;
+        }
+    }
+    regex.push('$');
+
+    Regex::new(&regex).with_context(|| format!("Invalid type pattern: {}", pattern))
+}
+
+impl SignaturePattern {
+    /// Parse a signature pattern
+    ///
+    /// Accepted forms include `fn(&str) -> Result<_>`, `fn parse(..) -> Config`,
+    /// `func(string) (int, error)` and `function(string): Promise<_>`. A missing
+    /// return type matches any return type.
+    pub fn parse(pattern: &str) -> Result<Self> {
+        let mut rest = pattern.trim();
+        for keyword in ["function", "func", "fn"] {
+            if let Some(stripped) = rest.strip_prefix(keyword) {
+                // Only strip whole keywords, so `function_name(..)` keeps its name
+                if !stripped.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+                    rest = stripped.trim_start();
+                    break;
+                }
+            }
+        }
+
+        let open = rest.find('(').ok_or_else(|| {
+            anyhow!(
+                "Signature pattern must contain a parameter list: {}",
+                pattern
+            )
+        })?;
+        let name = rest[..open].trim();
+
+        // Find the matching closing parenthesis
+        let mut depth = 0;
+        let mut close = None;
+        for (i, c) in rest[open..].char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        close = Some(open + i);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let close =
+            close.ok_or_else(|| anyhow!("Unbalanced parentheses in signature: {}", pattern))?;
+
+        let params = split_top_level(&rest[open + 1..close])
+            .into_iter()
+            .map(|param| {
+                if param == ".." {
+                    Ok(ParamPattern::Rest)
+                } else {
+                    compile_type_pattern(&param).map(ParamPattern::Type)
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let return_part = rest[close + 1..].trim();
+        let return_part = return_part
+            .strip_prefix("->")
+            .or_else(|| return_part.strip_prefix(':'))
+            .unwrap_or(return_part)
+            .trim();
+        let return_type = if return_part.is_empty() {
+            None
+        } else {
+            Some(compile_type_pattern(return_part)?)
+        };
+
+        let name = if name.is_empty() || name == "_" {
+            None
+        } else {
+            Some(Regex::new(&format!("^{}$", regex::escape(name)))?)
+        };
+
+        Ok(SignaturePattern {
+            name,
+            params,
+            return_type,
+        })
+    }
+
+    fn matches_params(patterns: &[ParamPattern], params: &[String]) -> bool {
+        match patterns.split_first() {
+            None => params.is_empty(),
+            Some((ParamPattern::Rest, rest)) => {
+                (0..=params.len()).any(|skip| Self::matches_params(rest, &params[skip..]))
+            }
+            Some((ParamPattern::Type(re), rest)) => match params.split_first() {
+                Some((param, remaining)) => {
+                    re.is_match(param) && Self::matches_params(rest, remaining)
+                }
+                None => false,
+            },
+        }
+    }
+
+    /// Check whether a function with the given name, parameter types and return type matches
+    pub fn matches(&self, name: &str, params: &[String], return_type: &str) -> bool {
+        if let Some(name_re) = &self.name {
+            if !name_re.is_match(name) {
+                return false;
+            }
+        }
+        if let Some(return_re) = &self.return_type {
+            if !return_re.is_match(&normalize_type(return_type)) {
+                return false;
+            }
+        }
+        let params: Vec<String> = params.iter().map(|p| normalize_type(p)).collect();
+        Self::matches_params(&self.params, &params)
+    }
+}
+
+/// Strip the leading `:` of a TypeScript type annotation
+fn annotation_text(node: Node, content: &[u8]) -> String {
+    let text = node.utf8_text(content).unwrap_or("").trim();
+    text.strip_prefix(':').unwrap_or(text).trim().to_string()
+}
+
+/// Read the parameter types of a function node
+fn parameter_types(node: Node, content: &[u8], language: SignatureLanguage) -> Vec<String> {
+    let Some(parameters) = node.child_by_field_name("parameters") else {
+        return Vec::new();
+    };
+
+    let mut types = Vec::new();
+    let mut cursor = parameters.walk();
+    for param in parameters.named_children(&mut cursor) {
+        match language {
+            SignatureLanguage::Rust => {
+                if param.kind() == "parameter" {
+                    if let Some(type_node) = param.child_by_field_name("type") {
+                        types.push(type_node.utf8_text(content).unwrap_or("").to_string());
+                    }
+                }
+            }
+            SignatureLanguage::Go => {
+                if let Some(type_node) = param.child_by_field_name("type") {
+                    let mut type_text = type_node.utf8_text(content).unwrap_or("").to_string();
+                    if param.kind() == "variadic_parameter_declaration" {
+                        type_text = format!("...{}", type_text);
+                    }
+                    // `a, b int` declares two parameters of the same type
+                    let mut name_cursor = param.walk();
+                    let names = param
+                        .children_by_field_name("name", &mut name_cursor)
+                        .count()
+                        .max(1);
+                    for _ in 0..names {
+                        types.push(type_text.clone());
+                    }
+                }
+            }
+            SignatureLanguage::TypeScript => {
+                if matches!(param.kind(), "required_parameter" | "optional_parameter") {
+                    let type_text = param
+                        .child_by_field_name("type")
+                        .map(|t| annotation_text(t, content))
+                        .unwrap_or_else(|| "any".to_string());
+                    types.push(type_text);
+                }
+            }
+        }
+    }
+
+    types
+}
+
+/// Read the declared return type of a function node
+fn return_type(node: Node, content: &[u8], language: SignatureLanguage) -> String {
+    let field = match language {
+        SignatureLanguage::Go => "result",
+        _ => "return_type",
+    };
+    match node.child_by_field_name(field) {
+        Some(type_node) => annotation_text(type_node, content),
+        None => language.default_return_type().to_string(),
+    }
+}
+
+/// Read the name of a function node (arrow functions take the variable name)
+fn function_name(node: Node, content: &[u8]) -> String {
+    if let Some(name) = node.child_by_field_name("name") {
+        return name.utf8_text(content).unwrap_or("").to_string();
+    }
+    node.parent()
+        .filter(|parent| parent.kind() == "variable_declarator")
+        .and_then(|parent| parent.child_by_field_name("name"))
+        .and_then(|name| name.utf8_text(content).ok())
+        .unwrap_or("")
+        .to_string()
+}
+
+/// Collect every function node in the tree
+fn collect_functions<'t>(node: Node<'t>, kinds: &[&str], functions: &mut Vec<Node<'t>>) {
+    if kinds.contains(&node.kind()) {
+        functions.push(node);
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_functions(child, kinds, functions);
+    }
+}
+
+/// Find all functions in a single file whose signature matches the pattern
+fn search_file(
+    file_path: &Path,
+    pattern: &SignaturePattern,
+    language_filter: Option<SignatureLanguage>,
+) -> Result<Vec<SignatureMatch>> {
+    let extension = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let Some(language) = SignatureLanguage::from_extension(extension) else {
+        return Ok(Vec::new());
+    };
+    if language_filter.is_some_and(|filter| filter != language) {
+        return Ok(Vec::new());
+    }
+    let Some(language_impl) = get_language_impl(extension) else {
+        return Ok(Vec::new());
+    };
+
+    let content = fs_access::read_to_string(file_path)
+        .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
+
+    let mut parser = TSParser::new();
+    parser.set_language(&language_impl.get_tree_sitter_language())?;
+    let tree = parser
+        .parse(&content, None)
+        .ok_or_else(|| anyhow!("Failed to parse file: {}", file_path.display()))?;
+
+    let mut functions = Vec::new();
+    collect_functions(tree.root_node(), language.function_kinds(), &mut functions);
+
+    let bytes = content.as_bytes();
+    let mut matches = Vec::new();
+    for function in functions {
+        let name = function_name(function, bytes);
+        let params = parameter_types(function, bytes, language);
+        let ret = return_type(function, bytes, language);
+
+        if !pattern.matches(&name, &params, &ret) {
+            continue;
+        }
+
+        // The signature is everything up to the body
+        let header_end = function
+            .child_by_field_name("body")
+            .map(|body| body.start_byte())
+            .unwrap_or_else(|| function.end_byte());
+        let signature = content[function.start_byte()..header_end]
+            .trim()
+            .to_string();
+
+        matches.push(SignatureMatch {
+            file_path: file_path.to_path_buf(),
+            name,
+            line_start: function.start_position().row + 1,
+            line_end: function.end_position().row + 1,
+            column_start: function.start_position().column + 1,
+            signature,
+            params,
+            return_type: ret,
+        });
+    }
+
+    Ok(matches)
+}
+
+/// Search for functions whose type signature matches a pattern
+pub fn perform_signature_search(options: &SignatureOptions) -> Result<Vec<SignatureMatch>> {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    let pattern = SignaturePattern::parse(options.pattern)?;
+    if debug_mode {
+        println!("DEBUG: Parsed signature pattern: {:?}", pattern);
+    }
+
+    let language_filter = match options.language {
+        Some(name) => Some(
+            SignatureLanguage::from_name(name)
+                .ok_or_else(|| anyhow!("Signature search does not support language: {}", name))?,
+        ),
+        None => None,
+    };
+
+    let file_list = file_list_cache::get_file_list(
+        options.path,
+        options.allow_tests,
+        options.ignore,
+        false,
+        file_list_cache::FileFilters::default(),
+    )?;
+
+    let mut matches: Vec<SignatureMatch> = file_list
+        .files
+        .par_iter()
+        .flat_map(|path| match search_file(path, &pattern, language_filter) {
+            Ok(matches) => matches,
+            Err(e) => {
+                if debug_mode {
+                    println!("DEBUG: Error searching file {:?}: {:?}", path, e);
+                }
+                Vec::new()
+            }
+        })
+        .collect();
+
+    // Keep output stable regardless of the parallel walk order
+    matches.sort_by(|a, b| {
+        a.file_path
+            .cmp(&b.file_path)
+            .then(a.line_start.cmp(&b.line_start))
+    });
+
+    if let Some(max) = options.max_results {
+        matches.truncate(max);
+    }
+
+    Ok(matches)
+}
+
+/// Handle the sig command
+pub fn handle_signature_search(
+    pattern: &str,
+    path: &Path,
+    language: Option<&str>,
+    ignore: &[String],
+    allow_tests: bool,
+    max_results: Option<usize>,
+    format: &str,
+) -> Result<()> {
+    // Only print information for non-JSON/XML formats
+    if format != "json" && format != "xml" {
+        println!("{} {}", "Signature:".bold().green(), pattern);
+        println!("{} {}", "Path:".bold().green(), path.display());
+        if let Some(lang) = language {
+            println!("{} {}", "Language:".bold().green(), lang);
+        }
+    }
+
+    let start_time = Instant::now();
+
+    let options = SignatureOptions {
+        path,
+        pattern,
+        language,
+        ignore,
+        allow_tests,
+        max_results,
+    };
+
+    let matches = perform_signature_search(&options)?;
+    let duration = start_time.elapsed();
+
+    if std::env::var("DEBUG").unwrap_or_default() == "1" {
+        for m in &matches {
+            println!(
+                "DEBUG: Matched {} ({}) -> {}",
+                m.name,
+                m.params.join(", "),
+                m.return_type
+            );
+        }
+    }
+
+    // Reuse the query output formats, showing the signature as the matched text
+    let ast_matches: Vec<AstMatch> = matches
+        .iter()
+        .map(|m| AstMatch {
+            file_path: m.file_path.clone(),
+            line_start: m.line_start,
+            line_end: m.line_end,
+            column_start: m.column_start,
+            column_end: m.column_start + m.signature.lines().last().unwrap_or("").len(),
+            matched_text: m.signature.clone(),
+        })
+        .collect();
+
+    if ast_matches.is_empty() && format != "json" && format != "xml" {
+        println!("{}", "No results found.".yellow().bold());
+        println!("Search completed in {:.2?}", duration);
+        return Ok(());
+    }
+
+    if format != "json" && format != "xml" {
+        println!("Found {} matches in {:.2?}", ast_matches.len(), duration);
+        println!();
+    }
+
+    format_and_print_query_results(&ast_matches, format)
+}
@@ -0,0 +1,334 @@
+//! Finding blocks similar to a piece of code.
+//!
+//! `probe similar --file foo.rs --lines 10-40` (or a snippet on stdin) answers "have we
+//! written this before?". The snippet's most distinctive identifiers are searched for with
+//! the reranker to collect candidate blocks, and the candidates are then ordered by how many
+//! token shingles, runs of consecutive tokens, they share with the snippet.
+
+use anyhow::{anyhow, Context, Result};
+use colored::*;
+use regex::Regex;
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use crate::fs_access;
+use crate::models::SearchResult;
+use crate::path_encoding::encode_path;
+use crate::search::query::balanced_or;
+use crate::search::tokenization::is_stop_word;
+use crate::search::{EngineOptions, ProbeEngine};
+
+/// Number of consecutive tokens in a shingle
+pub const SHINGLE_SIZE: usize = 3;
+
+/// Most identifiers of the snippet that are searched for
+const MAX_QUERY_WORDS: usize = 16;
+
+/// Candidates collected per requested result
+const CANDIDATES_PER_RESULT: usize = 5;
+
+/// Options for a similar-block search
+pub struct SimilarOptions<'a> {
+    /// Directory to search
+    pub path: &'a Path,
+    /// The code to find similar blocks for
+    pub snippet: &'a str,
+    /// File and lines the snippet was taken from, left out of the results
+    pub source: Option<(&'a Path, (usize, usize))>,
+    pub custom_ignores: &'a [String],
+    pub allow_tests: bool,
+    pub reranker: &'a str,
+    pub max_results: usize,
+    /// Blocks sharing less than this share of shingles with the snippet are left out
+    pub min_similarity: f64,
+}
+
+/// A block found similar to the snippet
+#[derive(Debug, Clone)]
+pub struct SimilarBlock {
+    pub result: SearchResult,
+    /// Jaccard similarity of the shingles of the block and the snippet, from 0 to 1
+    pub similarity: f64,
+}
+
+/// Helper function to split code into tokens: words, numbers and single symbols
+fn code_tokens(code: &str) -> Vec<&str> {
+    static TOKEN: OnceLock<Regex> = OnceLock::new();
+    TOKEN
+        .get_or_init(|| Regex::new(r"[\p{L}_][\p{L}\p{N}_]*|\p{N}+|\S").unwrap())
+        .find_iter(code)
+        .map(|m| m.as_str())
+        .collect()
+}
+
+/// Function to get the shingles of a piece of code
+///
+/// Code shorter than a shingle is a single shingle of all its tokens.
+pub fn shingles(code: &str) -> HashSet<Vec<&str>> {
+    let tokens = code_tokens(code);
+    if tokens.len() < SHINGLE_SIZE {
+        return [tokens].into_iter().filter(|t| !t.is_empty()).collect();
+    }
+    tokens
+        .windows(SHINGLE_SIZE)
+        .map(|window| window.to_vec())
+        .collect()
+}
+
+/// Function to compute the Jaccard similarity of the shingles of two pieces of code
+pub fn shingle_similarity(a: &str, b: &str) -> f64 {
+    let a = shingles(a);
+    let b = shingles(b);
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Helper function to build the candidate query from the snippet's identifiers
+///
+/// The longest identifiers tend to be the most distinctive, and any of them may match.
+fn candidate_query(snippet: &str) -> Option<String> {
+    let mut words: Vec<&str> = Vec::new();
+    for token in code_tokens(snippet) {
+        let is_identifier = token.chars().next().is_some_and(|c| c.is_alphabetic());
+        if is_identifier
+            && token.len() >= 3
+            && !is_stop_word(&token.to_lowercase())
+            && !words.contains(&token)
+        {
+            words.push(token);
+        }
+    }
+    // Stable, so identifiers of the same length keep the order they appear in
+    words.sort_by_key(|word| std::cmp::Reverse(word.len()));
+    words.truncate(MAX_QUERY_WORDS);
+
+    let words: Vec<String> = words.into_iter().map(str::to_string).collect();
+    (!words.is_empty()).then(|| balanced_or(&words))
+}
+
+/// Helper function to check whether a result is the block the snippet was taken from
+fn is_source(result: &SearchResult, source: Option<(&Path, (usize, usize))>) -> bool {
+    let Some((file, (start, end))) = source else {
+        return false;
+    };
+    let result_path = result
+        .file_path
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(&result.file));
+    let same_file = match (
+        std::fs::canonicalize(&result_path),
+        std::fs::canonicalize(file),
+    ) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => result_path == file,
+    };
+    same_file && result.lines.0 <= end && start <= result.lines.1
+}
+
+/// Function to find the blocks most similar to a snippet
+///
+/// Results are ordered by shingle similarity, with the reranker's score breaking ties.
+pub fn find_similar(options: &SimilarOptions) -> Result<Vec<SimilarBlock>> {
+    let Some(query) = candidate_query(options.snippet) else {
+        return Err(anyhow!("The snippet has no identifiers to search for"));
+    };
+    if std::env::var("DEBUG").unwrap_or_default() == "1" {
+        println!("DEBUG: Similar-block candidates query: {}", query);
+    }
+
+    let engine = ProbeEngine::with_options(
+        options.path,
+        EngineOptions {
+            custom_ignores: options.custom_ignores.to_vec(),
+            allow_tests: options.allow_tests,
+            reranker: options.reranker.to_string(),
+            max_results: Some(options.max_results.max(1) * CANDIDATES_PER_RESULT),
+            no_merge: true,
+            ..EngineOptions::default()
+        },
+    )?;
+    let candidates = engine.search(&query)?.results;
+
+    let mut blocks: Vec<SimilarBlock> = candidates
+        .into_iter()
+        .filter(|result| !is_source(result, options.source))
+        .map(|result| SimilarBlock {
+            similarity: shingle_similarity(options.snippet, &result.code),
+            result,
+        })
+        .filter(|block| block.similarity >= options.min_similarity)
+        .collect();
+
+    blocks.sort_by(|a, b| {
+        b.similarity
+            .partial_cmp(&a.similarity)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| {
+                b.result
+                    .score
+                    .partial_cmp(&a.result.score)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+    });
+    blocks.truncate(options.max_results);
+    Ok(blocks)
+}
+
+/// Helper function to parse a `START-END` line range, or a single line
+fn parse_lines(lines: &str) -> Result<(usize, usize)> {
+    let parse = |n: &str| {
+        n.trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| anyhow!("Invalid line range {:?}: expected START-END", lines))
+    };
+    let (start, end) = match lines.split_once('-') {
+        Some((start, end)) => (parse(start)?, parse(end)?),
+        None => (parse(lines)?, parse(lines)?),
+    };
+    if start > end {
+        return Err(anyhow!(
+            "Invalid line range {:?}: start is after end",
+            lines
+        ));
+    }
+    Ok((start, end))
+}
+
+/// Print similar blocks
+pub fn format_and_print_similar_blocks(blocks: &[SimilarBlock], format: &str) -> Result<()> {
+    if format == "json" {
+        let results: Vec<_> = blocks
+            .iter()
+            .map(|block| {
+                let encoded = encode_path(Path::new(&block.result.file));
+                let mut json = serde_json::json!({
+                    "file": encoded.value,
+                    "lines": [block.result.lines.0, block.result.lines.1],
+                    "node_type": block.result.node_type,
+                    "similarity": block.similarity,
+                    "score": block.result.score,
+                    "code": block.result.code,
+                });
+                if let Some(encoding) = encoded.encoding {
+                    json["file_encoding"] = encoding.into();
+                }
+                json
+            })
+            .collect();
+        let wrapper = serde_json::json!({
+            "results": results,
+            "summary": {
+                "count": blocks.len(),
+                "total_bytes": blocks.iter().map(|b| b.result.code.len()).sum::<usize>(),
+            }
+        });
+        println!("{}", serde_json::to_string_pretty(&wrapper)?);
+        return Ok(());
+    }
+
+    for block in blocks {
+        let location = format!(
+            "{}:{}-{}",
+            block.result.file, block.result.lines.0, block.result.lines.1
+        );
+        let similarity = format!("{:.0}% similar", block.similarity * 100.0);
+        if format == "plain" {
+            println!("{} ({})", location, similarity);
+        } else {
+            println!("{} ({})", location.cyan(), similarity.bold());
+        }
+        println!("{}", block.result.code);
+        println!();
+    }
+    Ok(())
+}
+
+/// Handle the similar command
+///
+/// The snippet is `lines` of `file` (the whole file without `lines`), or stdin without `file`.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_similar(
+    path: &Path,
+    file: Option<&Path>,
+    lines: Option<&str>,
+    ignore: &[String],
+    allow_tests: bool,
+    max_results: usize,
+    min_similarity: f64,
+    format: &str,
+) -> Result<()> {
+    let (snippet, source) = match file {
+        Some(file) => {
+            let content = fs_access::read_to_string(file)
+                .with_context(|| format!("Failed to read {:?}", file))?;
+            let range = lines.map(parse_lines).transpose()?;
+            let (start, end) = range.unwrap_or((1, content.lines().count().max(1)));
+            let snippet: Vec<&str> = content
+                .lines()
+                .skip(start - 1)
+                .take(end - start + 1)
+                .collect();
+            if snippet.is_empty() {
+                return Err(anyhow!("{:?} has no lines {}-{}", file, start, end));
+            }
+            (snippet.join("\n"), Some((file, (start, end))))
+        }
+        None => {
+            if lines.is_some() {
+                return Err(anyhow!("--lines needs --file"));
+            }
+            let mut snippet = String::new();
+            std::io::stdin()
+                .read_to_string(&mut snippet)
+                .context("Failed to read the snippet from stdin")?;
+            (snippet, None)
+        }
+    };
+
+    if format != "json" {
+        match source {
+            Some((file, (start, end))) => println!(
+                "{} {}:{}-{}",
+                "Similar to:".bold().green(),
+                file.display(),
+                start,
+                end
+            ),
+            None => println!("{} stdin", "Similar to:".bold().green()),
+        }
+        println!("{} {}", "Path:".bold().green(), path.display());
+    }
+
+    let start_time = Instant::now();
+    let blocks = find_similar(&SimilarOptions {
+        path,
+        snippet: &snippet,
+        source,
+        custom_ignores: ignore,
+        allow_tests,
+        reranker: "bm25",
+        max_results,
+        min_similarity,
+    })?;
+    let duration = start_time.elapsed();
+
+    if format != "json" {
+        if blocks.is_empty() {
+            println!("{}", "No similar blocks found.".yellow().bold());
+            println!("Search completed in {:.2?}", duration);
+            return Ok(());
+        }
+        println!("Found {} similar blocks in {:.2?}", blocks.len(), duration);
+        println!();
+    }
+
+    format_and_print_similar_blocks(blocks.as_slice(), format)
+}
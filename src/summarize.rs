@@ -0,0 +1,589 @@
+use anyhow::{anyhow, Result};
+use colored::*;
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tree_sitter::{Node, Parser as TSParser};
+
+use crate::definition::{
+    container_name, declarator_name, is_declaration_kind, reported_node, SymbolKind,
+};
+use crate::fs_access;
+use crate::impls::{node_text, walk};
+use crate::language::detection::{detect_language, LanguageOverrides};
+use crate::language::factory::get_language_impl;
+use crate::language::visibility::{block_visibility, Visibility};
+use crate::path_encoding::encode_path;
+use crate::search::dir_context::{python_module_docstring, rust_module_docs};
+use crate::search::file_list_cache;
+use crate::search::search_tokens::count_tokens;
+
+/// Declaration kinds whose members are public when the declaration is
+const INTERFACE_KINDS: &[&str] = &[
+    "trait_item",
+    "interface_declaration",
+    "protocol_declaration",
+];
+
+/// Tokens of markup around each symbol and file in the rendered card
+const SYMBOL_OVERHEAD_TOKENS: usize = 4;
+
+/// A public symbol: its signature and the doc comment attached to it
+#[derive(Debug, Clone)]
+pub struct SymbolSummary {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Name of the type or module the symbol is declared in
+    pub container: Option<String>,
+    pub signature: String,
+    pub doc: Option<String>,
+    pub line_start: usize,
+    pub line_end: usize,
+}
+
+/// The public API of one file
+#[derive(Debug, Clone)]
+pub struct FileSummary {
+    pub file_path: PathBuf,
+    pub module_doc: Option<String>,
+    pub symbols: Vec<SymbolSummary>,
+}
+
+/// The API card of a directory
+#[derive(Debug, Clone)]
+pub struct ApiCard {
+    pub path: PathBuf,
+    pub files: Vec<FileSummary>,
+    /// Symbols left out to stay within the token budget
+    pub omitted_symbols: usize,
+    /// Whether doc comments were cut to their first sentence to stay within the budget
+    pub docs_shortened: bool,
+    /// Estimated tokens of the card
+    pub tokens: usize,
+}
+
+/// Options for summarizing a directory
+pub struct SummarizeOptions<'a> {
+    pub path: &'a Path,
+    pub ignore: &'a [String],
+    pub allow_tests: bool,
+    pub max_tokens: Option<usize>,
+}
+
+/// Helper function to strip comment markers from a doc comment line
+fn strip_comment_markers(line: &str) -> &str {
+    line.trim()
+        .trim_start_matches(['/', '*', '!', '#', '-'])
+        .trim_end_matches("*/")
+        .trim()
+}
+
+/// Helper function to join the first paragraph of comment lines into one line
+fn first_paragraph<'a>(lines: impl Iterator<Item = &'a str>) -> Option<String> {
+    let paragraph: Vec<&str> = lines
+        .map(strip_comment_markers)
+        .skip_while(|line| line.is_empty())
+        .take_while(|line| !line.is_empty())
+        .collect();
+    (!paragraph.is_empty()).then(|| paragraph.join(" "))
+}
+
+/// Helper function to get the comment lines directly above a line, skipping attributes
+///
+/// `hash_comments` is set for languages where `#` starts a comment.
+fn doc_above(lines: &[&str], start: usize, hash_comments: bool) -> Option<String> {
+    let mut comment: Vec<&str> = Vec::new();
+    for line in lines[..start.saturating_sub(1)]
+        .iter()
+        .rev()
+        .map(|l| l.trim())
+    {
+        let is_attribute = line.starts_with("#[")
+            || line.starts_with('@')
+            || (line.starts_with('[') && line.ends_with(']'));
+        let is_comment = ["//", "/*", "*", "--"]
+            .iter()
+            .any(|marker| line.starts_with(marker))
+            || (hash_comments && line.starts_with('#') && !line.starts_with("#["));
+        if is_comment {
+            comment.push(line);
+        } else if !is_attribute {
+            break;
+        }
+    }
+    comment.reverse();
+    first_paragraph(comment.into_iter())
+}
+
+/// Helper function to get the docstring at the start of a Python body
+fn docstring(lines: &[&str], start: usize, end: usize) -> Option<String> {
+    let body = &lines[start.min(lines.len())..end.min(lines.len())];
+    let first = body
+        .iter()
+        .map(|line| line.trim())
+        .find(|l| !l.is_empty())?;
+    let quote = ["\"\"\"", "'''"]
+        .into_iter()
+        .find(|quote| first.starts_with(quote))?;
+    let mut text: Vec<&str> = Vec::new();
+    for (index, line) in body
+        .iter()
+        .map(|line| line.trim())
+        .skip_while(|line| line.is_empty())
+        .enumerate()
+    {
+        let inner = if index == 0 {
+            &line[quote.len()..]
+        } else {
+            line
+        };
+        text.push(inner.trim_end_matches(quote));
+        if inner.ends_with(quote) {
+            break;
+        }
+    }
+    first_paragraph(text.into_iter())
+}
+
+/// Helper function to get the doc comment of a module: `//!` docs, a Python docstring or
+/// the comment above a Go `package` clause
+fn module_doc(content: &str, lines: &[&str], language: &str) -> Option<String> {
+    let range = match language {
+        "rust" => rust_module_docs(content),
+        "python" => python_module_docstring(content),
+        "go" => {
+            let package = lines.iter().position(|line| line.starts_with("package "))?;
+            return doc_above(lines, package + 1, false);
+        }
+        _ => None,
+    }?;
+    let text = lines[range.0 - 1..range.1]
+        .iter()
+        .map(|line| line.trim().trim_matches(['"', '\'']));
+    first_paragraph(text)
+}
+
+/// Helper function to get a declaration's header: its text up to the body, on one line
+fn signature(node: Node, reported: Node, content: &[u8]) -> String {
+    let body = node.child_by_field_name("body").or_else(|| {
+        node.child_by_field_name("value")
+            .and_then(|value| value.child_by_field_name("body"))
+    });
+    let text = node_text(reported, content);
+    let header = match body {
+        Some(body) if body.start_byte() > reported.start_byte() => {
+            &text[..(body.start_byte() - reported.start_byte()).min(text.len())]
+        }
+        _ => text.lines().next().unwrap_or(""),
+    };
+    header
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(['{', ':', '=', ';'])
+        .trim_end()
+        .to_string()
+}
+
+/// Helper function to get the name a declaration node declares
+pub(crate) fn declared_name<'a>(node: Node, content: &'a [u8]) -> Option<&'a str> {
+    if let Some(name) = node.child_by_field_name("name") {
+        return Some(node_text(name, content));
+    }
+    if matches!(node.kind(), "function_definition" | "type_definition") {
+        let declarator = node_text(declarator_name(node)?, content);
+        return declarator.rsplit("::").next();
+    }
+    None
+}
+
+/// Helper function to check whether a symbol is part of the public API
+///
+/// Members of traits, interfaces and protocols take the visibility of their container, and
+/// symbols nested in a private type are not public. Symbols whose visibility is unknown
+/// (languages without a rule, such as C headers) count as public.
+fn is_public(node: Node, reported: Node, content: &[u8], language: &str) -> bool {
+    let mut visibility = block_visibility(language, node_text(reported, content));
+    let mut current = node.parent();
+    while let Some(parent) = current {
+        let kind = parent.kind();
+        if is_declaration_kind(kind) && parent.child_by_field_name("name").is_some() {
+            let parent_visibility = block_visibility(language, node_text(parent, content));
+            if INTERFACE_KINDS.contains(&kind) {
+                visibility = parent_visibility;
+            }
+            if parent_visibility == Some(Visibility::Private) {
+                return false;
+            }
+        }
+        current = parent.parent();
+    }
+    visibility != Some(Visibility::Private)
+}
+
+/// Helper function to check whether a node is nested in a function or a type
+fn enclosing_kind(node: Node) -> Option<SymbolKind> {
+    let mut current = node.parent();
+    while let Some(parent) = current {
+        if is_declaration_kind(parent.kind()) || parent.kind() == "impl_item" {
+            let kind = SymbolKind::from_node_kind(parent.kind());
+            if matches!(kind, SymbolKind::Function | SymbolKind::Type) {
+                return Some(kind);
+            }
+        }
+        current = parent.parent();
+    }
+    None
+}
+
+/// List the public symbols of one file
+fn summarize_file(file_path: &Path, allow_tests: bool) -> Result<Option<FileSummary>> {
+    let extension = file_path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let Some(language_impl) = get_language_impl(extension) else {
+        return Ok(None);
+    };
+
+    let content = fs_access::read_to_string(file_path)?;
+    let language = detect_language(file_path, &content, &LanguageOverrides::new()).unwrap_or("");
+    let mut parser = TSParser::new();
+    parser.set_language(&language_impl.get_tree_sitter_language())?;
+    let Some(tree) = parser.parse(&content, None) else {
+        return Ok(None);
+    };
+
+    let bytes = content.as_bytes();
+    let lines: Vec<&str> = content.lines().collect();
+    let hash_comments = matches!(language, "python" | "ruby" | "shell");
+    let mut symbols = Vec::new();
+    walk(tree.root_node(), &mut |node| {
+        if !is_declaration_kind(node.kind()) {
+            return;
+        }
+        let Some(name) = declared_name(node, bytes) else {
+            return;
+        };
+        let kind = SymbolKind::from_node_kind(node.kind());
+        // Locals, fields and enum variants are part of their container, not the API
+        match enclosing_kind(node) {
+            Some(SymbolKind::Function) => return,
+            Some(_) if !matches!(kind, SymbolKind::Function | SymbolKind::Type) => return,
+            _ if kind == SymbolKind::Field => return,
+            _ => {}
+        }
+        let reported = reported_node(node);
+        if !is_public(node, reported, bytes, language) {
+            return;
+        }
+        if !allow_tests && language_impl.is_test_node(&node, bytes) {
+            return;
+        }
+
+        let line_start = reported.start_position().row + 1;
+        let line_end = reported.end_position().row + 1;
+        let doc = doc_above(&lines, line_start, hash_comments).or_else(|| {
+            (language == "python")
+                .then(|| docstring(&lines, line_start, line_end))
+                .flatten()
+        });
+        symbols.push(SymbolSummary {
+            name: name.to_string(),
+            kind,
+            container: container_name(node, bytes),
+            signature: signature(node, reported, bytes),
+            doc,
+            line_start,
+            line_end,
+        });
+    });
+
+    let module_doc = module_doc(&content, &lines, language);
+    if symbols.is_empty() && module_doc.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(FileSummary {
+        file_path: file_path.to_path_buf(),
+        module_doc,
+        symbols,
+    }))
+}
+
+/// Helper function to cut a doc comment to its first sentence
+fn first_sentence(doc: &str) -> String {
+    match doc.find(". ") {
+        Some(end) => doc[..=end].to_string(),
+        None => doc.to_string(),
+    }
+}
+
+fn symbol_tokens(symbol: &SymbolSummary) -> usize {
+    count_tokens(&symbol.signature)
+        + symbol.doc.as_deref().map_or(0, count_tokens)
+        + SYMBOL_OVERHEAD_TOKENS
+}
+
+fn file_tokens(file: &FileSummary) -> usize {
+    count_tokens(&file.file_path.to_string_lossy())
+        + file.module_doc.as_deref().map_or(0, count_tokens)
+        + SYMBOL_OVERHEAD_TOKENS
+}
+
+fn card_tokens(files: &[FileSummary]) -> usize {
+    files
+        .iter()
+        .map(|file| file_tokens(file) + file.symbols.iter().map(symbol_tokens).sum::<usize>())
+        .sum()
+}
+
+/// Helper function to fit a card into a token budget
+///
+/// Doc comments are cut to their first sentence first. If the card is still too large,
+/// symbols are kept round-robin across files, so every file keeps its first symbols, and
+/// the rest are left out. Returns the number of symbols left out.
+fn apply_budget(card: &mut ApiCard, max_tokens: usize) -> usize {
+    if card_tokens(&card.files) <= max_tokens {
+        return 0;
+    }
+
+    card.docs_shortened = true;
+    for file in &mut card.files {
+        file.module_doc = file.module_doc.as_deref().map(first_sentence);
+        for symbol in &mut file.symbols {
+            symbol.doc = symbol.doc.as_deref().map(first_sentence);
+        }
+    }
+    if card_tokens(&card.files) <= max_tokens {
+        return 0;
+    }
+
+    let mut used: usize = card.files.iter().map(file_tokens).sum();
+    let mut kept = vec![0; card.files.len()];
+    let longest = card
+        .files
+        .iter()
+        .map(|f| f.symbols.len())
+        .max()
+        .unwrap_or(0);
+    'rounds: for round in 0..longest {
+        for (index, file) in card.files.iter().enumerate() {
+            let Some(symbol) = file.symbols.get(round) else {
+                continue;
+            };
+            let tokens = symbol_tokens(symbol);
+            if used + tokens > max_tokens {
+                break 'rounds;
+            }
+            used += tokens;
+            kept[index] += 1;
+        }
+    }
+
+    let mut omitted = 0;
+    for (file, keep) in card.files.iter_mut().zip(kept) {
+        omitted += file.symbols.len() - keep;
+        file.symbols.truncate(keep);
+    }
+    omitted
+}
+
+/// Build the API card of a directory: the public symbols of each file, with their
+/// signatures and doc comments, fitted into an optional token budget
+pub fn summarize(options: &SummarizeOptions) -> Result<ApiCard> {
+    let debug_mode = std::env::var("DEBUG").unwrap_or_default() == "1";
+
+    if !options.path.exists() {
+        return Err(anyhow!("Path does not exist: {}", options.path.display()));
+    }
+
+    let file_list = file_list_cache::get_file_list(
+        options.path,
+        options.allow_tests,
+        options.ignore,
+        false,
+        file_list_cache::FileFilters::default(),
+    )?;
+
+    let mut files: Vec<FileSummary> = file_list
+        .files
+        .par_iter()
+        .filter_map(|path| match summarize_file(path, options.allow_tests) {
+            Ok(summary) => summary,
+            Err(e) => {
+                if debug_mode {
+                    println!("DEBUG: Error summarizing file {:?}: {:?}", path, e);
+                }
+                None
+            }
+        })
+        .collect();
+    files.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+
+    let mut card = ApiCard {
+        path: options.path.to_path_buf(),
+        files,
+        omitted_symbols: 0,
+        docs_shortened: false,
+        tokens: 0,
+    };
+    if let Some(max_tokens) = options.max_tokens {
+        card.omitted_symbols = apply_budget(&mut card, max_tokens);
+    }
+    card.tokens = card_tokens(&card.files);
+
+    if debug_mode {
+        println!(
+            "DEBUG: Summarized {} files, {} symbols omitted, ~{} tokens",
+            card.files.len(),
+            card.omitted_symbols,
+            card.tokens
+        );
+    }
+
+    Ok(card)
+}
+
+/// Print an API card
+pub fn format_and_print_card(card: &ApiCard, format: &str) -> Result<()> {
+    let symbol_count: usize = card.files.iter().map(|f| f.symbols.len()).sum();
+
+    if format == "json" {
+        let files: Vec<_> = card
+            .files
+            .iter()
+            .map(|file| {
+                let encoded = encode_path(&file.file_path);
+                let symbols: Vec<_> = file
+                    .symbols
+                    .iter()
+                    .map(|symbol| {
+                        serde_json::json!({
+                            "name": symbol.name,
+                            "kind": symbol.kind.to_string(),
+                            "container": symbol.container,
+                            "signature": symbol.signature,
+                            "doc": symbol.doc,
+                            "lines": [symbol.line_start, symbol.line_end],
+                        })
+                    })
+                    .collect();
+                let mut json = serde_json::json!({
+                    "file": encoded.value,
+                    "module_doc": file.module_doc,
+                    "symbols": symbols,
+                });
+                if let Some(encoding) = encoded.encoding {
+                    json["file_encoding"] = encoding.into();
+                }
+                json
+            })
+            .collect();
+        let wrapper = serde_json::json!({
+            "path": card.path.to_string_lossy(),
+            "files": files,
+            "summary": {
+                "files": card.files.len(),
+                "symbols": symbol_count,
+                "omitted_symbols": card.omitted_symbols,
+                "docs_shortened": card.docs_shortened,
+                "tokens": card.tokens,
+            }
+        });
+        println!("{}", serde_json::to_string_pretty(&wrapper)?);
+        return Ok(());
+    }
+
+    let markdown = format == "markdown";
+    let colored_output = format == "color";
+    if markdown {
+        println!("# {}", card.path.display());
+    }
+    for file in &card.files {
+        let path = file.file_path.display().to_string();
+        println!();
+        if markdown {
+            println!("## {}", path);
+        } else if colored_output {
+            println!("{}", path.bold().cyan());
+        } else {
+            println!("{}", path);
+        }
+        if let Some(doc) = &file.module_doc {
+            if markdown {
+                println!();
+                println!("{}", doc);
+                println!();
+            } else {
+                println!("  {}", doc);
+            }
+        }
+        for symbol in &file.symbols {
+            let qualifier = symbol
+                .container
+                .as_deref()
+                .map(|container| format!(" ({})", container))
+                .unwrap_or_default();
+            if markdown {
+                match &symbol.doc {
+                    Some(doc) => println!("- `{}`{} — {}", symbol.signature, qualifier, doc),
+                    None => println!("- `{}`{}", symbol.signature, qualifier),
+                }
+                continue;
+            }
+            let location = format!(":{}", symbol.line_start);
+            if colored_output {
+                println!(
+                    "  {}{} {}",
+                    symbol.signature.bold(),
+                    qualifier,
+                    location.dimmed()
+                );
+            } else {
+                println!("  {}{} {}", symbol.signature, qualifier, location);
+            }
+            if let Some(doc) = &symbol.doc {
+                println!("      {}", doc);
+            }
+        }
+    }
+
+    if card.omitted_symbols > 0 {
+        println!();
+        println!(
+            "{} more symbols omitted to stay within the token budget",
+            card.omitted_symbols
+        );
+    }
+    Ok(())
+}
+
+/// Handle the summarize command
+pub fn handle_summarize(
+    path: &Path,
+    ignore: &[String],
+    allow_tests: bool,
+    max_tokens: Option<usize>,
+    format: &str,
+) -> Result<()> {
+    let start_time = Instant::now();
+    let options = SummarizeOptions {
+        path,
+        ignore,
+        allow_tests,
+        max_tokens,
+    };
+    let card = summarize(&options)?;
+    let duration = start_time.elapsed();
+
+    format_and_print_card(&card, format)?;
+    if format != "json" {
+        let symbol_count: usize = card.files.iter().map(|f| f.symbols.len()).sum();
+        println!();
+        println!(
+            "Summarized {} symbols in {} files (~{} tokens) in {:.2?}",
+            symbol_count,
+            card.files.len(),
+            card.tokens,
+            duration
+        );
+    }
+    Ok(())
+}
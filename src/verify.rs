@@ -0,0 +1,325 @@
+//! Checking saved search results against the current code.
+//!
+//! `probe verify <results.json>` reads the JSON output of an earlier `probe search` and
+//! hashes each file its results came from again. A result is stale when its file no
+//! longer has the `content_hash` recorded at search time, or no longer exists. With
+//! `--rerun <QUERY>`, the query runs again over the changed files only, so a cached
+//! result set can be refreshed without searching the whole repository.
+
+use anyhow::{anyhow, Context, Result};
+use colored::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::fs_access;
+use crate::path_encoding::decode_path;
+use crate::search::content_hash::{corpus_fingerprint, hash_content};
+
+/// Whether a saved result still matches its file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freshness {
+    /// The file has the content it was searched in
+    Fresh,
+    /// The file changed since the search
+    Changed,
+    /// The file cannot be read anymore
+    Missing,
+    /// The result was saved without a content hash, e.g. by an older probe
+    Unknown,
+}
+
+impl Freshness {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Freshness::Fresh => "fresh",
+            Freshness::Changed => "changed",
+            Freshness::Missing => "missing",
+            Freshness::Unknown => "unknown",
+        }
+    }
+
+    /// Whether the result no longer describes the current code
+    pub fn is_stale(&self) -> bool {
+        matches!(self, Freshness::Changed | Freshness::Missing)
+    }
+}
+
+/// A saved result and whether it still matches its file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedResult {
+    /// The file as written in the saved results
+    pub file: String,
+    /// The file on disk, resolved against the directory the results are checked from
+    pub path: PathBuf,
+    pub lines: (usize, usize),
+    pub freshness: Freshness,
+}
+
+/// The outcome of checking a saved result set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+    pub results: Vec<VerifiedResult>,
+    /// Results fingerprint in the saved summary, if any
+    pub recorded_fingerprint: Option<String>,
+    /// Fingerprint of the same files as they are now, if all of them could be read
+    pub current_fingerprint: Option<String>,
+}
+
+impl Verification {
+    /// Results whose file changed or disappeared
+    pub fn stale(&self) -> impl Iterator<Item = &VerifiedResult> {
+        self.results.iter().filter(|r| r.freshness.is_stale())
+    }
+
+    /// Number of results with `freshness`
+    pub fn count(&self, freshness: Freshness) -> usize {
+        self.results
+            .iter()
+            .filter(|r| r.freshness == freshness)
+            .count()
+    }
+
+    /// Files with `freshness`, each listed once in the order they first appear
+    pub fn files(&self, freshness: Freshness) -> Vec<&VerifiedResult> {
+        let mut files: Vec<&VerifiedResult> = Vec::new();
+        for result in self.results.iter().filter(|r| r.freshness == freshness) {
+            if !files.iter().any(|file| file.path == result.path) {
+                files.push(result);
+            }
+        }
+        files
+    }
+}
+
+/// The fields of the JSON search output that verification needs
+#[derive(Deserialize)]
+struct SavedResults {
+    results: Vec<SavedResult>,
+    #[serde(default)]
+    summary: Option<SavedSummary>,
+}
+
+#[derive(Deserialize)]
+struct SavedResult {
+    file: String,
+    #[serde(default)]
+    file_encoding: Option<String>,
+    lines: [usize; 2],
+    #[serde(default)]
+    content_hash: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SavedSummary {
+    #[serde(default)]
+    results_fingerprint: Option<String>,
+}
+
+/// Function to check saved JSON search output against the files as they are now
+///
+/// Relative paths in the results are resolved against `base`, which should be the
+/// directory the search ran in. The lines describing the search that `probe search`
+/// prints before its JSON are skipped.
+pub fn verify_results(json: &str, base: &Path) -> Result<Verification> {
+    let json = match json.find("\n{") {
+        Some(start) if !json.trim_start().starts_with('{') => &json[start + 1..],
+        _ => json,
+    };
+    let saved: SavedResults = serde_json::from_str(json)
+        .context("Saved results must be the JSON output of `probe search --format json`")?;
+
+    // Current hash of each file, or None when it cannot be read
+    let mut hashes: HashMap<PathBuf, Option<String>> = HashMap::new();
+    let mut results = Vec::with_capacity(saved.results.len());
+    for result in &saved.results {
+        let path = base.join(decode_path(&result.file, result.file_encoding.as_deref())?);
+        let current = hashes
+            .entry(path.clone())
+            .or_insert_with(|| {
+                fs_access::read_to_string(&path)
+                    .ok()
+                    .map(|content| hash_content(&content))
+            })
+            .as_deref();
+        let freshness = match (result.content_hash.as_deref(), current) {
+            (None, _) => Freshness::Unknown,
+            (Some(_), None) => Freshness::Missing,
+            (Some(recorded), Some(current)) if recorded == current => Freshness::Fresh,
+            (Some(_), Some(_)) => Freshness::Changed,
+        };
+        results.push(VerifiedResult {
+            file: result.file.clone(),
+            path,
+            lines: (result.lines[0], result.lines[1]),
+            freshness,
+        });
+    }
+
+    let current: Option<Vec<(&str, &str)>> = saved
+        .results
+        .iter()
+        .zip(&results)
+        .filter(|(saved, _)| saved.content_hash.is_some())
+        .map(|(saved, verified)| {
+            let hash = hashes.get(&verified.path)?.as_deref()?;
+            Some((saved.file.as_str(), hash))
+        })
+        .collect();
+    let current_fingerprint = current
+        .filter(|files| !files.is_empty())
+        .map(corpus_fingerprint);
+
+    Ok(Verification {
+        results,
+        recorded_fingerprint: saved.summary.and_then(|summary| summary.results_fingerprint),
+        current_fingerprint,
+    })
+}
+
+/// Helper function to print a verification in the given format
+fn print_verification(verification: &Verification, format: &str) -> Result<()> {
+    if format == "json" {
+        let results: Vec<serde_json::Value> = verification
+            .results
+            .iter()
+            .map(|r| {
+                serde_json::json!({
+                    "file": r.file,
+                    "lines": [r.lines.0, r.lines.1],
+                    "status": r.freshness.as_str(),
+                })
+            })
+            .collect();
+        let wrapper = serde_json::json!({
+            "results": results,
+            "summary": {
+                "count": verification.results.len(),
+                "stale": verification.stale().count(),
+                "changed_files": verification.files(Freshness::Changed).len(),
+                "missing_files": verification.files(Freshness::Missing).len(),
+                "unknown": verification.count(Freshness::Unknown),
+                "recorded_fingerprint": verification.recorded_fingerprint,
+                "current_fingerprint": verification.current_fingerprint,
+            }
+        });
+        println!("{}", serde_json::to_string_pretty(&wrapper)?);
+        return Ok(());
+    }
+
+    for result in verification.stale() {
+        let status = match result.freshness {
+            Freshness::Missing => result.freshness.as_str().red().bold(),
+            _ => result.freshness.as_str().yellow().bold(),
+        };
+        println!(
+            "{}  {}:{}-{}",
+            status, result.file, result.lines.0, result.lines.1
+        );
+    }
+    eprintln!("{}", summary_line(verification));
+    Ok(())
+}
+
+/// Helper function to sum up a verification in one line
+fn summary_line(verification: &Verification) -> String {
+    let total = verification.results.len();
+    let stale = verification.stale().count();
+    let unknown = verification.count(Freshness::Unknown);
+    let unknown_note = if unknown > 0 {
+        format!(" ({} saved without a content hash)", unknown)
+    } else {
+        String::new()
+    };
+    if stale == 0 {
+        return format!(
+            "{}{}",
+            format!("No stale results among {}.", total).green(),
+            unknown_note
+        );
+    }
+    format!(
+        "{} ({} files changed, {} missing){}",
+        format!("{} of {} results are stale", stale, total)
+            .yellow()
+            .bold(),
+        verification.files(Freshness::Changed).len(),
+        verification.files(Freshness::Missing).len(),
+        unknown_note
+    )
+}
+
+/// Function to check saved search results, optionally searching the changed files again
+///
+/// `results` is a file holding the JSON output of `probe search`, or `-` for stdin. Without
+/// `rerun`, the stale results are printed and the command fails when there are any. With
+/// `rerun`, the summary goes to stderr and the query runs again over the changed files,
+/// with its output in `format` on stdout.
+pub fn handle_verify(results: &Path, rerun: Option<&str>, format: &str) -> Result<()> {
+    let json = if results == Path::new("-") {
+        let mut json = String::new();
+        std::io::stdin()
+            .read_to_string(&mut json)
+            .context("Failed to read saved results from stdin")?;
+        json
+    } else {
+        fs_access::read_to_string(results)
+            .with_context(|| format!("Failed to read saved results {:?}", results))?
+    };
+    let cwd = std::env::current_dir()?;
+    let verification = verify_results(&json, &cwd)?;
+
+    let Some(query) = rerun else {
+        print_verification(&verification, format)?;
+        let stale = verification.stale().count();
+        if stale > 0 {
+            return Err(anyhow!(
+                "{} of {} results are stale",
+                stale,
+                verification.results.len()
+            ));
+        }
+        return Ok(());
+    };
+
+    eprintln!("{}", summary_line(&verification));
+    let changed = verification.files(Freshness::Changed);
+    if changed.is_empty() {
+        eprintln!("No changed files to search again.");
+        return Ok(());
+    }
+
+    // Paths are passed as the search printed them, relative to the working directory
+    let mut args: Vec<OsString> = vec!["search".into(), query.into()];
+    for result in &changed {
+        args.push("--path".into());
+        args.push(
+            result
+                .path
+                .strip_prefix(&cwd)
+                .unwrap_or(&result.path)
+                .into(),
+        );
+    }
+    args.extend(["--format".into(), format.into()]);
+    eprintln!(
+        "{} {} changed files: probe {}",
+        "Searching".bold().green(),
+        changed.len(),
+        args.iter()
+            .map(|arg| arg.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    let status = Command::new(std::env::current_exe()?)
+        .args(&args)
+        .status()
+        .context("Failed to run the search again")?;
+    if !status.success() {
+        return Err(anyhow!("Search of the changed files failed ({})", status));
+    }
+    Ok(())
+}
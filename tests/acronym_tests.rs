@@ -0,0 +1,81 @@
+use probe::search::acronyms::{acronym_pattern, is_acronym_candidate};
+use probe::search::{perform_probe, SearchOptions};
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, query: &str, acronyms: bool) -> Vec<(usize, usize)> {
+    let queries = vec![query.to_string()];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        exclude_filenames: true,
+        no_merge: true,
+        acronyms,
+        ..Default::default()
+    };
+    // Results come back ranked
+    perform_probe(&options)
+        .unwrap()
+        .results
+        .iter()
+        .map(|r| r.lines)
+        .collect()
+}
+
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("search.ts"),
+        r#"export function handleSearchResults(items: string[]): number {
+  return items.length;
+}
+
+export function hsrLookup(table: string[]): string {
+  return table[0];
+}
+
+export function high_speed_rail(): void {
+  return;
+}
+
+export function handleSearchResultsCache(): void {
+  return;
+}
+"#,
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_acronym_pattern_matches_identifier_initials() {
+    let regex = Regex::new(&format!("(?i){}", acronym_pattern("hsr"))).unwrap();
+
+    assert!(regex.is_match("handleSearchResults()"));
+    assert!(regex.is_match("let x = HandleSearchResults;"));
+    assert!(regex.is_match("handle_search_results"));
+    // More parts, fewer parts, or no part boundaries do not spell the acronym
+    assert!(!regex.is_match("handleSearchResultsCache"));
+    assert!(!regex.is_match("handleSearch"));
+    assert!(!regex.is_match("hashsearcher"));
+
+    assert!(is_acronym_candidate("hsr"));
+    assert!(!is_acronym_candidate("h"));
+    assert!(!is_acronym_candidate("handlesearch"));
+    assert!(!is_acronym_candidate("v2"));
+}
+
+#[test]
+fn test_acronyms_rank_below_full_term_matches() {
+    let temp_dir = create_project();
+
+    assert_eq!(search(temp_dir.path(), "hsr", false), vec![(5, 7)]);
+
+    let results = search(temp_dir.path(), "hsr", true);
+    assert_eq!(results[0], (5, 7));
+    let mut acronym_matches = results[1..].to_vec();
+    acronym_matches.sort();
+    assert_eq!(acronym_matches, vec![(1, 3), (9, 11)]);
+}
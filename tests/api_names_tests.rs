@@ -0,0 +1,105 @@
+use probe::models::SearchResult;
+use probe::search::api_names::{name_words, ApiNameTable};
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, query: &str) -> Vec<SearchResult> {
+    let queries = vec![query.to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        ..Default::default()
+    };
+    perform_probe(&options).unwrap().results
+}
+
+fn files(results: &[SearchResult]) -> Vec<String> {
+    let mut files: Vec<String> = results
+        .iter()
+        .map(|r| {
+            let name = Path::new(&r.file).file_name().unwrap();
+            name.to_string_lossy().to_string()
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn test_name_words() {
+    assert_eq!(name_words("user_id"), vec!["user", "id"]);
+    assert_eq!(name_words("UserProfile"), vec!["user", "profile"]);
+    assert_eq!(name_words("HTTPServerURL"), vec!["http", "server", "url"]);
+    assert_eq!(name_words("user-id2"), vec!["user", "id2"]);
+    assert_eq!(name_words("USER_ID"), vec!["user", "id"]);
+}
+
+#[test]
+fn test_default_table_variants() {
+    let table = ApiNameTable::default();
+    // Forms differing only in case are kept once, since searches ignore case
+    assert_eq!(
+        table.variants("UserProfile"),
+        vec!["user_profile", "userProfile", "user-profile"]
+    );
+    // Single words have no other forms
+    assert_eq!(table.variants("user"), vec!["user"]);
+}
+
+#[test]
+fn test_custom_table() {
+    let table = ApiNameTable::parse(
+        "# Go protobuf getters\ngo_getter = Get{pascal}\nconstant = k{pascal}  # Google style\n",
+    )
+    .unwrap();
+    assert_eq!(table.variants("user_id"), vec!["GetUserId", "kUserId"]);
+
+    assert!(ApiNameTable::parse("snake {snake}").is_err());
+    assert!(ApiNameTable::parse("upper = {upper}").is_err());
+    assert!(ApiNameTable::parse("plain = name").is_err());
+    assert!(ApiNameTable::parse("# nothing here").is_err());
+}
+
+#[test]
+fn test_expand_query() {
+    let table = ApiNameTable::default();
+    assert_eq!(
+        table.expand_query("user_id"),
+        r#"("user_id" OR ("userId" OR "user-id"))"#
+    );
+    // Operators, single words, prefixed and quoted words are kept
+    assert_eq!(
+        table.expand_query(r#"(userId OR name) -user_id "user_id""#),
+        r#"(("user_id" OR ("userId" OR "user-id")) OR name) -user_id "user_id""#
+    );
+}
+
+#[test]
+fn test_api_names_search_across_languages() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("user.go"),
+        "package api\n\ntype User struct {\n\tUserId string `json:\"userId\"`\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("user.py"),
+        "class User:\n    def __init__(self):\n        self.user_id = 1\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("view.ts"),
+        "export function other(u: any) {\n  return u.user + u.id;\n}\n",
+    )
+    .unwrap();
+
+    let query = ApiNameTable::default().expand_query("user_id");
+    let results = search(dir.path(), &query);
+    assert_eq!(files(&results), vec!["user.go", "user.py"]);
+}
@@ -0,0 +1,117 @@
+use probe::error::ProbeError;
+use probe::search::{perform_probe_async, ProbeEngine, SearchRequest};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+use tokio_util::sync::CancellationToken;
+
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    for name in ["alpha", "beta", "gamma"] {
+        fs::write(
+            temp_dir.path().join(format!("{}.rs", name)),
+            format!("fn {}_handler() {{\n    println!(\"needle\");\n}}\n", name),
+        )
+        .unwrap();
+    }
+    temp_dir
+}
+
+fn request(path: &Path) -> SearchRequest {
+    SearchRequest {
+        path: path.to_path_buf(),
+        queries: vec!["needle".to_string()],
+        files_only: false,
+        custom_ignores: Vec::new(),
+        exclude_filenames: true,
+        reranker: "bm25".to_string(),
+        frequency_search: true,
+        max_results: None,
+        max_bytes: None,
+        max_tokens: None,
+        allow_tests: false,
+        exact: false,
+        no_merge: false,
+        merge_threshold: None,
+        dry_run: false,
+        session: None,
+        report_skipped: false,
+        tests_for: None,
+        usages: false,
+        path_style: "native".to_string(),
+        follow_symlinks: false,
+        strict: false,
+        roots: vec![],
+        max_depth: None,
+        min_depth: None,
+        newer_than: None,
+        older_than: None,
+        min_size: None,
+        max_size: None,
+        author: None,
+        snippet_window: None,
+        oversize_strategy: "full".to_string(),
+        oversize_threshold: 300,
+        case_fold: false,
+        code_terms: None,
+        acronyms: false,
+        preproc_context: false,
+        embedded: false,
+        force_language: Vec::new(),
+        within_session: None,
+        exclude_blocks: Vec::new(),
+        pin_blocks: Vec::new(),
+        filename_weight: 1.0,
+        include_dir_context: false,
+        deprecated_penalty: 0.0,
+        visibility: "any".to_string(),
+        kinds: Vec::new(),
+        sort: "score".to_string(),
+        candidate_files: None,
+        max_line_length: 2000,
+        max_matches_per_file: 10000,
+        max_total_matches: None,
+        max_query_terms: 128,
+        code_pattern: false,
+    }
+}
+
+#[tokio::test]
+async fn test_perform_probe_async() {
+    let project = create_project();
+
+    let limited = perform_probe_async(request(project.path()), CancellationToken::new())
+        .await
+        .unwrap();
+    assert_eq!(limited.results.len(), 3);
+}
+
+#[tokio::test]
+async fn test_cancelled_search_returns_cancelled_error() {
+    let project = create_project();
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+
+    let err = perform_probe_async(request(project.path()), cancel)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ProbeError::Timeout(_)));
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_engine_search_async_does_not_need_a_multi_thread_runtime() {
+    let project = create_project();
+    let engine = Arc::new(ProbeEngine::new(project.path()).unwrap());
+
+    let limited = engine
+        .search_async("needle", CancellationToken::new())
+        .await
+        .unwrap();
+    assert_eq!(limited.results.len(), 3);
+
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let err = engine.search_async("needle", cancel).await.unwrap_err();
+    assert!(matches!(err, ProbeError::Timeout(_)));
+}
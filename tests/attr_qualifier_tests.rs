@@ -0,0 +1,91 @@
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn create_test_files(dir: &Path) {
+    fs::write(
+        dir.join("main.rs"),
+        "#[tokio::main]\nasync fn main() {\n    run().await;\n}\n\n#[derive(Debug, Clone)]\nstruct Config {\n    name: String,\n}\n\nfn plain() {}\n",
+    )
+    .unwrap();
+
+    fs::write(
+        dir.join("app.py"),
+        "@app.route(\"/users\")\ndef list_users():\n    return users\n\ndef helper():\n    pass\n",
+    )
+    .unwrap();
+
+    fs::write(
+        dir.join("Legacy.java"),
+        "public class Legacy {\n    @Deprecated\n    public void old() {\n        run();\n    }\n\n    public void fresh() {}\n}\n",
+    )
+    .unwrap();
+}
+
+fn search(dir: &Path, query: &str) -> Vec<(String, (usize, usize), String)> {
+    let queries = vec![query.to_string()];
+    let custom_ignores: Vec<String> = vec![];
+
+    let options = SearchOptions {
+        path: dir,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        ..Default::default()
+    };
+
+    perform_probe(&options)
+        .unwrap()
+        .results
+        .into_iter()
+        .map(|r| (r.file, r.lines, r.code))
+        .collect()
+}
+
+#[test]
+fn test_attr_returns_decorated_item() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let results = search(temp_dir.path(), "attr:tokio::main");
+    assert_eq!(results.len(), 1);
+    assert!(results[0].0.ends_with("main.rs"));
+    assert_eq!(results[0].1, (1, 4));
+    assert!(results[0].2.starts_with("#[tokio::main]"));
+    assert!(results[0].2.contains("async fn main()"));
+
+    // The last path segment is enough
+    let results = search(temp_dir.path(), "attr:main");
+    assert_eq!(results.len(), 1);
+}
+
+#[test]
+fn test_attr_across_languages() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let results = search(temp_dir.path(), "attr:@app.route");
+    assert_eq!(results.len(), 1);
+    assert!(results[0].2.contains("def list_users()"));
+    assert!(!results[0].2.contains("helper"));
+
+    let results = search(temp_dir.path(), "attr:@Deprecated");
+    assert_eq!(results.len(), 1);
+    assert!(results[0].2.contains("public void old()"));
+    assert!(!results[0].2.contains("fresh"));
+}
+
+#[test]
+fn test_attr_combined_with_terms() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let results = search(temp_dir.path(), "attr:derive Config");
+    assert_eq!(results.len(), 1);
+    assert!(results[0].2.contains("struct Config"));
+
+    // Terms that don't appear in any decorated item filter everything out
+    let results = search(temp_dir.path(), "attr:derive missing_term");
+    assert!(results.is_empty());
+}
@@ -0,0 +1,149 @@
+use probe::models::{LimitedSearchResults, SkipReason};
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn search(path: &Path, author: Option<&str>, files_only: bool) -> LimitedSearchResults {
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        files_only,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        report_skipped: true,
+        author,
+        ..Default::default()
+    };
+    perform_probe(&options).unwrap()
+}
+
+fn git(repo: &Path, args: &[&str], author: &str, timestamp: &str) -> bool {
+    Command::new("git")
+        .args(args)
+        .current_dir(repo)
+        .env("GIT_AUTHOR_NAME", author)
+        .env(
+            "GIT_AUTHOR_EMAIL",
+            format!("{}@example.com", author.to_lowercase()),
+        )
+        .env("GIT_COMMITTER_NAME", author)
+        .env(
+            "GIT_COMMITTER_EMAIL",
+            format!("{}@example.com", author.to_lowercase()),
+        )
+        .env("GIT_AUTHOR_DATE", timestamp)
+        .env("GIT_COMMITTER_DATE", timestamp)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Alice writes `alice.rs` and `shared.rs`, then Bob edits the second function in `shared.rs`.
+/// Returns None when git is not available.
+fn create_repo() -> Option<TempDir> {
+    let temp_dir = TempDir::new().unwrap();
+    let repo = temp_dir.path();
+    if !git(repo, &["init", "-q"], "Alice", "1700000000 +0000") {
+        return None;
+    }
+
+    fs::write(
+        repo.join("alice.rs"),
+        "fn alice() {\n    let needle = 1;\n}\n",
+    )
+    .unwrap();
+    let shared = "fn first() {\n    let needle = 2;\n}\n\nfn second() {\n    let needle = 3;\n}\n";
+    fs::write(repo.join("shared.rs"), shared).unwrap();
+    assert!(git(repo, &["add", "."], "Alice", "1700000000 +0000"));
+    assert!(git(
+        repo,
+        &["commit", "-q", "-m", "Initial"],
+        "Alice",
+        "1700000000 +0000"
+    ));
+
+    fs::write(
+        repo.join("shared.rs"),
+        shared.replace("needle = 3", "needle = 4"),
+    )
+    .unwrap();
+    assert!(git(
+        repo,
+        &["commit", "-q", "-am", "Tweak second"],
+        "Bob",
+        "1700100000 +0000"
+    ));
+    Some(temp_dir)
+}
+
+fn blocks(results: &LimitedSearchResults) -> Vec<(String, usize)> {
+    let mut blocks: Vec<(String, usize)> = results
+        .results
+        .iter()
+        .map(|r| {
+            let name = Path::new(&r.file).file_name().unwrap();
+            (name.to_string_lossy().to_string(), r.lines.0)
+        })
+        .collect();
+    blocks.sort();
+    blocks
+}
+
+#[test]
+fn test_author_filter_keeps_blocks_last_touched_by_author() {
+    let Some(repo) = create_repo() else {
+        return;
+    };
+
+    let all = search(repo.path(), None, false);
+    assert_eq!(all.results.len(), 3);
+
+    let bob = search(repo.path(), Some("bob"), false);
+    assert_eq!(blocks(&bob), vec![("shared.rs".to_string(), 5)]);
+    let skipped: Vec<SkipReason> = bob
+        .skip_report
+        .unwrap()
+        .into_iter()
+        .map(|s| s.reason)
+        .collect();
+    assert_eq!(skipped, vec![SkipReason::Author("bob".to_string())]);
+
+    // The pattern is a case-insensitive regex on "Name <email>"
+    let alice = search(repo.path(), Some("^alice <alice@"), false);
+    assert_eq!(
+        blocks(&alice),
+        vec![("alice.rs".to_string(), 1), ("shared.rs".to_string(), 1)]
+    );
+}
+
+#[test]
+fn test_author_filter_in_files_only_mode_uses_latest_commit() {
+    let Some(repo) = create_repo() else {
+        return;
+    };
+
+    let bob = search(repo.path(), Some("Bob"), true);
+    assert_eq!(blocks(&bob), vec![("shared.rs".to_string(), 1)]);
+}
+
+#[test]
+fn test_author_filter_drops_untracked_files() {
+    let Some(repo) = create_repo() else {
+        return;
+    };
+    fs::write(
+        repo.path().join("untracked.rs"),
+        "fn untracked() {\n    let needle = 5;\n}\n",
+    )
+    .unwrap();
+
+    let alice = search(repo.path(), Some("alice"), false);
+    assert!(blocks(&alice)
+        .iter()
+        .all(|(file, _)| file != "untracked.rs"));
+}
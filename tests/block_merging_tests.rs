@@ -3,14 +3,16 @@ use std::path::Path;
 use tempfile::TempDir;
 
 use probe::models::SearchResult;
-use probe::search::block_merging::merge_ranked_blocks;
-use probe::search::{perform_probe, SearchOptions};
+use probe::search::block_merging::{merge_ranked_blocks, should_merge_blocks};
+use probe::search::search_tokens::count_tokens;
+use probe::search::{perform_probe, EngineOptions, ProbeEngine, SearchOptions};
 
 #[test]
 fn test_merge_ranked_blocks() {
     // Create test blocks that should be merged
     let block1 = SearchResult {
         file: "test_file.rs".to_string(),
+        file_path: None,
         lines: (1, 5),
         node_type: "function".to_string(),
         code:
@@ -35,9 +37,24 @@ fn test_merge_ranked_blocks() {
         block_id: None,
         matched_keywords: None,
         tokenized_content: None,
+        merged_from: None,
+        normalized_score: None,
+        usage_kind: None,
+        via_symlink: false,
+        changed_lines: None,
+        symbol_name: None,
+        language: None,
+        extraction_confidence: None,
+        partial_parse: false,
+        deprecated: false,
+        embedded: None,
+        kind: None,
+        complexity: None,
+        content_hash: None,
     };
     let block2 = SearchResult {
     file: "test_file.rs".to_string(),
+    file_path: None,
     lines: (6, 10),
     node_type: "function".to_string(),
     code: "fn another_function() {\n    let z = 3;\n    let result = z * 2;\n    println!(\"{}\", result);\n}".to_string(),
@@ -60,11 +77,26 @@ fn test_merge_ranked_blocks() {
     block_id: None,
     matched_keywords: None,
     tokenized_content: None,
+    merged_from: None,
+    normalized_score: None,
+    usage_kind: None,
+    via_symlink: false,
+    changed_lines: None,
+    symbol_name: None,
+    language: None,
+    extraction_confidence: None,
+    partial_parse: false,
+    deprecated: false,
+    embedded: None,
+    kind: None,
+    complexity: None,
+    content_hash: None,
 };
 
     // Create block from a different file that should not be merged
     let block3 = SearchResult {
         file: "other_file.rs".to_string(),
+        file_path: None,
         lines: (1, 5),
         node_type: "function".to_string(),
         code: "fn other_function() {\n    let a = 10;\n    let b = 20;\n    println!(\"{}\", a + b);\n}".to_string(),
@@ -87,6 +119,20 @@ fn test_merge_ranked_blocks() {
         block_id: None,
         matched_keywords: None,
         tokenized_content: None,
+        merged_from: None,
+        normalized_score: None,
+        usage_kind: None,
+        via_symlink: false,
+        changed_lines: None,
+        symbol_name: None,
+        language: None,
+        extraction_confidence: None,
+        partial_parse: false,
+        deprecated: false,
+        embedded: None,
+        kind: None,
+        complexity: None,
+        content_hash: None,
     };
 
     // Create a vector with all blocks
@@ -157,6 +203,126 @@ fn test_merge_ranked_blocks() {
         (1, 5),
         "Unmerged block should preserve its line range"
     );
+
+    // Check that the merged block records where it came from
+    let merged_from = merged_block
+        .merged_from
+        .as_ref()
+        .expect("Merged block should record its provenance");
+    assert_eq!(
+        merged_from.len(),
+        2,
+        "Both original blocks should be recorded"
+    );
+    assert_eq!(merged_from[0].lines, (1, 5));
+    assert_eq!(merged_from[0].rank, Some(1));
+    assert_eq!(merged_from[0].score, Some(0.9));
+    assert_eq!(merged_from[1].lines, (6, 10));
+    assert_eq!(merged_from[1].rank, Some(2));
+    assert_eq!(merged_from[1].score, Some(0.8));
+
+    // Blocks that were never merged carry no provenance
+    assert!(preserved_block.merged_from.is_none());
+}
+
+fn create_block(
+    file: &str,
+    lines: (usize, usize),
+    rank: usize,
+    parent_file_id: &str,
+) -> SearchResult {
+    SearchResult {
+        file: file.to_string(),
+        file_path: None,
+        lines,
+        node_type: "function".to_string(),
+        code: format!("fn block_{}() {{}}", rank),
+        matched_by_filename: None,
+        rank: Some(rank),
+        score: Some(1.0 / rank as f64),
+        tfidf_score: None,
+        bm25_score: None,
+        tfidf_rank: None,
+        bm25_rank: None,
+        new_score: None,
+        hybrid2_rank: None,
+        combined_score_rank: None,
+        file_unique_terms: None,
+        file_total_matches: None,
+        file_match_rank: None,
+        block_unique_terms: Some(1),
+        block_total_matches: Some(1),
+        parent_file_id: Some(parent_file_id.to_string()),
+        block_id: Some(rank),
+        matched_keywords: None,
+        tokenized_content: None,
+        merged_from: None,
+        normalized_score: None,
+        usage_kind: None,
+        via_symlink: false,
+        changed_lines: None,
+        symbol_name: None,
+        language: None,
+        extraction_confidence: None,
+        partial_parse: false,
+        deprecated: false,
+        embedded: None,
+        kind: None,
+        complexity: None,
+        content_hash: None,
+    }
+}
+
+#[test]
+fn test_blocks_never_merged_across_files() {
+    // Adjacent blocks that claim the same parent file id but live in different files
+    let block1 = create_block("src/a.rs", (1, 5), 1, "shared");
+    let block2 = create_block("src/b.rs", (6, 10), 2, "shared");
+
+    assert!(
+        !should_merge_blocks(&block1, &block2, 5),
+        "Blocks from different files must never be merged"
+    );
+
+    let merged = merge_ranked_blocks(vec![block1, block2], Some(5));
+    assert_eq!(merged.len(), 2, "Both blocks should be kept separate");
+    assert!(merged.iter().all(|r| r.merged_from.is_none()));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_blocks_of_non_utf8_paths_with_the_same_display_are_not_merged() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::PathBuf;
+
+    // Both paths show as "src/\u{FFFD}.rs" once made lossy
+    let mut block1 = create_block("src/\u{FFFD}.rs", (1, 5), 1, "shared");
+    let mut block2 = create_block("src/\u{FFFD}.rs", (6, 10), 2, "shared");
+    block1.file_path = Some(PathBuf::from(OsStr::from_bytes(b"src/\xff.rs")));
+    block2.file_path = Some(PathBuf::from(OsStr::from_bytes(b"src/\xfe.rs")));
+
+    assert!(!should_merge_blocks(&block1, &block2, 5));
+    let merged = merge_ranked_blocks(vec![block1, block2], Some(5));
+    assert_eq!(merged.len(), 2);
+}
+
+#[test]
+fn test_merged_from_flattens_nested_merges() {
+    let blocks = vec![
+        create_block("src/a.rs", (1, 5), 3, "a"),
+        create_block("src/a.rs", (6, 10), 1, "a"),
+        create_block("src/a.rs", (11, 15), 2, "a"),
+    ];
+
+    let merged = merge_ranked_blocks(blocks, Some(5));
+    assert_eq!(merged.len(), 1, "All three blocks should be merged");
+
+    let merged_from = merged[0].merged_from.as_ref().unwrap();
+    let lines: Vec<(usize, usize)> = merged_from.iter().map(|b| b.lines).collect();
+    let ranks: Vec<Option<usize>> = merged_from.iter().map(|b| b.rank).collect();
+    assert_eq!(lines, vec![(1, 5), (6, 10), (11, 15)]);
+    assert_eq!(ranks, vec![Some(3), Some(1), Some(2)]);
 }
 
 #[test]
@@ -176,21 +342,13 @@ fn test_integration_with_search_flow() {
     let options = SearchOptions {
         path: temp_path,
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "combined",
         frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
 
-        exact: false,
-        no_merge: false,
         merge_threshold: Some(20), // Increase threshold to ensure non-adjacent blocks are merged
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Run a search that should produce multiple overlapping blocks
@@ -288,21 +446,13 @@ fn test_no_merge_flag() {
     let options_with_merge = SearchOptions {
         path: temp_path,
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "combined",
         frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
 
-        exact: false,
-        no_merge: false,
         merge_threshold: Some(20), // Increase threshold to ensure non-adjacent blocks are merged
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Run a search that should produce merged blocks
@@ -312,21 +462,14 @@ fn test_no_merge_flag() {
     let options_without_merge = SearchOptions {
         path: temp_path,
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "combined",
         frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
 
-        exact: false,
         no_merge: true,
         merge_threshold: Some(20), // Increase threshold to ensure non-adjacent blocks are merged
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Run a search that should not merge blocks
@@ -376,3 +519,49 @@ fn test_no_merge_flag() {
         );
     }
 }
+
+#[test]
+fn test_token_limit_holds_after_merging() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("lib.rs"),
+        "fn first_needle() {\n    needle_one();\n}\n\n\
+         // Lorem ipsum dolor sit amet consectetur adipiscing elit sed do eiusmod tempor\n\
+         // incididunt ut labore et dolore magna aliqua ut enim ad minim veniam quis nostrud\n\
+         // exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat duis aute\n\
+         fn second_needle() {\n    needle_two();\n}\n",
+    )
+    .unwrap();
+    let search = |max_tokens| {
+        ProbeEngine::with_options(
+            temp_dir.path(),
+            EngineOptions {
+                max_tokens: Some(max_tokens),
+                ..EngineOptions::default()
+            },
+        )
+        .unwrap()
+        .search("needle")
+        .unwrap()
+    };
+
+    // With room for the comment between them, the two functions are merged
+    let roomy = search(1000);
+    assert_eq!(roomy.results.len(), 1);
+    let merged_tokens = count_tokens(&roomy.results[0].code);
+    assert_eq!(
+        roomy.limits_applied.unwrap().post_merge_tokens,
+        Some(merged_tokens)
+    );
+
+    // Without it, they are returned as the separate blocks that fit the budget
+    let budget = merged_tokens - 1;
+    let tight = search(budget);
+    assert_eq!(tight.results.len(), 2);
+    let tokens: usize = tight.results.iter().map(|r| count_tokens(&r.code)).sum();
+    assert!(tokens <= budget);
+    assert_eq!(
+        tight.limits_applied.unwrap().post_merge_tokens,
+        Some(tokens)
+    );
+}
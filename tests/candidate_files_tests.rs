@@ -0,0 +1,73 @@
+use probe::models::{LimitedSearchResults, SkipReason};
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, candidate_files: Option<usize>) -> LimitedSearchResults {
+    let queries = vec!["alpha beta".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        report_skipped: true,
+        candidate_files,
+        ..Default::default()
+    };
+    perform_probe(&options).unwrap()
+}
+
+#[test]
+fn test_candidate_files_extracts_blocks_from_the_best_files_only() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("many.rs"),
+        "fn many() {\n    alpha();\n    beta();\n    alpha();\n    beta();\n}\n",
+    )
+    .unwrap();
+    for index in 0..5 {
+        fs::write(
+            temp_dir.path().join(format!("few_{index}.rs")),
+            format!("fn few_{index}() {{\n    alpha();\n    beta();\n}}\n"),
+        )
+        .unwrap();
+    }
+
+    let all = search(temp_dir.path(), None);
+    assert_eq!(all.results.len(), 6);
+
+    let candidates = search(temp_dir.path(), Some(1));
+    assert_eq!(candidates.results.len(), 1);
+    assert!(candidates.results[0].file.ends_with("many.rs"));
+
+    let report = candidates.skip_report.unwrap();
+    let passed_over: Vec<_> = report
+        .iter()
+        .filter(|skipped| skipped.reason == SkipReason::NotCandidate)
+        .collect();
+    assert_eq!(passed_over.len(), 5);
+}
+
+#[test]
+fn test_candidate_files_larger_than_the_match_count_changes_nothing() {
+    let temp_dir = TempDir::new().unwrap();
+    for index in 0..3 {
+        fs::write(
+            temp_dir.path().join(format!("file_{index}.rs")),
+            format!("fn file_{index}() {{\n    alpha();\n    beta();\n}}\n"),
+        )
+        .unwrap();
+    }
+
+    let all = search(temp_dir.path(), None);
+    let candidates = search(temp_dir.path(), Some(10));
+    let files = |results: &LimitedSearchResults| {
+        let mut files: Vec<String> = results.results.iter().map(|r| r.file.clone()).collect();
+        files.sort();
+        files
+    };
+    assert_eq!(files(&candidates), files(&all));
+}
@@ -0,0 +1,115 @@
+use probe::case_study::{case_study, CaseStudy, CaseStudyOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn create_test_files(dir: &Path) {
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::create_dir_all(dir.join("tests")).unwrap();
+
+    fs::write(
+        dir.join("src/config.rs"),
+        r#"pub fn parse_config(input: &str) -> u32 {
+    input.len() as u32
+}
+
+pub fn unrelated() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_empty_input() {
+        assert_eq!(parse_config(""), 0);
+    }
+
+    #[test]
+    fn other_thing() {
+        unrelated();
+    }
+}
+"#,
+    )
+    .unwrap();
+
+    fs::write(
+        dir.join("tests/config_test.rs"),
+        r#"use demo::config::parse_config;
+
+#[test]
+fn test_parse_config_counts_bytes() {
+    assert_eq!(parse_config("abc"), 3);
+    assert_eq!(parse_config("ab"), 2);
+}
+
+fn helper() -> u32 {
+    7
+}
+"#,
+    )
+    .unwrap();
+
+    fs::write(
+        dir.join("tests/test_cli.py"),
+        r#"from demo import parse_config_file
+
+def test_parse_config_file():
+    assert parse_config_file("x.toml")
+"#,
+    )
+    .unwrap();
+}
+
+fn study(dir: &Path, symbol: &str, max_tokens: Option<usize>) -> CaseStudy {
+    case_study(&CaseStudyOptions {
+        path: dir,
+        symbol,
+        ignore: &[],
+        max_tokens,
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_case_study_lists_definition_and_tests() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let study = study(temp_dir.path(), "parse_config", None);
+
+    let definition = study.definition.expect("definition");
+    assert!(definition.file_path.ends_with("src/config.rs"));
+    assert_eq!((definition.line_start, definition.line_end), (1, 3));
+
+    // Tests named after the symbol rank first; other tests and mere mentions of a
+    // longer identifier are left out
+    let names: Vec<&str> = study.tests.iter().map(|test| test.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["test_parse_config_counts_bytes", "parses_empty_input"]
+    );
+    assert_eq!(study.tests[0].references, 2);
+    assert!(study.tests[1]
+        .reasons
+        .contains(&"next to the definition".to_string()));
+    assert_eq!(study.omitted_tests, 0);
+}
+
+#[test]
+fn test_case_study_keeps_within_token_budget() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let full = study(temp_dir.path(), "parse_config", None);
+    let budget = full.tokens - 1;
+    let limited = study(temp_dir.path(), "parse_config", Some(budget));
+
+    assert!(limited.definition.is_some());
+    assert!(limited.tokens <= budget);
+    assert_eq!(
+        limited.tests.len() + limited.omitted_tests,
+        full.tests.len()
+    );
+    assert!(limited.omitted_tests > 0);
+}
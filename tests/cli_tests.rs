@@ -45,11 +45,15 @@ fn test_cli_basic_search() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with basic search
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
             "search",
+            "--format",
+            "color",
             "search", // Pattern to search for
             temp_dir.path().to_str().unwrap(),
         ])
@@ -85,11 +89,15 @@ fn test_cli_files_only() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with files-only option
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
             "search",
+            "--format",
+            "color",
             "search", // Pattern to search for
             temp_dir.path().to_str().unwrap(),
             "--files-only",
@@ -143,11 +151,15 @@ fn test_cli_filename_matching() {
     );
 
     // Run the CLI without exclude-filenames option (filename matching is enabled by default)
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
             "search",
+            "--format",
+            "color",
             "search", // Pattern to search for
             temp_dir.path().to_str().unwrap(),
         ])
@@ -175,11 +187,15 @@ fn test_cli_filename_matching() {
 
     // Second test: With exclude-filenames - filename matching should be disabled
     // Run the CLI with exclude-filenames option
+    let history_dir = TempDir::new().unwrap();
     let output2 = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
             "search",
+            "--format",
+            "color",
             "search", // Pattern to search for
             temp_dir.path().to_str().unwrap(),
             "--exclude-filenames",
@@ -213,7 +229,9 @@ fn test_cli_reranker() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with bm25 reranker
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -250,6 +268,22 @@ fn test_cli_reranker() {
             || stdout.contains("bm25"),
         "Should use BM25 reranker"
     );
+
+    // The older reranker names the library accepts are accepted here too
+    let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
+        .args([
+            "run",
+            "--",
+            "search",
+            "search",
+            temp_dir.path().to_str().unwrap(),
+            "--reranker",
+            "hybrid",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
 }
 
 #[test]
@@ -258,7 +292,9 @@ fn test_cli_default_frequency_search() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with default settings (frequency search should be enabled by default)
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -298,11 +334,15 @@ fn test_cli_exact_search() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with exact search option
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
             "search",
+            "--format",
+            "color",
             "search", // Pattern to search for
             temp_dir.path().to_str().unwrap(),
             "--exact",
@@ -335,7 +375,9 @@ fn test_cli_custom_ignores() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with custom ignore pattern and debug mode
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -378,7 +420,7 @@ fn test_cli_custom_ignores() {
 
     // Find where "search.js" appears in the debug output
     if let Some(pos) = stdout.find("search.js") {
-        let start = if pos > 50 { pos - 50 } else { 0 };
+        let start = pos.saturating_sub(50);
         let end = if pos + 50 < stdout.len() {
             pos + 50
         } else {
@@ -411,7 +453,9 @@ fn test_cli_max_results() {
     }
 
     // Run the CLI with max results limit
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -456,3 +500,29 @@ fn test_cli_max_results() {
         "Should find only 1 result"
     );
 }
+
+#[test]
+fn test_cli_no_history_leaves_searches_unrecorded() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    create_test_directory_structure(&temp_dir);
+    let history_dir = TempDir::new().unwrap();
+    let search = |extra: &[&str], env: &[(&str, &str)]| {
+        let output = Command::new("cargo")
+            .env("PROBE_HISTORY_DIR", history_dir.path())
+            .envs(env.iter().copied())
+            .args(["run", "--", "search", "search"])
+            .arg(temp_dir.path())
+            .args(extra)
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+    };
+    let recorded = || fs::read_dir(history_dir.path()).unwrap().count();
+
+    search(&["--no-history"], &[]);
+    search(&[], &[("PROBE_NO_HISTORY", "1")]);
+    assert_eq!(recorded(), 0, "No history file should be written");
+
+    search(&[], &[("PROBE_NO_HISTORY", "")]);
+    assert_eq!(recorded(), 1, "Searches are recorded by default");
+}
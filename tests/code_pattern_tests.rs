@@ -0,0 +1,114 @@
+use probe::models::SearchResult;
+use probe::search::code_pattern::normalize_code;
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, snippet: &str) -> Vec<SearchResult> {
+    let queries = vec![snippet.to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        code_pattern: true,
+        ..Default::default()
+    };
+    perform_probe(&options).unwrap().results
+}
+
+fn lines(results: &[SearchResult]) -> Vec<(usize, usize)> {
+    let mut lines: Vec<(usize, usize)> = results.iter().map(|r| r.lines).collect();
+    lines.sort();
+    lines
+}
+
+#[test]
+fn test_normalize_code() {
+    assert_eq!(
+        normalize_code("if err != nil {\n\treturn err\n}"),
+        "if err!=nil{return err}"
+    );
+    assert_eq!(normalize_code("f(\n    a,\n    b,\n)"), "f(a,b)");
+    assert_eq!(normalize_code("  \n "), "");
+}
+
+#[test]
+fn test_code_pattern_ignores_formatting() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("main.go"),
+        "package main\n\
+         \n\
+         func load(path string) error {\n\
+         \tdata, err := read(path)\n\
+         \tif err != nil {\n\
+         \t\treturn err\n\
+         \t}\n\
+         \t_ = data\n\
+         \treturn nil\n\
+         }\n\
+         \n\
+         func compact() error {\n\
+         \tif err != nil { return   err }\n\
+         \treturn nil\n\
+         }\n\
+         \n\
+         func other() error {\n\
+         \tif myerr != nil {\n\
+         \t\treturn myerr\n\
+         \t}\n\
+         \treturn nil\n\
+         }\n",
+    )
+    .unwrap();
+
+    let results = search(dir.path(), "if err != nil { return err }");
+    assert_eq!(lines(&results), vec![(3, 10), (12, 15)]);
+    assert!(results
+        .iter()
+        .all(|r| r.node_type == "function_declaration"));
+    assert_eq!(
+        results[0].matched_keywords,
+        Some(vec!["if err != nil { return err }".to_string()])
+    );
+}
+
+#[test]
+fn test_code_pattern_skips_comments_and_trailing_commas() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("lib.rs"),
+        "fn call() {\n\
+         \x20   let v = compute(\n\
+         \x20       alpha,\n\
+         \x20       beta, // the second\n\
+         \x20   );\n\
+         }\n\
+         \n\
+         fn unrelated() {\n\
+         \x20   compute(alpha, gamma);\n\
+         }\n",
+    )
+    .unwrap();
+
+    let results = search(dir.path(), "compute(alpha, beta)");
+    assert_eq!(lines(&results), vec![(1, 6)]);
+    assert_eq!(results[0].node_type, "function_item");
+}
+
+#[test]
+fn test_code_pattern_does_not_match_inside_identifiers() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("lib.rs"),
+        "fn a() {\n    total_count += 1;\n}\n\nfn b() {\n    count += 1;\n}\n",
+    )
+    .unwrap();
+
+    let results = search(dir.path(), "count += 1");
+    assert_eq!(lines(&results), vec![(5, 7)]);
+}
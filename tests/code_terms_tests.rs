@@ -0,0 +1,108 @@
+use probe::search::code_terms::{rules_for, with_code_terms};
+use probe::search::tokenization::tokenize;
+use probe::search::{perform_probe, prepare_query, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn options<'a>(
+    path: &'a Path,
+    queries: &'a [String],
+    code_terms: Option<&'a str>,
+) -> SearchOptions<'a> {
+    SearchOptions {
+        path,
+        queries,
+        exclude_filenames: true,
+        code_terms,
+        ..Default::default()
+    }
+}
+
+fn search(path: &Path, query: &str, code_terms: Option<&str>) -> Vec<(usize, usize)> {
+    let queries = vec![query.to_string()];
+    let mut lines: Vec<(usize, usize)> = perform_probe(&options(path, &queries, code_terms))
+        .unwrap()
+        .results
+        .iter()
+        .map(|r| r.lines)
+        .collect();
+    lines.sort();
+    lines
+}
+
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("store.rs"),
+        r#"pub fn rebuild_indices(store: &mut Vec<u32>) {
+    store.sort();
+}
+
+pub struct LedgerImpl {
+    total: u64,
+}
+"#,
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_code_terms_strip_affixes_and_irregular_plurals() {
+    assert!(tokenize("get_ledger_impl").contains(&"get".to_string()));
+
+    let rust = rules_for("rust");
+    with_code_terms(rust, || {
+        assert_eq!(tokenize("get_ledger_impl"), vec!["ledger"]);
+        assert_eq!(tokenize("LedgerImpl"), vec!["ledger"]);
+        assert_eq!(tokenize("vertices"), vec!["vertex"]);
+        // A prefix is kept when only stop words would be left
+        assert!(tokenize("getString").contains(&"get".to_string()));
+        // Prefixes of other languages are left alone
+        assert!(tokenize("useLedger").contains(&"use".to_string()));
+    });
+    with_code_terms(rules_for("javascript"), || {
+        assert_eq!(tokenize("useLedger"), vec!["ledger"]);
+    });
+    assert!(tokenize("get_ledger_impl").contains(&"get".to_string()));
+}
+
+#[test]
+fn test_code_terms_apply_per_search() {
+    let temp_dir = create_project();
+
+    assert!(search(temp_dir.path(), "getLedger", None).is_empty());
+    assert_eq!(
+        search(temp_dir.path(), "getLedger", Some("rust")),
+        vec![(5, 7)]
+    );
+
+    // The plural is matched although `index` is not part of `indices`
+    assert!(search(temp_dir.path(), "rebuild_index", None).is_empty());
+    assert_eq!(
+        search(temp_dir.path(), "rebuild_index", Some("all")),
+        vec![(1, 3)]
+    );
+}
+
+#[test]
+fn test_prepared_query_keeps_its_code_terms() {
+    let queries = vec!["getLedger".to_string()];
+    let plain = prepare_query(&queries, false).unwrap();
+    let rust = with_code_terms(rules_for("rust"), || prepare_query(&queries, false)).unwrap();
+
+    assert_eq!(plain.code_terms, None);
+    assert_eq!(rust.code_terms, Some("rust"));
+    assert!(plain.plan.term_indices.contains_key("get"));
+    assert!(!rust.plan.term_indices.contains_key("get"));
+}
+
+#[test]
+fn test_unknown_code_terms_language_is_rejected() {
+    let temp_dir = create_project();
+    let queries = vec!["ledger".to_string()];
+
+    let err = perform_probe(&options(temp_dir.path(), &queries, Some("cobol"))).unwrap_err();
+    assert!(err.to_string().contains("--code-terms"));
+}
@@ -11,6 +11,7 @@ fn test_merge_different_node_types() {
     // Create test blocks with different node types
     let block1 = SearchResult {
         file: "mixed_types.rs".to_string(),
+        file_path: None,
         lines: (1, 5),
         node_type: "function".to_string(),
         code:
@@ -35,9 +36,24 @@ fn test_merge_different_node_types() {
         block_id: None,
         matched_keywords: None,
         tokenized_content: None,
+        merged_from: None,
+        normalized_score: None,
+        usage_kind: None,
+        via_symlink: false,
+        changed_lines: None,
+        symbol_name: None,
+        language: None,
+        extraction_confidence: None,
+        partial_parse: false,
+        deprecated: false,
+        embedded: None,
+        kind: None,
+        complexity: None,
+        content_hash: None,
     };
     let block2 = SearchResult {
     file: "mixed_types.rs".to_string(),
+    file_path: None,
     lines: (6, 10),
     node_type: "comment".to_string(),
     code: "// This is a comment block\n// It explains the function above\n// And provides context\n// For the next function\n// Below".to_string(),
@@ -60,10 +76,25 @@ fn test_merge_different_node_types() {
     block_id: None,
     matched_keywords: None,
     tokenized_content: None,
+    merged_from: None,
+    normalized_score: None,
+    usage_kind: None,
+    via_symlink: false,
+    changed_lines: None,
+    symbol_name: None,
+    language: None,
+    extraction_confidence: None,
+    partial_parse: false,
+    deprecated: false,
+    embedded: None,
+    kind: None,
+    complexity: None,
+    content_hash: None,
 };
 
     let block3 = SearchResult {
         file: "mixed_types.rs".to_string(),
+        file_path: None,
         lines: (11, 15),
         node_type: "function".to_string(),
         code: "fn another_function() {\n    let z = 3;\n    let result = z * 2;\n    println!(\"{}\", result);\n}".to_string(),
@@ -86,6 +117,20 @@ fn test_merge_different_node_types() {
         block_id: None,
         matched_keywords: None,
         tokenized_content: None,
+        merged_from: None,
+        normalized_score: None,
+        usage_kind: None,
+        via_symlink: false,
+        changed_lines: None,
+        symbol_name: None,
+        language: None,
+        extraction_confidence: None,
+        partial_parse: false,
+        deprecated: false,
+        embedded: None,
+        kind: None,
+        complexity: None,
+        content_hash: None,
     };
 
     // Create a vector with all blocks
@@ -129,6 +174,7 @@ fn test_merge_with_gaps() {
     // Create test blocks with gaps between them
     let block1 = SearchResult {
         file: "gaps.rs".to_string(),
+        file_path: None,
         lines: (1, 5),
         node_type: "function".to_string(),
         code:
@@ -153,11 +199,26 @@ fn test_merge_with_gaps() {
         block_id: None,
         matched_keywords: None,
         tokenized_content: None,
+        merged_from: None,
+        normalized_score: None,
+        usage_kind: None,
+        via_symlink: false,
+        changed_lines: None,
+        symbol_name: None,
+        language: None,
+        extraction_confidence: None,
+        partial_parse: false,
+        deprecated: false,
+        embedded: None,
+        kind: None,
+        complexity: None,
+        content_hash: None,
     };
 
     // Gap of 3 lines between block1 and block2
     let block2 = SearchResult {
         file: "gaps.rs".to_string(),
+        file_path: None,
         lines: (9, 13),
         node_type: "function".to_string(),
         code: "fn second_function() {\n    let z = 3;\n    let result = z * 2;\n    println!(\"{}\", result);\n}".to_string(),
@@ -180,11 +241,26 @@ fn test_merge_with_gaps() {
         block_id: None,
         matched_keywords: None,
         tokenized_content: None,
+        merged_from: None,
+        normalized_score: None,
+        usage_kind: None,
+        via_symlink: false,
+        changed_lines: None,
+        symbol_name: None,
+        language: None,
+        extraction_confidence: None,
+        partial_parse: false,
+        deprecated: false,
+        embedded: None,
+        kind: None,
+        complexity: None,
+        content_hash: None,
     };
 
     // Gap of 2 lines between block2 and block3
     let block3 = SearchResult {
         file: "gaps.rs".to_string(),
+        file_path: None,
         lines: (16, 20),
         node_type: "function".to_string(),
         code:
@@ -209,6 +285,20 @@ fn test_merge_with_gaps() {
         block_id: None,
         matched_keywords: None,
         tokenized_content: None,
+        merged_from: None,
+        normalized_score: None,
+        usage_kind: None,
+        via_symlink: false,
+        changed_lines: None,
+        symbol_name: None,
+        language: None,
+        extraction_confidence: None,
+        partial_parse: false,
+        deprecated: false,
+        embedded: None,
+        kind: None,
+        complexity: None,
+        content_hash: None,
     };
 
     // Test with default threshold (5)
@@ -282,6 +372,7 @@ fn test_merge_overlapping_blocks() {
     // Create test blocks with overlapping lines
     let block1 = SearchResult {
         file: "overlap.rs".to_string(),
+        file_path: None,
         lines: (1, 7),
         node_type: "function".to_string(),
         code: "fn first_function() {\n    let x = 1;\n    let y = 2;\n    println!(\"{}\", x + y);\n    // Shared lines\n    let shared = true;\n}".to_string(),
@@ -304,11 +395,26 @@ fn test_merge_overlapping_blocks() {
         block_id: None,
         matched_keywords: None,
         tokenized_content: None,
+        merged_from: None,
+        normalized_score: None,
+        usage_kind: None,
+        via_symlink: false,
+        changed_lines: None,
+        symbol_name: None,
+        language: None,
+        extraction_confidence: None,
+        partial_parse: false,
+        deprecated: false,
+        embedded: None,
+        kind: None,
+        complexity: None,
+        content_hash: None,
     };
 
     // Overlaps with block1 (lines 5-7 are shared)
     let block2 = SearchResult {
         file: "overlap.rs".to_string(),
+        file_path: None,
         lines: (5, 10),
         node_type: "function".to_string(),
         code: "    // Shared lines\n    let shared = true;\n}\n\nfn second_function() {\n    let z = 3;\n}".to_string(),
@@ -331,6 +437,20 @@ fn test_merge_overlapping_blocks() {
         block_id: None,
         matched_keywords: None,
         tokenized_content: None,
+        merged_from: None,
+        normalized_score: None,
+        usage_kind: None,
+        via_symlink: false,
+        changed_lines: None,
+        symbol_name: None,
+        language: None,
+        extraction_confidence: None,
+        partial_parse: false,
+        deprecated: false,
+        embedded: None,
+        kind: None,
+        complexity: None,
+        content_hash: None,
     };
 
     // Create a vector with both blocks
@@ -409,21 +529,14 @@ fn third_function() {
         let options = SearchOptions {
             path: temp_path,
             queries: &queries,
-            files_only: false,
             custom_ignores: &custom_ignores,
             exclude_filenames: true,
             reranker: "combined",
             frequency_search: false,
-            max_results: None,
-            max_bytes: None,
-            max_tokens: None,
             allow_tests: true,
 
-            exact: false,
-            no_merge: false,
             merge_threshold: Some(threshold),
-            dry_run: false,
-            session: None,
+            ..Default::default()
         };
 
         // Run the search
@@ -479,6 +592,7 @@ fn test_merge_parent_child_blocks() {
     // Create test blocks with parent-child relationships
     let parent_block = SearchResult {
         file: "parent_child.rs".to_string(),
+        file_path: None,
         lines: (1, 10),
         node_type: "class".to_string(),
         code: "struct TestStruct {\n    x: i32,\n    y: i32,\n}\n\nimpl TestStruct {\n    fn new(x: i32, y: i32) -> Self {\n        Self { x, y }\n    }\n}".to_string(),
@@ -501,11 +615,26 @@ fn test_merge_parent_child_blocks() {
         block_id: Some(0),
         matched_keywords: None,
         tokenized_content: None,
+        merged_from: None,
+        normalized_score: None,
+        usage_kind: None,
+        via_symlink: false,
+        changed_lines: None,
+        symbol_name: None,
+        language: None,
+        extraction_confidence: None,
+        partial_parse: false,
+        deprecated: false,
+        embedded: None,
+        kind: None,
+        complexity: None,
+        content_hash: None,
     };
 
     // Child block (method inside the struct)
     let child_block = SearchResult {
         file: "parent_child.rs".to_string(),
+        file_path: None,
         lines: (7, 9),
         node_type: "function".to_string(),
         code: "    fn new(x: i32, y: i32) -> Self {\n        Self { x, y }\n    }".to_string(),
@@ -528,6 +657,20 @@ fn test_merge_parent_child_blocks() {
         block_id: Some(1),
         matched_keywords: None,
         tokenized_content: None,
+        merged_from: None,
+        normalized_score: None,
+        usage_kind: None,
+        via_symlink: false,
+        changed_lines: None,
+        symbol_name: None,
+        language: None,
+        extraction_confidence: None,
+        partial_parse: false,
+        deprecated: false,
+        embedded: None,
+        kind: None,
+        complexity: None,
+        content_hash: None,
     };
 
     // Create a vector with both blocks
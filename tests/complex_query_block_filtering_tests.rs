@@ -294,21 +294,13 @@ func (i *IPWhiteListMiddleware) Process() {
     let options = SearchOptions {
         path: temp_path,
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
         // Use all terms mode
         exact: false, // Enable stemming
         no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Run the search
@@ -372,20 +364,12 @@ func Process() {
     let options = SearchOptions {
         path: temp_path,
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
         exclude_filenames: false, // Include filenames in search
         reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
-        exact: false,
         no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Run the search
@@ -0,0 +1,105 @@
+use probe::models::{BlockComplexity, SearchResult};
+use probe::search::complexity::block_complexity;
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, query: &str, sort: &str) -> Vec<SearchResult> {
+    let queries = vec![query.to_string()];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        exclude_filenames: true,
+        allow_tests: true,
+        no_merge: true,
+        sort,
+        ..Default::default()
+    };
+    perform_probe(&options).unwrap().results
+}
+
+#[test]
+fn test_block_complexity_counts_branches_and_nesting() {
+    let code = r#"fn classify(value: i32) -> &'static str {
+    // if the value is negative, bail out
+    if value < 0 || value > 100 {
+        return "invalid";
+    }
+    for step in 0..value {
+        while step > 10 {
+            if step % 2 == 0 && value > 50 {
+                return "large";
+            }
+        }
+    }
+    "small"
+}"#;
+    assert_eq!(
+        block_complexity(code),
+        BlockComplexity {
+            lines: 14,
+            nesting_depth: 4,
+            cyclomatic: 7,
+        }
+    );
+}
+
+#[test]
+fn test_block_complexity_of_straight_line_code() {
+    let code = "\tdef total(items):\n\t\tresult = sum(items)\n\t\treturn result\n";
+    assert_eq!(
+        block_complexity(code),
+        BlockComplexity {
+            lines: 3,
+            nesting_depth: 1,
+            cyclomatic: 1,
+        }
+    );
+    // Keywords inside identifiers are not branches
+    assert_eq!(
+        block_complexity("let format_info = verify_iframe();").cyclomatic,
+        1
+    );
+}
+
+#[test]
+fn test_sort_by_complexity_puts_complex_blocks_first() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("checks.rs"),
+        r#"pub fn check_simple(order: &Order) -> bool {
+    order.total > 0
+}
+
+pub fn check_nested(order: &Order) -> bool {
+    for item in &order.items {
+        if item.count > 0 && item.price > 0 {
+            if item.discount {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+pub fn check_branch(order: &Order) -> bool {
+    if order.total > 0 {
+        return true;
+    }
+    false
+}
+"#,
+    )
+    .unwrap();
+
+    let results = search(temp_dir.path(), "check order", "complexity");
+    let first_lines: Vec<usize> = results.iter().map(|r| r.lines.0).collect();
+    assert_eq!(first_lines, vec![5, 16, 1]);
+
+    let metrics: Vec<usize> = results
+        .iter()
+        .map(|r| r.complexity.expect("every block has metrics").cyclomatic)
+        .collect();
+    assert_eq!(metrics, vec![5, 2, 1]);
+}
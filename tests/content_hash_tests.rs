@@ -0,0 +1,122 @@
+use probe::models::SearchResult;
+use probe::search::content_hash::{
+    corpus_fingerprint, files_fingerprint, hash_content, results_fingerprint,
+};
+use probe::search::{perform_probe, write_search_results, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn options<'a>(path: &'a Path, queries: &'a [String]) -> SearchOptions<'a> {
+    SearchOptions {
+        path,
+        queries,
+        exclude_filenames: true,
+        allow_tests: true,
+        no_merge: true,
+        ..Default::default()
+    }
+}
+
+fn search(path: &Path, query: &str) -> Vec<SearchResult> {
+    let queries = vec![query.to_string()];
+    perform_probe(&options(path, &queries)).unwrap().results
+}
+
+#[test]
+fn test_hash_content_is_stable_fnv1a() {
+    assert_eq!(hash_content(""), "cbf29ce484222325");
+    assert_eq!(hash_content("a"), "af63dc4c8601ec8c");
+    assert_ne!(hash_content("fn main() {}"), hash_content("fn main() {}\n"));
+}
+
+#[test]
+fn test_corpus_fingerprint_ignores_order_and_duplicates() {
+    let fingerprint = corpus_fingerprint([("a.rs", "1111"), ("b.rs", "2222")]);
+    assert_eq!(
+        corpus_fingerprint([("b.rs", "2222"), ("a.rs", "1111"), ("a.rs", "1111")]),
+        fingerprint
+    );
+    assert_ne!(
+        corpus_fingerprint([("a.rs", "1111"), ("b.rs", "3333")]),
+        fingerprint
+    );
+    // Moving content between files changes the fingerprint too
+    assert_ne!(
+        corpus_fingerprint([("a.rs", "2222"), ("b.rs", "1111")]),
+        fingerprint
+    );
+}
+
+#[test]
+fn test_results_carry_content_hash_and_fingerprint() {
+    let temp_dir = TempDir::new().unwrap();
+    let parser = "pub fn parse_config(text: &str) -> Config {\n    Config::from(text)\n}\n";
+    let loader = "pub fn load_config(path: &str) -> Config {\n    parse_config(path)\n}\n";
+    fs::write(temp_dir.path().join("parser.rs"), parser).unwrap();
+    fs::write(temp_dir.path().join("loader.rs"), loader).unwrap();
+
+    let results = search(temp_dir.path(), "config");
+    assert_eq!(results.len(), 2);
+    for result in &results {
+        let content = fs::read_to_string(result.path()).unwrap();
+        assert_eq!(result.content_hash, Some(hash_content(&content)));
+    }
+    let fingerprint = results_fingerprint(&results).unwrap();
+
+    let mut output = Vec::new();
+    write_search_results(
+        &mut output,
+        &results,
+        false,
+        "json",
+        None,
+        &[],
+        Some("corpus"),
+    )
+    .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json["summary"]["results_fingerprint"], fingerprint.as_str());
+    assert_eq!(json["summary"]["corpus_fingerprint"], "corpus");
+    assert_eq!(
+        json["results"][0]["content_hash"],
+        results[0].content_hash.as_deref().unwrap()
+    );
+
+    // Any change to a searched file changes the fingerprint
+    fs::write(
+        temp_dir.path().join("loader.rs"),
+        loader.replace("path", "file"),
+    )
+    .unwrap();
+    let changed = search(temp_dir.path(), "config");
+    assert_ne!(results_fingerprint(&changed).unwrap(), fingerprint);
+}
+
+#[test]
+fn test_files_fingerprint_covers_files_without_results() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("parser.rs"),
+        "pub fn parse_config() {}\n",
+    )
+    .unwrap();
+    fs::write(temp_dir.path().join("other.rs"), "pub fn unrelated() {}\n").unwrap();
+
+    let queries = vec!["config".to_string()];
+    let options = options(temp_dir.path(), &queries);
+    let fingerprint = files_fingerprint(&options).unwrap().unwrap();
+    let matched = results_fingerprint(&perform_probe(&options).unwrap().results);
+
+    // Editing a file that matches nothing leaves the results alone but not the corpus
+    fs::write(
+        temp_dir.path().join("other.rs"),
+        "pub fn unrelated() {}\npub fn unrelated_too() {}\n",
+    )
+    .unwrap();
+    assert_eq!(
+        results_fingerprint(&perform_probe(&options).unwrap().results),
+        matched
+    );
+    assert_ne!(files_fingerprint(&options).unwrap().unwrap(), fingerprint);
+}
@@ -0,0 +1,89 @@
+use probe::search::content_tokens::tokenize_content;
+use probe::search::tokenization::{tokenize, tokenize_code};
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, query: &str) -> Vec<String> {
+    let queries = vec![query.to_string()];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        exclude_filenames: true,
+        no_merge: true,
+        ..Default::default()
+    };
+    let mut files: Vec<String> = perform_probe(&options)
+        .unwrap()
+        .results
+        .iter()
+        .map(|r| {
+            Path::new(&r.file)
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("style.css"),
+        ".button {\n  font-size: 12px;\n  -webkit-transition: all 1s;\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("run.sh"),
+        "run_search() {\n  probe search --max-results 5 \"$1\"\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("lib.rs"),
+        "pub fn swap_out(v: &mut Vec<u8>) -> Vec<u8> {\n    std::mem::take(v)\n}\n",
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_content_keeps_compound_names_per_language() {
+    let rust = tokenize_content("std::mem::take(v)", Some("rust"));
+    assert!(rust.contains(&"std::mem::take".to_string()));
+    assert!(rust.contains(&"mem::take".to_string()));
+    // The parts are still tokens of their own
+    assert!(rust.contains(&"take".to_string()));
+
+    let js = tokenize_content("return user.profile.name;", Some("javascript"));
+    assert!(js.contains(&"user.profile.name".to_string()));
+    assert!(js.contains(&"profile.name".to_string()));
+
+    let css = tokenize_content("font-size: 12px;", Some("css"));
+    assert!(css.contains(&"font-size".to_string()));
+
+    // Each language joins names with its own separators only
+    assert!(!tokenize_content("font-size", Some("rust")).contains(&"font-size".to_string()));
+    assert!(!tokenize_content("std::mem", None).contains(&"std::mem".to_string()));
+}
+
+#[test]
+fn test_leading_dash_in_code_is_not_a_negation() {
+    assert!(tokenize("-webkit-transition").is_empty());
+    assert!(tokenize_code("-webkit-transition").contains(&"webkit".to_string()));
+    assert!(tokenize_code("--max-results").contains(&"max".to_string()));
+}
+
+#[test]
+fn test_search_matches_compound_names_and_dashed_code() {
+    let temp_dir = create_project();
+
+    assert_eq!(search(temp_dir.path(), "\"font-size\""), vec!["style.css"]);
+    assert_eq!(search(temp_dir.path(), "\"mem::take\""), vec!["lib.rs"]);
+    assert_eq!(search(temp_dir.path(), "webkit transition"), vec!["style.css"]);
+    assert_eq!(search(temp_dir.path(), "max results"), vec!["run.sh"]);
+}
@@ -0,0 +1,133 @@
+use probe::ranking::tokenize;
+use probe::search::corpus_stats::CorpusStats;
+use probe::search::{EngineOptions, ProbeEngine};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// The term ranking counts for a word
+fn term(word: &str) -> String {
+    tokenize(word).remove(0)
+}
+
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("alpha.rs"),
+        "fn alpha_handler() {\n    println!(\"alpha needle\");\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("beta.rs"),
+        "fn beta_handler() {\n    println!(\"beta needle\");\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("gamma.rs"),
+        "fn gamma_handler() {\n    println!(\"gamma haystack\");\n}\n",
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_corpus_stats_update_and_remove_files() {
+    let mut stats = CorpusStats::new();
+    stats.update_file(Path::new("a.rs"), "fn needle() {}");
+    stats.update_file(Path::new("b.rs"), "fn needle() {}");
+    assert_eq!(stats.document_count(), 2);
+    assert_eq!(stats.document_frequency(&term("needle")), 2);
+
+    stats.update_file(Path::new("b.rs"), "fn haystack() {}");
+    assert_eq!(stats.document_count(), 2);
+    assert_eq!(stats.document_frequency(&term("needle")), 1);
+    assert_eq!(stats.document_frequency(&term("haystack")), 1);
+
+    stats.remove_file(Path::new("a.rs"));
+    assert_eq!(stats.document_count(), 1);
+    assert_eq!(stats.document_frequency(&term("needle")), 0);
+    assert!(!stats.contains(Path::new("a.rs")));
+}
+
+#[test]
+fn test_engine_ranks_with_corpus_stats() {
+    let dir = create_project();
+    let options = EngineOptions {
+        corpus_stats: true,
+        ..EngineOptions::default()
+    };
+    let engine = ProbeEngine::with_options(dir.path(), options).unwrap();
+    {
+        let stats = engine.corpus_stats().unwrap().read().unwrap();
+        assert_eq!(stats.document_count(), 3);
+        assert_eq!(stats.document_frequency(&term("needle")), 2);
+    }
+
+    // The same block scores the same whatever else the query matched
+    let alone = engine.search("alpha").unwrap();
+    let together = engine.search("alpha OR haystack").unwrap();
+    let score = |results: &[probe::models::SearchResult]| {
+        results
+            .iter()
+            .find(|r| r.file.ends_with("alpha.rs"))
+            .and_then(|r| r.bm25_score)
+            .unwrap()
+    };
+    assert_eq!(score(&alone.results), score(&together.results));
+
+    assert!(ProbeEngine::new(dir.path())
+        .unwrap()
+        .corpus_stats()
+        .is_none());
+}
+
+#[test]
+fn test_engine_updates_corpus_stats_on_file_changes() {
+    let dir = create_project();
+    let options = EngineOptions {
+        corpus_stats: true,
+        ..EngineOptions::default()
+    };
+    let mut engine = ProbeEngine::with_options(dir.path(), options).unwrap();
+    let needle = term("needle");
+    let frequency = |engine: &ProbeEngine| {
+        let stats = engine.corpus_stats().unwrap().read().unwrap();
+        (stats.document_count(), stats.document_frequency(&needle))
+    };
+
+    let gamma: PathBuf = dir.path().join("gamma.rs");
+    fs::write(
+        &gamma,
+        "fn gamma_handler() {\n    println!(\"needle\");\n}\n",
+    )
+    .unwrap();
+    assert_eq!(frequency(&engine), (3, 2));
+    engine.update_files(std::slice::from_ref(&gamma)).unwrap();
+    assert_eq!(frequency(&engine), (3, 3));
+
+    let alpha = dir.path().join("alpha.rs");
+    fs::remove_file(&alpha).unwrap();
+    engine.update_files(&[alpha]).unwrap();
+    assert_eq!(frequency(&engine), (2, 2));
+
+    fs::write(dir.path().join("delta.rs"), "fn needle_delta() {}\n").unwrap();
+    engine.refresh().unwrap();
+    assert_eq!(frequency(&engine), (3, 3));
+}
+
+#[test]
+fn test_memory_engine_builds_corpus_stats() {
+    let engine = ProbeEngine::from_memory(
+        [
+            ("src/a.rs", "fn needle_one() {}\n"),
+            ("src/b.rs", "fn needle_two() {}\n"),
+        ],
+        EngineOptions {
+            corpus_stats: true,
+            ..EngineOptions::default()
+        },
+    );
+    let stats = engine.corpus_stats().unwrap().read().unwrap();
+    assert_eq!(stats.document_count(), 2);
+    assert_eq!(stats.document_frequency(&term("needle")), 2);
+}
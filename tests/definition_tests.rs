@@ -0,0 +1,126 @@
+use probe::definition::{resolve_definition, DefinitionOptions, SymbolKind, UseSite};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn create_test_files(dir: &Path) {
+    fs::create_dir_all(dir.join("src/config")).unwrap();
+    fs::create_dir_all(dir.join("src/cache")).unwrap();
+
+    fs::write(
+        dir.join("src/config/mod.rs"),
+        r#"pub struct Config;
+
+impl Config {
+    pub fn load(path: &str) -> Config {
+        Config
+    }
+}
+"#,
+    )
+    .unwrap();
+
+    fs::write(
+        dir.join("src/cache/store.rs"),
+        r#"pub fn load(key: &str) -> Option<String> {
+    None
+}
+"#,
+    )
+    .unwrap();
+
+    fs::write(
+        dir.join("src/main.rs"),
+        r#"use crate::cache::store::load;
+
+fn main() {
+    let value = load("key");
+}
+"#,
+    )
+    .unwrap();
+
+    fs::write(
+        dir.join("src/app.ts"),
+        r#"const load = (name: string) => name;
+
+export function start() {
+  return load("app");
+}
+"#,
+    )
+    .unwrap();
+}
+
+fn resolve(
+    dir: &Path,
+    identifier: &str,
+    from: Option<&UseSite>,
+    max_results: usize,
+) -> probe::definition::Resolution {
+    resolve_definition(&DefinitionOptions {
+        path: dir,
+        identifier,
+        from,
+        ignore: &[],
+        allow_tests: false,
+        max_results: Some(max_results),
+    })
+    .unwrap()
+}
+
+fn file_name(definition: &probe::definition::Definition) -> String {
+    definition
+        .file_path
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .to_string()
+}
+
+#[test]
+fn test_def_ranks_by_kind_and_qualifier() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let all = resolve(temp_dir.path(), "load", None, 10);
+    assert_eq!(all.name, "load");
+    assert_eq!(all.candidates, 3);
+    // Functions outrank the variable holding an arrow function
+    assert_eq!(all.definitions[2].kind, SymbolKind::Variable);
+    assert_eq!(file_name(&all.definitions[2]), "app.ts");
+    assert_eq!(all.definitions[2].line_start, 1);
+
+    let qualified = resolve(temp_dir.path(), "Config::load", None, 1);
+    assert_eq!(qualified.definitions.len(), 1);
+    let best = &qualified.definitions[0];
+    assert_eq!(file_name(best), "mod.rs");
+    assert_eq!(best.container.as_deref(), Some("Config"));
+    assert_eq!((best.line_start, best.line_end), (4, 6));
+    assert!(best
+        .reasons
+        .iter()
+        .any(|reason| reason == "matches qualifier"));
+}
+
+#[test]
+fn test_def_prefers_definitions_imported_by_the_use_site() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let from = UseSite::parse(&temp_dir.path().join("src/main.rs:4").to_string_lossy()).unwrap();
+    assert_eq!(from.line, Some(4));
+    let resolution = resolve(temp_dir.path(), "load", Some(&from), 1);
+    let best = &resolution.definitions[0];
+    assert_eq!(file_name(best), "store.rs");
+    assert!(best
+        .reasons
+        .iter()
+        .any(|reason| reason == "imported by name"));
+
+    let from = UseSite::parse(&temp_dir.path().join("src/app.ts:4").to_string_lossy()).unwrap();
+    let resolution = resolve(temp_dir.path(), "load", Some(&from), 1);
+    let best = &resolution.definitions[0];
+    assert_eq!(file_name(best), "app.ts");
+    assert!(best.reasons.iter().any(|reason| reason == "same file"));
+}
@@ -0,0 +1,84 @@
+use probe::language::deprecation::is_deprecated;
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(
+    path: &Path,
+    deprecated_penalty: f64,
+) -> anyhow::Result<Vec<probe::models::SearchResult>> {
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        deprecated_penalty,
+        ..Default::default()
+    };
+    Ok(perform_probe(&options)?.results)
+}
+
+fn lines(content: &str) -> Vec<&str> {
+    content.lines().collect()
+}
+
+#[test]
+fn test_deprecation_markers() {
+    let rust = lines("#[deprecated(since = \"2.0\", note = \"use parse_v2\")]\n#[inline]\nfn parse() {}\n\nfn parse_v2() {}\n");
+    assert!(is_deprecated(&rust, 3, 3));
+    assert!(is_deprecated(&rust, 1, 3));
+    assert!(!is_deprecated(&rust, 5, 5));
+
+    let java = lines("class Api {\n    @Deprecated\n    public void oldCall() {}\n\n    public void newCall() {}\n}\n");
+    assert!(is_deprecated(&java, 3, 3));
+    assert!(!is_deprecated(&java, 5, 5));
+    // A deprecated member does not make its class deprecated
+    assert!(!is_deprecated(&java, 1, 6));
+
+    let js = lines("/**\n * Fetch a user.\n * @deprecated Use fetchAccount instead.\n */\nfunction fetchUser() {}\n");
+    assert!(is_deprecated(&js, 5, 5));
+    assert!(is_deprecated(&js, 1, 5));
+
+    let go = lines(
+        "// OldClient talks to v1.\n//\n// Deprecated: use NewClient.\nfunc OldClient() {}\n",
+    );
+    assert!(is_deprecated(&go, 4, 4));
+}
+
+#[test]
+fn test_deprecated_penalty() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("lib.rs"),
+        r#"#[deprecated(note = "use the faster lookup")]
+fn lookup_needle() {
+    let needle = needle_index(needle);
+    println!("{} {}", needle, needle);
+}
+
+fn find_needle() {
+    let haystack = load();
+    println!("{}", needle_index(haystack));
+}
+"#,
+    )
+    .unwrap();
+
+    // Deprecated blocks are only demoted when a penalty is asked for
+    assert_eq!(SearchOptions::default().deprecated_penalty, 0.0);
+    let results = search(temp_dir.path(), 0.0).unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(results[0].code.contains("fn lookup_needle"));
+    assert!(results[0].deprecated);
+    assert!(!results[1].deprecated);
+
+    let results = search(temp_dir.path(), 1.0).unwrap();
+    assert!(results[0].code.contains("fn find_needle"));
+    assert!(results[1].deprecated);
+
+    assert!(search(temp_dir.path(), 2.0).is_err());
+}
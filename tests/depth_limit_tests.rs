@@ -0,0 +1,127 @@
+use probe::models::{LimitedSearchResults, SkipReason};
+use probe::search::file_list_cache::FileFilters;
+use probe::search::{perform_probe, EngineOptions, ProbeEngine, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(
+    path: &Path,
+    min_depth: Option<usize>,
+    max_depth: Option<usize>,
+    report_skipped: bool,
+) -> LimitedSearchResults {
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        report_skipped,
+        max_depth,
+        min_depth,
+        ..Default::default()
+    };
+    perform_probe(&options).unwrap()
+}
+
+/// `top.rs` at depth 1, `pkg/mod.rs` at depth 2 and `pkg/gen/deep.rs` at depth 3
+fn create_nested_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let files = [
+        ("top.rs", "fn top() {\n    let needle = 1;\n}\n"),
+        ("pkg/mod.rs", "fn package() {\n    let needle = 2;\n}\n"),
+        ("pkg/gen/deep.rs", "fn deep() {\n    let needle = 3;\n}\n"),
+    ];
+    for (name, content) in files {
+        let path = temp_dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    temp_dir
+}
+
+fn file_names(results: &LimitedSearchResults) -> Vec<String> {
+    let mut names: Vec<String> = results
+        .results
+        .iter()
+        .map(|r| {
+            Path::new(&r.file)
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_max_depth_stops_descending() {
+    let project = create_nested_project();
+
+    let results = search(project.path(), None, Some(1), false);
+    assert_eq!(file_names(&results), vec!["top.rs"]);
+
+    let results = search(project.path(), None, Some(2), false);
+    assert_eq!(file_names(&results), vec!["mod.rs", "top.rs"]);
+}
+
+#[test]
+fn test_min_depth_skips_shallow_files() {
+    let project = create_nested_project();
+
+    let results = search(project.path(), Some(2), None, false);
+    assert_eq!(file_names(&results), vec!["deep.rs", "mod.rs"]);
+
+    let results = search(project.path(), Some(2), Some(2), false);
+    assert_eq!(file_names(&results), vec!["mod.rs"]);
+}
+
+#[test]
+fn test_skip_report_names_depth_limits() {
+    let project = create_nested_project();
+
+    let results = search(project.path(), Some(2), Some(2), true);
+    let report = results.skip_report.unwrap();
+    let reasons: Vec<(String, bool, SkipReason)> = report
+        .into_iter()
+        .map(|s| {
+            let relative = s.path.strip_prefix(project.path()).unwrap();
+            (
+                relative.to_string_lossy().replace('\\', "/"),
+                s.is_dir,
+                s.reason,
+            )
+        })
+        .collect();
+    assert_eq!(
+        reasons,
+        vec![
+            ("pkg/gen".to_string(), true, SkipReason::Depth),
+            ("top.rs".to_string(), false, SkipReason::Depth),
+        ]
+    );
+}
+
+#[test]
+fn test_engine_applies_depth_limits() {
+    let project = create_nested_project();
+    let engine = ProbeEngine::with_options(
+        project.path(),
+        EngineOptions {
+            filters: FileFilters {
+                max_depth: Some(1),
+                ..FileFilters::default()
+            },
+            ..EngineOptions::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(engine.file_count(), 1);
+    let results = engine.search("needle").unwrap();
+    assert_eq!(file_names(&results), vec!["top.rs"]);
+}
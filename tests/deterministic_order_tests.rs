@@ -0,0 +1,82 @@
+use probe::search::file_list_cache::{get_file_list, FileFilters};
+use probe::search::query::{create_query_plan, create_structured_patterns};
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path) -> Vec<probe::models::SearchResult> {
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        ..Default::default()
+    };
+    perform_probe(&options).unwrap().results
+}
+
+#[test]
+fn test_file_list_is_sorted() {
+    let temp_dir = TempDir::new().unwrap();
+    for name in ["zeta", "alpha", "mu", "beta"] {
+        let dir = temp_dir.path().join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("b.rs"), "fn b() {}\n").unwrap();
+        fs::write(dir.join("a.rs"), "fn a() {}\n").unwrap();
+    }
+
+    let file_list =
+        get_file_list(temp_dir.path(), false, &[], false, FileFilters::default()).unwrap();
+    let mut sorted = file_list.files.clone();
+    sorted.sort();
+    assert_eq!(file_list.files.len(), 8);
+    assert_eq!(file_list.files, sorted);
+}
+
+#[test]
+fn test_patterns_come_in_the_same_order_every_run() {
+    let patterns = || {
+        let plan = create_query_plan("whitelist AND firewall AND network", false).unwrap();
+        create_structured_patterns(&plan)
+            .into_iter()
+            .map(|(pattern, _)| pattern)
+            .collect::<Vec<_>>()
+    };
+
+    let first = patterns();
+    assert!(first.len() > 2);
+    for _ in 0..10 {
+        assert_eq!(patterns(), first);
+    }
+}
+
+#[test]
+fn test_merged_results_come_in_the_same_order_every_run() {
+    let temp_dir = TempDir::new().unwrap();
+    for index in 0..12 {
+        // Adjacent functions in each file are merged into one block
+        fs::write(
+            temp_dir.path().join(format!("file_{:02}.rs", index)),
+            format!(
+                "fn needle_{index}() {{\n    needle();\n}}\n\nfn needle_again_{index}() {{\n    needle();\n}}\n"
+            ),
+        )
+        .unwrap();
+    }
+
+    let locations = |results: &[probe::models::SearchResult]| {
+        results
+            .iter()
+            .map(|result| (result.file.clone(), result.lines))
+            .collect::<Vec<_>>()
+    };
+
+    let first = search(temp_dir.path());
+    assert_eq!(first.len(), 12);
+    for _ in 0..5 {
+        assert_eq!(locations(&search(temp_dir.path())), locations(&first));
+    }
+}
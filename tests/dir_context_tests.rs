@@ -0,0 +1,101 @@
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, include_dir_context: bool) -> Vec<probe::models::SearchResult> {
+    let queries = vec!["credential".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        include_dir_context,
+        ..Default::default()
+    };
+    perform_probe(&options).unwrap().results
+}
+
+fn write(root: &Path, name: &str, content: &str) {
+    let path = root.join(name);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    let rust_fn = |name: &str| format!("fn {}() {{\n    check_credential();\n}}\n", name);
+    let python_fn = |name: &str| format!("def {}():\n    check_credential()\n", name);
+
+    write(
+        root,
+        "auth/README.md",
+        "# Auth\n\nLogin and token handling.\n",
+    );
+    write(root, "auth/login.rs", &rust_fn("login"));
+    write(root, "auth/token.rs", &rust_fn("refresh"));
+    write(root, "util/mod.rs", "//! Helpers shared by all services.\n//! Nothing here talks to the network.\n\npub mod a;\npub mod b;\n");
+    write(root, "util/a.rs", &rust_fn("first"));
+    write(root, "util/b.rs", &rust_fn("second"));
+    write(
+        root,
+        "pkg/__init__.py",
+        "\"\"\"Billing package.\n\nInvoices and payments.\n\"\"\"\n\nVERSION = 1\n",
+    );
+    write(root, "pkg/invoice.py", &python_fn("issue"));
+    write(root, "pkg/payment.py", &python_fn("pay"));
+    // A single result does not make a cluster
+    write(root, "lonely/README.md", "# Lonely\n");
+    write(root, "lonely/alone.rs", &rust_fn("alone"));
+    temp_dir
+}
+
+#[test]
+fn test_dir_context_blocks() {
+    let project = create_project();
+
+    let results = search(project.path(), true);
+    let contexts: Vec<_> = results
+        .iter()
+        .filter(|r| r.node_type == "dir_context")
+        .collect();
+    let mut files: Vec<String> = contexts
+        .iter()
+        .map(|r| {
+            Path::new(&r.file)
+                .strip_prefix(project.path())
+                .unwrap()
+                .to_string_lossy()
+                .replace('\\', "/")
+        })
+        .collect();
+    files.sort();
+    assert_eq!(
+        files,
+        vec!["auth/README.md", "pkg/__init__.py", "util/mod.rs"]
+    );
+
+    // Context blocks come after the regular results
+    assert_eq!(results.len(), 10);
+    assert!(results[7..].iter().all(|r| r.node_type == "dir_context"));
+
+    let util = contexts
+        .iter()
+        .find(|r| r.file.ends_with("mod.rs"))
+        .unwrap();
+    assert_eq!(util.lines, (1, 2));
+    assert!(util.code.contains("Helpers shared"));
+    let pkg = contexts
+        .iter()
+        .find(|r| r.file.ends_with("__init__.py"))
+        .unwrap();
+    assert_eq!(pkg.lines, (1, 4));
+    assert!(pkg.code.contains("Billing package"));
+
+    let results = search(project.path(), false);
+    assert_eq!(results.len(), 7);
+    assert!(results.iter().all(|r| r.node_type != "dir_context"));
+}
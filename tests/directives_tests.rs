@@ -0,0 +1,70 @@
+use probe::search::directives::{find_directives, Directive};
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, query: &str) -> Vec<probe::models::SearchResult> {
+    let queries = vec![query.to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        ..Default::default()
+    };
+    perform_probe(&options).unwrap().results
+}
+
+#[test]
+fn test_find_directives() {
+    let content = "// probe:ignore-next-block\n\nfn old() {}\n\n# probe:boost=3.5 (entry point)\n#[inline]\nfn main() {}\n/* probe:boost */\nfn helper() {}\n// probe:boosted is not a directive\nfn other() {}\n";
+    let directives = find_directives(content);
+
+    assert_eq!(directives.get(&3), Some(&Directive::Ignore));
+    // Attribute lines start with `#`, so the directive applies to the line after them
+    assert_eq!(directives.get(&7), Some(&Directive::Boost(3.5)));
+    assert_eq!(directives.get(&9), Some(&Directive::Boost(2.0)));
+    assert_eq!(directives.len(), 3);
+}
+
+#[test]
+fn test_ignore_and_boost_blocks() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("lib.rs"),
+        r#"fn find_needle_everywhere() {
+    let needle = lookup_needle();
+    println!("needle {}", needle);
+}
+
+// probe:ignore-next-block (deprecated, use find_needle_everywhere)
+fn old_needle_lookup() {
+    let needle = lookup_needle();
+    println!("needle {}", needle);
+}
+
+// probe:boost=100
+fn main_entry() {
+    let haystack = 1;
+    let other = haystack + 1;
+    println!("{} {}", other, stringify!(needle));
+}
+"#,
+    )
+    .unwrap();
+
+    let results = search(temp_dir.path(), "needle");
+    let names: Vec<&str> = results
+        .iter()
+        .map(|r| r.code.lines().find(|l| l.starts_with("fn")).unwrap_or(""))
+        .collect();
+
+    assert!(!names.iter().any(|name| name.contains("old_needle_lookup")));
+    assert!(names[0].contains("main_entry"), "{:?}", names);
+    assert!(names
+        .iter()
+        .any(|name| name.contains("find_needle_everywhere")));
+}
@@ -0,0 +1,165 @@
+use probe::language::ci_yaml::find_jobs;
+use probe::language::dockerfile::{find_stages, is_dockerfile};
+use probe::language::text_blocks::TextBlock;
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const DOCKERFILE: &str = r#"# syntax=docker/dockerfile:1
+ARG RUST_VERSION=1.80
+
+FROM rust:${RUST_VERSION} AS builder
+WORKDIR /app
+COPY . .
+RUN cargo build --release \
+    --locked
+
+FROM --platform=linux/amd64 debian:bookworm-slim
+COPY --from=builder /app/target/release/probe /usr/local/bin/probe
+ENTRYPOINT ["probe"]
+"#;
+
+const WORKFLOW: &str = r#"name: CI
+on:
+  push:
+    branches: [main]
+
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - name: Run tests
+        run: |
+          cargo test --workspace
+  release:
+    needs: test
+    runs-on: ubuntu-latest
+    steps:
+    - name: Publish crate
+      run: cargo publish
+"#;
+
+const GITLAB_CI: &str = r#"stages:
+  - build
+  - deploy
+
+variables:
+  CARGO_HOME: .cargo
+
+.rust-template:
+  image: rust:1.80
+
+build:
+  extends: .rust-template
+  stage: build
+  script:
+    - cargo build --release
+
+deploy:
+  stage: deploy
+  script:
+    - ./deploy.sh
+"#;
+
+fn block(start_line: usize, end_line: usize, kind: &'static str, name: &str) -> TextBlock {
+    TextBlock {
+        start_line,
+        end_line,
+        kind,
+        name: Some(name.to_string()),
+    }
+}
+
+#[test]
+fn test_dockerfile_stages() {
+    assert!(is_dockerfile(Path::new("Dockerfile")));
+    assert!(is_dockerfile(Path::new("docker/Dockerfile.prod")));
+    assert!(is_dockerfile(Path::new("api.dockerfile")));
+    assert!(is_dockerfile(Path::new("Containerfile")));
+    assert!(!is_dockerfile(Path::new("dockerfile_utils.rs")));
+
+    assert_eq!(
+        find_stages(DOCKERFILE),
+        vec![
+            block(4, 8, "stage", "builder"),
+            block(10, 12, "stage", "debian:bookworm-slim"),
+        ]
+    );
+}
+
+#[test]
+fn test_github_actions_jobs_and_steps() {
+    assert_eq!(
+        find_jobs(WORKFLOW),
+        vec![
+            block(7, 13, "job", "test"),
+            block(10, 10, "step", "actions/checkout@v4"),
+            block(11, 13, "step", "Run tests"),
+            block(14, 19, "job", "release"),
+            block(18, 19, "step", "Publish crate"),
+        ]
+    );
+}
+
+#[test]
+fn test_gitlab_ci_jobs() {
+    assert_eq!(
+        find_jobs(GITLAB_CI),
+        vec![
+            block(8, 9, "job", ".rust-template"),
+            block(11, 15, "job", "build"),
+            block(17, 20, "job", "deploy"),
+        ]
+    );
+
+    // Other YAML files have no jobs
+    assert!(find_jobs("name: probe\nversion: 1\nfeatures:\n  - search\n").is_empty());
+}
+
+fn search(path: &Path, query: &str) -> Vec<((usize, usize), String, Option<String>)> {
+    let queries = vec![query.to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        ..Default::default()
+    };
+    perform_probe(&options)
+        .unwrap()
+        .results
+        .into_iter()
+        .map(|r| (r.lines, r.node_type, r.symbol_name))
+        .collect()
+}
+
+#[test]
+fn test_search_returns_stage_and_step() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("Dockerfile"), DOCKERFILE).unwrap();
+    let workflows = temp_dir.path().join(".github").join("workflows");
+    fs::create_dir_all(&workflows).unwrap();
+    fs::write(workflows.join("ci.yml"), WORKFLOW).unwrap();
+    fs::write(temp_dir.path().join("config.yml"), "publish: false\n").unwrap();
+
+    assert_eq!(
+        search(temp_dir.path(), "ENTRYPOINT"),
+        vec![(
+            (10, 12),
+            "stage".to_string(),
+            Some("debian:bookworm-slim".to_string())
+        )]
+    );
+    assert_eq!(
+        search(temp_dir.path(), "publish"),
+        vec![(
+            (18, 19),
+            "step".to_string(),
+            Some("Publish crate".to_string())
+        )]
+    );
+}
@@ -102,20 +102,12 @@ fn test_required_term_query() {
     let options = SearchOptions {
         path: temp_path,
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "hybrid",
         frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
-        exact: false,
-        no_merge: false,
         merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Print the temp_path for debugging
@@ -208,20 +200,12 @@ fn test_excluded_term_query() {
     let options = SearchOptions {
         path: temp_path,
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "hybrid",
         frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
-        exact: false,
-        no_merge: false,
         merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Print the query for debugging
@@ -300,18 +284,11 @@ fn test_or_query() {
         queries: &queries,
         files_only: true, // Use files_only to ensure we find all matching files
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "hybrid",
         frequency_search: true, // Enable frequency search to improve matching
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
-        exact: false,
-        no_merge: false,
         merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Print the test files for debugging
@@ -396,20 +373,12 @@ fn test_complex_query_or() {
     let options = SearchOptions {
         path: temp_path,
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "hybrid",
         frequency_search: true, // Enable frequency search to improve matching
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
-        exact: false,
-        no_merge: false,
         merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Print the test files for debugging
@@ -492,20 +461,12 @@ fn test_complex_query_exclusion() {
     let options = SearchOptions {
         path: temp_path,
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "hybrid",
         frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
-        exact: false,
-        no_merge: false,
         merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Print the query for debugging
@@ -589,20 +550,12 @@ fn test_function() {
     let options = SearchOptions {
         path: temp_path,
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "hybrid",
         frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
-        exact: false,
-        no_merge: false,
         merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Run the search
@@ -0,0 +1,123 @@
+use probe::models::{EmbeddedLanguage, SearchResult};
+use probe::search::embedded::{classify, find_embedded_strings};
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, query: &str, embedded: bool) -> Vec<SearchResult> {
+    let queries = vec![query.to_string()];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        exclude_filenames: true,
+        no_merge: true,
+        embedded,
+        ..Default::default()
+    };
+    let mut results = perform_probe(&options).unwrap().results;
+    results.sort_by_key(|r| r.lines);
+    results
+}
+
+const REPOSITORY: &str = r#"pub fn recent_users(conn: &Connection) -> Result<Vec<User>> {
+    let mut stmt = conn.prepare(
+        "SELECT users.id, users.name
+         FROM users
+         LEFT JOIN orders ON orders.user_id = users.id
+         WHERE users.created_at > ?1",
+    )?;
+    let rows = stmt.query_map([since()], User::from_row)?;
+    rows.collect()
+}
+
+pub fn user_card(id: u32, name: &str) -> String {
+    format!(
+        "<div class=\"user-card\">
+  <span data-user-id=\"{}\">{}</span>
+</div>",
+        id, name
+    )
+}
+"#;
+
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("repository.rs"), REPOSITORY).unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_classify_embedded_strings() {
+    assert_eq!(
+        classify("\"SELECT id FROM users WHERE id = ?\""),
+        Some(EmbeddedLanguage::Sql)
+    );
+    assert_eq!(
+        classify("'insert into audit_log (event) values (?)'"),
+        Some(EmbeddedLanguage::Sql)
+    );
+    assert_eq!(
+        classify("`<ul class=\"items\"><li>${item}</li></ul>`"),
+        Some(EmbeddedLanguage::Html)
+    );
+
+    // Short strings and prose are left alone
+    assert_eq!(classify("\"<b>hi</b>\""), None);
+    assert_eq!(classify("\"Please select a file from the list\""), None);
+    assert_eq!(classify("\"Vec<u8> is not a tag\""), None);
+}
+
+#[test]
+fn test_find_embedded_strings_in_syntax_tree() {
+    let strings = find_embedded_strings(REPOSITORY, "rs");
+    let found: Vec<_> = strings.iter().map(|s| (s.language, s.lines)).collect();
+    assert_eq!(
+        found,
+        vec![
+            (EmbeddedLanguage::Sql, (3, 6)),
+            (EmbeddedLanguage::Html, (14, 16))
+        ]
+    );
+    assert!(strings[0].text.starts_with("\"SELECT users.id"));
+
+    assert!(find_embedded_strings(REPOSITORY, "unknown").is_empty());
+}
+
+#[test]
+fn test_embedded_phrases_are_reported_within_their_block() {
+    let temp_dir = create_project();
+
+    // Phrases inside string literals only match with --embedded
+    assert!(search(temp_dir.path(), "\"LEFT JOIN\"", false).is_empty());
+
+    let results = search(temp_dir.path(), "\"LEFT JOIN\"", true);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].lines, (3, 6));
+    assert_eq!(results[0].node_type, "embedded_sql");
+    assert!(results[0].code.contains("LEFT JOIN orders"));
+    let embedded = results[0].embedded.as_ref().unwrap();
+    assert_eq!(embedded.language, EmbeddedLanguage::Sql);
+    assert_eq!(embedded.host_lines, (1, 10));
+    assert_eq!(embedded.host_node_type, "function_item");
+
+    let results = search(temp_dir.path(), "\"data-user-id\"", true);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].lines, (14, 16));
+    assert_eq!(results[0].node_type, "embedded_html");
+}
+
+#[test]
+fn test_enclosing_block_is_kept_when_it_matches_outside_the_string() {
+    let temp_dir = create_project();
+
+    let results = search(temp_dir.path(), "\"users.created_at\" OR rows", true);
+    let found: Vec<_> = results
+        .iter()
+        .map(|r| (r.lines, r.node_type.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![((1, 10), "function_item"), ((3, 6), "embedded_sql")]
+    );
+}
@@ -0,0 +1,156 @@
+use probe::search::{EngineOptions, ProbeEngine, Session};
+use std::fs;
+use tempfile::TempDir;
+
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("alpha.rs"),
+        "fn alpha_handler() {\n    println!(\"alpha needle\");\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("beta.rs"),
+        "fn beta_handler() {\n    println!(\"beta needle\");\n}\n",
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_engine_is_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<ProbeEngine>();
+}
+
+#[test]
+fn test_engine_searches_concurrently() {
+    let project = create_project();
+    let engine = ProbeEngine::new(project.path()).unwrap();
+    assert_eq!(engine.file_count(), 2);
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = ["alpha", "beta", "needle"]
+            .into_iter()
+            .map(|query| {
+                let engine = &engine;
+                scope.spawn(move || engine.search(query).unwrap().results.len())
+            })
+            .collect();
+        let counts: Vec<usize> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(counts, vec![1, 1, 2]);
+    });
+}
+
+#[test]
+fn test_engine_reuses_its_file_list_until_refreshed() {
+    let project = create_project();
+    let mut engine = ProbeEngine::with_options(
+        project.path(),
+        EngineOptions {
+            max_results: Some(10),
+            ..EngineOptions::default()
+        },
+    )
+    .unwrap();
+
+    fs::write(
+        project.path().join("gamma.rs"),
+        "fn gamma_handler() {\n    println!(\"gamma needle\");\n}\n",
+    )
+    .unwrap();
+    assert!(engine.search("gamma").unwrap().results.is_empty());
+
+    engine.refresh().unwrap();
+    assert_eq!(engine.file_count(), 3);
+    assert_eq!(engine.search("gamma").unwrap().results.len(), 1);
+}
+
+#[test]
+fn test_engine_search_with_session() {
+    let project = create_project();
+    let engine = ProbeEngine::new(project.path()).unwrap();
+    let mut session = Session::new("engine");
+
+    assert_eq!(
+        engine
+            .search_with_session("needle", &mut session)
+            .unwrap()
+            .results
+            .len(),
+        2
+    );
+    assert!(engine
+        .search_with_session("needle", &mut session)
+        .unwrap()
+        .results
+        .is_empty());
+    // Searches without the session are unaffected
+    assert_eq!(engine.search("needle").unwrap().results.len(), 2);
+}
+
+#[test]
+fn test_engine_searches_files_in_memory() {
+    let mut files = std::collections::BTreeMap::new();
+    files.insert(
+        "buffers/alpha.rs",
+        "use std::io;\n\nfn alpha_handler() {\n    println!(\"alpha needle\");\n}\n",
+    );
+    files.insert(
+        "buffers/beta.rs",
+        "fn beta_handler() {\n    println!(\"beta needle\");\n}\n",
+    );
+    files.insert(
+        "buffers/alpha_test.rs",
+        "fn test_alpha() {\n    println!(\"needle\");\n}\n",
+    );
+    files.insert(
+        "node_modules/dep/index.js",
+        "function dep() { return 'needle'; }\n",
+    );
+
+    let mut engine = ProbeEngine::from_memory(files, EngineOptions::default());
+    assert_eq!(engine.file_count(), 2);
+    engine.refresh().unwrap();
+    assert_eq!(engine.file_count(), 2);
+
+    let results = engine.search("alpha needle").unwrap().results;
+    assert!(!results.is_empty());
+    assert_eq!(results[0].file, "buffers/alpha.rs");
+    assert!(results[0].code.starts_with("fn alpha_handler() {"));
+    assert!(!std::path::Path::new("buffers/alpha.rs").exists());
+
+    let mut files: Vec<String> = engine
+        .search("needle")
+        .unwrap()
+        .results
+        .into_iter()
+        .map(|result| result.file)
+        .collect();
+    files.sort();
+    assert_eq!(files, vec!["buffers/alpha.rs", "buffers/beta.rs"]);
+}
+
+#[test]
+fn test_memory_and_disk_engines_search_side_by_side() {
+    let project = create_project();
+    let disk = ProbeEngine::new(project.path()).unwrap();
+    let memory = ProbeEngine::from_memory(
+        [(
+            "gamma.rs",
+            "fn gamma_handler() {\n    println!(\"gamma needle\");\n}\n",
+        )],
+        EngineOptions::default(),
+    );
+
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| assert_eq!(disk.search("needle").unwrap().results.len(), 2));
+            scope.spawn(|| {
+                let results = memory.search("needle").unwrap().results;
+                assert_eq!(results.len(), 1);
+                assert_eq!(results[0].file, "gamma.rs");
+            });
+        }
+    });
+}
@@ -0,0 +1,134 @@
+use probe::error_trace::{trace_error_type, ErrorRole, ErrorTraceOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn create_test_files(dir: &Path) {
+    fs::write(
+        dir.join("lib.rs"),
+        r#"use std::io;
+
+pub enum ConfigError {
+    NotFound,
+    Io(io::Error),
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+pub fn load(path: &str) -> Result<String, ConfigError> {
+    let text = std::fs::read_to_string(path)?;
+    if text.is_empty() {
+        return Err(ConfigError::NotFound);
+    }
+    Ok(text)
+}
+
+pub fn run() {
+    match load("a") {
+        Err(ConfigError::NotFound) => println!("missing"),
+        _ => {}
+    }
+}
+
+pub fn unrelated() -> usize {
+    42
+}
+"#,
+    )
+    .unwrap();
+
+    fs::write(
+        dir.join("errors.go"),
+        r#"package config
+
+type ParseError struct {
+	Line int
+}
+
+func parse() error {
+	return &ParseError{Line: 1}
+}
+
+func handle(err error) {
+	switch err.(type) {
+	case *ParseError:
+		return
+	}
+}
+"#,
+    )
+    .unwrap();
+}
+
+fn trace(dir: &Path, type_name: &str) -> Vec<(ErrorRole, usize, usize)> {
+    let ignore: Vec<String> = vec![];
+    let options = ErrorTraceOptions {
+        path: dir,
+        type_name,
+        ignore: &ignore,
+        allow_tests: false,
+        max_results: None,
+    };
+
+    trace_error_type(&options)
+        .unwrap()
+        .into_iter()
+        .map(|site| (site.role, site.line_start, site.line_end))
+        .collect()
+}
+
+#[test]
+fn test_rust_error_roles() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let sites = trace(temp_dir.path(), "ConfigError");
+    assert_eq!(
+        sites,
+        vec![
+            (ErrorRole::Definition, 3, 6),
+            // `ConfigError::Io(e)` inside `from` and `ConfigError::NotFound` in `load`
+            (ErrorRole::Construction, 9, 11),
+            (ErrorRole::Construction, 14, 20),
+            (ErrorRole::Conversion, 8, 12),
+            // `load` returns the error and uses `?`
+            (ErrorRole::Propagation, 14, 20),
+            (ErrorRole::Match, 22, 27),
+        ]
+    );
+}
+
+#[test]
+fn test_go_error_roles() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let sites = trace(temp_dir.path(), "ParseError");
+    assert_eq!(
+        sites,
+        vec![
+            (ErrorRole::Definition, 3, 5),
+            (ErrorRole::Construction, 7, 9),
+            (ErrorRole::Match, 11, 16),
+        ]
+    );
+}
+
+#[test]
+fn test_invalid_type_name() {
+    let temp_dir = TempDir::new().unwrap();
+    let ignore: Vec<String> = vec![];
+    let options = ErrorTraceOptions {
+        path: temp_dir.path(),
+        type_name: "Config Error",
+        ignore: &ignore,
+        allow_tests: false,
+        max_results: None,
+    };
+
+    assert!(trace_error_type(&options).is_err());
+}
@@ -0,0 +1,89 @@
+use probe::eval::{load_dataset, run_eval, Expected};
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+fn create_project() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(
+        dir.path().join("src/config.rs"),
+        "fn parse_config(text: &str) -> Config {\n    Config::from(text)\n}\n\nfn load_settings() -> Settings {\n    Settings::default()\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("src/server.rs"),
+        "fn start_server(port: u16) {\n    listen(port);\n}\n",
+    )
+    .unwrap();
+    dir
+}
+
+#[test]
+fn test_expected_parses_files_and_blocks() {
+    assert_eq!(
+        Expected::parse("./src/config.rs").unwrap(),
+        Expected {
+            file: PathBuf::from("src/config.rs"),
+            lines: None,
+        }
+    );
+    assert_eq!(
+        Expected::parse("src/config.rs:5-7").unwrap(),
+        Expected {
+            file: PathBuf::from("src/config.rs"),
+            lines: Some((5, 7)),
+        }
+    );
+    assert!(Expected::parse("src/config.rs:7-5").is_err());
+}
+
+#[test]
+fn test_eval_scores_each_configuration() {
+    let dir = create_project();
+    let dataset = dir.path().join("dataset.json");
+    fs::write(
+        &dataset,
+        r#"{
+            "max_results": 5,
+            "configs": [{ "name": "default" }, { "name": "exact", "exact": true }],
+            "cases": [
+                { "query": "start_server", "expected": ["src/server.rs"] },
+                { "query": "load_settings", "expected": ["src/config.rs:5-7"] },
+                { "query": "parse_config", "expected": ["src/config.rs:5-7"] }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let (loaded, root) = load_dataset(&dataset).unwrap();
+    assert_eq!(root, dir.path());
+    let reports = run_eval(&loaded, &root, None).unwrap();
+    assert_eq!(reports.len(), 2);
+    assert_eq!(reports[0].name, "default");
+    assert_eq!(reports[1].name, "exact");
+
+    let cases = &reports[0].cases;
+    assert_eq!(cases[0].recall, 1.0);
+    assert_eq!(cases[0].reciprocal_rank, 1.0);
+    assert_eq!(cases[1].recall, 1.0);
+    // parse_config is in the same file, but not in the expected block
+    assert_eq!(cases[2].recall, 0.0);
+    assert_eq!(cases[2].reciprocal_rank, 0.0);
+    assert_eq!(cases[2].missed, vec!["src/config.rs:5-7".to_string()]);
+
+    assert!((reports[0].recall() - 2.0 / 3.0).abs() < 1e-9);
+    assert!((reports[0].mrr() - 2.0 / 3.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_eval_dataset_errors() {
+    let dir = create_project();
+    let dataset = dir.path().join("dataset.json");
+    fs::write(&dataset, r#"{ "cases": [] }"#).unwrap();
+    let (loaded, root) = load_dataset(&dataset).unwrap();
+    assert!(run_eval(&loaded, &root, None).is_err());
+
+    fs::write(&dataset, r#"{ "cases": [{ "query": "x" }] }"#).unwrap();
+    assert!(load_dataset(&dataset).is_err());
+}
@@ -146,6 +146,7 @@ fn test_format_and_print_extraction_results() {
     // Create a simple search result
     let result = probe::models::SearchResult {
         file: "test_file.rs".to_string(),
+        file_path: None,
         lines: (1, 5),
         node_type: "function".to_string(),
         code: "fn test() {\n    println!(\"Hello\");\n}".to_string(),
@@ -168,6 +169,20 @@ fn test_format_and_print_extraction_results() {
         block_id: None,
         matched_keywords: None,
         tokenized_content: None,
+        merged_from: None,
+        normalized_score: None,
+        usage_kind: None,
+        via_symlink: false,
+        changed_lines: None,
+        symbol_name: None,
+        language: None,
+        extraction_confidence: None,
+        partial_parse: false,
+        deprecated: false,
+        embedded: None,
+        kind: None,
+        complexity: None,
+        content_hash: None,
     };
 
     // Test different formats
@@ -204,7 +219,9 @@ fn test() {
     let project_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
 
     // Run the extract command with JSON format
+    let history_dir = tempfile::tempdir().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--manifest-path",
@@ -378,7 +395,9 @@ fn test() {
     let project_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
 
     // Run the extract command with XML format
+    let history_dir = tempfile::tempdir().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--manifest-path",
@@ -608,13 +627,17 @@ struct Point {
     println!("Project directory: {:?}", project_dir);
 
     // Run the extract command using cargo run from the project directory
+    let history_dir = tempfile::tempdir().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--manifest-path",
             project_dir.join("Cargo.toml").to_string_lossy().as_ref(),
             "--",
             "extract",
+            "--format",
+            "color",
             file_path.to_string_lossy().as_ref(),
             "--allow-tests", // Add this flag to ensure test files are included
         ])
@@ -646,13 +669,17 @@ struct Point {
     let project_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
 
     // Run with a line number
+    let history_dir = tempfile::tempdir().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--manifest-path",
             project_dir.join("Cargo.toml").to_string_lossy().as_ref(),
             "--",
             "extract",
+            "--format",
+            "color",
             &format!("{}:3", file_path.to_string_lossy()),
             "--allow-tests", // Add this flag to ensure test files are included
         ])
@@ -673,7 +700,9 @@ struct Point {
     let project_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
 
     // Run with a different format
+    let history_dir = tempfile::tempdir().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--manifest-path",
@@ -700,13 +729,17 @@ struct Point {
     let project_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
 
     // Run with a line range
+    let history_dir = tempfile::tempdir().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--manifest-path",
             project_dir.join("Cargo.toml").to_string_lossy().as_ref(),
             "--",
             "extract",
+            "--format",
+            "color",
             &format!("{}:2-7", file_path.to_string_lossy()),
             "--allow-tests", // Add this flag to ensure test files are included
         ])
@@ -750,7 +783,9 @@ fn main() {
     let project_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
 
     // Run the extract command with JSON format
+    let history_dir = tempfile::tempdir().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--manifest-path",
@@ -836,7 +871,9 @@ fn main() {
     let project_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
 
     // Run with a line number and JSON format
+    let history_dir = tempfile::tempdir().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--manifest-path",
@@ -1075,13 +1112,17 @@ index cb2cb64..3717769 100644
     let project_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
 
     // Run the extract command with diff option
+    let history_dir = tempfile::tempdir().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--manifest-path",
             project_dir.join("Cargo.toml").to_string_lossy().as_ref(),
             "--",
             "extract",
+            "--format",
+            "color",
             "--diff",
             diff_path.to_string_lossy().as_ref(),
             "--allow-tests", // Add this flag to ensure test files are included
@@ -1142,13 +1183,17 @@ index cb2cb64..3717769 100644
     let project_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
 
     // Run the extract command WITHOUT the diff flag - it should auto-detect
+    let history_dir = tempfile::tempdir().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--manifest-path",
             project_dir.join("Cargo.toml").to_string_lossy().as_ref(),
             "--",
             "extract",
+            "--format",
+            "color",
             diff_path.to_string_lossy().as_ref(),
             "--allow-tests", // Add this flag to ensure test files are included
         ])
@@ -1193,7 +1238,9 @@ fn main() {
     let project_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
 
     // Run the extract command with XML format
+    let history_dir = tempfile::tempdir().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--manifest-path",
@@ -1288,7 +1335,9 @@ fn main() {
     let project_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
 
     // Run with a line number and XML format
+    let history_dir = tempfile::tempdir().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--manifest-path",
@@ -1460,13 +1509,17 @@ fn test_tokenize_with_stemming() {
     let project_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
 
     // Run the extract command with the diff containing multiple files
+    let history_dir = tempfile::tempdir().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--manifest-path",
             project_dir.join("Cargo.toml").to_string_lossy().as_ref(),
             "--",
             "extract",
+            "--format",
+            "color",
             diff_path.to_string_lossy().as_ref(),
             "--allow-tests", // Add this flag to ensure test files are included
         ])
@@ -0,0 +1,152 @@
+use probe::extract::extract_line_changes_from_git_diff;
+use probe::models::LineChange;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .expect("Failed to run git");
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+fn run_extract(dir: &Path, args: &[&str]) -> Output {
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+    let history_dir = TempDir::new().unwrap();
+    Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
+        .args(["run", "--manifest-path", manifest.to_str().unwrap(), "--"])
+        .arg("extract")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("Failed to execute command")
+}
+
+/// A repository with two committed files, one function of which is changed afterwards
+fn create_repo() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+
+    fs::write(
+        dir.join("math.rs"),
+        "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\nfn sub(a: i32, b: i32) -> i32 {\n    a - b\n}\n",
+    )
+    .unwrap();
+    fs::write(dir.join("other.rs"), "fn untouched() {}\n").unwrap();
+
+    git(dir, &["init", "-q"]);
+    git(dir, &["config", "user.name", "Test User"]);
+    git(dir, &["config", "user.email", "test@example.com"]);
+    git(dir, &["add", "."]);
+    git(dir, &["commit", "-q", "-m", "Initial commit"]);
+
+    fs::write(
+        dir.join("math.rs"),
+        "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\nfn sub(a: i32, b: i32) -> i32 {\n    a.saturating_sub(b)\n}\n",
+    )
+    .unwrap();
+
+    temp_dir
+}
+
+#[test]
+fn test_diff_base_extracts_changed_blocks() {
+    let repo = create_repo();
+
+    let output = run_extract(repo.path(), &["--diff-base", "HEAD", "--format", "json"]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let json_start = stdout.find('{').expect("JSON output");
+    let json: serde_json::Value = serde_json::from_str(&stdout[json_start..]).unwrap();
+    let results = json["results"].as_array().unwrap();
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["file"], "math.rs");
+    let code = results[0]["code"].as_str().unwrap();
+    assert!(code.contains("fn sub"));
+    assert!(!code.contains("fn add"));
+}
+
+#[test]
+fn test_diff_base_limited_to_paths() {
+    let repo = create_repo();
+
+    let output = run_extract(repo.path(), &["--diff-base", "HEAD", "other.rs"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No changes against HEAD."));
+}
+
+#[test]
+fn test_diff_base_unknown_revision_fails() {
+    let repo = create_repo();
+
+    let output = run_extract(repo.path(), &["--diff-base", "no-such-branch"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("git diff against 'no-such-branch' failed"));
+}
+
+#[test]
+fn test_line_changes_from_diff() {
+    let diff = "diff --git a/lib.rs b/lib.rs\n\
+                --- a/lib.rs\n\
+                +++ b/lib.rs\n\
+                @@ -1,4 +1,5 @@\n \
+                fn f() {\n\
+                -    old();\n\
+                +    new();\n\
+                +    extra();\n \
+                }\n\
+                +// trailing\n";
+
+    let changes = extract_line_changes_from_git_diff(diff);
+    let lines: Vec<(usize, LineChange)> = changes[&PathBuf::from("lib.rs")]
+        .iter()
+        .map(|(&line, &kind)| (line, kind))
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            (2, LineChange::Modified),
+            (3, LineChange::Added),
+            (5, LineChange::Added),
+        ]
+    );
+}
+
+#[test]
+fn test_diff_base_reports_changed_lines() {
+    let repo = create_repo();
+    fs::write(
+        repo.path().join("math.rs"),
+        "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\nfn sub(a: i32, b: i32) -> i32 {\n    // saturating\n    a.saturating_sub(b)\n}\n",
+    )
+    .unwrap();
+
+    let output = run_extract(repo.path(), &["--diff-base", "HEAD", "--format", "json"]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let json_start = stdout.find('{').expect("JSON output");
+    let json: serde_json::Value = serde_json::from_str(&stdout[json_start..]).unwrap();
+    let changed = json["results"][0]["changed_lines"].as_array().unwrap();
+
+    assert_eq!(changed.len(), 2);
+    assert_eq!(changed[0]["line"], 6);
+    assert_eq!(changed[0]["kind"], "modified");
+    assert_eq!(changed[1]["line"], 7);
+    assert_eq!(changed[1]["kind"], "added");
+
+    // The terminal format shows the same lines in a gutter
+    let output = run_extract(repo.path(), &["--diff-base", "HEAD", "--format", "color"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("~     // saturating"));
+    assert!(stdout.contains("+     a.saturating_sub(b)"));
+    assert!(stdout.contains("  fn sub(a: i32, b: i32) -> i32 {"));
+}
@@ -0,0 +1,102 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+fn create_test_files(dir: &Path) {
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("src/lib.rs"),
+        r#"pub fn parse(input: &str) -> u32 {
+    let n = count(input);
+    n + helper()
+}
+
+fn count(input: &str) -> u32 {
+    input.len() as u32
+}
+
+fn helper() -> u32 {
+    1
+}
+"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.join("src/main.rs"),
+        r#"fn main() {
+    let value = demo::parse("abc");
+    println!("{}", value);
+}
+"#,
+    )
+    .unwrap();
+}
+
+fn run_extract(dir: &Path, args: &[&str]) -> serde_json::Value {
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+    let history_dir = TempDir::new().unwrap();
+    let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
+        .args(["run", "--manifest-path", manifest.to_str().unwrap(), "--"])
+        .arg("extract")
+        .args(args)
+        .args(["--format", "json"])
+        .current_dir(dir)
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+fn names_and_locations(symbols: &serde_json::Value) -> Vec<String> {
+    symbols
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|symbol| format!("{} {}:{}", symbol["name"], symbol["file"], symbol["line"]))
+        .collect()
+}
+
+#[test]
+fn test_suggest_related_lists_siblings_callers_and_callees() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let json = run_extract(temp_dir.path(), &["src/lib.rs#parse", "--suggest-related"]);
+    let related = json["related"].as_array().unwrap();
+    assert_eq!(related.len(), 1);
+    assert_eq!(related[0]["symbol"], "parse");
+    assert_eq!(
+        names_and_locations(&related[0]["siblings"]),
+        vec!["\"count\" \"src/lib.rs\":6", "\"helper\" \"src/lib.rs\":10"]
+    );
+    assert_eq!(
+        names_and_locations(&related[0]["callers"]),
+        vec!["\"main\" \"src/main.rs\":1"]
+    );
+    assert_eq!(
+        names_and_locations(&related[0]["callees"]),
+        vec!["\"count\" \"src/lib.rs\":6", "\"helper\" \"src/lib.rs\":10"]
+    );
+}
+
+#[test]
+fn test_related_code_is_only_listed_on_request() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let json = run_extract(temp_dir.path(), &["src/lib.rs:7"]);
+    assert!(json.get("related").is_none());
+
+    // A helper's caller is the function that calls it, and nothing is listed
+    // as called from a block that calls nothing
+    let json = run_extract(temp_dir.path(), &["src/lib.rs:7", "--suggest-related"]);
+    let related = &json["related"][0];
+    assert_eq!(related["symbol"], "count");
+    assert_eq!(
+        names_and_locations(&related["callers"]),
+        vec!["\"parse\" \"src/lib.rs\":1"]
+    );
+    assert!(related["callees"].as_array().unwrap().is_empty());
+}
@@ -0,0 +1,114 @@
+use probe::extract::{parse_spec, LineSpec};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_parse_yaml_spec() {
+    let spec = parse_spec(
+        "# Context bundle for the parser change\n\
+         context: 2\n\
+         entries:\n  \
+           - file: src/main.rs\n    \
+             symbol: main\n  \
+           - file: \"src/lib.rs\"\n    \
+             lines: 10-40\n    \
+             context: 0\n  \
+           - { file: src/util.rs, lines: [5, 9], allow_tests: true }\n  \
+           - src/other.rs:12\n",
+    )
+    .unwrap();
+
+    assert_eq!(spec.context, Some(2));
+    assert_eq!(spec.entries.len(), 4);
+    assert_eq!(spec.entries[0].file, "src/main.rs");
+    assert_eq!(spec.entries[0].symbol.as_deref(), Some("main"));
+    assert!(matches!(&spec.entries[1].lines, Some(LineSpec::Range(r)) if r == "10-40"));
+    assert_eq!(spec.entries[1].context, Some(0));
+    assert!(matches!(
+        spec.entries[2].lines,
+        Some(LineSpec::Pair([5, 9]))
+    ));
+    assert_eq!(spec.entries[2].allow_tests, Some(true));
+    assert_eq!(spec.entries[3].file, "src/other.rs:12");
+}
+
+#[test]
+fn test_parse_spec_list_and_json() {
+    let spec = parse_spec("- a.rs\n- file: b.rs\n  lines: 3\n").unwrap();
+    assert_eq!(spec.entries.len(), 2);
+    assert!(matches!(spec.entries[1].lines, Some(LineSpec::Single(3))));
+
+    let spec = parse_spec(r#"{"entries": [{"file": "a.rs", "symbol": "run"}]}"#).unwrap();
+    assert_eq!(spec.entries[0].symbol.as_deref(), Some("run"));
+}
+
+#[test]
+fn test_parse_spec_rejects_unknown_keys() {
+    let err = parse_spec("entries:\n  - file: a.rs\n    symbols: main\n").unwrap_err();
+    assert!(format!("{:#}", err).contains("symbols"));
+}
+
+#[test]
+fn test_extract_spec_ordered_output() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("first.rs"),
+        "fn alpha() {\n    println!(\"alpha\");\n}\n\nfn beta() {\n    println!(\"beta\");\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("notes.txt"),
+        "one\ntwo\nthree\nfour\nfive\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("extract.yaml"),
+        "entries:\n  \
+           - file: notes.txt\n    \
+             lines: 3\n    \
+             context: 1\n  \
+           - file: first.rs\n    \
+             symbol: beta\n  \
+           - file: missing.rs\n",
+    )
+    .unwrap();
+
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+    let history_dir = TempDir::new().unwrap();
+    let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
+        .args([
+            "run",
+            "--manifest-path",
+            manifest.to_str().unwrap(),
+            "--",
+            "extract",
+            "--spec",
+            "extract.yaml",
+            "--format",
+            "json",
+        ])
+        .current_dir(temp_dir.path())
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let json_start = stdout.find('{').expect("JSON output");
+    let json: serde_json::Value = serde_json::from_str(&stdout[json_start..]).unwrap();
+    let results = json["results"].as_array().unwrap();
+
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["file"], "notes.txt");
+    // The per-entry context widens the single line on both sides
+    assert_eq!(results[0]["code"], "two\nthree\nfour");
+    assert_eq!(results[1]["file"], "first.rs");
+    assert!(results[1]["code"].as_str().unwrap().contains("fn beta"));
+    assert!(!results[1]["code"].as_str().unwrap().contains("fn alpha"));
+
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("No files match spec entry: missing.rs")
+    );
+}
@@ -0,0 +1,101 @@
+use probe::extract::parse_file_list;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+type Spec = (PathBuf, Option<usize>, Option<usize>, Option<String>);
+
+#[test]
+fn test_parse_file_list_lines_and_specs() {
+    let list = "src/main.rs\n\nsrc/lib.rs:10\r\nsrc/lib.rs:1-5\nsrc/main.rs\nsrc/util.rs#helper\n";
+    let paths = parse_file_list(list, true);
+
+    let specs: Vec<Spec> = paths
+        .into_iter()
+        .map(|(path, start, end, symbol, _)| (path, start, end, symbol))
+        .collect();
+    assert_eq!(
+        specs,
+        vec![
+            (PathBuf::from("src/main.rs"), None, None, None),
+            (PathBuf::from("src/lib.rs"), Some(10), None, None),
+            (PathBuf::from("src/lib.rs"), Some(1), Some(5), None),
+            (
+                PathBuf::from("src/util.rs"),
+                None,
+                None,
+                Some("helper".to_string())
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_file_list_nul_separated() {
+    let paths = parse_file_list("a file.rs\0b.rs\0a file.rs\0", true);
+    let files: Vec<PathBuf> = paths.into_iter().map(|(path, ..)| path).collect();
+    assert_eq!(
+        files,
+        vec![PathBuf::from("a file.rs"), PathBuf::from("b.rs")]
+    );
+}
+
+#[test]
+fn test_extract_reads_file_list_from_stdin() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("first.rs"),
+        "fn first() {\n    println!(\"first\");\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("second.rs"),
+        "fn second() {\n    println!(\"second\");\n}\n",
+    )
+    .unwrap();
+
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+    let history_dir = TempDir::new().unwrap();
+    let mut child = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
+        .args([
+            "run",
+            "--manifest-path",
+            manifest.to_str().unwrap(),
+            "--",
+            "extract",
+            "-",
+            "--format",
+            "json",
+        ])
+        .current_dir(temp_dir.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute command");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"first.rs\nsecond.rs\nfirst.rs\ndeleted.rs\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let json_start = stdout.find('{').expect("JSON output");
+    let json: serde_json::Value = serde_json::from_str(&stdout[json_start..]).unwrap();
+
+    let files: Vec<&str> = json["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["file"].as_str().unwrap())
+        .collect();
+    assert_eq!(files, vec!["first.rs", "second.rs"]);
+    assert!(stderr.contains("File not found: deleted.rs"));
+}
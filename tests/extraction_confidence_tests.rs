@@ -0,0 +1,82 @@
+use probe::models::ExtractionConfidence;
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, query: &str) -> Vec<probe::models::SearchResult> {
+    let queries = vec![query.to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        ..Default::default()
+    };
+    perform_probe(&options).unwrap().results
+}
+
+#[test]
+fn test_extraction_confidence() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("ledger.rs"),
+        "pub fn settle_ledger(total: u64) -> u64 {\n    total * 2\n}\n",
+    )
+    .unwrap();
+    let notes: Vec<String> = (1..=40)
+        .map(|line| {
+            if line == 20 {
+                "reconcile the quarterly statements".to_string()
+            } else {
+                format!("note {}", line)
+            }
+        })
+        .collect();
+    fs::write(temp_dir.path().join("notes.txt"), notes.join("\n")).unwrap();
+    fs::write(temp_dir.path().join("todo.txt"), "archive old invoices\n").unwrap();
+
+    let confidence = |query: &str| -> Vec<Option<ExtractionConfidence>> {
+        search(temp_dir.path(), query)
+            .iter()
+            .map(|r| r.extraction_confidence)
+            .collect()
+    };
+
+    assert_eq!(
+        confidence("settle_ledger"),
+        vec![Some(ExtractionConfidence::Ast)]
+    );
+    assert_eq!(
+        confidence("quarterly"),
+        vec![Some(ExtractionConfidence::Heuristic)]
+    );
+    assert_eq!(
+        confidence("invoices"),
+        vec![Some(ExtractionConfidence::WholeFile)]
+    );
+}
+
+#[test]
+fn test_partial_parse_flag() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("ledger.rs"),
+        "pub fn settle_ledger(total: u64) -> u64 {\n    let doubled = total * (2;\n    doubled\n}\n\npub fn audit_ledger() -> bool {\n    true\n}\n",
+    )
+    .unwrap();
+
+    let results = search(temp_dir.path(), "settle_ledger");
+    assert_eq!(results.len(), 1);
+    assert!(results[0].partial_parse);
+    assert_eq!(
+        results[0].extraction_confidence,
+        Some(ExtractionConfidence::Ast)
+    );
+
+    let results = search(temp_dir.path(), "audit_ledger");
+    assert_eq!(results.len(), 1);
+    assert!(!results[0].partial_parse);
+}
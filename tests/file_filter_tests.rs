@@ -0,0 +1,143 @@
+use probe::models::SkipReason;
+use probe::search::file_list_cache::{parse_age, parse_size};
+use probe::search::{perform_probe, SearchOptions};
+use std::fs::{self, File};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Default)]
+struct Filters {
+    newer_than: Option<Duration>,
+    older_than: Option<Duration>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+}
+
+fn search(path: &Path, filters: Filters) -> (Vec<String>, Vec<(String, SkipReason)>) {
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        report_skipped: true,
+        newer_than: filters.newer_than,
+        older_than: filters.older_than,
+        min_size: filters.min_size,
+        max_size: filters.max_size,
+        ..Default::default()
+    };
+
+    let limited = perform_probe(&options).unwrap();
+    let name = |file: &Path| file.file_name().unwrap().to_string_lossy().to_string();
+    let mut files: Vec<String> = limited
+        .results
+        .iter()
+        .map(|r| name(Path::new(&r.file)))
+        .collect();
+    files.sort();
+    let skipped = limited
+        .skip_report
+        .unwrap_or_default()
+        .into_iter()
+        .map(|s| (name(&s.path), s.reason))
+        .collect();
+    (files, skipped)
+}
+
+/// `recent.rs` (small, modified now) and `stale.rs` (large, modified 30 days ago)
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("recent.rs"),
+        "fn recent() {\n    let needle = 1;\n}\n",
+    )
+    .unwrap();
+
+    let padding = "// padding\n".repeat(200);
+    let stale = temp_dir.path().join("stale.rs");
+    fs::write(
+        &stale,
+        format!("fn stale() {{\n    let needle = 2;\n}}\n{}", padding),
+    )
+    .unwrap();
+    File::options()
+        .write(true)
+        .open(&stale)
+        .unwrap()
+        .set_modified(SystemTime::now() - 30 * DAY)
+        .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_modified_time_filters() {
+    let project = create_project();
+
+    let (files, skipped) = search(
+        project.path(),
+        Filters {
+            newer_than: Some(7 * DAY),
+            ..Filters::default()
+        },
+    );
+    assert_eq!(files, vec!["recent.rs"]);
+    assert_eq!(
+        skipped,
+        vec![("stale.rs".to_string(), SkipReason::Modified)]
+    );
+
+    let (files, _) = search(
+        project.path(),
+        Filters {
+            older_than: Some(7 * DAY),
+            ..Filters::default()
+        },
+    );
+    assert_eq!(files, vec!["stale.rs"]);
+}
+
+#[test]
+fn test_size_filters() {
+    let project = create_project();
+
+    let (files, skipped) = search(
+        project.path(),
+        Filters {
+            max_size: Some(1024),
+            ..Filters::default()
+        },
+    );
+    assert_eq!(files, vec!["recent.rs"]);
+    assert_eq!(skipped, vec![("stale.rs".to_string(), SkipReason::Size)]);
+
+    let (files, _) = search(
+        project.path(),
+        Filters {
+            min_size: Some(1024),
+            ..Filters::default()
+        },
+    );
+    assert_eq!(files, vec!["stale.rs"]);
+}
+
+#[test]
+fn test_parse_age_and_size() {
+    assert_eq!(parse_age("90").unwrap(), Duration::from_secs(90));
+    assert_eq!(parse_age("30m").unwrap(), Duration::from_secs(30 * 60));
+    assert_eq!(parse_age("7d").unwrap(), 7 * DAY);
+    assert_eq!(parse_age("2w").unwrap(), 14 * DAY);
+    assert!(parse_age("7y").is_err());
+    assert!(parse_age("d").is_err());
+
+    assert_eq!(parse_size("500").unwrap(), 500);
+    assert_eq!(parse_size("10k").unwrap(), 10 * 1024);
+    assert_eq!(parse_size("2MB").unwrap(), 2 * 1024 * 1024);
+    assert_eq!(parse_size("1G").unwrap(), 1024 * 1024 * 1024);
+    assert!(parse_size("1x").is_err());
+    assert!(parse_size("k").is_err());
+}
@@ -0,0 +1,125 @@
+use probe::search::filename_qualifier::split_filename_qualifiers;
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn search(path: &Path, query: &str, filename_weight: f64) -> Vec<probe::models::SearchResult> {
+    let queries = vec![query.to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        no_merge: true,
+        filename_weight,
+        ..Default::default()
+    };
+    perform_probe(&options).unwrap().results
+}
+
+fn files(results: &[probe::models::SearchResult]) -> Vec<String> {
+    let mut files: Vec<String> = results
+        .iter()
+        .map(|r| {
+            PathBuf::from(&r.file)
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    for (name, body) in [
+        (
+            "config.rs",
+            "fn load() -> u64 {\n    let timeout = 30;\n    timeout\n}\n",
+        ),
+        (
+            "server.rs",
+            "fn serve() {\n    let timeout = 5;\n    wait(timeout);\n}\n",
+        ),
+        (
+            "server_test.js",
+            "function check() {\n    const timeout = 1;\n}\n",
+        ),
+    ] {
+        fs::write(temp_dir.path().join(name), body).unwrap();
+    }
+    temp_dir
+}
+
+#[test]
+fn test_split_filename_qualifiers() {
+    let queries = vec!["filename:Config -filename:*_test.js timeout".to_string()];
+    let (filters, remaining) = split_filename_qualifiers(&queries);
+    assert_eq!(remaining, vec!["timeout".to_string()]);
+    assert_eq!(filters.len(), 2);
+    assert_eq!(filters[0].value, "config");
+    assert!(!filters[0].excluded && !filters[0].is_glob());
+    assert!(filters[1].excluded && filters[1].is_glob());
+    assert!(filters[1].matches_name(Path::new("src/server_test.js")));
+
+    let (filters, remaining) = split_filename_qualifiers(&["filename:server".to_string()]);
+    assert_eq!(filters.len(), 1);
+    assert!(remaining.is_empty());
+}
+
+#[test]
+fn test_filename_qualifier_narrows_search() {
+    let project = create_project();
+
+    let results = search(project.path(), "filename:config timeout", 1.0);
+    assert_eq!(files(&results), vec!["config.rs"]);
+
+    let results = search(project.path(), "-filename:config timeout", 1.0);
+    assert_eq!(files(&results), vec!["server.rs", "server_test.js"]);
+
+    let results = search(
+        project.path(),
+        "filename:*.rs -filename:server timeout",
+        1.0,
+    );
+    assert_eq!(files(&results), vec!["config.rs"]);
+
+    // Without other terms the files matching by name are returned
+    let results = search(project.path(), "filename:server", 1.0);
+    assert_eq!(files(&results), vec!["server.rs", "server_test.js"]);
+}
+
+#[test]
+fn test_filename_weight() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("backoff.rs"),
+        "fn wait() {\n    sleep(1);\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("client.rs"),
+        "fn send() {\n    backoff(request);\n}\n",
+    )
+    .unwrap();
+
+    // With weight 0, a match in the file name alone adds nothing to the score
+    let results = search(temp_dir.path(), "backoff", 0.0);
+    let by_name = results
+        .iter()
+        .find(|r| r.file.ends_with("backoff.rs"))
+        .unwrap();
+    assert_eq!(by_name.score, Some(0.0));
+    assert!(results[0].file.ends_with("client.rs"));
+
+    let results = search(temp_dir.path(), "backoff", 1.0);
+    let by_name = results
+        .iter()
+        .find(|r| r.file.ends_with("backoff.rs"))
+        .unwrap();
+    assert!(by_name.score.unwrap() > 0.0);
+}
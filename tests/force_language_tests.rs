@@ -0,0 +1,101 @@
+use probe::language::detection::{detect_language, parse_language_overrides, LanguageOverrides};
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const TEMPLATE: &str = r#"<?php
+
+function render_invoice_header($invoice)
+{
+    $title = htmlspecialchars($invoice->title);
+    return "<h1>" . $title . "</h1>";
+}
+
+function render_invoice_footer($invoice)
+{
+    return "<footer>" . $invoice->total . "</footer>";
+}
+"#;
+
+fn search(path: &Path, force_language: &[String]) -> Vec<probe::models::SearchResult> {
+    let queries = vec!["htmlspecialchars".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        force_language,
+        ..Default::default()
+    };
+    perform_probe(&options).unwrap().results
+}
+
+#[test]
+fn test_detect_language() {
+    let none = LanguageOverrides::new();
+    assert_eq!(
+        detect_language(Path::new("src/lib.rs"), "", &none),
+        Some("rust")
+    );
+    assert_eq!(
+        detect_language(Path::new("Dockerfile"), "", &none),
+        Some("dockerfile")
+    );
+    assert_eq!(
+        detect_language(Path::new("vec.h"), "int len(void);", &none),
+        Some("c")
+    );
+    assert_eq!(
+        detect_language(Path::new("vec.h"), "namespace util {\nclass Vec;\n}", &none),
+        Some("cpp")
+    );
+    assert_eq!(
+        detect_language(
+            Path::new("bin/deploy"),
+            "#!/usr/bin/env python3\nimport sys",
+            &none
+        ),
+        Some("python")
+    );
+    assert_eq!(detect_language(Path::new("notes.inc"), "", &none), None);
+
+    let overrides =
+        parse_language_overrides(&["inc=php".to_string(), ".TPP=cpp".to_string()]).unwrap();
+    assert_eq!(
+        detect_language(Path::new("notes.inc"), "", &overrides),
+        Some("php")
+    );
+    assert_eq!(
+        detect_language(Path::new("vec.tpp"), "", &overrides),
+        Some("cpp")
+    );
+}
+
+#[test]
+fn test_parse_language_overrides_rejects_bad_mappings() {
+    assert!(parse_language_overrides(&["inc".to_string()]).is_err());
+    assert!(parse_language_overrides(&["=php".to_string()]).is_err());
+    let error = parse_language_overrides(&["inc=cobol".to_string()]).unwrap_err();
+    assert!(error.to_string().contains("Unknown language 'cobol'"));
+}
+
+#[test]
+fn test_force_language_parses_unknown_extension() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("invoice.inc"), TEMPLATE).unwrap();
+
+    // Without a mapping the file is searched as plain text
+    let results = search(temp_dir.path(), &[]);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].language, None);
+    assert_ne!(results[0].node_type, "function_definition");
+
+    let results = search(temp_dir.path(), &["inc=php".to_string()]);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].language.as_deref(), Some("php"));
+    assert_eq!(results[0].node_type, "function_definition");
+    assert_eq!(results[0].lines, (3, 7));
+}
@@ -0,0 +1,62 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+fn run_probe(dir: &Path, args: &[&str]) -> Output {
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+    let history_dir = TempDir::new().unwrap();
+    Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
+        .args(["run", "--manifest-path", manifest.to_str().unwrap(), "--"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("Failed to execute command")
+}
+
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("lib.rs"),
+        "fn parse_config() -> u32 {\n    42\n}\n",
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_piped_output_defaults_to_json() {
+    let project = create_project();
+    let output = run_probe(project.path(), &["search", "parse_config", "."]);
+    assert!(output.status.success());
+
+    // Captured stdout is not a terminal, so the default format is JSON
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let json_start = stdout.find('{').expect("JSON output");
+    let json: serde_json::Value = serde_json::from_str(&stdout[json_start..]).unwrap();
+    assert!(json["results"][0]["code"]
+        .as_str()
+        .unwrap()
+        .contains("fn parse_config"));
+}
+
+#[test]
+fn test_explicit_format_wins_when_piped() {
+    let project = create_project();
+    let output = run_probe(
+        project.path(),
+        &["search", "parse_config", ".", "--format", "plain"],
+    );
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("fn parse_config"));
+    assert!(!stdout.contains("\"results\""));
+
+    // Subcommands resolve the default the same way
+    let output = run_probe(project.path(), &["extract", "lib.rs:1"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let json_start = stdout.find('{').expect("JSON output");
+    assert!(serde_json::from_str::<serde_json::Value>(&stdout[json_start..]).is_ok());
+}
@@ -0,0 +1,312 @@
+use probe::extract::process_file_for_extraction;
+use probe::fs_access::{
+    self, FileSystem, FileText, MemoryFs, OverlayFs, ReadOnlyFs, READ_ONLY_ENV,
+};
+use std::fs;
+use std::io::{ErrorKind, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+fn run_probe(dir: &Path, home: &Path, args: &[&str]) -> Output {
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+    Command::new("cargo")
+        .args(["run", "--manifest-path", manifest.to_str().unwrap(), "--"])
+        .args(args)
+        .current_dir(dir)
+        .env("HOME", home)
+        .env_remove("PROBE_HISTORY_DIR")
+        .env_remove(READ_ONLY_ENV)
+        .output()
+        .expect("Failed to execute command")
+}
+
+#[test]
+fn test_read_only_fs_refuses_writes_outside_the_cache() {
+    let cache = Path::new("/home/user/.cache/probe");
+    let file_system = ReadOnlyFs::new(
+        MemoryFs::new().with_file("/repo/lib.rs", "fn main() {}\n"),
+        vec![cache.to_path_buf()],
+    );
+
+    assert_eq!(
+        file_system
+            .read_to_string(Path::new("/repo/lib.rs"))
+            .unwrap(),
+        "fn main() {}\n"
+    );
+    file_system
+        .write(&cache.join("sessions/a.json"), b"{}")
+        .unwrap();
+    file_system
+        .append(&cache.join("history/repo.jsonl"), b"{}\n")
+        .unwrap();
+
+    let err = file_system
+        .write(Path::new("/repo/lib.rs"), b"")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    let err = file_system
+        .rename(&cache.join("sessions/a.json"), Path::new("/repo/a.json"))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    assert!(file_system
+        .create_dir_all(&cache.join("../../escape"))
+        .is_err());
+    assert!(file_system.check_write(Path::new("/tmp/out.json")).is_err());
+
+    assert_eq!(
+        file_system.refused_writes(),
+        vec![
+            PathBuf::from("/repo/lib.rs"),
+            PathBuf::from("/repo/a.json"),
+            cache.join("../../escape"),
+            PathBuf::from("/tmp/out.json"),
+        ]
+    );
+    assert_eq!(
+        file_system
+            .read_to_string(Path::new("/repo/lib.rs"))
+            .unwrap(),
+        "fn main() {}\n"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_read_only_fs_resolves_symlinks_out_of_the_cache() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = temp_dir.path().join("cache");
+    let outside = temp_dir.path().join("repo");
+    fs::create_dir(&cache).unwrap();
+    fs::create_dir(&outside).unwrap();
+    std::os::unix::fs::symlink(&outside, cache.join("link")).unwrap();
+    let file_system = ReadOnlyFs::new(fs_access::RealFs, vec![cache.clone()]);
+
+    file_system
+        .write(&cache.join("sessions.json"), b"{}")
+        .unwrap();
+    // The path starts with the cache directory, but the link in it leads out
+    let err = file_system
+        .write(&cache.join("link/lib.rs"), b"")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    assert!(!outside.join("lib.rs").exists());
+    assert!(file_system
+        .create_dir_all(&cache.join("new/../../escape"))
+        .is_err());
+}
+
+#[test]
+fn test_memory_fs_files_and_directories() {
+    let file_system = MemoryFs::new().with_file("src/lib.rs", "pub fn a() {}\n");
+
+    assert!(file_system.exists(Path::new("src")));
+    assert!(file_system.exists(Path::new("src/lib.rs")));
+    assert!(!file_system.exists(Path::new("src/main.rs")));
+    assert!(file_system.is_file(Path::new("./src/lib.rs")));
+    assert!(!file_system.is_file(Path::new("src")));
+    assert_eq!(
+        file_system.read_dir(Path::new("src")).unwrap(),
+        vec![PathBuf::from("src/lib.rs")]
+    );
+    assert!(file_system.read_dir(Path::new("tests")).is_err());
+
+    file_system.append(Path::new("log.txt"), b"one\n").unwrap();
+    file_system.append(Path::new("log.txt"), b"two\n").unwrap();
+    file_system
+        .rename(Path::new("log.txt"), Path::new("old.txt"))
+        .unwrap();
+    assert_eq!(
+        file_system.read_to_string(Path::new("old.txt")).unwrap(),
+        "one\ntwo\n"
+    );
+    file_system.remove_file(Path::new("old.txt")).unwrap();
+    assert_eq!(
+        file_system
+            .read_to_string(Path::new("old.txt"))
+            .unwrap_err()
+            .kind(),
+        ErrorKind::NotFound
+    );
+}
+
+#[test]
+fn test_extract_reads_an_in_memory_fixture() {
+    fs_access::install(Arc::new(MemoryFs::new().with_file(
+        "virtual/config.rs",
+        "fn parse_config() -> u32 {\n    42\n}\n",
+    )));
+    let result = process_file_for_extraction(
+        Path::new("virtual/config.rs"),
+        Some(2),
+        None,
+        None,
+        false,
+        0,
+        None,
+    );
+    fs_access::install(Arc::new(fs_access::RealFs));
+
+    let result = result.unwrap();
+    assert!(result.code.contains("fn parse_config"));
+    assert!(!Path::new("virtual/config.rs").exists());
+}
+
+#[test]
+fn test_read_only_flag_keeps_output_files_from_being_written() {
+    let temp_dir = TempDir::new().unwrap();
+    let home = temp_dir.path().join("home");
+    fs::create_dir(&home).unwrap();
+    fs::write(
+        temp_dir.path().join("lib.rs"),
+        "fn parse_config() -> u32 {\n    42\n}\n",
+    )
+    .unwrap();
+
+    let output = run_probe(
+        temp_dir.path(),
+        &home,
+        &[
+            "search",
+            "parse_config",
+            ".",
+            "--read-only",
+            "--output",
+            "results.json",
+        ],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Read-only mode"));
+    assert!(!temp_dir.path().join("results.json").exists());
+
+    // Searching still records the query history in the cache directory
+    let output = run_probe(
+        temp_dir.path(),
+        &home,
+        &["search", "parse_config", ".", "--read-only"],
+    );
+    assert!(output.status.success());
+    assert!(home.join(".cache/probe/history").is_dir());
+}
+
+#[test]
+fn test_overlay_reads_buffers_before_the_inner_file_system() {
+    let buffers = fs_access::parse_overlay(
+        r#"{"./src/lib.rs": "fn unsaved() {}\n", "src/new.rs": "fn draft() {}\n"}"#,
+    )
+    .unwrap();
+    let file_system = OverlayFs::new(
+        MemoryFs::new()
+            .with_file("src/lib.rs", "fn saved() {}\n")
+            .with_file("src/main.rs", "fn main() {}\n"),
+        buffers,
+    );
+
+    assert_eq!(
+        file_system.read_to_string(Path::new("src/lib.rs")).unwrap(),
+        "fn unsaved() {}\n"
+    );
+    assert_eq!(
+        file_system
+            .read_to_string(Path::new("src/../src/new.rs"))
+            .unwrap(),
+        "fn draft() {}\n"
+    );
+    assert_eq!(
+        file_system
+            .read_to_string(Path::new("src/main.rs"))
+            .unwrap(),
+        "fn main() {}\n"
+    );
+    assert!(file_system.exists(Path::new("src/new.rs")));
+
+    assert!(fs_access::parse_overlay(r#"{"src/lib.rs": 1}"#).is_err());
+    assert!(fs_access::parse_overlay("missing-overlay.json").is_err());
+}
+
+#[test]
+fn test_overlay_flag_searches_unsaved_content() {
+    let temp_dir = TempDir::new().unwrap();
+    let home = temp_dir.path().join("home");
+    fs::create_dir(&home).unwrap();
+    fs::write(
+        temp_dir.path().join("lib.rs"),
+        "fn parse_config() -> u32 {\n    42\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("overlay.json"),
+        r#"{"lib.rs": "fn parse_config() -> u32 {\n    unsaved_helper()\n}\n"}"#,
+    )
+    .unwrap();
+
+    let output = run_probe(
+        temp_dir.path(),
+        &home,
+        &[
+            "search",
+            "unsaved_helper",
+            ".",
+            "--overlay",
+            "overlay.json",
+            "--format",
+            "json",
+        ],
+    );
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("unsaved_helper()"));
+
+    // Without the overlay the saved file is searched
+    let output = run_probe(
+        temp_dir.path(),
+        &home,
+        &["search", "unsaved_helper", ".", "--format", "json"],
+    );
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("unsaved_helper()"));
+}
+
+fn lines(text: &FileText) -> Vec<&str> {
+    text.lines().collect::<std::io::Result<_>>().unwrap()
+}
+
+#[test]
+fn test_read_text_maps_large_files_on_disk() {
+    let temp_dir = TempDir::new().unwrap();
+    let small = temp_dir.path().join("small.rs");
+    let large = temp_dir.path().join("large.rs");
+    let binary = temp_dir.path().join("large.bin");
+    fs::write(&small, "fn small() {}\n").unwrap();
+    let line = "fn large() {}\n";
+    let text = line.repeat(fs_access::MMAP_THRESHOLD as usize / line.len() + 1);
+    fs::write(&large, &text).unwrap();
+    let mut bytes = text.clone().into_bytes();
+    bytes[10] = 0xff;
+    fs::write(&binary, bytes).unwrap();
+
+    let read = fs_access::read_text(&small).unwrap();
+    assert!(matches!(read, FileText::Read(_)));
+    assert_eq!(lines(&read), ["fn small() {}"]);
+
+    let mapped = fs_access::read_text(&large).unwrap();
+    assert!(matches!(mapped, FileText::Mapped(_)));
+    assert_eq!(lines(&mapped), text.lines().collect::<Vec<_>>());
+
+    // Bytes that change under the map after it was checked are caught as lines are read
+    let mut file = fs::OpenOptions::new().write(true).open(&large).unwrap();
+    file.seek(SeekFrom::Start(10)).unwrap();
+    file.write_all(&[0xff]).unwrap();
+    let first = mapped.lines().next().unwrap();
+    assert_eq!(first.err().unwrap().kind(), ErrorKind::InvalidData);
+
+    let error = fs_access::read_text(&binary).err().unwrap();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+    // Files held elsewhere are read
+    let memory = Arc::new(MemoryFs::new().with_file(&large, "fn in_memory() {}\n"));
+    let text = fs_access::with_file_system(memory, || fs_access::read_text(&large).unwrap());
+    assert!(matches!(text, FileText::Read(_)));
+    assert_eq!(lines(&text), ["fn in_memory() {}"]);
+}
@@ -0,0 +1,101 @@
+use probe::language::graphql::find_definitions;
+use probe::language::text_blocks::TextBlock;
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const SCHEMA: &str = r#"# Accounts service schema
+scalar DateTime
+
+"""
+A registered account
+"""
+type Account implements Node {
+  id: ID!
+  email: String!
+  createdAt: DateTime
+}
+
+interface Node {
+  id: ID!
+}
+
+union SearchResult = Account | Invoice
+
+extend type Query {
+  account(id: ID!): Account
+}
+
+query AccountEmail($id: ID!) {
+  account(id: $id) {
+    email
+  }
+}
+"#;
+
+fn definition(
+    start_line: usize,
+    end_line: usize,
+    kind: &'static str,
+    name: Option<&str>,
+) -> TextBlock {
+    TextBlock {
+        start_line,
+        end_line,
+        kind,
+        name: name.map(str::to_string),
+    }
+}
+
+#[test]
+fn test_find_definitions() {
+    assert_eq!(
+        find_definitions(SCHEMA),
+        vec![
+            definition(2, 2, "scalar_type_definition", Some("DateTime")),
+            definition(4, 11, "object_type_definition", Some("Account")),
+            definition(13, 15, "interface_type_definition", Some("Node")),
+            definition(17, 17, "union_type_definition", Some("SearchResult")),
+            definition(19, 21, "type_system_extension", Some("Query")),
+            definition(23, 27, "operation_definition", Some("AccountEmail")),
+        ]
+    );
+
+    assert_eq!(
+        find_definitions("{\n  viewer { id }\n}\n"),
+        vec![definition(1, 3, "operation_definition", None)]
+    );
+}
+
+#[test]
+fn test_graphql_search_returns_definitions() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("schema.graphql"), SCHEMA).unwrap();
+
+    let queries = vec!["email".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path: temp_dir.path(),
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        ..Default::default()
+    };
+    let mut results = perform_probe(&options).unwrap().results;
+    results.sort_by_key(|r| r.lines.0);
+
+    let blocks: Vec<((usize, usize), &str, Option<&str>)> = results
+        .iter()
+        .map(|r| (r.lines, r.node_type.as_str(), r.symbol_name.as_deref()))
+        .collect();
+    assert_eq!(
+        blocks,
+        vec![
+            ((4, 11), "object_type_definition", Some("Account")),
+            ((23, 27), "operation_definition", Some("AccountEmail")),
+        ]
+    );
+    assert!(Path::new(&results[0].file).ends_with("schema.graphql"));
+}
@@ -0,0 +1,159 @@
+use probe::hierarchy::{build_hierarchy, HierarchyNode, HierarchyOptions, Relation};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn create_test_files(dir: &Path) {
+    fs::write(
+        dir.join("shapes.rs"),
+        r#"pub trait Shape {
+    fn area(&self) -> f64;
+}
+
+pub trait Polygon: Shape {
+    fn sides(&self) -> usize;
+}
+
+pub struct Square(f64);
+
+impl Polygon for Square {
+    fn sides(&self) -> usize {
+        4
+    }
+}
+
+impl Shape for Square {
+    fn area(&self) -> f64 {
+        self.0 * self.0
+    }
+}
+"#,
+    )
+    .unwrap();
+
+    fs::write(
+        dir.join("Animals.java"),
+        r#"public class Animal {
+}
+
+class Dog extends Animal implements Comparable<Dog> {
+    public int compareTo(Dog other) {
+        return 0;
+    }
+}
+
+class Puppy extends Dog {
+}
+"#,
+    )
+    .unwrap();
+
+    fs::write(
+        dir.join("io.go"),
+        r#"package io
+
+type Reader interface {
+	Read(p []byte) (int, error)
+}
+
+type ReadCloser interface {
+	Reader
+	Close() error
+}
+
+type File struct{}
+
+func (f *File) Read(p []byte) (int, error) { return 0, nil }
+"#,
+    )
+    .unwrap();
+}
+
+fn hierarchy(dir: &Path, type_name: &str, max_depth: Option<usize>) -> probe::hierarchy::Hierarchy {
+    build_hierarchy(&HierarchyOptions {
+        path: dir,
+        type_name,
+        ignore: &[],
+        allow_tests: false,
+        max_depth,
+    })
+    .unwrap()
+}
+
+fn summary(nodes: &[HierarchyNode]) -> Vec<(String, Relation, usize)> {
+    nodes
+        .iter()
+        .map(|node| (node.name.clone(), node.relation, node.children.len()))
+        .collect()
+}
+
+#[test]
+fn test_hierarchy_follows_relationships_in_both_directions() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let shape = hierarchy(temp_dir.path(), "Shape", None);
+    assert_eq!(shape.definition.as_ref().unwrap().line_start, 1);
+    assert!(shape.supertypes.is_empty());
+    assert_eq!(
+        summary(&shape.subtypes),
+        vec![
+            ("Polygon".to_string(), Relation::Extends, 1),
+            ("Square".to_string(), Relation::Impl, 0),
+        ]
+    );
+    let via_polygon = &shape.subtypes[0].children[0];
+    assert_eq!(via_polygon.name, "Square");
+    assert_eq!(via_polygon.location.as_ref().unwrap().line_start, 11);
+
+    let square = hierarchy(temp_dir.path(), "Square", None);
+    assert!(square.subtypes.is_empty());
+    assert_eq!(
+        summary(&square.supertypes),
+        vec![
+            ("Polygon".to_string(), Relation::Impl, 1),
+            ("Shape".to_string(), Relation::Impl, 0),
+        ]
+    );
+    // Supertypes point at their declaration
+    let polygon = square.supertypes[0].location.as_ref().unwrap();
+    assert_eq!((polygon.line_start, polygon.line_end), (5, 7));
+
+    let dog = hierarchy(temp_dir.path(), "Dog", None);
+    assert_eq!(
+        summary(&dog.supertypes),
+        vec![
+            ("Animal".to_string(), Relation::Extends, 0),
+            ("Comparable".to_string(), Relation::Extends, 0),
+        ]
+    );
+    assert!(dog.supertypes[1].location.is_none());
+    assert_eq!(
+        summary(&dog.subtypes),
+        vec![("Puppy".to_string(), Relation::Extends, 0)]
+    );
+}
+
+#[test]
+fn test_hierarchy_go_embedding_method_sets_and_depth() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let reader = hierarchy(temp_dir.path(), "io.Reader", None);
+    assert_eq!(reader.name, "Reader");
+    assert_eq!(
+        summary(&reader.subtypes),
+        vec![
+            ("File".to_string(), Relation::MethodSet, 0),
+            ("ReadCloser".to_string(), Relation::Embeds, 0),
+        ]
+    );
+
+    let animal = hierarchy(temp_dir.path(), "Animal", Some(1));
+    assert_eq!(
+        summary(&animal.subtypes),
+        vec![("Dog".to_string(), Relation::Extends, 0)]
+    );
+    let animal = hierarchy(temp_dir.path(), "Animal", None);
+    assert_eq!(animal.subtypes[0].children[0].name, "Puppy");
+}
@@ -0,0 +1,101 @@
+use probe::history::{block_history, parse_target, HistoryOptions};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .expect("Failed to run git");
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+/// `add` is written in one commit, moved below `other` and changed in a second
+fn create_repo() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let math = dir.join("src/math.rs");
+    fs::create_dir_all(math.parent().unwrap()).unwrap();
+
+    git(dir, &["init", "-q"]);
+    git(dir, &["config", "user.name", "Test User"]);
+    git(dir, &["config", "user.email", "test@example.com"]);
+
+    fs::write(
+        &math,
+        "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\nfn other() {}\n",
+    )
+    .unwrap();
+    git(dir, &["add", "."]);
+    git(dir, &["commit", "-q", "-m", "Add add"]);
+
+    fs::write(
+        &math,
+        "fn other() {}\n\nfn add(a: i32, b: i32) -> i32 {\n    a.wrapping_add(b)\n}\n",
+    )
+    .unwrap();
+    git(dir, &["commit", "-q", "-am", "Use wrapping add"]);
+
+    // Touches only `other`, so it is not part of the history of `add`
+    fs::write(
+        &math,
+        "fn other() -> bool {\n    true\n}\n\nfn add(a: i32, b: i32) -> i32 {\n    a.wrapping_add(b)\n}\n",
+    )
+    .unwrap();
+    git(dir, &["commit", "-q", "-am", "Change other"]);
+
+    temp_dir
+}
+
+#[test]
+fn test_history_lists_commits_touching_the_block() {
+    let repo = create_repo();
+    let target = format!("{}#add", repo.path().join("src/math.rs").display());
+
+    let history = block_history(&HistoryOptions {
+        target: &target,
+        max_commits: None,
+        versions: false,
+    })
+    .unwrap();
+
+    assert_eq!((history.line_start, history.line_end), (5, 7));
+    let summaries: Vec<&str> = history.commits.iter().map(|c| c.summary.as_str()).collect();
+    assert_eq!(summaries, vec!["Use wrapping add", "Add add"]);
+    assert!(history.commits[0].diff.contains("+    a.wrapping_add(b)"));
+    assert!(history.commits[0].before.is_none());
+}
+
+#[test]
+fn test_history_extracts_versions() {
+    let repo = create_repo();
+    let target = format!("{}#add", repo.path().join("src/math.rs").display());
+
+    let history = block_history(&HistoryOptions {
+        target: &target,
+        max_commits: Some(1),
+        versions: true,
+    })
+    .unwrap();
+
+    assert_eq!(history.commits.len(), 1);
+    let commit = &history.commits[0];
+    assert!(commit.before.as_deref().unwrap().contains("a + b"));
+    assert!(commit
+        .after
+        .as_deref()
+        .unwrap()
+        .contains("a.wrapping_add(b)"));
+}
+
+#[test]
+fn test_parse_target_requires_symbol() {
+    let (file, symbol) = parse_target("src/main.rs#main").unwrap();
+    assert_eq!(file, Path::new("src/main.rs"));
+    assert_eq!(symbol, "main");
+    assert!(parse_target("src/main.rs").is_err());
+    assert!(parse_target("src/main.rs#").is_err());
+}
@@ -0,0 +1,137 @@
+use probe::impls::{find_implementations, ImplKind, ImplsOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn create_test_files(dir: &Path) {
+    fs::write(
+        dir.join("shapes.rs"),
+        r#"use std::fmt;
+
+pub trait Shape {
+    fn area(&self) -> f64;
+}
+
+pub struct Circle(f64);
+
+impl Shape for Circle {
+    fn area(&self) -> f64 {
+        3.14 * self.0 * self.0
+    }
+}
+
+impl<T: Copy> crate::Shape for Grid<T> {
+    fn area(&self) -> f64 {
+        0.0
+    }
+}
+
+impl fmt::Display for Circle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "circle")
+    }
+}
+"#,
+    )
+    .unwrap();
+
+    fs::write(
+        dir.join("Square.java"),
+        r#"public class Square extends Base implements Shape, Comparable<Shape> {
+    public double area() {
+        return 4.0;
+    }
+}
+
+class Ordering implements Comparable<Shape> {
+}
+"#,
+    )
+    .unwrap();
+
+    fs::write(
+        dir.join("triangle.ts"),
+        r#"export class Triangle implements Shape {
+  area(): number {
+    return 1;
+  }
+}
+"#,
+    )
+    .unwrap();
+
+    fs::write(
+        dir.join("shapes.go"),
+        r#"package shapes
+
+type Shape interface {
+	Area() float64
+	Name() string
+}
+
+type Rect struct {
+	W, H float64
+}
+
+func (r *Rect) Area() float64 { return r.W * r.H }
+
+func (r Rect) Name() string { return "rect" }
+
+type Dot struct{}
+
+func (d Dot) Area() float64 { return 0 }
+"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_find_implementations_across_languages() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let options = ImplsOptions {
+        path: temp_dir.path(),
+        trait_name: "Shape",
+        ignore: &[],
+        allow_tests: false,
+        max_results: None,
+    };
+    let sites = find_implementations(&options).unwrap();
+    let found: Vec<(&str, ImplKind)> = sites
+        .iter()
+        .map(|site| (site.implementor.as_str(), site.kind))
+        .collect();
+
+    assert!(found.contains(&("Circle", ImplKind::Impl)));
+    assert!(found.contains(&("Grid<T>", ImplKind::Impl)));
+    assert!(found.contains(&("Square", ImplKind::Declaration)));
+    assert!(found.contains(&("Triangle", ImplKind::Declaration)));
+    // Rect has both methods of the interface, Dot only one
+    assert!(found.contains(&("Rect", ImplKind::MethodSet)));
+    assert_eq!(found.len(), 5, "{:?}", found);
+
+    let rect = sites
+        .iter()
+        .find(|site| site.implementor == "Rect")
+        .unwrap();
+    assert!(rect.code.starts_with("type Rect struct"));
+}
+
+#[test]
+fn test_find_implementations_of_qualified_trait() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let options = ImplsOptions {
+        path: temp_dir.path(),
+        trait_name: "std::fmt::Display",
+        ignore: &[],
+        allow_tests: false,
+        max_results: None,
+    };
+    let sites = find_implementations(&options).unwrap();
+    assert_eq!(sites.len(), 1);
+    assert_eq!(sites[0].implementor, "Circle");
+    assert_eq!(sites[0].line_start, 21);
+}
@@ -0,0 +1,117 @@
+use probe::search::index::{confined_to_tokens, IndexUpdate, SearchIndex, INDEX_DIR, INDEX_FILE};
+use probe::search::ProbeEngine;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn create_project() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(
+        dir.path().join("src/alpha.rs"),
+        "fn alpha_handler() {\n    find_needle();\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("src/beta.rs"),
+        "fn beta_handler() {\n    find_haystack();\n}\n",
+    )
+    .unwrap();
+    dir
+}
+
+fn result_files(root: &Path, query: &str) -> Vec<String> {
+    let results = ProbeEngine::new(root).unwrap().search(query).unwrap();
+    let mut files: Vec<String> = results
+        .results
+        .iter()
+        .map(|r| {
+            Path::new(&r.file)
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+#[test]
+fn test_patterns_confined_to_tokens() {
+    assert!(confined_to_tokens("(?i)(needle|haystack)"));
+    assert!(confined_to_tokens(r"\bv?1\.2\b"));
+    assert!(confined_to_tokens(
+        r"(?-i:\b[pP][a-z0-9]*(?:C|_[cC])[a-z0-9]*\b)"
+    ));
+    assert!(!confined_to_tokens("find needle"));
+    assert!(!confined_to_tokens(r"find\s+needle"));
+    assert!(!confined_to_tokens("find.needle"));
+    assert!(!confined_to_tokens("[^a]"));
+    assert!(!confined_to_tokens("x*"));
+}
+
+#[test]
+fn test_index_build_update_and_save() {
+    let dir = create_project();
+    let (index, report) = SearchIndex::build(dir.path()).unwrap();
+    assert_eq!(index.file_count(), 2);
+    assert_eq!(
+        report,
+        IndexUpdate {
+            updated: 2,
+            removed: 0
+        }
+    );
+
+    let saved = index.save(dir.path()).unwrap();
+    assert_eq!(saved, dir.path().join(INDEX_DIR).join(INDEX_FILE));
+    let loaded = SearchIndex::load(dir.path()).unwrap().unwrap();
+    assert_eq!(loaded.file_count(), 2);
+    assert_eq!(loaded.token_count(), index.token_count());
+
+    // Unchanged files are not read again
+    fs::write(dir.path().join("src/gamma.rs"), "fn gamma() {}\n").unwrap();
+    fs::remove_file(dir.path().join("src/beta.rs")).unwrap();
+    let (updated, report) = loaded.update(dir.path()).unwrap();
+    assert_eq!(updated.file_count(), 2);
+    assert_eq!(
+        report,
+        IndexUpdate {
+            updated: 1,
+            removed: 1
+        }
+    );
+
+    assert!(SearchIndex::load(&dir.path().join("src"))
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_search_uses_index_and_scans_changed_files() {
+    let dir = create_project();
+    let before = result_files(dir.path(), "needle OR haystack");
+    let (index, _) = SearchIndex::build(dir.path()).unwrap();
+    index.save(dir.path()).unwrap();
+
+    // The same results, with the index leaving files unread
+    assert_eq!(result_files(dir.path(), "needle OR haystack"), before);
+    assert_eq!(result_files(dir.path(), "needle"), vec!["alpha.rs"]);
+    assert_eq!(
+        result_files(&dir.path().join("src"), "needle"),
+        vec!["alpha.rs"]
+    );
+
+    // A file changed since indexing is read, even though its indexed tokens don't match
+    fs::write(
+        dir.path().join("src/beta.rs"),
+        "fn beta_handler() {\n    find_needle_too();\n}\n",
+    )
+    .unwrap();
+    assert_eq!(
+        result_files(dir.path(), "needle"),
+        vec!["alpha.rs", "beta.rs"]
+    );
+}
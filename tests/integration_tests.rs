@@ -136,21 +136,13 @@ fn test_search_single_term() {
     let options = SearchOptions {
         path: temp_dir.path(),
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
         exclude_filenames: true,
         reranker: "hybrid",
         frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: false,
 
-        exact: false,
         no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Search for a single term
@@ -199,21 +191,13 @@ fn test_search_multiple_terms() {
     let options = SearchOptions {
         path: temp_dir.path(),
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
         exclude_filenames: true,
         reranker: "hybrid",
         frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: false,
 
-        exact: false,
         no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Search for multiple terms
@@ -249,16 +233,9 @@ fn test_search_files_only() {
         exclude_filenames: true,
         reranker: "hybrid",
         frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: false,
 
-        exact: false,
         no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Search for files only
@@ -318,21 +295,12 @@ fn test_search_include_filenames() {
     let options = SearchOptions {
         path: temp_dir.path(),
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "hybrid",
         frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: false,
 
-        exact: false,
         no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Search with filename matching enabled
@@ -373,21 +341,14 @@ fn test_search_with_limits() {
     let options = SearchOptions {
         path: temp_dir.path(),
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
         exclude_filenames: true,
         reranker: "hybrid",
         frequency_search: false,
         max_results: Some(2), // limit to 2 results
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: false,
 
-        exact: false,
         no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Search with limits
@@ -422,21 +383,12 @@ fn test_frequency_search() {
     let options = SearchOptions {
         path: temp_dir.path(),
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
         exclude_filenames: true,
         reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: false,
 
-        exact: false,
         no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Search using frequency-based search
@@ -478,21 +430,13 @@ func main() {
     let options = SearchOptions {
         path: temp_dir.path(),
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
         exclude_filenames: false, // filename matching is enabled by default
         reranker: "hybrid",
         frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: false,
         // using "all terms" mode
-        exact: false,
         no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Search for both terms in "all terms" mode
@@ -518,21 +462,13 @@ fn test_search_with_custom_ignores() {
     let options = SearchOptions {
         path: temp_dir.path(),
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
         exclude_filenames: true,
         reranker: "hybrid",
         frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
-        allow_tests: false,
 
-        exact: false,
         no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Search with custom ignore patterns
@@ -634,21 +570,14 @@ fn calculate_product(a: i32, b: i32) -> i32 {
     let options = SearchOptions {
         path: temp_dir.path(),
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
         exclude_filenames: true,
         reranker: "combined",
         frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
 
-        exact: false,
-        no_merge: false,
         merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Perform search
@@ -0,0 +1,92 @@
+use probe::error::ProbeError;
+use probe::models::FileErrorKind;
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn options<'a>(
+    path: &'a Path,
+    queries: &'a [String],
+    custom_ignores: &'a [String],
+    strict: bool,
+) -> SearchOptions<'a> {
+    SearchOptions {
+        path,
+        queries,
+        custom_ignores,
+        exclude_filenames: true,
+        follow_symlinks: true,
+        strict,
+        ..Default::default()
+    }
+}
+
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("main.rs"),
+        "fn handler() {\n    println!(\"needle\");\n}\n",
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[cfg(unix)]
+#[test]
+fn test_dangling_link_is_reported_as_not_found() {
+    let project = create_project();
+    std::os::unix::fs::symlink(
+        project.path().join("deleted.rs"),
+        project.path().join("link.rs"),
+    )
+    .unwrap();
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+
+    let results =
+        perform_probe(&options(project.path(), &queries, &custom_ignores, false)).unwrap();
+    assert_eq!(results.results.len(), 1);
+    assert_eq!(results.errors.len(), 1);
+    assert_eq!(results.errors[0].path, project.path().join("link.rs"));
+    assert_eq!(results.errors[0].kind, FileErrorKind::NotFound);
+
+    let err = perform_probe(&options(project.path(), &queries, &custom_ignores, true)).unwrap_err();
+    assert!(matches!(err, ProbeError::Io(_)));
+    assert!(err.to_string().contains("1 file(s) could not be read"));
+    assert!(err.to_string().contains("link.rs"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_unreadable_file_is_reported_as_permission_denied() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let project = create_project();
+    let locked = project.path().join("locked.rs");
+    fs::write(&locked, "fn locked() {\n    println!(\"needle\");\n}\n").unwrap();
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+    // Privileged users can read the file anyway
+    if fs::read(&locked).is_ok() {
+        return;
+    }
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+
+    let results =
+        perform_probe(&options(project.path(), &queries, &custom_ignores, false)).unwrap();
+    assert_eq!(results.results.len(), 1);
+    assert_eq!(results.errors.len(), 1);
+    assert_eq!(results.errors[0].kind, FileErrorKind::PermissionDenied);
+}
+
+#[test]
+fn test_clean_search_has_no_errors() {
+    let project = create_project();
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+
+    let results = perform_probe(&options(project.path(), &queries, &custom_ignores, true)).unwrap();
+    assert_eq!(results.results.len(), 1);
+    assert!(results.errors.is_empty());
+}
@@ -14,21 +14,15 @@ fn test_ip_whitelist_stemming() {
     let options = SearchOptions {
         path: file_path.parent().unwrap().parent().unwrap(), // Use the tests directory
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
         exclude_filenames: true,
         reranker: "hybrid",
         frequency_search: true, // Use frequency search to get detailed term stats
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
 
         exact: false, // Important: set to false to enable stemming
         no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Enable debug mode to see the actual terms
@@ -102,21 +96,13 @@ fn test_negative_terms_exclude_files() {
     let options = SearchOptions {
         path: file_path.parent().unwrap().parent().unwrap(), // Use the tests directory
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
         exclude_filenames: true,
         reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
         // Important: set to false to require all terms
-        exact: false,
         no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Enable debug mode to see the actual terms
@@ -101,7 +101,9 @@ fn test_json_output_format_basic() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with JSON output format
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -196,7 +198,9 @@ fn test_json_output_with_special_characters() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with JSON output format, searching for special characters
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -267,7 +271,9 @@ fn test_json_output_with_multiple_terms() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with JSON output format, searching for multiple terms
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -330,7 +336,9 @@ fn test_json_output_with_no_results() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with JSON output format, searching for a term that doesn't exist
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -395,7 +403,9 @@ fn test_json_output_with_files_only() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with JSON output format and files-only option
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -434,3 +444,46 @@ fn test_json_output_with_files_only() {
         );
     }
 }
+
+#[test]
+fn test_json_output_lists_merged_blocks_with_merge_provenance() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    create_test_file(
+        &temp_dir,
+        "src/lib.rs",
+        "fn first_needle() {\n    needle();\n}\n\nfn second_needle() {\n    needle();\n}\n",
+    );
+
+    let search = |extra: &[&str]| -> Value {
+        let history_dir = TempDir::new().unwrap();
+        let output = Command::new("cargo")
+            .env("PROBE_HISTORY_DIR", history_dir.path())
+            .args(["run", "--", "search", "needle"])
+            .arg(temp_dir.path())
+            .args(["--format", "json"])
+            .args(extra)
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        serde_json::from_str(extract_json_from_output(&stdout))
+            .expect("Failed to parse JSON output")
+    };
+
+    // Both functions are merged into one result, which lists them
+    let json_result = search(&["--merge-provenance"]);
+    let results = json_result["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    let merged_from = results[0]["merged_from"].as_array().unwrap();
+    let lines: Vec<&Value> = merged_from.iter().map(|block| &block["lines"]).collect();
+    assert_eq!(
+        lines,
+        [&serde_json::json!([1, 3]), &serde_json::json!([5, 7])]
+    );
+
+    // Without the flag the merged result is the same, without the list
+    let json_result = search(&[]);
+    let results = json_result["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert!(results[0].get("merged_from").is_none());
+}
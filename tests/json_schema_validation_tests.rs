@@ -111,7 +111,9 @@ fn test_json_schema_validation_basic() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with JSON output format
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -187,7 +189,9 @@ fn test_json_schema_validation_special_characters() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with JSON output format, searching for special characters
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
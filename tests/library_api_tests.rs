@@ -0,0 +1,98 @@
+use probe::error::ProbeError;
+use probe::extract::parse_file_with_line;
+use probe::query::{perform_query, QueryOptions};
+use probe::{extract_blocks, ExtractBlocksOptions};
+use std::fs;
+use tempfile::TempDir;
+
+fn create_project() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("lib.rs"),
+        "fn first() -> u32 {\n    1\n}\n\nfn second() -> u32 {\n    first() + 1\n}\n",
+    )
+    .unwrap();
+    dir
+}
+
+#[test]
+fn test_extract_blocks_returns_blocks() {
+    let project = create_project();
+    let file = project.path().join("lib.rs").to_string_lossy().to_string();
+    let files = vec![format!("{}:6", file), format!("{}#first", file)];
+
+    let extraction = extract_blocks(&ExtractBlocksOptions {
+        files: &files,
+        context_lines: 0,
+        allow_tests: false,
+        custom_ignores: &[],
+        follow_symlinks: false,
+    })
+    .unwrap();
+
+    assert!(extraction.errors.is_empty(), "{:?}", extraction.errors);
+    let lines: Vec<(usize, usize)> = extraction.results.iter().map(|r| r.lines).collect();
+    assert_eq!(lines, vec![(5, 7), (1, 3)]);
+    assert!(extraction.results[0].code.starts_with("fn second()"));
+    assert_eq!(extraction.results[0].language.as_deref(), Some("rust"));
+}
+
+#[test]
+fn test_extract_blocks_reports_failures_per_file() {
+    let project = create_project();
+    let file = project.path().join("lib.rs").to_string_lossy().to_string();
+    let files = vec![format!("{}#missing_symbol", file), format!("{}:1", file)];
+
+    let extraction = extract_blocks(&ExtractBlocksOptions {
+        files: &files,
+        context_lines: 0,
+        allow_tests: false,
+        custom_ignores: &[],
+        follow_symlinks: false,
+    })
+    .unwrap();
+
+    assert_eq!(extraction.results.len(), 1);
+    assert_eq!(extraction.errors.len(), 1);
+}
+
+#[test]
+fn test_extract_blocks_ignores_last_for_one_call() {
+    let project = create_project();
+    let files = vec![project.path().join("lib.rs").to_string_lossy().to_string()];
+    let ignores = vec!["lib.rs".to_string()];
+    let options = |custom_ignores| ExtractBlocksOptions {
+        files: &files,
+        context_lines: 0,
+        allow_tests: false,
+        custom_ignores,
+        follow_symlinks: false,
+    };
+
+    let extraction = extract_blocks(&options(&ignores)).unwrap();
+    assert!(extraction.results.is_empty());
+    assert_eq!(extraction.errors.len(), 1);
+
+    // Resolving paths later on the same thread no longer applies them
+    assert_eq!(parse_file_with_line(&files[0], false).len(), 1);
+    let extraction = extract_blocks(&options(&[])).unwrap();
+    assert_eq!(extraction.results.len(), 1);
+}
+
+#[test]
+fn test_perform_query_returns_typed_matches() -> Result<(), ProbeError> {
+    let project = create_project();
+    let matches = perform_query(&QueryOptions {
+        path: project.path(),
+        pattern: "fn $NAME() -> u32 { $$$BODY }",
+        language: Some("rust"),
+        ignore: &[],
+        allow_tests: false,
+        max_results: None,
+        format: "json",
+    })?;
+
+    assert_eq!(matches.len(), 2);
+    assert!(matches.iter().all(|m| m.file_path.ends_with("lib.rs")));
+    Ok(())
+}
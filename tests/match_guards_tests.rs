@@ -0,0 +1,110 @@
+use probe::models::{CapReason, LimitedSearchResults, SkipReason};
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(
+    path: &Path,
+    max_line_length: usize,
+    max_matches_per_file: usize,
+    max_total_matches: Option<usize>,
+) -> LimitedSearchResults {
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        report_skipped: true,
+        max_line_length,
+        max_matches_per_file,
+        max_total_matches,
+        ..Default::default()
+    };
+    perform_probe(&options).unwrap()
+}
+
+#[test]
+fn test_max_line_length_skips_long_lines_and_reports_them() {
+    let temp_dir = TempDir::new().unwrap();
+    let long_line = format!("const DATA: &str = \"{} needle\";", "x".repeat(200));
+    fs::write(
+        temp_dir.path().join("data.rs"),
+        format!("{long_line}\n\nfn other() {{}}\n"),
+    )
+    .unwrap();
+
+    let default = search(temp_dir.path(), 2000, 10000, None);
+    assert_eq!(default.results.len(), 1);
+    assert!(default.capped.is_empty());
+
+    let guarded = search(temp_dir.path(), 100, 10000, None);
+    assert!(guarded.results.is_empty());
+    assert_eq!(guarded.capped.len(), 1);
+    assert_eq!(guarded.capped[0].reason, CapReason::LongLines(1));
+}
+
+#[test]
+fn test_max_matches_per_file_stops_scanning_the_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let functions: String = (0..20)
+        .map(|index| format!("fn needle_{index}() {{}}\n\n"))
+        .collect();
+    fs::write(temp_dir.path().join("many.rs"), functions).unwrap();
+
+    let guarded = search(temp_dir.path(), 2000, 5, None);
+    assert_eq!(guarded.results.len(), 5);
+    assert_eq!(guarded.capped.len(), 1);
+    assert_eq!(guarded.capped[0].reason, CapReason::FileMatches(5));
+}
+
+#[test]
+fn test_max_total_matches_stops_scanning_further_files() {
+    let temp_dir = TempDir::new().unwrap();
+    for index in 0..3 {
+        fs::write(
+            temp_dir.path().join(format!("file_{index}.rs")),
+            format!("fn needle_{index}() {{}}\n"),
+        )
+        .unwrap();
+    }
+
+    let guarded = search(temp_dir.path(), 2000, 10000, Some(1));
+    assert_eq!(guarded.results.len(), 1);
+    assert_eq!(guarded.capped.len(), 1);
+    assert_eq!(guarded.capped[0].reason, CapReason::TotalMatches(2));
+
+    let report = guarded.skip_report.unwrap();
+    let unsearched = report
+        .iter()
+        .filter(|skipped| skipped.reason == SkipReason::MatchLimit)
+        .count();
+    assert_eq!(unsearched, 2);
+}
+
+#[test]
+fn test_max_total_matches_is_the_same_across_parallel_batches() {
+    let temp_dir = TempDir::new().unwrap();
+    for index in 0..300 {
+        fs::write(
+            temp_dir.path().join(format!("file_{index:03}.rs")),
+            format!("fn needle_{index}() {{}}\n"),
+        )
+        .unwrap();
+    }
+
+    // Files are searched several batches at a time; the limit still cuts off at one file
+    for _ in 0..3 {
+        let guarded = search(temp_dir.path(), 2000, 10000, Some(120));
+        assert_eq!(guarded.results.len(), 120);
+        assert_eq!(guarded.capped.len(), 1);
+        assert_eq!(guarded.capped[0].reason, CapReason::TotalMatches(180));
+    }
+
+    let unguarded = search(temp_dir.path(), 2000, 10000, None);
+    assert_eq!(unguarded.results.len(), 300);
+    assert!(unguarded.capped.is_empty());
+}
@@ -0,0 +1,202 @@
+use probe::mcp::McpServer;
+use serde_json::{json, Value};
+use std::fs;
+use tempfile::TempDir;
+
+fn create_project() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("config.rs"),
+        "fn parse_port(value: &str) -> Option<u16> {\n    value.trim().parse::<u16>().ok()\n}\n\nfn default_host() -> String {\n    \"localhost\".to_string()\n}\n",
+    )
+    .unwrap();
+    dir
+}
+
+fn request(server: &mut McpServer, id: u64, method: &str, params: Value) -> Value {
+    let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+    server.handle_message(&message.to_string()).unwrap()
+}
+
+/// Call a tool, returning whether it failed and its text
+fn call_tool(server: &mut McpServer, name: &str, arguments: Value) -> (bool, String) {
+    let response = request(
+        server,
+        1,
+        "tools/call",
+        json!({ "name": name, "arguments": arguments }),
+    );
+    let result = &response["result"];
+    let text = result["content"][0]["text"].as_str().unwrap().to_string();
+    (result["isError"].as_bool().unwrap(), text)
+}
+
+#[test]
+fn test_initialize_and_list_tools() {
+    let mut server = McpServer::new();
+    let response = request(&mut server, 1, "initialize", json!({}));
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["result"]["serverInfo"]["name"], "probe");
+    assert!(response["result"]["capabilities"]["tools"].is_object());
+
+    // Notifications get no response
+    let notification = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+    assert!(server.handle_message(&notification.to_string()).is_none());
+
+    let response = request(&mut server, 2, "tools/list", json!({}));
+    let tools = response["result"]["tools"].as_array().unwrap();
+    let names: Vec<&str> = tools.iter().map(|t| t["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["search", "extract", "query"]);
+    for tool in tools {
+        assert_eq!(tool["inputSchema"]["type"], "object");
+        assert!(tool["inputSchema"]["required"].is_array());
+    }
+}
+
+#[test]
+fn test_search_extract_and_query_tools() {
+    let dir = create_project();
+    let path = dir.path().to_string_lossy().to_string();
+    let mut server = McpServer::new();
+
+    let (failed, text) = call_tool(
+        &mut server,
+        "search",
+        json!({ "path": path, "query": "parse_port" }),
+    );
+    assert!(!failed, "{}", text);
+    let output: Value = serde_json::from_str(&text).unwrap();
+    assert!(output["results"][0]["code"]
+        .as_str()
+        .unwrap()
+        .contains("fn parse_port"));
+
+    let file = dir.path().join("config.rs");
+    let (failed, text) = call_tool(
+        &mut server,
+        "extract",
+        json!({ "files": [format!("{}:6", file.display())] }),
+    );
+    assert!(!failed, "{}", text);
+    let output: Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(output["results"][0]["lines"], json!([5, 7]));
+
+    let (failed, text) = call_tool(
+        &mut server,
+        "query",
+        json!({ "path": path, "pattern": "fn default_host() -> String { $$$ }", "language": "rust" }),
+    );
+    assert!(!failed, "{}", text);
+    let output: Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(output["summary"]["count"], 1);
+}
+
+#[test]
+fn test_search_session_skips_returned_blocks() {
+    let dir = create_project();
+    let path = dir.path().to_string_lossy().to_string();
+    let mut server = McpServer::new();
+
+    let (_, text) = call_tool(
+        &mut server,
+        "search",
+        json!({ "path": path, "query": "parse_port", "session": "" }),
+    );
+    let first: Value = serde_json::from_str(&text).unwrap();
+    let session = first["session"].as_str().unwrap().to_string();
+    assert_eq!(session.len(), 4);
+    assert!(!first["results"].as_array().unwrap().is_empty());
+
+    let (_, text) = call_tool(
+        &mut server,
+        "search",
+        json!({ "path": path, "query": "parse_port", "session": session }),
+    );
+    let second: Value = serde_json::from_str(&text).unwrap();
+    assert!(second["results"].as_array().unwrap().is_empty());
+    assert!(second["cached_blocks_skipped"].as_u64().unwrap() > 0);
+}
+
+#[test]
+fn test_errors() {
+    let mut server = McpServer::new();
+
+    // Tool failures are results the agent can read
+    let (failed, text) = call_tool(
+        &mut server,
+        "search",
+        json!({ "path": "/no/such/dir", "query": "x" }),
+    );
+    assert!(failed);
+    assert!(text.starts_with("io:"), "{}", text);
+
+    let (failed, text) = call_tool(&mut server, "extract", json!({}));
+    assert!(failed);
+    assert!(text.contains("files"), "{}", text);
+
+    // Protocol errors are JSON-RPC errors
+    let response = request(&mut server, 3, "tools/call", json!({ "name": "nope" }));
+    assert_eq!(response["error"]["code"], -32602);
+    let response = request(&mut server, 4, "resources/list", json!({}));
+    assert_eq!(response["error"]["code"], -32601);
+    let response = server.handle_message("not json").unwrap();
+    assert_eq!(response["error"]["code"], -32700);
+    assert_eq!(response["id"], Value::Null);
+}
+
+#[test]
+fn test_searches_share_corpus_stats_until_files_change() {
+    let dir = TempDir::new().unwrap();
+    let alpha = dir.path().join("alpha.rs");
+    let beta = dir.path().join("beta.rs");
+    fs::write(&alpha, "fn alpha() {\n    needle();\n}\n").unwrap();
+    fs::write(&beta, "fn beta() {\n    haystack();\n}\n").unwrap();
+    let path = dir.path().to_string_lossy().to_string();
+    let mut server = McpServer::new();
+    let alpha_score = |server: &mut McpServer| {
+        let (_, text) = call_tool(server, "search", json!({ "path": path, "query": "needle" }));
+        let output: Value = serde_json::from_str(&text).unwrap();
+        output["results"][0]["score"].as_f64().unwrap()
+    };
+
+    let first = alpha_score(&mut server);
+    fs::write(&beta, "fn beta() {\n    needle();\n}\n").unwrap();
+    // Not counted again until the client reports the change
+    assert_eq!(alpha_score(&mut server), first);
+
+    let notification = json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/filesChanged",
+        "params": { "paths": [beta.to_string_lossy()] },
+    });
+    assert!(server.handle_message(&notification.to_string()).is_none());
+    // A term more files contain weighs less
+    assert!(alpha_score(&mut server) < first);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_search_results_percent_encode_non_utf8_paths() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = TempDir::new().unwrap();
+    let file_name = OsStr::from_bytes(b"caf\xe9.rs");
+    if fs::write(dir.path().join(file_name), "fn needle_handler() {}\n").is_err() {
+        // Some filesystems only accept UTF-8 names
+        return;
+    }
+    let path = dir.path().to_string_lossy().to_string();
+    let mut server = McpServer::new();
+
+    let (failed, text) = call_tool(
+        &mut server,
+        "search",
+        json!({ "path": path, "query": "needle_handler" }),
+    );
+    assert!(!failed, "{}", text);
+    let output: Value = serde_json::from_str(&text).unwrap();
+    let file = output["results"][0]["file"].as_str().unwrap();
+    assert!(file.ends_with("caf%E9.rs"), "{}", file);
+    assert_eq!(output["results"][0]["file_encoding"], "percent");
+}
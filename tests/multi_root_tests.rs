@@ -0,0 +1,193 @@
+use probe::search::{perform_probe, SearchOptions, SearchRoot};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn options<'a>(
+    path: &'a Path,
+    queries: &'a [String],
+    custom_ignores: &'a [String],
+    roots: &'a [SearchRoot],
+) -> SearchOptions<'a> {
+    SearchOptions {
+        path,
+        queries,
+        custom_ignores,
+        exclude_filenames: true,
+        roots,
+        ..Default::default()
+    }
+}
+
+/// A monorepo with generated code under backend/gen and an unrelated tools directory
+fn create_monorepo() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let files = [
+        (
+            "backend/server.rs",
+            "fn serve() {\n    let needle = 1;\n}\n",
+        ),
+        (
+            "backend/gen/api.rs",
+            "fn generated() {\n    let needle = 2;\n}\n",
+        ),
+        (
+            "frontend/app.js",
+            "function render() {\n  const needle = 3;\n}\n",
+        ),
+        (
+            "frontend/gen/bundle.js",
+            "function bundle() {\n  const needle = 4;\n}\n",
+        ),
+        ("tools/script.rs", "fn script() {\n    let needle = 5;\n}\n"),
+    ];
+    for (name, content) in files {
+        let path = temp_dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    temp_dir
+}
+
+fn result_files(results: &probe::models::LimitedSearchResults, root: &Path) -> Vec<String> {
+    let mut files: Vec<String> = results
+        .results
+        .iter()
+        .map(|r| {
+            Path::new(&r.file)
+                .strip_prefix(root)
+                .unwrap()
+                .to_string_lossy()
+                .replace('\\', "/")
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn test_ignores_apply_to_their_own_root() {
+    let repo = create_monorepo();
+    let roots = vec![
+        SearchRoot::new(repo.path().join("backend"), vec!["gen".to_string()]),
+        SearchRoot::new(repo.path().join("frontend"), vec![]),
+    ];
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+
+    let results = perform_probe(&options(repo.path(), &queries, &custom_ignores, &roots)).unwrap();
+    assert_eq!(
+        result_files(&results, repo.path()),
+        vec![
+            "backend/server.rs",
+            "frontend/app.js",
+            "frontend/gen/bundle.js"
+        ]
+    );
+}
+
+#[test]
+fn test_shared_ignores_apply_to_every_root() {
+    let repo = create_monorepo();
+    let roots = vec![
+        SearchRoot::new(repo.path().join("backend"), vec![]),
+        SearchRoot::new(repo.path().join("frontend"), vec![]),
+        // Overlapping roots list each file once
+        SearchRoot::new(repo.path().join("frontend/gen"), vec![]),
+    ];
+    let queries = vec!["needle".to_string()];
+    let custom_ignores = vec!["server.rs".to_string()];
+
+    let results = perform_probe(&options(repo.path(), &queries, &custom_ignores, &roots)).unwrap();
+    assert_eq!(
+        result_files(&results, repo.path()),
+        vec![
+            "backend/gen/api.rs",
+            "frontend/app.js",
+            "frontend/gen/bundle.js"
+        ]
+    );
+}
+
+#[test]
+fn test_root_prefixed_ignores_are_made_relative() {
+    let root = SearchRoot::new(
+        "backend",
+        vec!["backend/gen".to_string(), "*.pb.rs".to_string()],
+    );
+    assert_eq!(root.ignores, vec!["gen", "*.pb.rs"]);
+}
+
+#[test]
+fn test_cli_scopes_ignore_to_preceding_path() {
+    let repo = create_monorepo();
+    let history_dir = TempDir::new().unwrap();
+    let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
+        .args([
+            "run",
+            "--manifest-path",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"),
+            "--",
+            "search",
+            "needle",
+            "--path",
+            "backend",
+            "--ignore",
+            "backend/gen",
+            "--path",
+            "frontend",
+        ])
+        .current_dir(repo.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("backend/server.rs"));
+    assert!(stdout.contains("frontend/gen/bundle.js"));
+    assert!(!stdout.contains("backend/gen/api.rs"));
+    assert!(!stdout.contains("tools/script.rs"));
+}
+
+#[test]
+fn test_cli_searches_every_path_argument() {
+    let repo = create_monorepo();
+    let history_dir = TempDir::new().unwrap();
+    let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
+        .args([
+            "run",
+            "--manifest-path",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"),
+            "--",
+            "search",
+            "needle",
+            "backend",
+            "tools",
+            // Overlaps the first path, spelled differently
+            "./backend/gen",
+            "--format",
+            "json",
+        ])
+        .current_dir(repo.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let json_start = stdout.find('{').unwrap();
+    let json: serde_json::Value = serde_json::from_str(&stdout[json_start..]).unwrap();
+    let mut files: Vec<String> = json["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["file"].as_str().unwrap().replace('\\', "/"))
+        .collect();
+    files.sort();
+    assert_eq!(
+        files,
+        vec!["backend/gen/api.rs", "backend/server.rs", "tools/script.rs"]
+    );
+}
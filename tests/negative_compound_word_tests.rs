@@ -89,21 +89,13 @@ fn test_basic_negative_compound_word(temp_path: &Path) {
     let options = SearchOptions {
         path: temp_path,
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
 
         exact: false, // Enable stemming and compound word splitting
         no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Run the search
@@ -153,21 +145,13 @@ fn test_complex_negative_compound_word(temp_path: &Path) {
     let options = SearchOptions {
         path: temp_path,
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
         // Use all terms mode
         exact: false, // Enable stemming
         no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Run the search
@@ -0,0 +1,84 @@
+use probe::search::elastic_query::{parse_query, Expr};
+use probe::search::tokenization::{is_numeric_literal, tokenize};
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, query: &str) -> Vec<(usize, usize)> {
+    let queries = vec![query.to_string()];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        exclude_filenames: true,
+        no_merge: true,
+        ..Default::default()
+    };
+    let mut lines: Vec<(usize, usize)> = perform_probe(&options)
+        .unwrap()
+        .results
+        .iter()
+        .map(|r| r.lines)
+        .collect();
+    lines.sort();
+    lines
+}
+
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("config.py"),
+        r#"def pinned_version():
+    return "1.2.3"
+
+def other_version():
+    return "11.2.34"
+
+def mask_bits(value):
+    return value & 0x7f
+
+def server_port():
+    return 18080
+"#,
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_tokenizer_keeps_numeric_literals_whole() {
+    assert_eq!(tokenize("1.2.3"), vec!["1.2.3"]);
+    assert_eq!(tokenize("release v1.2.3."), vec!["releas", "1.2.3"]);
+    assert_eq!(tokenize("0x7F"), vec!["0x7f"]);
+    // Dots that are not between digits still split
+    assert_eq!(tokenize("config.load"), vec!["config", "load"]);
+
+    assert!(is_numeric_literal("1.2.3"));
+    assert!(is_numeric_literal("8080"));
+    assert!(!is_numeric_literal("v2"));
+    assert!(!is_numeric_literal("1.2."));
+}
+
+#[test]
+fn test_numeric_literal_terms_are_exact() {
+    match parse_query("0x7F").unwrap() {
+        Expr::Term {
+            keywords, exact, ..
+        } => {
+            assert_eq!(keywords, vec!["0x7f"]);
+            assert!(exact);
+        }
+        other => panic!("expected a term, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_search_matches_whole_version_strings() {
+    let temp_dir = create_project();
+
+    assert_eq!(search(temp_dir.path(), "1.2.3"), vec![(1, 2)]);
+    assert_eq!(search(temp_dir.path(), "v1.2.3"), vec![(1, 2)]);
+    assert_eq!(search(temp_dir.path(), "0x7f"), vec![(7, 8)]);
+    // A port number does not match inside a longer number
+    assert!(search(temp_dir.path(), "8080").is_empty());
+}
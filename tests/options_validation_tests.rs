@@ -0,0 +1,87 @@
+use probe::error::ProbeError;
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn options<'a>(path: &'a Path, queries: &'a [String]) -> SearchOptions<'a> {
+    SearchOptions {
+        path,
+        queries,
+        exclude_filenames: true,
+        no_merge: true,
+        ..Default::default()
+    }
+}
+
+fn error_message(options: &SearchOptions) -> String {
+    perform_probe(options).unwrap_err().to_string()
+}
+
+#[test]
+fn test_nonexistent_path_is_rejected() {
+    let temp_dir = TempDir::new().unwrap();
+    let missing = temp_dir.path().join("missing");
+    let queries = vec!["needle".to_string()];
+
+    let error = perform_probe(&options(&missing, &queries)).unwrap_err();
+    assert!(matches!(error, ProbeError::Io(_)), "{:?}", error);
+    assert!(
+        error.message().contains("Search path does not exist"),
+        "{}",
+        error
+    );
+
+    // Alongside an invalid option, the options are what is wrong
+    let error = perform_probe(&SearchOptions {
+        reranker: "semantic",
+        ..options(&missing, &queries)
+    })
+    .unwrap_err();
+    assert!(matches!(error, ProbeError::QueryParse(_)), "{:?}", error);
+    assert!(error.message().contains("Search path does not exist"));
+}
+
+#[test]
+fn test_conflicting_options_are_rejected_with_every_problem_listed() {
+    let temp_dir = TempDir::new().unwrap();
+    let queries = vec!["needle".to_string()];
+
+    let message = error_message(&SearchOptions {
+        files_only: true,
+        max_tokens: Some(100),
+        ..options(temp_dir.path(), &queries)
+    });
+    assert!(message.contains("--max-tokens has no effect with --files-only"));
+    assert!(message.contains("--max-results"), "{}", message);
+
+    let message = error_message(&SearchOptions {
+        reranker: "semantic",
+        min_depth: Some(3),
+        max_depth: Some(1),
+        ..options(temp_dir.path(), &queries)
+    });
+    assert!(
+        message.starts_with("Invalid search options:"),
+        "{}",
+        message
+    );
+    assert!(message.contains("Unknown --reranker 'semantic'"));
+    assert!(message.contains("bm25, hybrid, hybrid2, tfidf, combined"));
+    assert!(message.contains("--min-depth 3 is greater than --max-depth 1"));
+}
+
+#[test]
+fn test_redundant_options_only_warn() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("lib.rs"), "fn needle() {}\n").unwrap();
+    let queries = vec!["needle".to_string()];
+
+    let results = perform_probe(&SearchOptions {
+        no_merge: true,
+        merge_threshold: Some(10),
+        ..options(temp_dir.path(), &queries)
+    })
+    .unwrap();
+    assert_eq!(results.results.len(), 1);
+}
@@ -0,0 +1,146 @@
+use probe::output_file::{check_output_options, split_artifact_path, Compression, OutputFile};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+fn run_probe(dir: &Path, args: &[&str]) -> Output {
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+    let history_dir = TempDir::new().unwrap();
+    Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
+        .args(["run", "--manifest-path", manifest.to_str().unwrap(), "--"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("Failed to execute command")
+}
+
+#[test]
+fn test_output_file_compression_round_trip() {
+    let temp_dir = TempDir::new().unwrap();
+    let text = "{\"results\": []}\n".repeat(100);
+
+    let gzip_path = temp_dir.path().join("out.json.gz");
+    let mut file = OutputFile::create(&gzip_path, Some(Compression::Gzip)).unwrap();
+    file.write_all(text.as_bytes()).unwrap();
+    file.finish().unwrap();
+    let mut decoded = String::new();
+    flate2::read::GzDecoder::new(fs::File::open(&gzip_path).unwrap())
+        .read_to_string(&mut decoded)
+        .unwrap();
+    assert_eq!(decoded, text);
+
+    let zstd_path = temp_dir.path().join("out.json.zst");
+    let mut file = OutputFile::create(&zstd_path, Some(Compression::Zstd)).unwrap();
+    file.write_all(text.as_bytes()).unwrap();
+    file.finish().unwrap();
+    let decoded = zstd::decode_all(fs::File::open(&zstd_path).unwrap()).unwrap();
+    assert_eq!(String::from_utf8(decoded).unwrap(), text);
+}
+
+#[test]
+fn test_compress_needs_output_and_machine_format() {
+    let path = Path::new("out.json.gz");
+    assert_eq!(
+        check_output_options(Some(path), Some("gzip"), "json").unwrap(),
+        Some(Compression::Gzip)
+    );
+    assert_eq!(
+        check_output_options(Some(path), None, "color").unwrap(),
+        None
+    );
+    assert!(check_output_options(None, Some("gzip"), "json").is_err());
+    assert!(check_output_options(Some(path), Some("zstd"), "markdown").is_err());
+}
+
+#[test]
+fn test_search_writes_results_to_output_file() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("lib.rs"),
+        "fn parse_config() -> u32 {\n    42\n}\n",
+    )
+    .unwrap();
+
+    let output = run_probe(
+        temp_dir.path(),
+        &[
+            "search",
+            "parse_config",
+            ".",
+            "--format",
+            "json",
+            "--output",
+            "results.json",
+        ],
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("\"results\""));
+
+    let written = fs::read_to_string(temp_dir.path().join("results.json")).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&written).unwrap();
+    assert!(json["results"][0]["code"]
+        .as_str()
+        .unwrap()
+        .contains("fn parse_config"));
+}
+
+#[test]
+fn test_split_artifact_path_mirrors_source_path() {
+    let dir = Path::new("out");
+    assert_eq!(
+        split_artifact_path(dir, "./src/main.rs", "json", None),
+        Path::new("out/src/main.rs.json")
+    );
+    assert_eq!(
+        split_artifact_path(dir, "../lib/util.py", "xml", Some(Compression::Zstd)),
+        Path::new("out/__/lib/util.py.xml.zst")
+    );
+    assert_eq!(
+        split_artifact_path(dir, "/abs/notes.md", "markdown", None),
+        Path::new("out/abs/notes.md.md")
+    );
+}
+
+#[test]
+fn test_search_splits_results_per_file() {
+    let temp_dir = TempDir::new().unwrap();
+    for name in ["a", "b"] {
+        fs::create_dir(temp_dir.path().join(name)).unwrap();
+        fs::write(
+            temp_dir.path().join(name).join("lib.rs"),
+            "fn parse_config() -> u32 {\n    42\n}\n",
+        )
+        .unwrap();
+    }
+
+    let output = run_probe(
+        temp_dir.path(),
+        &[
+            "search",
+            "parse_config",
+            ".",
+            "--format",
+            "json",
+            "--split-per-file",
+            "split",
+        ],
+    );
+    assert!(output.status.success());
+
+    for name in ["a", "b"] {
+        let written =
+            fs::read_to_string(temp_dir.path().join("split").join(name).join("lib.rs.json"))
+                .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&written).unwrap();
+        let results = json["results"].as_array().unwrap();
+        assert!(!results.is_empty());
+        assert!(results.iter().all(|r| r["file"]
+            .as_str()
+            .unwrap()
+            .contains(&format!("{}/lib.rs", name))));
+    }
+}
@@ -187,7 +187,9 @@ fn test_json_output_with_long_lines() {
     create_edge_case_test_files(&temp_dir);
 
     // Run the CLI with JSON output format, searching for "longLine"
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -258,7 +260,9 @@ fn test_json_output_with_many_lines() {
     create_edge_case_test_files(&temp_dir);
 
     // Run the CLI with JSON output format, searching for "Line"
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -314,7 +318,9 @@ fn test_json_output_with_nested_structures() {
     create_edge_case_test_files(&temp_dir);
 
     // Run the CLI with JSON output format, searching for "nestedStructure"
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -387,7 +393,9 @@ fn test_json_output_with_mixed_encodings() {
     create_edge_case_test_files(&temp_dir);
 
     // Run the CLI with JSON output format, searching for "mixedEncodings"
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -460,7 +468,9 @@ fn test_xml_output_with_long_lines() {
     create_edge_case_test_files(&temp_dir);
 
     // Run the CLI with XML output format, searching for "longLine"
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -538,7 +548,9 @@ fn test_xml_output_with_nested_structures() {
     create_edge_case_test_files(&temp_dir);
 
     // Run the CLI with XML output format, searching for "nestedStructure"
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -618,7 +630,9 @@ fn test_xml_output_with_mixed_encodings() {
     create_edge_case_test_files(&temp_dir);
 
     // Run the CLI with XML output format, searching for "mixedEncodings"
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -0,0 +1,114 @@
+use anyhow::anyhow;
+use probe::models::{LimitedSearchResults, SearchResult};
+use probe::search::{
+    perform_probe, write_search_results, CallbackSink, NullSink, OutputSink, SearchOptions,
+    SearchOutput, WriterSink,
+};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path) -> LimitedSearchResults {
+    let queries = vec!["config".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        ..Default::default()
+    };
+    perform_probe(&options).unwrap()
+}
+
+fn create_project() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("parser.rs"),
+        "pub fn parse_config(text: &str) -> Config {\n    Config::from(text)\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("loader.rs"),
+        "pub fn load_config(path: &str) -> Config {\n    parse_config(path)\n}\n",
+    )
+    .unwrap();
+    dir
+}
+
+#[test]
+fn test_writer_sink_matches_the_cli_formatters() {
+    let project = create_project();
+    let limited = search(project.path());
+    assert_eq!(limited.results.len(), 2);
+
+    for format in ["plain", "json", "xml"] {
+        let mut sink = WriterSink::new(Vec::new(), format);
+        sink.emit(&SearchOutput::from(&limited)).unwrap();
+
+        let mut expected = Vec::new();
+        write_search_results(&mut expected, &limited.results, false, format, None, &[], None).unwrap();
+        assert_eq!(sink.into_inner(), expected, "format {}", format);
+    }
+}
+
+#[test]
+fn test_json_sink_writes_parseable_results() {
+    let project = create_project();
+    let limited = search(project.path());
+
+    let mut sink = WriterSink::json(Vec::new());
+    sink.emit(&SearchOutput::from(&limited)).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&sink.into_inner()).unwrap();
+    assert_eq!(json["results"].as_array().unwrap().len(), 2);
+    assert_eq!(json["summary"]["count"], 2);
+}
+
+#[test]
+fn test_callback_sink_receives_each_result() {
+    let project = create_project();
+    let limited = search(project.path());
+
+    let mut captured: Vec<(String, (usize, usize))> = Vec::new();
+    let mut sink = CallbackSink::new(|result: &SearchResult| {
+        captured.push((result.file.clone(), result.lines));
+        Ok(())
+    });
+    sink.emit(&SearchOutput::from(&limited)).unwrap();
+    let expected: Vec<(String, (usize, usize))> = limited
+        .results
+        .iter()
+        .map(|r| (r.file.clone(), r.lines))
+        .collect();
+    assert_eq!(captured, expected);
+}
+
+#[test]
+fn test_callback_sink_stops_at_the_first_error() {
+    let project = create_project();
+    let limited = search(project.path());
+
+    let mut calls = 0;
+    let mut sink = CallbackSink::new(|_: &SearchResult| {
+        calls += 1;
+        Err(anyhow!("consumer is full"))
+    });
+    let err = sink.emit(&SearchOutput::from(&limited)).unwrap_err();
+    assert_eq!(err.to_string(), "consumer is full");
+    assert_eq!(calls, 1);
+}
+
+#[test]
+fn test_sinks_can_be_chosen_at_runtime() {
+    let project = create_project();
+    let limited = search(project.path());
+
+    let mut sinks: Vec<Box<dyn OutputSink>> = vec![
+        Box::new(NullSink),
+        Box::new(WriterSink::new(std::io::sink(), "plain")),
+    ];
+    for sink in &mut sinks {
+        sink.emit(&SearchOutput::from(&limited)).unwrap();
+    }
+}
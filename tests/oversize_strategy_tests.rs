@@ -0,0 +1,80 @@
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, oversize_strategy: &str, oversize_threshold: usize) -> Vec<String> {
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        oversize_strategy,
+        oversize_threshold,
+        ..Default::default()
+    };
+    perform_probe(&options)
+        .unwrap()
+        .results
+        .into_iter()
+        .map(|r| r.code)
+        .collect()
+}
+
+/// A large function with two nested helper functions, only the call site uses `needle`
+fn create_large_function() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let mut code = String::from("fn outer() -> usize {\n    fn first() -> usize {\n");
+    for i in 0..40 {
+        code.push_str(&format!("        let step{} = {};\n", i, i));
+    }
+    code.push_str("        0\n    }\n\n    fn second() -> usize {\n");
+    for i in 0..40 {
+        code.push_str(&format!("        let part{} = {};\n", i, i));
+    }
+    code.push_str("        1\n    }\n\n    let needle = first() + second();\n    needle\n}\n");
+    fs::write(temp_dir.path().join("outer.rs"), code).unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_outline_keeps_signatures_and_matched_lines() {
+    let project = create_large_function();
+
+    let outline = search(project.path(), "outline", 50);
+    assert_eq!(outline.len(), 1);
+    let lines: Vec<&str> = outline[0].lines().collect();
+    assert_eq!(
+        lines,
+        vec![
+            "fn outer() -> usize {",
+            "    fn first() -> usize {",
+            "        ... 43 lines folded ...",
+            "    fn second() -> usize {",
+            "        ... 43 lines folded ...",
+            "    let needle = first() + second();",
+            "    needle",
+            "... 1 line folded ...",
+        ]
+    );
+
+    // Blocks under the threshold are returned in full
+    let full = search(project.path(), "outline", 500);
+    assert_eq!(full[0].lines().count(), 92);
+}
+
+#[test]
+fn test_truncate_keeps_leading_lines() {
+    let project = create_large_function();
+
+    let truncated = search(project.path(), "truncate", 10);
+    let lines: Vec<&str> = truncated[0].lines().collect();
+    assert_eq!(lines.len(), 11);
+    assert_eq!(lines[0], "fn outer() -> usize {");
+    assert_eq!(lines[10], "        ... 82 lines folded ...");
+
+    let full = search(project.path(), "full", 10);
+    assert_eq!(full[0].lines().count(), 92);
+}
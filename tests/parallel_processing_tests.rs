@@ -0,0 +1,48 @@
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path) -> Vec<probe::models::SearchResult> {
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        ..Default::default()
+    };
+    perform_probe(&options).unwrap().results
+}
+
+#[test]
+fn test_result_processing_covers_every_file_in_a_stable_order() {
+    let temp_dir = TempDir::new().unwrap();
+    for index in 0..48 {
+        let module = temp_dir.path().join(format!("module_{:02}", index % 6));
+        fs::create_dir_all(&module).unwrap();
+        fs::write(
+            module.join(format!("file_{:02}.rs", index)),
+            format!(
+                "fn needle_{index}(input: u32) -> u32 {{\n    let needle = input + {index};\n    needle\n}}\n\nfn other_{index}() {{}}\n"
+            ),
+        )
+        .unwrap();
+    }
+
+    let locations = |results: &[probe::models::SearchResult]| {
+        results
+            .iter()
+            .map(|result| (result.file.clone(), result.lines))
+            .collect::<Vec<_>>()
+    };
+
+    let first = search(temp_dir.path());
+    assert_eq!(first.len(), 48);
+    assert!(first.iter().all(|result| result.code.contains("needle")));
+    for _ in 0..3 {
+        assert_eq!(locations(&search(temp_dir.path())), locations(&first));
+    }
+}
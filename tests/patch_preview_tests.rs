@@ -0,0 +1,114 @@
+use probe::extract::{apply_patch, changed_file_paths};
+use probe::fs_access::{self, MemoryFs};
+use probe::models::LineChange;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+const MATH: &str =
+    "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\nfn sub(a: i32, b: i32) -> i32 {\n    a - b\n}\n";
+
+fn run_preview(dir: &Path, args: &[&str]) -> Output {
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+    let history_dir = TempDir::new().unwrap();
+    Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
+        .args(["run", "--manifest-path", manifest.to_str().unwrap(), "--"])
+        .arg("preview")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("Failed to execute command")
+}
+
+#[test]
+fn test_apply_patch_in_memory() {
+    // The hunk header is two lines off, and `-- ` starts a removed SQL comment
+    let patch = "diff --git a/math.rs b/math.rs\n\
+                 --- a/math.rs\n\
+                 +++ b/math.rs\n\
+                 @@ -7,3 +7,4 @@\n \
+                 fn sub(a: i32, b: i32) -> i32 {\n\
+                 -    a - b\n\
+                 +    let diff = a - b;\n\
+                 +    diff\n \
+                 }\n\
+                 diff --git a/schema.sql b/schema.sql\n\
+                 --- a/schema.sql\n\
+                 +++ b/schema.sql\n\
+                 @@ -1,2 +1 @@\n\
+                 --- drop me\n \
+                 CREATE TABLE t (id INT);\n\
+                 \\ No newline at end of file\n\
+                 diff --git a/new.rs b/new.rs\n\
+                 new file mode 100644\n\
+                 --- /dev/null\n\
+                 +++ b/new.rs\n\
+                 @@ -0,0 +1 @@\n\
+                 +fn brand_new() {}\n";
+    let memory = MemoryFs::new()
+        .with_file("math.rs", MATH)
+        .with_file("schema.sql", "-- drop me\nCREATE TABLE t (id INT);");
+
+    let patched = fs_access::with_file_system(Arc::new(memory), || apply_patch(patch)).unwrap();
+
+    assert_eq!(patched.len(), 3);
+    assert_eq!(
+        patched[0].contents,
+        "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\nfn sub(a: i32, b: i32) -> i32 {\n    let diff = a - b;\n    diff\n}\n"
+    );
+    assert_eq!(
+        patched[0].changes.iter().collect::<Vec<_>>(),
+        vec![(&6, &LineChange::Modified), (&7, &LineChange::Added)]
+    );
+    assert_eq!(patched[1].contents, "CREATE TABLE t (id INT);");
+    assert!(patched[1].changes.is_empty());
+    assert_eq!(patched[2].path, PathBuf::from("new.rs"));
+    assert_eq!(patched[2].source, None);
+    assert_eq!(patched[2].contents, "fn brand_new() {}\n");
+
+    // Only files with added or changed lines are extracted
+    let paths = changed_file_paths(&patched, false);
+    assert_eq!(paths.len(), 2);
+    assert_eq!(paths[0].0, PathBuf::from("math.rs"));
+    assert_eq!((paths[0].1, paths[0].2), (Some(6), Some(7)));
+}
+
+#[test]
+fn test_apply_patch_rejects_hunks_that_do_not_match() {
+    let patch = "--- math.rs\n+++ math.rs\n@@ -2 +2 @@\n-    a * b\n+    a / b\n";
+    let memory = MemoryFs::new().with_file("math.rs", MATH);
+
+    let err = fs_access::with_file_system(Arc::new(memory), || apply_patch(patch)).unwrap_err();
+    assert!(format!("{:#}", err).contains("Patch does not apply to math.rs"));
+}
+
+#[test]
+fn test_preview_extracts_patched_blocks_without_writing() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join("math.rs"), MATH).unwrap();
+    fs::write(
+        temp_dir.path().join("edit.diff"),
+        "--- a/math.rs\n+++ b/math.rs\n@@ -5,3 +5,3 @@\n fn sub(a: i32, b: i32) -> i32 {\n-    a - b\n+    a.saturating_sub(b)\n }\n",
+    )
+    .unwrap();
+
+    let output = run_preview(
+        temp_dir.path(),
+        &["--patch", "edit.diff", "--format", "json"],
+    );
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let results = json["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(
+        results[0]["code"].as_str().unwrap(),
+        "fn sub(a: i32, b: i32) -> i32 {\n    a.saturating_sub(b)\n}"
+    );
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("math.rs")).unwrap(),
+        MATH
+    );
+}
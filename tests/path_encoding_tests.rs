@@ -0,0 +1,73 @@
+use probe::path_encoding::{decode_path, encode_path, PERCENT_ENCODING};
+use std::path::Path;
+
+#[test]
+fn test_utf8_paths_are_unchanged() {
+    let encoded = encode_path(Path::new("src/ünïcödé 100%.rs"));
+    assert_eq!(encoded.value, "src/ünïcödé 100%.rs");
+    assert_eq!(encoded.encoding, None);
+    assert_eq!(encoded.xml_attribute(), "");
+
+    assert_eq!(
+        decode_path(&encoded.value, encoded.encoding).unwrap(),
+        Path::new("src/ünïcödé 100%.rs")
+    );
+}
+
+#[test]
+fn test_invalid_escapes_are_rejected() {
+    assert!(decode_path("caf%E", Some(PERCENT_ENCODING)).is_err());
+    assert!(decode_path("caf%ZZ.rs", Some(PERCENT_ENCODING)).is_err());
+    assert!(decode_path("cafe.rs", Some("base64")).is_err());
+}
+
+#[cfg(unix)]
+mod unix {
+    use probe::path_encoding::{decode_path, encode_path};
+    use probe::search::{perform_probe, SearchOptions};
+    use std::ffi::OsStr;
+    use std::fs;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_non_utf8_paths_round_trip() {
+        let path = Path::new(OsStr::from_bytes(b"src/caf\xe9 50%.rs"));
+        let encoded = encode_path(path);
+
+        assert_eq!(encoded.value, "src/caf%E9 50%25.rs");
+        assert_eq!(encoded.encoding, Some("percent"));
+        assert_eq!(encoded.xml_attribute(), " encoding=\"percent\"");
+        assert_eq!(decode_path(&encoded.value, encoded.encoding).unwrap(), path);
+    }
+
+    #[test]
+    fn test_search_results_carry_exact_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_name = OsStr::from_bytes(b"caf\xe9.rs");
+        if fs::write(
+            temp_dir.path().join(file_name),
+            "fn needle_handler() {\n    println!(\"needle\");\n}\n",
+        )
+        .is_err()
+        {
+            // Some filesystems only accept UTF-8 names
+            return;
+        }
+
+        let queries = vec!["needle".to_string()];
+        let custom_ignores: Vec<String> = vec![];
+        let options = SearchOptions {
+            path: temp_dir.path(),
+            queries: &queries,
+            custom_ignores: &custom_ignores,
+            exclude_filenames: true,
+            ..Default::default()
+        };
+
+        let results = perform_probe(&options).unwrap().results;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path(), temp_dir.path().join(file_name));
+    }
+}
@@ -0,0 +1,128 @@
+use probe::path_encoding::{format_path_for_output, PathStyle};
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search<'a>(
+    path: &'a Path,
+    queries: &'a [String],
+    custom_ignores: &'a [String],
+    path_style: &'a str,
+) -> Vec<probe::models::SearchResult> {
+    let options = SearchOptions {
+        path,
+        queries,
+        custom_ignores,
+        exclude_filenames: true,
+        path_style,
+        ..Default::default()
+    };
+    perform_probe(&options).unwrap().results
+}
+
+fn create_nested_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let nested = temp_dir.path().join("src").join("handlers");
+    fs::create_dir_all(&nested).unwrap();
+    fs::write(
+        nested.join("needle.rs"),
+        "fn needle_handler() {\n    println!(\"needle\");\n}\n",
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_path_style_names() {
+    assert_eq!(PathStyle::from_name("unix"), PathStyle::Unix);
+    assert_eq!(PathStyle::from_name("native"), PathStyle::Native);
+    assert_eq!(PathStyle::default(), PathStyle::Native);
+}
+
+#[test]
+fn test_unix_path_style_uses_forward_slashes() {
+    let temp_dir = create_nested_project();
+    let queries = vec!["needle".to_string()];
+
+    let results = search(temp_dir.path(), &queries, &[], "unix");
+    assert_eq!(results.len(), 1);
+    assert!(results[0].file.ends_with("src/handlers/needle.rs"));
+    // The exact path is untouched and still points at the file
+    assert!(results[0].path().exists());
+}
+
+#[cfg(not(windows))]
+#[test]
+fn test_paths_are_unchanged_outside_windows() {
+    // Backslashes are ordinary file name characters here
+    assert_eq!(
+        format_path_for_output(r"src/odd\name.rs", PathStyle::Unix),
+        r"src/odd\name.rs"
+    );
+    assert_eq!(
+        format_path_for_output(r"\\?\C:\src\lib.rs", PathStyle::Native),
+        r"\\?\C:\src\lib.rs"
+    );
+}
+
+#[cfg(windows)]
+mod windows {
+    use super::*;
+
+    #[test]
+    fn test_separators_follow_style() {
+        assert_eq!(
+            format_path_for_output(r"C:\src/lib.rs", PathStyle::Unix),
+            "C:/src/lib.rs"
+        );
+        assert_eq!(
+            format_path_for_output(r"C:\src/lib.rs", PathStyle::Native),
+            r"C:\src\lib.rs"
+        );
+    }
+
+    #[test]
+    fn test_verbatim_prefix_is_stripped() {
+        assert_eq!(
+            format_path_for_output(r"\\?\C:\src\lib.rs", PathStyle::Native),
+            r"C:\src\lib.rs"
+        );
+        assert_eq!(
+            format_path_for_output(r"\\?\UNC\server\share\lib.rs", PathStyle::Native),
+            r"\\server\share\lib.rs"
+        );
+    }
+
+    #[test]
+    fn test_long_paths_are_searched() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut dir = temp_dir.path().to_path_buf();
+        while dir.as_os_str().len() < 300 {
+            dir.push("a_fairly_long_directory_name");
+        }
+        let verbatim = format!(r"\\?\{}", dir.display());
+        fs::create_dir_all(&verbatim).unwrap();
+        fs::write(
+            Path::new(&verbatim).join("needle.rs"),
+            "fn needle_handler() {}\n",
+        )
+        .unwrap();
+
+        let queries = vec!["needle".to_string()];
+        let root = format!(r"\\?\{}", temp_dir.path().display());
+        let results = search(Path::new(&root), &queries, &[], "native");
+        assert_eq!(results.len(), 1);
+        assert!(!results[0].file.starts_with(r"\\?\"));
+    }
+
+    #[test]
+    fn test_ignore_patterns_are_case_insensitive() {
+        let temp_dir = create_nested_project();
+        let queries = vec!["needle".to_string()];
+        let ignores = vec!["HANDLERS".to_string()];
+
+        let results = search(temp_dir.path(), &queries, &ignores, "native");
+        assert!(results.is_empty());
+    }
+}
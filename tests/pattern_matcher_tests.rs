@@ -0,0 +1,170 @@
+use probe::search::pattern_matcher::{PatternMatcher, DEFAULT_SIZE_LIMIT};
+use probe::search::query::{create_query_plan, create_structured_patterns};
+use probe::search::{perform_probe, SearchOptions};
+use regex::{RegexBuilder, RegexSetBuilder};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const WORDS: &[&str] = &[
+    "alpha", "beta", "gamma", "delta", "epsilon", "zeta", "theta", "iota", "kappa", "lambda",
+    "sigma", "omega", "needle",
+];
+
+const LINES: &[&str] = &[
+    "let alpha = beta(gamma);",
+    "fn find_needle() -> Omega {",
+    "// nothing to see here",
+    "KAPPA + Lambda + sigma",
+];
+
+/// The patterns generated for the first `count` words ORed together
+fn or_patterns(count: usize) -> Vec<String> {
+    let plan = create_query_plan(&WORDS[..count].join(" OR "), false).unwrap();
+    create_structured_patterns(&plan)
+        .into_iter()
+        .map(|(pattern, _)| pattern)
+        .collect()
+}
+
+/// Patterns matching `line`, found by matching each pattern that fits `size_limit` on its own
+fn expected_matches(patterns: &[String], size_limit: usize, line: &str) -> BTreeSet<usize> {
+    patterns
+        .iter()
+        .enumerate()
+        .filter(|(_, pattern)| {
+            RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .size_limit(size_limit)
+                .build()
+                .is_ok_and(|regex| regex.is_match(line))
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+fn matches(matcher: &PatternMatcher, line: &str) -> BTreeSet<usize> {
+    let mut matched = BTreeSet::new();
+    matcher.for_each_match(line, |index| {
+        matched.insert(index);
+    });
+    matched
+}
+
+#[test]
+fn test_small_queries_use_one_combined_regex() {
+    let patterns = vec!["(alpha)".to_string(), "(beta)".to_string()];
+    let matcher = PatternMatcher::new(&patterns).unwrap();
+    assert!(matches!(matcher, PatternMatcher::Combined(..)));
+    assert_eq!(matcher.passes(), 1);
+    assert_eq!(matches(&matcher, LINES[0]), BTreeSet::from([0, 1]));
+    assert!(matches(&matcher, LINES[2]).is_empty());
+}
+
+#[test]
+fn test_combined_regex_and_sets_find_the_same_patterns() {
+    // Patterns with groups of their own, as generated for compound and stemmed terms
+    let plan = create_query_plan("whitelist AND firewall AND network", false).unwrap();
+    let patterns: Vec<String> = create_structured_patterns(&plan)
+        .into_iter()
+        .map(|(pattern, _)| pattern)
+        .collect();
+    let combined = PatternMatcher::new(&patterns).unwrap();
+    assert!(matches!(combined, PatternMatcher::Combined(..)));
+    let set = RegexSetBuilder::new(&patterns)
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+    let sets = PatternMatcher::Sets(vec![(0, set)]);
+
+    for line in [
+        "// FirewallConfig configures the network firewall",
+        "type NetworkWhitelist struct {",
+        "// nothing to see here",
+    ] {
+        assert_eq!(matches(&combined, line), matches(&sets, line), "{}", line);
+    }
+    assert!(!matches(&combined, "the network firewall").is_empty());
+}
+
+#[test]
+fn test_large_or_groups_fall_back_to_regex_sets() {
+    let patterns = or_patterns(12);
+    let matcher = PatternMatcher::new(&patterns).unwrap();
+    assert!(matches!(matcher, PatternMatcher::Sets(_)));
+    for line in LINES {
+        assert_eq!(
+            matches(&matcher, line),
+            expected_matches(&patterns, DEFAULT_SIZE_LIMIT, line),
+            "{}",
+            line
+        );
+    }
+}
+
+#[test]
+fn test_sets_are_split_to_fit_the_size_limit() {
+    let patterns = or_patterns(12);
+    let matcher = PatternMatcher::with_size_limit(&patterns, 1 << 20).unwrap();
+    assert!(matcher.passes() > 1);
+    // Patterns too large on their own are left out; the patterns of their terms still match
+    for line in LINES {
+        assert_eq!(
+            matches(&matcher, line),
+            expected_matches(&patterns, 1 << 20, line),
+            "{}",
+            line
+        );
+    }
+}
+
+#[test]
+fn test_invalid_patterns_are_errors() {
+    assert!(PatternMatcher::new(&["(unclosed".to_string()]).is_err());
+    assert!(PatternMatcher::cached(&["(unclosed".to_string()]).is_err());
+}
+
+#[test]
+fn test_cached_matchers_are_reused() {
+    let patterns = vec!["(?:cached)".to_string(), "matcher_reuse".to_string()];
+    let first = PatternMatcher::cached(&patterns).unwrap();
+    let second = PatternMatcher::cached(&patterns).unwrap();
+    assert!(std::sync::Arc::ptr_eq(&first, &second));
+    assert!(second.is_match("a cached line"));
+
+    let other = PatternMatcher::cached(&patterns[..1]).unwrap();
+    assert!(!std::sync::Arc::ptr_eq(&first, &other));
+}
+
+#[test]
+fn test_search_with_a_large_or_group_succeeds() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("lib.rs"),
+        "fn find_needle() -> bool {\n    true\n}\n\nfn other() {}\n",
+    )
+    .unwrap();
+
+    let results = search(temp_dir.path(), &WORDS.join(" OR "));
+    assert_eq!(results, vec![(1, 3)]);
+}
+
+fn search(path: &Path, query: &str) -> Vec<(usize, usize)> {
+    let queries = vec![query.to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        ..Default::default()
+    };
+    perform_probe(&options)
+        .unwrap()
+        .results
+        .iter()
+        .map(|r| r.lines)
+        .collect()
+}
@@ -0,0 +1,71 @@
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, preproc_context: bool) -> Vec<((usize, usize), String)> {
+    let queries = vec!["posix_open".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        preproc_context,
+        ..Default::default()
+    };
+    perform_probe(&options)
+        .unwrap()
+        .results
+        .into_iter()
+        .map(|r| (r.lines, r.code))
+        .collect()
+}
+
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let code = r#"#include <stdio.h>
+
+#ifdef _WIN32
+static int open_file(const char *path)
+{
+    return win_open(path);
+}
+#else
+static int open_file(const char *path)
+{
+    return posix_open(path, 0);
+}
+#endif
+"#;
+    fs::write(temp_dir.path().join("io.c"), code).unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_preproc_context_adds_directive_lines() {
+    let project = create_project();
+
+    let plain = search(project.path(), false);
+    assert_eq!(plain.len(), 1);
+    assert_eq!(plain[0].0, (9, 12));
+    assert!(plain[0].1.starts_with("static int open_file"));
+
+    let with_context = search(project.path(), true);
+    assert_eq!(with_context.len(), 1);
+    assert_eq!(with_context[0].0, (3, 13));
+    let lines: Vec<&str> = with_context[0].1.lines().collect();
+    assert_eq!(
+        lines,
+        vec![
+            "#ifdef _WIN32",
+            "... 4 lines folded ...",
+            "#else",
+            "static int open_file(const char *path)",
+            "{",
+            "    return posix_open(path, 0);",
+            "}",
+            "#endif",
+        ]
+    );
+}
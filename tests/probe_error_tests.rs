@@ -0,0 +1,93 @@
+use probe::error::ProbeError;
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(
+    path: &Path,
+    query: &str,
+    force_language: &[String],
+    sort: &str,
+) -> Result<usize, ProbeError> {
+    let queries = vec![query.to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        force_language,
+        sort,
+        ..Default::default()
+    };
+    perform_probe(&options).map(|limited| limited.results.len())
+}
+
+fn create_project() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("lib.rs"),
+        "fn handler() {\n    println!(\"needle\");\n}\n",
+    )
+    .unwrap();
+    dir
+}
+
+#[test]
+fn test_successful_search_has_no_error() {
+    let project = create_project();
+    assert_eq!(search(project.path(), "needle", &[], "score"), Ok(1));
+}
+
+#[test]
+fn test_invalid_options_are_query_parse_errors() {
+    let project = create_project();
+    let err = search(project.path(), "needle", &[], "sideways").unwrap_err();
+    assert!(matches!(err, ProbeError::QueryParse(_)));
+    assert_eq!(err.kind(), "query_parse");
+    assert!(err.message().contains("sideways"));
+}
+
+#[test]
+fn test_unparseable_query_is_a_query_parse_error() {
+    let project = create_project();
+    let err = search(project.path(), "( )", &[], "score").unwrap_err();
+    assert!(matches!(err, ProbeError::QueryParse(_)));
+    assert!(err.to_string().contains("Failed to parse query"));
+}
+
+#[test]
+fn test_unknown_forced_language_is_a_language_error() {
+    let project = create_project();
+    let force_language = vec!["tpl=klingon".to_string()];
+    let err = search(project.path(), "needle", &force_language, "score").unwrap_err();
+    assert!(matches!(err, ProbeError::Language(_)));
+    assert!(err.message().contains("klingon"));
+}
+
+#[test]
+fn test_anyhow_errors_are_classified_by_their_chain() {
+    use anyhow::Context;
+
+    let io = std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
+    let err: ProbeError = Err::<(), _>(io)
+        .context("Failed to read lib.rs")
+        .unwrap_err()
+        .into();
+    assert_eq!(
+        err,
+        ProbeError::Io("Failed to read lib.rs: gone".to_string())
+    );
+
+    // An error raised as a ProbeError keeps its variant under added context
+    let err: ProbeError = Err::<(), _>(ProbeError::Language("no such language".to_string()))
+        .context("Failed to prepare the search")
+        .unwrap_err()
+        .into();
+    assert_eq!(err.kind(), "language");
+
+    let err: ProbeError = anyhow::anyhow!("something else").into();
+    assert_eq!(err, ProbeError::Other("something else".to_string()));
+}
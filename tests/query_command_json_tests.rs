@@ -92,7 +92,9 @@ fn test_query_json_output_rust_functions() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with JSON output format
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -201,7 +203,9 @@ fn test_query_json_output_javascript_functions() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with JSON output format
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -246,7 +250,9 @@ fn test_query_json_output_with_special_characters() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with JSON output format, searching for the escape function
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -317,7 +323,9 @@ fn test_query_json_output_with_multiple_languages() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with JSON output format, without specifying a language
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -378,7 +386,9 @@ fn test_query_json_output_with_no_results() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with JSON output format, searching for a pattern that doesn't exist
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -92,7 +92,9 @@ fn test_query_xml_output_rust_functions() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with XML output format
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -241,7 +243,9 @@ fn test_query_xml_output_javascript_functions() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with XML output format
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -301,7 +305,9 @@ fn test_query_xml_output_with_special_characters() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with XML output format, searching for the escape function
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -404,7 +410,9 @@ fn test_query_xml_output_with_multiple_languages() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with XML output format, searching for Python functions
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -480,7 +488,9 @@ fn test_query_xml_output_with_no_results() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with XML output format, searching for a pattern that doesn't exist
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -0,0 +1,127 @@
+use probe::models::SearchResult;
+use probe::query_history::{results_digest, HistoryEntry, QueryHistory};
+use std::fs;
+use tempfile::TempDir;
+
+fn result(file: &str, lines: (usize, usize), code: &str) -> SearchResult {
+    SearchResult {
+        file: file.to_string(),
+        file_path: None,
+        lines,
+        node_type: "function_item".to_string(),
+        code: code.to_string(),
+        matched_by_filename: None,
+        rank: None,
+        score: None,
+        tfidf_score: None,
+        bm25_score: None,
+        tfidf_rank: None,
+        bm25_rank: None,
+        new_score: None,
+        hybrid2_rank: None,
+        combined_score_rank: None,
+        file_unique_terms: None,
+        file_total_matches: None,
+        file_match_rank: None,
+        block_unique_terms: None,
+        block_total_matches: None,
+        parent_file_id: None,
+        block_id: None,
+        matched_keywords: None,
+        tokenized_content: None,
+        merged_from: None,
+        normalized_score: None,
+        usage_kind: None,
+        via_symlink: false,
+        changed_lines: None,
+        symbol_name: None,
+        language: None,
+        extraction_confidence: None,
+        partial_parse: false,
+        deprecated: false,
+        embedded: None,
+        kind: None,
+        complexity: None,
+        content_hash: None,
+    }
+}
+
+fn entry(query: &str, digest: &str) -> HistoryEntry {
+    HistoryEntry {
+        timestamp: 0,
+        cwd: ".".to_string(),
+        args: vec!["search".to_string(), query.to_string()],
+        query: query.to_string(),
+        path: ".".to_string(),
+        result_count: 1,
+        digest: digest.to_string(),
+    }
+}
+
+#[test]
+fn test_results_digest_tracks_blocks_and_order() {
+    let a = result("a.rs", (1, 3), "fn a() {}");
+    let b = result("b.rs", (1, 3), "fn b() {}");
+
+    let digest = results_digest(&[a.clone(), b.clone()]);
+    assert_eq!(digest.len(), 16);
+    assert_eq!(digest, results_digest(&[a.clone(), b.clone()]));
+    assert_ne!(digest, results_digest(&[b.clone(), a.clone()]));
+    assert_ne!(digest, results_digest(std::slice::from_ref(&a)));
+    assert_ne!(
+        digest,
+        results_digest(&[a, result("b.rs", (1, 3), "fn b() { edited() }")])
+    );
+}
+
+#[test]
+fn test_history_is_kept_per_repository() {
+    let history_dir = TempDir::new().unwrap();
+    let workspace = TempDir::new().unwrap();
+    let repo = workspace.path().join("repo");
+    let other = workspace.path().join("other");
+    fs::create_dir_all(repo.join(".git")).unwrap();
+    fs::create_dir_all(repo.join("src")).unwrap();
+    fs::create_dir_all(&other).unwrap();
+
+    // Searches anywhere in a repository share its history
+    let history = QueryHistory::for_repository(history_dir.path(), &repo);
+    let nested = QueryHistory::for_repository(history_dir.path(), &repo.join("src"));
+    assert_eq!(history.file(), nested.file());
+    assert_ne!(
+        history.file(),
+        QueryHistory::for_repository(history_dir.path(), &other).file()
+    );
+
+    assert!(history.entries().unwrap().is_empty());
+    history.record(&entry("retry", "0000000000000001")).unwrap();
+    nested
+        .record(&entry("backoff", "0000000000000002"))
+        .unwrap();
+
+    let entries = history.entries().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].query, "retry");
+    assert_eq!(history.entry(2).unwrap().query, "backoff");
+    assert!(history.entry(0).is_err());
+    assert!(history.entry(3).is_err());
+}
+
+#[test]
+fn test_history_keeps_only_the_latest_entries() {
+    let history_dir = TempDir::new().unwrap();
+    let repo = TempDir::new().unwrap();
+    let history = QueryHistory::for_repository(history_dir.path(), repo.path()).with_max_entries(2);
+
+    for query in ["first", "second", "third"] {
+        history.record(&entry(query, "0000000000000001")).unwrap();
+    }
+
+    let queries: Vec<String> = history
+        .entries()
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.query)
+        .collect();
+    assert_eq!(queries, vec!["second", "third"]);
+}
@@ -0,0 +1,139 @@
+use probe::error::ProbeError;
+use probe::search::query_limits::{nesting_depth, MAX_QUERY_NESTING};
+use probe::search::{perform_probe, EngineOptions, ProbeEngine, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, query: &str, max_query_terms: usize) -> Result<usize, ProbeError> {
+    let queries = vec![query.to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        max_query_terms,
+        ..Default::default()
+    };
+    perform_probe(&options).map(|limited| limited.results.len())
+}
+
+fn create_project() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("lib.rs"),
+        "fn handler() {\n    println!(\"needle\");\n}\n",
+    )
+    .unwrap();
+    dir
+}
+
+/// `count` distinct words joined by `separator`
+fn word_query(count: usize, separator: &str) -> String {
+    (0..count)
+        .map(|i| {
+            // Letters only, so the words are not split on digits
+            let mut word = String::new();
+            let mut n = i;
+            for _ in 0..4 {
+                word.push((b'a' + (n % 26) as u8) as char);
+                n /= 26;
+            }
+            word
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+#[test]
+fn test_nesting_depth_ignores_quoted_parentheses() {
+    assert_eq!(nesting_depth("needle"), 0);
+    assert_eq!(nesting_depth("(a OR b) AND (c OR (d AND e))"), 2);
+    assert_eq!(nesting_depth("\"((not a group))\" OR x"), 0);
+    assert_eq!(nesting_depth("\"say \\\"(\\\"\" (a)"), 1);
+}
+
+#[test]
+fn test_small_queries_pass_the_guard() {
+    let project = create_project();
+    assert_eq!(search(project.path(), "needle OR handler", 128), Ok(1));
+    assert_eq!(search(project.path(), "((needle))", 128), Ok(1));
+}
+
+#[test]
+fn test_too_many_terms_are_rejected() {
+    let project = create_project();
+    let query = format!("needle {}", word_query(200, " "));
+
+    let err = search(project.path(), &query, 128).unwrap_err();
+    assert!(matches!(err, ProbeError::LimitExceeded(_)));
+    assert!(err.message().contains("more than the limit of 128"));
+    assert!(err.message().contains("--max-query-terms"));
+
+    // The limit can be raised for queries that really need the terms
+    assert!(search(project.path(), &query, 500).is_ok());
+}
+
+#[test]
+fn test_deep_nesting_is_rejected() {
+    let project = create_project();
+    let depth = MAX_QUERY_NESTING + 1;
+    let query = format!("{}needle{}", "(".repeat(depth), ")".repeat(depth));
+
+    let err = search(project.path(), &query, 128).unwrap_err();
+    assert!(matches!(err, ProbeError::LimitExceeded(_)));
+    assert!(err.message().contains(&format!("{} levels deep", depth)));
+}
+
+#[test]
+fn test_large_or_groups_are_rejected() {
+    let project = create_project();
+    // Each OR doubles the patterns, so a few dozen terms would not fit in memory
+    let query = format!("needle OR {}", word_query(40, " OR "));
+
+    let err = search(project.path(), &query, 128).unwrap_err();
+    assert!(matches!(err, ProbeError::LimitExceeded(_)));
+    assert!(err.message().contains("KB of search patterns"));
+
+    assert_eq!(
+        search(
+            project.path(),
+            &format!("needle OR {}", word_query(5, " OR ")),
+            128
+        ),
+        Ok(1)
+    );
+}
+
+#[test]
+fn test_zero_term_limit_is_invalid() {
+    let project = create_project();
+    let err = search(project.path(), "needle", 0).unwrap_err();
+    assert!(matches!(err, ProbeError::QueryParse(_)));
+    assert!(err.message().contains("--max-query-terms"));
+}
+
+#[test]
+fn test_engine_applies_its_term_limit() {
+    let project = create_project();
+    let query = format!("needle {}", word_query(20, " "));
+
+    let engine = ProbeEngine::with_options(
+        project.path(),
+        EngineOptions {
+            max_query_terms: 10,
+            ..EngineOptions::default()
+        },
+    )
+    .unwrap();
+    assert!(matches!(
+        engine.search(&query),
+        Err(ProbeError::LimitExceeded(_))
+    ));
+    assert!(ProbeEngine::new(project.path())
+        .unwrap()
+        .search(&query)
+        .is_ok());
+}
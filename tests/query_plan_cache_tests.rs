@@ -0,0 +1,60 @@
+use probe::search::{perform_probe_prepared, prepare_query, ProbeEngine, SearchOptions};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+fn create_project(function: &str) -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("main.rs"),
+        format!("fn {}() {{\n    println!(\"needle\");\n}}\n", function),
+    )
+    .unwrap();
+    temp_dir
+}
+
+fn options<'a>(path: &'a Path, custom_ignores: &'a [String]) -> SearchOptions<'a> {
+    SearchOptions {
+        path,
+        custom_ignores,
+        exclude_filenames: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_repeated_query_reuses_the_cached_plan() {
+    let queries = vec!["needle AND cached_plan_marker".to_string()];
+    let first = prepare_query(&queries, false).unwrap();
+    let second = prepare_query(&queries, false).unwrap();
+    assert!(Arc::ptr_eq(&first, &second));
+    assert!(!first.patterns.is_empty());
+
+    // Exact and non-exact queries are planned separately
+    let exact = prepare_query(&queries, true).unwrap();
+    assert!(!Arc::ptr_eq(&first, &exact));
+    assert!(exact.exact);
+}
+
+#[test]
+fn test_prepared_query_is_reused_across_roots() {
+    let first = create_project("first_handler");
+    let second = create_project("second_handler");
+    let prepared = prepare_query(&["needle".to_string()], false).unwrap();
+    let custom_ignores: Vec<String> = vec![];
+
+    for project in [&first, &second] {
+        let results =
+            perform_probe_prepared(&options(project.path(), &custom_ignores), &prepared, None)
+                .unwrap();
+        assert_eq!(results.results.len(), 1);
+        assert!(results.results[0]
+            .file
+            .starts_with(&*project.path().to_string_lossy()));
+
+        let engine = ProbeEngine::new(project.path()).unwrap();
+        let results = engine.search_prepared(&prepared).unwrap();
+        assert_eq!(results.results.len(), 1);
+    }
+}
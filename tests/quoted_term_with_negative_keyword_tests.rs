@@ -71,20 +71,12 @@ fn test_quoted_term_with_negative_keyword() {
     let options = SearchOptions {
         path: temp_path,
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "hybrid",
         frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
-        exact: false,
-        no_merge: false,
         merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Print the query for debugging
@@ -150,20 +142,12 @@ fn test_negative_quoted_term() {
     let options = SearchOptions {
         path: temp_path,
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "hybrid",
         frequency_search: false,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
-        exact: false,
-        no_merge: false,
         merge_threshold: Some(5),
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Print the query for debugging
@@ -0,0 +1,104 @@
+use probe::models::SkipReason;
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn create_test_files(dir: &Path) {
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::create_dir_all(dir.join("node_modules/lib")).unwrap();
+    fs::create_dir_all(dir.join("generated")).unwrap();
+
+    let source = "fn needle_handler() {\n    println!(\"needle\");\n}\n";
+    fs::write(dir.join("src/main.rs"), source).unwrap();
+    fs::write(dir.join("src/other.rs"), source.replace("handler", "other")).unwrap();
+    fs::write(dir.join("src/main_test.rs"), source).unwrap();
+    fs::write(dir.join("generated/output.rs"), source).unwrap();
+    fs::write(
+        dir.join("node_modules/lib/index.js"),
+        "function needle() {}\n",
+    )
+    .unwrap();
+
+    // Invalid UTF-8 content that still contains the search term
+    let mut binary = b"needle ".to_vec();
+    binary.extend_from_slice(&[0xff, 0xfe, 0x00, 0x80]);
+    fs::write(dir.join("src/data.rs"), binary).unwrap();
+}
+
+#[test]
+fn test_report_skipped_lists_reasons() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let queries = vec!["needle".to_string()];
+    let custom_ignores = vec!["generated".to_string()];
+
+    let options = SearchOptions {
+        path: temp_dir.path(),
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        max_results: Some(1),
+        no_merge: true,
+        report_skipped: true,
+        ..Default::default()
+    };
+
+    let results = perform_probe(&options).unwrap();
+    let report = results
+        .skip_report
+        .expect("Skip report should be populated when requested");
+
+    let reason_for = |suffix: &str| {
+        report
+            .iter()
+            .find(|s| s.path.ends_with(suffix))
+            .map(|s| s.reason.clone())
+    };
+
+    assert_eq!(
+        reason_for("generated"),
+        Some(SkipReason::CustomIgnore("generated".to_string()))
+    );
+    assert_eq!(
+        reason_for("node_modules"),
+        Some(SkipReason::DefaultIgnore("node_modules".to_string()))
+    );
+    assert_eq!(reason_for("src/main_test.rs"), Some(SkipReason::TestFilter));
+    assert_eq!(reason_for("src/data.rs"), Some(SkipReason::Binary));
+
+    // Only one of the two matching source files fits within --max-results
+    assert_eq!(results.results.len(), 1);
+    let limited: Vec<_> = report
+        .iter()
+        .filter(|s| s.reason == SkipReason::Limit)
+        .collect();
+    assert_eq!(limited.len(), 1, "One file should be dropped by the limit");
+
+    // Files that made it into the results are never reported as skipped
+    for result in &results.results {
+        assert!(!report.iter().any(|s| s.path == Path::new(&result.file)));
+    }
+}
+
+#[test]
+fn test_no_skip_report_by_default() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+
+    let options = SearchOptions {
+        path: temp_dir.path(),
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        ..Default::default()
+    };
+
+    let results = perform_probe(&options).unwrap();
+    assert!(results.skip_report.is_none());
+}
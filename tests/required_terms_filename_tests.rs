@@ -65,11 +65,15 @@ fn test_required_terms_with_filename_matching() {
         .unwrap();
 
     // Run the search with the query "api +load +process"
+    let history_dir = tempdir().unwrap();
     let output = std::process::Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
             "search",
+            "--format",
+            "color",
             "api +load +process",
             temp_path.to_str().unwrap(),
         ])
@@ -0,0 +1,154 @@
+use probe::restrict_root::{RestrictRoot, RESTRICT_ROOT_ENV};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+fn run_probe(dir: &Path, args: &[&str], env_root: Option<&Path>) -> Output {
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+    let history_dir = TempDir::new().unwrap();
+    let mut command = Command::new("cargo");
+    command
+        .env("PROBE_HISTORY_DIR", history_dir.path())
+        .args(["run", "--manifest-path", manifest.to_str().unwrap(), "--"])
+        .args(args)
+        .current_dir(dir)
+        .env_remove(RESTRICT_ROOT_ENV);
+    if let Some(root) = env_root {
+        command.env(RESTRICT_ROOT_ENV, root);
+    }
+    command.output().expect("Failed to execute command")
+}
+
+/// A project with `root/sub/lib.rs` and a file in `outside/` next to the root
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::create_dir_all(temp_dir.path().join("root/sub")).unwrap();
+    fs::create_dir_all(temp_dir.path().join("outside")).unwrap();
+    fs::write(
+        temp_dir.path().join("root/sub/lib.rs"),
+        "fn parse_config() -> u32 {\n    42\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("outside/secret.rs"),
+        "fn parse_secret() -> u32 {\n    7\n}\n",
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_paths_resolving_outside_the_root_are_rejected() {
+    let project = create_project();
+    let root = project.path().join("root");
+    let restriction = RestrictRoot::from_options(Some(&root)).unwrap().unwrap();
+
+    assert!(restriction.contains(&root));
+    assert!(restriction.contains(&root.join("sub/lib.rs")));
+    assert!(restriction.contains(&root.join("sub/../sub/lib.rs")));
+    assert!(!restriction.contains(&root.join("../outside/secret.rs")));
+    assert!(!restriction.contains(project.path()));
+
+    // Paths that don't exist yet resolve through their nearest existing ancestor
+    assert!(restriction.contains(&root.join("sub/new/results.json")));
+    assert!(!restriction.contains(&root.join("sub/new/../../../outside")));
+
+    let err = restriction
+        .check(&root.join("../outside/secret.rs"))
+        .unwrap_err();
+    assert!(err.to_string().contains("outside the restricted root"));
+}
+
+#[test]
+fn test_restrict_root_must_be_an_existing_directory() {
+    let project = create_project();
+    assert!(RestrictRoot::from_options(Some(&project.path().join("missing"))).is_err());
+    assert!(RestrictRoot::from_options(Some(&project.path().join("root/sub/lib.rs"))).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_symlinks_out_of_the_root_are_rejected() {
+    let project = create_project();
+    let root = project.path().join("root");
+    std::os::unix::fs::symlink(project.path().join("outside"), root.join("link")).unwrap();
+    let restriction = RestrictRoot::from_options(Some(&root)).unwrap().unwrap();
+
+    assert!(!restriction.contains(&root.join("link")));
+    assert!(!restriction.contains(&root.join("link/secret.rs")));
+}
+
+#[test]
+fn test_commands_reject_paths_outside_the_root() {
+    let project = create_project();
+    let root = project.path().join("root");
+
+    let output = run_probe(
+        &root,
+        &["search", "parse", "..", "--restrict-root", "."],
+        None,
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("outside the restricted root"));
+
+    let output = run_probe(
+        &root,
+        &["extract", "../outside/secret.rs", "--restrict-root", "."],
+        None,
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("outside the restricted root"));
+
+    // The environment variable applies without the flag
+    let output = run_probe(
+        &root,
+        &["query", "fn $NAME() -> u32 { $$$BODY }", ".."],
+        Some(&root),
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("outside the restricted root"));
+
+    let output = run_probe(
+        &root,
+        &[
+            "search",
+            "parse_config",
+            ".",
+            "--restrict-root",
+            ".",
+            "--format",
+            "json",
+        ],
+        None,
+    );
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("fn parse_config"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_search_drops_files_reached_through_symlinks_out_of_the_root() {
+    let project = create_project();
+    let root = project.path().join("root");
+    std::os::unix::fs::symlink(project.path().join("outside"), root.join("link")).unwrap();
+
+    let output = run_probe(
+        &root,
+        &[
+            "search",
+            "parse",
+            ".",
+            "--follow-symlinks",
+            "--restrict-root",
+            ".",
+            "--format",
+            "json",
+        ],
+        None,
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("fn parse_config"));
+    assert!(!stdout.contains("parse_secret"));
+}
@@ -0,0 +1,121 @@
+use probe::models::{ResultKind, SearchResult};
+use probe::search::result_kind::parse_kinds;
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, query: &str, kinds: &[String]) -> Vec<SearchResult> {
+    let queries = vec![query.to_string()];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        exclude_filenames: true,
+        allow_tests: true,
+        no_merge: true,
+        kinds,
+        ..Default::default()
+    };
+    let mut results = perform_probe(&options).unwrap().results;
+    results.sort_by_key(|r| (r.file.clone(), r.lines));
+    results
+}
+
+fn kinds_by_line(results: &[SearchResult]) -> Vec<(usize, Option<&'static str>)> {
+    results
+        .iter()
+        .map(|r| (r.lines.0, r.kind.map(|kind| kind.as_str())))
+        .collect()
+}
+
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("config.rs"),
+        r#"use crate::store::load_config;
+
+pub fn load_config(path: &str) -> Config {
+    parse(path)
+}
+
+pub fn start() {
+    let config = load_config("app.toml");
+    run(config);
+}
+
+pub fn describe() -> &'static str {
+    "calls load_config at startup"
+}
+
+pub fn noted() {
+    // load_config runs before this
+    run_all();
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_defaults() {
+        assert!(super::load_config("x").ok);
+    }
+}
+"#,
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("app.js"),
+        "import { loadConfig } from './store';\n\nexport function start() {\n  return loadConfig('app.json');\n}\n",
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_parse_kinds() {
+    assert_eq!(
+        parse_kinds(&["call_site".to_string(), "test".to_string()]),
+        Ok(vec![ResultKind::CallSite, ResultKind::Test])
+    );
+    assert_eq!(
+        parse_kinds(&["call-site".to_string()]),
+        Err("call-site".to_string())
+    );
+}
+
+#[test]
+fn test_results_are_classified_from_syntax() {
+    let temp_dir = create_project();
+
+    let results = search(temp_dir.path(), "load_config", &[]);
+    assert_eq!(
+        kinds_by_line(&results),
+        vec![
+            // app.js, whose loadConfig is the same symbol
+            (1, Some("import")),
+            (3, Some("call_site")),
+            // config.rs
+            (1, Some("import")),
+            (3, Some("definition")),
+            (7, Some("call_site")),
+            (12, Some("string")),
+            (16, Some("comment")),
+            (24, Some("test")),
+        ]
+    );
+}
+
+#[test]
+fn test_kind_filter_keeps_requested_kinds() {
+    let temp_dir = create_project();
+
+    let kinds = vec!["call_site".to_string(), "test".to_string()];
+    let results = search(temp_dir.path(), "load_config", &kinds);
+    assert_eq!(
+        kinds_by_line(&results),
+        vec![
+            (3, Some("call_site")),
+            (7, Some("call_site")),
+            (24, Some("test"))
+        ]
+    );
+}
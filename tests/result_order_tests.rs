@@ -0,0 +1,101 @@
+use probe::models::{LimitedSearchResults, SkipReason};
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, sort: &str, max_results: Option<usize>) -> LimitedSearchResults {
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        max_results,
+        no_merge: true,
+        report_skipped: true,
+        sort,
+        ..Default::default()
+    };
+    perform_probe(&options).unwrap()
+}
+
+/// Creates enough files that processing them takes several batches
+fn create_files(dir: &Path) -> usize {
+    let count = rayon::current_num_threads() * 12;
+    for index in 0..count {
+        fs::write(
+            dir.join(format!("file_{:04}.rs", index)),
+            format!("fn caller_{index}() {{\n    needle();\n}}\n"),
+        )
+        .unwrap();
+    }
+    count
+}
+
+fn locations(results: &LimitedSearchResults) -> Vec<(String, (usize, usize))> {
+    results
+        .results
+        .iter()
+        .map(|result| (result.file.clone(), result.lines))
+        .collect()
+}
+
+#[test]
+fn test_sort_path_returns_results_in_path_order() {
+    let temp_dir = TempDir::new().unwrap();
+    let count = create_files(temp_dir.path());
+
+    let all = search(temp_dir.path(), "path", None);
+    assert_eq!(all.results.len(), count);
+    let mut sorted = locations(&all);
+    sorted.sort();
+    assert_eq!(locations(&all), sorted);
+
+    // Stopping early returns the same results as limiting the full result set
+    let limited = search(temp_dir.path(), "path", Some(3));
+    assert_eq!(locations(&limited), sorted[..3].to_vec());
+}
+
+#[test]
+fn test_sort_path_stops_processing_once_max_results_is_filled() {
+    let temp_dir = TempDir::new().unwrap();
+    let count = create_files(temp_dir.path());
+
+    let results = search(temp_dir.path(), "path", Some(3));
+    let report = results.skip_report.unwrap();
+    let last_file = format!("file_{:04}.rs", count - 1);
+    let last = report
+        .iter()
+        .find(|skipped| skipped.path.ends_with(&last_file))
+        .expect("The last file should not have been processed");
+    assert_eq!(last.reason, SkipReason::Limit);
+}
+
+#[test]
+fn test_sort_path_compares_paths_by_component() {
+    let temp_dir = TempDir::new().unwrap();
+    // As strings "a-b/" sorts before "a/", as paths after it
+    let count = rayon::current_num_threads() * 12;
+    for index in 1..=count {
+        let dir = temp_dir.path().join("a").join(format!("d{}", index));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("x.rs"), "fn caller() {\n    needle();\n}\n").unwrap();
+    }
+    fs::create_dir(temp_dir.path().join("a-b")).unwrap();
+    fs::write(
+        temp_dir.path().join("a-b/y.rs"),
+        "fn caller() {\n    needle();\n}\n",
+    )
+    .unwrap();
+
+    let all = search(temp_dir.path(), "path", None);
+    assert_eq!(all.results.len(), count + 1);
+    assert!(Path::new(&all.results[0].file).ends_with("a/d1/x.rs"));
+    assert!(Path::new(&all.results[count].file).ends_with("a-b/y.rs"));
+
+    // Stopping early returns the same results as limiting the full result set
+    let limited = search(temp_dir.path(), "path", Some(1));
+    assert_eq!(locations(&limited), locations(&all)[..1].to_vec());
+}
@@ -0,0 +1,92 @@
+use probe::models::{MergedBlockInfo, SearchResult};
+use probe::search::score_normalization::normalize_scores;
+
+fn create_result(node_type: &str, lines: (usize, usize), score: Option<f64>) -> SearchResult {
+    SearchResult {
+        file: "src/lib.rs".to_string(),
+        file_path: None,
+        lines,
+        node_type: node_type.to_string(),
+        code: String::new(),
+        matched_by_filename: None,
+        rank: None,
+        score,
+        normalized_score: None,
+        tfidf_score: None,
+        bm25_score: score,
+        tfidf_rank: None,
+        bm25_rank: None,
+        new_score: None,
+        hybrid2_rank: None,
+        combined_score_rank: None,
+        file_unique_terms: None,
+        file_total_matches: None,
+        file_match_rank: None,
+        block_unique_terms: None,
+        block_total_matches: None,
+        parent_file_id: None,
+        block_id: None,
+        matched_keywords: None,
+        tokenized_content: None,
+        merged_from: None,
+        usage_kind: None,
+        via_symlink: false,
+        changed_lines: None,
+        symbol_name: None,
+        language: None,
+        extraction_confidence: None,
+        partial_parse: false,
+        deprecated: false,
+        embedded: None,
+        kind: None,
+        complexity: None,
+        content_hash: None,
+    }
+}
+
+#[test]
+fn test_normalized_scores_are_within_unit_range() {
+    let mut results = vec![
+        create_result("function", (1, 5), Some(12.0)),
+        create_result("function", (10, 20), Some(3.0)),
+        create_result("struct_item", (30, 40), Some(0.0)),
+        create_result("impl_item", (50, 60), None),
+    ];
+
+    normalize_scores(&mut results);
+
+    let scores: Vec<f64> = results
+        .iter()
+        .map(|r| r.normalized_score.unwrap())
+        .collect();
+    assert_eq!(scores, vec![1.0, 0.25, 0.0, 0.0]);
+}
+
+#[test]
+fn test_each_result_kind_is_normalized_separately() {
+    let mut merged = create_result("function", (100, 140), Some(40.0));
+    merged.merged_from = Some(vec![MergedBlockInfo {
+        block_id: None,
+        lines: (100, 120),
+        node_type: "function".to_string(),
+        rank: Some(1),
+        score: Some(40.0),
+    }]);
+
+    let mut results = vec![
+        create_result("file", (1, 500), Some(200.0)),
+        create_result("file", (1, 300), Some(100.0)),
+        merged,
+        create_result("function", (1, 5), Some(4.0)),
+        create_result("function", (10, 20), Some(2.0)),
+    ];
+
+    normalize_scores(&mut results);
+
+    // The best result of every kind is scaled to 1.0, regardless of raw magnitude
+    assert_eq!(results[0].normalized_score, Some(1.0));
+    assert_eq!(results[1].normalized_score, Some(0.5));
+    assert_eq!(results[2].normalized_score, Some(1.0));
+    assert_eq!(results[3].normalized_score, Some(1.0));
+    assert_eq!(results[4].normalized_score, Some(0.5));
+}
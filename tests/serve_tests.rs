@@ -0,0 +1,330 @@
+use probe::search::{EngineOptions, ProbeEngine};
+use probe::serve::{AppliedChanges, SearchServer};
+use serde_json::Value;
+use std::fs;
+use std::io::{BufRead, BufReader, Cursor, Read, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+fn create_project() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(
+        dir.path().join("src/alpha.rs"),
+        "fn alpha_handler() {\n    find_needle();\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("src/beta.rs"),
+        "fn beta_handler() {\n    find_haystack();\n}\n",
+    )
+    .unwrap();
+    dir
+}
+
+fn result_files(output: &Value) -> Vec<String> {
+    let mut files: Vec<String> = output["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| {
+            Path::new(r["file"].as_str().unwrap())
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// Apply watched changes until `done` holds for them, as events arrive a little later
+fn wait_for_changes(server: &SearchServer, done: impl Fn(AppliedChanges) -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        if done(server.apply_changes().unwrap()) {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    panic!("The watcher reported no matching changes");
+}
+
+/// Apply watched changes until the webhook at `listener` receives a payload, and answer it
+fn receive_webhook(server: &SearchServer, listener: &TcpListener) -> Value {
+    listener.set_nonblocking(true).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut stream = loop {
+        server.apply_changes().unwrap();
+        match listener.accept() {
+            Ok((stream, _)) => break stream,
+            Err(_) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(50)),
+            Err(e) => panic!("The webhook received nothing: {}", e),
+        }
+    };
+    stream.set_nonblocking(false).unwrap();
+
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).unwrap();
+        if header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().unwrap();
+            }
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+    stream
+        .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+        .unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[test]
+fn test_engine_reuses_parse_trees_of_unchanged_files() {
+    let dir = create_project();
+    let engine = ProbeEngine::with_options(
+        dir.path(),
+        EngineOptions {
+            cache_trees: true,
+            ..EngineOptions::default()
+        },
+    )
+    .unwrap();
+    let counts = || {
+        let trees = engine.tree_cache().unwrap().lock().unwrap();
+        (trees.hits(), trees.misses())
+    };
+
+    engine.search("needle").unwrap();
+    let (hits, misses) = counts();
+    assert_eq!(hits, 0);
+    assert!(misses > 0);
+
+    engine.search("needle").unwrap();
+    let (hits, again) = counts();
+    assert!(hits > 0);
+    assert_eq!(again, misses);
+
+    // Changed content is parsed again
+    fs::write(
+        dir.path().join("src/alpha.rs"),
+        "fn alpha_handler() {\n    find_needle_again();\n}\n",
+    )
+    .unwrap();
+    engine.search("needle").unwrap();
+    assert!(counts().1 > misses);
+
+    assert!(ProbeEngine::new(dir.path()).unwrap().tree_cache().is_none());
+}
+
+#[test]
+fn test_server_answers_request_lines() {
+    let dir = create_project();
+    let server = SearchServer::new(dir.path(), EngineOptions::default()).unwrap();
+    let input = [
+        "needle",
+        "",
+        r#"{"query": "handler", "session": "s1"}"#,
+        r#"{"query": "handler", "session": "s1"}"#,
+        r#"{"session": "s1"}"#,
+    ]
+    .join("\n");
+    let mut output = Vec::new();
+    server.serve(Cursor::new(input), &mut output).unwrap();
+
+    let responses: Vec<Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(responses.len(), 4);
+    assert_eq!(result_files(&responses[0]), vec!["alpha.rs"]);
+    assert!(responses[0]["summary"]["elapsed_ms"].is_number());
+
+    // The session skips the blocks it already returned
+    assert_eq!(responses[1]["session"], "s1");
+    assert_eq!(result_files(&responses[1]), vec!["alpha.rs", "beta.rs"]);
+    assert!(result_files(&responses[2]).is_empty());
+    assert!(responses[2]["cached_blocks_skipped"].as_u64().unwrap() > 0);
+
+    assert!(responses[3]["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("query"));
+}
+
+#[test]
+fn test_watching_server_applies_file_changes() {
+    let dir = create_project();
+    let mut server = SearchServer::new(dir.path(), EngineOptions::default()).unwrap();
+    server.watch().unwrap();
+    assert_eq!(server.engine().read().unwrap().file_count(), 2);
+
+    // An added file walks the directory again
+    fs::write(
+        dir.path().join("src/gamma.rs"),
+        "fn gamma_handler() {\n    find_needle();\n}\n",
+    )
+    .unwrap();
+    wait_for_changes(&server, |changes| changes.walked);
+    assert_eq!(server.engine().read().unwrap().file_count(), 3);
+    assert_eq!(
+        result_files(&server.handle_request("needle")),
+        vec!["alpha.rs", "gamma.rs"]
+    );
+
+    // A changed file is counted and parsed again
+    fs::write(
+        dir.path().join("src/beta.rs"),
+        "fn beta_handler() {\n    find_needle_too();\n}\n",
+    )
+    .unwrap();
+    wait_for_changes(&server, |changes| changes.updated > 0);
+    assert_eq!(
+        result_files(&server.handle_request("needle")),
+        vec!["alpha.rs", "beta.rs", "gamma.rs"]
+    );
+
+    fs::remove_file(dir.path().join("src/alpha.rs")).unwrap();
+    wait_for_changes(&server, |changes| changes.walked);
+    assert_eq!(server.engine().read().unwrap().file_count(), 2);
+}
+
+#[test]
+fn test_webhook_receives_only_new_results() {
+    let dir = create_project();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let mut server = SearchServer::new(dir.path(), EngineOptions::default()).unwrap();
+    server.watch().unwrap();
+    server.notify(&url, &["needle".to_string()]).unwrap();
+
+    // alpha.rs matched before any change, so only the added file is sent
+    fs::write(
+        dir.path().join("src/gamma.rs"),
+        "fn gamma_handler() {\n    find_needle();\n}\n",
+    )
+    .unwrap();
+    let payload = receive_webhook(&server, &listener);
+    assert_eq!(payload["query"], "needle");
+    assert_eq!(result_files(&payload), vec!["gamma.rs"]);
+    assert_eq!(payload["summary"]["count"], 1);
+}
+
+/// Helper function to answer request lines from one client, returning the responses
+fn serve_lines(server: &SearchServer, lines: &[&str]) -> Vec<Value> {
+    let mut output = Vec::new();
+    server
+        .serve(Cursor::new(lines.join("\n")), &mut output)
+        .unwrap();
+    String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_server_requires_its_token() {
+    let dir = create_project();
+    let mut server = SearchServer::new(dir.path(), EngineOptions::default()).unwrap();
+    server.require_token("s3cret");
+
+    let responses = serve_lines(
+        &server,
+        &[
+            "needle",
+            r#"{"query": "needle", "token": "wrong!"}"#,
+            r#"{"query": "needle", "token": "s3cret"}"#,
+        ],
+    );
+    assert_eq!(responses[0]["error"]["kind"], "unauthorized");
+    assert_eq!(responses[1]["error"]["kind"], "unauthorized");
+    assert_eq!(result_files(&responses[2]), vec!["alpha.rs"]);
+}
+
+#[test]
+fn test_server_limits_requests_per_token() {
+    let dir = create_project();
+    let mut server = SearchServer::new(dir.path(), EngineOptions::default()).unwrap();
+    server.require_token("s3cret");
+    server.limit_rate(2);
+
+    let request = r#"{"query": "needle", "token": "s3cret"}"#;
+    let responses = serve_lines(&server, &[request, request]);
+    assert_eq!(result_files(&responses[1]), vec!["alpha.rs"]);
+
+    // Connecting again doesn't start a new count for the token
+    let responses = serve_lines(&server, &[request]);
+    assert_eq!(responses[0]["error"]["kind"], "limit_exceeded");
+
+    // Requests without the token are counted together, whatever token they guess
+    let responses = serve_lines(
+        &server,
+        &[
+            "needle",
+            r#"{"query": "needle", "token": "guess1"}"#,
+            r#"{"query": "needle", "token": "guess2"}"#,
+        ],
+    );
+    assert_eq!(responses[0]["error"]["kind"], "unauthorized");
+    assert_eq!(responses[1]["error"]["kind"], "unauthorized");
+    assert_eq!(responses[2]["error"]["kind"], "limit_exceeded");
+}
+
+#[test]
+fn test_request_paths_limit_the_searched_files() {
+    let dir = create_project();
+    let server = SearchServer::new(dir.path(), EngineOptions::default()).unwrap();
+
+    let responses = serve_lines(
+        &server,
+        &[
+            r#"{"query": "handler", "paths": ["src/beta.rs"]}"#,
+            r#"{"query": "handler", "paths": ["src"]}"#,
+            r#"{"query": "handler", "paths": ["docs"]}"#,
+            r#"{"query": "handler", "paths": ["src/../.."]}"#,
+            r#"{"query": "handler", "paths": ["/etc"]}"#,
+        ],
+    );
+    assert_eq!(result_files(&responses[0]), vec!["beta.rs"]);
+    assert_eq!(result_files(&responses[1]), vec!["alpha.rs", "beta.rs"]);
+    assert!(result_files(&responses[2]).is_empty());
+    assert_eq!(responses[3]["error"]["kind"], "query_parse");
+    assert_eq!(responses[4]["error"]["kind"], "query_parse");
+}
+
+#[test]
+fn test_allowed_paths_limit_every_request() {
+    let dir = create_project();
+    fs::create_dir(dir.path().join("docs")).unwrap();
+    fs::write(dir.path().join("docs/guide.rs"), "fn guide_handler() {}\n").unwrap();
+    let mut server = SearchServer::new(dir.path(), EngineOptions::default()).unwrap();
+    server.allow_paths(&["./src".into()]).unwrap();
+    assert!(server.allow_paths(&["../elsewhere".into()]).is_err());
+
+    let responses = serve_lines(
+        &server,
+        &[
+            "handler",
+            r#"{"query": "handler", "paths": ["src/beta.rs"]}"#,
+            r#"{"query": "handler", "paths": ["docs"]}"#,
+            r#"{"query": "handler", "paths": ["src", "."]}"#,
+        ],
+    );
+    assert_eq!(result_files(&responses[0]), vec!["alpha.rs", "beta.rs"]);
+    assert_eq!(result_files(&responses[1]), vec!["beta.rs"]);
+    assert_eq!(responses[2]["error"]["kind"], "unauthorized");
+    assert_eq!(responses[3]["error"]["kind"], "unauthorized");
+}
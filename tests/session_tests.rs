@@ -0,0 +1,324 @@
+#[cfg(feature = "sqlite")]
+use probe::search::session_store::{SessionStore, SqliteStore};
+use probe::search::{
+    perform_probe_with_session, perform_probe_within_session, SearchOptions, Session,
+};
+use std::fs;
+use std::path::Path;
+#[cfg(feature = "sqlite")]
+use std::sync::Arc;
+use tempfile::TempDir;
+
+fn options<'a>(
+    path: &'a Path,
+    queries: &'a [String],
+    custom_ignores: &'a [String],
+) -> SearchOptions<'a> {
+    SearchOptions {
+        path,
+        queries,
+        custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        ..Default::default()
+    }
+}
+
+fn search(path: &Path, session: &mut Session) -> Vec<probe::models::SearchResult> {
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        max_results: Some(1),
+        ..options(path, &queries, &custom_ignores)
+    };
+    perform_probe_with_session(&options, session)
+        .unwrap()
+        .results
+}
+
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("first.rs"),
+        "fn first_needle() {\n    println!(\"needle\");\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("second.rs"),
+        "fn second_needle() {\n    println!(\"needle\");\n}\n",
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_sessions_are_independent() {
+    let project = create_project();
+    let mut one = Session::new("one");
+    let mut two = Session::new("two");
+
+    let first = search(project.path(), &mut one);
+    assert_eq!(first.len(), 1);
+    assert!(one.has_seen(&first[0]));
+
+    // The next page of the same session skips the block it already returned
+    let second = search(project.path(), &mut one);
+    assert_eq!(second.len(), 1);
+    assert_ne!(second[0].file, first[0].file);
+    assert!(search(project.path(), &mut one).is_empty());
+
+    // Another session starts from the beginning
+    let other = search(project.path(), &mut two);
+    assert_eq!(other.len(), 1);
+    assert_eq!(one.len(), 2);
+    assert_eq!(two.len(), 1);
+}
+
+#[test]
+fn test_stored_session_round_trip() {
+    let project = create_project();
+    let sessions = TempDir::new().unwrap();
+
+    let mut session = Session::create(sessions.path()).unwrap();
+    assert_eq!(session.id().len(), 4);
+    search(project.path(), &mut session);
+
+    // Nothing is written until the session is saved
+    let path = session.path().unwrap();
+    assert!(!path.exists());
+    session.save().unwrap();
+    assert!(path.exists());
+
+    let mut loaded = Session::load(sessions.path(), session.id()).unwrap();
+    assert_eq!(loaded.len(), 1);
+    let next = search(project.path(), &mut loaded);
+    assert_eq!(next.len(), 1);
+    assert!(!session.has_seen(&next[0]));
+
+    loaded.clear().unwrap();
+    assert!(loaded.is_empty());
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_in_memory_session_is_not_written() {
+    let session = Session::new("memory");
+    assert!(session.path().is_none());
+    session.save().unwrap();
+}
+
+#[test]
+fn test_concurrent_saves_to_one_session_keep_every_block() {
+    let project = create_project();
+    let sessions = TempDir::new().unwrap();
+    let id = Session::create(sessions.path()).unwrap().id().to_string();
+
+    // Each writer loads the session before any other has saved, then saves its own page
+    let mut writers: Vec<Session> = (0..2)
+        .map(|_| Session::load(sessions.path(), &id).unwrap())
+        .collect();
+    std::thread::scope(|scope| {
+        for (i, session) in writers.iter_mut().enumerate() {
+            let queries = vec![if i == 0 { "first" } else { "second" }.to_string()];
+            let project = project.path();
+            scope.spawn(move || {
+                let custom_ignores: Vec<String> = vec![];
+                let options = SearchOptions {
+                    path: project,
+                    queries: &queries,
+                    custom_ignores: &custom_ignores,
+                    exclude_filenames: true,
+                    no_merge: true,
+                    ..Default::default()
+                };
+                perform_probe_with_session(&options, session).unwrap();
+                session.save().unwrap();
+            });
+        }
+    });
+
+    let loaded = Session::load(sessions.path(), &id).unwrap();
+    assert_eq!(loaded.len(), 2);
+    // Only the session and its lock file are left behind
+    let mut names: Vec<String> = std::fs::read_dir(sessions.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        vec![format!("{}.json", id), format!("{}.json.lock", id)]
+    );
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_sqlite_store_round_trip() {
+    let project = create_project();
+    let dir = TempDir::new().unwrap();
+    let db = dir.path().join("sessions.db");
+    let store: Arc<dyn SessionStore> = Arc::new(SqliteStore::open(&db).unwrap());
+
+    let mut session = Session::create_in(store.clone()).unwrap();
+    assert!(!store.exists(session.id()).unwrap());
+    search(project.path(), &mut session);
+    session.save().unwrap();
+    assert!(store.exists(session.id()).unwrap());
+
+    // A second connection, as another probe process would open
+    let other: Arc<dyn SessionStore> = Arc::new(SqliteStore::open(&db).unwrap());
+    let mut loaded = Session::load_from(other, session.id()).unwrap();
+    assert_eq!(loaded.len(), 1);
+    let next = search(project.path(), &mut loaded);
+    assert_eq!(next.len(), 1);
+    assert!(!session.has_seen(&next[0]));
+    loaded.save().unwrap();
+    assert_eq!(store.load(session.id()).unwrap().len(), 2);
+
+    loaded.clear().unwrap();
+    assert!(loaded.is_empty());
+    assert!(!store.exists(session.id()).unwrap());
+}
+
+#[test]
+fn test_search_within_session_blocks() {
+    let project = TempDir::new().unwrap();
+    for (name, body) in [
+        ("fetch.rs", "backoff(request)"),
+        ("parse.rs", "decode(request)"),
+        ("upload.rs", "backoff(chunk)"),
+    ] {
+        fs::write(
+            project.path().join(name),
+            format!("fn handle_request() {{\n    {};\n}}\n", body),
+        )
+        .unwrap();
+    }
+
+    // Earlier search: two of the three handlers were returned
+    let queries = vec!["handle_request".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let mut returned = perform_probe_with_session(
+        &options(project.path(), &queries, &custom_ignores),
+        &mut Session::new("earlier"),
+    )
+    .unwrap()
+    .results;
+    assert_eq!(returned.len(), 3);
+    returned.retain(|r| !r.file.ends_with("upload.rs"));
+    let mut within = Session::new("within");
+    within.record(&returned);
+    assert_eq!(within.len(), 2);
+
+    // Refinement: of those, which back off? upload.rs does too but was never returned
+    let queries = vec!["backoff".to_string()];
+    let options = options(project.path(), &queries, &custom_ignores);
+    let results = perform_probe_within_session(&options, &within, None)
+        .unwrap()
+        .results;
+    assert_eq!(results.len(), 1);
+    assert!(results[0].file.ends_with("fetch.rs"));
+
+    // An empty session has nothing to narrow down
+    assert!(perform_probe_within_session(&options, &Session::new("empty"), None).is_err());
+}
+
+#[test]
+fn test_exclude_and_pin_blocks() {
+    let project = create_project();
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let first = project
+        .path()
+        .join("first.rs")
+        .to_string_lossy()
+        .to_string();
+    let second = project
+        .path()
+        .join("second.rs")
+        .to_string_lossy()
+        .to_string();
+
+    // Excluded blocks are dropped and skipped by later searches in the session
+    let exclude_blocks = vec![format!("{}:1-3", first)];
+    let mut session = Session::new("pins");
+    let results = perform_probe_with_session(
+        &SearchOptions {
+            exclude_blocks: &exclude_blocks,
+            ..options(project.path(), &queries, &custom_ignores)
+        },
+        &mut session,
+    )
+    .unwrap()
+    .results;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].file, second);
+    let options = options(project.path(), &queries, &custom_ignores);
+    assert!(perform_probe_with_session(&options, &mut session)
+        .unwrap()
+        .results
+        .is_empty());
+
+    // A pinned block comes back, re-read from disk, although the session has seen it
+    fs::write(
+        project.path().join("second.rs"),
+        "fn second_needle() {\n    println!(\"needle\");\n    println!(\"edited\");\n}\n",
+    )
+    .unwrap();
+    let pin_blocks = vec![format!("{}:1-3", second)];
+    let results = perform_probe_with_session(
+        &SearchOptions {
+            pin_blocks: &pin_blocks,
+            ..options
+        },
+        &mut session,
+    )
+    .unwrap()
+    .results;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].lines, (1, 4));
+    assert!(results[0].code.contains("edited"));
+
+    // Block identifiers must be file:start-end
+    let invalid = vec!["second.rs".to_string()];
+    let options = SearchOptions {
+        pin_blocks: &invalid,
+        ..options
+    };
+    assert!(perform_probe_with_session(&options, &mut session).is_err());
+}
+
+#[test]
+fn test_queries_that_found_nothing_are_cached_until_files_change() {
+    let project = create_project();
+    let sessions = TempDir::new().unwrap();
+    let queries = vec!["haystack".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = options(project.path(), &queries, &custom_ignores);
+    let search = |session: &mut Session| perform_probe_with_session(&options, session).unwrap();
+
+    let mut session = Session::create(sessions.path()).unwrap();
+    let first = search(&mut session);
+    assert!(first.results.is_empty());
+    assert!(!first.empty_query_cached);
+    let again = search(&mut session);
+    assert!(again.results.is_empty());
+    assert!(again.empty_query_cached);
+
+    // Other sessions search as usual, and stored sessions keep the query apart from blocks
+    assert!(!search(&mut Session::new("other")).empty_query_cached);
+    session.save().unwrap();
+    let mut loaded = Session::load(sessions.path(), session.id()).unwrap();
+    assert!(loaded.is_empty());
+    assert!(search(&mut loaded).empty_query_cached);
+
+    // A changed file is searched again
+    fs::write(
+        project.path().join("first.rs"),
+        "fn first_needle() {\n    println!(\"haystack\");\n}\n",
+    )
+    .unwrap();
+    let found = search(&mut loaded);
+    assert_eq!(found.results.len(), 1);
+    assert!(!found.empty_query_cached);
+}
@@ -0,0 +1,142 @@
+use probe::signature::{perform_signature_search, SignatureOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn create_test_files(dir: &Path) {
+    fs::write(
+        dir.join("lib.rs"),
+        r#"
+pub fn parse_config(input: &str) -> anyhow::Result<Config> {
+    todo!()
+}
+
+pub fn parse_name<'a>(input: &'a str) -> Result<&'a str> {
+    todo!()
+}
+
+fn render(config: &Config, width: usize) -> String {
+    String::new()
+}
+
+impl Config {
+    fn validate(&self) {}
+}
+"#,
+    )
+    .unwrap();
+
+    fs::write(
+        dir.join("main.go"),
+        r#"package main
+
+func ParseConfig(input string) (*Config, error) {
+	return nil, nil
+}
+
+func Join(a, b string) string {
+	return a + b
+}
+"#,
+    )
+    .unwrap();
+
+    fs::write(
+        dir.join("api.ts"),
+        r#"
+export function fetchUser(id: string): Promise<User> {
+  return api.get(id);
+}
+
+const formatUser = (user: User, verbose: boolean): string => {
+  return user.name;
+};
+"#,
+    )
+    .unwrap();
+}
+
+fn search(dir: &Path, pattern: &str, language: Option<&str>) -> Vec<String> {
+    let ignore: Vec<String> = vec![];
+    let options = SignatureOptions {
+        path: dir,
+        pattern,
+        language,
+        ignore: &ignore,
+        allow_tests: false,
+        max_results: None,
+    };
+
+    perform_signature_search(&options)
+        .unwrap()
+        .into_iter()
+        .map(|m| m.name)
+        .collect()
+}
+
+#[test]
+fn test_rust_signature_with_wildcards() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    // Lifetimes are ignored and `Result` matches path-qualified results
+    let names = search(temp_dir.path(), "fn(&str) -> Result<_>", Some("rust"));
+    assert_eq!(names, vec!["parse_config", "parse_name"]);
+
+    // `..` matches any number of parameters, and `self` is not a parameter type
+    let names = search(temp_dir.path(), "fn(..) -> String", Some("rust"));
+    assert_eq!(names, vec!["render"]);
+    let names = search(temp_dir.path(), "fn() -> ()", Some("rust"));
+    assert_eq!(names, vec!["validate"]);
+}
+
+#[test]
+fn test_signature_with_name() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let names = search(temp_dir.path(), "fn parse_name(_)", None);
+    assert_eq!(names, vec!["parse_name"]);
+}
+
+#[test]
+fn test_go_signature() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let names = search(temp_dir.path(), "func(string) (*Config, error)", None);
+    assert_eq!(names, vec!["ParseConfig"]);
+
+    // Grouped parameters count once per name
+    let names = search(temp_dir.path(), "func(string, string) string", None);
+    assert_eq!(names, vec!["Join"]);
+}
+
+#[test]
+fn test_typescript_signature() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let names = search(temp_dir.path(), "function(string): Promise<_>", None);
+    assert_eq!(names, vec!["fetchUser"]);
+
+    // Arrow functions take their name from the variable they are assigned to
+    let names = search(temp_dir.path(), "(User, boolean): string", None);
+    assert_eq!(names, vec!["formatUser"]);
+}
+
+#[test]
+fn test_invalid_signature_pattern() {
+    let temp_dir = TempDir::new().unwrap();
+    let ignore: Vec<String> = vec![];
+    let options = SignatureOptions {
+        path: temp_dir.path(),
+        pattern: "fn -> String",
+        language: None,
+        ignore: &ignore,
+        allow_tests: false,
+        max_results: None,
+    };
+
+    assert!(perform_signature_search(&options).is_err());
+}
@@ -0,0 +1,93 @@
+use probe::similar::{find_similar, shingle_similarity, SimilarOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const PARSE_PORT: &str = "fn parse_port(value: &str) -> Option<u16> {
+    let trimmed = value.trim();
+    let port = trimmed.parse::<u16>().ok()?;
+    if port == 0 {
+        return None;
+    }
+    Some(port)
+}";
+
+fn create_project() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("config.rs"),
+        format!(
+            "{}\n\nfn unrelated_helper() -> usize {{\n    42\n}}\n",
+            PARSE_PORT
+        ),
+    )
+    .unwrap();
+    // A copy with the function renamed
+    fs::write(
+        dir.path().join("server.rs"),
+        PARSE_PORT.replace("parse_port", "read_listen_port"),
+    )
+    .unwrap();
+    // Shares identifiers but not the code
+    fs::write(
+        dir.path().join("client.rs"),
+        "fn connect(port: u16, value: &str) {\n    println!(\"{} {}\", value, port);\n}\n",
+    )
+    .unwrap();
+    dir
+}
+
+fn similar_files(
+    path: &Path,
+    snippet: &str,
+    source: Option<(&Path, (usize, usize))>,
+) -> Vec<(String, f64)> {
+    let blocks = find_similar(&SimilarOptions {
+        path,
+        snippet,
+        source,
+        custom_ignores: &[],
+        allow_tests: false,
+        reranker: "bm25",
+        max_results: 10,
+        min_similarity: 0.1,
+    })
+    .unwrap();
+    blocks
+        .iter()
+        .map(|block| {
+            let file = Path::new(&block.result.file).file_name().unwrap();
+            (file.to_string_lossy().to_string(), block.similarity)
+        })
+        .collect()
+}
+
+#[test]
+fn test_shingle_similarity() {
+    assert_eq!(shingle_similarity(PARSE_PORT, PARSE_PORT), 1.0);
+    // Formatting does not change the tokens
+    assert_eq!(shingle_similarity("a + b * c", "a+b*c"), 1.0);
+    assert!(shingle_similarity(PARSE_PORT, "fn main() { run(); }") < 0.1);
+    assert_eq!(shingle_similarity("", ""), 0.0);
+}
+
+#[test]
+fn test_similar_finds_copies_of_a_snippet() {
+    let project = create_project();
+    let found = similar_files(project.path(), PARSE_PORT, None);
+
+    assert_eq!(found[0], ("config.rs".to_string(), 1.0));
+    assert_eq!(found[1].0, "server.rs");
+    assert!(found[1].1 > 0.7 && found[1].1 < 1.0);
+    assert!(found.iter().all(|(file, _)| file != "client.rs"));
+}
+
+#[test]
+fn test_similar_leaves_out_the_source_block() {
+    let project = create_project();
+    let source = project.path().join("config.rs");
+    let found = similar_files(project.path(), PARSE_PORT, Some((&source, (1, 8))));
+
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].0, "server.rs");
+}
@@ -0,0 +1,84 @@
+use probe::search::snippet_window::trim_to_window;
+use probe::search::{perform_probe, SearchOptions};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, snippet_window: Option<usize>) -> Vec<String> {
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        snippet_window,
+        ..Default::default()
+    };
+    perform_probe(&options)
+        .unwrap()
+        .results
+        .into_iter()
+        .map(|r| r.code)
+        .collect()
+}
+
+/// A 200-line function with a single matching line in the middle
+fn create_huge_function() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let mut code = String::from("fn huge(input: u32) -> u32 {\n");
+    for i in 0..200 {
+        if i == 100 {
+            code.push_str("    let needle = input * 2;\n");
+        } else {
+            code.push_str(&format!("    let step{} = input + {};\n", i, i));
+        }
+    }
+    code.push_str("    needle\n}\n");
+    fs::write(temp_dir.path().join("huge.rs"), code).unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_snippet_window_trims_huge_blocks() {
+    let project = create_huge_function();
+
+    let full = search(project.path(), None);
+    assert_eq!(full.len(), 1);
+    assert_eq!(full[0].lines().count(), 203);
+
+    let trimmed = search(project.path(), Some(2));
+    assert_eq!(trimmed.len(), 1);
+    let lines: Vec<&str> = trimmed[0].lines().collect();
+    assert_eq!(
+        lines,
+        vec![
+            "fn huge(input: u32) -> u32 {",
+            "    ... 98 lines folded ...",
+            "    let step98 = input + 98;",
+            "    let step99 = input + 99;",
+            "    let needle = input * 2;",
+            "    let step101 = input + 101;",
+            "    let step102 = input + 102;",
+            "    ... 95 lines folded ...",
+            "    let step198 = input + 198;",
+            "    let step199 = input + 199;",
+            "    needle",
+            "}",
+        ]
+    );
+}
+
+#[test]
+fn test_trim_to_window_leaves_small_blocks_alone() {
+    let terms: HashSet<String> = ["needle".to_string()].into_iter().collect();
+    let code = "fn small() {\n    let needle = 1;\n}";
+
+    assert_eq!(trim_to_window(code, &terms, 1), None);
+    assert_eq!(trim_to_window("fn other() {\n}", &terms, 0), None);
+    assert_eq!(
+        trim_to_window(code, &terms, 0).unwrap(),
+        "fn small() {\n    let needle = 1;\n... 1 line folded ..."
+    );
+}
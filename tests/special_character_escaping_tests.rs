@@ -290,7 +290,9 @@ fn test_html_tags_in_json_output() {
     create_special_character_test_files(&temp_dir);
 
     // Run the CLI with JSON output format, searching for "HTML"
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -359,7 +361,9 @@ fn test_html_tags_in_xml_output() {
     create_special_character_test_files(&temp_dir);
 
     // Run the CLI with XML output format, searching for "HTML"
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -84,21 +84,13 @@ fn test_stemming_with_and_query(temp_path: &Path) {
     let options = SearchOptions {
         path: temp_path,
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
         // Use all terms mode
         exact: false, // Enable stemming
         no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Run the search
@@ -162,21 +154,13 @@ fn test_stemming_with_or_query(temp_path: &Path) {
     let options = SearchOptions {
         path: temp_path,
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
         // Use any term mode
         exact: false, // Enable stemming
         no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Run the search
@@ -363,21 +347,13 @@ fn test_compound_word_splitting(temp_path: &Path) {
     let options = SearchOptions {
         path: temp_path,
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
 
         exact: false, // Enable stemming and compound word splitting
         no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Run the search
@@ -406,21 +382,13 @@ fn test_compound_word_splitting(temp_path: &Path) {
     let complex_options = SearchOptions {
         path: temp_path,
         queries: &complex_queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
         // Use all terms mode
         exact: false, // Enable stemming
         no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Run the search
@@ -462,21 +430,13 @@ fn test_negative_compound_word_in_existing_tests(temp_path: &Path) {
     let options = SearchOptions {
         path: temp_path,
         queries: &queries,
-        files_only: false,
         custom_ignores: &custom_ignores,
-        exclude_filenames: false,
         reranker: "hybrid",
-        frequency_search: true,
-        max_results: None,
-        max_bytes: None,
-        max_tokens: None,
         allow_tests: true,
 
         exact: false, // Enable stemming and compound word splitting
         no_merge: true,
-        merge_threshold: None,
-        dry_run: false,
-        session: None,
+        ..Default::default()
     };
 
     // Run the search
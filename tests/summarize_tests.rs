@@ -0,0 +1,138 @@
+use probe::summarize::{summarize, ApiCard, SummarizeOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn create_test_files(dir: &Path) {
+    fs::write(
+        dir.join("lib.rs"),
+        r#"//! Configuration loading.
+//!
+//! Details that are not part of the summary.
+
+/// A loaded configuration. It is immutable.
+#[derive(Debug)]
+pub struct Config {
+    pub name: String,
+}
+
+impl Config {
+    /// Load the configuration from a file.
+    pub fn load(path: &str) -> Config {
+        let helper = |x: u32| x;
+        Config { name: path.to_string() }
+    }
+
+    fn private_helper(&self) {}
+}
+
+pub trait Source {
+    /// Read the raw text.
+    fn read(&self) -> String;
+}
+
+fn hidden() {}
+"#,
+    )
+    .unwrap();
+
+    fs::write(
+        dir.join("util.py"),
+        r#""""Utility helpers."""
+
+def parse(text: str) -> list:
+    """Split text into words.
+
+    More detail here.
+    """
+    return text.split()
+
+def _private():
+    pass
+"#,
+    )
+    .unwrap();
+}
+
+fn card(dir: &Path, max_tokens: Option<usize>) -> ApiCard {
+    summarize(&SummarizeOptions {
+        path: dir,
+        ignore: &[],
+        allow_tests: false,
+        max_tokens,
+    })
+    .unwrap()
+}
+
+fn signatures(card: &ApiCard, file: &str) -> Vec<String> {
+    card.files
+        .iter()
+        .find(|summary| summary.file_path.ends_with(file))
+        .unwrap()
+        .symbols
+        .iter()
+        .map(|symbol| symbol.signature.clone())
+        .collect()
+}
+
+#[test]
+fn test_summarize_lists_public_symbols_with_docs() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let card = card(temp_dir.path(), None);
+    assert_eq!(card.files.len(), 2);
+    assert_eq!(card.omitted_symbols, 0);
+    assert_eq!(
+        signatures(&card, "lib.rs"),
+        vec![
+            "pub struct Config",
+            "pub fn load(path: &str) -> Config",
+            "pub trait Source",
+            "fn read(&self) -> String",
+        ]
+    );
+    assert_eq!(
+        signatures(&card, "util.py"),
+        vec!["def parse(text: str) -> list"]
+    );
+
+    let lib = &card.files[0];
+    assert_eq!(lib.module_doc.as_deref(), Some("Configuration loading."));
+    assert_eq!(
+        lib.symbols[0].doc.as_deref(),
+        Some("A loaded configuration. It is immutable.")
+    );
+    assert_eq!(lib.symbols[1].container.as_deref(), Some("Config"));
+    let util = &card.files[1];
+    assert_eq!(util.module_doc.as_deref(), Some("Utility helpers."));
+    assert_eq!(
+        util.symbols[0].doc.as_deref(),
+        Some("Split text into words.")
+    );
+}
+
+#[test]
+fn test_summarize_fits_token_budget() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let full = card(temp_dir.path(), None);
+    let budget = full.tokens - 3;
+    let shortened = card(temp_dir.path(), Some(budget));
+    assert!(shortened.docs_shortened);
+    assert_eq!(shortened.omitted_symbols, 0);
+    assert_eq!(
+        shortened.files[0].symbols[0].doc.as_deref(),
+        Some("A loaded configuration.")
+    );
+    assert!(shortened.tokens <= budget);
+
+    let budget = full.tokens * 3 / 4;
+    let tight = card(temp_dir.path(), Some(budget));
+    assert!(tight.tokens <= budget);
+    assert!(tight.omitted_symbols > 0);
+    // Every file keeps its first symbol before any file gets a third one
+    assert!(tight.files.iter().all(|file| !file.symbols.is_empty()));
+    assert!(tight.files[0].symbols.len() <= 2);
+}
@@ -0,0 +1,149 @@
+#![cfg(unix)]
+
+use probe::models::{SearchResult, SkipReason};
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn search(path: &Path, follow_symlinks: bool, report_skipped: bool) -> Vec<SearchResult> {
+    search_with_report(path, follow_symlinks, report_skipped).0
+}
+
+fn search_with_report(
+    path: &Path,
+    follow_symlinks: bool,
+    report_skipped: bool,
+) -> (Vec<SearchResult>, Vec<(String, SkipReason)>) {
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        report_skipped,
+        follow_symlinks,
+        ..Default::default()
+    };
+
+    let limited = perform_probe(&options).unwrap();
+    let skipped = limited
+        .skip_report
+        .unwrap_or_default()
+        .into_iter()
+        .map(|s| (s.path.to_string_lossy().to_string(), s.reason))
+        .collect();
+    (limited.results, skipped)
+}
+
+/// A project whose `src` holds one real file, plus a directory outside it reached by a link
+fn create_linked_project() -> (TempDir, TempDir) {
+    let project = TempDir::new().unwrap();
+    let outside = TempDir::new().unwrap();
+
+    fs::create_dir_all(project.path().join("src")).unwrap();
+    fs::write(
+        project.path().join("src/local.rs"),
+        "fn local_needle() {\n    println!(\"needle\");\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        outside.path().join("shared.rs"),
+        "fn shared_needle() {\n    println!(\"needle\");\n}\n",
+    )
+    .unwrap();
+    symlink(outside.path(), project.path().join("shared")).unwrap();
+
+    (project, outside)
+}
+
+#[test]
+fn test_symlinks_are_not_followed_by_default() {
+    let (project, _outside) = create_linked_project();
+
+    let (results, skipped) = search_with_report(project.path(), false, true);
+    assert_eq!(results.len(), 1);
+    assert!(results[0].file.ends_with("local.rs"));
+    assert!(!results[0].via_symlink);
+
+    let link = skipped
+        .iter()
+        .find(|(path, _)| path.ends_with("shared"))
+        .expect("the link should be reported");
+    assert_eq!(link.1, SkipReason::Symlink);
+}
+
+#[test]
+fn test_follow_symlinks_marks_linked_results() {
+    let (project, _outside) = create_linked_project();
+
+    let mut results = search(project.path(), true, false);
+    results.sort_by(|a, b| a.file.cmp(&b.file));
+    assert_eq!(results.len(), 2);
+
+    assert!(results[0].file.ends_with("shared/shared.rs"));
+    assert!(results[0].via_symlink);
+    assert!(results[1].file.ends_with("src/local.rs"));
+    assert!(!results[1].via_symlink);
+}
+
+#[test]
+fn test_symlink_cycles_and_aliases_are_walked_once() {
+    let (project, _outside) = create_linked_project();
+    // A link back to the project root and a second name for `src`
+    symlink(project.path(), project.path().join("src/loop")).unwrap();
+    symlink(project.path().join("src"), project.path().join("alias")).unwrap();
+
+    let (results, skipped) = search_with_report(project.path(), true, true);
+    let local: Vec<&SearchResult> = results
+        .iter()
+        .filter(|r| r.file.ends_with("local.rs"))
+        .collect();
+    assert_eq!(local.len(), 1);
+    assert!(!local[0].via_symlink);
+
+    assert!(skipped
+        .iter()
+        .any(|(path, reason)| path.ends_with("alias/local.rs")
+            && *reason == SkipReason::LinkedDuplicate));
+}
+
+#[test]
+fn test_extract_globs_follow_the_same_policy() {
+    let (project, _outside) = create_linked_project();
+    let manifest = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+
+    let run_extract = |extra: &[&str]| {
+        let mut args = vec![
+            "run",
+            "--manifest-path",
+            manifest.to_str().unwrap(),
+            "--",
+            "extract",
+            "*/*.rs",
+            "--format",
+            "json",
+        ];
+        args.extend_from_slice(extra);
+        let history_dir = TempDir::new().unwrap();
+        let output = Command::new("cargo")
+            .env("PROBE_HISTORY_DIR", history_dir.path())
+            .args(&args)
+            .current_dir(project.path())
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    let default_output = run_extract(&[]);
+    assert!(default_output.contains("local.rs"));
+    assert!(!default_output.contains("shared.rs"));
+
+    let followed_output = run_extract(&["--follow-symlinks"]);
+    assert!(followed_output.contains("shared.rs"));
+    assert!(followed_output.contains("\"via_symlink\": true"));
+}
@@ -0,0 +1,141 @@
+use probe::search::telemetry::{
+    with_telemetry, MetricsEndpoint, StatsdSink, TelemetryEvent, TelemetrySink,
+};
+use probe::search::{perform_probe, ProbeEngine, SearchOptions};
+use std::fs;
+use std::net::UdpSocket;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Records a short description of every event
+#[derive(Default)]
+struct RecordingSink {
+    events: Mutex<Vec<String>>,
+}
+
+impl TelemetrySink for RecordingSink {
+    fn record(&self, event: &TelemetryEvent<'_>) {
+        let description = match event {
+            TelemetryEvent::QueryStarted { queries, .. } => format!("started {}", queries[0]),
+            TelemetryEvent::FilesScanned { matched_files, .. } => {
+                format!("scanned {}", matched_files)
+            }
+            TelemetryEvent::StageCompleted { stage, .. } => format!("stage {}", stage),
+            TelemetryEvent::ResultsReturned { results, .. } => format!("results {}", results),
+            TelemetryEvent::QueryFinished { succeeded, .. } => format!("finished {}", succeeded),
+        };
+        self.events.lock().unwrap().push(description);
+    }
+}
+
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("alpha.rs"),
+        "fn alpha_handler() {\n    println!(\"needle\");\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("beta.rs"),
+        "fn beta_handler() {\n    println!(\"haystack\");\n}\n",
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_search_emits_events_in_order() {
+    let project = create_project();
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path: project.path(),
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        ..Default::default()
+    };
+
+    let sink = Arc::new(RecordingSink::default());
+    let results = with_telemetry(sink.clone(), || perform_probe(&options)).unwrap();
+    assert_eq!(results.results.len(), 1);
+
+    let events = sink.events.lock().unwrap();
+    assert_eq!(events.first().unwrap(), "started needle");
+    assert!(events.contains(&"scanned 1".to_string()));
+    assert!(events.contains(&"stage file_searching".to_string()));
+    assert_eq!(events[events.len() - 2], "results 1");
+    assert_eq!(events.last().unwrap(), "finished true");
+
+    // Searches outside `with_telemetry` are not reported
+    let count = events.len();
+    drop(events);
+    perform_probe(&options).unwrap();
+    assert_eq!(sink.events.lock().unwrap().len(), count);
+}
+
+#[test]
+fn test_engine_reports_to_its_sink() {
+    let project = create_project();
+    let sink = Arc::new(RecordingSink::default());
+    let mut engine = ProbeEngine::new(project.path()).unwrap();
+    engine.set_telemetry(sink.clone());
+
+    engine.search("needle").unwrap();
+    engine.search("haystack").unwrap();
+
+    let events = sink.events.lock().unwrap();
+    let started: Vec<&String> = events.iter().filter(|e| e.starts_with("started")).collect();
+    assert_eq!(started, vec!["started needle", "started haystack"]);
+}
+
+#[test]
+fn test_parse_metrics_endpoint() {
+    assert_eq!(
+        MetricsEndpoint::parse("statsd://localhost:8125").unwrap(),
+        MetricsEndpoint::Statsd("localhost:8125".to_string())
+    );
+    assert_eq!(
+        MetricsEndpoint::parse("http://collector:4318").unwrap(),
+        MetricsEndpoint::OtlpHttp {
+            authority: "collector:4318".to_string(),
+            path: "/v1/metrics".to_string()
+        }
+    );
+    assert_eq!(
+        MetricsEndpoint::parse("http://collector:4318/custom").unwrap(),
+        MetricsEndpoint::OtlpHttp {
+            authority: "collector:4318".to_string(),
+            path: "/custom".to_string()
+        }
+    );
+    assert!(MetricsEndpoint::parse("https://collector:4318").is_err());
+    assert!(MetricsEndpoint::parse("statsd://").is_err());
+}
+
+#[test]
+fn test_statsd_sink_sends_metrics() {
+    let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+    server
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let address = server.local_addr().unwrap().to_string();
+
+    let sink = StatsdSink::new(&address, "probe.search").unwrap();
+    sink.record(&TelemetryEvent::QueryFinished {
+        duration: Duration::from_millis(42),
+        succeeded: false,
+    });
+
+    let mut buffer = [0u8; 256];
+    let mut received = Vec::new();
+    for _ in 0..2 {
+        let len = server.recv(&mut buffer).unwrap();
+        received.push(String::from_utf8_lossy(&buffer[..len]).to_string());
+    }
+    assert_eq!(
+        received,
+        vec!["probe.search.duration:42|ms", "probe.search.failures:1|c"]
+    );
+}
@@ -0,0 +1,75 @@
+use probe::models::SearchResult;
+use probe::search::render_template_results;
+
+fn create_result(file: &str, lines: (usize, usize), code: &str) -> SearchResult {
+    SearchResult {
+        file: file.to_string(),
+        file_path: None,
+        lines,
+        node_type: "function_item".to_string(),
+        code: code.to_string(),
+        matched_by_filename: None,
+        rank: Some(1),
+        score: Some(2.5),
+        normalized_score: Some(1.0),
+        tfidf_score: None,
+        bm25_score: Some(2.5),
+        tfidf_rank: None,
+        bm25_rank: None,
+        new_score: None,
+        hybrid2_rank: None,
+        combined_score_rank: None,
+        file_unique_terms: None,
+        file_total_matches: None,
+        file_match_rank: None,
+        block_unique_terms: None,
+        block_total_matches: None,
+        parent_file_id: None,
+        block_id: None,
+        matched_keywords: Some(vec!["parse".to_string()]),
+        tokenized_content: None,
+        merged_from: None,
+        usage_kind: None,
+        via_symlink: false,
+        changed_lines: None,
+        symbol_name: None,
+        language: None,
+        extraction_confidence: None,
+        partial_parse: false,
+        deprecated: false,
+        embedded: None,
+        kind: None,
+        complexity: None,
+        content_hash: None,
+    }
+}
+
+#[test]
+fn test_template_renders_result_fields() {
+    let results = vec![
+        create_result("src/lib.rs", (3, 5), "fn parse() -> bool {\n    a < b\n}"),
+        create_result("src/main.rs", (10, 12), "fn main() {}"),
+    ];
+
+    let template = "{{ for r in results }}* {r.file}:{r.line_start}-{r.line_end} ({r.node_type}, {r.extension})\n#+BEGIN_SRC\n{r.code}\n#+END_SRC\n{{ endfor }}{summary.count} results";
+    let output = render_template_results(&results, template, None).unwrap();
+
+    assert_eq!(
+        output,
+        "* src/lib.rs:3-5 (function_item, rs)\n#+BEGIN_SRC\nfn parse() -> bool {\n    a < b\n}\n#+END_SRC\n\
+         * src/main.rs:10-12 (function_item, rs)\n#+BEGIN_SRC\nfn main() {}\n#+END_SRC\n\
+         2 results"
+    );
+}
+
+#[test]
+fn test_template_errors_are_reported() {
+    let results = vec![create_result("src/lib.rs", (1, 1), "fn a() {}")];
+
+    // Unclosed block
+    assert!(render_template_results(&results, "{{ for r in results }}{r.file}", None).is_err());
+    // Unknown field
+    assert!(
+        render_template_results(&results, "{{ for r in results }}{r.missing}{{ endfor }}", None).is_err()
+    );
+}
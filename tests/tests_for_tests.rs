@@ -0,0 +1,85 @@
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn create_test_files(dir: &Path) {
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::create_dir_all(dir.join("tests")).unwrap();
+
+    fs::write(
+        dir.join("src/config.rs"),
+        "pub fn parse_config(input: &str) -> Config {\n    Config::from(input)\n}\n",
+    )
+    .unwrap();
+
+    // A test that exercises the symbol
+    fs::write(
+        dir.join("tests/config_usage.rs"),
+        "fn config_roundtrip() {\n    let config = parse_config(\"a=1\");\n    assert!(config.is_valid());\n}\n",
+    )
+    .unwrap();
+
+    // A test that mentions the query term but not the symbol
+    fs::write(
+        dir.join("tests/unrelated.rs"),
+        "fn config_defaults() {\n    let config = Config::default();\n    assert!(config.is_valid());\n}\n",
+    )
+    .unwrap();
+}
+
+fn search(dir: &Path, allow_tests: bool, tests_for: Option<&str>) -> Vec<String> {
+    let queries = vec!["config".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+
+    let options = SearchOptions {
+        path: dir,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        allow_tests,
+        no_merge: true,
+        tests_for,
+        ..Default::default()
+    };
+
+    perform_probe(&options)
+        .unwrap()
+        .results
+        .into_iter()
+        .map(|r| r.file)
+        .collect()
+}
+
+#[test]
+fn test_tests_for_includes_only_referencing_tests() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let files = search(temp_dir.path(), false, Some("parse_config"));
+
+    assert!(files.iter().any(|f| f.ends_with("src/config.rs")));
+    assert!(
+        files.iter().any(|f| f.ends_with("tests/config_usage.rs")),
+        "Test referencing the symbol should be included: {:?}",
+        files
+    );
+    assert!(
+        !files.iter().any(|f| f.ends_with("tests/unrelated.rs")),
+        "Test not referencing the symbol should be excluded: {:?}",
+        files
+    );
+}
+
+#[test]
+fn test_tests_excluded_without_tests_for() {
+    let temp_dir = TempDir::new().unwrap();
+    create_test_files(temp_dir.path());
+
+    let files = search(temp_dir.path(), false, None);
+    assert!(!files.iter().any(|f| f.contains("/tests/")));
+
+    // --allow-tests still includes every test file
+    let files = search(temp_dir.path(), true, Some("parse_config"));
+    assert!(files.iter().any(|f| f.ends_with("tests/unrelated.rs")));
+}
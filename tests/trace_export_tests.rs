@@ -0,0 +1,86 @@
+use probe::search::trace_export::{with_trace_export, OtlpTraceLayer};
+use probe::search::ProbeEngine;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use tempfile::TempDir;
+
+/// Accept one request and return its body, answering 200
+fn receive_one_request(listener: TcpListener) -> std::thread::JoinHandle<(String, String)> {
+    std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).unwrap();
+            if header.trim().is_empty() {
+                break;
+            }
+            if let Some(value) = header.to_lowercase().strip_prefix("content-length:") {
+                content_length = value.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0u8; content_length];
+        reader.read_exact(&mut body).unwrap();
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+        (request_line, String::from_utf8(body).unwrap())
+    })
+}
+
+#[test]
+fn test_search_spans_are_exported_as_one_trace() {
+    let project = TempDir::new().unwrap();
+    fs::write(
+        project.path().join("main.rs"),
+        "fn handler() {\n    println!(\"needle\");\n}\n",
+    )
+    .unwrap();
+    let engine = ProbeEngine::new(project.path()).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let collector = receive_one_request(listener);
+
+    let layer = OtlpTraceLayer::new(&endpoint).unwrap();
+    let results = with_trace_export(layer, || engine.search("needle")).unwrap();
+    assert_eq!(results.results.len(), 1);
+
+    let (request_line, body) = collector.join().unwrap();
+    assert!(request_line.starts_with("POST /v1/traces "));
+
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let spans = json["resourceSpans"][0]["scopeSpans"][0]["spans"]
+        .as_array()
+        .unwrap();
+    let root = spans
+        .iter()
+        .find(|span| span["name"] == "probe.search")
+        .unwrap();
+    assert_eq!(root["parentSpanId"], "");
+    assert!(root["attributes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|a| a["key"] == "results" && a["value"]["stringValue"] == "1"));
+
+    let stage = spans
+        .iter()
+        .find(|span| span["name"] == "file_searching")
+        .unwrap();
+    assert_eq!(stage["parentSpanId"], root["spanId"]);
+    assert!(spans.iter().all(|span| span["traceId"] == root["traceId"]));
+}
+
+#[test]
+fn test_trace_endpoint_must_be_http() {
+    assert!(OtlpTraceLayer::new("http://localhost:4318").is_ok());
+    assert!(OtlpTraceLayer::new("statsd://localhost:8125").is_err());
+    assert!(OtlpTraceLayer::new("http:///v1/traces").is_err());
+}
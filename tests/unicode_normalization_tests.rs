@@ -0,0 +1,83 @@
+use probe::search::normalization::normalize;
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, query: &str, case_fold: bool) -> Vec<String> {
+    let queries = vec![query.to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        case_fold,
+        ..Default::default()
+    };
+    let mut files: Vec<String> = perform_probe(&options)
+        .unwrap()
+        .results
+        .iter()
+        .map(|r| {
+            Path::new(&r.file)
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// The same Swift identifier written precomposed (NFC) and with a combining accent (NFD)
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("composed.swift"),
+        "func brew() {\n    let caf\u{e9}Order = 1\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("decomposed.swift"),
+        "func pour() {\n    let cafe\u{301}Order = 2\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("street.py"),
+        "def route():\n    stra\u{df}e_name = 'Hauptstra\u{df}e'\n    return stra\u{df}e_name\n",
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_composed_and_decomposed_forms_match() {
+    let project = create_project();
+    let expected = vec!["composed.swift", "decomposed.swift"];
+
+    assert_eq!(search(project.path(), "caf\u{e9}Order", false), expected);
+    assert_eq!(search(project.path(), "cafe\u{301}Order", false), expected);
+}
+
+#[test]
+fn test_case_fold_matches_full_case_variants() {
+    let project = create_project();
+
+    assert!(search(project.path(), "STRASSE_NAME", false).is_empty());
+    assert_eq!(
+        search(project.path(), "STRASSE_NAME", true),
+        vec!["street.py"]
+    );
+}
+
+#[test]
+fn test_normalize() {
+    assert_eq!(normalize("plain", true), "plain");
+    assert_eq!(normalize("cafe\u{301}", false), "caf\u{e9}");
+    assert_eq!(normalize("caf\u{e9}", false), "caf\u{e9}");
+    assert_eq!(normalize("Stra\u{df}e", false), "Stra\u{df}e");
+    assert_eq!(normalize("Stra\u{df}e", true), "strasse");
+    assert_eq!(normalize("\u{fb01}le", true), "file");
+}
@@ -0,0 +1,117 @@
+use probe::models::{SearchResult, UsageKind};
+use probe::search::usages::{classify_usages, extract_symbol_names};
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use tempfile::TempDir;
+
+fn create_result(code: &str) -> SearchResult {
+    SearchResult {
+        file: "src/lib.rs".to_string(),
+        file_path: None,
+        lines: (1, code.lines().count()),
+        node_type: "function_item".to_string(),
+        code: code.to_string(),
+        matched_by_filename: None,
+        rank: None,
+        score: None,
+        normalized_score: None,
+        tfidf_score: None,
+        bm25_score: None,
+        tfidf_rank: None,
+        bm25_rank: None,
+        new_score: None,
+        hybrid2_rank: None,
+        combined_score_rank: None,
+        file_unique_terms: None,
+        file_total_matches: None,
+        file_match_rank: None,
+        block_unique_terms: None,
+        block_total_matches: None,
+        parent_file_id: None,
+        block_id: None,
+        matched_keywords: None,
+        tokenized_content: None,
+        merged_from: None,
+        usage_kind: None,
+        via_symlink: false,
+        changed_lines: None,
+        symbol_name: None,
+        language: None,
+        extraction_confidence: None,
+        partial_parse: false,
+        deprecated: false,
+        embedded: None,
+        kind: None,
+        complexity: None,
+        content_hash: None,
+    }
+}
+
+#[test]
+fn test_extract_symbol_names() {
+    let queries = vec!["parse_config AND -legacy OR \"Config\"".to_string()];
+    assert_eq!(
+        extract_symbol_names(&queries),
+        vec!["parse_config".to_string(), "Config".to_string()]
+    );
+}
+
+#[test]
+fn test_classify_definitions_and_usages() {
+    let mut results = vec![
+        create_result("pub fn parse_config(input: &str) -> Config {\n    todo!()\n}"),
+        create_result("fn main() {\n    let c = parse_config(\"x\");\n}"),
+        create_result("def parse_config(path):\n    return {}"),
+        create_result("func (l *Loader) parse_config(p string) error {\n    return nil\n}"),
+        create_result("int main() {\n    return parse_config(argv[1]);\n}"),
+        create_result("fn unrelated() {}"),
+    ];
+
+    classify_usages(&mut results, &["parse_config".to_string()]);
+
+    let kinds: Vec<Option<UsageKind>> = results.iter().map(|r| r.usage_kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            Some(UsageKind::Definition),
+            Some(UsageKind::Usage),
+            Some(UsageKind::Definition),
+            Some(UsageKind::Definition),
+            Some(UsageKind::Usage),
+            None,
+        ]
+    );
+}
+
+#[test]
+fn test_usages_are_prioritized_over_definition() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("config.rs"),
+        "pub fn parse_config(input: &str) -> String {\n    // parse_config turns input into a config\n    input.to_string()\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("app.rs"),
+        "fn start() {\n    let value = parse_config(\"a=1\");\n    println!(\"{}\", value);\n}\n",
+    )
+    .unwrap();
+
+    let queries = vec!["parse_config".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path: temp_dir.path(),
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        max_results: Some(1),
+        no_merge: true,
+        usages: true,
+        ..Default::default()
+    };
+
+    let results = perform_probe(&options).unwrap();
+    assert_eq!(results.results.len(), 1);
+    assert!(results.results[0].file.ends_with("app.rs"));
+    assert_eq!(results.results[0].usage_kind, Some(UsageKind::Usage));
+}
@@ -0,0 +1,129 @@
+use probe::models::SearchResult;
+use probe::search::{perform_probe, write_search_results, SearchOptions};
+use probe::verify::{verify_results, Freshness};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, query: &str) -> Vec<SearchResult> {
+    let queries = vec![query.to_string()];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        exclude_filenames: true,
+        allow_tests: true,
+        no_merge: true,
+        ..Default::default()
+    };
+    perform_probe(&options).unwrap().results
+}
+
+fn saved_json(results: &[SearchResult]) -> String {
+    let mut output = Vec::new();
+    write_search_results(&mut output, results, false, "json", None, &[], None).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+fn freshness_by_file(json: &str, base: &Path) -> Vec<(String, Freshness)> {
+    let mut statuses: Vec<(String, Freshness)> = verify_results(json, base)
+        .unwrap()
+        .results
+        .into_iter()
+        .map(|r| {
+            let name = r.path.file_name().unwrap().to_string_lossy().to_string();
+            (name, r.freshness)
+        })
+        .collect();
+    statuses.sort_by(|a, b| a.0.cmp(&b.0));
+    statuses
+}
+
+fn create_project() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    let files = [
+        (
+            "parser.rs",
+            "pub fn parse_config(text: &str) -> Config {\n    Config::from(text)\n}\n",
+        ),
+        (
+            "loader.rs",
+            "pub fn load_config(path: &str) -> Config {\n    parse_config(path)\n}\n",
+        ),
+        (
+            "store.rs",
+            "pub fn store_config(config: &Config) {\n    save(config)\n}\n",
+        ),
+    ];
+    for (name, content) in files {
+        fs::write(temp_dir.path().join(name), content).unwrap();
+    }
+    temp_dir
+}
+
+#[test]
+fn test_unchanged_results_are_fresh() {
+    let temp_dir = create_project();
+    let json = saved_json(&search(temp_dir.path(), "config"));
+
+    let verification = verify_results(&json, temp_dir.path()).unwrap();
+    assert_eq!(verification.results.len(), 3);
+    assert_eq!(verification.stale().count(), 0);
+    assert!(verification.recorded_fingerprint.is_some());
+    assert_eq!(
+        verification.current_fingerprint,
+        verification.recorded_fingerprint
+    );
+}
+
+#[test]
+fn test_changed_and_missing_files_are_stale() {
+    let temp_dir = create_project();
+    let json = saved_json(&search(temp_dir.path(), "config"));
+
+    fs::write(
+        temp_dir.path().join("loader.rs"),
+        "pub fn load_config(file: &str) -> Config {\n    parse_config(file)\n}\n",
+    )
+    .unwrap();
+    fs::remove_file(temp_dir.path().join("store.rs")).unwrap();
+
+    assert_eq!(
+        freshness_by_file(&json, temp_dir.path()),
+        vec![
+            ("loader.rs".to_string(), Freshness::Changed),
+            ("parser.rs".to_string(), Freshness::Fresh),
+            ("store.rs".to_string(), Freshness::Missing),
+        ]
+    );
+    let verification = verify_results(&json, temp_dir.path()).unwrap();
+    assert_eq!(verification.files(Freshness::Changed).len(), 1);
+    assert_eq!(verification.current_fingerprint, None);
+}
+
+#[test]
+fn test_results_without_hashes_are_unknown() {
+    let temp_dir = create_project();
+    let json = r#"{"results": [{"file": "parser.rs", "lines": [1, 3], "node_type": "function_item", "code": ""}]}"#;
+
+    let verification = verify_results(json, temp_dir.path()).unwrap();
+    assert_eq!(verification.results[0].freshness, Freshness::Unknown);
+    assert_eq!(
+        verification.results[0].path,
+        temp_dir.path().join("parser.rs")
+    );
+    assert_eq!(verification.stale().count(), 0);
+
+    assert!(verify_results("not json", temp_dir.path()).is_err());
+}
+
+#[test]
+fn test_search_header_before_json_is_skipped() {
+    let temp_dir = create_project();
+    let json = format!(
+        "Pattern: config\nPath: .\n{}",
+        saved_json(&search(temp_dir.path(), "config"))
+    );
+
+    let verification = verify_results(&json, temp_dir.path()).unwrap();
+    assert_eq!(verification.count(Freshness::Fresh), 3);
+}
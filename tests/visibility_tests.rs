@@ -0,0 +1,107 @@
+use probe::language::visibility::{block_visibility, Visibility};
+use probe::search::{perform_probe, SearchOptions};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn search(path: &Path, visibility: &str) -> Vec<probe::models::SearchResult> {
+    let queries = vec!["needle".to_string()];
+    let custom_ignores: Vec<String> = vec![];
+    let options = SearchOptions {
+        path,
+        queries: &queries,
+        custom_ignores: &custom_ignores,
+        exclude_filenames: true,
+        no_merge: true,
+        visibility,
+        ..Default::default()
+    };
+    perform_probe(&options).unwrap().results
+}
+
+#[test]
+fn test_block_visibility_rules() {
+    let public = Some(Visibility::Public);
+    let private = Some(Visibility::Private);
+
+    assert_eq!(
+        block_visibility("rust", "/// Docs\n#[inline]\npub fn open() {}"),
+        public
+    );
+    assert_eq!(
+        block_visibility("rust", "pub(crate) fn helper() {}"),
+        private
+    );
+    assert_eq!(block_visibility("rust", "async fn helper() {}"), private);
+    assert_eq!(block_visibility("rust", "impl Parser {\n}"), None);
+
+    assert_eq!(
+        block_visibility("go", "func Open(path string) error {"),
+        public
+    );
+    assert_eq!(
+        block_visibility("go", "func (c *Client) send() error {"),
+        private
+    );
+    assert_eq!(block_visibility("go", "type Client struct {"), public);
+
+    assert_eq!(
+        block_visibility("typescript", "export async function load() {"),
+        public
+    );
+    assert_eq!(
+        block_visibility("typescript", "function helper() {"),
+        private
+    );
+    assert_eq!(
+        block_visibility("javascript", "#cache = new Map();"),
+        private
+    );
+
+    assert_eq!(
+        block_visibility("python", "@cached\ndef _parse(text):"),
+        private
+    );
+    assert_eq!(block_visibility("python", "def __init__(self):"), public);
+    assert_eq!(
+        block_visibility("java", "@Override\npublic String toString() {"),
+        public
+    );
+    assert_eq!(block_visibility("java", "private void reset() {"), private);
+}
+
+#[test]
+fn test_visibility_filter() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("lib.rs"),
+        r#"pub fn find_needle() {
+    println!("needle");
+}
+
+fn count_needle() {
+    println!("needle");
+}
+
+pub(crate) fn scan_needle() {
+    println!("needle");
+}
+"#,
+    )
+    .unwrap();
+    let declared = |visibility: &str| -> Vec<String> {
+        let mut names: Vec<String> = search(temp_dir.path(), visibility)
+            .iter()
+            .map(|r| r.code.lines().next().unwrap().to_string())
+            .collect();
+        names.sort();
+        names
+    };
+
+    assert_eq!(declared("any").len(), 3);
+    assert_eq!(declared("public"), vec!["pub fn find_needle() {"]);
+    assert_eq!(
+        declared("private"),
+        vec!["fn count_needle() {", "pub(crate) fn scan_needle() {"]
+    );
+}
@@ -101,7 +101,9 @@ fn test_xml_output_format_basic() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with XML output format
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -224,7 +226,9 @@ fn test_xml_output_with_special_characters() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with XML output format, searching for special characters
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -326,7 +330,9 @@ fn test_xml_output_with_multiple_terms() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with XML output format, searching for multiple terms
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -416,7 +422,9 @@ fn test_xml_output_with_no_results() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with XML output format, searching for a term that doesn't exist
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -510,7 +518,9 @@ fn test_xml_output_with_files_only() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with XML output format and files-only option
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -188,7 +188,9 @@ fn test_xml_schema_validation_basic() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with XML output format
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -246,7 +248,9 @@ fn test_xml_schema_validation_special_characters() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with XML output format, searching for special characters
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -339,7 +343,9 @@ fn test_xml_schema_validation_empty_results() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with XML output format, searching for a term that doesn't exist
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",
@@ -406,7 +412,9 @@ fn test_xml_schema_validation_multiple_terms() {
     create_test_directory_structure(&temp_dir);
 
     // Run the CLI with XML output format, searching for multiple terms
+    let history_dir = TempDir::new().unwrap();
     let output = Command::new("cargo")
+        .env("PROBE_HISTORY_DIR", history_dir.path())
         .args([
             "run",
             "--",